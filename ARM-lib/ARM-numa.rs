@@ -0,0 +1,222 @@
+//! NUMA topology and CPU affinity
+//!
+//! Detects memory nodes from sysfs and binds child processes to a node's CPUs,
+//! so compute-heavy work (model servers, source builds) keeps its memory local.
+//! Binding goes through `numactl` when installed (CPUs and memory), otherwise
+//! `taskset` (CPUs only).
+
+use anyhow::{anyhow, Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+/// Where Linux exposes NUMA nodes
+pub const NODE_ROOT: &str = "/sys/devices/system/node";
+
+/// A NUMA node and the CPUs attached to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumaNode {
+    pub id: u32,
+    pub cpus: Vec<usize>,
+    /// Memory attached to the node
+    pub memory_kb: Option<u64>,
+}
+
+/// Memory nodes on this machine
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NumaTopology {
+    pub nodes: Vec<NumaNode>,
+}
+
+impl NumaTopology {
+    /// Read the topology; machines without NUMA report a single node with every CPU
+    pub fn detect() -> Result<Self> {
+        let root = Path::new(NODE_ROOT);
+        if cfg!(target_os = "linux") && root.is_dir() {
+            let topology = Self::from_sysfs(root)?;
+            if !topology.nodes.is_empty() {
+                return Ok(topology);
+            }
+        }
+        let cpus = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        Ok(Self {
+            nodes: vec![NumaNode { id: 0, cpus: (0..cpus).collect(), memory_kb: None }],
+        })
+    }
+
+    /// Read `node*/cpulist` and `node*/meminfo` under a sysfs node directory
+    pub fn from_sysfs(root: &Path) -> Result<Self> {
+        let mut nodes = Vec::new();
+        for entry in std::fs::read_dir(root).with_context(|| format!("Failed to read {}", root.display()))? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(id) = name.strip_prefix("node").and_then(|id| id.parse::<u32>().ok()) else {
+                continue;
+            };
+            let cpulist = std::fs::read_to_string(entry.path().join("cpulist"))
+                .with_context(|| format!("Failed to read cpulist for node {}", id))?;
+            let cpus = parse_cpulist(&cpulist)?;
+            // Memory-only nodes (CXL, HBM) have no CPUs to bind to
+            if cpus.is_empty() {
+                continue;
+            }
+            let memory_kb = std::fs::read_to_string(entry.path().join("meminfo"))
+                .ok()
+                .and_then(|m| parse_mem_total(&m));
+            nodes.push(NumaNode { id, cpus, memory_kb });
+        }
+        nodes.sort_by_key(|n| n.id);
+        Ok(Self { nodes })
+    }
+
+    pub fn is_numa(&self) -> bool {
+        self.nodes.len() > 1
+    }
+
+    pub fn node(&self, id: u32) -> Result<&NumaNode> {
+        self.nodes.iter().find(|n| n.id == id).ok_or_else(|| {
+            let ids: Vec<String> = self.nodes.iter().map(|n| n.id.to_string()).collect();
+            anyhow!("NUMA node {} not found (available: {})", id, ids.join(", "))
+        })
+    }
+}
+
+/// Parse a kernel CPU list such as `0-7,16-23`
+pub fn parse_cpulist(list: &str) -> Result<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for part in list.trim().split(',').filter(|p| !p.is_empty()) {
+        let parse = |s: &str| s.trim().parse::<usize>().map_err(|_| anyhow!("Invalid CPU list: {}", list.trim()));
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (parse(start)?, parse(end)?);
+                if start > end {
+                    return Err(anyhow!("Invalid CPU range: {}", part));
+                }
+                cpus.extend(start..=end);
+            }
+            None => cpus.push(parse(part)?),
+        }
+    }
+    cpus.sort_unstable();
+    cpus.dedup();
+    Ok(cpus)
+}
+
+/// Format CPUs as a compact kernel CPU list
+pub fn format_cpulist(cpus: &[usize]) -> String {
+    let mut sorted = cpus.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for cpu in sorted {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == cpu => *end = cpu,
+            _ => ranges.push((cpu, cpu)),
+        }
+    }
+    ranges.iter()
+        .map(|(start, end)| if start == end { start.to_string() } else { format!("{}-{}", start, end) })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// `Node 0 MemTotal:  32823528 kB`
+fn parse_mem_total(meminfo: &str) -> Option<u64> {
+    meminfo.lines()
+        .find(|l| l.contains("MemTotal:"))
+        .and_then(|l| l.split_whitespace().rev().nth(1))
+        .and_then(|kb| kb.parse().ok())
+}
+
+/// Tool used to apply affinity to a child process
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AffinityTool {
+    Numactl,
+    Taskset,
+}
+
+impl AffinityTool {
+    pub fn detect() -> Option<Self> {
+        if !cfg!(target_os = "linux") {
+            return None;
+        }
+        if on_path("numactl") {
+            Some(Self::Numactl)
+        } else if on_path("taskset") {
+            Some(Self::Taskset)
+        } else {
+            None
+        }
+    }
+
+    /// Command-line prefix that runs a program on `node`
+    pub fn prefix(&self, node: &NumaNode) -> Vec<String> {
+        match self {
+            Self::Numactl => vec![
+                "numactl".to_string(),
+                format!("--cpunodebind={}", node.id),
+                format!("--membind={}", node.id),
+            ],
+            Self::Taskset => vec!["taskset".to_string(), "-c".to_string(), format_cpulist(&node.cpus)],
+        }
+    }
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+/// Rebuild `cmd` so it runs bound to `node`, keeping its arguments, environment and directory
+pub fn bind_command(cmd: &Command, node: &NumaNode) -> Result<Command> {
+    let tool = AffinityTool::detect()
+        .ok_or_else(|| anyhow!("CPU affinity needs numactl or taskset (util-linux) on Linux"))?;
+    let prefix = tool.prefix(node);
+
+    let mut bound = Command::new(&prefix[0]);
+    bound.args(&prefix[1..]).arg(cmd.get_program()).args(cmd.get_args());
+    if let Some(dir) = cmd.get_current_dir() {
+        bound.current_dir(dir);
+    }
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => bound.env(key, value),
+            None => bound.env_remove(key),
+        };
+    }
+    Ok(bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpulist_round_trip() {
+        assert_eq!(parse_cpulist("0-3,8,10-11\n").unwrap(), vec![0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(format_cpulist(&[11, 0, 1, 2, 3, 8, 10]), "0-3,8,10-11");
+        assert!(parse_cpulist("").unwrap().is_empty());
+        assert!(parse_cpulist("4-2").is_err());
+        assert_eq!(parse_mem_total("Node 1 MemTotal:       32823528 kB\nNode 1 MemFree: 1 kB"), Some(32823528));
+    }
+
+    #[test]
+    fn test_from_sysfs() {
+        let root = std::env::temp_dir().join(format!("arm-numa-test-{}", std::process::id()));
+        for (node, cpus) in [("node0", "0-3"), ("node1", "4-7"), ("node2", "")] {
+            std::fs::create_dir_all(root.join(node)).unwrap();
+            std::fs::write(root.join(node).join("cpulist"), format!("{}\n", cpus)).unwrap();
+        }
+        std::fs::create_dir_all(root.join("power")).unwrap();
+
+        let topology = NumaTopology::from_sysfs(&root).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(topology.is_numa());
+        assert_eq!(topology.nodes.len(), 2);
+        assert_eq!(topology.node(1).unwrap().cpus, vec![4, 5, 6, 7]);
+        assert!(topology.node(2).is_err());
+
+        let prefix = AffinityTool::Taskset.prefix(topology.node(1).unwrap());
+        assert_eq!(prefix, vec!["taskset", "-c", "4-7"]);
+    }
+}
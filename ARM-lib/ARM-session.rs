@@ -0,0 +1,204 @@
+//! Safe session API over the ARM assembly routines
+//!
+//! `ArmSession` is the only way to reach the register routines from outside
+//! this crate. Each call checks what the assembly assumes (an x86-64 CPU with
+//! the features it probes, non-empty workloads, aligned SIMD buffers, non-zero
+//! loop counts) before crossing the FFI boundary, and register save/restore is
+//! tied to a guard so a restore can never happen without a matching save.
+
+use super::{ArmContext, OptimizationLevel, PerformanceMetrics, RegisterOptimization, RegisterState, SimdPattern};
+use anyhow::{anyhow, Result};
+use std::num::NonZeroU64;
+use std::ops::{Deref, DerefMut};
+
+/// Bytes the SIMD routine writes per iteration (one 256-bit YMM store)
+pub const SIMD_CHUNK_BYTES: usize = 32;
+
+/// Fail unless the assembly can run on this machine
+pub fn ensure_supported() -> Result<()> {
+    if cfg!(target_arch = "x86_64") {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "ARM register routines are x86-64 assembly; unsupported on {}",
+            std::env::consts::ARCH
+        ))
+    }
+}
+
+/// A non-empty computation workload
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workload(Vec<u64>);
+
+impl Workload {
+    pub fn new(values: Vec<u64>) -> Result<Self> {
+        if values.is_empty() {
+            return Err(anyhow!("Workload cannot be empty"));
+        }
+        Ok(Self(values))
+    }
+
+    pub fn as_slice(&self) -> &[u64] {
+        &self.0
+    }
+}
+
+/// SIMD deployment size in bytes: a non-zero multiple of [`SIMD_CHUNK_BYTES`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VectorSize(usize);
+
+impl VectorSize {
+    pub fn new(bytes: usize) -> Result<Self> {
+        if bytes == 0 || bytes % SIMD_CHUNK_BYTES != 0 {
+            return Err(anyhow!(
+                "Vector size must be a non-zero multiple of {} bytes, got {}",
+                SIMD_CHUNK_BYTES, bytes
+            ));
+        }
+        Ok(Self(bytes))
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.0
+    }
+}
+
+/// One aligned chunk of the buffer the SIMD routine fills with `vmovdqa`
+#[repr(C, align(32))]
+#[derive(Clone, Copy)]
+struct SimdChunk([u64; SIMD_CHUNK_BYTES / 8]);
+
+/// Safe handle to the ARM register routines
+pub struct ArmSession {
+    context: ArmContext,
+}
+
+impl ArmSession {
+    /// Open a session; fails on architectures the assembly does not support
+    pub fn new() -> Result<Self> {
+        ensure_supported()?;
+        Ok(Self { context: ArmContext::new() })
+    }
+
+    /// ARM LET RAX --map
+    pub fn rax_map(&mut self, optimization: RegisterOptimization, flags: u64) -> Result<()> {
+        // SAFETY: the routine only touches its own allocation table; the
+        // session was created on x86-64.
+        unsafe { self.context.let_rax_map(optimization, flags) }
+    }
+
+    /// ARM LET RDX --optimize
+    pub fn rdx_optimize(&mut self, pattern: u64, workload: NonZeroU64) -> Result<()> {
+        // SAFETY: a zero workload would feed `bsr` an undefined result.
+        unsafe { self.context.let_rdx_optimize(pattern, workload.get()) }
+    }
+
+    /// ARM LET SIMD --deploy
+    pub fn simd_deploy(&mut self, size: VectorSize, pattern: SimdPattern) -> Result<()> {
+        // The routine's "SSE" probe reads CPUID bit 25 (AES-NI); without it
+        // it falls back to a scalar path that cannot be given a valid buffer.
+        #[cfg(target_arch = "x86_64")]
+        if !std::arch::is_x86_feature_detected!("aes") {
+            return Err(anyhow!("SIMD deploy requires a CPU with AES-NI"));
+        }
+
+        let mut buffer = vec![SimdChunk([0; SIMD_CHUNK_BYTES / 8]); size.bytes() / SIMD_CHUNK_BYTES];
+        // SAFETY: the buffer is 32-byte aligned and exactly `size` bytes, and
+        // `size` is a non-zero multiple of the 32-byte store width.
+        unsafe {
+            self.context.let_simd_deploy(
+                size.bytes(),
+                pattern,
+                buffer.as_mut_ptr() as *mut u64,
+            )
+        }
+    }
+
+    /// Optimize a workload at the given level, returning elapsed cycles
+    pub fn optimize(&mut self, workload: &Workload, level: OptimizationLevel) -> Result<u64> {
+        // SAFETY: `Workload` is never empty, so the routine's read of the
+        // first element is in bounds.
+        unsafe { self.context.optimize_computation(workload.as_slice(), level) }
+    }
+
+    /// Benchmark a register pattern
+    pub fn benchmark(&mut self, pattern: u64, iterations: NonZeroU64) -> Result<PerformanceMetrics> {
+        // SAFETY: the benchmark loop uses `loop`, which would wrap on zero.
+        unsafe { self.context.benchmark(pattern, iterations.get()) }
+    }
+
+    /// Current register state
+    pub fn state(&self) -> RegisterState {
+        // SAFETY: reads the routine's allocation table and perf counters only.
+        unsafe { self.context.get_register_state() }
+    }
+
+    /// Save the register context; it is restored when the guard drops
+    pub fn save(&mut self) -> Result<SavedContext<'_>> {
+        // SAFETY: pairs with the restore in `SavedContext::drop`.
+        unsafe { self.context.save_context()? };
+        Ok(SavedContext { session: self })
+    }
+
+    pub fn history(&self) -> &[(RegisterOptimization, u64)] {
+        self.context.get_optimization_history()
+    }
+
+    pub fn clear_history(&mut self) {
+        self.context.clear_history();
+    }
+}
+
+/// Saved register context; derefs to the session and restores on drop
+pub struct SavedContext<'a> {
+    session: &'a mut ArmSession,
+}
+
+impl SavedContext<'_> {
+    /// Restore now instead of at the end of the scope
+    pub fn restore(self) {
+        drop(self);
+    }
+}
+
+impl Deref for SavedContext<'_> {
+    type Target = ArmSession;
+
+    fn deref(&self) -> &ArmSession {
+        self.session
+    }
+}
+
+impl DerefMut for SavedContext<'_> {
+    fn deref_mut(&mut self) -> &mut ArmSession {
+        self.session
+    }
+}
+
+impl Drop for SavedContext<'_> {
+    fn drop(&mut self) {
+        // SAFETY: the guard only exists after a successful save.
+        unsafe {
+            let _ = self.session.context.restore_context();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preconditions() {
+        assert!(Workload::new(Vec::new()).is_err());
+        assert_eq!(Workload::new(vec![7]).unwrap().as_slice(), &[7]);
+
+        assert!(VectorSize::new(0).is_err());
+        assert!(VectorSize::new(48).is_err());
+        assert_eq!(VectorSize::new(256).unwrap().bytes(), 256);
+
+        assert_eq!(std::mem::align_of::<SimdChunk>(), SIMD_CHUNK_BYTES);
+        assert_eq!(std::mem::size_of::<SimdChunk>(), SIMD_CHUNK_BYTES);
+        assert_eq!(ensure_supported().is_ok(), cfg!(target_arch = "x86_64"));
+    }
+}
@@ -6,8 +6,15 @@
 use anyhow::{anyhow, Context, Result};
 use std::ffi::c_void;
 use std::mem;
+use std::num::NonZeroU64;
 use std::slice;
 
+pub mod crypto;
+pub mod numa;
+pub mod session;
+
+pub use session::{ArmSession, SavedContext, VectorSize, Workload};
+
 /// Register optimization types for LET commands
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum RegisterOptimization {
@@ -55,6 +62,8 @@ pub struct PerformanceMetrics {
 }
 
 /// ARM context for managing register operations
+///
+/// The raw FFI calls are private; use [`ArmSession`] from outside the crate.
 pub struct ArmContext {
     saved_state: Option<RegisterState>,
     performance_baseline: u64,
@@ -65,7 +74,7 @@ pub struct ArmContext {
 extern "C" {
     fn arm_let_rax_map(computation_type: u64, optimization_flags: u64);
     fn arm_let_rdx_optimize(pattern: u64, target_workload: u64);
-    fn arm_let_simd_deploy(vector_size: u64, pattern_ptr: *const u64);
+    fn arm_let_simd_deploy(vector_size: u64, buffer_ptr: *mut u64);
     fn arm_get_register_state() -> u64;
     fn arm_optimize_computation(workload_ptr: *const u64, optimization_level: u64) -> u64;
     fn arm_benchmark_registers(test_pattern: u64, iterations: u64) -> u64;
@@ -87,7 +96,7 @@ impl ArmContext {
     }
 
     /// ARM LET RAX --map: Map RAX register for specific computation
-    pub unsafe fn let_rax_map(&mut self, optimization: RegisterOptimization, flags: u64) -> Result<()> {
+    unsafe fn let_rax_map(&mut self, optimization: RegisterOptimization, flags: u64) -> Result<()> {
         self.save_context()?;
         
         arm_let_rax_map(optimization as u64, flags);
@@ -100,7 +109,7 @@ impl ArmContext {
     }
 
     /// ARM LET RDX --optimize: Optimize RDX register usage
-    pub unsafe fn let_rdx_optimize(&mut self, pattern: u64, workload: u64) -> Result<()> {
+    unsafe fn let_rdx_optimize(&mut self, pattern: u64, workload: u64) -> Result<()> {
         self.save_context()?;
         
         arm_let_rdx_optimize(pattern, workload);
@@ -108,8 +117,10 @@ impl ArmContext {
         Ok(())
     }
 
-    /// ARM LET SIMD --deploy: Deploy SIMD optimization
-    pub unsafe fn let_simd_deploy(&mut self, vector_size: usize, pattern: SimdPattern) -> Result<()> {
+    /// ARM LET SIMD --deploy: Deploy SIMD optimization into `buffer`
+    ///
+    /// `buffer` must be 32-byte aligned and `vector_size` bytes long.
+    unsafe fn let_simd_deploy(&mut self, vector_size: usize, pattern: SimdPattern, buffer: *mut u64) -> Result<()> {
         let pattern_value = match pattern {
             SimdPattern::Sequential => 0x0123456789ABCDEF,
             SimdPattern::Reverse => 0xFEDCBA9876543210,
@@ -118,13 +129,15 @@ impl ArmContext {
             SimdPattern::Custom(val) => val,
         };
 
-        arm_let_simd_deploy(vector_size as u64, &pattern_value as *const u64);
+        // Seed the buffer with the pattern; the routine replicates its own table over it
+        *buffer = pattern_value;
+        arm_let_simd_deploy(vector_size as u64, buffer);
         
         Ok(())
     }
 
     /// Get current register state
-    pub unsafe fn get_register_state(&self) -> RegisterState {
+    unsafe fn get_register_state(&self) -> RegisterState {
         let raw_state = arm_get_register_state();
         
         RegisterState {
@@ -136,7 +149,7 @@ impl ArmContext {
     }
 
     /// Optimize computation with specified level
-    pub unsafe fn optimize_computation(&mut self, workload: &[u64], level: OptimizationLevel) -> Result<u64> {
+    unsafe fn optimize_computation(&mut self, workload: &[u64], level: OptimizationLevel) -> Result<u64> {
         if workload.is_empty() {
             return Err(anyhow!("Workload cannot be empty"));
         }
@@ -146,7 +159,7 @@ impl ArmContext {
     }
 
     /// Benchmark register performance patterns
-    pub unsafe fn benchmark(&mut self, pattern: u64, iterations: u64) -> Result<PerformanceMetrics> {
+    unsafe fn benchmark(&mut self, pattern: u64, iterations: u64) -> Result<PerformanceMetrics> {
         arm_perf_start();
         let cycles = arm_benchmark_registers(pattern, iterations);
         arm_perf_end();
@@ -162,15 +175,18 @@ impl ArmContext {
         })
     }
 
-    /// Save current register context
+    /// Save current register context; nested saves keep the outermost snapshot
     unsafe fn save_context(&mut self) -> Result<()> {
+        if self.saved_state.is_some() {
+            return Ok(());
+        }
         arm_save_register_context();
         self.saved_state = Some(self.get_register_state());
         Ok(())
     }
 
     /// Restore register context
-    pub unsafe fn restore_context(&mut self) -> Result<()> {
+    unsafe fn restore_context(&mut self) -> Result<()> {
         if self.saved_state.is_none() {
             return Err(anyhow!("No saved context to restore"));
         }
@@ -240,15 +256,21 @@ impl Drop for ArmContext {
 
 /// High-level ARM LET command interface
 pub struct ArmLet {
-    context: ArmContext,
+    session: Option<ArmSession>,
 }
 
 impl ArmLet {
     /// Create new ARM LET interface
     pub fn new() -> Self {
-        Self {
-            context: ArmContext::new(),
+        Self { session: None }
+    }
+
+    /// Session opened on first use, so parsing works on any architecture
+    fn session(&mut self) -> Result<&mut ArmSession> {
+        if self.session.is_none() {
+            self.session = Some(ArmSession::new()?);
         }
+        Ok(self.session.as_mut().expect("session was just opened"))
     }
 
     /// Execute ARM LET command: arm let rax --map
@@ -256,51 +278,55 @@ impl ArmLet {
         let optimization = self.parse_computation_type(computation)?;
         let flag_value = self.parse_optimization_flags(flags)?;
         
-        unsafe {
-            self.context.let_rax_map(optimization, flag_value)
-        }
+        self.session()?.rax_map(optimization, flag_value)
     }
 
     /// Execute ARM LET command: arm let rdx --optimize  
     pub fn rdx_optimize(&mut self, pattern: &str, workload: u64) -> Result<()> {
         let pattern_value = self.parse_optimization_pattern(pattern)?;
         
-        unsafe {
-            self.context.let_rdx_optimize(pattern_value, workload)
-        }
+        let workload = NonZeroU64::new(workload).ok_or_else(|| anyhow!("RDX workload must be non-zero"))?;
+        self.session()?.rdx_optimize(pattern_value, workload)
     }
 
     /// Execute ARM LET command: arm let simd --deploy
     pub fn simd_deploy(&mut self, vector_size: usize, pattern: &str) -> Result<()> {
         let simd_pattern = self.parse_simd_pattern(pattern)?;
+        let size = VectorSize::new(vector_size)?;
         
-        unsafe {
-            self.context.let_simd_deploy(vector_size, simd_pattern)
-        }
+        self.session()?.simd_deploy(size, simd_pattern)
     }
 
     /// Execute ARM LET command: arm let benchmark --run
     pub fn benchmark_run(&mut self, pattern: &str, iterations: u64) -> Result<PerformanceMetrics> {
         let pattern_value = self.parse_optimization_pattern(pattern)?;
         
-        unsafe {
-            self.context.benchmark(pattern_value, iterations)
-        }
+        let iterations = NonZeroU64::new(iterations).ok_or_else(|| anyhow!("Benchmark needs at least one iteration"))?;
+        self.session()?.benchmark(pattern_value, iterations)
     }
 
     /// Execute ARM LET command: arm let optimize --computation
     pub fn optimize_computation(&mut self, workload: &[u64], level: &str) -> Result<u64> {
         let opt_level = self.parse_optimization_level(level)?;
+        let workload = Workload::new(workload.to_vec())?;
         
-        unsafe {
-            self.context.optimize_computation(workload, opt_level)
-        }
+        self.session()?.optimize(&workload, opt_level)
     }
 
     /// Get register status
-    pub fn status(&self) -> Result<RegisterState> {
-        unsafe {
-            Ok(self.context.get_register_state())
+    pub fn status(&mut self) -> Result<RegisterState> {
+        Ok(self.session()?.state())
+    }
+
+    /// Optimizations applied in this session
+    pub fn history(&self) -> &[(RegisterOptimization, u64)] {
+        self.session.as_ref().map(|s| s.history()).unwrap_or(&[])
+    }
+
+    /// Clear the optimization history
+    pub fn clear_history(&mut self) {
+        if let Some(session) = self.session.as_mut() {
+            session.clear_history();
         }
     }
 
@@ -439,6 +465,14 @@ pub mod cli {
         Metrics,
         /// Reset ARM context
         Reset,
+        /// Show NUMA nodes and their CPUs
+        Numa,
+        /// Compare hardware and portable crypto throughput
+        Crypto {
+            /// Benchmark on this file instead of a generated sample
+            #[arg(long)]
+            file: Option<std::path::PathBuf>,
+        },
     }
 
     /// Execute ARM CLI command
@@ -489,15 +523,58 @@ pub mod cli {
             ArmCommands::Metrics => {
                 // Show performance metrics from context
                 println!("📈 Performance Metrics:");
-                println!("  Optimization history: {} entries", arm.context.optimization_history.len());
-                for (opt, cycles) in arm.context.get_optimization_history() {
+                println!("  Optimization history: {} entries", arm.history().len());
+                for (opt, cycles) in arm.history() {
                     println!("    {:?}: {} cycles", opt, cycles);
                 }
             }
             ArmCommands::Reset => {
-                arm.context.clear_history();
+                arm.clear_history();
                 println!("🔄 ARM context reset");
             }
+            ArmCommands::Numa => {
+                let topology = numa::NumaTopology::detect()?;
+                println!("🧩 NUMA Topology ({} node{}):", topology.nodes.len(), if topology.is_numa() { "s" } else { "" });
+                for node in &topology.nodes {
+                    let memory = node.memory_kb
+                        .map(|kb| format!(", {} MiB", kb / 1024))
+                        .unwrap_or_default();
+                    println!("  Node {}: CPUs {}{}", node.id, numa::format_cpulist(&node.cpus), memory);
+                }
+            }
+            ArmCommands::Crypto { file } => {
+                let data = match &file {
+                    Some(path) => std::fs::read(path)
+                        .with_context(|| format!("Failed to read {}", path.display()))?,
+                    None => crypto::bench_sample(),
+                };
+                println!("🔐 Crypto throughput over {} KiB:", data.len() / 1024);
+                for primitive in crypto::Primitive::ALL {
+                    let portable = match primitive {
+                        crypto::Primitive::Sha256 => crypto::throughput(&data, |d| {
+                            let mut hasher = crypto::Sha256::portable();
+                            hasher.update(d);
+                            hasher.finalize();
+                        }),
+                        crypto::Primitive::Crc32c => crypto::throughput(&data, |d| { crypto::crc32c_portable(0, d); }),
+                        crypto::Primitive::Memeq => crypto::throughput(&data, |d| { crypto::memeq_portable(d, d); }),
+                    };
+                    if !primitive.accelerated() {
+                        println!("  {:?}: {:.0} MiB/s (no hardware path)", primitive, portable);
+                        continue;
+                    }
+                    let hardware = match primitive {
+                        crypto::Primitive::Sha256 => crypto::throughput(&data, |d| { crypto::sha256(d); }),
+                        crypto::Primitive::Crc32c => crypto::throughput(&data, |d| { crypto::crc32c(d); }),
+                        crypto::Primitive::Memeq => crypto::throughput(&data, |d| { crypto::memeq(d, d); }),
+                    };
+                    println!("  {:?}: {:.0} MiB/s hardware, {:.0} MiB/s portable ({:.1}x)",
+                        primitive, hardware, portable, hardware / portable.max(f64::MIN_POSITIVE));
+                }
+                if file.is_some() {
+                    println!("  SHA-256: {}", crypto::to_hex(&crypto::sha256(&data)));
+                }
+            }
         }
 
         Ok(())
@@ -0,0 +1,295 @@
+//! Automatic serving backend selection
+//!
+//! Picks a backend from the model format, the detected hardware and the
+//! runtimes available on PATH, and records why each decision was made
+
+use anyhow::{anyhow, Result};
+use tokio::process::Command as AsyncCommand;
+use crate::gguf::GgufMetadata;
+use crate::{ModelFormat, ServingBackend};
+
+/// Environment variable used to force a backend without passing `--backend`
+pub const BACKEND_ENV: &str = "RCM_GPT_BACKEND";
+
+/// Environment variable holding the remote OpenAI-compatible endpoint
+pub const REMOTE_URL_ENV: &str = "RCM_GPT_REMOTE_URL";
+
+/// Detected accelerator
+#[derive(Debug, Clone)]
+pub struct GpuInfo {
+    pub vendor: String,
+    pub name: String,
+    pub memory_mb: Option<u64>,
+}
+
+/// Hardware available for serving
+#[derive(Debug, Clone, Default)]
+pub struct HardwareProfile {
+    pub gpu: Option<GpuInfo>,
+    pub cpu_threads: usize,
+}
+
+/// Serving runtimes found on this machine
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeAvailability {
+    pub ollama: bool,
+    pub llamacpp: bool,
+    pub vllm: bool,
+    /// Remote endpoint from `RCM_GPT_REMOTE_URL`, if configured
+    pub remote: Option<String>,
+}
+
+/// Result of backend selection
+#[derive(Debug, Clone)]
+pub struct BackendChoice {
+    pub backend: ServingBackend,
+    pub gpu_layers: Option<u32>,
+    pub reasons: Vec<String>,
+}
+
+impl BackendChoice {
+    /// Print the reasoning behind the selection (`--explain`)
+    pub fn explain(&self) {
+        println!("🧭 Backend selection: {:?}", self.backend);
+        if let Some(layers) = self.gpu_layers {
+            println!("   GPU layers: {}", layers);
+        }
+        for reason in &self.reasons {
+            println!("   • {}", reason);
+        }
+    }
+}
+
+impl HardwareProfile {
+    /// Probe the machine for GPUs and CPU threads
+    pub async fn detect() -> Self {
+        let cpu_threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+
+        Self {
+            gpu: detect_gpu().await,
+            cpu_threads,
+        }
+    }
+}
+
+impl RuntimeAvailability {
+    /// Probe PATH for serving runtimes and read the configured remote endpoint
+    pub async fn detect() -> Self {
+        Self {
+            ollama: probe("ollama", &["--version"]).await,
+            llamacpp: probe("llama-server", &["--help"]).await,
+            vllm: probe("vllm", &["--version"]).await,
+            remote: remote_url(),
+        }
+    }
+
+    fn any_local(&self) -> bool {
+        self.ollama || self.llamacpp || self.vllm
+    }
+}
+
+/// The remote endpoint configured in `RCM_GPT_REMOTE_URL`
+pub fn remote_url() -> Option<String> {
+    std::env::var(REMOTE_URL_ENV).ok().filter(|url| !url.trim().is_empty())
+}
+
+/// Error for backends RCM has no serving runtime for
+pub fn unavailable(backend: &ServingBackend) -> anyhow::Error {
+    anyhow!(
+        "The {:?} backend is not available in this build. Use --backend llamacpp, ollama, vllm or remote",
+        backend
+    )
+}
+
+/// Select a serving backend
+///
+/// `preference` is the parsed `--backend` flag or `RCM_GPT_BACKEND`; when set it
+/// always wins and only the GPU layer suggestion is computed.
+pub fn select_backend(
+    format: &ModelFormat,
+    metadata: Option<&GgufMetadata>,
+    hardware: &HardwareProfile,
+    runtimes: &RuntimeAvailability,
+    preference: Option<ServingBackend>,
+) -> Result<BackendChoice> {
+    let mut reasons = Vec::new();
+
+    let gpu_layers = match (&hardware.gpu, metadata) {
+        (Some(gpu), Some(meta)) => {
+            let layers = suggest_gpu_layers(gpu, meta);
+            reasons.push(format!(
+                "{} {} detected; offloading {} layer(s)",
+                gpu.vendor, gpu.name, layers
+            ));
+            Some(layers)
+        }
+        (Some(gpu), None) => {
+            reasons.push(format!("{} {} detected", gpu.vendor, gpu.name));
+            None
+        }
+        (None, _) => {
+            reasons.push(format!("No GPU detected; using {} CPU thread(s)", hardware.cpu_threads));
+            None
+        }
+    };
+
+    if let Some(backend) = preference {
+        reasons.insert(0, format!("Backend {:?} requested explicitly", backend));
+        return Ok(BackendChoice { backend, gpu_layers, reasons });
+    }
+
+    let backend = match format {
+        ModelFormat::Ollama if runtimes.ollama => {
+            reasons.push("Model was pulled through Ollama".to_string());
+            ServingBackend::Ollama
+        }
+        ModelFormat::GGUF if runtimes.llamacpp && hardware.gpu.is_some() => {
+            reasons.push("GGUF model with GPU available → llama.cpp".to_string());
+            ServingBackend::LlamaCpp
+        }
+        ModelFormat::GGUF if runtimes.llamacpp => {
+            reasons.push("GGUF model → llama.cpp".to_string());
+            ServingBackend::LlamaCpp
+        }
+        ModelFormat::GGUF if runtimes.ollama => {
+            reasons.push("GGUF model, llama-server missing → Ollama".to_string());
+            ServingBackend::Ollama
+        }
+        ModelFormat::Safetensors | ModelFormat::PyTorch if runtimes.vllm && hardware.gpu.is_some() => {
+            reasons.push("Safetensors weights with GPU available → vLLM".to_string());
+            ServingBackend::Vllm
+        }
+        _ if runtimes.ollama => {
+            reasons.push(format!("No specialised runtime for {:?}; falling back to Ollama", format));
+            ServingBackend::Ollama
+        }
+        _ => {
+            let url = runtimes.remote.clone().ok_or_else(|| anyhow!(
+                "No local runtime can serve {:?} and no remote endpoint is configured. \
+                 Install llama-server, ollama or vllm, or set {} to an OpenAI-compatible URL",
+                format, REMOTE_URL_ENV
+            ))?;
+            if runtimes.any_local() {
+                reasons.push(format!("No local runtime can serve {:?}; using remote API {}", format, url));
+            } else {
+                reasons.push(format!("No local runtime installed; using remote API {}", url));
+            }
+            ServingBackend::Remote(url)
+        }
+    };
+
+    Ok(BackendChoice { backend, gpu_layers, reasons })
+}
+
+/// Estimate how many layers fit in GPU memory
+fn suggest_gpu_layers(gpu: &GpuInfo, metadata: &GgufMetadata) -> u32 {
+    let total_layers = metadata.layer_count().unwrap_or(0) + 1;
+
+    match gpu.memory_mb {
+        Some(vram_mb) if metadata.file_size > 0 && total_layers > 1 => {
+            // Keep ~10% headroom for KV cache and scratch buffers
+            let usable = (vram_mb as f64 * 1024.0 * 1024.0) * 0.9;
+            let ratio = (usable / metadata.file_size as f64).min(1.0);
+            ((total_layers as f64) * ratio).floor() as u32
+        }
+        _ if total_layers > 1 => total_layers,
+        // Unknown layout: let llama.cpp offload everything it can
+        _ => 999,
+    }
+}
+
+/// Detect an NVIDIA, AMD or Apple GPU
+async fn detect_gpu() -> Option<GpuInfo> {
+    if let Ok(output) = AsyncCommand::new("nvidia-smi")
+        .args(["--query-gpu=name,memory.total", "--format=csv,noheader,nounits"])
+        .output()
+        .await
+    {
+        if output.status.success() {
+            let text = String::from_utf8_lossy(&output.stdout);
+            if let Some(line) = text.lines().next() {
+                let mut parts = line.split(',').map(|s| s.trim());
+                let name = parts.next().unwrap_or("GPU").to_string();
+                let memory_mb = parts.next().and_then(|m| m.parse().ok());
+                return Some(GpuInfo { vendor: "NVIDIA".to_string(), name, memory_mb });
+            }
+        }
+    }
+
+    if probe("rocm-smi", &["--showproductname"]).await {
+        return Some(GpuInfo {
+            vendor: "AMD".to_string(),
+            name: "ROCm device".to_string(),
+            memory_mb: None,
+        });
+    }
+
+    if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        return Some(GpuInfo {
+            vendor: "Apple".to_string(),
+            name: "Metal".to_string(),
+            memory_mb: None,
+        });
+    }
+
+    None
+}
+
+/// Check whether a command runs successfully
+async fn probe(command: &str, args: &[&str]) -> bool {
+    AsyncCommand::new(command)
+        .args(args)
+        .output()
+        .await
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Read the backend override from the environment
+pub fn env_preference() -> Option<String> {
+    std::env::var(BACKEND_ENV).ok().filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runtimes(ollama: bool, llamacpp: bool) -> RuntimeAvailability {
+        RuntimeAvailability { ollama, llamacpp, ..Default::default() }
+    }
+
+    #[test]
+    fn test_gguf_prefers_llamacpp() {
+        let choice = select_backend(&ModelFormat::GGUF, None, &HardwareProfile::default(), &runtimes(true, true), None).unwrap();
+        assert!(matches!(choice.backend, ServingBackend::LlamaCpp));
+    }
+
+    #[test]
+    fn test_explicit_preference_wins() {
+        let choice = select_backend(
+            &ModelFormat::GGUF,
+            None,
+            &HardwareProfile::default(),
+            &runtimes(true, true),
+            Some(ServingBackend::Ollama),
+        ).unwrap();
+        assert!(matches!(choice.backend, ServingBackend::Ollama));
+    }
+
+    #[test]
+    fn test_remote_fallback() {
+        let runtimes = RuntimeAvailability { remote: Some("https://llm.internal/v1".to_string()), ..Default::default() };
+        let choice = select_backend(&ModelFormat::ONNX, None, &HardwareProfile::default(), &runtimes, None).unwrap();
+        assert!(matches!(choice.backend, ServingBackend::Remote(url) if url == "https://llm.internal/v1"));
+    }
+
+    #[test]
+    fn test_no_runtime_without_remote_endpoint() {
+        let err = select_backend(&ModelFormat::Safetensors, None, &HardwareProfile::default(), &runtimes(false, false), None)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(REMOTE_URL_ENV), "{}", err);
+    }
+}
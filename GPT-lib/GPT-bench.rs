@@ -0,0 +1,445 @@
+//! Serving benchmarks (`rcm gpt bench`)
+//!
+//! Runs a fixed prompt set against a running model through its streaming API
+//! and measures time-to-first-token, generation speed and the resident memory
+//! of the backend processes. Results are appended to
+//! `.rcm/gpt-configs/benchmarks.json`; the comparison table shows the latest
+//! run for every model/backend pair so backends and quantizations can be
+//! weighed against each other on the same machine.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+use crate::{secrets, GptManager, ModelInstance, ServingBackend};
+
+const RESULTS_FILE: &str = "benchmarks.json";
+
+/// Runs kept in the results file
+const MAX_HISTORY: usize = 200;
+
+/// The standard prompt set: short answers, code, summarising and long-form output
+pub const PROMPTS: [(&str, &str); 5] = [
+    ("qa", "What is the capital of Australia? Answer in one sentence."),
+    ("code", "Write a Rust function that returns the n-th Fibonacci number iteratively."),
+    ("summary", "Summarise in three bullet points why package lockfiles matter for reproducible builds."),
+    ("reasoning", "A train leaves at 14:10 and arrives at 17:45. How long is the journey? Explain your steps."),
+    ("story", "Write a short story about a lighthouse keeper who finds a message in a bottle."),
+];
+
+/// Options for `rcm gpt bench`
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// Times the prompt set is run
+    pub repetitions: u32,
+    /// Tokens generated per prompt
+    pub max_tokens: usize,
+    /// Print results without adding them to the results file
+    pub no_save: bool,
+}
+
+/// Measurements for one prompt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptResult {
+    pub prompt: String,
+    pub ttft_ms: f64,
+    pub total_ms: f64,
+    pub tokens: u64,
+    pub tokens_per_sec: f64,
+}
+
+/// One benchmark run of a model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchResult {
+    pub model: String,
+    pub backend: String,
+    pub endpoint: String,
+    pub max_tokens: usize,
+    pub run_at: String,
+    /// Median time to first token
+    pub ttft_ms: f64,
+    /// Generated tokens per second of generation time, over all prompts
+    pub tokens_per_sec: f64,
+    /// Peak resident memory of the backend processes; `None` for remote backends
+    pub peak_memory: Option<u64>,
+    pub prompts: Vec<PromptResult>,
+}
+
+/// What one streamed chunk of a response carried
+#[derive(Debug, Default, PartialEq)]
+pub struct StreamEvent {
+    pub text: String,
+    /// Server-reported generated tokens, on the final chunk
+    pub tokens: Option<u64>,
+    /// Server-reported generation time (Ollama `eval_duration`)
+    pub generation: Option<Duration>,
+    pub done: bool,
+}
+
+/// Parse one line of Ollama's NDJSON `/api/generate` stream
+pub fn parse_ollama_line(line: &str) -> Option<StreamEvent> {
+    let value: Value = serde_json::from_str(line.trim()).ok()?;
+    Some(StreamEvent {
+        text: value["response"].as_str().unwrap_or_default().to_string(),
+        tokens: value["eval_count"].as_u64(),
+        generation: value["eval_duration"].as_u64().map(Duration::from_nanos),
+        done: value["done"].as_bool().unwrap_or(false),
+    })
+}
+
+/// Parse one server-sent event line of an OpenAI-compatible completions stream
+pub fn parse_sse_line(line: &str) -> Option<StreamEvent> {
+    let data = line.trim().strip_prefix("data:")?.trim();
+    if data == "[DONE]" {
+        return Some(StreamEvent { done: true, ..Default::default() });
+    }
+    let value: Value = serde_json::from_str(data).ok()?;
+    Some(StreamEvent {
+        text: value["choices"][0]["text"].as_str().unwrap_or_default().to_string(),
+        tokens: value["usage"]["completion_tokens"].as_u64(),
+        generation: None,
+        done: false,
+    })
+}
+
+/// Turn timings of one streamed response into a result
+///
+/// The first token is counted in the time to first token, so generation speed
+/// covers the tokens after it unless the server reported its own timing.
+pub fn measure(prompt: &str, ttft: Duration, total: Duration, tokens: u64, generation: Option<Duration>) -> PromptResult {
+    let tokens_per_sec = match generation {
+        Some(generation) if !generation.is_zero() => tokens as f64 / generation.as_secs_f64(),
+        _ if tokens > 1 && total > ttft => (tokens - 1) as f64 / (total - ttft).as_secs_f64(),
+        _ if !total.is_zero() => tokens as f64 / total.as_secs_f64(),
+        _ => 0.0,
+    };
+    PromptResult {
+        prompt: prompt.to_string(),
+        ttft_ms: ttft.as_secs_f64() * 1000.0,
+        total_ms: total.as_secs_f64() * 1000.0,
+        tokens,
+        tokens_per_sec,
+    }
+}
+
+/// Median time to first token and the token-weighted generation speed
+pub fn summarize(prompts: &[PromptResult]) -> (f64, f64) {
+    let mut ttfts: Vec<f64> = prompts.iter().map(|p| p.ttft_ms).collect();
+    ttfts.sort_by(f64::total_cmp);
+    let ttft = match ttfts.len() {
+        0 => 0.0,
+        n if n % 2 == 1 => ttfts[n / 2],
+        n => (ttfts[n / 2 - 1] + ttfts[n / 2]) / 2.0,
+    };
+    let tokens: u64 = prompts.iter().map(|p| p.tokens).sum();
+    let seconds: f64 = prompts.iter()
+        .filter(|p| p.tokens_per_sec > 0.0)
+        .map(|p| p.tokens as f64 / p.tokens_per_sec)
+        .sum();
+    let tokens_per_sec = if seconds > 0.0 { tokens as f64 / seconds } else { 0.0 };
+    (ttft, tokens_per_sec)
+}
+
+/// Latest run per model and backend, fastest first
+pub fn latest_per_model(results: &[BenchResult]) -> Vec<&BenchResult> {
+    let mut latest: Vec<&BenchResult> = Vec::new();
+    for result in results {
+        match latest.iter_mut().find(|r| r.model == result.model && r.backend == result.backend) {
+            Some(existing) if existing.run_at < result.run_at => *existing = result,
+            Some(_) => {}
+            None => latest.push(result),
+        }
+    }
+    latest.sort_by(|a, b| b.tokens_per_sec.total_cmp(&a.tokens_per_sec));
+    latest
+}
+
+/// Resident memory of the given processes, in bytes
+async fn resident_memory(pids: &[u32]) -> Option<u64> {
+    if pids.is_empty() || cfg!(windows) {
+        return None;
+    }
+    let mut total = 0;
+    for pid in pids {
+        let output = tokio::process::Command::new("ps")
+            .args(["-o", "rss=", "-p", &pid.to_string()])
+            .output()
+            .await
+            .ok()?;
+        let kib: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+        total += kib * 1024;
+    }
+    Some(total)
+}
+
+/// Bearer token for the instance, resolving `secret:` references
+async fn auth_token(instance: &ModelInstance) -> Result<Option<String>> {
+    match instance.config.serving_config.auth_token.clone().or_else(|| std::env::var("RCM_GPT_API_KEY").ok()) {
+        Some(token) => Ok(Some(secrets::resolve_value(&token).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Stream one completion and time it
+async fn run_prompt(client: &reqwest::Client, instance: &ModelInstance, token: Option<&str>, id: &str, prompt: &str, max_tokens: usize) -> Result<PromptResult> {
+    let endpoint = instance.endpoint.trim_end_matches('/');
+    let (url, body, parse): (String, Value, fn(&str) -> Option<StreamEvent>) = match instance.config.backend {
+        ServingBackend::Ollama => (
+            format!("{}/api/generate", endpoint),
+            serde_json::json!({
+                "model": instance.config.name,
+                "prompt": prompt,
+                "stream": true,
+                "options": { "num_predict": max_tokens, "temperature": 0.0 },
+            }),
+            parse_ollama_line,
+        ),
+        ServingBackend::LlamaCpp | ServingBackend::Vllm | ServingBackend::Remote(_) => {
+            // llama-server is registered without the `/v1` prefix vLLM and remotes carry
+            let base = match instance.config.backend {
+                ServingBackend::LlamaCpp => format!("{}/v1", endpoint),
+                _ => endpoint.to_string(),
+            };
+            (
+                format!("{}/completions", base),
+                serde_json::json!({
+                    "model": instance.config.name,
+                    "prompt": prompt,
+                    "max_tokens": max_tokens,
+                    "temperature": 0.0,
+                    "stream": true,
+                    "stream_options": { "include_usage": true },
+                }),
+                parse_sse_line,
+            )
+        }
+        _ => return Err(anyhow!("Benchmarking is not implemented for backend: {:?}", instance.config.backend)),
+    };
+
+    let mut request = client.post(&url).json(&body);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let started = Instant::now();
+    let mut response = request.send().await
+        .with_context(|| format!("Failed to reach {}", url))?;
+    if !response.status().is_success() {
+        return Err(anyhow!("API request failed: {}", response.status()));
+    }
+
+    let mut first_token = None;
+    let mut chunks = 0;
+    let mut reported = None;
+    let mut generation = None;
+    let mut buffer = String::new();
+    'stream: while let Some(bytes) = response.chunk().await? {
+        buffer.push_str(&String::from_utf8_lossy(&bytes));
+        while let Some(newline) = buffer.find('\n') {
+            let line: String = buffer.drain(..=newline).collect();
+            let Some(event) = parse(&line) else { continue };
+            if !event.text.is_empty() {
+                first_token.get_or_insert_with(|| started.elapsed());
+                chunks += 1;
+            }
+            reported = event.tokens.or(reported);
+            generation = event.generation.or(generation);
+            if event.done {
+                break 'stream;
+            }
+        }
+    }
+    let total = started.elapsed();
+
+    let first_token = first_token.ok_or_else(|| anyhow!("'{}' produced no tokens for the {} prompt", instance.config.name, id))?;
+    // Streams carry about one token per chunk when the server reports no usage
+    Ok(measure(id, first_token, total, reported.unwrap_or(chunks), generation))
+}
+
+impl GptManager {
+    /// Benchmark one running model with the standard prompt set
+    async fn bench_model(&self, model: &str, options: &BenchOptions) -> Result<BenchResult> {
+        let instance = self.registry.active_models.get(model)
+            .ok_or_else(|| anyhow!("Model '{}' is not running. Start it with 'rcm gpt serve {} --deploy'", model, model))?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(instance.config.serving_config.timeout_seconds.max(300)))
+            .build()?;
+        let token = auth_token(instance).await?;
+        let pids: Vec<u32> = instance.process_id.into_iter()
+            .chain(instance.replicas.iter().filter_map(|r| r.process_id))
+            .collect();
+
+        // Loading weights is not part of serving speed; a real failure shows up on the first prompt
+        let _ = run_prompt(&client, instance, token.as_deref(), "warmup", "Hello", 1).await;
+
+        let mut prompts = Vec::new();
+        let mut peak_memory = resident_memory(&pids).await;
+        for repetition in 0..options.repetitions.max(1) {
+            for (id, prompt) in PROMPTS {
+                let result = run_prompt(&client, instance, token.as_deref(), id, prompt, options.max_tokens).await?;
+                println!("   {:<10} run {}: {:>7.0} ms to first token, {:>7.2} tok/s ({} tokens)",
+                    id, repetition + 1, result.ttft_ms, result.tokens_per_sec, result.tokens);
+                prompts.push(result);
+                peak_memory = peak_memory.max(resident_memory(&pids).await);
+            }
+        }
+
+        let (ttft_ms, tokens_per_sec) = summarize(&prompts);
+        Ok(BenchResult {
+            model: model.to_string(),
+            backend: format!("{:?}", instance.config.backend),
+            endpoint: instance.endpoint.clone(),
+            max_tokens: options.max_tokens,
+            run_at: chrono::Utc::now().to_rfc3339(),
+            ttft_ms,
+            tokens_per_sec,
+            peak_memory,
+            prompts,
+        })
+    }
+
+    async fn load_benchmarks(&self) -> Vec<BenchResult> {
+        match tokio::fs::read_to_string(self.configs_dir.join(RESULTS_FILE)).await {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring unreadable {}: {}", RESULTS_FILE, e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Benchmark the given running models, or compare saved results when none are given
+    pub async fn bench(&self, models: &[String], options: &BenchOptions, format: &str) -> Result<()> {
+        if format != "table" && format != "json" {
+            return Err(anyhow!("Unsupported format: {}. Use table or json", format));
+        }
+        let mut results = self.load_benchmarks().await;
+
+        if self.dry_run() {
+            for model in models {
+                println!("[dry-run] Would run {} prompts x{} against '{}'", PROMPTS.len(), options.repetitions.max(1), model);
+            }
+            return Ok(());
+        }
+
+        for model in models {
+            println!("⏱️  Benchmarking {} ({} prompts, {} tokens each)...", model, PROMPTS.len(), options.max_tokens);
+            let result = self.bench_model(model, options).await?;
+            println!("✅ {}: {:.2} tok/s, {:.0} ms median time to first token{}",
+                model, result.tokens_per_sec, result.ttft_ms,
+                result.peak_memory.map(|m| format!(", {} peak memory", crate::util::format_bytes(m))).unwrap_or_default());
+            results.push(result);
+        }
+
+        if !models.is_empty() && !options.no_save {
+            let excess = results.len().saturating_sub(MAX_HISTORY);
+            results.drain(..excess);
+            tokio::fs::create_dir_all(&self.configs_dir).await?;
+            let path = self.configs_dir.join(RESULTS_FILE);
+            tokio::fs::write(&path, serde_json::to_string_pretty(&results)?).await
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+
+        let latest = latest_per_model(&results);
+        if format == "json" {
+            println!("{}", serde_json::to_string_pretty(&latest)?);
+            return Ok(());
+        }
+        if latest.is_empty() {
+            println!("No benchmark results yet. Run 'rcm gpt bench <model>' against a running model.");
+            return Ok(());
+        }
+        print_comparison(&latest);
+        Ok(())
+    }
+}
+
+fn print_comparison(latest: &[&BenchResult]) {
+    use tabled::{Table, Tabled};
+
+    #[derive(Tabled)]
+    struct BenchRow {
+        #[tabled(rename = "Model")]
+        model: String,
+        #[tabled(rename = "Backend")]
+        backend: String,
+        #[tabled(rename = "Tok/s")]
+        tokens_per_sec: String,
+        #[tabled(rename = "vs best")]
+        relative: String,
+        #[tabled(rename = "TTFT (ms)")]
+        ttft: String,
+        #[tabled(rename = "Peak memory")]
+        memory: String,
+        #[tabled(rename = "Run at")]
+        run_at: String,
+    }
+
+    let best = latest.first().map(|r| r.tokens_per_sec).unwrap_or(0.0);
+    let rows = latest.iter().map(|r| BenchRow {
+        model: r.model.clone(),
+        backend: r.backend.clone(),
+        tokens_per_sec: format!("{:.2}", r.tokens_per_sec),
+        relative: if best > 0.0 { format!("{:.0}%", r.tokens_per_sec / best * 100.0) } else { "-".to_string() },
+        ttft: format!("{:.0}", r.ttft_ms),
+        memory: r.peak_memory.map(crate::util::format_bytes).unwrap_or_else(|| "-".to_string()),
+        run_at: r.run_at.clone(),
+    });
+    println!("{}", Table::new(rows));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_parsing() {
+        let chunk = parse_ollama_line(r#"{"model":"llama3","response":"Can","done":false}"#).unwrap();
+        assert_eq!((chunk.text.as_str(), chunk.done), ("Can", false));
+        let last = parse_ollama_line(r#"{"response":"","done":true,"eval_count":42,"eval_duration":2000000000}"#).unwrap();
+        assert_eq!((last.tokens, last.generation, last.done), (Some(42), Some(Duration::from_secs(2)), true));
+
+        let chunk = parse_sse_line(r#"data: {"choices":[{"index":0,"text":" Canberra"}]}"#).unwrap();
+        assert_eq!(chunk.text, " Canberra");
+        let usage = parse_sse_line(r#"data: {"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":9}}"#).unwrap();
+        assert_eq!(usage.tokens, Some(9));
+        assert!(parse_sse_line("data: [DONE]").unwrap().done);
+        assert!(parse_sse_line(": keep-alive").is_none());
+    }
+
+    #[test]
+    fn test_measure_and_summarize() {
+        let server = measure("qa", Duration::from_millis(200), Duration::from_secs(3), 40, Some(Duration::from_secs(2)));
+        assert_eq!(server.tokens_per_sec, 20.0);
+        let client = measure("qa", Duration::from_millis(500), Duration::from_millis(2500), 41, None);
+        assert_eq!(client.tokens_per_sec, 20.0);
+
+        let slow = measure("code", Duration::from_millis(900), Duration::from_millis(4900), 41, None);
+        let (ttft, tps) = summarize(&[server, client, slow]);
+        assert_eq!(ttft, 500.0);
+        assert!((tps - 122.0 / 8.15).abs() < 1e-9);
+        assert_eq!(summarize(&[]), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_latest_per_model() {
+        let result = |model: &str, backend: &str, run_at: &str, tokens_per_sec| BenchResult {
+            model: model.to_string(),
+            backend: backend.to_string(),
+            endpoint: String::new(),
+            max_tokens: 128,
+            run_at: run_at.to_string(),
+            ttft_ms: 0.0,
+            tokens_per_sec,
+            peak_memory: None,
+            prompts: Vec::new(),
+        };
+        let results = vec![
+            result("llama3", "Ollama", "2026-01-01T00:00:00Z", 30.0),
+            result("llama3", "LlamaCpp", "2026-01-01T00:00:00Z", 35.0),
+            result("llama3", "Ollama", "2026-02-01T00:00:00Z", 40.0),
+        ];
+        let latest = latest_per_model(&results);
+        assert_eq!(latest.len(), 2);
+        assert_eq!((latest[0].backend.as_str(), latest[0].tokens_per_sec), ("Ollama", 40.0));
+    }
+}
@@ -0,0 +1,317 @@
+//! Interactive chat for `rcm gpt chat`
+//!
+//! Multi-turn conversations with system prompts, slash-commands and history
+//! persisted under `.rcm/gpt-configs/chats/`
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use crate::GptManager;
+
+/// Rough characters-per-token ratio used to budget the context window
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Speaker of a chat message
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+/// A single chat message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub content: String,
+}
+
+/// A persisted conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatSession {
+    pub name: String,
+    pub model: String,
+    pub system_prompt: Option<String>,
+    pub messages: Vec<ChatMessage>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Generation settings for a chat
+#[derive(Debug, Clone)]
+pub struct ChatOptions {
+    pub max_tokens: usize,
+    pub temperature: f32,
+    /// Model context length in tokens
+    pub context_length: usize,
+}
+
+impl ChatSession {
+    pub fn new(name: &str, model: &str, system_prompt: Option<String>) -> Self {
+        let now = chrono::Utc::now().to_rfc3339();
+        Self {
+            name: name.to_string(),
+            model: model.to_string(),
+            system_prompt,
+            messages: Vec::new(),
+            created_at: now.clone(),
+            updated_at: now,
+        }
+    }
+
+    /// Clear the conversation, keeping the model and system prompt
+    pub fn reset(&mut self) {
+        self.messages.clear();
+        self.updated_at = chrono::Utc::now().to_rfc3339();
+    }
+
+    pub fn push(&mut self, role: Role, content: &str) {
+        self.messages.push(ChatMessage { role, content: content.to_string() });
+        self.updated_at = chrono::Utc::now().to_rfc3339();
+    }
+
+    /// Assemble the prompt for the next turn
+    ///
+    /// Keeps the system prompt and as many of the most recent turns as fit in
+    /// `budget_chars`, then opens an assistant turn.
+    pub fn assemble_prompt(&self, budget_chars: usize) -> String {
+        let header = self
+            .system_prompt
+            .as_ref()
+            .map(|s| format!("System: {}\n\n", s))
+            .unwrap_or_default();
+
+        let mut remaining = budget_chars.saturating_sub(header.len());
+        let mut turns: Vec<String> = Vec::new();
+
+        for message in self.messages.iter().rev() {
+            let label = match message.role {
+                Role::System => "System",
+                Role::User => "User",
+                Role::Assistant => "Assistant",
+            };
+            let turn = format!("{}: {}\n", label, message.content);
+            // Always include the latest user message, even if it alone overflows
+            if turn.len() > remaining && !turns.is_empty() {
+                break;
+            }
+            remaining = remaining.saturating_sub(turn.len());
+            turns.push(turn);
+        }
+
+        turns.reverse();
+        format!("{}{}Assistant:", header, turns.concat())
+    }
+}
+
+fn session_path(chats_dir: &Path, name: &str) -> PathBuf {
+    let safe: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    chats_dir.join(format!("{}.json", safe))
+}
+
+/// Load a saved session
+pub async fn load_session(chats_dir: &Path, name: &str) -> Result<Option<ChatSession>> {
+    let path = session_path(chats_dir, name);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).await
+        .with_context(|| format!("Failed to read chat history {}", path.display()))?;
+    Ok(Some(serde_json::from_str(&content).context("Failed to parse chat history")?))
+}
+
+/// Save a session
+pub async fn save_session(chats_dir: &Path, session: &ChatSession) -> Result<PathBuf> {
+    fs::create_dir_all(chats_dir).await
+        .context("Failed to create chats directory")?;
+    let path = session_path(chats_dir, &session.name);
+    fs::write(&path, serde_json::to_string_pretty(session)?).await
+        .context("Failed to save chat history")?;
+    Ok(path)
+}
+
+/// Run one turn and record the reply
+pub async fn send(
+    manager: &GptManager,
+    session: &mut ChatSession,
+    message: &str,
+    options: &ChatOptions,
+) -> Result<String> {
+    session.push(Role::User, message);
+
+    // Leave room for the reply inside the model's context window
+    let budget = options
+        .context_length
+        .saturating_sub(options.max_tokens)
+        .max(256)
+        * CHARS_PER_TOKEN;
+    let prompt = session.assemble_prompt(budget);
+
+    match manager.generate_text(&session.model, &prompt, options.max_tokens, options.temperature).await {
+        Ok(reply) => {
+            let reply = reply.trim().to_string();
+            session.push(Role::Assistant, &reply);
+            Ok(reply)
+        }
+        Err(e) => {
+            // Drop the unanswered turn so a retry does not duplicate it
+            session.messages.pop();
+            Err(e)
+        }
+    }
+}
+
+fn print_help() {
+    println!("Commands:");
+    println!("  /reset            Clear the conversation");
+    println!("  /save [name]      Save history (optionally under a new name)");
+    println!("  /model <name>     Switch to another running model");
+    println!("  /system <prompt>  Replace the system prompt");
+    println!("  /history          Show the conversation so far");
+    println!("  /exit             Save and quit");
+}
+
+/// Interactive read-eval-print loop
+pub async fn run_repl(
+    manager: &GptManager,
+    chats_dir: &Path,
+    mut session: ChatSession,
+    options: ChatOptions,
+) -> Result<()> {
+    println!("💬 Chatting with {} (session '{}'). Type /help for commands.", session.model, session.name);
+    if !session.messages.is_empty() {
+        println!("   Resumed {} message(s) of history", session.messages.len());
+    }
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+
+    loop {
+        print!("you> ");
+        std::io::stdout().flush()?;
+
+        let Some(line) = lines.next_line().await? else {
+            break;
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(command) = line.strip_prefix('/') {
+            let (name, arg) = command.split_once(' ').map(|(n, a)| (n, a.trim())).unwrap_or((command, ""));
+            match name {
+                "exit" | "quit" => break,
+                "help" => print_help(),
+                "reset" => {
+                    session.reset();
+                    println!("🧹 Conversation cleared");
+                }
+                "save" => {
+                    if !arg.is_empty() {
+                        session.name = arg.to_string();
+                    }
+                    let path = save_session(chats_dir, &session).await?;
+                    println!("💾 Saved to {}", path.display());
+                }
+                "model" if !arg.is_empty() => {
+                    session.model = arg.to_string();
+                    println!("🔁 Switched to {}", arg);
+                }
+                "system" => {
+                    session.system_prompt = (!arg.is_empty()).then(|| arg.to_string());
+                    println!("📝 System prompt {}", if arg.is_empty() { "cleared" } else { "updated" });
+                }
+                "history" => {
+                    for message in &session.messages {
+                        println!("{:?}: {}", message.role, message.content);
+                    }
+                }
+                _ => println!("Unknown command: /{}. Type /help for commands.", name),
+            }
+            continue;
+        }
+
+        match send(manager, &mut session, line, &options).await {
+            Ok(reply) => println!("{}> {}", session.model, reply),
+            Err(e) => println!("❌ {}", e),
+        }
+    }
+
+    let path = save_session(chats_dir, &session).await?;
+    println!("💾 History saved to {}", path.display());
+    Ok(())
+}
+
+/// Entry point for `rcm gpt chat`
+pub async fn chat(
+    manager: &GptManager,
+    chats_dir: &Path,
+    model: &str,
+    message: Option<&str>,
+    interactive: bool,
+    system: Option<String>,
+    session_name: Option<&str>,
+    options: ChatOptions,
+) -> Result<()> {
+    let name = session_name.unwrap_or(model);
+    let mut session = match load_session(chats_dir, name).await? {
+        Some(mut existing) => {
+            existing.model = model.to_string();
+            if system.is_some() {
+                existing.system_prompt = system;
+            }
+            existing
+        }
+        None => ChatSession::new(name, model, system),
+    };
+
+    if let Some(message) = message {
+        let reply = send(manager, &mut session, message, &options).await?;
+        println!("{}", reply);
+        if !interactive {
+            save_session(chats_dir, &session).await?;
+            return Ok(());
+        }
+    } else if !interactive {
+        return Err(anyhow!("Provide a message or use --interactive"));
+    }
+
+    run_repl(manager, chats_dir, session, options).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assemble_prompt_includes_system_and_turns() {
+        let mut session = ChatSession::new("t", "llama2", Some("Be brief.".to_string()));
+        session.push(Role::User, "Hi");
+        session.push(Role::Assistant, "Hello!");
+        session.push(Role::User, "What is Rust?");
+
+        let prompt = session.assemble_prompt(10_000);
+        assert!(prompt.starts_with("System: Be brief."));
+        assert!(prompt.contains("User: Hi\nAssistant: Hello!\nUser: What is Rust?\n"));
+        assert!(prompt.ends_with("Assistant:"));
+    }
+
+    #[test]
+    fn test_assemble_prompt_drops_oldest_turns() {
+        let mut session = ChatSession::new("t", "llama2", None);
+        session.push(Role::User, &"a".repeat(100));
+        session.push(Role::Assistant, &"b".repeat(100));
+        session.push(Role::User, "latest");
+
+        let prompt = session.assemble_prompt(120);
+        assert!(!prompt.contains("aaaa"));
+        assert!(prompt.contains("User: latest"));
+    }
+}
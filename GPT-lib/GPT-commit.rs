@@ -0,0 +1,350 @@
+//! Commit messages and changelogs for `rcm gpt commit-msg` and `rcm gpt changelog`
+//!
+//! Staged diffs and commit history are only sent to models served on this
+//! machine; remote endpoints are refused so source never leaves it.
+
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use crate::GptManager;
+
+/// Conventional commit types, in changelog order
+pub const COMMIT_TYPES: [&str; 11] = [
+    "feat", "fix", "perf", "refactor", "docs", "test", "build", "ci", "style", "chore", "revert",
+];
+
+/// Diff characters kept per file, so one large file cannot crowd out the rest
+const FILE_DIFF_CHARS: usize = 3000;
+
+/// Tokens the model may spend on a commit message or changelog summary
+const MESSAGE_MAX_TOKENS: usize = 300;
+const SUMMARY_MAX_TOKENS: usize = 400;
+
+/// A commit in a changelog range
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitEntry {
+    pub hash: String,
+    pub subject: String,
+}
+
+/// Parsed conventional-commit header
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConventionalHeader {
+    pub kind: String,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    pub description: String,
+}
+
+fn git(root: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        return Err(anyhow!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Whether an endpoint is served from this machine
+pub fn is_local_endpoint(endpoint: &str) -> bool {
+    let rest = endpoint.split_once("://").map(|(_, rest)| rest).unwrap_or(endpoint);
+    let authority = rest.split('/').next().unwrap_or_default();
+    let host = if let Some(v6) = authority.strip_prefix('[') {
+        v6.split(']').next().unwrap_or_default()
+    } else {
+        authority.rsplit_once(':').map(|(host, _)| host).unwrap_or(authority)
+    };
+    matches!(host, "localhost" | "::1" | "0.0.0.0") || host.starts_with("127.")
+}
+
+/// Parse `type(scope)!: description`
+pub fn parse_header(line: &str) -> Option<ConventionalHeader> {
+    let (head, description) = line.split_once(": ")?;
+    let (head, breaking) = match head.strip_suffix('!') {
+        Some(head) => (head, true),
+        None => (head, false),
+    };
+    let (kind, scope) = match head.split_once('(') {
+        Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?.to_string())),
+        None => (head, None),
+    };
+    if !COMMIT_TYPES.contains(&kind) || description.trim().is_empty() {
+        return None;
+    }
+    Some(ConventionalHeader {
+        kind: kind.to_string(),
+        scope,
+        breaking,
+        description: description.trim().to_string(),
+    })
+}
+
+/// Staged diff with each file's hunk truncated to [`FILE_DIFF_CHARS`]
+pub fn truncate_diff(diff: &str, max_chars: usize) -> String {
+    let mut out = String::new();
+    for file in diff.split("\ndiff --git ").filter(|f| !f.is_empty()) {
+        let file = file.strip_prefix("diff --git ").unwrap_or(file);
+        let mut section = format!("diff --git {}", file.trim_end());
+        if section.len() > FILE_DIFF_CHARS {
+            let cut = (0..=FILE_DIFF_CHARS).rev().find(|i| section.is_char_boundary(*i)).unwrap_or(0);
+            section.truncate(cut);
+            section.push_str("\n[... diff truncated ...]");
+        }
+        if out.len() + section.len() > max_chars {
+            out.push_str("\n[... remaining files omitted ...]\n");
+            break;
+        }
+        out.push_str(&section);
+        out.push('\n');
+    }
+    out
+}
+
+pub fn build_commit_prompt(stat: &str, diff: &str) -> String {
+    format!(
+        "Write a git commit message for the staged changes below using the Conventional Commits format.\n\
+         First line: <type>(<optional scope>): <summary>, at most 72 characters, imperative mood, no trailing period.\n\
+         Allowed types: {}. Add ! after the type for breaking changes.\n\
+         Then a blank line and a short body explaining what changed and why, wrapped at 72 characters.\n\
+         Reply with the commit message only.\n\n\
+         Files changed:\n{}\n\
+         Diff:\n{}\n\
+         Commit message:\n",
+        COMMIT_TYPES.join(", "),
+        stat.trim_end(),
+        diff,
+    )
+}
+
+/// Strip code fences and chatter around the message; fall back to `chore:` when the header is not conventional
+pub fn normalize_message(reply: &str) -> String {
+    let lines: Vec<&str> = reply
+        .lines()
+        .filter(|l| !l.trim_start().starts_with("```"))
+        .skip_while(|l| l.trim().is_empty() || (parse_header(l.trim()).is_none() && l.trim_end().ends_with(':')))
+        .collect();
+    let message = lines.join("\n").trim().trim_matches('"').trim().to_string();
+
+    let header = message.lines().next().unwrap_or_default().trim().to_string();
+    let body = message.lines().skip(1).collect::<Vec<_>>().join("\n").trim().to_string();
+    let header = match parse_header(&header) {
+        Some(_) => header,
+        None => format!("chore: {}", header.trim_end_matches('.')),
+    };
+    if body.is_empty() {
+        header
+    } else {
+        format!("{}\n\n{}", header, body)
+    }
+}
+
+/// Group commit subjects by conventional type; non-conventional subjects go under "other"
+pub fn group_commits(commits: &[CommitEntry]) -> BTreeMap<usize, (String, Vec<String>)> {
+    let mut groups: BTreeMap<usize, (String, Vec<String>)> = BTreeMap::new();
+    for commit in commits {
+        let short = &commit.hash[..commit.hash.len().min(7)];
+        let (order, kind, line) = match parse_header(&commit.subject) {
+            Some(header) => {
+                let order = COMMIT_TYPES.iter().position(|t| *t == header.kind).unwrap_or(COMMIT_TYPES.len());
+                let scope = header.scope.map(|s| format!("**{}:** ", s)).unwrap_or_default();
+                let breaking = if header.breaking { "⚠️ " } else { "" };
+                (order, header.kind, format!("{}{}{} ({})", breaking, scope, header.description, short))
+            }
+            None => (COMMIT_TYPES.len(), "other".to_string(), format!("{} ({})", commit.subject, short)),
+        };
+        groups.entry(order).or_insert_with(|| (kind, Vec::new())).1.push(line);
+    }
+    groups
+}
+
+fn section_title(kind: &str) -> &'static str {
+    match kind {
+        "feat" => "Features",
+        "fix" => "Bug Fixes",
+        "perf" => "Performance",
+        "refactor" => "Refactoring",
+        "docs" => "Documentation",
+        "test" => "Tests",
+        "build" | "ci" => "Build and CI",
+        "revert" => "Reverts",
+        _ => "Other Changes",
+    }
+}
+
+/// Render a changelog section; `summary` is the model's highlights paragraph
+pub fn render_changelog(version: &str, date: &str, summary: Option<&str>, commits: &[CommitEntry]) -> String {
+    let mut out = format!("## {} ({})\n\n", version, date);
+    if let Some(summary) = summary.map(str::trim).filter(|s| !s.is_empty()) {
+        out.push_str(summary);
+        out.push_str("\n\n");
+    }
+    let mut last_title = "";
+    for (kind, lines) in group_commits(commits).values() {
+        let title = section_title(kind);
+        if title != last_title {
+            out.push_str(&format!("### {}\n\n", title));
+            last_title = title;
+        }
+        for line in lines {
+            out.push_str(&format!("- {}\n", line));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Refuse models whose endpoint is not on this machine
+fn local_model<'a>(manager: &'a GptManager, model: &str) -> Result<&'a str> {
+    let instance = manager.registry.active_models.get(model)
+        .ok_or_else(|| anyhow!("Model '{}' is not running. Start it with 'rcm gpt serve {} --deploy'", model, model))?;
+    if !is_local_endpoint(&instance.endpoint) {
+        return Err(anyhow!(
+            "Model '{}' is served from {}; commit messages and changelogs are only generated with local models",
+            model, instance.endpoint
+        ));
+    }
+    Ok(&instance.endpoint)
+}
+
+/// Propose a commit message for the staged changes, optionally committing with it
+pub async fn commit_msg(manager: &GptManager, root: &Path, model: &str, commit: bool, max_diff_chars: usize) -> Result<()> {
+    local_model(manager, model)?;
+
+    let stat = git(root, &["diff", "--cached", "--stat"])?;
+    if stat.trim().is_empty() {
+        return Err(anyhow!("Nothing staged. Stage changes with 'git add' first"));
+    }
+    let diff = git(root, &["diff", "--cached", "--no-color", "--no-ext-diff", "-U3"])?;
+    let prompt = build_commit_prompt(&stat, &truncate_diff(&diff, max_diff_chars));
+
+    let reply = manager.generate_text(model, &prompt, MESSAGE_MAX_TOKENS, 0.2).await?;
+    let message = normalize_message(&reply);
+    if !commit {
+        println!("{}", message);
+        return Ok(());
+    }
+
+    if manager.dry_run() {
+        println!("[dry-run] git commit -e -F - <<'EOF'\n{}\nEOF", message);
+        return Ok(());
+    }
+    // `-e` opens the editor with the proposal so it is reviewed before the commit is made
+    let mut child = Command::new("git")
+        .args(["commit", "-e", "-F", "-"])
+        .current_dir(root)
+        .stdin(Stdio::piped())
+        .spawn()
+        .context("Failed to run git commit")?;
+    child.stdin.take().ok_or_else(|| anyhow!("Failed to open git stdin"))?
+        .write_all(message.as_bytes())?;
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(anyhow!("git commit exited with {}", status));
+    }
+    Ok(())
+}
+
+/// Commits between `since` (default: latest tag) and HEAD
+pub fn commits_since(root: &Path, since: Option<&str>) -> Result<Vec<CommitEntry>> {
+    let since = match since {
+        Some(since) => Some(since.to_string()),
+        None => git(root, &["describe", "--tags", "--abbrev=0"]).ok().map(|t| t.trim().to_string()),
+    };
+    let range = since.map(|s| format!("{}..HEAD", s)).unwrap_or_else(|| "HEAD".to_string());
+    let log = git(root, &["log", "--no-merges", "--format=%H%x09%s", &range])?;
+    Ok(log
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(hash, subject)| CommitEntry { hash: hash.to_string(), subject: subject.to_string() })
+        .collect())
+}
+
+/// Draft a changelog section for the commits since `since`
+///
+/// Sections come from the commit headers; the model only writes the highlights
+/// paragraph on top, so the entries stay accurate even if the summary is weak.
+/// Release tooling calls this to prefill release notes.
+pub async fn draft_changelog(
+    manager: &GptManager,
+    root: &Path,
+    model: Option<&str>,
+    since: Option<&str>,
+    version: &str,
+) -> Result<String> {
+    let commits = commits_since(root, since)?;
+    if commits.is_empty() {
+        return Err(anyhow!("No commits to summarize"));
+    }
+
+    let summary = match model {
+        Some(model) => {
+            local_model(manager, model)?;
+            let subjects: Vec<&str> = commits.iter().map(|c| c.subject.as_str()).collect();
+            let prompt = format!(
+                "Summarize the following commits for release notes in two or three sentences. \
+                 Mention breaking changes first. Reply with the summary only.\n\n{}\n\nSummary:\n",
+                subjects.join("\n")
+            );
+            Some(manager.generate_text(model, &prompt, SUMMARY_MAX_TOKENS, 0.3).await?)
+        }
+        None => None,
+    };
+
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    Ok(render_changelog(version, &date, summary.as_deref(), &commits))
+}
+
+/// Prepend a section to a CHANGELOG file, below its title if it has one
+pub fn prepend_changelog(existing: &str, section: &str) -> String {
+    match existing.split_once('\n') {
+        Some((title, rest)) if title.starts_with("# ") => {
+            format!("{}\n\n{}\n\n{}", title, section.trim_end(), rest.trim_start_matches('\n').trim_end()) + "\n"
+        }
+        _ if existing.trim().is_empty() => format!("# Changelog\n\n{}", section),
+        _ => format!("{}\n{}", section, existing),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header_and_normalize() {
+        let header = parse_header("feat(api)!: drop v1 endpoints").unwrap();
+        assert_eq!((header.kind.as_str(), header.scope.as_deref(), header.breaking), ("feat", Some("api"), true));
+        assert!(parse_header("Update stuff").is_none());
+        assert!(parse_header("feature: nope").is_none());
+
+        let reply = "Here is the message:\n```\nfix(lock): keep go.sum order\n\nSorting made diffs noisy.\n```";
+        assert_eq!(normalize_message(reply), "fix(lock): keep go.sum order\n\nSorting made diffs noisy.");
+        assert_eq!(normalize_message("Update the readme."), "chore: Update the readme");
+
+        assert!(is_local_endpoint("http://localhost:11434"));
+        assert!(is_local_endpoint("http://127.0.0.1:8000/v1"));
+        assert!(is_local_endpoint("http://[::1]:8080"));
+        assert!(!is_local_endpoint("https://api.example.com/v1"));
+    }
+
+    #[test]
+    fn test_render_changelog() {
+        let commit = |hash: &str, subject: &str| CommitEntry { hash: hash.to_string(), subject: subject.to_string() };
+        let commits = vec![
+            commit("aaaaaaa1", "fix: handle empty go.sum"),
+            commit("bbbbbbb2", "feat(gem): add rcm gem audit"),
+            commit("ccccccc3", "Merge tidy-ups"),
+        ];
+        let changelog = render_changelog("1.4.0", "2026-10-16", Some("Adds Ruby support."), &commits);
+        assert_eq!(changelog, "## 1.4.0 (2026-10-16)\n\nAdds Ruby support.\n\n\
+            ### Features\n\n- **gem:** add rcm gem audit (bbbbbbb)\n\n\
+            ### Bug Fixes\n\n- handle empty go.sum (aaaaaaa)\n\n\
+            ### Other Changes\n\n- Merge tidy-ups (ccccccc)\n\n");
+
+        assert_eq!(prepend_changelog("# Changelog\n\n## 1.3.0\n", "## 1.4.0\n"), "# Changelog\n\n## 1.4.0\n\n## 1.3.0\n");
+    }
+}
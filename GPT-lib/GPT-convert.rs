@@ -0,0 +1,266 @@
+//! Model conversion and quantization (`rcm gpt convert`)
+//!
+//! Drives llama.cpp's `convert_hf_to_gguf.py` to turn a Hugging Face
+//! safetensors checkpoint into GGUF, then `llama-quantize` for the requested
+//! quantization. The result is registered as its own model with lineage back
+//! to the source, after checking its GGUF header and that llama.cpp can load it.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command as AsyncCommand;
+use crate::{gguf, GptManager, ModelConfig, ModelFormat, ServingBackend};
+
+/// Environment variable pointing at a llama.cpp checkout or install prefix
+pub const LLAMA_CPP_DIR_ENV: &str = "LLAMA_CPP_DIR";
+
+/// Conversion script name in llama.cpp checkouts
+const CONVERT_SCRIPT: &str = "convert_hf_to_gguf.py";
+
+/// Output types `convert_hf_to_gguf.py` writes directly, without `llama-quantize`
+const DIRECT_TYPES: &[&str] = &["F32", "F16", "BF16", "Q8_0"];
+
+/// Types `llama-quantize` accepts that RCM offers
+const QUANT_TYPES: &[&str] = &[
+    "Q2_K", "Q3_K_S", "Q3_K_M", "Q3_K_L", "Q4_0", "Q4_1", "Q4_K_S", "Q4_K_M",
+    "Q5_0", "Q5_1", "Q5_K_S", "Q5_K_M", "Q6_K", "Q8_0", "IQ4_NL", "IQ4_XS",
+    "F16", "BF16", "F32",
+];
+
+/// Where a derived model came from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lineage {
+    /// Registry name of the source model
+    pub source: String,
+    /// Source revision at conversion time (Hub commit)
+    #[serde(default)]
+    pub source_revision: Option<String>,
+    /// Quantization of the derived file (llama.cpp name, e.g. `Q4_K_M`)
+    pub quantization: String,
+    pub converted_at: String,
+}
+
+/// Options for `rcm gpt convert`
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    pub to: String,
+    pub quant: String,
+    /// Registry name for the result (defaults to `<source>-<quant>`)
+    pub name: Option<String>,
+    /// Keep the unquantized F16 GGUF next to the result
+    pub keep_intermediate: bool,
+    /// Skip the llama.cpp load check
+    pub no_validate: bool,
+    /// Replace an existing model with the same name
+    pub force: bool,
+}
+
+/// Normalize a quantization name (`q4_k_m` -> `Q4_K_M`) and reject unknown ones
+pub fn parse_quant(quant: &str) -> Result<String> {
+    let upper = quant.trim().to_uppercase().replace('-', "_");
+    if QUANT_TYPES.contains(&upper.as_str()) {
+        Ok(upper)
+    } else {
+        Err(anyhow!("Unknown quantization '{}'. Choose one of: {}", quant, QUANT_TYPES.join(", ")))
+    }
+}
+
+/// Registry name for a converted model
+pub fn derived_name(source: &str, quant: &str) -> String {
+    format!("{}-{}", source, quant.to_lowercase())
+}
+
+/// File name of the converted GGUF inside the derived model's directory
+fn output_file_name(source: &str, quant: &str) -> String {
+    let base = source.rsplit('/').next().unwrap_or(source).replace(':', "-");
+    format!("{}.{}.gguf", base, quant)
+}
+
+fn on_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).map(|dir| dir.join(name)).find(|p| p.is_file())
+}
+
+/// `convert_hf_to_gguf.py`: under `$LLAMA_CPP_DIR`, then on PATH
+fn find_convert_script() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(LLAMA_CPP_DIR_ENV).map(PathBuf::from) {
+        let script = dir.join(CONVERT_SCRIPT);
+        if script.is_file() {
+            return Some(script);
+        }
+    }
+    on_path(CONVERT_SCRIPT)
+}
+
+/// `llama-quantize` (or the pre-2024 `quantize`): under `$LLAMA_CPP_DIR`, then on PATH
+fn find_quantize() -> Option<PathBuf> {
+    let dir = std::env::var_os(LLAMA_CPP_DIR_ENV).map(PathBuf::from);
+    for name in ["llama-quantize", "quantize"] {
+        if let Some(dir) = &dir {
+            for candidate in [dir.join(name), dir.join("build").join("bin").join(name)] {
+                if candidate.is_file() {
+                    return Some(candidate);
+                }
+            }
+        }
+        if let Some(path) = on_path(name) {
+            return Some(path);
+        }
+    }
+    None
+}
+
+async fn run(cmd: &mut AsyncCommand, what: &str) -> Result<()> {
+    let output = cmd.output().await.with_context(|| format!("Failed to start {}", what))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let tail: Vec<&str> = stderr.lines().rev().take(10).collect();
+        return Err(anyhow!("{} failed:\n{}", what, tail.into_iter().rev().collect::<Vec<_>>().join("\n")));
+    }
+    Ok(())
+}
+
+/// Check the GGUF header matches the request and that llama.cpp can load the file
+async fn validate(path: &Path, quant: &str, load: bool) -> Result<gguf::GgufMetadata> {
+    let metadata = gguf::read_metadata(path)
+        .with_context(|| format!("Converted file {} is not valid GGUF", path.display()))?;
+    if metadata.tensor_count == 0 {
+        return Err(anyhow!("Converted file {} has no tensors", path.display()));
+    }
+    if let Some(found) = metadata.quantization.as_deref().filter(|q| *q != "unknown") {
+        if found != quant {
+            return Err(anyhow!("Converted file is {} but {} was requested", found, quant));
+        }
+    }
+
+    if load {
+        // One generated token is enough to prove the weights load and run
+        let mut cmd = AsyncCommand::new("llama-bench");
+        cmd.arg("-m").arg(path).args(["-p", "0", "-n", "1", "-r", "1", "-o", "json"]);
+        run(&mut cmd, "llama-bench load check").await
+            .context("The converted model did not load in llama.cpp (skip with --no-validate)")?;
+    }
+    Ok(metadata)
+}
+
+impl GptManager {
+    /// Convert a registered safetensors model to a quantized GGUF and register the result
+    pub async fn convert_model(&mut self, source: &str, options: &ConvertOptions) -> Result<()> {
+        if !options.to.eq_ignore_ascii_case("gguf") {
+            return Err(anyhow!("Unsupported target format '{}'. Only gguf is supported", options.to));
+        }
+        let quant = parse_quant(&options.quant)?;
+        let source_config = self.registry.models.get(source)
+            .ok_or_else(|| anyhow!("Model '{}' is not installed. Install it with 'rcm gpt install {} --source huggingface'", source, source))?
+            .clone();
+        if !matches!(source_config.format, ModelFormat::Safetensors | ModelFormat::PyTorch)
+            || !source_config.model_path.join("config.json").is_file()
+        {
+            return Err(anyhow!(
+                "'{}' is {:?}, not a Hugging Face checkpoint; conversion needs a directory with config.json and weights",
+                source, source_config.format
+            ));
+        }
+
+        let name = options.name.clone().unwrap_or_else(|| derived_name(source, &quant));
+        if self.registry.models.contains_key(&name) && !options.force {
+            return Err(anyhow!("Model '{}' already exists. Use --force to convert again", name));
+        }
+        let out_dir = self.models_dir.join(&name);
+        let output = out_dir.join(output_file_name(source, &quant));
+        let direct = DIRECT_TYPES.contains(&quant.as_str());
+        let intermediate = if direct { output.clone() } else { out_dir.join(output_file_name(source, "F16")) };
+
+        let script = find_convert_script().ok_or_else(|| anyhow!(
+            "{} not found. Clone llama.cpp and set {} to the checkout, or put the script on PATH",
+            CONVERT_SCRIPT, LLAMA_CPP_DIR_ENV
+        ))?;
+        let quantize = if direct {
+            None
+        } else {
+            Some(find_quantize().ok_or_else(|| anyhow!(
+                "llama-quantize not found. Build llama.cpp and set {} or add it to PATH", LLAMA_CPP_DIR_ENV
+            ))?)
+        };
+
+        let mut convert = AsyncCommand::new("python3");
+        convert.arg(&script).arg(&source_config.model_path)
+            .arg("--outfile").arg(&intermediate)
+            .arg("--outtype").arg(if direct { quant.to_lowercase() } else { "f16".to_string() });
+        let mut quantize_cmd = quantize.map(|bin| {
+            let mut cmd = AsyncCommand::new(bin);
+            cmd.arg(&intermediate).arg(&output).arg(&quant);
+            cmd
+        });
+
+        if self.skip_in_dry_run(&convert) {
+            if let Some(cmd) = &quantize_cmd {
+                self.skip_in_dry_run(cmd);
+            }
+            println!("[dry-run] register {} derived from {}", name, source);
+            return Ok(());
+        }
+
+        tokio::fs::create_dir_all(&out_dir).await
+            .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+
+        println!("🔄 Converting {} to GGUF ({})...", source, if direct { quant.as_str() } else { "F16" });
+        run(&mut convert, CONVERT_SCRIPT).await?;
+        if let Some(cmd) = quantize_cmd.as_mut() {
+            println!("🗜️  Quantizing to {}...", quant);
+            run(cmd, "llama-quantize").await?;
+            if !options.keep_intermediate {
+                tokio::fs::remove_file(&intermediate).await.ok();
+            }
+        }
+
+        println!("🔎 Validating {}...", output.display());
+        let metadata = validate(&output, &quant, !options.no_validate).await?;
+
+        let mut config = ModelConfig {
+            name: name.clone(),
+            revision: None,
+            format: ModelFormat::GGUF,
+            backend: ServingBackend::LlamaCpp,
+            model_path: output.clone(),
+            config_path: None,
+            tokenizer_path: None,
+            lineage: Some(Lineage {
+                source: source.to_string(),
+                source_revision: source_config.revision.clone(),
+                quantization: quant.clone(),
+                converted_at: chrono::Utc::now().to_rfc3339(),
+            }),
+            ..source_config
+        };
+        if let Some(context_length) = metadata.context_length {
+            config.parameters.context_length = context_length;
+        }
+        self.registry.models.insert(name.clone(), config);
+        self.save_registry().await?;
+
+        let source_size = crate::lifecycle::disk_usage(&self.registry.models[source].model_path);
+        println!("✅ Registered '{}' ({}, {:.1} GiB from {:.1} GiB)", name, quant,
+            metadata.file_size as f64 / 1_073_741_824.0, source_size as f64 / 1_073_741_824.0);
+        println!("   Serve it with: rcm gpt serve {} --deploy", name);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quant() {
+        assert_eq!(parse_quant("q4_k_m").unwrap(), "Q4_K_M");
+        assert_eq!(parse_quant("Q8-0").unwrap(), "Q8_0");
+        assert!(parse_quant("q9_k").is_err());
+    }
+
+    #[test]
+    fn test_derived_names() {
+        assert_eq!(derived_name("mistralai/Mistral-7B-v0.1", "Q4_K_M"), "mistralai/Mistral-7B-v0.1-q4_k_m");
+        assert_eq!(output_file_name("mistralai/Mistral-7B-v0.1", "Q4_K_M"), "Mistral-7B-v0.1.Q4_K_M.gguf");
+    }
+}
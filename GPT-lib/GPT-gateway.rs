@@ -0,0 +1,729 @@
+//! Model gateway (`rcm gpt gateway serve`)
+//!
+//! One OpenAI-compatible endpoint in front of every model this workspace is
+//! serving. Clients authenticate with their own bearer token and pick a model
+//! with the request's `model` field; the gateway routes to that model's
+//! replicas, enforces the client's budget and records usage in the ledger
+//! (see [`crate::usage`]).
+//!
+//! - `GET /v1/models` lists the running models the client may use
+//! - `POST /v1/chat/completions`, `/v1/completions` and `/v1/embeddings` are proxied
+//!
+//! Token counts come from the backend's `usage` field; streamed requests ask for
+//! it with `stream_options.include_usage`. Backends that don't report usage are
+//! charged an estimate of four bytes per token.
+//!
+//! Content filters configured for a model (see [`crate::safety`]) run on the
+//! prompt before it is forwarded and on the completion before it is returned.
+//!
+//! Each request is traced as its own span (see [`crate::otel`]) with the client,
+//! model, backend, token counts and status.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use clap::Subcommand;
+use console::style;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tabled::{Table, Tabled};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use tracing::{Instrument, Span};
+use crate::otel;
+use crate::safety::{self, FilterRule, SafetyEvent, Screening, Stage};
+use crate::share::{self, RequestHead};
+use crate::usage::{self, Budget, GatewayClient, GatewayConfig, Ledger, UsageRecord};
+use crate::{secrets, GptManager, ModelInstance, ModelRegistry, ServingBackend};
+
+pub const DEFAULT_PORT: u16 = 4000;
+
+/// Largest request body accepted (embedding batches can be big)
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+const PROXIED: &[&str] = &["/v1/chat/completions", "/v1/completions", "/v1/embeddings"];
+
+#[derive(Subcommand)]
+pub enum GatewayCommands {
+    /// Serve every running model behind one authenticated endpoint
+    Serve {
+        /// Address to bind
+        #[arg(long, default_value = "0.0.0.0")]
+        host: String,
+        #[arg(long, default_value_t = DEFAULT_PORT)]
+        port: u16,
+    },
+    /// Add a client and print its token
+    Add {
+        name: String,
+        /// Tokens per rolling 24 hours
+        #[arg(long)]
+        tokens_per_day: Option<u64>,
+        /// Requests per rolling hour
+        #[arg(long)]
+        requests_per_hour: Option<u32>,
+        /// Only allow these models
+        #[arg(long, value_delimiter = ',')]
+        models: Vec<String>,
+        /// Issue a new token for an existing client
+        #[arg(long)]
+        rotate: bool,
+    },
+    /// Change a client's budget, or the default budget without a name (0 means unlimited)
+    Budget {
+        name: Option<String>,
+        #[arg(long)]
+        tokens_per_day: Option<u64>,
+        #[arg(long)]
+        requests_per_hour: Option<u32>,
+    },
+    /// Remove a client, revoking its token
+    Remove {
+        name: String,
+    },
+    /// List clients and their budgets
+    Clients,
+    /// Show content filter rules, or try them on a prompt with --test
+    Filters {
+        /// Only rules that apply to this model
+        model: Option<String>,
+        /// Text to screen as a prompt
+        #[arg(long, requires = "model")]
+        test: Option<String>,
+    },
+}
+
+/// Base URL of a backend's OpenAI-compatible API (vLLM and remote endpoints already end in `/v1`)
+pub fn openai_base(endpoint: &str, backend: &ServingBackend) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    match backend {
+        ServingBackend::Vllm | ServingBackend::Remote(_) => endpoint.to_string(),
+        _ => format!("{}/v1", endpoint),
+    }
+}
+
+/// Prompt and completion tokens from an OpenAI-style `usage` object
+pub fn usage_of(value: &Value) -> Option<(u64, u64)> {
+    let usage = value.get("usage").filter(|u| u.is_object())?;
+    let prompt = usage.get("prompt_tokens").and_then(Value::as_u64).unwrap_or(0);
+    let completion = usage.get("completion_tokens").and_then(Value::as_u64)
+        .or_else(|| Some(usage.get("total_tokens")?.as_u64()?.saturating_sub(prompt)))
+        .unwrap_or(0);
+    Some((prompt, completion))
+}
+
+/// Rough token count for backends that don't report usage
+pub fn estimate_tokens(bytes: usize) -> u64 {
+    (bytes as u64).div_ceil(4)
+}
+
+/// Keep a completion request within the client's remaining daily tokens
+pub fn cap_max_tokens(body: &mut Value, remaining: u64) {
+    let requested = body.get("max_tokens").and_then(Value::as_u64);
+    if requested.map_or(true, |requested| requested > remaining) {
+        body["max_tokens"] = json!(remaining.max(1));
+    }
+}
+
+struct Gateway {
+    manager: GptManager,
+    ledger: Mutex<Ledger>,
+    http: reqwest::Client,
+}
+
+impl GptManager {
+    /// Serve the gateway until interrupted
+    pub async fn serve_gateway(self, host: &str, port: u16) -> Result<()> {
+        let config = GatewayConfig::load(&self.configs_dir).await?;
+        if config.clients.is_empty() {
+            return Err(anyhow!("No gateway clients yet; add one with `rcm gpt gateway add <name>`"));
+        }
+        safety::validate(&config.safety)?;
+        let ledger = Ledger::new(usage::read_ledger(&self.configs_dir).await?, Utc::now());
+        let listener = TcpListener::bind((host, port)).await
+            .with_context(|| format!("Failed to bind {}:{}", host, port))?;
+
+        println!("{}", style(format!("🚪 Model gateway listening on http://{}:{}/v1", host, port)).green().bold());
+        for model in self.registry.active_models.keys() {
+            println!("   {}", style(model).cyan());
+        }
+        println!("   {} client(s); usage is recorded in {}", config.clients.len(), self.configs_dir.join(usage::LEDGER_FILE).display());
+        let rules: usize = config.safety.values().map(Vec::len).sum();
+        if rules > 0 {
+            println!("   {} content filter rule(s); matches are recorded in {}", rules, self.configs_dir.join(safety::EVENTS_FILE).display());
+        }
+
+        let gateway = Arc::new(Gateway { manager: self, ledger: Mutex::new(ledger), http: reqwest::Client::new() });
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let gateway = gateway.clone();
+            tokio::spawn(async move {
+                if let Err(e) = gateway.handle(stream).await {
+                    crate::telemetry::record_error(&Span::current(), &e);
+                    tracing::debug!(%peer, "Gateway request failed: {}", e);
+                }
+            }.instrument(otel::gateway_span(&peer)));
+        }
+    }
+}
+
+async fn respond_error(stream: &mut TcpStream, status: &str, kind: &str, message: &str, retry_after: Option<u64>) -> Result<()> {
+    record_status(status.split(' ').next().and_then(|code| code.parse().ok()).unwrap_or(500));
+    let body = serde_json::to_vec(&json!({ "error": { "message": message, "type": kind } }))?;
+    let retry = retry_after.map(|secs| format!("Retry-After: {}\r\n", secs)).unwrap_or_default();
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+        status, body.len(), retry
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    Ok(())
+}
+
+/// Record the response status on the request's span
+fn record_status(code: u16) {
+    let span = Span::current();
+    span.record("http.response.status_code", code);
+    if code >= 500 {
+        span.record("otel.status_code", "error");
+    }
+}
+
+impl Gateway {
+    async fn handle(&self, mut stream: TcpStream) -> Result<()> {
+        let Some(RequestHead { method, path, headers, mut body }) = share::read_head(&mut stream).await? else {
+            return Ok(());
+        };
+        let path = path.split('?').next().unwrap_or_default().to_string();
+        let span = Span::current();
+        span.record("http.request.method", method.as_str());
+        span.record("url.path", path.as_str());
+        if path == "/health" {
+            return share::respond(&mut stream, "200 OK", "text/plain", b"ok\n", false).await;
+        }
+
+        // Reloaded per request so `gateway add/budget/remove` apply without a restart
+        let config = GatewayConfig::load(&self.manager.configs_dir).await?;
+        let token = headers.get("authorization").and_then(|v| v.strip_prefix("Bearer ")).unwrap_or_default();
+        let Some((client, entry)) = config.authenticate(token) else {
+            return respond_error(&mut stream, "401 Unauthorized", "invalid_api_key", "Unknown or missing gateway token", None).await;
+        };
+        span.record("rcm.client", client);
+
+        match (method.as_str(), path.as_str()) {
+            ("GET", "/v1/models") => {
+                let data: Vec<Value> = self.running().await?.active_models.into_keys()
+                    .filter(|model| allowed(entry, model))
+                    .map(|model| json!({ "id": model, "object": "model", "owned_by": "rcm" }))
+                    .collect();
+                let body = serde_json::to_vec(&json!({ "object": "list", "data": data }))?;
+                share::respond(&mut stream, "200 OK", "application/json", &body, false).await
+            }
+            ("POST", path) if PROXIED.contains(&path) => {
+                if headers.get("transfer-encoding").is_some() {
+                    return respond_error(&mut stream, "411 Length Required", "invalid_request_error", "Send a Content-Length", None).await;
+                }
+                let length: usize = headers.get("content-length").and_then(|l| l.parse().ok()).unwrap_or(0);
+                if length > MAX_BODY_BYTES {
+                    return respond_error(&mut stream, "413 Payload Too Large", "invalid_request_error", "Request body too large", None).await;
+                }
+                while body.len() < length {
+                    let mut chunk = vec![0u8; (length - body.len()).min(64 * 1024)];
+                    let n = stream.read(&mut chunk).await?;
+                    if n == 0 {
+                        return Ok(());
+                    }
+                    body.extend_from_slice(&chunk[..n]);
+                }
+                self.proxy(&mut stream, &config, client, entry, path, &body).await
+            }
+            _ => respond_error(&mut stream, "404 Not Found", "invalid_request_error", "Unknown endpoint", None).await,
+        }
+    }
+
+    /// The registry as `rcm gpt serve` last left it
+    async fn running(&self) -> Result<ModelRegistry> {
+        let content = tokio::fs::read_to_string(&self.manager.registry.registry_path).await?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    async fn record(&self, ledger: &mut Ledger, record: UsageRecord) {
+        if let Err(e) = usage::append(&self.manager.configs_dir, &record).await {
+            tracing::warn!("Failed to record gateway usage: {}", e);
+        }
+        ledger.push(record);
+    }
+
+    async fn proxy(
+        &self,
+        stream: &mut TcpStream,
+        config: &GatewayConfig,
+        client: &str,
+        entry: &GatewayClient,
+        path: &str,
+        raw: &[u8],
+    ) -> Result<()> {
+        let Ok(mut body) = serde_json::from_slice::<Value>(raw) else {
+            return respond_error(stream, "400 Bad Request", "invalid_request_error", "Body is not JSON", None).await;
+        };
+        let Some(model) = body.get("model").and_then(Value::as_str).map(str::to_string) else {
+            return respond_error(stream, "400 Bad Request", "invalid_request_error", "Missing `model`", None).await;
+        };
+        let span = Span::current();
+        span.record("otel.name", format!("{} {}", otel::operation(path), model).as_str());
+        span.record("gen_ai.operation.name", otel::operation(path));
+        span.record("gen_ai.request.model", model.as_str());
+        if !allowed(entry, &model) {
+            let message = format!("Client '{}' may not use '{}'", client, model);
+            return respond_error(stream, "403 Forbidden", "permission_error", &message, None).await;
+        }
+        let Some(instance) = self.running().await?.active_models.remove(&model) else {
+            let message = format!("Model '{}' is not running; start it with `rcm gpt serve {} --deploy`", model, model);
+            return respond_error(stream, "404 Not Found", "model_not_found", &message, None).await;
+        };
+        span.record("gen_ai.system", otel::system(&instance.config.backend));
+
+        let budget = config.budget(client);
+        let admission = {
+            let mut ledger = self.ledger.lock().await;
+            let now = Utc::now();
+            let requested = match path {
+                "/v1/embeddings" => Some(0),
+                _ => body.get("max_tokens").and_then(Value::as_u64),
+            };
+            match ledger.admit(client, budget, now, estimate_tokens(raw.len()), requested) {
+                Ok(admission) => admission,
+                Err(over) => {
+                    let record = UsageRecord {
+                        timestamp: now, client: client.to_string(), model: model.clone(),
+                        prompt_tokens: 0, completion_tokens: 0, status: usage::REFUSED,
+                    };
+                    self.record(&mut ledger, record).await;
+                    let message = format!("Budget exceeded for '{}': {}", client, over.reason);
+                    return respond_error(stream, "429 Too Many Requests", "rate_limit_exceeded", &message, Some(over.retry_after_secs)).await;
+                }
+            }
+        };
+        if path != "/v1/embeddings" {
+            if let Some(max_tokens) = admission.max_tokens {
+                cap_max_tokens(&mut body, max_tokens);
+            }
+        }
+
+        // The reservation is settled with the actual usage, or released if nothing was used
+        let (used, outcome) = self.proxy_admitted(stream, config, client, &model, &instance, path, raw, body, &span).await;
+        let mut ledger = self.ledger.lock().await;
+        match used {
+            Some(record) => {
+                if let Err(e) = usage::append(&self.manager.configs_dir, &record).await {
+                    tracing::warn!("Failed to record gateway usage: {}", e);
+                }
+                ledger.settle(admission.id, record);
+            }
+            None => ledger.release(admission.id),
+        }
+        outcome
+    }
+
+    /// Screen, forward and relay an admitted request
+    ///
+    /// Returns the usage to settle, if any, alongside the outcome of answering the client.
+    #[allow(clippy::too_many_arguments)]
+    async fn proxy_admitted(
+        &self,
+        stream: &mut TcpStream,
+        config: &GatewayConfig,
+        client: &str,
+        model: &str,
+        instance: &ModelInstance,
+        path: &str,
+        raw: &[u8],
+        mut body: Value,
+        span: &Span,
+    ) -> (Option<UsageRecord>, Result<()>) {
+        let rules = safety::rules_for(&config.safety, model);
+        if safety::checks(&rules, Stage::Prompt) {
+            let screening = match safety::screen(&self.manager, &rules, Stage::Prompt, &mut safety::prompt_texts(&mut body)).await {
+                Ok(screening) => screening,
+                Err(e) => {
+                    let message = format!("Safety check failed: {}", e);
+                    return (None, respond_error(stream, "503 Service Unavailable", "api_error", &message, None).await);
+                }
+            };
+            self.log_screening(client, model, Stage::Prompt, &screening).await;
+            if let Some(rule) = screening.blocked {
+                span.record("rcm.content_filter", rule.as_str());
+                let message = format!("Prompt blocked by content filter '{}'", rule);
+                let record = UsageRecord {
+                    timestamp: Utc::now(), client: client.to_string(), model: model.to_string(),
+                    prompt_tokens: 0, completion_tokens: 0, status: 400,
+                };
+                return (Some(record), respond_error(stream, "400 Bad Request", "content_filter", &message, None).await);
+            }
+        }
+
+        otel::record_prompt(span, &mut body);
+
+        // Completion filters need the whole text, so those requests aren't streamed upstream
+        let screen_completion = path != "/v1/embeddings" && safety::checks(&rules, Stage::Completion);
+        let streaming = body.get("stream").and_then(Value::as_bool).unwrap_or(false);
+        let upstream_streaming = streaming && !screen_completion;
+        if upstream_streaming && body.get("stream_options").is_none() {
+            body["stream_options"] = json!({ "include_usage": true });
+        } else if streaming && screen_completion {
+            body["stream"] = json!(false);
+            if let Some(fields) = body.as_object_mut() {
+                fields.remove("stream_options");
+            }
+        }
+
+        let response = match self.forward(instance, path, &body).await {
+            Ok(Some(response)) => response,
+            Ok(None) => {
+                let message = format!("No replica of '{}' is reachable", model);
+                return (None, respond_error(stream, "502 Bad Gateway", "api_error", &message, None).await);
+            }
+            Err(e) => return (None, Err(e)),
+        };
+
+        let status = response.status();
+        let content_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok()).unwrap_or("application/json").to_string();
+        let (copied, counted) = if screen_completion {
+            self.relay_screened(stream, response, status, &content_type, &rules, client, model, streaming).await
+        } else {
+            relay(stream, response, status, &content_type, upstream_streaming).await
+        };
+
+        let (prompt_tokens, completion_tokens) = counted.usage.unwrap_or_else(|| {
+            // Streams: one event per token is close enough; bodies: bytes per token
+            let completion = if upstream_streaming { counted.events } else { estimate_tokens(counted.bytes) };
+            (estimate_tokens(raw.len()), completion)
+        });
+        let record = UsageRecord {
+            timestamp: Utc::now(), client: client.to_string(), model: model.to_string(),
+            prompt_tokens, completion_tokens, status: status.as_u16(),
+        };
+        otel::record_usage(span, Some(prompt_tokens), Some(completion_tokens));
+        record_status(record.status);
+        tracing::info!(client, model = %record.model, status = record.status, tokens = record.tokens(), "gateway request");
+        (Some(record), copied)
+    }
+
+    /// Record and log what the content filters matched
+    async fn log_screening(&self, client: &str, model: &str, stage: Stage, screening: &Screening) {
+        let events: Vec<SafetyEvent> = screening.hits.iter().map(|(rule, action, matches)| {
+            tracing::warn!(client, model, rule = %rule, ?stage, ?action, matches, "content filter matched");
+            SafetyEvent {
+                timestamp: Utc::now(), client: client.to_string(), model: model.to_string(),
+                rule: rule.clone(), stage, action: *action, matches: *matches,
+            }
+        }).collect();
+        if let Err(e) = safety::record_events(&self.manager.configs_dir, &events).await {
+            tracing::warn!("Failed to record content filter events: {}", e);
+        }
+    }
+
+    /// Buffer the response, run completion filters over it, then send it on
+    ///
+    /// Streamed requests get the filtered result as a single event. A failed
+    /// check withholds the completion rather than letting it through.
+    #[allow(clippy::too_many_arguments)]
+    async fn relay_screened(
+        &self,
+        stream: &mut TcpStream,
+        response: reqwest::Response,
+        status: reqwest::StatusCode,
+        content_type: &str,
+        rules: &[&FilterRule],
+        client: &str,
+        model: &str,
+        as_events: bool,
+    ) -> (Result<()>, Counted) {
+        let mut counted = Counted::default();
+        let bytes = match response.bytes().await {
+            Ok(bytes) => bytes,
+            Err(e) => return (Err(e.into()), counted),
+        };
+        counted.bytes = bytes.len();
+        let parsed = serde_json::from_slice::<Value>(&bytes).ok().filter(|_| status.is_success());
+        let Some(mut value) = parsed else {
+            let status = status.to_string();
+            return (share::respond(stream, &status, content_type, &bytes, false).await, counted);
+        };
+        counted.usage = usage_of(&value);
+
+        match safety::screen(&self.manager, rules, Stage::Completion, &mut safety::completion_texts(&mut value)).await {
+            Ok(screening) => {
+                self.log_screening(client, model, Stage::Completion, &screening).await;
+                if let Some(rule) = &screening.blocked {
+                    Span::current().record("rcm.content_filter", rule.as_str());
+                    safety::withhold(&mut value);
+                }
+            }
+            Err(e) => {
+                tracing::warn!(client, model, "Completion safety check failed, withholding: {}", e);
+                safety::withhold(&mut value);
+            }
+        }
+
+        otel::record_completion(&Span::current(), &mut value);
+        let sent = if as_events {
+            share::respond(stream, &status.to_string(), "text/event-stream", safety::as_event_stream(&value).as_bytes(), false).await
+        } else {
+            match serde_json::to_vec(&value) {
+                Ok(body) => share::respond(stream, &status.to_string(), "application/json", &body, false).await,
+                Err(e) => Err(e.into()),
+            }
+        };
+        (sent, counted)
+    }
+
+    /// Send the request to the model's replicas in routing order
+    async fn forward(&self, instance: &ModelInstance, path: &str, body: &Value) -> Result<Option<reqwest::Response>> {
+        let token = match instance.config.serving_config.auth_token.clone()
+            .or_else(|| std::env::var("RCM_GPT_API_KEY").ok())
+        {
+            Some(token) => Some(secrets::resolve_value(&token).await?),
+            None => None,
+        };
+        for endpoint in self.manager.route(instance).await? {
+            let url = format!("{}{}", openai_base(&endpoint, &instance.config.backend), path.trim_start_matches("/v1"));
+            let mut request = self.http.post(&url).json(body);
+            if let Some(token) = &token {
+                request = request.bearer_auth(token);
+            }
+            match request.send().await {
+                Ok(response) => {
+                    Span::current().record("server.address", endpoint.as_str());
+                    return Ok(Some(response));
+                }
+                Err(e) if e.is_connect() || e.is_timeout() => {
+                    tracing::warn!("Replica {} of '{}' unreachable: {}", endpoint, instance.config.name, e);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn allowed(entry: &GatewayClient, model: &str) -> bool {
+    entry.models.is_empty() || entry.models.iter().any(|m| m == model)
+}
+
+/// What a relayed response said about its token use
+#[derive(Default)]
+struct Counted {
+    usage: Option<(u64, u64)>,
+    bytes: usize,
+    events: u64,
+}
+
+/// Copy the backend's response to the client, reading usage along the way
+///
+/// Usage is returned even when the client hangs up part way, so what the
+/// backend already generated is still charged.
+async fn relay(
+    stream: &mut TcpStream,
+    mut response: reqwest::Response,
+    status: reqwest::StatusCode,
+    content_type: &str,
+    streaming: bool,
+) -> (Result<()>, Counted) {
+    let mut counted = Counted::default();
+    let mut body = Vec::new();
+    let mut pending = Vec::new();
+    let mut streamed = String::new();
+    let capture = crate::telemetry::capture_content();
+    let copied: Result<()> = async {
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+            status, content_type
+        );
+        stream.write_all(head.as_bytes()).await?;
+        while let Some(chunk) = response.chunk().await? {
+            counted.bytes += chunk.len();
+            if streaming {
+                // Server-sent events: one `data: {json}` line per chunk
+                pending.extend_from_slice(&chunk);
+                while let Some(end) = pending.iter().position(|b| *b == b'\n') {
+                    let line: Vec<u8> = pending.drain(..=end).collect();
+                    let line = String::from_utf8_lossy(&line);
+                    let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else { continue };
+                    if let Ok(event) = serde_json::from_str::<Value>(data) {
+                        counted.events += 1;
+                        let choice = &event["choices"][0];
+                        if let Some(text) = choice["delta"]["content"].as_str().or(choice["text"].as_str()).filter(|_| capture) {
+                            streamed.push_str(text);
+                        }
+                        if let Some(usage) = usage_of(&event) {
+                            counted.usage = Some(usage);
+                        }
+                    }
+                }
+            } else {
+                body.extend_from_slice(&chunk);
+            }
+            stream.write_all(&chunk).await?;
+        }
+        Ok(())
+    }.await;
+    let span = Span::current();
+    if streaming {
+        crate::telemetry::record_content(&span, "gen_ai.completion", &streamed);
+    } else if let Ok(mut value) = serde_json::from_slice::<Value>(&body) {
+        counted.usage = usage_of(&value);
+        otel::record_completion(&span, &mut value);
+    }
+    (copied, counted)
+}
+
+#[derive(Tabled)]
+struct FilterRow {
+    #[tabled(rename = "Model")]
+    model: String,
+    #[tabled(rename = "Rule")]
+    rule: String,
+    #[tabled(rename = "Type")]
+    kind: String,
+    #[tabled(rename = "Stage")]
+    stage: String,
+    #[tabled(rename = "Action")]
+    action: String,
+}
+
+#[derive(Tabled)]
+struct ClientRow {
+    #[tabled(rename = "Client")]
+    name: String,
+    #[tabled(rename = "Budget")]
+    budget: String,
+    #[tabled(rename = "Models")]
+    models: String,
+}
+
+/// `rcm gpt gateway add|budget|remove|clients`
+pub async fn manage(manager: &GptManager, cmd: GatewayCommands) -> Result<()> {
+    let configs_dir = &manager.configs_dir;
+    let mut config = GatewayConfig::load(configs_dir).await?;
+    match cmd {
+        GatewayCommands::Serve { .. } => unreachable!("handled by serve_gateway"),
+        GatewayCommands::Add { name, tokens_per_day, requests_per_hour, models, rotate } => {
+            if config.clients.contains_key(&name) && !rotate {
+                return Err(anyhow!("Client '{}' exists; use --rotate for a new token or `rcm gpt gateway budget {}`", name, name));
+            }
+            let token = format!("rcm-{}", uuid::Uuid::new_v4().simple());
+            let client = config.clients.entry(name.clone()).or_insert_with(|| GatewayClient {
+                token_sha256: String::new(),
+                budget: Budget::default(),
+                models: Vec::new(),
+            });
+            client.token_sha256 = usage::hash_token(&token);
+            client.budget.update(tokens_per_day, requests_per_hour);
+            if !models.is_empty() {
+                client.models = models;
+            }
+            let budget = config.budget(&name);
+            config.save(configs_dir, manager.dry_run()).await?;
+            println!("✅ Gateway client '{}' ({})", name, budget.describe());
+            println!("🔑 Token (shown once): {}", style(&token).bold());
+        }
+        GatewayCommands::Budget { name, tokens_per_day, requests_per_hour } => {
+            match &name {
+                Some(name) => config.clients.get_mut(name)
+                    .ok_or_else(|| anyhow!("No gateway client '{}'", name))?
+                    .budget.update(tokens_per_day, requests_per_hour),
+                None => config.default_budget.update(tokens_per_day, requests_per_hour),
+            }
+            let budget = match &name {
+                Some(name) => config.budget(name),
+                None => config.default_budget,
+            };
+            config.save(configs_dir, manager.dry_run()).await?;
+            println!("✅ {} budget: {}", name.as_deref().unwrap_or("Default"), budget.describe());
+        }
+        GatewayCommands::Remove { name } => {
+            if config.clients.remove(&name).is_none() {
+                return Err(anyhow!("No gateway client '{}'", name));
+            }
+            config.save(configs_dir, manager.dry_run()).await?;
+            println!("🗑️ Removed gateway client '{}'", name);
+        }
+        GatewayCommands::Clients => {
+            if config.clients.is_empty() {
+                println!("No gateway clients; add one with `rcm gpt gateway add <name>`");
+                return Ok(());
+            }
+            let rows: Vec<ClientRow> = config.clients.iter().map(|(name, client)| ClientRow {
+                name: name.clone(),
+                budget: config.budget(name).describe(),
+                models: if client.models.is_empty() { "all".to_string() } else { client.models.join(", ") },
+            }).collect();
+            println!("{}", Table::new(&rows));
+        }
+        GatewayCommands::Filters { model, test } => {
+            safety::validate(&config.safety)?;
+            let rows: Vec<FilterRow> = config.safety.iter()
+                .filter(|(scope, _)| model.as_deref().map_or(true, |m| scope.as_str() == "*" || scope.as_str() == m))
+                .flat_map(|(scope, rules)| rules.iter().map(move |rule| FilterRow {
+                    model: scope.clone(),
+                    rule: rule.name.clone(),
+                    kind: match &rule.kind {
+                        safety::FilterKind::Regex { .. } => "regex".to_string(),
+                        safety::FilterKind::Blocklist { words } => format!("blocklist ({} words)", words.len()),
+                        safety::FilterKind::Classifier { model } => format!("classifier ({})", model),
+                    },
+                    stage: format!("{:?}", rule.stage).to_lowercase(),
+                    action: format!("{:?}", rule.action).to_lowercase(),
+                }))
+                .collect();
+            if rows.is_empty() {
+                println!("No content filters; add rules under \"safety\" in {}", configs_dir.join(usage::GATEWAY_FILE).display());
+                return Ok(());
+            }
+            println!("{}", Table::new(&rows));
+
+            if let (Some(model), Some(mut text)) = (model, test) {
+                let rules = safety::rules_for(&config.safety, &model);
+                let screening = safety::screen(manager, &rules, Stage::Prompt, &mut [&mut text]).await?;
+                for (rule, action, matches) in &screening.hits {
+                    println!("   {} {} ({:?}, {} match(es))", style("⚑").yellow(), rule, action, matches);
+                }
+                match screening.blocked {
+                    Some(rule) => println!("{}", style(format!("🚫 Blocked by '{}'", rule)).red().bold()),
+                    None => println!("✅ Passes: {}", text),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_and_caps() {
+        let chat = json!({ "usage": { "prompt_tokens": 12, "completion_tokens": 30, "total_tokens": 42 } });
+        assert_eq!(usage_of(&chat), Some((12, 30)));
+        let embeddings = json!({ "usage": { "prompt_tokens": 8, "total_tokens": 8 } });
+        assert_eq!(usage_of(&embeddings), Some((8, 0)));
+        assert_eq!(usage_of(&json!({ "usage": null })), None);
+        assert_eq!(estimate_tokens(9), 3);
+
+        let mut body = json!({ "model": "llama3", "max_tokens": 512 });
+        cap_max_tokens(&mut body, 100);
+        assert_eq!(body["max_tokens"], 100);
+        let mut body = json!({ "model": "llama3", "max_tokens": 50 });
+        cap_max_tokens(&mut body, 100);
+        assert_eq!(body["max_tokens"], 50);
+    }
+
+    #[test]
+    fn test_openai_base() {
+        assert_eq!(openai_base("http://localhost:11434", &ServingBackend::Ollama), "http://localhost:11434/v1");
+        assert_eq!(openai_base("http://localhost:8000/v1", &ServingBackend::Vllm), "http://localhost:8000/v1");
+    }
+}
@@ -0,0 +1,421 @@
+//! GGUF metadata parser
+//!
+//! Reads the header, key/value metadata and tensor table of GGUF model files
+//! so RCM can size the context window and pick compatible backends
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// GGUF magic bytes ("GGUF" little-endian)
+const GGUF_MAGIC: u32 = 0x4655_4747;
+
+/// Upper bound for strings read from the header (guards against corrupt files)
+const MAX_STRING_LEN: u64 = 16 * 1024 * 1024;
+
+/// Upper bound for metadata arrays (tokenizer vocabularies are the largest)
+const MAX_ARRAY_LEN: u64 = 4 * 1024 * 1024;
+
+/// Upper bound for arrays nested in arrays (real files use at most one level)
+const MAX_ARRAY_DEPTH: usize = 8;
+
+/// Typed GGUF metadata value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GgufValue {
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    F32(f32),
+    Bool(bool),
+    String(String),
+    Array(Vec<GgufValue>),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+}
+
+impl GgufValue {
+    /// Interpret value as an unsigned integer if possible
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Self::U8(v) => Some(*v as u64),
+            Self::U16(v) => Some(*v as u64),
+            Self::U32(v) => Some(*v as u64),
+            Self::U64(v) => Some(*v),
+            Self::I8(v) if *v >= 0 => Some(*v as u64),
+            Self::I16(v) if *v >= 0 => Some(*v as u64),
+            Self::I32(v) if *v >= 0 => Some(*v as u64),
+            Self::I64(v) if *v >= 0 => Some(*v as u64),
+            _ => None,
+        }
+    }
+
+    /// Interpret value as a string if possible
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// Summary of a GGUF model file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GgufMetadata {
+    pub path: PathBuf,
+    pub version: u32,
+    pub tensor_count: u64,
+    pub architecture: Option<String>,
+    pub name: Option<String>,
+    pub parameter_count: Option<u64>,
+    pub context_length: Option<usize>,
+    pub quantization: Option<String>,
+    pub file_size: u64,
+    #[serde(skip)]
+    pub values: HashMap<String, GgufValue>,
+}
+
+impl GgufMetadata {
+    /// Number of transformer blocks (`<arch>.block_count`)
+    pub fn layer_count(&self) -> Option<u32> {
+        let arch = self.architecture.as_ref()?;
+        self.values
+            .get(&format!("{}.block_count", arch))
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32)
+    }
+
+    /// Human-readable parameter count (e.g. "7.2B")
+    pub fn parameter_label(&self) -> Option<String> {
+        self.parameter_count.map(|count| {
+            if count >= 1_000_000_000 {
+                format!("{:.1}B", count as f64 / 1e9)
+            } else if count >= 1_000_000 {
+                format!("{:.0}M", count as f64 / 1e6)
+            } else {
+                count.to_string()
+            }
+        })
+    }
+}
+
+/// Check whether a path looks like a GGUF file
+pub fn is_gguf_file(path: &Path) -> bool {
+    if !path.is_file() {
+        return false;
+    }
+
+    let mut magic = [0u8; 4];
+    File::open(path)
+        .and_then(|mut f| f.read_exact(&mut magic))
+        .map(|_| u32::from_le_bytes(magic) == GGUF_MAGIC)
+        .unwrap_or(false)
+}
+
+/// Locate the GGUF file for a model path (the file itself or the first one in a directory)
+pub fn find_gguf_file(model_path: &Path) -> Option<PathBuf> {
+    if model_path.is_file() {
+        return is_gguf_file(model_path).then(|| model_path.to_path_buf());
+    }
+
+    let mut candidates: Vec<PathBuf> = std::fs::read_dir(model_path)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("gguf"))
+        .collect();
+
+    candidates.sort();
+    candidates.into_iter().find(|p| is_gguf_file(p))
+}
+
+/// Parse GGUF header and metadata from a file
+pub fn read_metadata(path: &Path) -> Result<GgufMetadata> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open GGUF file: {}", path.display()))?;
+    let file_size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let mut reader = GgufReader::new(BufReader::new(file));
+
+    let magic = reader.read_u32()?;
+    if magic != GGUF_MAGIC {
+        return Err(anyhow!("Not a GGUF file: {}", path.display()));
+    }
+
+    let version = reader.read_u32()?;
+    if !(2..=3).contains(&version) {
+        return Err(anyhow!("Unsupported GGUF version {} in {}", version, path.display()));
+    }
+
+    let tensor_count = reader.read_u64()?;
+    let kv_count = reader.read_u64()?;
+
+    let mut values = HashMap::new();
+    for _ in 0..kv_count {
+        let key = reader.read_string()?;
+        let value_type = reader.read_u32()?;
+        let value = reader.read_value(value_type, 0)?;
+        values.insert(key, value);
+    }
+
+    let architecture = values
+        .get("general.architecture")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let name = values
+        .get("general.name")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let context_length = architecture
+        .as_ref()
+        .and_then(|arch| values.get(&format!("{}.context_length", arch)))
+        .and_then(|v| v.as_u64())
+        .map(|v| v as usize);
+
+    let quantization = values
+        .get("general.file_type")
+        .and_then(|v| v.as_u64())
+        .map(|ft| file_type_name(ft as u32).to_string());
+
+    // Prefer the declared count, otherwise sum the tensor shapes
+    let parameter_count = match values.get("general.parameter_count").and_then(|v| v.as_u64()) {
+        Some(count) => Some(count),
+        None => reader.sum_tensor_elements(tensor_count).ok(),
+    };
+
+    Ok(GgufMetadata {
+        path: path.to_path_buf(),
+        version,
+        tensor_count,
+        architecture,
+        name,
+        parameter_count,
+        context_length,
+        quantization,
+        file_size,
+        values,
+    })
+}
+
+/// Map `general.file_type` to the llama.cpp quantization name
+pub fn file_type_name(file_type: u32) -> &'static str {
+    match file_type {
+        0 => "F32",
+        1 => "F16",
+        2 => "Q4_0",
+        3 => "Q4_1",
+        7 => "Q8_0",
+        8 => "Q5_0",
+        9 => "Q5_1",
+        10 => "Q2_K",
+        11 => "Q3_K_S",
+        12 => "Q3_K_M",
+        13 => "Q3_K_L",
+        14 => "Q4_K_S",
+        15 => "Q4_K_M",
+        16 => "Q5_K_S",
+        17 => "Q5_K_M",
+        18 => "Q6_K",
+        19 => "IQ2_XXS",
+        20 => "IQ2_XS",
+        21 => "Q2_K_S",
+        22 => "IQ3_XS",
+        23 => "IQ3_XXS",
+        24 => "IQ1_S",
+        25 => "IQ4_NL",
+        26 => "IQ3_S",
+        27 => "IQ3_M",
+        28 => "IQ2_S",
+        29 => "IQ2_M",
+        30 => "IQ4_XS",
+        31 => "IQ1_M",
+        32 => "BF16",
+        _ => "unknown",
+    }
+}
+
+/// Little-endian reader for GGUF primitives
+struct GgufReader<R: Read> {
+    inner: R,
+}
+
+impl<R: Read> GgufReader<R> {
+    fn new(inner: R) -> Self {
+        Self { inner }
+    }
+
+    fn read_bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut buf = [0u8; N];
+        self.inner.read_exact(&mut buf)
+            .context("Unexpected end of GGUF file")?;
+        Ok(buf)
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.read_bytes::<4>()?))
+    }
+
+    fn read_u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.read_bytes::<8>()?))
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        let len = self.read_u64()?;
+        if len > MAX_STRING_LEN {
+            return Err(anyhow!("GGUF string too long ({} bytes)", len));
+        }
+
+        let mut buf = vec![0u8; len as usize];
+        self.inner.read_exact(&mut buf)
+            .context("Unexpected end of GGUF file")?;
+        Ok(String::from_utf8_lossy(&buf).into_owned())
+    }
+
+    fn read_value(&mut self, value_type: u32, depth: usize) -> Result<GgufValue> {
+        Ok(match value_type {
+            0 => GgufValue::U8(self.read_bytes::<1>()?[0]),
+            1 => GgufValue::I8(self.read_bytes::<1>()?[0] as i8),
+            2 => GgufValue::U16(u16::from_le_bytes(self.read_bytes::<2>()?)),
+            3 => GgufValue::I16(i16::from_le_bytes(self.read_bytes::<2>()?)),
+            4 => GgufValue::U32(self.read_u32()?),
+            5 => GgufValue::I32(i32::from_le_bytes(self.read_bytes::<4>()?)),
+            6 => GgufValue::F32(f32::from_le_bytes(self.read_bytes::<4>()?)),
+            7 => GgufValue::Bool(self.read_bytes::<1>()?[0] != 0),
+            8 => GgufValue::String(self.read_string()?),
+            9 => {
+                if depth >= MAX_ARRAY_DEPTH {
+                    return Err(anyhow!("GGUF arrays nested deeper than {} levels", MAX_ARRAY_DEPTH));
+                }
+                let item_type = self.read_u32()?;
+                let len = self.read_u64()?;
+                if len > MAX_ARRAY_LEN {
+                    return Err(anyhow!("GGUF array too long ({} items)", len));
+                }
+                let mut items = Vec::with_capacity(len.min(1024) as usize);
+                for _ in 0..len {
+                    items.push(self.read_value(item_type, depth + 1)?);
+                }
+                GgufValue::Array(items)
+            }
+            10 => GgufValue::U64(self.read_u64()?),
+            11 => GgufValue::I64(i64::from_le_bytes(self.read_bytes::<8>()?)),
+            12 => GgufValue::F64(f64::from_le_bytes(self.read_bytes::<8>()?)),
+            _ => return Err(anyhow!("Unknown GGUF value type: {}", value_type)),
+        })
+    }
+
+    /// Walk the tensor info table and sum element counts
+    fn sum_tensor_elements(&mut self, tensor_count: u64) -> Result<u64> {
+        let mut total = 0u64;
+
+        for _ in 0..tensor_count {
+            let _name = self.read_string()?;
+            let n_dims = self.read_u32()?;
+            if n_dims > 8 {
+                return Err(anyhow!("Invalid tensor rank: {}", n_dims));
+            }
+
+            let mut elements = 1u64;
+            for _ in 0..n_dims {
+                elements = elements.saturating_mul(self.read_u64()?);
+            }
+
+            let _tensor_type = self.read_u32()?;
+            let _offset = self.read_u64()?;
+            total = total.saturating_add(elements);
+        }
+
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn push_string(buf: &mut Vec<u8>, s: &str) {
+        buf.extend_from_slice(&(s.len() as u64).to_le_bytes());
+        buf.extend_from_slice(s.as_bytes());
+    }
+
+    fn sample_file() -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&GGUF_MAGIC.to_le_bytes());
+        buf.extend_from_slice(&3u32.to_le_bytes());
+        buf.extend_from_slice(&1u64.to_le_bytes()); // tensors
+        buf.extend_from_slice(&3u64.to_le_bytes()); // kv pairs
+
+        push_string(&mut buf, "general.architecture");
+        buf.extend_from_slice(&8u32.to_le_bytes());
+        push_string(&mut buf, "llama");
+
+        push_string(&mut buf, "llama.context_length");
+        buf.extend_from_slice(&4u32.to_le_bytes());
+        buf.extend_from_slice(&4096u32.to_le_bytes());
+
+        push_string(&mut buf, "general.file_type");
+        buf.extend_from_slice(&4u32.to_le_bytes());
+        buf.extend_from_slice(&15u32.to_le_bytes());
+
+        push_string(&mut buf, "token_embd.weight");
+        buf.extend_from_slice(&2u32.to_le_bytes());
+        buf.extend_from_slice(&4096u64.to_le_bytes());
+        buf.extend_from_slice(&32000u64.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf.extend_from_slice(&0u64.to_le_bytes());
+        buf
+    }
+
+    #[test]
+    fn test_read_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.gguf");
+        std::fs::File::create(&path).unwrap().write_all(&sample_file()).unwrap();
+
+        let meta = read_metadata(&path).unwrap();
+        assert_eq!(meta.architecture.as_deref(), Some("llama"));
+        assert_eq!(meta.context_length, Some(4096));
+        assert_eq!(meta.quantization.as_deref(), Some("Q4_K_M"));
+        assert_eq!(meta.parameter_count, Some(4096 * 32000));
+        assert_eq!(find_gguf_file(dir.path()), Some(path));
+    }
+
+    #[test]
+    fn test_rejects_non_gguf() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.bin");
+        std::fs::write(&path, b"not a model").unwrap();
+
+        assert!(!is_gguf_file(&path));
+        assert!(read_metadata(&path).is_err());
+    }
+
+    #[test]
+    fn test_rejects_deeply_nested_arrays() {
+        // `levels` arrays, each holding the next; the innermost is an empty u32 array
+        let nested = |levels: usize| {
+            let mut buf = Vec::new();
+            for _ in 1..levels {
+                buf.extend_from_slice(&9u32.to_le_bytes());
+                buf.extend_from_slice(&1u64.to_le_bytes());
+            }
+            buf.extend_from_slice(&4u32.to_le_bytes());
+            buf.extend_from_slice(&0u64.to_le_bytes());
+            buf
+        };
+
+        let shallow = nested(MAX_ARRAY_DEPTH);
+        assert!(GgufReader::new(&shallow[..]).read_value(9, 0).is_ok());
+
+        let deep = nested(MAX_ARRAY_DEPTH + 1);
+        let err = GgufReader::new(&deep[..]).read_value(9, 0).unwrap_err();
+        assert!(err.to_string().contains("nested"), "{}", err);
+    }
+}
@@ -0,0 +1,326 @@
+//! Hugging Face Hub downloader for GPT-lib
+//!
+//! Resolves repository files over the Hub HTTP API and downloads only the
+//! weights a backend needs, with resumable transfers and progress bars
+
+use anyhow::{anyhow, Context, Result};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use crate::artifacts::{Expectations, Verifier};
+use crate::download_cache::DownloadCache;
+use crate::secrets;
+
+/// Default Hub endpoint (override with `HF_ENDPOINT` for mirrors)
+pub const DEFAULT_ENDPOINT: &str = "https://huggingface.co";
+
+/// GGUF quantization picked when a repository ships several
+const PREFERRED_QUANTS: &[&str] = &["Q4_K_M", "Q4_K_S", "Q5_K_M", "Q4_0", "Q8_0"];
+
+/// Small metadata files fetched alongside safetensors weights
+const SUPPORT_FILES: &[&str] = &[
+    "config.json",
+    "generation_config.json",
+    "tokenizer.json",
+    "tokenizer_config.json",
+    "tokenizer.model",
+    "special_tokens_map.json",
+    "vocab.json",
+    "merges.txt",
+];
+
+/// A file in a Hub repository
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RepoFile {
+    #[serde(rename = "rfilename")]
+    pub path: String,
+    pub size: Option<u64>,
+    /// Git LFS pointer; present for weights and other large files
+    #[serde(default)]
+    pub lfs: Option<LfsInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LfsInfo {
+    pub sha256: String,
+}
+
+#[derive(Deserialize)]
+struct ModelInfo {
+    /// Commit the requested revision resolves to
+    #[serde(default)]
+    sha: Option<String>,
+    #[serde(default)]
+    siblings: Vec<RepoFile>,
+}
+
+/// Hub API client for one endpoint and token
+pub struct HubClient {
+    client: reqwest::Client,
+    endpoint: String,
+    token: Option<String>,
+}
+
+impl HubClient {
+    /// Create a client, resolving the token from `HF_TOKEN` or the RCM `huggingface` auth entry
+    pub async fn new() -> Result<Self> {
+        let endpoint = std::env::var("HF_ENDPOINT")
+            .unwrap_or_else(|_| DEFAULT_ENDPOINT.to_string())
+            .trim_end_matches('/')
+            .to_string();
+
+        let env_token = std::env::var("HF_TOKEN")
+            .or_else(|_| std::env::var("HUGGING_FACE_HUB_TOKEN"))
+            .ok();
+        let token = match env_token {
+            // May be a `secret:` reference
+            Some(token) => Some(secrets::resolve_value(&token).await?),
+            None => config_token().await?,
+        };
+
+        Ok(Self { client: reqwest::Client::new(), endpoint, token })
+    }
+
+    fn get(&self, url: &str) -> reqwest::RequestBuilder {
+        let request = self.client.get(url);
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// List the files of a repository at a revision
+    pub async fn list_files(&self, repo: &str, revision: &str) -> Result<Vec<RepoFile>> {
+        Ok(self.model_info(repo, revision).await?.siblings)
+    }
+
+    /// Commit hash a branch or tag currently points at
+    pub async fn resolve_revision(&self, repo: &str, revision: &str) -> Result<String> {
+        self.model_info(repo, revision).await?
+            .sha
+            .ok_or_else(|| anyhow!("Hugging Face did not report a commit for {}@{}", repo, revision))
+    }
+
+    async fn model_info(&self, repo: &str, revision: &str) -> Result<ModelInfo> {
+        let url = format!("{}/api/models/{}/revision/{}?blobs=true", self.endpoint, repo, revision);
+        let response = self.get(&url).send().await
+            .with_context(|| format!("Failed to query Hugging Face for {}", repo))?;
+
+        match response.status() {
+            s if s.is_success() => {}
+            reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+                return Err(anyhow!(
+                    "Access to {} denied. Set HF_TOKEN or run `rcm auth login huggingface`",
+                    repo
+                ));
+            }
+            reqwest::StatusCode::NOT_FOUND => {
+                return Err(anyhow!("Model {} (revision {}) not found on Hugging Face", repo, revision));
+            }
+            s => return Err(anyhow!("Hugging Face API request failed: {}", s)),
+        }
+
+        response.json().await
+            .context("Invalid Hugging Face API response")
+    }
+
+    /// Download one file, resuming a previous partial download
+    pub async fn download(&self, repo: &str, revision: &str, file: &RepoFile, dest_dir: &Path) -> Result<PathBuf> {
+        let dest = dest_dir.join(&file.path);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        if let (Some(size), Ok(meta)) = (file.size, fs::metadata(&dest).await) {
+            if meta.len() == size {
+                println!("✔️ {} already downloaded", file.path);
+                return Ok(dest);
+            }
+        }
+
+        // Shards shared between repos or revisions are stored once, by digest
+        let cache = DownloadCache::from_user_config().await;
+        if let Some(lfs) = &file.lfs {
+            if cache.restore(&lfs.sha256, &dest).await? {
+                println!("✔️ {} restored from the download cache", file.path);
+                return Ok(dest);
+            }
+        }
+
+        let partial = dest.with_file_name(format!(
+            "{}.part",
+            dest.file_name().and_then(|n| n.to_str()).unwrap_or("download")
+        ));
+        let offset = fs::metadata(&partial).await.map(|m| m.len()).unwrap_or(0);
+
+        let url = format!("{}/{}/resolve/{}/{}", self.endpoint, repo, revision, file.path);
+        let mut request = self.get(&url);
+        if offset > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+        }
+
+        let mut response = request.send().await
+            .with_context(|| format!("Failed to download {}", file.path))?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Download of {} failed: {}", file.path, response.status()));
+        }
+
+        // Servers that ignore Range send the whole file again
+        let resumed = offset > 0 && response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+        let mut out = if resumed {
+            fs::OpenOptions::new().append(true).open(&partial).await?
+        } else {
+            fs::File::create(&partial).await?
+        };
+
+        let total = file.size.or_else(|| response.content_length().map(|l| l + if resumed { offset } else { 0 }));
+        let progress = ProgressBar::new(total.unwrap_or(0));
+        progress.set_style(
+            ProgressStyle::default_bar()
+                .template("{msg} [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
+                .unwrap()
+                .progress_chars("#>-"),
+        );
+        progress.set_message(file.path.clone());
+        progress.set_position(if resumed { offset } else { 0 });
+
+        while let Some(chunk) = response.chunk().await
+            .with_context(|| format!("Connection lost while downloading {} (re-run to resume)", file.path))?
+        {
+            out.write_all(&chunk).await?;
+            progress.inc(chunk.len() as u64);
+        }
+        out.flush().await?;
+        progress.finish();
+
+        if let Some(size) = file.size {
+            let written = fs::metadata(&partial).await?.len();
+            if written != size {
+                return Err(anyhow!(
+                    "Incomplete download of {} ({} of {} bytes); re-run to resume",
+                    file.path, written, size
+                ));
+            }
+        }
+
+        fs::rename(&partial, &dest).await
+            .with_context(|| format!("Failed to move {} into place", dest.display()))?;
+        let expect = Expectations { sha256: file.lfs.as_ref().map(|lfs| lfs.sha256.clone()), signature: None };
+        Verifier::from_user_config().await.verify(&url, &dest, &expect).await?;
+        if let Some(lfs) = &file.lfs {
+            cache.adopt(&dest, &lfs.sha256).await
+                .with_context(|| format!("Downloaded {} does not match the Hub checksum", file.path))?;
+        }
+        Ok(dest)
+    }
+}
+
+/// Hugging Face token from `rcm auth login huggingface` (or an `auth.hf` entry)
+async fn config_token() -> Result<Option<String>> {
+    let config = crate::config::Config::load(None).await.unwrap_or_default();
+    for key in ["huggingface", "huggingface.co", "hf"] {
+        if let Some(crate::auth::Credential::Bearer(token)) = crate::auth::credential(&config, key).await? {
+            return Ok(Some(token));
+        }
+    }
+    Ok(None)
+}
+
+/// Pick the files a backend needs from a repository listing
+///
+/// `include` selects files whose path contains the pattern (e.g. a quantization
+/// like `Q5_K_M`). Otherwise a single GGUF is preferred, then safetensors shards
+/// with their config and tokenizer files, then the whole repository.
+pub fn select_files(files: &[RepoFile], include: Option<&str>) -> Vec<RepoFile> {
+    if let Some(pattern) = include {
+        let pattern = pattern.to_lowercase();
+        return files.iter().filter(|f| f.path.to_lowercase().contains(&pattern)).cloned().collect();
+    }
+
+    let ggufs: Vec<&RepoFile> = files.iter().filter(|f| f.path.ends_with(".gguf")).collect();
+    if !ggufs.is_empty() {
+        let preferred = PREFERRED_QUANTS
+            .iter()
+            .find_map(|q| ggufs.iter().find(|f| f.path.to_uppercase().contains(q)))
+            // Fall back to the smallest file
+            .or_else(|| ggufs.iter().min_by_key(|f| f.size.unwrap_or(u64::MAX)));
+        return preferred.map(|f| vec![(*f).clone()]).unwrap_or_default();
+    }
+
+    if files.iter().any(|f| f.path.ends_with(".safetensors")) {
+        return files
+            .iter()
+            .filter(|f| {
+                f.path.ends_with(".safetensors")
+                    || f.path == "model.safetensors.index.json"
+                    || SUPPORT_FILES.contains(&f.path.as_str())
+            })
+            .cloned()
+            .collect();
+    }
+
+    files.iter().filter(|f| !f.path.starts_with(".git")).cloned().collect()
+}
+
+/// Download the selected files of a repository into `dest_dir`
+pub async fn download_repo(repo: &str, revision: &str, dest_dir: &Path, include: Option<&str>) -> Result<Vec<PathBuf>> {
+    let hub = HubClient::new().await?;
+    let files = hub.list_files(repo, revision).await?;
+    let selected = select_files(&files, include);
+
+    if selected.is_empty() {
+        return Err(anyhow!(
+            "No matching files in {}{}",
+            repo,
+            include.map(|p| format!(" for pattern '{}'", p)).unwrap_or_default()
+        ));
+    }
+
+    let total: u64 = selected.iter().filter_map(|f| f.size).sum();
+    println!("📋 {} file(s), {:.2} GB", selected.len(), total as f64 / 1_073_741_824.0);
+
+    let mut paths = Vec::with_capacity(selected.len());
+    for file in &selected {
+        paths.push(hub.download(repo, revision, file, dest_dir).await?);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, size: u64) -> RepoFile {
+        RepoFile { path: path.to_string(), size: Some(size), lfs: None }
+    }
+
+    #[test]
+    fn test_select_prefers_single_gguf_quant() {
+        let files = vec![
+            file("README.md", 1),
+            file("model.Q8_0.gguf", 800),
+            file("model.Q4_K_M.gguf", 400),
+            file("model.Q2_K.gguf", 200),
+        ];
+        assert_eq!(select_files(&files, None), vec![file("model.Q4_K_M.gguf", 400)]);
+        assert_eq!(select_files(&files, Some("q8_0")), vec![file("model.Q8_0.gguf", 800)]);
+    }
+
+    #[test]
+    fn test_select_safetensors_skips_duplicate_weights() {
+        let files = vec![
+            file("config.json", 1),
+            file("tokenizer.json", 1),
+            file("model-00001-of-00002.safetensors", 100),
+            file("model-00002-of-00002.safetensors", 100),
+            file("model.safetensors.index.json", 1),
+            file("pytorch_model.bin", 200),
+            file(".gitattributes", 1),
+        ];
+        let selected: Vec<String> = select_files(&files, None).into_iter().map(|f| f.path).collect();
+        assert_eq!(selected.len(), 5);
+        assert!(!selected.contains(&"pytorch_model.bin".to_string()));
+    }
+}
@@ -0,0 +1,530 @@
+//! Kubernetes manifest generation for model deployment
+//!
+//! Renders Deployment, Service and HorizontalPodAutoscaler manifests (or Helm
+//! values) from a model's `ModelConfig` and `ServingConfig`
+
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use crate::{ModelConfig, ServingBackend};
+
+/// `rcm gpt k8s` subcommands
+#[derive(Subcommand)]
+pub enum K8sCommands {
+    /// Generate manifests for serving a model in a cluster
+    Generate {
+        /// Model name
+        model: String,
+        /// Output directory (defaults to .rcm/k8s/<model>)
+        #[arg(long)]
+        out: Option<String>,
+        /// Kubernetes namespace
+        #[arg(long, default_value = "default")]
+        namespace: String,
+        /// Serving backend (defaults to the model's configured backend)
+        #[arg(long)]
+        backend: Option<String>,
+        /// Container image (defaults to the backend's upstream image)
+        #[arg(long)]
+        image: Option<String>,
+        /// Initial replica count
+        #[arg(long, default_value = "1")]
+        replicas: u32,
+        /// Maximum replicas for the autoscaler
+        #[arg(long, default_value = "3")]
+        max_replicas: u32,
+        /// GPUs requested per pod (defaults to 1 when GPU layers are configured)
+        #[arg(long)]
+        gpus: Option<u32>,
+        /// Emit a Helm values file instead of raw manifests
+        #[arg(long)]
+        helm: bool,
+        /// Print to stdout instead of writing files
+        #[arg(long)]
+        stdout: bool,
+    },
+}
+
+/// Options that are not part of the model configuration
+#[derive(Debug, Clone)]
+pub struct K8sOptions {
+    pub namespace: String,
+    pub image: Option<String>,
+    pub replicas: u32,
+    pub max_replicas: u32,
+    pub gpus: Option<u32>,
+}
+
+/// A rendered manifest file
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    pub file_name: String,
+    pub content: String,
+}
+
+/// Container image, arguments and port for a backend
+struct BackendRuntime {
+    image: String,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    port: u16,
+}
+
+/// Kubernetes-safe resource name for a model
+pub fn resource_name(model: &str) -> String {
+    let name: String = model
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let name = format!("rcm-gpt-{}", name.trim_matches('-'));
+    name.chars().take(63).collect::<String>().trim_end_matches('-').to_string()
+}
+
+/// Kubernetes label value for a model: at most 63 of `[A-Za-z0-9._-]`, alphanumeric at both ends
+pub fn label_value(model: &str) -> String {
+    let value: String = model
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') { c } else { '-' })
+        .collect();
+    let value: String = value.trim_matches(|c: char| !c.is_ascii_alphanumeric()).chars().take(63).collect();
+    value.trim_end_matches(|c: char| !c.is_ascii_alphanumeric()).to_string()
+}
+
+fn backend_runtime(config: &ModelConfig, image: Option<&str>) -> Result<BackendRuntime> {
+    let port = config.serving_config.port;
+    let params = &config.parameters;
+    let model_file = config
+        .model_path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_else(|| config.name.clone());
+
+    let mut runtime = match &config.backend {
+        ServingBackend::Ollama => BackendRuntime {
+            image: "ollama/ollama:latest".to_string(),
+            args: Vec::new(),
+            env: vec![("OLLAMA_HOST".to_string(), format!("0.0.0.0:{}", port))],
+            port,
+        },
+        ServingBackend::LlamaCpp => {
+            let mut args = vec![
+                "-m".to_string(), format!("/models/{}", model_file),
+                "--host".to_string(), "0.0.0.0".to_string(),
+                "--port".to_string(), port.to_string(),
+                "-c".to_string(), params.context_length.to_string(),
+            ];
+            if let Some(layers) = params.gpu_layers {
+                args.push("-ngl".to_string());
+                args.push(layers.to_string());
+            }
+            BackendRuntime {
+                image: "ghcr.io/ggerganov/llama.cpp:server".to_string(),
+                args,
+                env: Vec::new(),
+                port,
+            }
+        }
+        ServingBackend::Vllm => BackendRuntime {
+            image: "vllm/vllm-openai:latest".to_string(),
+            args: vec![
+                "--model".to_string(), config.name.clone(),
+                "--port".to_string(), port.to_string(),
+                "--max-model-len".to_string(), params.context_length.to_string(),
+            ],
+            env: Vec::new(),
+            port,
+        },
+        ServingBackend::TorchServe => BackendRuntime {
+            image: "pytorch/torchserve:latest".to_string(),
+            args: Vec::new(),
+            env: Vec::new(),
+            port,
+        },
+        ServingBackend::TensorFlowServing => BackendRuntime {
+            image: "tensorflow/serving:latest".to_string(),
+            args: vec![format!("--rest_api_port={}", port), format!("--model_name={}", config.name)],
+            env: Vec::new(),
+            port,
+        },
+        ServingBackend::Remote(url) => {
+            return Err(anyhow!("Model '{}' uses a remote endpoint ({}); nothing to deploy", config.name, url));
+        }
+        other => {
+            let image = image.ok_or_else(|| anyhow!(
+                "No default image for backend {:?}; pass --image", other
+            ))?;
+            BackendRuntime {
+                image: image.to_string(),
+                args: Vec::new(),
+                env: Vec::new(),
+                port,
+            }
+        }
+    };
+
+    if let Some(image) = image {
+        runtime.image = image.to_string();
+    }
+    if let Some(threads) = params.cpu_threads {
+        runtime.env.push(("RCM_GPT_THREADS".to_string(), threads.to_string()));
+    }
+
+    Ok(runtime)
+}
+
+fn gpu_count(config: &ModelConfig, options: &K8sOptions) -> u32 {
+    options.gpus.unwrap_or(match config.parameters.gpu_layers {
+        Some(layers) if layers > 0 => 1,
+        _ => 0,
+    })
+}
+
+fn yaml_str(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Render Deployment, Service, ConfigMap and HPA manifests
+pub fn render_manifests(config: &ModelConfig, options: &K8sOptions) -> Result<Vec<Manifest>> {
+    let name = resource_name(&config.name);
+    let runtime = backend_runtime(config, options.image.as_deref())?;
+    let serving = &config.serving_config;
+    let gpus = gpu_count(config, options);
+    let labels = format!(
+        "app.kubernetes.io/name: {}\n    app.kubernetes.io/managed-by: rcm\n    rcm.dev/model: {}",
+        name,
+        yaml_str(&label_value(&config.name))
+    );
+
+    let mut manifests = Vec::new();
+
+    // Serving settings are exposed to the container through a ConfigMap
+    let mut config_data = vec![
+        ("RCM_GPT_MODEL", config.name.clone()),
+        ("RCM_GPT_API_VERSION", serving.api_version.clone()),
+        ("RCM_GPT_ENABLE_CORS", serving.enable_cors.to_string()),
+        ("RCM_GPT_TIMEOUT_SECONDS", serving.timeout_seconds.to_string()),
+        ("RCM_GPT_CONTEXT_LENGTH", config.parameters.context_length.to_string()),
+        ("RCM_GPT_TEMPERATURE", config.parameters.temperature.to_string()),
+    ];
+    if let Some(limit) = serving.rate_limit {
+        config_data.push(("RCM_GPT_RATE_LIMIT", limit.to_string()));
+    }
+    manifests.push(Manifest {
+        file_name: "configmap.yaml".to_string(),
+        content: format!(
+            "apiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: {name}-config\n  namespace: {ns}\n  labels:\n    {labels}\ndata:\n{data}\n",
+            name = name,
+            ns = options.namespace,
+            labels = labels,
+            data = config_data
+                .iter()
+                .map(|(k, v)| format!("  {}: {}", k, yaml_str(v)))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+    });
+
+    if serving.auth_token.is_some() {
+        manifests.push(Manifest {
+            file_name: "secret.yaml".to_string(),
+            content: format!(
+                "apiVersion: v1\nkind: Secret\nmetadata:\n  name: {name}-auth\n  namespace: {ns}\n  labels:\n    {labels}\ntype: Opaque\nstringData:\n  # Replace with the real token, or manage this Secret outside of RCM\n  RCM_GPT_AUTH_TOKEN: \"change-me\"\n",
+                name = name,
+                ns = options.namespace,
+                labels = labels,
+            ),
+        });
+    }
+
+    let args = if runtime.args.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "          args:\n{}\n",
+            runtime.args.iter().map(|a| format!("            - {}", yaml_str(a))).collect::<Vec<_>>().join("\n")
+        )
+    };
+
+    let env = if runtime.env.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "          env:\n{}\n",
+            runtime.env
+                .iter()
+                .map(|(k, v)| format!("            - name: {}\n              value: {}", k, yaml_str(v)))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    };
+
+    let secret_ref = if serving.auth_token.is_some() {
+        format!("            - secretRef:\n                name: {}-auth\n", name)
+    } else {
+        String::new()
+    };
+
+    let gpu_resources = if gpus > 0 {
+        format!("              nvidia.com/gpu: {}\n", gpus)
+    } else {
+        String::new()
+    };
+
+    let probe_delay = if gpus > 0 { 60 } else { 30 };
+
+    manifests.push(Manifest {
+        file_name: "deployment.yaml".to_string(),
+        content: format!(
+            r#"apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: {name}
+  namespace: {ns}
+  labels:
+    {labels}
+spec:
+  replicas: {replicas}
+  selector:
+    matchLabels:
+      app.kubernetes.io/name: {name}
+  template:
+    metadata:
+      labels:
+        app.kubernetes.io/name: {name}
+        app.kubernetes.io/managed-by: rcm
+    spec:
+      containers:
+        - name: model
+          image: {image}
+{args}{env}          envFrom:
+            - configMapRef:
+                name: {name}-config
+{secret_ref}          ports:
+            - name: http
+              containerPort: {port}
+          readinessProbe:
+            httpGet:
+              path: {health}
+              port: http
+            initialDelaySeconds: {probe_delay}
+            periodSeconds: 10
+            timeoutSeconds: {timeout}
+          livenessProbe:
+            httpGet:
+              path: {health}
+              port: http
+            initialDelaySeconds: {liveness_delay}
+            periodSeconds: 30
+            timeoutSeconds: {timeout}
+            failureThreshold: 3
+          resources:
+            requests:
+              cpu: "{cpu}"
+              memory: 8Gi
+            limits:
+              memory: 16Gi
+{gpu_resources}          volumeMounts:
+            - name: models
+              mountPath: /models
+      volumes:
+        - name: models
+          persistentVolumeClaim:
+            claimName: {name}-models
+"#,
+            name = name,
+            ns = options.namespace,
+            labels = labels,
+            replicas = options.replicas,
+            image = runtime.image,
+            args = args,
+            env = env,
+            secret_ref = secret_ref,
+            port = runtime.port,
+            health = serving.health_check_path,
+            probe_delay = probe_delay,
+            liveness_delay = probe_delay * 2,
+            timeout = serving.timeout_seconds.max(1),
+            cpu = config.parameters.cpu_threads.unwrap_or(2),
+            gpu_resources = gpu_resources,
+        ),
+    });
+
+    manifests.push(Manifest {
+        file_name: "service.yaml".to_string(),
+        content: format!(
+            "apiVersion: v1\nkind: Service\nmetadata:\n  name: {name}\n  namespace: {ns}\n  labels:\n    {labels}\nspec:\n  selector:\n    app.kubernetes.io/name: {name}\n  ports:\n    - name: http\n      port: {port}\n      targetPort: http\n",
+            name = name,
+            ns = options.namespace,
+            labels = labels,
+            port = runtime.port,
+        ),
+    });
+
+    manifests.push(Manifest {
+        file_name: "hpa.yaml".to_string(),
+        content: format!(
+            "apiVersion: autoscaling/v2\nkind: HorizontalPodAutoscaler\nmetadata:\n  name: {name}\n  namespace: {ns}\n  labels:\n    {labels}\nspec:\n  scaleTargetRef:\n    apiVersion: apps/v1\n    kind: Deployment\n    name: {name}\n  minReplicas: {min}\n  maxReplicas: {max}\n  metrics:\n    - type: Resource\n      resource:\n        name: cpu\n        target:\n          type: Utilization\n          averageUtilization: 75\n",
+            name = name,
+            ns = options.namespace,
+            labels = labels,
+            min = options.replicas,
+            max = options.max_replicas.max(options.replicas),
+        ),
+    });
+
+    Ok(manifests)
+}
+
+/// Render a Helm values file for a generic model-serving chart
+pub fn render_helm_values(config: &ModelConfig, options: &K8sOptions) -> Result<Manifest> {
+    let runtime = backend_runtime(config, options.image.as_deref())?;
+    let serving = &config.serving_config;
+    let gpus = gpu_count(config, options);
+    let (repository, tag) = runtime.image.rsplit_once(':').unwrap_or((runtime.image.as_str(), "latest"));
+
+    let args = if runtime.args.is_empty() {
+        " []".to_string()
+    } else {
+        format!("\n{}", runtime.args.iter().map(|a| format!("  - {}", yaml_str(a))).collect::<Vec<_>>().join("\n"))
+    };
+
+    let env = if runtime.env.is_empty() {
+        " {}".to_string()
+    } else {
+        format!("\n{}", runtime.env.iter().map(|(k, v)| format!("  {}: {}", k, yaml_str(v))).collect::<Vec<_>>().join("\n"))
+    };
+
+    let content = format!(
+        r#"# Helm values generated by `rcm gpt k8s generate --helm`
+nameOverride: {name}
+namespace: {ns}
+replicaCount: {replicas}
+
+image:
+  repository: {repository}
+  tag: {tag}
+
+model:
+  name: {model}
+  backend: {backend}
+  contextLength: {context}
+
+args:{args}
+
+env:{env}
+
+service:
+  port: {port}
+
+probes:
+  path: {health}
+  timeoutSeconds: {timeout}
+
+resources:
+  requests:
+    cpu: "{cpu}"
+    memory: 8Gi
+  limits:
+    memory: 16Gi
+    nvidia.com/gpu: {gpus}
+
+autoscaling:
+  enabled: true
+  minReplicas: {replicas}
+  maxReplicas: {max}
+  targetCPUUtilizationPercentage: 75
+
+serving:
+  apiVersion: {api_version}
+  enableCors: {cors}
+  rateLimit: {rate_limit}
+  authTokenSecret: {auth_secret}
+"#,
+        name = resource_name(&config.name),
+        ns = options.namespace,
+        replicas = options.replicas,
+        repository = repository,
+        tag = tag,
+        model = yaml_str(&config.name),
+        backend = yaml_str(&format!("{:?}", config.backend)),
+        context = config.parameters.context_length,
+        args = args,
+        env = env,
+        port = runtime.port,
+        health = serving.health_check_path,
+        timeout = serving.timeout_seconds.max(1),
+        cpu = config.parameters.cpu_threads.unwrap_or(2),
+        gpus = gpus,
+        max = options.max_replicas.max(options.replicas),
+        api_version = serving.api_version,
+        cors = serving.enable_cors,
+        rate_limit = serving.rate_limit.map(|r| r.to_string()).unwrap_or_else(|| "null".to_string()),
+        auth_secret = if serving.auth_token.is_some() {
+            format!("{}-auth", resource_name(&config.name))
+        } else {
+            "null".to_string()
+        },
+    );
+
+    Ok(Manifest { file_name: "values.yaml".to_string(), content })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ModelFormat, ModelParameters, ServingConfig};
+    use std::path::PathBuf;
+
+    fn config(backend: ServingBackend) -> ModelConfig {
+        let mut parameters = ModelParameters::default();
+        parameters.gpu_layers = Some(32);
+        ModelConfig {
+            name: "llama2:7b".to_string(),
+            version: "latest".to_string(),
+            revision: None,
+            format: ModelFormat::GGUF,
+            backend,
+            model_path: PathBuf::from("/models/llama-2-7b.Q4_K_M.gguf"),
+            config_path: None,
+            tokenizer_path: None,
+            parameters,
+            serving_config: ServingConfig::default(),
+            lineage: None,
+        }
+    }
+
+    fn options() -> K8sOptions {
+        K8sOptions {
+            namespace: "ml".to_string(),
+            image: None,
+            replicas: 1,
+            max_replicas: 4,
+            gpus: None,
+        }
+    }
+
+    #[test]
+    fn test_resource_name() {
+        assert_eq!(resource_name("llama2:7b"), "rcm-gpt-llama2-7b");
+        assert_eq!(label_value("llama2:7b"), "llama2-7b");
+        assert_eq!(label_value("TheBloke/Mistral-7B-GGUF"), "TheBloke-Mistral-7B-GGUF");
+        assert_eq!(label_value(":latest_"), "latest");
+        assert_eq!(label_value(&"a".repeat(70)).len(), 63);
+    }
+
+    #[test]
+    fn test_deployment_uses_health_path_and_gpu() {
+        let manifests = render_manifests(&config(ServingBackend::LlamaCpp), &options()).unwrap();
+        let deployment = manifests.iter().find(|m| m.file_name == "deployment.yaml").unwrap();
+        assert!(deployment.content.contains("path: /health"));
+        assert!(deployment.content.contains("nvidia.com/gpu: 1"));
+        assert!(deployment.content.contains("llama.cpp:server"));
+        assert!(deployment.content.contains("rcm.dev/model: \"llama2-7b\""));
+        assert!(manifests.iter().any(|m| m.file_name == "hpa.yaml"));
+    }
+
+    #[test]
+    fn test_remote_backend_rejected() {
+        let result = render_manifests(&config(ServingBackend::Remote("https://x".to_string())), &options());
+        assert!(result.is_err());
+    }
+}
@@ -14,6 +14,27 @@ use tokio::net::TcpListener;
 use tokio::process::Command as AsyncCommand;
 use reqwest;
 use serde_json;
+use tracing::Instrument;
+
+pub mod backend;
+pub mod bench;
+pub mod chat;
+pub mod commit;
+pub mod convert;
+pub mod gateway;
+pub mod gguf;
+pub mod hub;
+pub mod k8s;
+pub mod lifecycle;
+pub mod otel;
+pub mod prompt;
+pub mod routing;
+pub mod safety;
+pub mod secrets;
+pub mod service;
+pub mod share;
+pub mod tune;
+pub mod usage;
 
 /// GPT model formats supported by RCM
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +56,8 @@ pub enum ServingBackend {
     Candle,
     TorchServe,
     TensorFlowServing,
+    Vllm,
+    Remote(String),
     Custom(String),
 }
 
@@ -42,7 +65,11 @@ pub enum ServingBackend {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelConfig {
     pub name: String,
+    /// Tag or branch the model tracks (`latest`, `8b`, `main`)
     pub version: String,
+    /// Exact upstream revision installed: Ollama manifest digest or Hub commit
+    #[serde(default)]
+    pub revision: Option<String>,
     pub format: ModelFormat,
     pub backend: ServingBackend,
     pub model_path: PathBuf,
@@ -50,6 +77,9 @@ pub struct ModelConfig {
     pub tokenizer_path: Option<PathBuf>,
     pub parameters: ModelParameters,
     pub serving_config: ServingConfig,
+    /// Source model this one was converted from (`rcm gpt convert`)
+    #[serde(default)]
+    pub lineage: Option<convert::Lineage>,
 }
 
 /// Model runtime parameters
@@ -64,6 +94,9 @@ pub struct ModelParameters {
     pub max_tokens: usize,
     pub gpu_layers: Option<u32>,
     pub cpu_threads: Option<u32>,
+    /// NUMA node the backend process is bound to
+    #[serde(default)]
+    pub numa_node: Option<u32>,
 }
 
 /// Serving configuration
@@ -77,6 +110,19 @@ pub struct ServingConfig {
     pub rate_limit: Option<u32>,
     pub timeout_seconds: u64,
     pub health_check_path: String,
+    /// Environment for the backend process; values may be `secret:<name>` references
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Backend processes serving the model, on consecutive ports from `port`
+    #[serde(default = "default_replicas")]
+    pub replicas: u32,
+    /// How requests are spread over the replicas
+    #[serde(default)]
+    pub routing: routing::RoutingPolicy,
+}
+
+fn default_replicas() -> u32 {
+    1
 }
 
 /// Model registry for managing available models
@@ -98,6 +144,9 @@ pub struct ModelInstance {
     pub started_at: String,
     pub memory_usage: Option<u64>,
     pub gpu_usage: Option<f32>,
+    /// Extra processes serving the same model beside the primary
+    #[serde(default)]
+    pub replicas: Vec<routing::Replica>,
 }
 
 /// Model status
@@ -129,18 +178,33 @@ pub enum GptCommands {
         /// GPU layers to use
         #[arg(long)]
         gpu_layers: Option<u32>,
-        /// CPU threads
+        /// CPU threads (keeps the configured or tuned value when omitted)
         #[arg(long)]
         threads: Option<u32>,
-        /// Context length
-        #[arg(long, default_value = "2048")]
-        context: usize,
+        /// Bind the backend to one NUMA node's CPUs and memory
+        #[arg(long)]
+        numa_node: Option<u32>,
+        /// Context length (defaults to the model's GGUF metadata, then 2048)
+        #[arg(long)]
+        context: Option<usize>,
         /// Creativity level (temperature)
         #[arg(long, default_value = "0.7")]
         creativity: f32,
-        /// Serving backend
-        #[arg(long, default_value = "ollama")]
-        backend: String,
+        /// Serving backend (auto-selected from model format and hardware when omitted)
+        #[arg(long)]
+        backend: Option<String>,
+        /// Explain why the backend was chosen
+        #[arg(long)]
+        explain: bool,
+        /// Stay attached and supervise the backend process (used by `gpt service`)
+        #[arg(long)]
+        foreground: bool,
+        /// Number of backend processes to spread requests over
+        #[arg(long)]
+        replicas: Option<u32>,
+        /// Replica routing: round-robin or least-loaded
+        #[arg(long)]
+        routing: Option<String>,
     },
     
     /// Download and install a model
@@ -150,9 +214,12 @@ pub enum GptCommands {
         /// Model version or tag
         #[arg(long)]
         version: Option<String>,
-        /// Source registry
+        /// Source registry (ollama, huggingface, local, or a team registry URL)
         #[arg(long, default_value = "ollama")]
         source: String,
+        /// Only download Hugging Face files matching this pattern (e.g. Q5_K_M)
+        #[arg(long)]
+        include: Option<String>,
         /// Force reinstall
         #[arg(long)]
         force: bool,
@@ -195,7 +262,17 @@ pub enum GptCommands {
     /// Update model to latest version
     Update {
         /// Model name
-        model: String,
+        #[arg(required_unless_present = "all")]
+        model: Option<String>,
+        /// Update every installed model
+        #[arg(long, conflicts_with = "model")]
+        all: bool,
+        /// Report available updates without downloading
+        #[arg(long)]
+        check: bool,
+        /// Restore the version replaced by the last update
+        #[arg(long, conflicts_with_all = ["all", "check"])]
+        rollback: bool,
     },
     
     /// Chat with a model
@@ -207,6 +284,18 @@ pub enum GptCommands {
         /// Interactive mode
         #[arg(long)]
         interactive: bool,
+        /// System prompt for the conversation
+        #[arg(long)]
+        system: Option<String>,
+        /// Session name for saved history (defaults to the model name)
+        #[arg(long)]
+        session: Option<String>,
+        /// Maximum tokens per reply
+        #[arg(long, default_value = "512")]
+        max_tokens: usize,
+        /// Temperature (creativity)
+        #[arg(long, default_value = "0.7")]
+        temperature: f32,
     },
     
     /// Generate text completion
@@ -214,7 +303,14 @@ pub enum GptCommands {
         /// Model name
         model: String,
         /// Prompt text
-        prompt: String,
+        #[arg(required_unless_present = "template", conflicts_with = "template")]
+        prompt: Option<String>,
+        /// Render a saved prompt template instead (see `rcm gpt prompt`)
+        #[arg(long)]
+        template: Option<String>,
+        /// Template variable values (key=value)
+        #[arg(long = "var", requires = "template")]
+        vars: Vec<String>,
         /// Maximum tokens to generate
         #[arg(long, default_value = "100")]
         max_tokens: usize,
@@ -223,6 +319,100 @@ pub enum GptCommands {
         temperature: f32,
     },
     
+    /// Propose a conventional-commit message for the staged diff (local models only)
+    CommitMsg {
+        /// Model name
+        model: String,
+        /// Commit with the proposed message after review in $EDITOR
+        #[arg(long)]
+        commit: bool,
+        /// Maximum diff characters sent to the model
+        #[arg(long, default_value = "12000")]
+        max_diff_chars: usize,
+    },
+
+    /// Draft a changelog section from commits since the last tag
+    Changelog {
+        /// Model used for the highlights paragraph (omit to only group commits)
+        #[arg(long)]
+        model: Option<String>,
+        /// Start of the range (defaults to the latest tag)
+        #[arg(long)]
+        since: Option<String>,
+        /// Version heading for the section
+        #[arg(long, default_value = "Unreleased")]
+        version: String,
+        /// Prepend the section to this file instead of printing it
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    
+    /// Benchmark CPU threads and batch sizes for a llama.cpp model and save the best
+    Tune {
+        /// Model name
+        model: String,
+        /// Thread counts to try (defaults to candidates from the CPU topology)
+        #[arg(long, value_delimiter = ',')]
+        threads: Vec<u32>,
+        /// Batch sizes to try
+        #[arg(long, value_delimiter = ',')]
+        batch: Vec<usize>,
+        /// Repetitions per configuration
+        #[arg(long, default_value = "3")]
+        repetitions: u32,
+        /// Only report results; leave the model config unchanged
+        #[arg(long)]
+        no_save: bool,
+    },
+    
+    /// Benchmark running models with a standard prompt set and compare results
+    Bench {
+        /// Running models to benchmark (shows saved results when omitted)
+        models: Vec<String>,
+        /// Times to run the prompt set
+        #[arg(long, default_value = "1")]
+        repetitions: u32,
+        /// Tokens generated per prompt
+        #[arg(long, default_value = "128")]
+        max_tokens: usize,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+        /// Print results without saving them
+        #[arg(long)]
+        no_save: bool,
+    },
+    
+    /// Convert a Hugging Face model to quantized GGUF with llama.cpp and register it
+    Convert {
+        /// Installed safetensors model
+        model: String,
+        /// Target format
+        #[arg(long, default_value = "gguf")]
+        to: String,
+        /// Quantization (q4_k_m, q5_k_m, q8_0, f16, ...)
+        #[arg(long, default_value = "q4_k_m")]
+        quant: String,
+        /// Registry name for the result (defaults to <model>-<quant>)
+        #[arg(long)]
+        name: Option<String>,
+        /// Keep the unquantized F16 GGUF
+        #[arg(long)]
+        keep_intermediate: bool,
+        /// Skip loading the result in llama.cpp
+        #[arg(long)]
+        no_validate: bool,
+        /// Replace an existing model with the same name
+        #[arg(long)]
+        force: bool,
+    },
+    
+    /// Manage named prompt templates and presets
+    Prompt {
+        #[command(subcommand)]
+        cmd: prompt::PromptCommands,
+    },
+    
     /// Configure model settings
     Config {
         /// Model name
@@ -234,6 +424,43 @@ pub enum GptCommands {
         #[arg(long)]
         show: bool,
     },
+    
+    /// Run a model as a system service
+    Service {
+        #[command(subcommand)]
+        cmd: service::ServiceCommands,
+    },
+    
+    /// Share this workspace's models with teammates over HTTP
+    Registry {
+        #[command(subcommand)]
+        cmd: share::RegistryCommands,
+    },
+    
+    /// One authenticated endpoint for all served models, with per-client budgets
+    Gateway {
+        #[command(subcommand)]
+        cmd: gateway::GatewayCommands,
+    },
+    
+    /// Report gateway usage per client and model
+    Usage {
+        /// Only this client
+        #[arg(long)]
+        client: Option<String>,
+        /// Reporting window: 30m, 24h, 7d or a date
+        #[arg(long, default_value = "24h")]
+        since: String,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    
+    /// Generate Kubernetes manifests for a model
+    K8s {
+        #[command(subcommand)]
+        cmd: k8s::K8sCommands,
+    },
 }
 
 /// GPT model manager
@@ -256,6 +483,7 @@ impl Default for ModelParameters {
             max_tokens: 256,
             gpu_layers: None,
             cpu_threads: None,
+            numa_node: None,
         }
     }
 }
@@ -271,6 +499,9 @@ impl Default for ServingConfig {
             rate_limit: None,
             timeout_seconds: 30,
             health_check_path: "/health".to_string(),
+            env: HashMap::new(),
+            replicas: 1,
+            routing: routing::RoutingPolicy::default(),
         }
     }
 }
@@ -307,11 +538,45 @@ impl GptManager {
         })
     }
     
+    /// Whether `--dry-run` is set; commands and file changes are printed instead
+    pub fn dry_run(&self) -> bool {
+        crate::util::is_dry_run()
+    }
+    
+    /// Print a command in dry-run mode; returns true when it should be skipped
+    /// Run a backend process on its configured NUMA node (`--numa-node`)
+    fn bind_numa(cmd: AsyncCommand, config: &ModelConfig) -> Result<AsyncCommand> {
+        let Some(node) = config.parameters.numa_node else {
+            return Ok(cmd);
+        };
+        #[cfg(feature = "arm")]
+        {
+            let topology = arm_lib::numa::NumaTopology::detect()?;
+            let bound = arm_lib::numa::bind_command(cmd.as_std(), topology.node(node)?)?;
+            Ok(AsyncCommand::from(bound))
+        }
+        #[cfg(not(feature = "arm"))]
+        {
+            drop(cmd);
+            Err(anyhow!("--numa-node {} needs GPT-lib built with the arm feature", node))
+        }
+    }
+    
+    fn skip_in_dry_run(&self, cmd: &AsyncCommand) -> bool {
+        if !self.dry_run() {
+            return false;
+        }
+        let cmd = cmd.as_std();
+        let args: Vec<String> = cmd.get_args().map(|a| a.to_string_lossy().into_owned()).collect();
+        println!("[dry-run] {} {}", cmd.get_program().to_string_lossy(), args.join(" "));
+        true
+    }
+    
     /// Serve a model with LET imperative
     pub async fn serve_model(&mut self, cmd: &GptCommands) -> Result<()> {
         if let GptCommands::Serve { 
-            model, deploy, port, host, gpu_layers, threads, 
-            context, creativity, backend 
+            model, deploy, port, host, gpu_layers, threads, numa_node,
+            context, creativity, backend, explain, foreground, replicas, routing 
         } = cmd {
             
             println!("🚀 RCM LET GPT serve {} --deploy", model);
@@ -319,21 +584,62 @@ impl GptManager {
             // Check if model exists
             if !self.model_exists(model).await? {
                 println!("📥 Model '{}' not found, downloading...", model);
-                self.install_model(model, None, "ollama", false).await?;
+                self.install_model(model, None, "ollama", None, false).await?;
             }
             
             // Configure model parameters
             let mut model_config = self.get_or_create_model_config(model).await?;
-            model_config.parameters.context_length = *context;
+            let metadata = self.apply_gguf_metadata(&mut model_config)?;
+            if let Some(context) = context {
+                model_config.parameters.context_length = *context;
+            }
             model_config.parameters.temperature = *creativity;
             model_config.parameters.gpu_layers = *gpu_layers;
-            model_config.parameters.cpu_threads = *threads;
+            if threads.is_some() {
+                model_config.parameters.cpu_threads = *threads;
+            }
+            model_config.parameters.numa_node = *numa_node;
             model_config.serving_config.host = host.clone();
             model_config.serving_config.port = *port;
-            model_config.backend = self.parse_backend(backend)?;
+            if let Some(replicas) = replicas {
+                if *replicas == 0 {
+                    return Err(anyhow!("--replicas must be at least 1"));
+                }
+                model_config.serving_config.replicas = *replicas;
+            }
+            if let Some(routing) = routing {
+                model_config.serving_config.routing = routing.parse()?;
+            }
+            
+            // Flag > RCM_GPT_BACKEND > automatic selection
+            let preference = match backend.clone().or_else(backend::env_preference) {
+                Some(name) if name != "auto" => Some(self.parse_backend(&name)?),
+                _ => None,
+            };
+            let hardware = backend::HardwareProfile::detect().await;
+            let runtimes = backend::RuntimeAvailability::detect().await;
+            let choice = backend::select_backend(
+                &model_config.format, metadata.as_ref(), &hardware, &runtimes, preference,
+            )?;
+            
+            if *explain {
+                choice.explain();
+            }
+            
+            model_config.backend = choice.backend;
+            if model_config.parameters.gpu_layers.is_none() {
+                model_config.parameters.gpu_layers = choice.gpu_layers;
+            }
+            
+            if let Some(metadata) = &metadata {
+                Self::validate_gguf_backend(metadata, &model_config.backend)?;
+            }
             
             if *deploy {
                 self.deploy_model(&model_config).await?;
+                if *foreground {
+                    self.supervise_model(&model_config.name).await?;
+                }
             } else {
                 self.configure_model(&model_config).await?;
             }
@@ -344,14 +650,111 @@ impl GptManager {
         }
     }
     
+    /// Block while the deployed backend is alive, failing when it exits
+    async fn supervise_model(&self, model: &str) -> Result<()> {
+        let pid = self.registry.active_models.get(model).and_then(|i| i.process_id);
+        
+        let Some(pid) = pid else {
+            // Remote backends have no local process to watch
+            println!("👀 Supervising '{}' (remote); press Ctrl+C to stop", model);
+            tokio::signal::ctrl_c().await?;
+            return Ok(());
+        };
+        
+        println!("👀 Supervising '{}' (pid {})", model, pid);
+        loop {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {
+                    let _ = AsyncCommand::new("kill").arg(pid.to_string()).output().await;
+                    println!("🛑 Stopped '{}'", model);
+                    return Ok(());
+                }
+                _ = tokio::time::sleep(std::time::Duration::from_secs(5)) => {
+                    if !Self::process_alive(pid).await {
+                        return Err(anyhow!("Backend process for '{}' (pid {}) exited", model, pid));
+                    }
+                }
+            }
+        }
+    }
+    
+    async fn process_alive(pid: u32) -> bool {
+        let mut cmd = if cfg!(windows) {
+            let mut c = AsyncCommand::new("tasklist");
+            c.args(["/FI", &format!("PID eq {}", pid), "/NH"]);
+            c
+        } else {
+            let mut c = AsyncCommand::new("kill");
+            c.args(["-0", &pid.to_string()]);
+            c
+        };
+        
+        match cmd.output().await {
+            Ok(output) if cfg!(windows) => String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()),
+            Ok(output) => output.status.success(),
+            Err(_) => false,
+        }
+    }
+    
+    /// Generate Kubernetes manifests or Helm values for a model
+    pub async fn generate_k8s(
+        &mut self,
+        model: &str,
+        backend: Option<&str>,
+        options: &k8s::K8sOptions,
+        out: Option<&str>,
+        helm: bool,
+        stdout: bool,
+    ) -> Result<()> {
+        let mut config = self.get_or_create_model_config(model).await?;
+        self.apply_gguf_metadata(&mut config)?;
+        if let Some(name) = backend {
+            config.backend = self.parse_backend(name)?;
+        }
+        
+        let manifests = if helm {
+            vec![k8s::render_helm_values(&config, options)?]
+        } else {
+            k8s::render_manifests(&config, options)?
+        };
+        
+        if stdout {
+            let docs: Vec<&str> = manifests.iter().map(|m| m.content.as_str()).collect();
+            println!("{}", docs.join("---\n"));
+            return Ok(());
+        }
+        
+        let out_dir = match out {
+            Some(dir) => PathBuf::from(dir),
+            None => self.workspace_root.join(".rcm").join("k8s").join(k8s::resource_name(model)),
+        };
+        fs::create_dir_all(&out_dir).await
+            .context("Failed to create manifest output directory")?;
+        
+        for manifest in &manifests {
+            let path = out_dir.join(&manifest.file_name);
+            if self.dry_run() {
+                println!("[dry-run] write {} ({} lines)", path.display(), manifest.content.lines().count());
+                continue;
+            }
+            fs::write(&path, &manifest.content).await
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("📄 Wrote {}", path.display());
+        }
+        
+        println!("✅ Generated {} manifest(s) for '{}' ({:?})", manifests.len(), model, config.backend);
+        Ok(())
+    }
+    
     /// Install a model
-    pub async fn install_model(&mut self, model: &str, version: Option<&str>, source: &str, force: bool) -> Result<()> {
+    pub async fn install_model(&mut self, model: &str, version: Option<&str>, source: &str, include: Option<&str>, force: bool) -> Result<()> {
         println!("📦 Installing model: {} from {}", model, source);
         
         match source {
             "ollama" => self.install_ollama_model(model, version, force).await,
-            "huggingface" => self.install_huggingface_model(model, version, force).await,
+            "huggingface" => self.install_huggingface_model(model, version, include, force).await,
             "local" => self.install_local_model(model, version).await,
+            s if s.starts_with("http://") || s.starts_with("https://") => self.install_from_registry(s, model, force).await,
             _ => Err(anyhow!("Unsupported model source: {}", source)),
         }
     }
@@ -376,6 +779,10 @@ impl GptManager {
             cmd.arg("--force");
         }
         
+        if self.skip_in_dry_run(&cmd) {
+            return Ok(());
+        }
+        
         let output = cmd.output().await?;
         
         if !output.status.success() {
@@ -385,10 +792,18 @@ impl GptManager {
             ));
         }
         
+        // Record the pulled manifest so `rcm gpt update` can tell when the tag moves
+        let revision = self.ollama_models().await.ok().and_then(|models| {
+            models.into_iter()
+                .find(|m| m.name == lifecycle::ollama_spec(&model_spec, ""))
+                .map(|m| m.id)
+        });
+        
         // Register model in RCM registry
         let config = ModelConfig {
             name: model.to_string(),
             version: version.unwrap_or("latest").to_string(),
+            revision,
             format: ModelFormat::Ollama,
             backend: ServingBackend::Ollama,
             model_path: self.models_dir.join(model),
@@ -396,6 +811,7 @@ impl GptManager {
             tokenizer_path: None,
             parameters: ModelParameters::default(),
             serving_config: ServingConfig::default(),
+            lineage: None,
         };
         
         self.registry.models.insert(model.to_string(), config);
@@ -406,42 +822,41 @@ impl GptManager {
     }
     
     /// Install model from Hugging Face
-    async fn install_huggingface_model(&mut self, model: &str, version: Option<&str>, force: bool) -> Result<()> {
+    async fn install_huggingface_model(&mut self, model: &str, version: Option<&str>, include: Option<&str>, force: bool) -> Result<()> {
         println!("📥 Downloading from Hugging Face: {}", model);
         
-        // Use huggingface-hub or git clone
         let model_dir = self.models_dir.join(model);
         
-        if model_dir.exists() && !force {
+        // An unregistered directory holds an interrupted download, which is resumed
+        if model_dir.exists() && !force && self.registry.models.contains_key(model) {
             return Err(anyhow!("Model already exists. Use --force to reinstall."));
         }
         
-        // Clone from Hugging Face
-        let repo_url = format!("https://huggingface.co/{}", model);
-        let mut cmd = AsyncCommand::new("git");
-        cmd.arg("clone")
-           .arg(&repo_url)
-           .arg(&model_dir);
-        
-        if let Some(ver) = version {
-            cmd.arg("--branch").arg(ver);
+        if self.dry_run() {
+            if force && model_dir.exists() {
+                println!("[dry-run] remove {}", model_dir.display());
+            }
+            println!("[dry-run] download {}@{} into {}", model, version.unwrap_or("main"), model_dir.display());
+            return Ok(());
         }
         
-        let output = cmd.output().await?;
-        
-        if !output.status.success() {
-            return Err(anyhow!(
-                "Failed to clone model: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
+        if force && model_dir.exists() {
+            fs::remove_dir_all(&model_dir).await
+                .with_context(|| format!("Failed to remove {}", model_dir.display()))?;
         }
         
+        // Download the commit the revision points at now, and remember it for updates
+        let revision = version.unwrap_or("main");
+        let commit = hub::HubClient::new().await?.resolve_revision(model, revision).await?;
+        hub::download_repo(model, &commit, &model_dir, include).await?;
+        
         // Auto-detect model format
         let format = self.detect_model_format(&model_dir).await?;
         
         let config = ModelConfig {
             name: model.to_string(),
-            version: version.unwrap_or("main").to_string(),
+            version: revision.to_string(),
+            revision: Some(commit),
             format,
             backend: ServingBackend::LlamaCpp, // Default for HF models
             model_path: model_dir,
@@ -449,6 +864,7 @@ impl GptManager {
             tokenizer_path: None,
             parameters: ModelParameters::default(),
             serving_config: ServingConfig::default(),
+            lineage: None,
         };
         
         self.registry.models.insert(model.to_string(), config);
@@ -462,10 +878,73 @@ impl GptManager {
     async fn deploy_model(&mut self, config: &ModelConfig) -> Result<()> {
         println!("🚀 Deploying model: {}", config.name);
         
+        let replicas = config.serving_config.replicas.max(1);
+        if replicas > 1 && !routing::supports_replicas(&config.backend) {
+            return Err(anyhow!("Backend {:?} cannot run multiple replicas", config.backend));
+        }
+        
+        self.deploy_backend(config).await?;
+        if replicas == 1 {
+            self.announce_deployed(&config.name).await;
+            return Ok(());
+        }
+        
+        // Each replica is its own backend process on the next port up
+        let mut primary = self.registry.active_models.remove(&config.name);
+        for index in 1..replicas {
+            let mut replica_config = config.clone();
+            replica_config.serving_config.port = u16::try_from(index)
+                .ok()
+                .and_then(|offset| config.serving_config.port.checked_add(offset))
+                .ok_or_else(|| anyhow!("Not enough ports above {} for {} replicas", config.serving_config.port, replicas))?;
+            
+            let deployed = self.deploy_backend(&replica_config).await;
+            let replica = self.registry.active_models.remove(&config.name);
+            if let Err(e) = deployed {
+                if let Some(primary) = primary {
+                    self.registry.active_models.insert(config.name.clone(), primary);
+                }
+                self.stop_instance(&config.name).await?;
+                return Err(e.context(format!("Failed to start replica {} of '{}'", index + 1, config.name)));
+            }
+            if let (Some(primary), Some(replica)) = (primary.as_mut(), replica) {
+                primary.replicas.push(routing::Replica { process_id: replica.process_id, endpoint: replica.endpoint });
+            }
+        }
+        
+        if let Some(primary) = primary {
+            self.registry.active_models.insert(config.name.clone(), primary);
+            self.save_registry().await?;
+        }
+        println!("⚖️ Model '{}' serving from {} replicas ({:?} routing)", 
+                config.name, replicas, config.serving_config.routing);
+        self.announce_deployed(&config.name).await;
+        
+        Ok(())
+    }
+    
+    /// Send the `model.deployed` webhook for a model that is now serving
+    async fn announce_deployed(&self, name: &str) {
+        let Some(instance) = self.registry.active_models.get(name).filter(|_| !self.dry_run()) else {
+            return;
+        };
+        let data = serde_json::json!({
+            "name": name,
+            "backend": format!("{:?}", instance.config.backend),
+            "endpoint": instance.endpoint,
+            "replicas": 1 + instance.replicas.len(),
+        });
+        crate::webhooks::emit(&self.workspace_root, crate::webhooks::MODEL_DEPLOYED, data).await;
+    }
+    
+    /// Start one backend process for a model and register it as active
+    async fn deploy_backend(&mut self, config: &ModelConfig) -> Result<()> {
         match config.backend {
             ServingBackend::Ollama => self.deploy_ollama_model(config).await,
             ServingBackend::LlamaCpp => self.deploy_llamacpp_model(config).await,
-            ServingBackend::Candle => self.deploy_candle_model(config).await,
+            ServingBackend::Vllm => self.deploy_vllm_model(config).await,
+            ServingBackend::Remote(_) => self.deploy_remote_model(config).await,
+            ServingBackend::Candle | ServingBackend::Onnx => Err(backend::unavailable(&config.backend)),
             _ => Err(anyhow!("Backend not yet implemented: {:?}", config.backend)),
         }
     }
@@ -482,10 +961,18 @@ impl GptManager {
             cmd.env("OLLAMA_NUM_GPU", gpu_layers.to_string());
         }
         
-        if let Some(threads) = config.parameters.cpu_threads {
+        if let Some(threads) = config.parameters.cpu_threads.or_else(|| numa_threads(config.parameters.numa_node)) {
             cmd.env("OLLAMA_NUM_THREAD", threads.to_string());
         }
         
+        secrets::apply_env(&mut cmd, &config.serving_config.env).await?;
+        let mut cmd = Self::bind_numa(cmd, config)?;
+        
+        if self.skip_in_dry_run(&cmd) {
+            println!("[dry-run] ollama run {} --verbose", config.name);
+            return Ok(());
+        }
+        
         // Start ollama serve in background
         let child = cmd.spawn()?;
         
@@ -494,7 +981,8 @@ impl GptManager {
         
         // Load the specific model
         let mut load_cmd = AsyncCommand::new("ollama");
-        load_cmd.arg("run").arg(&config.name).arg("--verbose");
+        load_cmd.arg("run").arg(&config.name).arg("--verbose")
+            .env("OLLAMA_HOST", format!("{}:{}", config.serving_config.host, config.serving_config.port));
         
         let output = load_cmd.output().await?;
         
@@ -514,6 +1002,7 @@ impl GptManager {
             started_at: chrono::Utc::now().to_rfc3339(),
             memory_usage: None,
             gpu_usage: None,
+            replicas: Vec::new(),
         };
         
         self.registry.active_models.insert(config.name.clone(), instance);
@@ -544,10 +1033,22 @@ impl GptManager {
             cmd.arg("--n-gpu-layers").arg(gpu_layers.to_string());
         }
         
-        if let Some(threads) = config.parameters.cpu_threads {
+        if let Some(threads) = config.parameters.cpu_threads.or_else(|| numa_threads(config.parameters.numa_node)) {
             cmd.arg("--threads").arg(threads.to_string());
         }
         
+        // Left to llama-server's default unless tuned or configured
+        if config.parameters.batch_size > 1 {
+            cmd.arg("--batch-size").arg(config.parameters.batch_size.to_string());
+        }
+        
+        secrets::apply_env(&mut cmd, &config.serving_config.env).await?;
+        let mut cmd = Self::bind_numa(cmd, config)?;
+        
+        if self.skip_in_dry_run(&cmd) {
+            return Ok(());
+        }
+        
         let child = cmd.spawn()?;
         
         // Register as active model
@@ -559,6 +1060,7 @@ impl GptManager {
             started_at: chrono::Utc::now().to_rfc3339(),
             memory_usage: None,
             gpu_usage: None,
+            replicas: Vec::new(),
         };
         
         self.registry.active_models.insert(config.name.clone(), instance);
@@ -570,6 +1072,71 @@ impl GptManager {
         Ok(())
     }
     
+    /// Deploy model using vLLM's OpenAI-compatible server
+    async fn deploy_vllm_model(&mut self, config: &ModelConfig) -> Result<()> {
+        let mut cmd = AsyncCommand::new("vllm");
+        cmd.arg("serve").arg(&config.model_path)
+           .arg("--host").arg(&config.serving_config.host)
+           .arg("--port").arg(config.serving_config.port.to_string())
+           .arg("--max-model-len").arg(config.parameters.context_length.to_string())
+           .arg("--served-model-name").arg(&config.name);
+        
+        secrets::apply_env(&mut cmd, &config.serving_config.env).await?;
+        let mut cmd = Self::bind_numa(cmd, config)?;
+        
+        if self.skip_in_dry_run(&cmd) {
+            return Ok(());
+        }
+        
+        let child = cmd.spawn()
+            .context("Failed to start vLLM. Install with `pip install vllm`.")?;
+        
+        let instance = ModelInstance {
+            config: config.clone(),
+            process_id: child.id(),
+            endpoint: format!("http://{}:{}/v1", config.serving_config.host, config.serving_config.port),
+            status: ModelStatus::Running,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            memory_usage: None,
+            gpu_usage: None,
+            replicas: Vec::new(),
+        };
+        
+        self.registry.active_models.insert(config.name.clone(), instance);
+        self.save_registry().await?;
+        
+        println!("✅ Model '{}' deployed with vLLM on {}:{}", 
+                config.name, config.serving_config.host, config.serving_config.port);
+        
+        Ok(())
+    }
+    
+    /// Register a remote OpenAI-compatible endpoint as the serving backend
+    async fn deploy_remote_model(&mut self, config: &ModelConfig) -> Result<()> {
+        let url = match &config.backend {
+            ServingBackend::Remote(url) => url.trim_end_matches('/').to_string(),
+            _ => return Err(anyhow!("Model '{}' is not configured for a remote backend", config.name)),
+        };
+        
+        let instance = ModelInstance {
+            config: config.clone(),
+            process_id: None,
+            endpoint: url.clone(),
+            status: ModelStatus::Running,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            memory_usage: None,
+            gpu_usage: None,
+            replicas: Vec::new(),
+        };
+        
+        self.registry.active_models.insert(config.name.clone(), instance);
+        self.save_registry().await?;
+        
+        println!("☁️ Model '{}' routed to remote API {}", config.name, url);
+        
+        Ok(())
+    }
+    
     /// List available models
     pub async fn list_models(&self, running_only: bool, format: &str) -> Result<()> {
         match format {
@@ -605,14 +1172,21 @@ impl GptManager {
             }
             
             let (status, endpoint) = if let Some(instance) = self.registry.active_models.get(name) {
-                (format!("{:?}", instance.status), instance.endpoint.clone())
+                let endpoint = match instance.replicas.len() {
+                    0 => instance.endpoint.clone(),
+                    n => format!("{} (+{} replicas)", instance.endpoint, n),
+                };
+                (format!("{:?}", instance.status), endpoint)
             } else {
                 ("Stopped".to_string(), "N/A".to_string())
             };
             
             rows.push(ModelRow {
                 name: name.clone(),
-                version: config.version.clone(),
+                version: match &config.lineage {
+                    Some(lineage) => format!("{} of {}", lineage.quantization, lineage.source),
+                    None => config.version.clone(),
+                },
                 backend: format!("{:?}", config.backend),
                 status,
                 endpoint,
@@ -634,9 +1208,46 @@ impl GptManager {
         let instance = self.registry.active_models.get(model)
             .ok_or_else(|| anyhow!("Model '{}' is not running", model))?;
         
+        let span = otel::generation_span(model, &instance.config.backend, max_tokens, temperature);
+        crate::telemetry::record_content(&span, "gen_ai.prompt", prompt);
+        let result = self.generate_routed(model, instance, prompt, max_tokens, temperature).instrument(span.clone()).await;
+        match &result {
+            Ok(text) => crate::telemetry::record_content(&span, "gen_ai.completion", text),
+            Err(e) => crate::telemetry::record_error(&span, e),
+        }
+        result
+    }
+    
+    /// Generate text on the first reachable replica of `instance`
+    async fn generate_routed(&self, model: &str, instance: &ModelInstance, prompt: &str, max_tokens: usize, temperature: f32) -> Result<String> {
+        if instance.replicas.is_empty() {
+            return self.generate_on(instance, prompt, max_tokens, temperature).await;
+        }
+        
+        // Try replicas in routing order, moving on only when one cannot be reached
+        let mut last_error = None;
+        for endpoint in self.route(instance).await? {
+            let target = ModelInstance { endpoint, ..instance.clone() };
+            match self.generate_on(&target, prompt, max_tokens, temperature).await {
+                Err(e) if routing::is_unreachable(&e) => {
+                    tracing::warn!("Replica {} of '{}' unreachable: {}", target.endpoint, model, e);
+                    last_error = Some(e);
+                }
+                result => return result,
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("No replica of '{}' is reachable", model)))
+    }
+    
+    /// Generate text against one backend endpoint
+    async fn generate_on(&self, instance: &ModelInstance, prompt: &str, max_tokens: usize, temperature: f32) -> Result<String> {
+        tracing::Span::current().record("server.address", instance.endpoint.as_str());
         match instance.config.backend {
             ServingBackend::Ollama => self.generate_ollama(instance, prompt, max_tokens, temperature).await,
             ServingBackend::LlamaCpp => self.generate_llamacpp(instance, prompt, max_tokens, temperature).await,
+            ServingBackend::Vllm | ServingBackend::Remote(_) => {
+                self.generate_openai_compatible(instance, prompt, max_tokens, temperature).await
+            }
             _ => Err(anyhow!("Text generation not implemented for backend: {:?}", instance.config.backend)),
         }
     }
@@ -666,6 +1277,7 @@ impl GptManager {
         }
         
         let result: serde_json::Value = response.json().await?;
+        otel::record_usage(&tracing::Span::current(), result["prompt_eval_count"].as_u64(), result["eval_count"].as_u64());
         let generated_text = result["response"]
             .as_str()
             .ok_or_else(|| anyhow!("Invalid response format"))?;
@@ -673,6 +1285,112 @@ impl GptManager {
         Ok(generated_text.to_string())
     }
     
+    /// Generate text using an OpenAI-compatible completions API (vLLM, remote)
+    async fn generate_openai_compatible(&self, instance: &ModelInstance, prompt: &str, max_tokens: usize, temperature: f32) -> Result<String> {
+        let client = reqwest::Client::new();
+        let url = format!("{}/completions", instance.endpoint);
+        
+        let request_body = serde_json::json!({
+            "model": instance.config.name,
+            "prompt": prompt,
+            "max_tokens": max_tokens,
+            "temperature": temperature,
+        });
+        
+        let mut request = client.post(&url).json(&request_body);
+        if let Some(token) = instance.config.serving_config.auth_token.clone()
+            .or_else(|| std::env::var("RCM_GPT_API_KEY").ok())
+        {
+            // Either may be a `secret:` reference
+            request = request.bearer_auth(secrets::resolve_value(&token).await?);
+        }
+        
+        let response = request.send().await?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow!("API request failed: {}", response.status()));
+        }
+        
+        let result: serde_json::Value = response.json().await?;
+        otel::record_usage(&tracing::Span::current(), result["usage"]["prompt_tokens"].as_u64(), result["usage"]["completion_tokens"].as_u64());
+        let generated_text = result["choices"][0]["text"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Invalid response format"))?;
+        
+        Ok(generated_text.to_string())
+    }
+    
+    /// Generate text using llama-server
+    ///
+    /// Uses the OpenAI-compatible `/v1/completions` endpoint and falls back to the
+    /// native `/completion` endpoint on older llama.cpp builds.
+    async fn generate_llamacpp(&self, instance: &ModelInstance, prompt: &str, max_tokens: usize, temperature: f32) -> Result<String> {
+        let client = reqwest::Client::new();
+        let endpoint = instance.endpoint.trim_end_matches('/');
+        
+        let token = match instance.config.serving_config.auth_token.clone()
+            .or_else(|| std::env::var("RCM_GPT_API_KEY").ok())
+        {
+            Some(token) => Some(secrets::resolve_value(&token).await?),
+            None => None,
+        };
+        
+        let request_body = serde_json::json!({
+            "model": instance.config.name,
+            "prompt": prompt,
+            "max_tokens": max_tokens,
+            "temperature": temperature,
+        });
+        
+        let mut request = client.post(format!("{}/v1/completions", endpoint)).json(&request_body);
+        if let Some(token) = &token {
+            request = request.bearer_auth(token);
+        }
+        
+        let response = request.send().await
+            .with_context(|| format!("Failed to reach llama-server at {}", endpoint))?;
+        
+        if response.status().is_success() {
+            let result: serde_json::Value = response.json().await?;
+            otel::record_usage(&tracing::Span::current(), result["usage"]["prompt_tokens"].as_u64(), result["usage"]["completion_tokens"].as_u64());
+            return result["choices"][0]["text"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("Invalid response format"));
+        }
+        
+        if response.status() != reqwest::StatusCode::NOT_FOUND {
+            return Err(anyhow!("API request failed: {}", response.status()));
+        }
+        
+        // Native llama.cpp API
+        let request_body = serde_json::json!({
+            "prompt": prompt,
+            "n_predict": max_tokens,
+            "temperature": temperature,
+            "stream": false,
+        });
+        
+        let mut request = client.post(format!("{}/completion", endpoint)).json(&request_body);
+        if let Some(token) = &token {
+            request = request.bearer_auth(token);
+        }
+        
+        let response = request.send().await?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow!("API request failed: {}", response.status()));
+        }
+        
+        let result: serde_json::Value = response.json().await?;
+        otel::record_usage(&tracing::Span::current(), result["tokens_evaluated"].as_u64(), result["tokens_predicted"].as_u64());
+        let generated_text = result["content"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Invalid response format"))?;
+        
+        Ok(generated_text.to_string())
+    }
+    
     // Helper methods
     async fn model_exists(&self, model: &str) -> Result<bool> {
         Ok(self.registry.models.contains_key(model))
@@ -697,7 +1415,9 @@ impl GptManager {
     }
     
     async fn detect_model_format(&self, model_dir: &Path) -> Result<ModelFormat> {
-        if model_dir.join("config.json").exists() {
+        if gguf::find_gguf_file(model_dir).is_some() {
+            Ok(ModelFormat::GGUF)
+        } else if model_dir.join("config.json").exists() {
             Ok(ModelFormat::Safetensors)
         } else if model_dir.join("pytorch_model.bin").exists() {
             Ok(ModelFormat::PyTorch)
@@ -716,6 +1436,7 @@ impl GptManager {
             let config = ModelConfig {
                 name: model.to_string(),
                 version: "latest".to_string(),
+                revision: None,
                 format: ModelFormat::Ollama,
                 backend: ServingBackend::Ollama,
                 model_path: self.models_dir.join(model),
@@ -723,19 +1444,62 @@ impl GptManager {
                 tokenizer_path: None,
                 parameters: ModelParameters::default(),
                 serving_config: ServingConfig::default(),
+                lineage: None,
             };
             Ok(config)
         }
     }
     
+    /// Populate model parameters from GGUF metadata when the model is a GGUF file
+    fn apply_gguf_metadata(&self, config: &mut ModelConfig) -> Result<Option<gguf::GgufMetadata>> {
+        let gguf_path = match gguf::find_gguf_file(&config.model_path) {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        
+        let metadata = gguf::read_metadata(&gguf_path)?;
+        
+        config.format = ModelFormat::GGUF;
+        config.model_path = gguf_path;
+        if let Some(context_length) = metadata.context_length {
+            config.parameters.context_length = context_length;
+        }
+        
+        println!("🔎 GGUF: {} {} {} (context {})",
+                metadata.architecture.as_deref().unwrap_or("unknown"),
+                metadata.parameter_label().unwrap_or_else(|| "?".to_string()),
+                metadata.quantization.as_deref().unwrap_or("unknown"),
+                config.parameters.context_length);
+        
+        Ok(Some(metadata))
+    }
+    
+    /// Check that a GGUF model can be served by the selected backend
+    fn validate_gguf_backend(metadata: &gguf::GgufMetadata, backend: &ServingBackend) -> Result<()> {
+        match backend {
+            ServingBackend::Ollama | ServingBackend::LlamaCpp | ServingBackend::Custom(_) => Ok(()),
+            ServingBackend::Candle | ServingBackend::Onnx => Err(backend::unavailable(backend)),
+            _ => Err(anyhow!(
+                "Backend {:?} cannot serve GGUF models ({}). Use --backend llamacpp or ollama",
+                backend,
+                metadata.path.display()
+            )),
+        }
+    }
+    
     fn parse_backend(&self, backend: &str) -> Result<ServingBackend> {
         match backend.to_lowercase().as_str() {
             "ollama" => Ok(ServingBackend::Ollama),
             "llamacpp" | "llama.cpp" => Ok(ServingBackend::LlamaCpp),
-            "onnx" => Ok(ServingBackend::Onnx),
-            "candle" => Ok(ServingBackend::Candle),
+            "onnx" => Err(backend::unavailable(&ServingBackend::Onnx)),
+            "candle" => Err(backend::unavailable(&ServingBackend::Candle)),
             "torchserve" => Ok(ServingBackend::TorchServe),
             "tensorflow" | "tfserving" => Ok(ServingBackend::TensorFlowServing),
+            "vllm" => Ok(ServingBackend::Vllm),
+            "remote" => backend::remote_url().map(ServingBackend::Remote).ok_or_else(|| anyhow!(
+                "--backend remote needs {} set to an OpenAI-compatible endpoint",
+                backend::REMOTE_URL_ENV
+            )),
             _ => Ok(ServingBackend::Custom(backend.to_string())),
         }
     }
@@ -749,8 +1513,11 @@ impl GptManager {
     
     async fn save_registry(&self) -> Result<()> {
         let content = serde_json::to_string_pretty(&self.registry)?;
-        fs::write(&self.registry.registry_path, content).await?;
-        Ok(())
+        if self.dry_run() {
+            println!("[dry-run] update {}", self.registry.registry_path.display());
+            return Ok(());
+        }
+        crate::storage::replace_file(&self.registry.registry_path, content.into_bytes()).await
     }
     
     // Placeholder implementations
@@ -758,18 +1525,25 @@ impl GptManager {
         todo!("Local model installation")
     }
     
-    async fn deploy_candle_model(&mut self, _config: &ModelConfig) -> Result<()> {
-        todo!("Candle backend deployment")
-    }
-    
     async fn list_models_json(&self, _running_only: bool) -> Result<()> {
         let json = serde_json::to_string_pretty(&self.registry)?;
         println!("{}", json);
         Ok(())
     }
-    
-    async fn generate_llamacpp(&self, _instance: &ModelInstance, _prompt: &str, _max_tokens: usize, _temperature: f32) -> Result<String> {
-        todo!("LlamaCpp text generation")
+
+}
+
+/// CPUs on a NUMA node, the default thread count for a backend bound to it
+fn numa_threads(node: Option<u32>) -> Option<u32> {
+    #[cfg(feature = "arm")]
+    {
+        arm_lib::numa::NumaTopology::detect().ok()
+            .and_then(|topology| topology.node(node?).ok().map(|n| n.cpus.len() as u32))
+    }
+    #[cfg(not(feature = "arm"))]
+    {
+        let _ = node;
+        None
     }
 }
 
@@ -781,17 +1555,124 @@ pub async fn handle_command(workspace: &crate::workspace::Workspace, cmd: GptCom
         GptCommands::Serve { .. } => {
             gpt_manager.serve_model(&cmd).await
         }
-        GptCommands::Install { model, version, source, force } => {
-            gpt_manager.install_model(&model, version.as_deref(), &source, force).await
+        GptCommands::Install { model, version, source, include, force } => {
+            gpt_manager.install_model(&model, version.as_deref(), &source, include.as_deref(), force).await
         }
         GptCommands::List { running, format } => {
             gpt_manager.list_models(running, &format).await
         }
-        GptCommands::Generate { model, prompt, max_tokens, temperature } => {
+        GptCommands::Generate { model, prompt, template, vars, max_tokens, temperature } => {
+            let prompt = match (prompt, template) {
+                (Some(prompt), _) => prompt,
+                (None, Some(template)) => {
+                    let (template, rendered) = gpt_manager.render_prompt(&template, &vars).await?;
+                    match template.system {
+                        Some(system) => format!("{}\n\n{}", system, rendered),
+                        None => rendered,
+                    }
+                }
+                (None, None) => return Err(anyhow!("Pass a prompt or --template")),
+            };
             let result = gpt_manager.generate_text(&model, &prompt, max_tokens, temperature).await?;
             println!("{}", result);
             Ok(())
         }
+        GptCommands::Chat { model, message, interactive, system, session, max_tokens, temperature } => {
+            let context_length = gpt_manager.registry.models.get(&model)
+                .map(|c| c.parameters.context_length)
+                .unwrap_or_else(|| ModelParameters::default().context_length);
+            let options = chat::ChatOptions { max_tokens, temperature, context_length };
+            let chats_dir = gpt_manager.configs_dir.join("chats");
+            chat::chat(
+                &gpt_manager, &chats_dir, &model, message.as_deref(), interactive,
+                system, session.as_deref(), options,
+            ).await
+        }
+        GptCommands::Update { model, all, check, rollback } => match model {
+            Some(model) if rollback => gpt_manager.rollback_model(&model).await,
+            model => gpt_manager.update_models(model.as_deref(), all, check).await,
+        },
+        GptCommands::Remove { model, all_versions } => {
+            gpt_manager.remove_model(&model, all_versions).await
+        }
+        GptCommands::Tune { model, threads, batch, repetitions, no_save } => {
+            let options = tune::TuneOptions { threads, batch_sizes: batch, repetitions, no_save };
+            tune::tune(&mut gpt_manager, &model, &options).await
+        }
+        GptCommands::Bench { models, repetitions, max_tokens, format, no_save } => {
+            let options = bench::BenchOptions { repetitions, max_tokens, no_save };
+            gpt_manager.bench(&models, &options, &format).await
+        }
+        GptCommands::Convert { model, to, quant, name, keep_intermediate, no_validate, force } => {
+            let options = convert::ConvertOptions { to, quant, name, keep_intermediate, no_validate, force };
+            gpt_manager.convert_model(&model, &options).await
+        }
+        GptCommands::Prompt { cmd } => prompt::handle_command(&gpt_manager, cmd).await,
+        GptCommands::CommitMsg { model, commit, max_diff_chars } => {
+            commit::commit_msg(&gpt_manager, workspace.root(), &model, commit, max_diff_chars).await
+        }
+        GptCommands::Changelog { model, since, version, out } => {
+            let section = commit::draft_changelog(
+                &gpt_manager, workspace.root(), model.as_deref(), since.as_deref(), &version,
+            ).await?;
+            match out {
+                Some(path) => {
+                    let existing = std::fs::read_to_string(&path).unwrap_or_default();
+                    if gpt_manager.dry_run() {
+                        println!("[dry-run] Would prepend to {}:\n{}", path.display(), section);
+                    } else {
+                        std::fs::write(&path, commit::prepend_changelog(&existing, &section))?;
+                        println!("📝 Changelog updated: {}", path.display());
+                    }
+                }
+                None => print!("{}", section),
+            }
+            Ok(())
+        }
+        GptCommands::Registry { cmd } => match cmd {
+            share::RegistryCommands::Serve { host, port, models, no_auth } => {
+                gpt_manager.serve_registry(&host, port, models.as_deref(), no_auth).await
+            }
+            share::RegistryCommands::Ls { source } => share::list_remote(&source).await,
+        },
+        GptCommands::Gateway { cmd: gateway::GatewayCommands::Serve { host, port } } => {
+            gpt_manager.serve_gateway(&host, port).await
+        }
+        GptCommands::Gateway { cmd } => gateway::manage(&gpt_manager, cmd).await,
+        GptCommands::Usage { client, since, format } => {
+            usage::report(&gpt_manager.configs_dir, client.as_deref(), &since, &format).await
+        }
+        GptCommands::Service { cmd } => match cmd {
+            service::ServiceCommands::Install {
+                model, port, host, backend, restart, memory_max, cpu_quota, env, system, no_enable,
+            } => {
+                if !gpt_manager.model_exists(&model).await? {
+                    println!("📥 Model '{}' not found, downloading...", model);
+                    gpt_manager.install_model(&model, None, "ollama", None, false).await?;
+                }
+                let spec = service::build_spec(
+                    workspace.root(), &model, &host, port, backend.as_deref(),
+                    &restart, memory_max, cpu_quota, &env, system,
+                )?;
+                service::install(&spec, !no_enable).await
+            }
+            service::ServiceCommands::Status { model, system } => {
+                service::status(&model, system).await
+            }
+            service::ServiceCommands::Remove { model, system } => {
+                service::remove(&model, system).await
+            }
+        },
+        GptCommands::K8s { cmd } => match cmd {
+            k8s::K8sCommands::Generate {
+                model, out, namespace, backend, image, replicas, max_replicas, gpus, helm, stdout,
+            } => {
+                let options = k8s::K8sOptions { namespace, image, replicas, max_replicas, gpus };
+                gpt_manager.generate_k8s(
+                    &model, backend.as_deref(), &options, out.as_deref(), helm, stdout,
+                ).await
+            }
+        },
         _ => {
             println!("Command not yet implemented: {:?}", cmd);
             Ok(())
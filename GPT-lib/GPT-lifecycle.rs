@@ -0,0 +1,565 @@
+//! Model lifecycle: updates, rollback and removal
+//!
+//! Updating compares the installed revision (Ollama manifest digest or Hub
+//! commit) with what the source registry serves for the tracked tag and only
+//! downloads when they differ. The version it replaces is kept - as a
+//! `<tag>-rcm-previous` Ollama tag or under `.rcm/models/.previous/` - until the next
+//! update, so `--rollback` can swap it back. Removing a model stops its running
+//! instance, deletes what it occupies on disk and prunes it from the registry.
+
+use anyhow::{anyhow, Context, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tokio::process::Command as AsyncCommand;
+
+use crate::{hub, gguf, GptManager, ModelConfig, ModelFormat, ServingBackend};
+
+/// Ollama registry serving `ollama pull`
+const OLLAMA_REGISTRY: &str = "https://registry.ollama.ai";
+
+/// Suffix of the Ollama tag the pre-update model is copied to
+const PREVIOUS_TAG: &str = "rcm-previous";
+
+/// A model in `ollama list`
+#[derive(Debug, Clone, PartialEq)]
+pub struct OllamaModel {
+    pub name: String,
+    /// Abbreviated manifest digest
+    pub id: String,
+    pub size: u64,
+}
+
+/// Where a registry entry's updates come from
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum UpdateSource {
+    Ollama,
+    Hub,
+}
+
+/// Model name without its `:tag` or `@revision`
+pub fn base_name(spec: &str) -> &str {
+    spec.split([':', '@']).next().unwrap_or(spec)
+}
+
+/// Ollama's `name:tag` for a registry entry, as `ollama list` prints it
+pub fn ollama_spec(name: &str, version: &str) -> String {
+    if name.contains(':') {
+        name.to_string()
+    } else if version.is_empty() {
+        format!("{}:latest", name)
+    } else {
+        format!("{}:{}", name, version)
+    }
+}
+
+/// Tag keeping the pre-update copy of an Ollama `name:tag`, distinct per tag
+pub fn previous_spec(spec: &str) -> String {
+    match spec.rsplit_once(':') {
+        Some((base, tag)) => format!("{}:{}-{}", base, tag, PREVIOUS_TAG),
+        None => format!("{}:latest-{}", spec, PREVIOUS_TAG),
+    }
+}
+
+/// Rows of `ollama list`
+pub fn parse_ollama_list(output: &str) -> Vec<OllamaModel> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?.to_string();
+            let id = fields.next()?.to_string();
+            let size = parse_size(fields.next()?, fields.next()?)?;
+            Some(OllamaModel { name, id, size })
+        })
+        .collect()
+}
+
+/// Whether two revisions are the same, allowing either to be abbreviated
+pub fn same_revision(a: &str, b: &str) -> bool {
+    let a = a.trim_start_matches("sha256:");
+    let b = b.trim_start_matches("sha256:");
+    let len = a.len().min(b.len());
+    len >= 7 && a[..len].eq_ignore_ascii_case(&b[..len])
+}
+
+fn short_revision(revision: &str) -> &str {
+    let revision = revision.trim_start_matches("sha256:");
+    &revision[..revision.len().min(12)]
+}
+
+/// File-system safe name for per-model state (`org/repo:tag` -> `org--repo--tag`)
+fn state_name(model: &str) -> String {
+    model.replace(['/', ':', '@'], "--")
+}
+
+/// Digest of the manifest the Ollama registry serves for a tag
+async fn ollama_remote_digest(spec: &str) -> Result<String> {
+    let (name, tag) = spec.split_once(':').unwrap_or((spec, "latest"));
+    let repo = if name.contains('/') { name.to_string() } else { format!("library/{}", name) };
+    let url = format!("{}/v2/{}/manifests/{}", OLLAMA_REGISTRY, repo, tag);
+    let response = reqwest::Client::new()
+        .get(&url)
+        .header("Accept", "application/vnd.docker.distribution.manifest.v2+json")
+        .send()
+        .await
+        .with_context(|| format!("Failed to query the Ollama registry for {}", spec))?;
+    match response.status() {
+        s if s.is_success() => {}
+        reqwest::StatusCode::NOT_FOUND => return Err(anyhow!("{} not found in the Ollama registry", spec)),
+        s => return Err(anyhow!("Ollama registry request failed: {}", s)),
+    }
+    // `ollama list` IDs are the leading characters of this digest
+    let manifest = response.bytes().await.context("Failed to read Ollama manifest")?;
+    Ok(format!("{:x}", Sha256::digest(&manifest)))
+}
+
+/// Ollama prints decimal sizes such as `4.7 GB`
+fn parse_size(value: &str, unit: &str) -> Option<u64> {
+    let value: f64 = value.parse().ok()?;
+    let scale = match unit {
+        "B" => 1.0,
+        "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        _ => return None,
+    };
+    Some((value * scale) as u64)
+}
+
+/// Total size of a file or directory tree
+pub fn disk_usage(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| disk_usage(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+impl GptManager {
+    /// Stop, delete and unregister a model; `all_versions` covers every tag of its base name
+    pub async fn remove_model(&mut self, model: &str, all_versions: bool) -> Result<()> {
+        let base = base_name(model);
+        let mut targets: Vec<String> = self.registry.models.keys()
+            .filter(|name| name.as_str() == model || (all_versions && base_name(name) == base))
+            .cloned()
+            .collect();
+        targets.sort();
+
+        // Ollama tags pulled outside RCM are still removed with --all-versions
+        let ollama_models = if self.check_ollama_available().await {
+            self.ollama_models().await.unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let ollama_models: Vec<(String, u64)> = ollama_models.into_iter().map(|m| (m.name, m.size)).collect();
+        let mut ollama_specs: Vec<String> = Vec::new();
+        for name in &targets {
+            let config = &self.registry.models[name];
+            if matches!(config.format, ModelFormat::Ollama) {
+                let spec = ollama_spec(name, &config.version);
+                // The copy kept for rollback goes with the model
+                let kept = previous_spec(&spec);
+                ollama_specs.push(spec);
+                if ollama_models.iter().any(|(name, _)| *name == kept) {
+                    ollama_specs.push(kept);
+                }
+            }
+        }
+        if all_versions {
+            for (name, _) in &ollama_models {
+                if base_name(name) == base && !ollama_specs.contains(name) {
+                    ollama_specs.push(name.clone());
+                }
+            }
+        }
+
+        if targets.is_empty() && ollama_specs.is_empty() {
+            return Err(anyhow!("Model '{}' is not installed", model));
+        }
+
+        let mut reclaimed = 0u64;
+        for name in &targets {
+            self.stop_instance(name).await?;
+
+            let config = self.registry.models[name].clone();
+            if !matches!(config.format, ModelFormat::Ollama) {
+                reclaimed += self.remove_model_files(name, &config.model_path).await?;
+            }
+            reclaimed += self.remove_model_files(name, &self.previous_dir(name)).await?;
+            for state in [
+                self.configs_dir.join("tune").join(format!("{}.json", name)),
+                self.previous_config_path(name),
+            ] {
+                if state.exists() && !self.dry_run() {
+                    let _ = tokio::fs::remove_file(&state).await;
+                }
+            }
+
+            self.registry.models.remove(name);
+            if self.registry.default_model.as_deref() == Some(name.as_str()) {
+                self.registry.default_model = None;
+            }
+        }
+
+        for spec in &ollama_specs {
+            let size = ollama_models.iter().find(|(name, _)| name == spec).map(|(_, size)| *size);
+            let mut cmd = AsyncCommand::new("ollama");
+            cmd.arg("rm").arg(spec);
+            if self.skip_in_dry_run(&cmd) {
+                continue;
+            }
+            let output = cmd.output().await.context("Failed to run ollama rm")?;
+            if output.status.success() {
+                reclaimed += size.unwrap_or(0);
+                println!("🗑️  ollama rm {}", spec);
+            } else {
+                // Already gone from Ollama; the registry entry is still pruned
+                println!("⚠️  ollama rm {}: {}", spec, String::from_utf8_lossy(&output.stderr).trim());
+            }
+        }
+
+        if !targets.is_empty() {
+            self.save_registry().await?;
+        }
+
+        let removed = if targets.is_empty() { ollama_specs.clone() } else { targets };
+        if self.dry_run() {
+            println!("[dry-run] Would remove {}", removed.join(", "));
+        } else {
+            println!("✅ Removed {}; reclaimed {}", removed.join(", "), crate::util::format_bytes(reclaimed));
+        }
+        Ok(())
+    }
+
+    /// Update one model, or every registered model with `all`; `check_only` reports without downloading
+    pub async fn update_models(&mut self, model: Option<&str>, all: bool, check_only: bool) -> Result<()> {
+        let names: Vec<String> = if all {
+            let mut names: Vec<String> = self.registry.models.keys().cloned().collect();
+            names.sort();
+            names
+        } else {
+            let model = model.ok_or_else(|| anyhow!("Specify a model or use --all"))?;
+            vec![model.to_string()]
+        };
+
+        let mut failures = 0;
+        for name in &names {
+            if let Err(e) = self.update_model(name, check_only).await {
+                if !all {
+                    return Err(e);
+                }
+                failures += 1;
+                println!("❌ {}: {:#}", name, e);
+            }
+        }
+        if failures > 0 {
+            return Err(anyhow!("{} of {} model(s) failed to update", failures, names.len()));
+        }
+        Ok(())
+    }
+
+    async fn update_model(&mut self, name: &str, check_only: bool) -> Result<()> {
+        let mut config = self.registry.models.get(name).cloned()
+            .ok_or_else(|| anyhow!("Model '{}' is not installed", name))?;
+        let Some(source) = self.update_source(&config) else {
+            println!("⏭️  {}: not installed from Ollama or Hugging Face; skipping", name);
+            return Ok(());
+        };
+
+        let (installed, latest) = match source {
+            UpdateSource::Ollama => {
+                let spec = ollama_spec(name, &config.version);
+                let installed = self.ollama_models().await.ok()
+                    .and_then(|models| models.into_iter().find(|m| m.name == spec).map(|m| m.id))
+                    .or_else(|| config.revision.clone());
+                (installed, ollama_remote_digest(&spec).await?)
+            }
+            UpdateSource::Hub => {
+                let latest = hub::HubClient::new().await?.resolve_revision(name, &config.version).await?;
+                (config.revision.clone(), latest)
+            }
+        };
+
+        if let Some(installed) = &installed {
+            if same_revision(installed, &latest) {
+                println!("✅ {}: up to date ({})", name, short_revision(installed));
+                return Ok(());
+            }
+        }
+        println!(
+            "⬆️  {}: {} -> {} ({})",
+            name,
+            installed.as_deref().map(short_revision).unwrap_or("unknown"),
+            short_revision(&latest),
+            config.version
+        );
+        if check_only {
+            return Ok(());
+        }
+
+        self.save_previous_config(&config).await?;
+        match source {
+            UpdateSource::Ollama => self.update_ollama(name, &config, installed.is_some()).await?,
+            UpdateSource::Hub => self.update_hub(name, &config, &latest).await?,
+        }
+        if self.dry_run() {
+            return Ok(());
+        }
+
+        config.revision = Some(latest);
+        self.registry.models.insert(name.to_string(), config);
+        self.save_registry().await?;
+        if self.registry.active_models.contains_key(name) {
+            println!("ℹ️  '{}' is running; restart it with `rcm gpt serve {} --deploy` to load the update", name, name);
+        }
+        println!("✅ Updated '{}'; `rcm gpt update {} --rollback` restores the previous version", name, name);
+        Ok(())
+    }
+
+    /// Swap a model back to the version its last update replaced
+    pub async fn rollback_model(&mut self, name: &str) -> Result<()> {
+        let current = self.registry.models.get(name).cloned()
+            .ok_or_else(|| anyhow!("Model '{}' is not installed", name))?;
+        let content = tokio::fs::read_to_string(self.previous_config_path(name)).await
+            .map_err(|_| anyhow!("No previous version of '{}' is kept; nothing to roll back", name))?;
+        let previous: ModelConfig = serde_json::from_str(&content)
+            .context("Invalid previous model config")?;
+
+        match self.update_source(&current) {
+            Some(UpdateSource::Ollama) => {
+                let spec = ollama_spec(name, &previous.version);
+                let kept = previous_spec(&spec);
+                // Swap through a scratch tag so rolling back twice rolls forward again
+                let scratch = format!("{}-swap", kept);
+                self.run_ollama(&["cp", &spec, &scratch]).await?;
+                self.run_ollama(&["cp", &kept, &spec]).await?;
+                self.run_ollama(&["cp", &scratch, &kept]).await?;
+                self.run_ollama(&["rm", &scratch]).await?;
+            }
+            Some(UpdateSource::Hub) => {
+                let previous_dir = self.previous_dir(name);
+                if !previous_dir.exists() {
+                    return Err(anyhow!("Previous files for '{}' are missing ({})", name, previous_dir.display()));
+                }
+                if self.dry_run() {
+                    println!("[dry-run] swap {} and {}", current.model_path.display(), previous_dir.display());
+                } else {
+                    let scratch = previous_dir.with_file_name(format!("{}.swap", state_name(name)));
+                    tokio::fs::rename(&current.model_path, &scratch).await?;
+                    tokio::fs::rename(&previous_dir, &current.model_path).await?;
+                    tokio::fs::rename(&scratch, &previous_dir).await?;
+                }
+            }
+            None => return Err(anyhow!("'{}' was not installed from Ollama or Hugging Face", name)),
+        }
+        if self.dry_run() {
+            return Ok(());
+        }
+
+        self.save_previous_config(&current).await?;
+        let restored = previous.revision.clone().unwrap_or_else(|| previous.version.clone());
+        self.registry.models.insert(name.to_string(), previous);
+        self.save_registry().await?;
+        println!("⏪ Rolled '{}' back to {}", name, short_revision(&restored));
+        Ok(())
+    }
+
+    fn update_source(&self, config: &ModelConfig) -> Option<UpdateSource> {
+        match (&config.format, &config.backend) {
+            (_, ServingBackend::Remote(_)) => None,
+            // Converted models are rebuilt from their source with `rcm gpt convert --force`
+            _ if config.lineage.is_some() => None,
+            (ModelFormat::Ollama, _) => Some(UpdateSource::Ollama),
+            // Hub downloads land in `.rcm/models/<org>/<repo>`
+            _ if config.name.contains('/') && config.model_path.starts_with(&self.models_dir) => Some(UpdateSource::Hub),
+            _ => None,
+        }
+    }
+
+    /// Keep the installed tag as `<name>:<tag>-rcm-previous`, then pull the new one
+    async fn update_ollama(&self, name: &str, config: &ModelConfig, keep_previous: bool) -> Result<()> {
+        let spec = ollama_spec(name, &config.version);
+        if keep_previous {
+            self.run_ollama(&["cp", &spec, &previous_spec(&spec)]).await?;
+        }
+        self.run_ollama(&["pull", &spec]).await
+    }
+
+    /// Move the installed files aside and download the new commit in their place
+    async fn update_hub(&self, name: &str, config: &ModelConfig, commit: &str) -> Result<()> {
+        let model_dir = &config.model_path;
+        let previous_dir = self.previous_dir(name);
+        // Fetch the same GGUF quantization that is installed now
+        let include = gguf::find_gguf_file(model_dir)
+            .and_then(|path| path.file_name().map(|n| n.to_string_lossy().to_string()));
+
+        if self.dry_run() {
+            println!("[dry-run] move {} to {}", model_dir.display(), previous_dir.display());
+            println!("[dry-run] download {}@{} into {}", name, commit, model_dir.display());
+            return Ok(());
+        }
+
+        if previous_dir.exists() {
+            tokio::fs::remove_dir_all(&previous_dir).await
+                .with_context(|| format!("Failed to remove {}", previous_dir.display()))?;
+        }
+        if let Some(parent) = previous_dir.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        if model_dir.exists() {
+            tokio::fs::rename(model_dir, &previous_dir).await
+                .with_context(|| format!("Failed to move {} aside", model_dir.display()))?;
+        }
+
+        if let Err(e) = hub::download_repo(name, commit, model_dir, include.as_deref()).await {
+            // Put the working version back rather than leave a partial download
+            let _ = tokio::fs::remove_dir_all(model_dir).await;
+            if previous_dir.exists() {
+                tokio::fs::rename(&previous_dir, model_dir).await?;
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    async fn run_ollama(&self, args: &[&str]) -> Result<()> {
+        let mut cmd = AsyncCommand::new("ollama");
+        cmd.args(args);
+        if self.skip_in_dry_run(&cmd) {
+            return Ok(());
+        }
+        let status = cmd.status().await.context("Failed to run ollama")?;
+        if !status.success() {
+            return Err(anyhow!("ollama {} failed", args.join(" ")));
+        }
+        Ok(())
+    }
+
+    fn previous_dir(&self, name: &str) -> PathBuf {
+        self.models_dir.join(".previous").join(state_name(name))
+    }
+
+    fn previous_config_path(&self, name: &str) -> PathBuf {
+        self.configs_dir.join("previous").join(format!("{}.json", state_name(name)))
+    }
+
+    async fn save_previous_config(&self, config: &ModelConfig) -> Result<()> {
+        if self.dry_run() {
+            return Ok(());
+        }
+        let path = self.previous_config_path(&config.name);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, serde_json::to_string_pretty(config)?).await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Kill a model's backend processes, replicas included, and drop it from the active set
+    pub(crate) async fn stop_instance(&mut self, name: &str) -> Result<()> {
+        let Some(instance) = self.registry.active_models.get(name) else {
+            return Ok(());
+        };
+        let pids: Vec<u32> = instance.process_id.into_iter()
+            .chain(instance.replicas.iter().filter_map(|r| r.process_id))
+            .collect();
+        for pid in pids {
+            let mut cmd = AsyncCommand::new("kill");
+            cmd.arg(pid.to_string());
+            if !self.skip_in_dry_run(&cmd) && Self::process_alive(pid).await {
+                cmd.output().await.context("Failed to stop model process")?;
+                println!("🛑 Stopped '{}' (pid {})", name, pid);
+            }
+        }
+        if !self.dry_run() {
+            self.registry.active_models.remove(name);
+            let data = serde_json::json!({ "name": name });
+            crate::webhooks::emit(&self.workspace_root, crate::webhooks::MODEL_STOPPED, data).await;
+        }
+        Ok(())
+    }
+
+    /// Delete files RCM downloaded; models configured from elsewhere are left in place
+    async fn remove_model_files(&self, name: &str, path: &Path) -> Result<u64> {
+        if !path.exists() {
+            return Ok(0);
+        }
+        // Compare resolved paths: `..` or a symlink in the registry must not reach outside
+        let inside = match (path.canonicalize(), self.models_dir.canonicalize()) {
+            (Ok(path), Ok(models_dir)) => path.starts_with(&models_dir) && path != models_dir,
+            _ => false,
+        };
+        if !inside {
+            println!("ℹ️  Leaving '{}' files at {} (outside {})", name, path.display(), self.models_dir.display());
+            return Ok(0);
+        }
+
+        let size = disk_usage(path);
+        if self.dry_run() {
+            println!("[dry-run] remove {} ({})", path.display(), crate::util::format_bytes(size));
+            return Ok(0);
+        }
+        if path.is_dir() {
+            tokio::fs::remove_dir_all(path).await
+        } else {
+            tokio::fs::remove_file(path).await
+        }
+        .with_context(|| format!("Failed to remove {}", path.display()))?;
+        println!("🗑️  Deleted {} ({})", path.display(), crate::util::format_bytes(size));
+        Ok(size)
+    }
+
+    /// Models Ollama has on disk
+    pub(crate) async fn ollama_models(&self) -> Result<Vec<OllamaModel>> {
+        let output = AsyncCommand::new("ollama").arg("list").output().await
+            .context("Failed to run ollama list")?;
+        if !output.status.success() {
+            return Err(anyhow!("ollama list failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(parse_ollama_list(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_and_sizes() {
+        assert_eq!(base_name("llama3:8b"), "llama3");
+        assert_eq!(base_name("TheBloke/Mistral-7B-GGUF@main"), "TheBloke/Mistral-7B-GGUF");
+        assert_eq!(base_name("phi3"), "phi3");
+        assert_eq!(ollama_spec("llama3", "8b"), "llama3:8b");
+        assert_eq!(ollama_spec("llama3", ""), "llama3:latest");
+        assert_eq!(ollama_spec("llama3:70b", "latest"), "llama3:70b");
+        assert_eq!(previous_spec("llama3:8b"), "llama3:8b-rcm-previous");
+        assert_ne!(previous_spec("llama3:8b"), previous_spec("llama3:70b"));
+
+        let list = "NAME            ID              SIZE      MODIFIED\n\
+                    llama3:latest   365c0bd3c000    4.7 GB    2 days ago\n\
+                    llama3:70b      786f3184aec0    39 GB     3 weeks ago\n\
+                    phi3:mini       4f2222927938    2.2 GB    5 weeks ago\n";
+        let models = parse_ollama_list(list);
+        assert_eq!(models.len(), 3);
+        assert_eq!(models[0].name, "llama3:latest");
+        assert_eq!(models[0].size, 4_700_000_000);
+        assert_eq!(models[1].size, 39_000_000_000);
+    }
+
+    #[test]
+    fn test_revisions() {
+        let digest = "365c0bd3c000a25d28ddbf732fe1c6add414de7275464c4e4d1c3b5fcb5d8ad1";
+        assert!(same_revision("365c0bd3c000", digest));
+        assert!(same_revision(&format!("sha256:{}", digest), digest));
+        assert!(!same_revision("786f3184aec0", digest));
+        assert!(!same_revision("", digest));
+        assert_eq!(short_revision(digest), "365c0bd3c000");
+        assert_eq!(state_name("TheBloke/Mistral-7B-GGUF"), "TheBloke--Mistral-7B-GGUF");
+    }
+}
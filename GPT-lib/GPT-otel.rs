@@ -0,0 +1,122 @@
+//! OpenTelemetry spans for model traffic
+//!
+//! Generation calls and gateway requests get spans named and attributed after
+//! the GenAI semantic conventions; RCM exports them when a collector is
+//! configured (see `telemetry` in the RCM config).
+
+use serde_json::Value;
+use tracing::field::Empty;
+use tracing::Span;
+use crate::safety;
+use crate::telemetry;
+use crate::ServingBackend;
+
+/// `gen_ai.system` for a backend
+pub fn system(backend: &ServingBackend) -> &'static str {
+    match backend {
+        ServingBackend::Ollama => "ollama",
+        ServingBackend::LlamaCpp => "llama.cpp",
+        ServingBackend::Vllm => "vllm",
+        ServingBackend::Onnx => "onnx",
+        ServingBackend::Candle => "candle",
+        ServingBackend::TorchServe => "torchserve",
+        ServingBackend::TensorFlowServing => "tensorflow_serving",
+        ServingBackend::Remote(_) => "openai_compatible",
+        ServingBackend::Custom(_) => "custom",
+    }
+}
+
+/// `gen_ai.operation.name` for a proxied OpenAI path
+pub fn operation(path: &str) -> &'static str {
+    match path.trim_start_matches("/v1") {
+        "/chat/completions" => "chat",
+        "/embeddings" => "embeddings",
+        _ => "text_completion",
+    }
+}
+
+/// A client span for one generation against `model`
+pub fn generation_span(model: &str, backend: &ServingBackend, max_tokens: usize, temperature: f32) -> Span {
+    tracing::info_span!(
+        "gen_ai.generate",
+        otel.name = %format_args!("text_completion {}", model),
+        otel.kind = "client",
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+        gen_ai.operation.name = "text_completion",
+        gen_ai.system = system(backend),
+        gen_ai.request.model = model,
+        gen_ai.request.max_tokens = max_tokens as u64,
+        gen_ai.request.temperature = temperature as f64,
+        gen_ai.usage.input_tokens = Empty,
+        gen_ai.usage.output_tokens = Empty,
+        gen_ai.prompt = Empty,
+        gen_ai.completion = Empty,
+        server.address = Empty,
+        error.type = Empty,
+    )
+}
+
+/// A server span for one gateway request, as the root of its own trace
+pub fn gateway_span(peer: &std::net::SocketAddr) -> Span {
+    tracing::info_span!(
+        parent: None,
+        "gateway request",
+        otel.name = "gateway request",
+        otel.kind = "server",
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+        client.address = %peer.ip(),
+        http.request.method = Empty,
+        url.path = Empty,
+        http.response.status_code = Empty,
+        rcm.client = Empty,
+        rcm.content_filter = Empty,
+        gen_ai.operation.name = Empty,
+        gen_ai.system = Empty,
+        gen_ai.request.model = Empty,
+        gen_ai.usage.input_tokens = Empty,
+        gen_ai.usage.output_tokens = Empty,
+        gen_ai.prompt = Empty,
+        gen_ai.completion = Empty,
+        server.address = Empty,
+        error.type = Empty,
+    )
+}
+
+pub fn record_usage(span: &Span, input_tokens: Option<u64>, output_tokens: Option<u64>) {
+    if let Some(tokens) = input_tokens {
+        span.record("gen_ai.usage.input_tokens", tokens);
+    }
+    if let Some(tokens) = output_tokens {
+        span.record("gen_ai.usage.output_tokens", tokens);
+    }
+}
+
+/// Prompt text of an OpenAI request, when content capture is on
+pub fn record_prompt(span: &Span, body: &mut Value) {
+    if telemetry::capture_content() {
+        let texts: Vec<String> = safety::prompt_texts(body).into_iter().map(|t| t.clone()).collect();
+        telemetry::record_content(span, "gen_ai.prompt", &texts.join("\n\n"));
+    }
+}
+
+/// Completion text of an OpenAI response, when content capture is on
+pub fn record_completion(span: &Span, response: &mut Value) {
+    if telemetry::capture_content() {
+        let texts: Vec<String> = safety::completion_texts(response).into_iter().map(|t| t.clone()).collect();
+        telemetry::record_content(span, "gen_ai.completion", &texts.join("\n\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operation() {
+        assert_eq!(operation("/v1/chat/completions"), "chat");
+        assert_eq!(operation("/v1/completions"), "text_completion");
+        assert_eq!(operation("/v1/embeddings"), "embeddings");
+    }
+}
@@ -0,0 +1,487 @@
+//! Prompt templates and presets (`rcm gpt prompt`)
+//!
+//! Named templates live in `.rcm/gpt-configs/prompts/<name>.toml`; a few
+//! built-ins are always available and a file of the same name overrides them.
+//! Placeholders are `{{name}}`; `{{name | contents}}` inlines the file the
+//! variable points at. A template may also preset the model, system prompt,
+//! token budget and temperature used when it is run.
+//!
+//! ```toml
+//! description = "Review a source file"
+//! template = "Review {{file}} for bugs:\n\n{{file | contents}}"
+//! max_tokens = 800
+//!
+//! [variables]
+//! focus = "correctness"
+//! ```
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use crate::GptManager;
+
+/// Largest file `{{var | contents}}` will inline
+const MAX_INLINE_BYTES: u64 = 256 * 1024;
+
+#[derive(Subcommand, Debug)]
+pub enum PromptCommands {
+    /// Save a named prompt template
+    Add {
+        /// Template name
+        name: String,
+        /// Template text with {{variable}} placeholders
+        #[arg(long, required_unless_present = "file", conflicts_with = "file")]
+        template: Option<String>,
+        /// Read the template text from a file
+        #[arg(long)]
+        file: Option<PathBuf>,
+        /// One-line description shown in `prompt list`
+        #[arg(long)]
+        description: Option<String>,
+        /// System prompt sent with the template
+        #[arg(long)]
+        system: Option<String>,
+        /// Default model for `prompt run`
+        #[arg(long)]
+        model: Option<String>,
+        /// Default token budget
+        #[arg(long)]
+        max_tokens: Option<usize>,
+        /// Default temperature
+        #[arg(long)]
+        temperature: Option<f32>,
+        /// Default variable values (key=value)
+        #[arg(long = "var")]
+        vars: Vec<String>,
+        /// Replace an existing template
+        #[arg(long)]
+        force: bool,
+    },
+    /// List saved and built-in templates
+    List {
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    /// Print a template with its variables and presets
+    Show {
+        name: String,
+    },
+    /// Delete a saved template
+    Remove {
+        name: String,
+    },
+    /// Render a template and send it to a model
+    Run {
+        /// Template name
+        name: String,
+        /// Model (defaults to the template's preset)
+        #[arg(long)]
+        model: Option<String>,
+        /// Variable values (key=value)
+        #[arg(long = "var")]
+        vars: Vec<String>,
+        /// Maximum tokens (defaults to the template's preset, then 512)
+        #[arg(long)]
+        max_tokens: Option<usize>,
+        /// Temperature (defaults to the template's preset, then 0.7)
+        #[arg(long)]
+        temperature: Option<f32>,
+        /// Print the rendered prompt instead of sending it
+        #[arg(long)]
+        render_only: bool,
+    },
+}
+
+/// A named prompt with its presets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    #[serde(default)]
+    pub description: Option<String>,
+    pub template: String,
+    #[serde(default)]
+    pub system: Option<String>,
+    /// Default values; variables without one must be passed with `--var`
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub max_tokens: Option<usize>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+}
+
+impl PromptTemplate {
+    fn new(template: &str, description: &str) -> Self {
+        Self {
+            description: Some(description.to_string()),
+            template: template.to_string(),
+            system: None,
+            variables: BTreeMap::new(),
+            model: None,
+            max_tokens: None,
+            temperature: None,
+        }
+    }
+}
+
+/// Templates available in every workspace
+pub fn builtin() -> BTreeMap<&'static str, PromptTemplate> {
+    BTreeMap::from([
+        ("code-review", PromptTemplate {
+            max_tokens: Some(800),
+            ..PromptTemplate::new(
+                "Review the following file ({{file}}) for bugs, unclear code and missing error handling. \
+                 List concrete issues with line references, most important first.\n\n{{file | contents}}",
+                "Review a source file",
+            )
+        }),
+        ("explain", PromptTemplate::new(
+            "Explain what this code in {{file}} does, for a developer new to the project.\n\n{{file | contents}}",
+            "Explain a source file",
+        )),
+        ("summarize", PromptTemplate {
+            variables: BTreeMap::from([("length".to_string(), "five bullet points".to_string())]),
+            ..PromptTemplate::new("Summarize the following text in {{length}}.\n\n{{text}}", "Summarize text")
+        }),
+    ])
+}
+
+/// A placeholder found in a template
+#[derive(Debug, PartialEq)]
+struct Placeholder<'a> {
+    /// Byte range of `{{ ... }}` in the template
+    start: usize,
+    end: usize,
+    variable: &'a str,
+    contents: bool,
+}
+
+fn placeholders(template: &str) -> Result<Vec<Placeholder<'_>>> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(open) = template[offset..].find("{{") {
+        let start = offset + open;
+        let close = template[start..].find("}}")
+            .ok_or_else(|| anyhow!("Unclosed '{{{{' at byte {}", start))?;
+        let end = start + close + 2;
+        let inner = &template[start + 2..end - 2];
+        let (variable, filter) = match inner.split_once('|') {
+            Some((variable, filter)) => (variable.trim(), Some(filter.trim())),
+            None => (inner.trim(), None),
+        };
+        if variable.is_empty() {
+            return Err(anyhow!("Empty placeholder at byte {}", start));
+        }
+        let contents = match filter {
+            None => false,
+            Some("contents") => true,
+            Some(other) => return Err(anyhow!("Unknown filter '{}' on {{{{{}}}}}; only 'contents' is supported", other, variable)),
+        };
+        found.push(Placeholder { start, end, variable, contents });
+        offset = end;
+    }
+    Ok(found)
+}
+
+/// Variables a template uses, in order of first appearance
+pub fn variables(template: &str) -> Result<Vec<String>> {
+    let mut names: Vec<String> = Vec::new();
+    for placeholder in placeholders(template)? {
+        if !names.iter().any(|n| n == placeholder.variable) {
+            names.push(placeholder.variable.to_string());
+        }
+    }
+    Ok(names)
+}
+
+/// Parse `key=value` arguments
+pub fn parse_vars(vars: &[String]) -> Result<BTreeMap<String, String>> {
+    vars.iter()
+        .map(|var| {
+            let (key, value) = var.split_once('=')
+                .ok_or_else(|| anyhow!("Invalid --var '{}'. Use key=value", var))?;
+            Ok((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Fill in placeholders; `read` loads files for `| contents`
+pub fn render(template: &str, values: &BTreeMap<String, String>, read: impl Fn(&str) -> Result<String>) -> Result<String> {
+    let missing: Vec<String> = variables(template)?.into_iter()
+        .filter(|v| !values.contains_key(v))
+        .collect();
+    if !missing.is_empty() {
+        return Err(anyhow!("Missing template variables: {}. Pass them with --var name=value", missing.join(", ")));
+    }
+
+    let found = placeholders(template)?;
+    let mut rendered = String::with_capacity(template.len());
+    let mut last = 0;
+    for placeholder in found {
+        rendered.push_str(&template[last..placeholder.start]);
+        let value = &values[placeholder.variable];
+        if placeholder.contents {
+            rendered.push_str(&read(value)?);
+        } else {
+            rendered.push_str(value);
+        }
+        last = placeholder.end;
+    }
+    rendered.push_str(&template[last..]);
+    Ok(rendered)
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow!("Invalid template name '{}'. Use letters, digits, '-' and '_'", name))
+    }
+}
+
+impl GptManager {
+    fn prompts_dir(&self) -> PathBuf {
+        self.configs_dir.join("prompts")
+    }
+
+    /// A saved template, falling back to the built-ins
+    pub async fn load_prompt(&self, name: &str) -> Result<PromptTemplate> {
+        validate_name(name)?;
+        let path = self.prompts_dir().join(format!("{}.toml", name));
+        match fs::read_to_string(&path).await {
+            Ok(content) => toml::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", path.display())),
+            Err(_) => builtin().remove(name)
+                .ok_or_else(|| anyhow!("No prompt template '{}'. See 'rcm gpt prompt list'", name)),
+        }
+    }
+
+    async fn saved_prompts(&self) -> Result<BTreeMap<String, PromptTemplate>> {
+        let mut saved = BTreeMap::new();
+        let Ok(mut entries) = fs::read_dir(self.prompts_dir()).await else {
+            return Ok(saved);
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()).filter(|_| path.extension().is_some_and(|e| e == "toml")) else {
+                continue;
+            };
+            match fs::read_to_string(&path).await.map_err(anyhow::Error::from).and_then(|c| Ok(toml::from_str(&c)?)) {
+                Ok(template) => {
+                    saved.insert(name.to_string(), template);
+                }
+                Err(e) => tracing::warn!("Skipping unreadable template {}: {}", path.display(), e),
+            }
+        }
+        Ok(saved)
+    }
+
+    /// Render a template with `--var` values over its defaults
+    pub async fn render_prompt(&self, name: &str, vars: &[String]) -> Result<(PromptTemplate, String)> {
+        let template = self.load_prompt(name).await?;
+        let mut values = template.variables.clone();
+        values.extend(parse_vars(vars)?);
+        let root = self.workspace_root.clone();
+        let rendered = render(&template.template, &values, |file| read_inline(&root, file))?;
+        Ok((template, rendered))
+    }
+
+    async fn add_prompt(&self, name: &str, template: PromptTemplate, force: bool) -> Result<()> {
+        validate_name(name)?;
+        variables(&template.template)?;
+        let path = self.prompts_dir().join(format!("{}.toml", name));
+        if path.exists() && !force {
+            return Err(anyhow!("Template '{}' already exists. Use --force to replace it", name));
+        }
+        if self.dry_run() {
+            println!("[dry-run] Would write {}", path.display());
+            return Ok(());
+        }
+        fs::create_dir_all(self.prompts_dir()).await?;
+        fs::write(&path, toml::to_string_pretty(&template)?).await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("✅ Saved prompt template '{}' ({})", name, path.display());
+        Ok(())
+    }
+
+    async fn list_prompts(&self, format: &str) -> Result<()> {
+        let saved = self.saved_prompts().await?;
+        let mut all: BTreeMap<String, (PromptTemplate, &str)> = builtin().into_iter()
+            .map(|(name, template)| (name.to_string(), (template, "built-in")))
+            .collect();
+        all.extend(saved.into_iter().map(|(name, template)| (name, (template, "saved"))));
+
+        match format {
+            "json" => {
+                let json: BTreeMap<&String, &PromptTemplate> = all.iter().map(|(name, (t, _))| (name, t)).collect();
+                println!("{}", serde_json::to_string_pretty(&json)?);
+                Ok(())
+            }
+            "table" => {
+                use tabled::{Table, Tabled};
+
+                #[derive(Tabled)]
+                struct PromptRow {
+                    #[tabled(rename = "Name")]
+                    name: String,
+                    #[tabled(rename = "Source")]
+                    source: String,
+                    #[tabled(rename = "Variables")]
+                    variables: String,
+                    #[tabled(rename = "Description")]
+                    description: String,
+                }
+
+                let rows = all.iter().map(|(name, (template, source))| PromptRow {
+                    name: name.clone(),
+                    source: source.to_string(),
+                    variables: variables(&template.template).map(|v| v.join(", ")).unwrap_or_else(|e| e.to_string()),
+                    description: template.description.clone().unwrap_or_default(),
+                });
+                println!("{}", Table::new(rows));
+                Ok(())
+            }
+            _ => Err(anyhow!("Unsupported format: {}", format)),
+        }
+    }
+
+    async fn remove_prompt(&self, name: &str) -> Result<()> {
+        validate_name(name)?;
+        let path = self.prompts_dir().join(format!("{}.toml", name));
+        if !path.exists() {
+            let hint = if builtin().contains_key(name) { " (built-in templates cannot be removed)" } else { "" };
+            return Err(anyhow!("No saved template '{}'{}", name, hint));
+        }
+        if self.dry_run() {
+            println!("[dry-run] Would remove {}", path.display());
+            return Ok(());
+        }
+        fs::remove_file(&path).await?;
+        println!("🗑️ Removed prompt template '{}'", name);
+        Ok(())
+    }
+}
+
+/// Read a file for `{{var | contents}}`, relative to the workspace
+///
+/// The path is resolved through symlinks and `..` and must stay inside the
+/// workspace, so a template variable can't pull in `~/.ssh` or `/etc`.
+fn read_inline(root: &Path, file: &str) -> Result<String> {
+    let root = root.canonicalize()
+        .with_context(|| format!("Cannot resolve workspace {}", root.display()))?;
+    let path = root.join(file).canonicalize()
+        .with_context(|| format!("Cannot read {} for the template", file))?;
+    if !path.starts_with(&root) {
+        return Err(anyhow!("{} is outside the workspace; templates only inline workspace files", file));
+    }
+    let size = std::fs::metadata(&path)
+        .with_context(|| format!("Cannot read {} for the template", path.display()))?
+        .len();
+    if size > MAX_INLINE_BYTES {
+        return Err(anyhow!("{} is {} bytes; templates inline at most {}", file, size, MAX_INLINE_BYTES));
+    }
+    std::fs::read_to_string(&path).with_context(|| format!("{} is not a UTF-8 text file", path.display()))
+}
+
+/// Handle `rcm gpt prompt`
+pub async fn handle_command(manager: &GptManager, cmd: PromptCommands) -> Result<()> {
+    match cmd {
+        PromptCommands::Add { name, template, file, description, system, model, max_tokens, temperature, vars, force } => {
+            let text = match (template, file) {
+                (Some(text), _) => text,
+                (None, Some(file)) => fs::read_to_string(&file).await
+                    .with_context(|| format!("Failed to read {}", file.display()))?,
+                (None, None) => return Err(anyhow!("Pass --template or --file")),
+            };
+            let template = PromptTemplate {
+                description,
+                template: text,
+                system,
+                variables: parse_vars(&vars)?,
+                model,
+                max_tokens,
+                temperature,
+            };
+            manager.add_prompt(&name, template, force).await
+        }
+        PromptCommands::List { format } => manager.list_prompts(&format).await,
+        PromptCommands::Show { name } => {
+            let template = manager.load_prompt(&name).await?;
+            println!("{}", toml::to_string_pretty(&template)?);
+            println!("# variables: {}", variables(&template.template)?.join(", "));
+            Ok(())
+        }
+        PromptCommands::Remove { name } => manager.remove_prompt(&name).await,
+        PromptCommands::Run { name, model, vars, max_tokens, temperature, render_only } => {
+            let (template, rendered) = manager.render_prompt(&name, &vars).await?;
+            if render_only {
+                println!("{}", rendered);
+                return Ok(());
+            }
+            let model = model.or(template.model)
+                .ok_or_else(|| anyhow!("Template '{}' has no preset model; pass --model", name))?;
+            let prompt = match &template.system {
+                Some(system) => format!("{}\n\n{}", system, rendered),
+                None => rendered,
+            };
+            let result = manager.generate_text(
+                &model,
+                &prompt,
+                max_tokens.or(template.max_tokens).unwrap_or(512),
+                temperature.or(template.temperature).unwrap_or(0.7),
+            ).await?;
+            println!("{}", result);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let template = "Review {{file}} ({{ focus }}):\n{{file | contents}}";
+        assert_eq!(variables(template).unwrap(), vec!["file", "focus"]);
+
+        let values = parse_vars(&["file=src/main.rs".to_string(), "focus=a=b".to_string()]).unwrap();
+        let rendered = render(template, &values, |f| Ok(format!("<{}>", f))).unwrap();
+        assert_eq!(rendered, "Review src/main.rs (a=b):\n<src/main.rs>");
+
+        let err = render(template, &BTreeMap::new(), |_| Ok(String::new())).unwrap_err();
+        assert!(err.to_string().contains("file, focus"));
+        assert!(placeholders("{{x | upper}}").is_err());
+        assert!(placeholders("{{unclosed").is_err());
+        assert!(parse_vars(&["novalue".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_read_inline_stays_in_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("ws");
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(root.join("docs/notes.md"), "notes").unwrap();
+        std::fs::write(dir.path().join("outside.txt"), "secret").unwrap();
+
+        assert_eq!(read_inline(&root, "docs/notes.md").unwrap(), "notes");
+        assert_eq!(read_inline(&root, "docs/../docs/notes.md").unwrap(), "notes");
+        assert!(read_inline(&root, "../outside.txt").is_err());
+        assert!(read_inline(&root, dir.path().join("outside.txt").to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_builtin_templates_parse() {
+        for (name, template) in builtin() {
+            assert!(variables(&template.template).is_ok(), "{}", name);
+            let text = toml::to_string_pretty(&template).unwrap();
+            assert_eq!(toml::from_str::<PromptTemplate>(&text).unwrap(), template);
+        }
+    }
+}
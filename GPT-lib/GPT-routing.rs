@@ -0,0 +1,231 @@
+//! Request routing across replicas of one served model
+//!
+//! A model deployed with `serving_config.replicas > 1` runs one backend
+//! process per replica on consecutive ports. Each request goes to a replica
+//! picked by `serving_config.routing`:
+//!
+//! - `round_robin` keeps a per-model cursor in `gpt-configs/routing.json`, so
+//!   separate CLI invocations spread out as well as calls within one chat.
+//! - `least_loaded` asks every replica how busy it is (llama-server `/slots`,
+//!   vLLM `/metrics`) and prefers the idlest, then the fastest to answer.
+//!
+//! Replicas that cannot be reached are skipped and the next one is tried.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use crate::{GptManager, ModelInstance, ServingBackend};
+
+/// How long a load probe may take before the replica counts as unreachable
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+const CURSOR_FILE: &str = "routing.json";
+
+/// How requests are spread over a model's replicas
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutingPolicy {
+    #[default]
+    RoundRobin,
+    LeastLoaded,
+}
+
+impl std::str::FromStr for RoutingPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.replace('-', "_").as_str() {
+            "round_robin" => Ok(Self::RoundRobin),
+            "least_loaded" => Ok(Self::LeastLoaded),
+            _ => Err(anyhow!("Unknown routing policy: {}. Use round-robin or least-loaded", s)),
+        }
+    }
+}
+
+/// A backend process serving the same model as the primary instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replica {
+    pub process_id: Option<u32>,
+    pub endpoint: String,
+}
+
+/// Backends whose server can run several times side by side on different ports
+pub fn supports_replicas(backend: &ServingBackend) -> bool {
+    matches!(backend, ServingBackend::Ollama | ServingBackend::LlamaCpp | ServingBackend::Vllm)
+}
+
+/// Every endpoint serving the instance, primary first
+pub fn endpoints(instance: &ModelInstance) -> Vec<String> {
+    std::iter::once(instance.endpoint.clone())
+        .chain(instance.replicas.iter().map(|r| r.endpoint.clone()))
+        .collect()
+}
+
+/// Start the list at `cursor`, wrapping around
+pub fn rotate(mut endpoints: Vec<String>, cursor: usize) -> Vec<String> {
+    if !endpoints.is_empty() {
+        let len = endpoints.len();
+        endpoints.rotate_left(cursor % len);
+    }
+    endpoints
+}
+
+/// How busy one replica said it was
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplicaLoad {
+    pub endpoint: String,
+    pub busy: u32,
+    pub latency: Duration,
+}
+
+/// Idlest first, then fastest to answer
+pub fn rank(mut loads: Vec<ReplicaLoad>) -> Vec<String> {
+    loads.sort_by_key(|l| (l.busy, l.latency));
+    loads.into_iter().map(|l| l.endpoint).collect()
+}
+
+/// Busy slots in llama-server's `/slots` (`is_processing`, or `state != 0` on older builds)
+pub fn busy_slots(slots: &Value) -> Option<u32> {
+    let slots = slots.as_array()?;
+    let busy = slots
+        .iter()
+        .filter(|slot| match slot.get("is_processing").and_then(Value::as_bool) {
+            Some(processing) => processing,
+            None => slot.get("state").and_then(Value::as_u64).is_some_and(|state| state != 0),
+        })
+        .count();
+    Some(busy as u32)
+}
+
+/// Requests in flight from vLLM's Prometheus `/metrics`
+pub fn running_requests(metrics: &str) -> Option<u32> {
+    let running: Vec<f64> = metrics
+        .lines()
+        .filter(|line| line.starts_with("vllm:num_requests_running"))
+        .filter_map(|line| line.rsplit(' ').next()?.parse().ok())
+        .collect();
+    (!running.is_empty()).then(|| running.iter().sum::<f64>() as u32)
+}
+
+/// Ask one replica how busy it is; `None` when it does not answer
+async fn probe(client: reqwest::Client, endpoint: String, backend: ServingBackend) -> Option<ReplicaLoad> {
+    let started = Instant::now();
+    // vLLM endpoints are registered with their `/v1` suffix
+    let base = endpoint.trim_end_matches('/').trim_end_matches("/v1").to_string();
+    let busy = match backend {
+        ServingBackend::LlamaCpp => {
+            match client.get(format!("{}/slots", base)).send().await.ok()?.json::<Value>().await {
+                Ok(slots) => busy_slots(&slots).unwrap_or(0),
+                // `/slots` is disabled by default on newer builds; `/health` still proves it is up
+                Err(_) => {
+                    client.get(format!("{}/health", base)).send().await.ok()?.error_for_status().ok()?;
+                    0
+                }
+            }
+        }
+        ServingBackend::Vllm => {
+            let metrics = client.get(format!("{}/metrics", base)).send().await.ok()?.text().await.ok()?;
+            running_requests(&metrics).unwrap_or(0)
+        }
+        // Ollama does not report in-flight requests; answer time is the only signal
+        _ => {
+            client.get(format!("{}/api/ps", base)).send().await.ok()?.error_for_status().ok()?;
+            0
+        }
+    };
+    Some(ReplicaLoad { endpoint, busy, latency: started.elapsed() })
+}
+
+/// Whether an error means the replica could not be reached at all
+pub fn is_unreachable(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<reqwest::Error>())
+        .any(|e| e.is_connect() || e.is_timeout())
+}
+
+impl GptManager {
+    /// Endpoints to try for one request, most preferred first
+    pub(crate) async fn route(&self, instance: &ModelInstance) -> Result<Vec<String>> {
+        let endpoints = endpoints(instance);
+        if endpoints.len() == 1 {
+            return Ok(endpoints);
+        }
+        match instance.config.serving_config.routing {
+            RoutingPolicy::RoundRobin => {
+                let cursor = self.next_cursor(&instance.config.name).await;
+                Ok(rotate(endpoints, cursor))
+            }
+            RoutingPolicy::LeastLoaded => {
+                let client = reqwest::Client::builder().timeout(PROBE_TIMEOUT).build()?;
+                let mut probes = tokio::task::JoinSet::new();
+                for endpoint in endpoints {
+                    probes.spawn(probe(client.clone(), endpoint, instance.config.backend.clone()));
+                }
+                let mut loads = Vec::new();
+                while let Some(load) = probes.join_next().await {
+                    loads.extend(load.ok().flatten());
+                }
+                if loads.is_empty() {
+                    return Err(anyhow!("No replica of '{}' is reachable", instance.config.name));
+                }
+                Ok(rank(loads))
+            }
+        }
+    }
+
+    /// Advance the model's round-robin cursor; concurrent callers may share a slot
+    async fn next_cursor(&self, model: &str) -> usize {
+        let path = self.configs_dir.join(CURSOR_FILE);
+        let mut cursors: HashMap<String, usize> = tokio::fs::read_to_string(&path)
+            .await
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        let cursor = cursors.get(model).copied().unwrap_or(0);
+        cursors.insert(model.to_string(), cursor.wrapping_add(1));
+        if !self.dry_run() {
+            if let Ok(content) = serde_json::to_string_pretty(&cursors) {
+                let _ = tokio::fs::write(&path, content).await;
+            }
+        }
+        cursor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_and_rank() {
+        let endpoints = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert_eq!(rotate(endpoints.clone(), 4), vec!["b", "c", "a"]);
+        assert_eq!(rotate(Vec::new(), 3), Vec::<String>::new());
+
+        let load = |endpoint: &str, busy, ms| ReplicaLoad {
+            endpoint: endpoint.to_string(),
+            busy,
+            latency: Duration::from_millis(ms),
+        };
+        assert_eq!(rank(vec![load("a", 2, 1), load("b", 0, 40), load("c", 0, 5)]), vec!["c", "b", "a"]);
+        assert_eq!("least-loaded".parse::<RoutingPolicy>().unwrap(), RoutingPolicy::LeastLoaded);
+        assert!("random".parse::<RoutingPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_load_signals() {
+        let slots = serde_json::json!([{ "id": 0, "is_processing": true }, { "id": 1, "is_processing": false }]);
+        assert_eq!(busy_slots(&slots), Some(1));
+        let legacy = serde_json::json!([{ "id": 0, "state": 1 }, { "id": 1, "state": 1 }, { "id": 2, "state": 0 }]);
+        assert_eq!(busy_slots(&legacy), Some(2));
+
+        let metrics = "# HELP vllm:num_requests_running Number of requests running\n\
+                       vllm:num_requests_running{model_name=\"m\"} 3.0\n\
+                       vllm:num_requests_waiting{model_name=\"m\"} 7.0\n";
+        assert_eq!(running_requests(metrics), Some(3));
+        assert_eq!(running_requests("up 1"), None);
+    }
+}
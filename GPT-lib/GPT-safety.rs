@@ -0,0 +1,428 @@
+//! Content filters for the model gateway
+//!
+//! Rules live under `safety` in `gpt-configs/gateway.json`, keyed by model name
+//! (`*` applies to every model), and run in order on prompts, completions or
+//! both:
+//!
+//! ```json
+//! "safety": {
+//!   "*": [
+//!     { "name": "secrets", "type": "regex", "pattern": "AKIA[0-9A-Z]{16}", "action": "redact" },
+//!     { "name": "guard", "type": "classifier", "model": "llama-guard3:1b", "stage": "prompt", "action": "block" }
+//!   ],
+//!   "llama3": [
+//!     { "name": "codenames", "type": "blocklist", "words": ["bluebird"], "action": "log" }
+//!   ]
+//! }
+//! ```
+//!
+//! A `classifier` is a served model that answers `safe` or `unsafe` (Llama
+//! Guard's convention). Tool call arguments and tool descriptions are screened
+//! along with message content. Every match is appended to
+//! `gpt-configs/safety.jsonl` with the rule name but not the matched text.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use tokio::io::AsyncWriteExt;
+use crate::GptManager;
+
+pub const EVENTS_FILE: &str = "safety.jsonl";
+
+/// Replaces redacted matches
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Which side of a request a rule checks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Prompt,
+    Completion,
+    #[default]
+    Both,
+}
+
+impl Stage {
+    fn covers(self, stage: Stage) -> bool {
+        self == Stage::Both || self == stage
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Refuse the prompt, or withhold the completion
+    Block,
+    /// Replace matches with `[REDACTED]`
+    Redact,
+    /// Let it through and record the match
+    Log,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FilterKind {
+    Regex {
+        pattern: String,
+    },
+    /// Whole words or phrases, matched case-insensitively
+    Blocklist {
+        words: Vec<String>,
+    },
+    /// A served model that answers `safe` or `unsafe`
+    Classifier {
+        model: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilterRule {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: FilterKind,
+    #[serde(default)]
+    pub stage: Stage,
+    pub action: Action,
+}
+
+enum Matcher<'a> {
+    Pattern(Regex),
+    Classifier(&'a str),
+}
+
+/// Compiled patterns by source and case sensitivity; the gateway reloads its config per request
+static COMPILED: OnceLock<Mutex<HashMap<(String, bool), Regex>>> = OnceLock::new();
+
+/// Compile `pattern`, reusing an earlier compilation of the same pattern
+fn compile(pattern: &str, case_insensitive: bool) -> Result<Regex, regex::Error> {
+    let cache = COMPILED.get_or_init(Default::default);
+    let key = (pattern.to_string(), case_insensitive);
+    if let Some(regex) = cache.lock().unwrap().get(&key) {
+        return Ok(regex.clone());
+    }
+    let regex = RegexBuilder::new(pattern).case_insensitive(case_insensitive).build()?;
+    cache.lock().unwrap().insert(key, regex.clone());
+    Ok(regex)
+}
+
+/// Pattern for a blocklist entry; `\b` only applies next to word characters, so `c++` still matches
+fn whole_word(word: &str) -> String {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let start = if is_word(word.chars().next()) { r"\b" } else { "" };
+    let end = if is_word(word.chars().last()) { r"\b" } else { "" };
+    format!("{}{}{}", start, regex::escape(word), end)
+}
+
+impl FilterRule {
+    fn matcher(&self) -> Result<Matcher<'_>> {
+        match &self.kind {
+            FilterKind::Regex { pattern } => Ok(Matcher::Pattern(
+                compile(pattern, false).with_context(|| format!("Invalid pattern in safety rule '{}'", self.name))?,
+            )),
+            FilterKind::Blocklist { words } => {
+                let words: Vec<String> = words.iter().filter(|w| !w.trim().is_empty()).map(|w| whole_word(w.trim())).collect();
+                if words.is_empty() {
+                    return Err(anyhow!("Safety rule '{}' has an empty blocklist", self.name));
+                }
+                Ok(Matcher::Pattern(compile(&format!("(?:{})", words.join("|")), true)?))
+            }
+            FilterKind::Classifier { model } if self.action == Action::Redact => Err(anyhow!(
+                "Safety rule '{}': a classifier ({}) can block or log, not redact", self.name, model
+            )),
+            FilterKind::Classifier { model } => Ok(Matcher::Classifier(model)),
+        }
+    }
+}
+
+/// Rules for `model` and for every model, as configured
+pub fn rules_for<'a>(safety: &'a BTreeMap<String, Vec<FilterRule>>, model: &str) -> Vec<&'a FilterRule> {
+    safety.get("*").into_iter().chain(safety.get(model)).flatten().collect()
+}
+
+/// Check every rule compiles, so mistakes surface at startup instead of per request
+pub fn validate(safety: &BTreeMap<String, Vec<FilterRule>>) -> Result<()> {
+    for rule in safety.values().flatten() {
+        rule.matcher()?;
+    }
+    Ok(())
+}
+
+/// Whether any rule checks `stage`
+pub fn checks(rules: &[&FilterRule], stage: Stage) -> bool {
+    rules.iter().any(|rule| rule.stage.covers(stage))
+}
+
+/// A rule that matched
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SafetyEvent {
+    pub timestamp: DateTime<Utc>,
+    pub client: String,
+    pub model: String,
+    pub rule: String,
+    pub stage: Stage,
+    pub action: Action,
+    /// Pattern matches, or 1 for a classifier verdict
+    pub matches: usize,
+}
+
+/// What screening found
+#[derive(Debug, Default)]
+pub struct Screening {
+    /// Name of the rule that blocked, if any
+    pub blocked: Option<String>,
+    /// (rule, action, matches) for every rule that matched
+    pub hits: Vec<(String, Action, usize)>,
+}
+
+/// Collect the strings in a message `content`, prompt or input (plain, list, or `{ "text": ... }` parts)
+fn collect<'a>(value: &'a mut Value, out: &mut Vec<&'a mut String>) {
+    match value {
+        Value::String(text) => out.push(text),
+        Value::Array(items) => items.iter_mut().for_each(|item| collect(item, out)),
+        Value::Object(part) => {
+            if let Some(text) = part.get_mut("text") {
+                collect(text, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Content and tool call arguments of a chat message
+fn message_texts<'a>(message: &'a mut Value, out: &mut Vec<&'a mut String>) {
+    let Value::Object(fields) = message else { return };
+    for (key, value) in fields.iter_mut() {
+        match key.as_str() {
+            "content" => collect(value, out),
+            "tool_calls" => {
+                for call in value.as_array_mut().into_iter().flatten() {
+                    if let Some(Value::String(arguments)) = call.pointer_mut("/function/arguments") {
+                        out.push(arguments);
+                    }
+                }
+            }
+            // The legacy single-call form
+            "function_call" => {
+                if let Some(Value::String(arguments)) = value.get_mut("arguments") {
+                    out.push(arguments);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Descriptions in tool definitions, which the model reads like prompt text
+fn descriptions<'a>(value: &'a mut Value, out: &mut Vec<&'a mut String>) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(|item| descriptions(item, out)),
+        Value::Object(fields) => {
+            for (key, value) in fields.iter_mut() {
+                match (key.as_str(), value) {
+                    ("description", Value::String(text)) => out.push(text),
+                    (_, value) => descriptions(value, out),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Prompt text of a chat, completion or embeddings request
+pub fn prompt_texts(body: &mut Value) -> Vec<&mut String> {
+    let mut out = Vec::new();
+    if let Value::Object(fields) = body {
+        for (key, value) in fields.iter_mut() {
+            match (key.as_str(), value) {
+                ("messages", Value::Array(messages)) => {
+                    for message in messages.iter_mut() {
+                        message_texts(message, &mut out);
+                    }
+                }
+                ("tools" | "functions", value) => descriptions(value, &mut out),
+                ("prompt" | "input", value) => collect(value, &mut out),
+                _ => {}
+            }
+        }
+    }
+    out
+}
+
+/// Generated text of a chat or completion response
+pub fn completion_texts(response: &mut Value) -> Vec<&mut String> {
+    let mut out = Vec::new();
+    if let Some(Value::Array(choices)) = response.get_mut("choices") {
+        for choice in choices.iter_mut().filter_map(Value::as_object_mut) {
+            for (key, value) in choice.iter_mut() {
+                match key.as_str() {
+                    "text" => collect(value, &mut out),
+                    "message" => message_texts(value, &mut out),
+                    _ => {}
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Withhold a blocked completion the way OpenAI does: empty text, `content_filter` finish
+pub fn withhold(response: &mut Value) {
+    for text in completion_texts(response) {
+        text.clear();
+    }
+    if let Some(Value::Array(choices)) = response.get_mut("choices") {
+        for choice in choices {
+            choice["finish_reason"] = Value::String("content_filter".to_string());
+        }
+    }
+}
+
+/// Whether a Llama Guard style answer flags the text
+pub fn classifier_flags(answer: &str) -> bool {
+    answer.trim().lines().next().is_some_and(|verdict| verdict.trim().eq_ignore_ascii_case("unsafe"))
+}
+
+/// Run the rules for `stage` over `texts`, redacting in place; stops at the first block
+pub async fn screen(manager: &GptManager, rules: &[&FilterRule], stage: Stage, texts: &mut [&mut String]) -> Result<Screening> {
+    let mut screening = Screening::default();
+    for rule in rules.iter().filter(|rule| rule.stage.covers(stage)) {
+        let matches = match rule.matcher()? {
+            Matcher::Pattern(regex) => {
+                let mut count = 0;
+                for text in texts.iter_mut() {
+                    let found = regex.find_iter(text.as_str()).count();
+                    if found > 0 && rule.action == Action::Redact {
+                        **text = regex.replace_all(text.as_str(), REDACTED).into_owned();
+                    }
+                    count += found;
+                }
+                count
+            }
+            Matcher::Classifier(model) => {
+                let joined: Vec<&str> = texts.iter().map(|t| t.as_str()).collect();
+                let answer = manager.generate_text(model, &joined.join("\n\n"), 16, 0.0).await
+                    .with_context(|| format!("Safety classifier '{}' failed; is it running?", model))?;
+                usize::from(classifier_flags(&answer))
+            }
+        };
+        if matches == 0 {
+            continue;
+        }
+        screening.hits.push((rule.name.clone(), rule.action, matches));
+        if rule.action == Action::Block {
+            screening.blocked = Some(rule.name.clone());
+            break;
+        }
+    }
+    Ok(screening)
+}
+
+pub async fn record_events(configs_dir: &Path, events: &[SafetyEvent]) -> Result<()> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(configs_dir.join(EVENTS_FILE))
+        .await?;
+    let mut lines = String::new();
+    for event in events {
+        lines.push_str(&serde_json::to_string(event)?);
+        lines.push('\n');
+    }
+    file.write_all(lines.as_bytes()).await?;
+    Ok(())
+}
+
+/// A buffered chat or completion response as one server-sent event, for streamed requests
+pub fn as_event_stream(response: &Value) -> String {
+    let mut chunk = response.clone();
+    let is_chat = response.get("object").and_then(Value::as_str) == Some("chat.completion");
+    if is_chat {
+        chunk["object"] = Value::String("chat.completion.chunk".to_string());
+        if let Some(Value::Array(choices)) = chunk.get_mut("choices") {
+            for choice in choices.iter_mut().filter_map(Value::as_object_mut) {
+                if let Some(message) = choice.remove("message") {
+                    choice.insert("delta".to_string(), message);
+                }
+            }
+        }
+    }
+    format!("data: {}\n\ndata: [DONE]\n\n", chunk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(kind: FilterKind, action: Action) -> FilterRule {
+        FilterRule { name: "test".to_string(), kind, stage: Stage::Both, action }
+    }
+
+    #[test]
+    fn test_text_extraction() {
+        let mut chat = json!({
+            "model": "llama3",
+            "messages": [
+                { "role": "system", "content": "be brief" },
+                { "role": "user", "content": [{ "type": "text", "text": "hello" }, { "type": "image_url", "image_url": { "url": "x" } }] }
+            ]
+        });
+        let texts: Vec<String> = prompt_texts(&mut chat).into_iter().map(|t| t.clone()).collect();
+        assert_eq!(texts, vec!["be brief", "hello"]);
+
+        let mut response = json!({ "object": "chat.completion", "choices": [{ "index": 0, "message": { "role": "assistant", "content": "secret" }, "finish_reason": "stop" }] });
+        assert_eq!(completion_texts(&mut response).len(), 1);
+        withhold(&mut response);
+        assert_eq!(response["choices"][0]["message"]["content"], "");
+        assert_eq!(response["choices"][0]["finish_reason"], "content_filter");
+        assert!(as_event_stream(&response).contains("\"delta\""));
+    }
+
+    #[test]
+    fn test_tool_call_extraction() {
+        let mut chat = json!({
+            "messages": [
+                { "role": "assistant", "content": null, "tool_calls": [{ "id": "1", "type": "function", "function": { "name": "lookup", "arguments": "{\"key\":\"AKIA\"}" } }] }
+            ],
+            "tools": [{ "type": "function", "function": { "name": "lookup", "description": "Find a key", "parameters": { "type": "object", "properties": { "key": { "type": "string", "description": "Key id" } } } } }]
+        });
+        let texts: Vec<String> = prompt_texts(&mut chat).into_iter().map(|t| t.clone()).collect();
+        assert_eq!(texts, vec!["{\"key\":\"AKIA\"}", "Find a key", "Key id"]);
+
+        let mut response = json!({ "choices": [{ "message": { "role": "assistant", "tool_calls": [{ "function": { "name": "lookup", "arguments": "{}" } }] } }] });
+        assert_eq!(completion_texts(&mut response).len(), 1);
+    }
+
+    #[test]
+    fn test_matchers() {
+        let blocklist = rule(FilterKind::Blocklist { words: vec!["Blue Bird".to_string()] }, Action::Log);
+        let Matcher::Pattern(regex) = blocklist.matcher().unwrap() else { panic!("expected a pattern") };
+        assert!(regex.is_match("the blue bird flies"));
+        assert!(!regex.is_match("bluebirds"));
+
+        let symbols = rule(FilterKind::Blocklist { words: vec!["C++".to_string(), "#secret".to_string()] }, Action::Log);
+        let Matcher::Pattern(regex) = symbols.matcher().unwrap() else { panic!("expected a pattern") };
+        assert!(regex.is_match("written in c++ today"));
+        assert!(regex.is_match("tagged #secret."));
+        assert!(!regex.is_match("#secrets"));
+
+        assert!(rule(FilterKind::Regex { pattern: "(".to_string() }, Action::Block).matcher().is_err());
+        assert!(rule(FilterKind::Classifier { model: "guard".to_string() }, Action::Redact).matcher().is_err());
+        assert!(classifier_flags("unsafe\nS1"));
+        assert!(!classifier_flags("safe"));
+
+        let mut safety = BTreeMap::new();
+        safety.insert("*".to_string(), vec![blocklist.clone()]);
+        safety.insert("llama3".to_string(), vec![blocklist]);
+        assert_eq!(rules_for(&safety, "llama3").len(), 2);
+        assert_eq!(rules_for(&safety, "mistral").len(), 1);
+    }
+}
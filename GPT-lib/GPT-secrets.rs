@@ -0,0 +1,87 @@
+//! Secret resolution for served model processes
+//!
+//! Resolves `secret:<name>` values in `ServingConfig::env` (and inherited
+//! environment) from the same secret backend used by `rcm secret`
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tokio::process::Command as AsyncCommand;
+
+/// Prefix marking an environment value as a keychain reference
+pub const SECRET_PREFIX: &str = "secret:";
+
+fn redaction_set() -> &'static Mutex<Vec<String>> {
+    static VALUES: OnceLock<Mutex<Vec<String>>> = OnceLock::new();
+    VALUES.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Mask resolved secret values in `text`
+pub fn redact(text: &str) -> String {
+    let mut output = text.to_string();
+    if let Ok(values) = redaction_set().lock() {
+        for value in values.iter() {
+            output = output.replace(value.as_str(), "[REDACTED]");
+        }
+    }
+    output
+}
+
+fn remember(value: &str) {
+    if value.len() >= 4 {
+        if let Ok(mut values) = redaction_set().lock() {
+            if !values.iter().any(|v| v == value) {
+                values.push(value.to_string());
+                values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+            }
+        }
+    }
+}
+
+/// Look up a secret the same way `rcm secret` does (env override, then the configured backend)
+pub async fn get_secret(name: &str) -> Result<String> {
+    let value = crate::secrets::get_secret(name).await?;
+    remember(&value);
+    Ok(value)
+}
+
+/// Resolve a single value, passing non-references through
+pub async fn resolve_value(value: &str) -> Result<String> {
+    match value.strip_prefix(SECRET_PREFIX).map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => get_secret(name).await,
+        None => Ok(value.to_string()),
+    }
+}
+
+/// Set resolved environment on a backend process
+///
+/// Covers `ServingConfig::env` plus inherited variables holding `secret:` references,
+/// so service units can carry references instead of values.
+pub async fn apply_env(cmd: &mut AsyncCommand, env: &HashMap<String, String>) -> Result<()> {
+    for (key, value) in std::env::vars() {
+        if value.starts_with(SECRET_PREFIX) && !env.contains_key(&key) {
+            cmd.env(&key, resolve_value(&value).await
+                .with_context(|| format!("Failed to resolve {}", key))?);
+        }
+    }
+
+    for (key, value) in env {
+        cmd.env(key, resolve_value(value).await
+            .with_context(|| format!("Failed to resolve {}", key))?);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve_value() {
+        std::env::set_var("RCM_SECRET_GPT_UNIT_TEST", "resolved-value");
+        assert_eq!(resolve_value("secret:gpt-unit-test").await.unwrap(), "resolved-value");
+        assert_eq!(resolve_value("plain").await.unwrap(), "plain");
+        assert_eq!(redact("x resolved-value y"), "x [REDACTED] y");
+    }
+}
@@ -0,0 +1,563 @@
+//! System service generation for persistent model serving
+//!
+//! Generates and registers a systemd unit (Linux), launchd agent (macOS) or
+//! Windows service that runs `rcm gpt serve --foreground` for a model
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::process::Command as AsyncCommand;
+
+/// `rcm gpt service` subcommands
+#[derive(Subcommand)]
+pub enum ServiceCommands {
+    /// Generate and enable a service that keeps a model served
+    Install {
+        /// Model name
+        model: String,
+        /// Port to serve on
+        #[arg(long, default_value = "11434")]
+        port: u16,
+        /// Host to bind to
+        #[arg(long, default_value = "localhost")]
+        host: String,
+        /// Serving backend (auto-selected when omitted)
+        #[arg(long)]
+        backend: Option<String>,
+        /// Restart policy (always, on-failure, never)
+        #[arg(long, default_value = "on-failure")]
+        restart: String,
+        /// Memory limit (e.g. 8G, 512M)
+        #[arg(long)]
+        memory_max: Option<String>,
+        /// CPU quota as a percentage of one core (e.g. 400 for four cores)
+        #[arg(long)]
+        cpu_quota: Option<u32>,
+        /// Environment variables for the service (KEY=VALUE)
+        #[arg(long = "env", value_name = "KEY=VALUE", num_args = 0.., action = clap::ArgAction::Append)]
+        env: Vec<String>,
+        /// Install as a system-wide service instead of a per-user one
+        #[arg(long)]
+        system: bool,
+        /// Write the service definition without enabling it
+        #[arg(long)]
+        no_enable: bool,
+    },
+    /// Show service status
+    Status {
+        /// Model name
+        model: String,
+        /// Query the system-wide service
+        #[arg(long)]
+        system: bool,
+    },
+    /// Stop, disable and delete a model service
+    Remove {
+        /// Model name
+        model: String,
+        /// Remove the system-wide service
+        #[arg(long)]
+        system: bool,
+    },
+}
+
+/// Restart behaviour shared by all service managers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RestartPolicy {
+    Always,
+    OnFailure,
+    Never,
+}
+
+impl RestartPolicy {
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "always" => Ok(Self::Always),
+            "on-failure" | "onfailure" => Ok(Self::OnFailure),
+            "never" | "no" => Ok(Self::Never),
+            _ => Err(anyhow!("Unknown restart policy: {}. Use always, on-failure or never", s)),
+        }
+    }
+
+    fn systemd(&self) -> &'static str {
+        match self {
+            Self::Always => "always",
+            Self::OnFailure => "on-failure",
+            Self::Never => "no",
+        }
+    }
+}
+
+/// Everything needed to render a service definition
+#[derive(Debug, Clone)]
+pub struct ServiceSpec {
+    pub model: String,
+    pub executable: PathBuf,
+    pub args: Vec<String>,
+    pub working_dir: PathBuf,
+    pub restart: RestartPolicy,
+    pub memory_max: Option<String>,
+    pub cpu_quota: Option<u32>,
+    pub env: HashMap<String, String>,
+    pub system: bool,
+}
+
+impl ServiceSpec {
+    /// Service identifier derived from the model name
+    pub fn name(&self) -> String {
+        service_name(&self.model)
+    }
+}
+
+/// Stable service name for a model (`rcm-gpt-<model>`)
+pub fn service_name(model: &str) -> String {
+    let sanitized: String = model
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    format!("rcm-gpt-{}", sanitized.trim_matches('-'))
+}
+
+/// Render a systemd unit file
+pub fn render_systemd_unit(spec: &ServiceSpec) -> String {
+    let mut unit = String::new();
+    unit.push_str("[Unit]\n");
+    unit.push_str(&format!("Description=RCM GPT model server ({})\n", spec.model));
+    unit.push_str("After=network-online.target\n");
+    unit.push_str("Wants=network-online.target\n\n");
+
+    unit.push_str("[Service]\n");
+    unit.push_str("Type=simple\n");
+    unit.push_str(&format!("WorkingDirectory={}\n", spec.working_dir.display()));
+    unit.push_str(&format!(
+        "ExecStart={} {}\n",
+        spec.executable.display(),
+        spec.args.iter().map(|a| systemd_quote(a)).collect::<Vec<_>>().join(" ")
+    ));
+    unit.push_str(&format!("Restart={}\n", spec.restart.systemd()));
+    unit.push_str("RestartSec=5\n");
+
+    let mut env: Vec<_> = spec.env.iter().collect();
+    env.sort();
+    for (key, value) in env {
+        unit.push_str(&format!("Environment={}\n", systemd_quote(&format!("{}={}", key, value))));
+    }
+
+    if let Some(memory) = &spec.memory_max {
+        unit.push_str(&format!("MemoryMax={}\n", memory));
+    }
+    if let Some(quota) = spec.cpu_quota {
+        unit.push_str(&format!("CPUQuota={}%\n", quota));
+    }
+
+    unit.push_str("\n[Install]\n");
+    unit.push_str(if spec.system { "WantedBy=multi-user.target\n" } else { "WantedBy=default.target\n" });
+    unit
+}
+
+/// Render a launchd property list
+pub fn render_launchd_plist(spec: &ServiceSpec) -> String {
+    let mut args = vec![spec.executable.display().to_string()];
+    args.extend(spec.args.iter().cloned());
+
+    let program_args = args
+        .iter()
+        .map(|a| format!("        <string>{}</string>", xml_escape(a)))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let keep_alive = match spec.restart {
+        RestartPolicy::Always => "    <key>KeepAlive</key>\n    <true/>\n".to_string(),
+        RestartPolicy::OnFailure => "    <key>KeepAlive</key>\n    <dict>\n        <key>SuccessfulExit</key>\n        <false/>\n    </dict>\n".to_string(),
+        RestartPolicy::Never => "    <key>KeepAlive</key>\n    <false/>\n".to_string(),
+    };
+
+    let mut env: Vec<_> = spec.env.iter().collect();
+    env.sort();
+    let env_entries = env
+        .iter()
+        .map(|(k, v)| format!("        <key>{}</key>\n        <string>{}</string>", xml_escape(k), xml_escape(v)))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let limits = spec
+        .memory_max
+        .as_deref()
+        .and_then(parse_size_bytes)
+        .map(|bytes| format!(
+            "    <key>HardResourceLimits</key>\n    <dict>\n        <key>ResidentSetSize</key>\n        <integer>{}</integer>\n    </dict>\n",
+            bytes
+        ))
+        .unwrap_or_default();
+
+    let log_dir = spec.working_dir.join(".rcm").join("logs");
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{program_args}
+    </array>
+    <key>WorkingDirectory</key>
+    <string>{working_dir}</string>
+    <key>RunAtLoad</key>
+    <true/>
+{keep_alive}    <key>EnvironmentVariables</key>
+    <dict>
+{env_entries}
+    </dict>
+{limits}    <key>StandardOutPath</key>
+    <string>{stdout}</string>
+    <key>StandardErrorPath</key>
+    <string>{stderr}</string>
+</dict>
+</plist>
+"#,
+        label = launchd_label(&spec.model),
+        program_args = program_args,
+        working_dir = xml_escape(&spec.working_dir.display().to_string()),
+        keep_alive = keep_alive,
+        env_entries = env_entries,
+        limits = limits,
+        stdout = xml_escape(&log_dir.join(format!("{}.out.log", spec.name())).display().to_string()),
+        stderr = xml_escape(&log_dir.join(format!("{}.err.log", spec.name())).display().to_string()),
+    )
+}
+
+/// Install and (optionally) enable a service
+pub async fn install(spec: &ServiceSpec, enable: bool) -> Result<()> {
+    if cfg!(target_os = "linux") {
+        install_systemd(spec, enable).await
+    } else if cfg!(target_os = "macos") {
+        install_launchd(spec, enable).await
+    } else if cfg!(target_os = "windows") {
+        install_windows(spec, enable).await
+    } else {
+        Err(anyhow!("Service installation is not supported on {}", std::env::consts::OS))
+    }
+}
+
+/// Print service status
+pub async fn status(model: &str, system: bool) -> Result<()> {
+    let name = service_name(model);
+
+    let mut cmd = if cfg!(target_os = "linux") {
+        let mut c = AsyncCommand::new("systemctl");
+        if !system {
+            c.arg("--user");
+        }
+        c.args(["status", "--no-pager", &format!("{}.service", name)]);
+        c
+    } else if cfg!(target_os = "macos") {
+        let mut c = AsyncCommand::new("launchctl");
+        c.args(["list", &launchd_label(model)]);
+        c
+    } else if cfg!(target_os = "windows") {
+        let mut c = AsyncCommand::new("sc.exe");
+        c.args(["query", &name]);
+        c
+    } else {
+        return Err(anyhow!("Service management is not supported on {}", std::env::consts::OS));
+    };
+
+    let output = cmd.output().await
+        .context("Failed to query service manager")?;
+
+    print!("{}", String::from_utf8_lossy(&output.stdout));
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stderr.trim().is_empty() {
+            eprintln!("{}", stderr.trim());
+        }
+        println!("⚠️ Service '{}' is not running or not installed", name);
+    }
+
+    Ok(())
+}
+
+/// Stop, disable and delete a service
+pub async fn remove(model: &str, system: bool) -> Result<()> {
+    let name = service_name(model);
+
+    if cfg!(target_os = "linux") {
+        let unit = format!("{}.service", name);
+        let _ = systemctl(system, &["disable", "--now", &unit]).await;
+        let path = systemd_unit_dir(system)?.join(&unit);
+        if path.exists() {
+            fs::remove_file(&path).await
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+        systemctl(system, &["daemon-reload"]).await?;
+    } else if cfg!(target_os = "macos") {
+        let path = launchd_plist_path(model, system)?;
+        if path.exists() {
+            let _ = run("launchctl", &["unload", "-w", &path.display().to_string()]).await;
+            fs::remove_file(&path).await
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+    } else if cfg!(target_os = "windows") {
+        let _ = run("sc.exe", &["stop", &name]).await;
+        run("sc.exe", &["delete", &name]).await?;
+    } else {
+        return Err(anyhow!("Service management is not supported on {}", std::env::consts::OS));
+    }
+
+    println!("🗑️ Service '{}' removed", name);
+    Ok(())
+}
+
+async fn install_systemd(spec: &ServiceSpec, enable: bool) -> Result<()> {
+    let dir = systemd_unit_dir(spec.system)?;
+    fs::create_dir_all(&dir).await
+        .context("Failed to create systemd unit directory")?;
+
+    let unit = format!("{}.service", spec.name());
+    let path = dir.join(&unit);
+    fs::write(&path, render_systemd_unit(spec)).await
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("📄 Wrote {}", path.display());
+
+    systemctl(spec.system, &["daemon-reload"]).await?;
+    if enable {
+        systemctl(spec.system, &["enable", "--now", &unit]).await?;
+        println!("✅ Service '{}' enabled and started", spec.name());
+    }
+
+    Ok(())
+}
+
+async fn install_launchd(spec: &ServiceSpec, enable: bool) -> Result<()> {
+    let path = launchd_plist_path(&spec.model, spec.system)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await
+            .context("Failed to create LaunchAgents directory")?;
+    }
+    fs::create_dir_all(spec.working_dir.join(".rcm").join("logs")).await?;
+
+    if spec.cpu_quota.is_some() {
+        println!("⚠️ launchd does not support CPU quotas; --cpu-quota ignored");
+    }
+
+    fs::write(&path, render_launchd_plist(spec)).await
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("📄 Wrote {}", path.display());
+
+    if enable {
+        run("launchctl", &["load", "-w", &path.display().to_string()]).await?;
+        println!("✅ Service '{}' loaded", launchd_label(&spec.model));
+    }
+
+    Ok(())
+}
+
+async fn install_windows(spec: &ServiceSpec, enable: bool) -> Result<()> {
+    if !spec.env.is_empty() || spec.memory_max.is_some() || spec.cpu_quota.is_some() {
+        println!("⚠️ Windows services do not support --env, --memory-max or --cpu-quota; set them on the host instead");
+    }
+
+    let bin_path = format!(
+        "\"{}\" {}",
+        spec.executable.display(),
+        spec.args.iter().map(|a| format!("\"{}\"", a)).collect::<Vec<_>>().join(" ")
+    );
+    let name = spec.name();
+    let start = if enable { "auto" } else { "demand" };
+
+    run("sc.exe", &["create", &name, "binPath=", &bin_path, "start=", start,
+                    "DisplayName=", &format!("RCM GPT model server ({})", spec.model)]).await?;
+
+    if spec.restart != RestartPolicy::Never {
+        run("sc.exe", &["failure", &name, "reset=", "86400", "actions=", "restart/5000/restart/5000/restart/5000"]).await?;
+    }
+
+    if enable {
+        run("sc.exe", &["start", &name]).await?;
+        println!("✅ Service '{}' created and started", name);
+    } else {
+        println!("✅ Service '{}' created", name);
+    }
+
+    Ok(())
+}
+
+fn systemd_unit_dir(system: bool) -> Result<PathBuf> {
+    if system {
+        Ok(PathBuf::from("/etc/systemd/system"))
+    } else {
+        dirs::config_dir()
+            .map(|d| d.join("systemd").join("user"))
+            .ok_or_else(|| anyhow!("Could not determine user config directory"))
+    }
+}
+
+fn launchd_label(model: &str) -> String {
+    format!("com.rcm.gpt.{}", service_name(model).trim_start_matches("rcm-gpt-"))
+}
+
+fn launchd_plist_path(model: &str, system: bool) -> Result<PathBuf> {
+    let dir = if system {
+        PathBuf::from("/Library/LaunchDaemons")
+    } else {
+        dirs::home_dir()
+            .map(|h| h.join("Library").join("LaunchAgents"))
+            .ok_or_else(|| anyhow!("Could not determine home directory"))?
+    };
+    Ok(dir.join(format!("{}.plist", launchd_label(model))))
+}
+
+async fn systemctl(system: bool, args: &[&str]) -> Result<()> {
+    let mut full = Vec::new();
+    if !system {
+        full.push("--user");
+    }
+    full.extend_from_slice(args);
+    run("systemctl", &full).await
+}
+
+async fn run(command: &str, args: &[&str]) -> Result<()> {
+    let output = AsyncCommand::new(command)
+        .args(args)
+        .output()
+        .await
+        .with_context(|| format!("Failed to execute {}", command))?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} {} failed: {}",
+            command,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
+
+fn systemd_quote(value: &str) -> String {
+    if value.chars().any(|c| c.is_whitespace() || c == '"' || c == '\\') {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Parse sizes like "512M" or "8G" into bytes
+fn parse_size_bytes(size: &str) -> Option<u64> {
+    let size = size.trim();
+    let (number, unit) = size.split_at(size.find(|c: char| !c.is_ascii_digit()).unwrap_or(size.len()));
+    let number: u64 = number.parse().ok()?;
+    let multiplier = match unit.to_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1024,
+        "M" | "MB" => 1024 * 1024,
+        "G" | "GB" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    Some(number * multiplier)
+}
+
+/// Build the spec used by `rcm gpt service install`
+pub fn build_spec(
+    workspace_root: &Path,
+    model: &str,
+    host: &str,
+    port: u16,
+    backend: Option<&str>,
+    restart: &str,
+    memory_max: Option<String>,
+    cpu_quota: Option<u32>,
+    env: &[String],
+    system: bool,
+) -> Result<ServiceSpec> {
+    let executable = std::env::current_exe()
+        .context("Failed to locate the rcm executable")?;
+
+    let mut args = vec![
+        "gpt".to_string(), "serve".to_string(), model.to_string(),
+        "--deploy".to_string(), "--foreground".to_string(),
+        "--host".to_string(), host.to_string(),
+        "--port".to_string(), port.to_string(),
+    ];
+    if let Some(backend) = backend {
+        args.push("--backend".to_string());
+        args.push(backend.to_string());
+    }
+
+    let mut env_vars = HashMap::new();
+    for entry in env {
+        let (key, value) = entry
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Invalid environment variable (expected KEY=VALUE): {}", entry))?;
+        env_vars.insert(key.to_string(), value.to_string());
+    }
+
+    Ok(ServiceSpec {
+        model: model.to_string(),
+        executable,
+        args,
+        working_dir: workspace_root.to_path_buf(),
+        restart: RestartPolicy::from_str(restart)?,
+        memory_max,
+        cpu_quota,
+        env: env_vars,
+        system,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> ServiceSpec {
+        ServiceSpec {
+            model: "llama2:13b".to_string(),
+            executable: PathBuf::from("/usr/local/bin/rcm"),
+            args: vec!["gpt".into(), "serve".into(), "llama2:13b".into(), "--foreground".into()],
+            working_dir: PathBuf::from("/srv/app"),
+            restart: RestartPolicy::Always,
+            memory_max: Some("8G".to_string()),
+            cpu_quota: Some(400),
+            env: HashMap::from([("OLLAMA_KEEP_ALIVE".to_string(), "24h".to_string())]),
+            system: false,
+        }
+    }
+
+    #[test]
+    fn test_service_name() {
+        assert_eq!(service_name("llama2:13b"), "rcm-gpt-llama2-13b");
+        assert_eq!(service_name("TheBloke/Mistral"), "rcm-gpt-thebloke-mistral");
+    }
+
+    #[test]
+    fn test_systemd_unit() {
+        let unit = render_systemd_unit(&spec());
+        assert!(unit.contains("ExecStart=/usr/local/bin/rcm gpt serve llama2:13b --foreground"));
+        assert!(unit.contains("Restart=always"));
+        assert!(unit.contains("MemoryMax=8G"));
+        assert!(unit.contains("CPUQuota=400%"));
+        assert!(unit.contains("Environment=OLLAMA_KEEP_ALIVE=24h"));
+        assert!(unit.contains("WantedBy=default.target"));
+    }
+
+    #[test]
+    fn test_launchd_plist() {
+        let plist = render_launchd_plist(&spec());
+        assert!(plist.contains("<string>com.rcm.gpt.llama2-13b</string>"));
+        assert!(plist.contains("<integer>8589934592</integer>"));
+    }
+}
@@ -0,0 +1,656 @@
+//! Team model registry (`rcm gpt registry serve`)
+//!
+//! Serves the workspace's file-backed models (Hub downloads, conversions and
+//! quantizations) over HTTP, so teammates can run `rcm gpt install <model>
+//! --source http://buildbox:9000` instead of downloading and converting them
+//! again. Models Ollama pulled live in Ollama's own store and aren't shared.
+//!
+//! Every request except `/health` needs `Authorization: Bearer <token>`. The
+//! token is the `gpt-registry` secret, created on first start; clients store
+//! it with `rcm auth login <host>` and only send it over https (or to loopback),
+//! so put a TLS proxy in front of a registry shared across machines.
+//!
+//! - `GET /v1/models` lists manifests
+//! - `GET /v1/models/<name>` is one manifest: settings plus files with size and SHA-256
+//! - `GET /v1/models/<name>/files/<path>` streams a file, honouring `Range: bytes=N-`
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use tabled::{Table, Tabled};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use crate::auth::Credential;
+use crate::download_cache::DownloadCache;
+use crate::{GptManager, ModelConfig, ModelFormat, ModelParameters, ServingBackend, ServingConfig};
+
+/// Secret holding the server's bearer token
+pub const TOKEN_SECRET: &str = "gpt-registry";
+
+pub const DEFAULT_PORT: u16 = 9000;
+
+/// Requests with larger headers are rejected
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+
+/// File digests by path, size and mtime, so restarts don't rehash every model
+const DIGEST_CACHE: &str = "registry-digests.json";
+
+#[derive(Subcommand)]
+pub enum RegistryCommands {
+    /// Serve this workspace's models to teammates over HTTP
+    Serve {
+        /// Address to bind
+        #[arg(long, default_value = "0.0.0.0")]
+        host: String,
+        #[arg(long, default_value_t = DEFAULT_PORT)]
+        port: u16,
+        /// Only share these models
+        #[arg(long, value_delimiter = ',')]
+        models: Option<Vec<String>>,
+        /// Serve without a token (trusted networks only)
+        #[arg(long)]
+        no_auth: bool,
+    },
+    /// List the models a registry serves
+    Ls {
+        /// Registry URL, e.g. http://buildbox:9000
+        source: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// What a client needs to install a model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelManifest {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub revision: Option<String>,
+    pub format: ModelFormat,
+    pub backend: ServingBackend,
+    pub parameters: ModelParameters,
+    /// File the model is served from; empty when it is the whole directory
+    #[serde(default)]
+    pub entry: String,
+    pub files: Vec<ManifestFile>,
+}
+
+impl ModelManifest {
+    pub fn size(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+}
+
+/// A request path, decoded
+#[derive(Debug, PartialEq)]
+pub enum Route {
+    Health,
+    List,
+    Manifest(String),
+    File(String, String),
+    NotFound,
+}
+
+/// Escape a model name for one path segment (Hub names contain `/`)
+pub fn encode_name(name: &str) -> String {
+    let mut out = String::new();
+    for byte in name.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b':' => out.push(byte as char),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+fn decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%' && i + 2 < bytes.len())
+            .then(|| std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()))
+            .flatten();
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+pub fn route(path: &str) -> Route {
+    let path = path.split('?').next().unwrap_or_default();
+    match path {
+        "/health" => return Route::Health,
+        "/v1/models" | "/v1/models/" => return Route::List,
+        _ => {}
+    }
+    let Some(rest) = path.strip_prefix("/v1/models/") else {
+        return Route::NotFound;
+    };
+    match rest.split_once("/files/") {
+        Some((name, file)) if !file.is_empty() => Route::File(decode(name), decode(file)),
+        Some(_) => Route::NotFound,
+        None if !rest.contains('/') => Route::Manifest(decode(rest)),
+        None => Route::NotFound,
+    }
+}
+
+/// Start offset of a `Range: bytes=N-` request within a file of `size` bytes
+pub fn parse_range(header: &str, size: u64) -> Option<u64> {
+    let start: u64 = header.trim().strip_prefix("bytes=")?.strip_suffix('-')?.parse().ok()?;
+    (start < size).then_some(start)
+}
+
+/// Compare tokens without stopping at the first differing byte
+pub(crate) fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len() && given.bytes().zip(expected.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// The directory a model's files are relative to, its entry file and the files to share
+fn model_files(config: &ModelConfig) -> Option<(PathBuf, String, Vec<(String, PathBuf)>)> {
+    let path = &config.model_path;
+    if path.is_file() {
+        let root = path.parent()?.to_path_buf();
+        let entry = path.file_name()?.to_string_lossy().into_owned();
+        return Some((root, entry.clone(), vec![(entry, path.clone())]));
+    }
+    if !path.is_dir() {
+        return None;
+    }
+    let files: Vec<(String, PathBuf)> = walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && !e.file_name().to_string_lossy().ends_with(".part"))
+        .filter_map(|e| {
+            let relative = e.path().strip_prefix(path).ok()?.to_string_lossy().replace('\\', "/");
+            Some((relative, e.path().to_path_buf()))
+        })
+        .collect();
+    (!files.is_empty()).then(|| (path.clone(), String::new(), files))
+}
+
+struct Shared {
+    manifests: BTreeMap<String, ModelManifest>,
+    roots: HashMap<String, PathBuf>,
+    token: Option<String>,
+}
+
+impl GptManager {
+    /// Manifests for the models to share, hashing files not seen before
+    async fn registry_manifests(&self, only: Option<&[String]>) -> Result<(BTreeMap<String, ModelManifest>, HashMap<String, PathBuf>, Vec<String>)> {
+        if let Some(unknown) = only.into_iter().flatten().find(|name| !self.registry.models.contains_key(*name)) {
+            return Err(anyhow!("Model '{}' is not installed in this workspace", unknown));
+        }
+        let cache_path = self.configs_dir.join(DIGEST_CACHE);
+        let mut digests: HashMap<String, (u64, u64, String)> = fs::read_to_string(&cache_path).await.ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        let mut manifests = BTreeMap::new();
+        let mut roots = HashMap::new();
+        let mut skipped = Vec::new();
+        let mut names: Vec<&String> = self.registry.models.keys()
+            .filter(|name| only.map_or(true, |only| only.contains(name)))
+            .collect();
+        names.sort();
+        for name in names {
+            let config = &self.registry.models[name];
+            let Some((root, entry, files)) = model_files(config) else {
+                skipped.push(name.clone());
+                continue;
+            };
+            let mut manifest_files = Vec::new();
+            for (relative, path) in files {
+                let meta = fs::metadata(&path).await?;
+                let mtime = meta.modified().ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map_or(0, |d| d.as_secs());
+                let key = path.display().to_string();
+                let sha256 = match digests.get(&key) {
+                    Some((size, modified, sha256)) if *size == meta.len() && *modified == mtime => sha256.clone(),
+                    _ => {
+                        println!("🔢 Hashing {} ({})", relative, crate::util::format_bytes(meta.len()));
+                        let hashed = path.clone();
+                        let sha256 = tokio::task::spawn_blocking(move || crate::util::hash_file_streaming(&hashed, |_| {})).await??;
+                        digests.insert(key, (meta.len(), mtime, sha256.clone()));
+                        sha256
+                    }
+                };
+                manifest_files.push(ManifestFile { path: relative, size: meta.len(), sha256 });
+            }
+            manifests.insert(name.clone(), ModelManifest {
+                name: name.clone(),
+                version: config.version.clone(),
+                revision: config.revision.clone(),
+                format: config.format.clone(),
+                backend: config.backend.clone(),
+                parameters: config.parameters.clone(),
+                entry,
+                files: manifest_files,
+            });
+            roots.insert(name.clone(), root);
+        }
+
+        if !self.dry_run() {
+            if let Err(e) = fs::write(&cache_path, serde_json::to_string(&digests)?).await {
+                tracing::warn!("Failed to save {}: {}", cache_path.display(), e);
+            }
+        }
+        Ok((manifests, roots, skipped))
+    }
+
+    /// Serve the workspace's models until interrupted
+    pub async fn serve_registry(&self, host: &str, port: u16, only: Option<&[String]>, no_auth: bool) -> Result<()> {
+        let token = if no_auth { None } else { Some(server_token().await?) };
+        let (manifests, roots, skipped) = self.registry_manifests(only).await?;
+        if manifests.is_empty() {
+            return Err(anyhow!("No file-backed models to share; install some with `rcm gpt install <model> --source huggingface`"));
+        }
+        let listener = TcpListener::bind((host, port)).await
+            .with_context(|| format!("Failed to bind {}:{}", host, port))?;
+
+        println!("{}", style(format!("📚 Model registry listening on http://{}:{}", host, port)).green().bold());
+        for manifest in manifests.values() {
+            println!("   {} {} ({})", style(&manifest.name).cyan(), manifest.version, crate::util::format_bytes(manifest.size()));
+        }
+        if !skipped.is_empty() {
+            println!("   {}", style(format!("Not shared (no local files, e.g. Ollama-managed): {}", skipped.join(", "))).dim());
+        }
+        match &token {
+            Some(_) => println!("   Clients: rcm auth login <this host>, with the `{}` secret as the token", TOKEN_SECRET),
+            None => println!("   {}", style("⚠️  Authentication is off").yellow()),
+        }
+
+        let shared = Arc::new(Shared { manifests, roots, token });
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let shared = shared.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle(stream, &shared).await {
+                    tracing::debug!(%peer, "Registry request failed: {}", e);
+                }
+            });
+        }
+    }
+
+    /// Install a model from a team registry (`--source http://host:port`)
+    pub(crate) async fn install_from_registry(&mut self, source: &str, model: &str, force: bool) -> Result<()> {
+        if self.registry.models.contains_key(model) && !force {
+            return Err(anyhow!("Model already exists. Use --force to reinstall."));
+        }
+        let client = RegistryClient::new(source).await?;
+        let manifest: ModelManifest = client.get_json(&format!("/v1/models/{}", encode_name(model))).await?;
+        let model_dir = self.models_dir.join(model);
+        println!("📋 {} file(s), {}", manifest.files.len(), crate::util::format_bytes(manifest.size()));
+        if self.dry_run() {
+            println!("[dry-run] download {} from {} into {}", model, source, model_dir.display());
+            return Ok(());
+        }
+        if force && model_dir.exists() {
+            fs::remove_dir_all(&model_dir).await
+                .with_context(|| format!("Failed to remove {}", model_dir.display()))?;
+        }
+
+        for path in manifest.files.iter().map(|f| f.path.as_str())
+            .chain(Some(manifest.entry.as_str()).filter(|entry| !entry.is_empty()))
+        {
+            if !is_safe_relative(path) {
+                return Err(anyhow!("Registry sent an unsafe path: {}", path));
+            }
+        }
+        let cache = DownloadCache::from_user_config().await;
+        for file in &manifest.files {
+            client.download(model, file, &model_dir.join(&file.path), &cache).await?;
+        }
+
+        let parameters = ModelParameters {
+            // Thread counts and NUMA placement were tuned for the serving box
+            cpu_threads: None,
+            numa_node: None,
+            ..manifest.parameters
+        };
+        let config = ModelConfig {
+            name: model.to_string(),
+            version: manifest.version,
+            revision: manifest.revision,
+            format: manifest.format,
+            backend: manifest.backend,
+            model_path: if manifest.entry.is_empty() { model_dir } else { model_dir.join(&manifest.entry) },
+            config_path: None,
+            tokenizer_path: None,
+            parameters,
+            serving_config: ServingConfig::default(),
+            lineage: None,
+        };
+        self.registry.models.insert(model.to_string(), config);
+        self.save_registry().await?;
+        println!("✅ Model '{}' installed from {}", model, source);
+        Ok(())
+    }
+}
+
+/// Whether a registry-supplied path stays below the model directory
+fn is_safe_relative(path: &str) -> bool {
+    let path = Path::new(path);
+    !path.as_os_str().is_empty()
+        && !path.to_string_lossy().contains('\\')
+        && path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// The server's token, created and stored on first start
+async fn server_token() -> Result<String> {
+    if let Ok(token) = crate::secrets::get_secret(TOKEN_SECRET).await {
+        return Ok(token);
+    }
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    crate::secrets::set_secret(TOKEN_SECRET, &token).await
+        .context("Failed to store a new registry token; set one with `rcm secret set gpt-registry` or use --no-auth")?;
+    println!("🔑 Created a registry token (secret `{}`): {}", TOKEN_SECRET, token);
+    Ok(token)
+}
+
+pub(crate) async fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8], head_only: bool) -> Result<()> {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status, content_type, body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    if !head_only {
+        stream.write_all(body).await?;
+    }
+    Ok(())
+}
+
+async fn respond_json(stream: &mut TcpStream, value: &impl Serialize, head_only: bool) -> Result<()> {
+    respond(stream, "200 OK", "application/json", &serde_json::to_vec(value)?, head_only).await
+}
+
+/// A request line and headers (lowercased names), plus body bytes read with them
+pub(crate) struct RequestHead {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+/// Read a request head; `None` when the client hung up or was refused
+pub(crate) async fn read_head(stream: &mut TcpStream) -> Result<Option<RequestHead>> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+        if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end;
+        }
+        if buffer.len() > MAX_HEADER_BYTES {
+            respond(stream, "431 Request Header Fields Too Large", "text/plain", b"headers too large\n", false).await?;
+            return Ok(None);
+        }
+    };
+    let head = String::from_utf8_lossy(&buffer[..head_end]).into_owned();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim().to_string()))
+        .collect();
+    Ok(Some(RequestHead { method, path, headers, body: buffer[head_end + 4..].to_vec() }))
+}
+
+async fn handle(mut stream: TcpStream, shared: &Shared) -> Result<()> {
+    let Some(RequestHead { method, path, headers, .. }) = read_head(&mut stream).await? else {
+        return Ok(());
+    };
+    let (method, path) = (method.as_str(), path.as_str());
+
+    let head_only = method == "HEAD";
+    if method != "GET" && !head_only {
+        return respond(&mut stream, "405 Method Not Allowed", "text/plain", b"GET only\n", false).await;
+    }
+    let route = route(path);
+    if route == Route::Health {
+        return respond(&mut stream, "200 OK", "text/plain", b"ok\n", head_only).await;
+    }
+    if let Some(token) = &shared.token {
+        let given = headers.get("authorization").and_then(|v| v.strip_prefix("Bearer ")).unwrap_or_default();
+        if !token_matches(given, token) {
+            let head = "HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Bearer\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+            stream.write_all(head.as_bytes()).await?;
+            return Ok(());
+        }
+    }
+
+    match route {
+        Route::List => {
+            let manifests: Vec<&ModelManifest> = shared.manifests.values().collect();
+            respond_json(&mut stream, &manifests, head_only).await
+        }
+        Route::Manifest(name) => match shared.manifests.get(&name) {
+            Some(manifest) => respond_json(&mut stream, manifest, head_only).await,
+            None => respond(&mut stream, "404 Not Found", "text/plain", b"no such model\n", head_only).await,
+        },
+        Route::File(name, file) => {
+            // Only paths listed in the manifest are served, so nothing outside the model leaks
+            let found = shared.manifests.get(&name)
+                .and_then(|m| m.files.iter().find(|f| f.path == file))
+                .zip(shared.roots.get(&name));
+            let Some((entry, root)) = found else {
+                return respond(&mut stream, "404 Not Found", "text/plain", b"no such file\n", head_only).await;
+            };
+            let mut source = fs::File::open(root.join(&entry.path)).await?;
+            let start = headers.get("range").and_then(|r| parse_range(r, entry.size));
+            let head = match start {
+                Some(start) => {
+                    source.seek(std::io::SeekFrom::Start(start)).await?;
+                    format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nConnection: close\r\n\r\n",
+                        entry.size - start, start, entry.size - 1, entry.size
+                    )
+                }
+                None => format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\nAccept-Ranges: bytes\r\nConnection: close\r\n\r\n",
+                    entry.size
+                ),
+            };
+            stream.write_all(head.as_bytes()).await?;
+            if !head_only {
+                tokio::io::copy(&mut source, &mut stream).await?;
+            }
+            Ok(())
+        }
+        Route::Health | Route::NotFound => respond(&mut stream, "404 Not Found", "text/plain", b"not found\n", head_only).await,
+    }
+}
+
+/// HTTP client for a team registry, authenticated from `rcm auth login <host>`
+struct RegistryClient {
+    base: String,
+    http: reqwest::Client,
+    credential: Option<Credential>,
+}
+
+impl RegistryClient {
+    async fn new(source: &str) -> Result<Self> {
+        let base = source.trim_end_matches('/').to_string();
+        let url = reqwest::Url::parse(&base).with_context(|| format!("Invalid registry URL: {}", source))?;
+        let host = url.host_str().ok_or_else(|| anyhow!("Registry URL has no host: {}", source))?;
+        let config = crate::config::Config::load(None).await.unwrap_or_default();
+        let credential = crate::auth::credential(&config, host).await?;
+        let loopback = host == "localhost"
+            || host.trim_matches(['[', ']']).parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback());
+        if credential.is_some() && url.scheme() != "https" && !loopback {
+            return Err(anyhow!(
+                "Not sending the {} token over {}; serve the registry behind https or start it with --no-auth",
+                host, url.scheme()
+            ));
+        }
+        Ok(Self { base, http: reqwest::Client::new(), credential })
+    }
+
+    fn get(&self, path: &str) -> reqwest::RequestBuilder {
+        let request = self.http.get(format!("{}{}", self.base, path));
+        match &self.credential {
+            Some(credential) => credential.apply(request),
+            None => request,
+        }
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let response = self.get(path).send().await
+            .with_context(|| format!("Failed to reach {}", self.base))?;
+        match response.status() {
+            status if status.is_success() => Ok(response.json().await?),
+            reqwest::StatusCode::UNAUTHORIZED => Err(anyhow!(
+                "{} needs a token; run `rcm auth login {}`",
+                self.base, reqwest::Url::parse(&self.base).ok().and_then(|u| u.host_str().map(str::to_string)).unwrap_or_default()
+            )),
+            reqwest::StatusCode::NOT_FOUND => Err(anyhow!("{} has no such model", self.base)),
+            status => Err(anyhow!("{} answered {}", self.base, status)),
+        }
+    }
+
+    /// Download one file, resuming a partial download and checking its digest
+    async fn download(&self, model: &str, file: &ManifestFile, dest: &Path, cache: &DownloadCache) -> Result<()> {
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        if cache.restore(&file.sha256, dest).await? {
+            println!("✔️ {} restored from the download cache", file.path);
+            return Ok(());
+        }
+
+        let partial = dest.with_file_name(format!(
+            "{}.part",
+            dest.file_name().and_then(|n| n.to_str()).unwrap_or("download")
+        ));
+        let offset = fs::metadata(&partial).await.map(|m| m.len()).unwrap_or(0);
+        let mut request = self.get(&format!("/v1/models/{}/files/{}", encode_name(model), file.path));
+        if offset > 0 && offset < file.size {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+        }
+        println!("⬇️  {} ({})", file.path, crate::util::format_bytes(file.size));
+        let mut response = request.send().await
+            .with_context(|| format!("Failed to download {}", file.path))?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Download of {} failed: {}", file.path, response.status()));
+        }
+        let resumed = response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+        let mut out = if resumed {
+            fs::OpenOptions::new().append(true).open(&partial).await?
+        } else {
+            fs::File::create(&partial).await?
+        };
+        while let Some(bytes) = response.chunk().await? {
+            out.write_all(&bytes).await?;
+        }
+        out.flush().await?;
+        drop(out);
+
+        let sha256 = crate::util::get_file_hash(&partial).await?;
+        if sha256 != file.sha256 {
+            fs::remove_file(&partial).await.ok();
+            return Err(anyhow!("Checksum mismatch for {}: expected {}, got {}", file.path, file.sha256, sha256));
+        }
+        fs::rename(&partial, dest).await?;
+        if let Err(e) = cache.adopt(dest, &file.sha256).await {
+            tracing::warn!("Failed to cache {}: {}", file.path, e);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Tabled)]
+struct RegistryRow {
+    #[tabled(rename = "Model")]
+    name: String,
+    #[tabled(rename = "Version")]
+    version: String,
+    #[tabled(rename = "Format")]
+    format: String,
+    #[tabled(rename = "Size")]
+    size: String,
+}
+
+/// `rcm gpt registry ls <source>`
+pub async fn list_remote(source: &str) -> Result<()> {
+    let client = RegistryClient::new(source).await?;
+    let manifests: Vec<ModelManifest> = client.get_json("/v1/models").await?;
+    if manifests.is_empty() {
+        println!("{} shares no models", source);
+        return Ok(());
+    }
+    let rows: Vec<RegistryRow> = manifests.iter().map(|m| RegistryRow {
+        name: m.name.clone(),
+        version: m.version.clone(),
+        format: format!("{:?}", m.format),
+        size: crate::util::format_bytes(m.size()),
+    }).collect();
+    println!("{}", Table::new(&rows));
+    println!("💡 rcm gpt install <model> --source {}", source);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routes() {
+        let name = "TheBloke/Llama-2-7B-GGUF";
+        assert_eq!(encode_name(name), "TheBloke%2FLlama-2-7B-GGUF");
+        assert_eq!(route("/v1/models"), Route::List);
+        assert_eq!(route(&format!("/v1/models/{}", encode_name(name))), Route::Manifest(name.to_string()));
+        assert_eq!(
+            route(&format!("/v1/models/{}/files/q4/model.Q4_K_M.gguf", encode_name(name))),
+            Route::File(name.to_string(), "q4/model.Q4_K_M.gguf".to_string())
+        );
+        assert_eq!(route("/v1/models/a/b"), Route::NotFound);
+        assert_eq!(route("/etc/passwd"), Route::NotFound);
+    }
+
+    #[test]
+    fn test_range_and_token() {
+        assert_eq!(parse_range("bytes=100-", 1000), Some(100));
+        assert_eq!(parse_range("bytes=1000-", 1000), None);
+        assert_eq!(parse_range("bytes=0-99", 1000), None);
+        assert!(token_matches("abc", "abc"));
+        assert!(!token_matches("abd", "abc"));
+        assert!(!token_matches("", "abc"));
+    }
+
+    #[test]
+    fn test_safe_relative() {
+        assert!(is_safe_relative("q4/model.Q4_K_M.gguf"));
+        assert!(!is_safe_relative("../../.."));
+        assert!(!is_safe_relative("q4/../../x"));
+        assert!(!is_safe_relative("/etc/passwd"));
+        assert!(!is_safe_relative("..\\..\\x"));
+        assert!(!is_safe_relative(""));
+    }
+}
@@ -0,0 +1,313 @@
+//! CPU tuning for llama.cpp serving (`rcm gpt tune`)
+//!
+//! With the `arm` feature, ARM-lib's register benchmark is run on a growing
+//! number of threads first; thread counts past the point where CPU throughput
+//! stops scaling (usually SMT siblings) are dropped before the slower model
+//! benchmark. `llama-bench` then measures the remaining thread counts and batch
+//! sizes against the model itself, and the winners are saved to its config.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::process::Command as AsyncCommand;
+use crate::backend::HardwareProfile;
+use crate::{GptManager, ServingBackend};
+
+/// Batch sizes tried when none are given
+pub const DEFAULT_BATCH_SIZES: [usize; 4] = [128, 256, 512, 1024];
+
+/// Prompt and generation lengths for each llama-bench run
+const BENCH_PROMPT_TOKENS: u32 = 512;
+const BENCH_GEN_TOKENS: u32 = 64;
+
+/// Smallest throughput gain that justifies another thread count
+#[cfg(feature = "arm")]
+const MIN_SCALING_GAIN: f64 = 0.05;
+
+/// Register-benchmark iterations per thread when probing scaling
+#[cfg(feature = "arm")]
+const PROBE_ITERATIONS: u64 = 5_000_000;
+
+/// Options for `rcm gpt tune`
+#[derive(Debug, Clone, Default)]
+pub struct TuneOptions {
+    /// Thread counts to try instead of the detected candidates
+    pub threads: Vec<u32>,
+    /// Batch sizes to try instead of [`DEFAULT_BATCH_SIZES`]
+    pub batch_sizes: Vec<usize>,
+    /// llama-bench repetitions per configuration
+    pub repetitions: u32,
+    /// Print the results without updating the model config
+    pub no_save: bool,
+}
+
+/// One llama-bench measurement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchRun {
+    pub n_threads: u32,
+    pub n_batch: usize,
+    pub n_prompt: u32,
+    pub n_gen: u32,
+    /// Average tokens per second
+    pub avg_ts: f64,
+}
+
+/// Best configuration found
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TuneChoice {
+    pub threads: u32,
+    pub batch_size: usize,
+    pub generation_tps: f64,
+    pub prompt_tps: Option<f64>,
+}
+
+/// Saved next to the registry so later runs and bug reports can see how a config was chosen
+#[derive(Debug, Serialize)]
+struct TuneReport<'a> {
+    model: &'a str,
+    tuned_at: String,
+    cpu_threads: usize,
+    simd: Vec<&'static str>,
+    choice: &'a TuneChoice,
+    runs: &'a [BenchRun],
+}
+
+/// SIMD extensions the CPU supports at runtime
+pub fn detect_simd() -> Vec<&'static str> {
+    #[allow(unused_mut)]
+    let mut features = Vec::new();
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx") { features.push("avx"); }
+        if is_x86_feature_detected!("avx2") { features.push("avx2"); }
+        if is_x86_feature_detected!("fma") { features.push("fma"); }
+        if is_x86_feature_detected!("f16c") { features.push("f16c"); }
+        if is_x86_feature_detected!("avx512f") { features.push("avx512f"); }
+        if is_x86_feature_detected!("avx512vnni") { features.push("avx512vnni"); }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") { features.push("neon"); }
+        if std::arch::is_aarch64_feature_detected!("dotprod") { features.push("dotprod"); }
+        if std::arch::is_aarch64_feature_detected!("i8mm") { features.push("i8mm"); }
+        if std::arch::is_aarch64_feature_detected!("sve") { features.push("sve"); }
+    }
+    features
+}
+
+/// Thread counts worth benchmarking on a machine with `logical` hardware threads
+///
+/// Powers of two plus half and all of the threads; very low counts are skipped
+/// on large machines because they never win.
+pub fn thread_candidates(logical: usize) -> Vec<u32> {
+    let logical = logical.max(1);
+    let floor = (logical / 8).max(1);
+    let mut counts: Vec<usize> = std::iter::successors(Some(1usize), |n| Some(n * 2))
+        .take_while(|n| *n <= logical)
+        .chain([logical / 2, logical])
+        .filter(|n| *n >= floor)
+        .collect();
+    counts.sort_unstable();
+    counts.dedup();
+    counts.into_iter().map(|n| n as u32).collect()
+}
+
+/// Keep thread counts up to the first one that adds less than `min_gain` throughput
+///
+/// `samples` are `(threads, operations per second)` in ascending thread order.
+pub fn prune_by_scaling(samples: &[(u32, f64)], min_gain: f64) -> Vec<u32> {
+    let mut kept = Vec::new();
+    let mut best = 0.0_f64;
+    for (threads, throughput) in samples {
+        if !kept.is_empty() && *throughput < best * (1.0 + min_gain) {
+            break;
+        }
+        kept.push(*threads);
+        best = best.max(*throughput);
+    }
+    kept
+}
+
+/// Aggregate register-benchmark throughput for each thread count
+#[cfg(feature = "arm")]
+fn arm_scaling(counts: &[u32]) -> Result<Vec<(u32, f64)>> {
+    counts.iter().map(|&n| {
+        let start = std::time::Instant::now();
+        let handles: Vec<_> = (0..n)
+            .map(|_| std::thread::spawn(|| arm_lib::ArmLet::new().benchmark_run("alternating", PROBE_ITERATIONS)))
+            .collect();
+        for handle in handles {
+            handle.join().map_err(|_| anyhow!("ARM benchmark thread panicked"))??;
+        }
+        let ops = (n as u64 * PROBE_ITERATIONS) as f64;
+        Ok((n, ops / start.elapsed().as_secs_f64()))
+    }).collect()
+}
+
+/// Narrow the thread candidates to those where the CPU still scales
+#[cfg(feature = "arm")]
+fn probe_threads(candidates: Vec<u32>) -> Result<Vec<u32>> {
+    println!("🔬 Probing CPU scaling with ARM-lib on {:?} threads...", candidates);
+    let samples = tokio::task::block_in_place(|| arm_scaling(&candidates))?;
+    for (threads, ops) in &samples {
+        println!("   {:>3} threads: {:.2e} ops/s", threads, ops);
+    }
+    Ok(prune_by_scaling(&samples, MIN_SCALING_GAIN))
+}
+
+#[cfg(not(feature = "arm"))]
+fn probe_threads(candidates: Vec<u32>) -> Result<Vec<u32>> {
+    Ok(candidates)
+}
+
+/// Parse `llama-bench -o json` output
+pub fn parse_llama_bench(output: &str) -> Result<Vec<BenchRun>> {
+    serde_json::from_str(output.trim()).context("Unexpected llama-bench output")
+}
+
+/// Threads from the fastest generation run, then the batch size with the fastest prompt processing at that thread count
+///
+/// Generation speed is what users notice when chatting; batch size only
+/// matters for prompt processing, so it is picked second.
+pub fn pick_best(runs: &[BenchRun]) -> Option<TuneChoice> {
+    let generation = runs.iter()
+        .filter(|r| r.n_gen > 0 && r.n_prompt == 0)
+        .max_by(|a, b| a.avg_ts.total_cmp(&b.avg_ts))?;
+    let prompt = runs.iter()
+        .filter(|r| r.n_prompt > 0 && r.n_gen == 0 && r.n_threads == generation.n_threads)
+        .max_by(|a, b| a.avg_ts.total_cmp(&b.avg_ts));
+    Some(TuneChoice {
+        threads: generation.n_threads,
+        batch_size: prompt.map(|p| p.n_batch).unwrap_or(generation.n_batch),
+        generation_tps: generation.avg_ts,
+        prompt_tps: prompt.map(|p| p.avg_ts),
+    })
+}
+
+fn join<T: ToString>(values: &[T]) -> String {
+    values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",")
+}
+
+async fn run_llama_bench(model_path: &Path, threads: &[u32], batch_sizes: &[usize], repetitions: u32) -> Result<Vec<BenchRun>> {
+    let output = AsyncCommand::new("llama-bench")
+        .arg("-m").arg(model_path)
+        .arg("-t").arg(join(threads))
+        .arg("-b").arg(join(batch_sizes))
+        .arg("-p").arg(BENCH_PROMPT_TOKENS.to_string())
+        .arg("-n").arg(BENCH_GEN_TOKENS.to_string())
+        .arg("-r").arg(repetitions.to_string())
+        .arg("-o").arg("json")
+        .output()
+        .await
+        .context("Failed to run llama-bench. It ships with llama.cpp next to llama-server")?;
+    if !output.status.success() {
+        return Err(anyhow!("llama-bench failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    parse_llama_bench(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Benchmark thread counts and batch sizes for a llama.cpp model and save the best
+pub async fn tune(manager: &mut GptManager, model: &str, options: &TuneOptions) -> Result<()> {
+    let config = manager.registry.models.get(model)
+        .ok_or_else(|| anyhow!("Model '{}' is not configured. Install it with 'rcm gpt install {}'", model, model))?
+        .clone();
+    if !matches!(config.backend, ServingBackend::LlamaCpp) {
+        return Err(anyhow!("Tuning applies to llama.cpp serving; '{}' uses {:?}", model, config.backend));
+    }
+    if !config.model_path.is_file() {
+        return Err(anyhow!("Model file not found: {}", config.model_path.display()));
+    }
+
+    let hardware = HardwareProfile::detect().await;
+    let simd = detect_simd();
+    println!("🖥️  {} hardware threads, SIMD: {}", hardware.cpu_threads,
+        if simd.is_empty() { "none detected".to_string() } else { simd.join(", ") });
+
+    let threads = if options.threads.is_empty() {
+        probe_threads(thread_candidates(hardware.cpu_threads))?
+    } else {
+        options.threads.clone()
+    };
+    let batch_sizes = if options.batch_sizes.is_empty() {
+        DEFAULT_BATCH_SIZES.to_vec()
+    } else {
+        options.batch_sizes.clone()
+    };
+
+    if manager.dry_run() {
+        println!("[dry-run] llama-bench -m {} -t {} -b {} -p {} -n {} -r {} -o json",
+            config.model_path.display(), join(&threads), join(&batch_sizes),
+            BENCH_PROMPT_TOKENS, BENCH_GEN_TOKENS, options.repetitions);
+        return Ok(());
+    }
+
+    println!("⏱️  Benchmarking {} with threads [{}] and batch sizes [{}]...", model, join(&threads), join(&batch_sizes));
+    let runs = run_llama_bench(&config.model_path, &threads, &batch_sizes, options.repetitions.max(1)).await?;
+    let choice = pick_best(&runs).ok_or_else(|| anyhow!("llama-bench reported no generation results"))?;
+
+    println!("📊 Generation (tokens/s):");
+    let mut generation: Vec<&BenchRun> = runs.iter().filter(|r| r.n_gen > 0 && r.n_prompt == 0).collect();
+    generation.sort_by_key(|r| (r.n_threads, r.n_batch));
+    for run in generation {
+        let marker = if run.n_threads == choice.threads { "◀" } else { "" };
+        println!("   {:>3} threads, batch {:>5}: {:>8.2} {}", run.n_threads, run.n_batch, run.avg_ts, marker);
+    }
+    println!("✅ Best: {} threads, batch size {} ({:.2} tok/s generation{})",
+        choice.threads, choice.batch_size, choice.generation_tps,
+        choice.prompt_tps.map(|t| format!(", {:.2} tok/s prompt", t)).unwrap_or_default());
+
+    let report = TuneReport {
+        model,
+        tuned_at: chrono::Utc::now().to_rfc3339(),
+        cpu_threads: hardware.cpu_threads,
+        simd,
+        choice: &choice,
+        runs: &runs,
+    };
+    let tune_dir = manager.configs_dir.join("tune");
+    tokio::fs::create_dir_all(&tune_dir).await?;
+    tokio::fs::write(tune_dir.join(format!("{}.json", model)), serde_json::to_string_pretty(&report)?).await?;
+
+    if options.no_save {
+        return Ok(());
+    }
+    let mut config = config;
+    config.parameters.cpu_threads = Some(choice.threads);
+    config.parameters.batch_size = choice.batch_size;
+    manager.configure_model(&config).await?;
+    if manager.registry.active_models.contains_key(model) {
+        println!("🔄 Restart the model to apply: rcm gpt stop {} && rcm gpt serve {} --deploy", model, model);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thread_candidates_and_pruning() {
+        assert_eq!(thread_candidates(12), vec![1, 2, 4, 6, 8, 12]);
+        assert_eq!(thread_candidates(64), vec![8, 16, 32, 64]);
+        assert_eq!(thread_candidates(1), vec![1]);
+
+        let samples = [(1, 100.0), (2, 195.0), (4, 380.0), (8, 410.0), (16, 415.0)];
+        assert_eq!(prune_by_scaling(&samples, 0.05), vec![1, 2, 4, 8]);
+        assert_eq!(prune_by_scaling(&samples[..1], 0.05), vec![1]);
+    }
+
+    #[test]
+    fn test_pick_best() {
+        let output = r#"[
+            {"n_threads": 4, "n_batch": 256, "n_prompt": 512, "n_gen": 0, "avg_ts": 80.5, "model_type": "llama 7B Q4_0"},
+            {"n_threads": 4, "n_batch": 512, "n_prompt": 512, "n_gen": 0, "avg_ts": 95.1},
+            {"n_threads": 4, "n_batch": 256, "n_prompt": 0, "n_gen": 64, "avg_ts": 12.0},
+            {"n_threads": 8, "n_batch": 256, "n_prompt": 512, "n_gen": 0, "avg_ts": 140.0},
+            {"n_threads": 8, "n_batch": 256, "n_prompt": 0, "n_gen": 64, "avg_ts": 11.2}
+        ]"#;
+        let runs = parse_llama_bench(output).unwrap();
+        let choice = pick_best(&runs).unwrap();
+        assert_eq!((choice.threads, choice.batch_size, choice.prompt_tps), (4, 512, Some(95.1)));
+        assert!(pick_best(&runs[..2]).is_none());
+    }
+}
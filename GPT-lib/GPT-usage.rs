@@ -0,0 +1,493 @@
+//! Usage budgets and ledger for the model gateway
+//!
+//! Gateway clients are named and hold a bearer token, stored hashed in
+//! `gpt-configs/gateway.json` with an optional budget: tokens per rolling 24
+//! hours and requests per rolling hour. Clients without their own limits get
+//! the default budget; a limit of 0 is unlimited and overrides the default.
+//!
+//! Every proxied or refused request is appended to `gpt-configs/usage.jsonl`.
+//! The gateway replays the last day of it on start, so a restart doesn't reset
+//! anyone's budget, and `rcm gpt usage` reports from the same file.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tabled::{Table, Tabled};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+pub const GATEWAY_FILE: &str = "gateway.json";
+pub const LEDGER_FILE: &str = "usage.jsonl";
+
+/// Status recorded for requests the budget refused
+pub const REFUSED: u16 = 429;
+
+/// Limits for one client; `None` falls back to the default budget, `Some(0)` is unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Budget {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_per_day: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_hour: Option<u32>,
+}
+
+impl Budget {
+    /// Fill unset limits from `fallback`; the result has `None` for every unlimited value
+    pub fn or(self, fallback: Budget) -> Budget {
+        Budget {
+            tokens_per_day: self.tokens_per_day.or(fallback.tokens_per_day).filter(|t| *t > 0),
+            requests_per_hour: self.requests_per_hour.or(fallback.requests_per_hour).filter(|r| *r > 0),
+        }
+    }
+
+    /// Apply `--tokens-per-day` / `--requests-per-hour`, where 0 means unlimited
+    pub fn update(&mut self, tokens_per_day: Option<u64>, requests_per_hour: Option<u32>) {
+        if let Some(tokens) = tokens_per_day {
+            self.tokens_per_day = Some(tokens);
+        }
+        if let Some(requests) = requests_per_hour {
+            self.requests_per_hour = Some(requests);
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let tokens = self.tokens_per_day.filter(|t| *t > 0).map(|t| format!("{} tokens/day", t)).unwrap_or_else(|| "unlimited tokens".to_string());
+        let requests = self.requests_per_hour.filter(|r| *r > 0).map(|r| format!("{} requests/hour", r)).unwrap_or_else(|| "unlimited requests".to_string());
+        format!("{}, {}", tokens, requests)
+    }
+}
+
+/// A client allowed through the gateway
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GatewayClient {
+    /// SHA-256 of the client's token; the token itself is only shown once
+    pub token_sha256: String,
+    #[serde(default)]
+    pub budget: Budget,
+    /// Models the client may use; empty allows every running model
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<String>,
+}
+
+/// Clients and budgets, `gpt-configs/gateway.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GatewayConfig {
+    /// Limits for clients that don't set their own
+    #[serde(default)]
+    pub default_budget: Budget,
+    #[serde(default)]
+    pub clients: BTreeMap<String, GatewayClient>,
+    /// Content filter rules by model, `*` for all (see [`crate::safety`])
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub safety: BTreeMap<String, Vec<crate::safety::FilterRule>>,
+}
+
+impl GatewayConfig {
+    pub async fn load(configs_dir: &Path) -> Result<Self> {
+        match fs::read_to_string(configs_dir.join(GATEWAY_FILE)).await {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Invalid {}", configs_dir.join(GATEWAY_FILE).display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn save(&self, configs_dir: &Path, dry_run: bool) -> Result<()> {
+        let path = configs_dir.join(GATEWAY_FILE);
+        if dry_run {
+            println!("[dry-run] update {}", path.display());
+            return Ok(());
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?).await?;
+        Ok(())
+    }
+
+    /// The client a bearer token belongs to
+    pub fn authenticate(&self, token: &str) -> Option<(&str, &GatewayClient)> {
+        if token.is_empty() {
+            return None;
+        }
+        let digest = hash_token(token);
+        self.clients
+            .iter()
+            .find(|(_, client)| crate::share::token_matches(&digest, &client.token_sha256))
+            .map(|(name, client)| (name.as_str(), client))
+    }
+
+    /// A client's effective budget
+    pub fn budget(&self, client: &str) -> Budget {
+        self.clients.get(client).map(|c| c.budget).unwrap_or_default().or(self.default_budget)
+    }
+}
+
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// One request through the gateway
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub timestamp: DateTime<Utc>,
+    pub client: String,
+    pub model: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Status returned to the client; 429 when the budget refused the request
+    pub status: u16,
+}
+
+impl UsageRecord {
+    pub fn tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    fn refused(&self) -> bool {
+        self.status == REFUSED
+    }
+}
+
+/// Why a request was refused and when to try again
+#[derive(Debug, PartialEq)]
+pub struct OverBudget {
+    pub reason: String,
+    pub retry_after_secs: u64,
+}
+
+/// Budget held for a request between admission and its response
+#[derive(Debug)]
+struct Reservation {
+    client: String,
+    tokens: u64,
+}
+
+/// A request let through by [`Ledger::admit`]
+#[derive(Debug, PartialEq)]
+pub struct Admission {
+    pub id: u64,
+    /// Most completion tokens the request may use, when the client has a token budget
+    pub max_tokens: Option<u64>,
+}
+
+/// The last day of usage, kept in memory by the gateway
+#[derive(Debug, Default)]
+pub struct Ledger {
+    recent: Vec<UsageRecord>,
+    /// Requests still in flight, counted against the budget until settled
+    pending: BTreeMap<u64, Reservation>,
+    next_id: u64,
+}
+
+impl Ledger {
+    pub fn new(records: Vec<UsageRecord>, now: DateTime<Utc>) -> Self {
+        let mut ledger = Self { recent: records, ..Self::default() };
+        ledger.prune(now);
+        ledger
+    }
+
+    fn prune(&mut self, now: DateTime<Utc>) {
+        let cutoff = now - Duration::days(1);
+        self.recent.retain(|r| r.timestamp > cutoff);
+    }
+
+    fn counted<'a>(&'a self, client: &'a str, since: DateTime<Utc>) -> impl Iterator<Item = &'a UsageRecord> + 'a {
+        self.recent.iter().filter(move |r| r.client == client && r.timestamp > since && !r.refused())
+    }
+
+    /// Tokens used in the last day and requests made in the last hour, in-flight ones included
+    pub fn used(&self, client: &str, now: DateTime<Utc>) -> (u64, u32) {
+        let pending: Vec<&Reservation> = self.pending.values().filter(|r| r.client == client).collect();
+        let tokens = self.counted(client, now - Duration::days(1)).map(UsageRecord::tokens).sum::<u64>()
+            + pending.iter().map(|r| r.tokens).sum::<u64>();
+        let requests = self.counted(client, now - Duration::hours(1)).count() as u32 + pending.len() as u32;
+        (tokens, requests)
+    }
+
+    /// Tokens left in the daily budget
+    pub fn remaining_tokens(&self, client: &str, budget: Budget, now: DateTime<Utc>) -> Option<u64> {
+        budget.tokens_per_day.map(|limit| limit.saturating_sub(self.used(client, now).0))
+    }
+
+    /// Let a request through and hold its share of the budget until [`Ledger::settle`]
+    ///
+    /// The reservation covers `prompt_tokens` and, with a token budget, the
+    /// completion tokens granted, capped at `requested`. Concurrent requests
+    /// therefore split what is left instead of each being offered all of it.
+    pub fn admit(
+        &mut self,
+        client: &str,
+        budget: Budget,
+        now: DateTime<Utc>,
+        prompt_tokens: u64,
+        requested: Option<u64>,
+    ) -> std::result::Result<Admission, OverBudget> {
+        self.check(client, budget, now)?;
+        let max_tokens = self.remaining_tokens(client, budget, now).map(|remaining| {
+            let left = remaining.saturating_sub(prompt_tokens).max(1);
+            requested.map_or(left, |requested| requested.min(left))
+        });
+        let id = self.next_id;
+        self.next_id += 1;
+        let tokens = prompt_tokens + max_tokens.or(requested).unwrap_or(0);
+        self.pending.insert(id, Reservation { client: client.to_string(), tokens });
+        Ok(Admission { id, max_tokens })
+    }
+
+    /// Replace a reservation with what the request actually used
+    pub fn settle(&mut self, id: u64, record: UsageRecord) {
+        self.pending.remove(&id);
+        self.recent.push(record);
+    }
+
+    /// Drop a reservation for a request that never reached a model
+    pub fn release(&mut self, id: u64) {
+        self.pending.remove(&id);
+    }
+
+    /// Refuse a request when the client is at either limit
+    ///
+    /// Token use is only known afterwards, so the request that crosses the
+    /// daily limit still completes; the next one is refused.
+    pub fn check(&mut self, client: &str, budget: Budget, now: DateTime<Utc>) -> std::result::Result<(), OverBudget> {
+        self.prune(now);
+        let (tokens, requests) = self.used(client, now);
+
+        if let Some(limit) = budget.requests_per_hour {
+            if requests >= limit {
+                let hour_ago = now - Duration::hours(1);
+                let oldest = self.counted(client, hour_ago).map(|r| r.timestamp).min().unwrap_or(now);
+                return Err(OverBudget {
+                    reason: format!("{} requests per hour", limit),
+                    retry_after_secs: seconds_until(oldest + Duration::hours(1), now),
+                });
+            }
+        }
+
+        if let Some(limit) = budget.tokens_per_day {
+            if tokens >= limit {
+                // Wait until enough of the day's usage ages out to get back under the limit
+                let mut records: Vec<&UsageRecord> = self.counted(client, now - Duration::days(1)).collect();
+                records.sort_by_key(|r| r.timestamp);
+                let mut over = tokens - limit;
+                let mut frees_at = now;
+                for record in records {
+                    frees_at = record.timestamp + Duration::days(1);
+                    if record.tokens() > over {
+                        break;
+                    }
+                    over -= record.tokens();
+                }
+                return Err(OverBudget {
+                    reason: format!("{} tokens per day", limit),
+                    retry_after_secs: seconds_until(frees_at, now),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn push(&mut self, record: UsageRecord) {
+        self.recent.push(record);
+    }
+}
+
+fn seconds_until(at: DateTime<Utc>, now: DateTime<Utc>) -> u64 {
+    (at - now).num_seconds().max(1) as u64
+}
+
+/// Every record in the ledger, skipping lines that don't parse
+pub async fn read_ledger(configs_dir: &Path) -> Result<Vec<UsageRecord>> {
+    match fs::read_to_string(configs_dir.join(LEDGER_FILE)).await {
+        Ok(content) => Ok(content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn append(configs_dir: &Path, record: &UsageRecord) -> Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(configs_dir.join(LEDGER_FILE))
+        .await?;
+    file.write_all(format!("{}\n", serde_json::to_string(record)?).as_bytes()).await?;
+    Ok(())
+}
+
+/// Start of a reporting window: `30m`, `24h`, `7d` or a date (`2024-05-01`)
+pub fn parse_since(since: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let since = since.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(since, "%Y-%m-%d") {
+        return Ok(date.and_time(chrono::NaiveTime::MIN).and_utc());
+    }
+    let Some(unit) = since.chars().last() else {
+        return Err(anyhow!("Invalid --since ''. Use e.g. 30m, 24h, 7d or 2024-05-01"));
+    };
+    let amount: i64 = since[..since.len() - unit.len_utf8()].parse().map_err(|_| anyhow!("Invalid --since '{}'. Use e.g. 30m, 24h, 7d or 2024-05-01", since))?;
+    let window = match unit {
+        'm' => Duration::minutes(amount),
+        'h' => Duration::hours(amount),
+        'd' => Duration::days(amount),
+        _ => return Err(anyhow!("Invalid --since '{}'. Use e.g. 30m, 24h, 7d or 2024-05-01", since)),
+    };
+    Ok(now - window)
+}
+
+/// Usage of one model by one client
+#[derive(Debug, Clone, PartialEq, Serialize, Tabled)]
+pub struct UsageRow {
+    #[tabled(rename = "Client")]
+    pub client: String,
+    #[tabled(rename = "Model")]
+    pub model: String,
+    #[tabled(rename = "Requests")]
+    pub requests: u64,
+    #[tabled(rename = "Refused")]
+    pub refused: u64,
+    #[tabled(rename = "Prompt tokens")]
+    pub prompt_tokens: u64,
+    #[tabled(rename = "Completion tokens")]
+    pub completion_tokens: u64,
+}
+
+pub fn summarize(records: &[UsageRecord], since: DateTime<Utc>) -> Vec<UsageRow> {
+    let mut rows: BTreeMap<(String, String), UsageRow> = BTreeMap::new();
+    for record in records.iter().filter(|r| r.timestamp >= since) {
+        let row = rows.entry((record.client.clone(), record.model.clone())).or_insert_with(|| UsageRow {
+            client: record.client.clone(),
+            model: record.model.clone(),
+            requests: 0,
+            refused: 0,
+            prompt_tokens: 0,
+            completion_tokens: 0,
+        });
+        if record.refused() {
+            row.refused += 1;
+        } else {
+            row.requests += 1;
+            row.prompt_tokens += record.prompt_tokens;
+            row.completion_tokens += record.completion_tokens;
+        }
+    }
+    rows.into_values().collect()
+}
+
+/// `rcm gpt usage`
+pub async fn report(configs_dir: &Path, client: Option<&str>, since: &str, format: &str) -> Result<()> {
+    let now = Utc::now();
+    let start = parse_since(since, now)?;
+    let mut records = read_ledger(configs_dir).await?;
+    if let Some(client) = client {
+        records.retain(|r| r.client == client);
+    }
+    let rows = summarize(&records, start);
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+    if rows.is_empty() {
+        println!("No gateway usage since {}", start.format("%Y-%m-%d %H:%M UTC"));
+    } else {
+        println!("📊 Gateway usage since {}", start.format("%Y-%m-%d %H:%M UTC"));
+        println!("{}", Table::new(&rows));
+    }
+
+    // Where each client stands against its budget right now
+    let config = GatewayConfig::load(configs_dir).await?;
+    let ledger = Ledger::new(records, now);
+    for name in config.clients.keys().filter(|name| client.map_or(true, |c| c == name.as_str())) {
+        let budget = config.budget(name);
+        let (tokens, requests) = ledger.used(name, now);
+        let limit = |value: Option<String>| value.unwrap_or_else(|| "∞".to_string());
+        println!(
+            "   {}: {}/{} tokens (24h), {}/{} requests (1h)",
+            name, tokens, limit(budget.tokens_per_day.map(|t| t.to_string())),
+            requests, limit(budget.requests_per_hour.map(|r| r.to_string())),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(client: &str, minutes_ago: i64, tokens: u64, status: u16, now: DateTime<Utc>) -> UsageRecord {
+        UsageRecord {
+            timestamp: now - Duration::minutes(minutes_ago),
+            client: client.to_string(),
+            model: "llama3".to_string(),
+            prompt_tokens: tokens / 2,
+            completion_tokens: tokens - tokens / 2,
+            status,
+        }
+    }
+
+    #[test]
+    fn test_request_and_token_limits() {
+        let now = Utc::now();
+        let mut ledger = Ledger::new(vec![
+            record("alice", 30, 600, 200, now),
+            record("alice", 10, 500, 200, now),
+            record("alice", 5, 0, REFUSED, now),
+            record("bob", 5, 5000, 200, now),
+            record("alice", 60 * 25, 9000, 200, now),
+        ], now);
+        assert_eq!(ledger.used("alice", now), (1100, 2));
+
+        let hourly = Budget { tokens_per_day: None, requests_per_hour: Some(2) };
+        let over = ledger.check("alice", hourly, now).unwrap_err();
+        assert_eq!(over.retry_after_secs, 30 * 60);
+
+        let daily = Budget { tokens_per_day: Some(1000), requests_per_hour: None };
+        let over = ledger.check("alice", daily, now).unwrap_err();
+        assert_eq!(over.retry_after_secs, (24 * 60 - 30) * 60);
+        assert_eq!(ledger.remaining_tokens("alice", daily, now), Some(0));
+        assert!(ledger.check("carol", daily, now).is_ok());
+    }
+
+    #[test]
+    fn test_concurrent_admissions() {
+        let now = Utc::now();
+        let mut ledger = Ledger::new(Vec::new(), now);
+        let hourly = Budget { tokens_per_day: None, requests_per_hour: Some(1) };
+        let first = ledger.admit("alice", hourly, now, 10, None).unwrap();
+        assert!(ledger.admit("alice", hourly, now, 10, None).is_err());
+        ledger.release(first.id);
+        assert!(ledger.admit("alice", hourly, now, 10, None).is_ok());
+
+        let daily = Budget { tokens_per_day: Some(1000), requests_per_hour: None };
+        let first = ledger.admit("bob", daily, now, 100, Some(500)).unwrap();
+        assert_eq!(first.max_tokens, Some(500));
+        let second = ledger.admit("bob", daily, now, 100, None).unwrap();
+        assert_eq!(second.max_tokens, Some(300));
+        assert!(ledger.admit("bob", daily, now, 100, None).is_err());
+
+        ledger.settle(first.id, record("bob", 0, 150, 200, now));
+        assert_eq!(ledger.used("bob", now), (550, 2));
+    }
+
+    #[test]
+    fn test_budget_fallback_and_summary() {
+        let default = Budget { tokens_per_day: Some(100_000), requests_per_hour: Some(60) };
+        let mut own = Budget::default();
+        own.update(None, Some(10));
+        assert_eq!(own.or(default), Budget { tokens_per_day: Some(100_000), requests_per_hour: Some(10) });
+        own.update(None, Some(0));
+        assert_eq!(own.or(default), Budget { tokens_per_day: Some(100_000), requests_per_hour: None });
+        assert_eq!(own.describe(), "unlimited tokens, unlimited requests");
+
+        let now = Utc::now();
+        let records = vec![record("alice", 5, 10, 200, now), record("alice", 4, 0, REFUSED, now), record("alice", 60 * 48, 10, 200, now)];
+        let rows = summarize(&records, parse_since("24h", now).unwrap());
+        assert_eq!(rows.len(), 1);
+        assert_eq!((rows[0].requests, rows[0].refused, rows[0].prompt_tokens), (1, 1, 5));
+        assert!(parse_since("7w", now).is_err());
+        assert!(parse_since("", now).is_err());
+        assert!(parse_since("h", now).is_err());
+        assert_eq!(parse_since("2024-05-01", now).unwrap().to_rfc3339(), "2024-05-01T00:00:00+00:00");
+    }
+}
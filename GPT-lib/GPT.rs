@@ -53,7 +53,7 @@ pub async fn enhanced_let_command(
     }
     
     // Fall back to existing LET implementation
-    letcmd::run(workspace, target, deploy, false, false, build, test, false, false, args, None, 1).await
+    letcmd::run(workspace, target, None, deploy, false, false, build, test, false, false, false, args, None, 1, None, None).await
 }
 
 /// Handle GPT-specific LET commands
@@ -100,9 +100,14 @@ async fn handle_gpt_let_command(
                     host: "localhost".to_string(),
                     gpu_layers: None,
                     threads: None,
-                    context: 2048,
+                    numa_node: None,
+                    context: None,
                     creativity,
-                    backend: "ollama".to_string(),
+                    backend: None,
+                    explain: false,
+                    foreground: false,
+                    replicas: None,
+                    routing: None,
                 };
                 
                 gpt_manager.serve_model(&cmd).await?;
@@ -132,9 +137,14 @@ async fn handle_gpt_let_command(
             host: "localhost".to_string(),
             gpu_layers: None,
             threads: None,
-            context: 2048,
+            numa_node: None,
+            context: None,
             creativity,
-            backend: "ollama".to_string(),
+            backend: None,
+            explain: false,
+            foreground: false,
+            replicas: None,
+            routing: None,
         };
         
         gpt_manager.serve_model(&cmd).await?;
@@ -229,10 +239,11 @@ pub mod examples {
             model: "llama2".to_string(),
             version: None,
             source: "ollama".to_string(),
+            include: None,
             force: false,
         };
         
-        gpt_lib::handle_command(&workspace, install_cmd).await?;
+        gpt_lib::handle_command(&workspace, install_cmd, false).await?;
         
         // Serve the model with custom creativity
         let serve_cmd = gpt_lib::GptCommands::Serve {
@@ -242,12 +253,17 @@ pub mod examples {
             host: "localhost".to_string(),
             gpu_layers: None,
             threads: None,
-            context: 4096,
+            numa_node: None,
+            context: Some(4096),
             creativity: 0.8,
-            backend: "ollama".to_string(),
+            backend: None,
+            explain: false,
+            foreground: false,
+            replicas: None,
+            routing: None,
         };
         
-        gpt_lib::handle_command(&workspace, serve_cmd).await?;
+        gpt_lib::handle_command(&workspace, serve_cmd, false).await?;
         
         println!("✅ Model serving example completed");
         Ok(())
@@ -288,9 +304,10 @@ pub mod examples {
                 model: model.to_string(),
                 version: None,
                 source: "ollama".to_string(),
+                include: None,
                 force: false,
             };
-            gpt_lib::handle_command(&workspace, install_cmd).await?;
+            gpt_lib::handle_command(&workspace, install_cmd, false).await?;
         }
         
         // Serve models on different ports
@@ -303,12 +320,17 @@ pub mod examples {
                 host: "localhost".to_string(),
                 gpu_layers: None,
                 threads: None,
-                context: 2048,
+                numa_node: None,
+                context: None,
                 creativity: 0.7,
-                backend: "ollama".to_string(),
+                backend: None,
+                explain: false,
+                foreground: false,
+                replicas: None,
+                routing: None,
             };
             
-            gpt_lib::handle_command(&workspace, serve_cmd).await?;
+            gpt_lib::handle_command(&workspace, serve_cmd, false).await?;
             println!("✅ {} serving on port {}", model, port);
         }
         
@@ -318,7 +340,7 @@ pub mod examples {
             format: "table".to_string(),
         };
         
-        gpt_lib::handle_command(&workspace, list_cmd).await?;
+        gpt_lib::handle_command(&workspace, list_cmd, false).await?;
         
         println!("✅ Multi-model serving example completed");
         Ok(())
@@ -385,9 +407,12 @@ rcm gpt status llama2 --detailed
 # Generate text
 rcm gpt generate llama2 "Write a story about AI" --max-tokens 200
 rcm gpt chat llama2 --interactive
+rcm gpt chat llama2 --interactive --system "You are a Rust expert" --session rust-help
+rcm gpt chat llama2 "Summarize this repo" --session rust-help
 
 # Install from different sources
 rcm gpt install microsoft/DialoGPT-medium --source huggingface
+rcm gpt install TheBloke/Mistral-7B-Instruct-v0.2-GGUF --source huggingface --include Q5_K_M
 rcm gpt install llama2:13b --source ollama
 
 # Multi-model serving
@@ -424,7 +449,17 @@ rcm let gpt serve llama2 --deploy --arm-accelerated
 # Custom backends and formats
 rcm gpt install ./my-model.gguf --source local
 rcm gpt serve my-model --backend llamacpp --deploy
+rcm gpt serve my-model --deploy --explain   # auto-select and explain the backend
 rcm gpt serve onnx-model --backend onnx --deploy
+
+# Persistent services
+rcm gpt service install llama2 --restart always --memory-max 8G --env OLLAMA_KEEP_ALIVE=24h
+rcm gpt service status llama2
+rcm gpt service remove llama2
+
+# Kubernetes
+rcm gpt k8s generate llama2 --namespace ml --max-replicas 4 --gpus 1
+rcm gpt k8s generate llama2 --helm --stdout > values.yaml
 "#;
 }
 
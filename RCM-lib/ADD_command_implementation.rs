@@ -7,10 +7,18 @@ use console::style;
 use dialoguer::{Confirm, Select};
 use regex::Regex;
 use crate::workspace::Workspace;
-use crate::npm::{NpmManager, NpmManagerType};
+use crate::gem::GemManager;
+use crate::isolation;
+use crate::mirrors;
+use crate::platform;
+use crate::npm::{is_dist_tag, resolve_manager_type, split_package_spec, NpmManager, NpmManagerType};
 use crate::ppm::ComposerManager;
 use crate::system::SystemManager;
-use crate::util::validate_package_name;
+use crate::telemetry;
+use crate::transaction::Transaction;
+use crate::util::{self, validate_package_name};
+use crate::version_picker;
+use crate::webhooks;
 
 /// Add a package to the workspace
 pub async fn run(
@@ -18,6 +26,7 @@ pub async fn run(
     spec: &str,
     manager: Option<&str>,
     dev: bool,
+    platforms: &[String],
 ) -> Result<()> {
     println!("{}", style(format!("📦 Adding package: {}", spec)).cyan().bold());
     
@@ -44,20 +53,32 @@ pub async fn run(
         ));
     }
     
-    // Install package using appropriate manager
-    match target_manager.as_str() {
-        "cargo" => install_cargo_package(workspace, &package_name, &version, dev).await?,
-        "npm" => install_npm_package(workspace, &package_name, &version, dev).await?,
-        "composer" => install_composer_package(workspace, &package_name, &version, dev).await?,
-        "system" => install_system_package(workspace, &package_name).await?,
-        _ => return Err(anyhow!("Unsupported package manager: {}", target_manager)),
-    }
+    // Offer a compatible release up front instead of letting the resolver fail
+    let version = match version_picker::pick(workspace, &target_manager, &package_name, &version).await? {
+        Some(picked) => picked,
+        None => version,
+    };
     
-    // Update workspace manifest
-    let mut workspace_mut = workspace.clone();
-    workspace_mut.add_dependency(&package_name, &version, &target_manager, dev).await?;
+    // Manifests and lockfiles are restored if the install or the manifest update fails
+    let transaction = Transaction::begin(workspace.root(), &format!("add {}", spec), &[target_manager.clone()]).await?;
+    let result = telemetry::traced(
+        telemetry::manager_span("add", &target_manager),
+        install_and_record(workspace, &target_manager, &package_name, &version, dev, platforms),
+    ).await;
+    transaction.finish(result).await?;
+    
+    if util::is_dry_run() {
+        return Ok(());
+    }
     
     println!("{}", style(format!("✅ Successfully added {} ({})", package_name, target_manager)).green().bold());
+    webhooks::emit(workspace.root(), webhooks::DEPENDENCY_ADDED, serde_json::json!({
+        "name": package_name,
+        "version": version,
+        "manager": target_manager,
+        "dev": dev,
+        "platforms": platforms,
+    })).await;
     
     // Suggest related packages
     suggest_related_packages(&target_manager, &package_name).await?;
@@ -65,29 +86,68 @@ pub async fn run(
     Ok(())
 }
 
+/// Install the package and record it in the workspace manifest
+async fn install_and_record(
+    workspace: &Workspace,
+    target_manager: &str,
+    package_name: &str,
+    version: &str,
+    dev: bool,
+    platforms: &[String],
+) -> Result<()> {
+    let here = platform::Target::current();
+    if platforms.is_empty() {
+        match target_manager {
+            "cargo" => install_cargo_package(workspace, package_name, version, dev, None).await?,
+            "npm" => install_npm_package(workspace, package_name, version, dev).await?,
+            "composer" => install_composer_package(workspace, package_name, version, dev).await?,
+            "bundler" => install_bundler_package(workspace, package_name, version, dev).await?,
+            "system" => install_system_package(workspace, package_name).await?,
+            _ => return Err(anyhow!("Unsupported package manager: {}", target_manager)),
+        }
+    } else if !platform::SCOPED_MANAGERS.contains(&target_manager) {
+        return Err(anyhow!(
+            "{} has no per-platform dependencies; --platform works with {}",
+            target_manager, platform::SCOPED_MANAGERS.join(", ")
+        ));
+    } else if target_manager == "cargo" {
+        // Cargo.toml holds it as a target-specific dependency, so every machine agrees
+        install_cargo_package(workspace, package_name, version, dev, platform::cargo_cfg(platforms).as_deref()).await?;
+    } else if !here.allows(platforms) {
+        println!("{}", style(format!("⏭  Not installing on {} (declared for {})", here, platforms.join(","))).dim());
+    } else if target_manager == "npm" {
+        // Kept out of package.json so other platforms don't install it; `ensure` reinstalls it here
+        install_npm_unsaved(workspace, &[npm_spec(package_name, version)]).await?;
+    } else {
+        install_system_package(workspace, package_name).await?;
+    }
+    
+    // Update workspace manifest
+    if util::is_dry_run() {
+        let only = if platforms.is_empty() { String::new() } else { format!(" for {}", platforms.join(",")) };
+        println!("[dry-run] record {}@{} ({}){} in the workspace manifest", package_name, version, target_manager, only);
+        return Ok(());
+    }
+    let mut workspace_mut = workspace.clone();
+    workspace_mut.add_dependency(package_name, version, target_manager, dev, platforms).await
+}
+
 /// Parse package specification (name[@version] or manager:name[@version])
 fn parse_package_spec(spec: &str) -> Result<(String, String, Option<String>)> {
-    // Check for manager prefix (e.g., npm:package@1.0.0)
-    if let Some((manager, rest)) = spec.split_once(':') {
-        let (name, version) = if let Some((n, v)) = rest.split_once('@') {
-            (n.to_string(), v.to_string())
-        } else {
-            (rest.to_string(), "latest".to_string())
-        };
-        
-        validate_package_name(&name)?;
-        return Ok((name, version, Some(manager.to_string())));
-    }
-    
-    // Parse name@version
-    let (name, version) = if let Some((n, v)) = spec.split_once('@') {
-        (n.to_string(), v.to_string())
-    } else {
-        (spec.to_string(), "latest".to_string())
+    // Check for manager prefix (e.g., npm:package@1.0.0); `gem:` is an alias for bundler
+    let (manager, rest) = match spec.split_once(':') {
+        Some(("gem", rest)) => (Some("bundler".to_string()), rest),
+        Some((manager, rest)) => (Some(manager.to_string()), rest),
+        None => (None, spec),
     };
     
-    validate_package_name(&name)?;
-    Ok((name, version, None))
+    // Parse name@version; scoped npm names keep their leading '@' and the
+    // version may be a dist-tag channel such as `next` or `beta`
+    let (name, version) = split_package_spec(rest);
+    let version = version.filter(|v| !v.is_empty()).unwrap_or("latest");
+    
+    validate_package_name(name)?;
+    Ok((name.to_string(), version.to_string(), manager))
 }
 
 /// Auto-detect appropriate package manager
@@ -118,6 +178,13 @@ async fn detect_manager(workspace: &Workspace, package_name: &str) -> Result<Str
         }
     }
     
+    // Ruby gem patterns
+    if enabled_managers.contains(&"bundler".to_string()) {
+        if is_gem_package(package_name) {
+            candidates.push(("bundler", 85));
+        }
+    }
+    
     // System package patterns
     if enabled_managers.contains(&"system".to_string()) {
         if is_system_package(package_name) {
@@ -195,6 +262,16 @@ fn is_composer_package(name: &str) -> bool {
     php_patterns.iter().any(|&pattern| name.contains(pattern))
 }
 
+/// Check if package name matches Ruby gem patterns
+fn is_gem_package(name: &str) -> bool {
+    let ruby_patterns = [
+        "rails", "rspec", "rubocop", "sinatra", "devise", "sidekiq", "puma",
+        "nokogiri", "pry", "rake", "capybara", "factory_bot", "activerecord",
+    ];
+    
+    ruby_patterns.iter().any(|&pattern| name.contains(pattern))
+}
+
 /// Check if package name matches system package patterns
 fn is_system_package(name: &str) -> bool {
     let system_packages = [
@@ -224,6 +301,10 @@ fn detect_by_workspace_context(workspace: &Workspace) -> Vec<(&str, i32)> {
         candidates.push(("composer", 80));
     }
     
+    if workspace.root().join("Gemfile").exists() {
+        candidates.push(("bundler", 80));
+    }
+    
     // Always consider system as fallback
     candidates.push(("system", 50));
     
@@ -239,6 +320,7 @@ async fn interactive_manager_selection(enabled_managers: &[String]) -> Result<St
             "cargo" => "🦀 Cargo (Rust)".to_string(),
             "npm" => "📦 NPM (Node.js)".to_string(),
             "composer" => "🐘 Composer (PHP)".to_string(),
+            "bundler" => "💎 Bundler (Ruby)".to_string(),
             "system" => "🔧 System (OS packages)".to_string(),
             _ => format!("📋 {}", m),
         }
@@ -259,6 +341,7 @@ async fn install_cargo_package(
     name: &str,
     version: &str,
     dev: bool,
+    target: Option<&str>,
 ) -> Result<()> {
     let cargo_toml = workspace.root().join("Cargo.toml");
     if !cargo_toml.exists() {
@@ -269,7 +352,10 @@ async fn install_cargo_package(
     
     let mut cmd = tokio::process::Command::new("cargo");
     cmd.current_dir(workspace.root());
+    cmd.envs(isolation::env_vars(workspace.root()));
+    cmd.envs(mirrors::env_vars());
     cmd.arg("add");
+    cmd.args(mirrors::cargo_args());
     cmd.arg(if version == "latest" {
         name.to_string()
     } else {
@@ -279,8 +365,14 @@ async fn install_cargo_package(
     if dev {
         cmd.arg("--dev");
     }
+    if let Some(target) = target {
+        cmd.args(["--target", target]);
+    }
+    if util::skip_in_dry_run(cmd.as_std()) {
+        return Ok(());
+    }
     
-    let output = cmd.output().await
+    let output = util::traced_output(&mut cmd).await
         .context("Failed to execute cargo add")?;
     
     if !output.status.success() {
@@ -307,20 +399,49 @@ async fn install_npm_package(
     }
     
     println!("{}", style("🔧 Installing NPM package...").blue());
+    if version != "latest" && is_dist_tag(version) {
+        // The channel itself is recorded in the manifest so updates follow it
+        println!("{}", style(format!("📡 Tracking the '{}' channel of {}", version, name)).blue());
+    }
     
-    let npm_manager = NpmManager::new(workspace.root(), NpmManagerType::Npm);
-    let packages = vec![if version == "latest" {
-        name.to_string()
-    } else {
-        format!("{}@{}", name, version)
-    }];
-    
-    npm_manager.install(&packages, dev, false).await?;
+    let npm_manager = NpmManager::new(workspace.root(), resolve_manager_type(workspace.root(), None).await?);
+    npm_manager.install(&[npm_spec(name, version)], dev, false).await?;
     
     println!("{}", style("✅ NPM package installed").green());
     Ok(())
 }
 
+/// `name@version`, or just `name` for latest
+fn npm_spec(name: &str, version: &str) -> String {
+    if version == "latest" {
+        name.to_string()
+    } else {
+        format!("{}@{}", name, version)
+    }
+}
+
+/// Install npm packages limited to this platform, leaving package.json alone
+pub(crate) async fn install_npm_unsaved(workspace: &Workspace, packages: &[String]) -> Result<()> {
+    println!("{}", style(format!("🔧 Installing {} for {}...", packages.join(", "), platform::Target::current())).blue());
+    let npm_manager = NpmManager::new(workspace.root(), resolve_manager_type(workspace.root(), None).await?);
+    npm_manager.install_unsaved(packages).await
+}
+
+/// Platform-limited npm dependencies that apply here but are missing from node_modules
+pub(crate) async fn missing_platform_npm(workspace: &Workspace) -> Vec<String> {
+    let npm = NpmManager::new(workspace.root(), NpmManagerType::Npm);
+    let mut missing = Vec::new();
+    for (name, spec) in workspace.list_dependencies() {
+        if spec.manager != "npm" || spec.platforms.is_empty() || !platform::applies(&spec.platforms) {
+            continue;
+        }
+        if npm.installed_version(&name).await.is_none() {
+            missing.push(npm_spec(&name, &spec.version));
+        }
+    }
+    missing
+}
+
 /// Install Composer package
 async fn install_composer_package(
     workspace: &Workspace,
@@ -348,6 +469,32 @@ async fn install_composer_package(
     Ok(())
 }
 
+/// Install Ruby gem
+async fn install_bundler_package(
+    workspace: &Workspace,
+    name: &str,
+    version: &str,
+    dev: bool,
+) -> Result<()> {
+    let gem = GemManager::new(workspace.root());
+    if !gem.has_gemfile() {
+        return Err(anyhow!("No Gemfile found. Run 'bundle init' first."));
+    }
+    
+    println!("{}", style("🔧 Installing Ruby gem...").blue());
+    
+    let gems = vec![if version == "latest" {
+        name.to_string()
+    } else {
+        format!("{}@{}", name, version)
+    }];
+    
+    gem.add(&gems, dev.then_some("development")).await?;
+    
+    println!("{}", style("✅ Ruby gem installed").green());
+    Ok(())
+}
+
 /// Install system package
 async fn install_system_package(workspace: &Workspace, name: &str) -> Result<()> {
     println!("{}", style("🔧 Installing system package...").blue());
@@ -376,6 +523,7 @@ async fn suggest_related_packages(manager: &str, package_name: &str) -> Result<(
         "cargo" => get_cargo_suggestions(package_name),
         "npm" => get_npm_suggestions(package_name),
         "composer" => get_composer_suggestions(package_name),
+        "bundler" => get_gem_suggestions(package_name),
         "system" => get_system_suggestions(package_name),
         _ => Vec::new(),
     };
@@ -480,6 +628,36 @@ fn get_composer_suggestions(package_name: &str) -> Vec<String> {
     suggestions
 }
 
+/// Get Ruby gem suggestions
+fn get_gem_suggestions(package_name: &str) -> Vec<String> {
+    let mut suggestions = Vec::new();
+    
+    match package_name {
+        "rails" => {
+            suggestions.extend([
+                "rspec-rails".to_string(),
+                "rubocop-rails".to_string(),
+                "puma".to_string(),
+            ]);
+        }
+        "sinatra" => {
+            suggestions.extend([
+                "puma".to_string(),
+                "rack-test".to_string(),
+            ]);
+        }
+        "rspec" => {
+            suggestions.extend([
+                "simplecov".to_string(),
+                "factory_bot".to_string(),
+            ]);
+        }
+        _ => {}
+    }
+    
+    suggestions
+}
+
 /// Get system package suggestions
 fn get_system_suggestions(package_name: &str) -> Vec<String> {
     let mut suggestions = Vec::new();
@@ -0,0 +1,45 @@
+//! Apply command implementation
+//!
+//! Recomputes the plan and applies only the delta
+
+use anyhow::{anyhow, Result};
+use console::style;
+use dialoguer::Confirm;
+use crate::planner;
+use crate::workspace::Workspace;
+
+/// Apply the planned changes
+pub async fn run(workspace: &Workspace, managers: Option<Vec<String>>, force: bool) -> Result<()> {
+    let target_managers = managers.unwrap_or_else(|| workspace.enabled_managers());
+
+    if target_managers.is_empty() {
+        return Err(anyhow!("No package managers enabled. Run 'rcm init' to configure managers."));
+    }
+
+    let plan = planner::compute_plan(workspace, &target_managers).await?;
+
+    if plan.is_empty() {
+        println!("{}", style("✅ Nothing to apply. Workspace matches the manifest.").green().bold());
+        return Ok(());
+    }
+
+    println!("{}", style("📋 Execution plan").cyan().bold());
+    plan.print_text();
+
+    if !force {
+        let confirm = Confirm::new()
+            .with_prompt("Apply these changes?")
+            .default(false)
+            .interact()?;
+
+        if !confirm {
+            println!("{}", style("Apply cancelled").yellow());
+            return Ok(());
+        }
+    }
+
+    planner::apply_plan(workspace, &plan).await?;
+
+    println!("{}", style("✅ Plan applied successfully").green().bold());
+    Ok(())
+}
@@ -0,0 +1,371 @@
+//! Artifact verification for downloads
+//!
+//! Every file RCM downloads is hashed and recorded in `<data_dir>/artifacts.jsonl`.
+//! With `core.verify_checksums` an expected SHA-256 must match, and with
+//! `security.verify_signatures` a detached minisign (`.minisig`) or GPG
+//! (`.asc`/`.sig`) signature, given explicitly or published next to the file,
+//! must verify against one of `security.trusted_keys`:
+//!
+//! - `RW...` — a minisign public key
+//! - `*.pub` — a minisign public key file
+//! - `*.asc`, `*.gpg` — an armored or binary GPG public key file
+//! - anything else — a full (40 or 64 hex digit) GPG fingerprint in the user's
+//!   keyring; short key IDs are ignored because they can be forged
+//!
+//! Failures block the install unless `security.allow_insecure` is set, in which
+//! case they are reported and recorded.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command as AsyncCommand;
+use crate::config::Config;
+use crate::download_cache::DownloadCache;
+use crate::util;
+
+/// Verification log inside the RCM data directory
+pub const ARTIFACT_LOG: &str = "artifacts.jsonl";
+
+/// Detached signature suffixes probed next to a download, in order
+const SIGNATURE_SUFFIXES: &[&str] = &[".minisig", ".asc", ".sig"];
+
+/// A key signatures are checked against
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrustedKey {
+    Minisign(String),
+    MinisignFile(PathBuf),
+    GpgFile(PathBuf),
+    GpgFingerprint(String),
+}
+
+/// Classify a `security.trusted_keys` entry
+pub fn parse_trusted_key(entry: &str) -> TrustedKey {
+    let entry = entry.trim();
+    if entry.starts_with("RW") && !entry.contains(['/', '\\']) {
+        TrustedKey::Minisign(entry.to_string())
+    } else if entry.ends_with(".pub") {
+        TrustedKey::MinisignFile(PathBuf::from(entry))
+    } else if entry.ends_with(".asc") || entry.ends_with(".gpg") {
+        TrustedKey::GpgFile(PathBuf::from(entry))
+    } else {
+        TrustedKey::GpgFingerprint(entry.replace(' ', ""))
+    }
+}
+
+/// Whether `fingerprint` is a whole v4 or v5 GPG fingerprint rather than a key ID
+pub fn is_full_fingerprint(fingerprint: &str) -> bool {
+    matches!(fingerprint.len(), 40 | 64) && fingerprint.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Signature outcome recorded with an artifact
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// No signature was given or published
+    Unsigned,
+    /// Verified with the named trusted key
+    Verified(String),
+    /// A signature exists but no trusted key could check it
+    Unchecked,
+    Failed,
+    /// Signature checks are off (`security.verify_signatures = false`)
+    Skipped,
+}
+
+/// One line of the artifact log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactRecord {
+    pub url: String,
+    pub path: PathBuf,
+    pub sha256: String,
+    pub size: u64,
+    pub checksum_verified: bool,
+    pub signature: SignatureStatus,
+    /// Accepted despite a failed check because of `allow_insecure`; blocked installs are `false`
+    #[serde(default)]
+    pub insecure: bool,
+    pub recorded_at: String,
+}
+
+/// What a download is checked against
+#[derive(Debug, Clone, Default)]
+pub struct Expectations {
+    pub sha256: Option<String>,
+    /// Signature file path or URL; probed next to the download when absent
+    pub signature: Option<String>,
+}
+
+/// Settings from the `core` and `security` config sections
+#[derive(Debug, Clone)]
+pub struct Verifier {
+    verify_checksums: bool,
+    verify_signatures: bool,
+    allow_insecure: bool,
+    trusted_keys: Vec<TrustedKey>,
+    log: PathBuf,
+}
+
+impl Verifier {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            verify_checksums: config.core.verify_checksums,
+            verify_signatures: config.security.verify_signatures,
+            allow_insecure: config.security.allow_insecure,
+            trusted_keys: config.security.trusted_keys.iter()
+                .map(|k| parse_trusted_key(k))
+                .filter(|key| match key {
+                    TrustedKey::GpgFingerprint(fingerprint) if !is_full_fingerprint(fingerprint) => {
+                        tracing::warn!("Ignoring trusted key '{}': use the full GPG fingerprint, not a key ID", fingerprint);
+                        false
+                    }
+                    _ => true,
+                })
+                .collect(),
+            log: config.data_dir().join(ARTIFACT_LOG),
+        }
+    }
+
+    /// The verifier configured in the user's RCM config, for callers without a `Config`
+    pub async fn from_user_config() -> Self {
+        Self::new(&Config::load(None).await.unwrap_or_default())
+    }
+
+    /// Hash `path`, check it against `expect`, and record the result
+    pub async fn verify(&self, url: &str, path: &Path, expect: &Expectations) -> Result<ArtifactRecord> {
+        let sha256 = util::get_file_hash(path).await?;
+        let size = fs::metadata(path).await?.len();
+        let mut failures = Vec::new();
+
+        let checksum_verified = match expect.sha256.as_deref() {
+            Some(expected) if self.verify_checksums => {
+                let matches = expected.trim().eq_ignore_ascii_case(&sha256);
+                if !matches {
+                    failures.push(format!("SHA-256 is {}, expected {}", sha256, expected.trim()));
+                }
+                matches
+            }
+            _ => false,
+        };
+
+        let signature = if self.verify_signatures {
+            self.check_signature(url, path, expect.signature.as_deref()).await?
+        } else {
+            SignatureStatus::Skipped
+        };
+        match &signature {
+            SignatureStatus::Failed => failures.push("signature does not verify against any trusted key".to_string()),
+            SignatureStatus::Unchecked => println!(
+                "  {} {} is signed but security.trusted_keys has no key for it",
+                style("⚠").yellow(), url
+            ),
+            _ => {}
+        }
+
+        let record = ArtifactRecord {
+            url: url.to_string(),
+            path: path.to_path_buf(),
+            sha256,
+            size,
+            checksum_verified,
+            signature,
+            insecure: !failures.is_empty() && self.allow_insecure,
+            recorded_at: chrono::Utc::now().to_rfc3339(),
+        };
+        if let Err(e) = self.record(&record).await {
+            tracing::warn!("Failed to record artifact {}: {}", url, e);
+        }
+
+        if !failures.is_empty() {
+            let message = format!("Verification of {} failed: {}", url, failures.join("; "));
+            if !self.allow_insecure {
+                fs::remove_file(path).await.ok();
+                return Err(anyhow!("{}. Set security.allow_insecure to install anyway", message));
+            }
+            println!("  {} {} (allowed by security.allow_insecure)", style("⚠").yellow(), message);
+        }
+        Ok(record)
+    }
+
+    /// Fetch the signature (explicit, or probed next to `url`) and check it
+    async fn check_signature(&self, url: &str, path: &Path, signature: Option<&str>) -> Result<SignatureStatus> {
+        // Probing costs a request per suffix, so only do it when a key could use the result
+        if signature.is_none() && self.trusted_keys.is_empty() {
+            return Ok(SignatureStatus::Unsigned);
+        }
+        let Some((sig_path, temp)) = self.fetch_signature(url, path, signature).await? else {
+            return Ok(SignatureStatus::Unsigned);
+        };
+        let status = self.check_with_keys(path, &sig_path).await;
+        if let Some(temp) = temp {
+            fs::remove_dir_all(temp).await.ok();
+        }
+        Ok(status)
+    }
+
+    async fn check_with_keys(&self, path: &Path, sig_path: &Path) -> SignatureStatus {
+        if self.trusted_keys.is_empty() {
+            return SignatureStatus::Unchecked;
+        }
+        let minisign = sig_path.extension().is_some_and(|ext| ext == "minisig");
+        for key in &self.trusted_keys {
+            let verified = match key {
+                TrustedKey::Minisign(_) | TrustedKey::MinisignFile(_) if minisign => minisign_verify(key, path, sig_path).await,
+                TrustedKey::GpgFile(_) | TrustedKey::GpgFingerprint(_) if !minisign => gpg_verify(key, path, sig_path).await,
+                _ => continue,
+            };
+            if verified {
+                return SignatureStatus::Verified(key_label(key));
+            }
+        }
+        SignatureStatus::Failed
+    }
+
+    /// Signature as a local file, plus the temp dir to remove when it was downloaded
+    async fn fetch_signature(&self, url: &str, path: &Path, signature: Option<&str>) -> Result<Option<(PathBuf, Option<PathBuf>)>> {
+        if let Some(signature) = signature.filter(|s| !util::is_valid_url(s)) {
+            let local = PathBuf::from(signature);
+            if !local.is_file() {
+                return Err(anyhow!("Signature file {} not found", local.display()));
+            }
+            return Ok(Some((local, None)));
+        }
+
+        let candidates: Vec<String> = match signature {
+            Some(signature) => vec![signature.to_string()],
+            None if util::is_valid_url(url) => SIGNATURE_SUFFIXES.iter().map(|suffix| format!("{}{}", url, suffix)).collect(),
+            None => return Ok(None),
+        };
+        let dir = util::create_temp_dir("rcm-signature").await?;
+        let base = path.file_name().and_then(|n| n.to_str()).unwrap_or("artifact");
+        for candidate in candidates {
+            let suffix = SIGNATURE_SUFFIXES.iter().find(|s| candidate.ends_with(*s)).copied().unwrap_or(".sig");
+            let dest = dir.join(format!("{}{}", base, suffix));
+            match util::download_file(&candidate, &dest).await {
+                Ok(()) => return Ok(Some((dest, Some(dir)))),
+                Err(e) if signature.is_some() => return Err(e.context(format!("Failed to download signature {}", candidate))),
+                Err(_) => continue,
+            }
+        }
+        Ok(None)
+    }
+
+    async fn record(&self, record: &ArtifactRecord) -> Result<()> {
+        if util::is_dry_run() || util::is_read_only() {
+            return Ok(());
+        }
+        if let Some(parent) = self.log.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(&self.log).await
+            .with_context(|| format!("Failed to open {}", self.log.display()))?;
+        file.write_all(format!("{}\n", serde_json::to_string(record)?).as_bytes()).await?;
+        Ok(())
+    }
+}
+
+fn key_label(key: &TrustedKey) -> String {
+    match key {
+        TrustedKey::Minisign(key) => format!("minisign:{}", &key[..key.len().min(16)]),
+        TrustedKey::MinisignFile(path) | TrustedKey::GpgFile(path) => path.display().to_string(),
+        TrustedKey::GpgFingerprint(fingerprint) => format!("gpg:{}", fingerprint),
+    }
+}
+
+async fn succeeds(cmd: &mut AsyncCommand) -> bool {
+    cmd.stdout(std::process::Stdio::null()).stderr(std::process::Stdio::null());
+    cmd.status().await.map(|s| s.success()).unwrap_or(false)
+}
+
+async fn minisign_verify(key: &TrustedKey, file: &Path, signature: &Path) -> bool {
+    let mut cmd = AsyncCommand::new("minisign");
+    cmd.arg("-Vq").arg("-m").arg(file).arg("-x").arg(signature);
+    match key {
+        TrustedKey::Minisign(key) => cmd.arg("-P").arg(key),
+        TrustedKey::MinisignFile(path) => cmd.arg("-p").arg(path),
+        _ => return false,
+    };
+    succeeds(&mut cmd).await
+}
+
+async fn gpg_verify(key: &TrustedKey, file: &Path, signature: &Path) -> bool {
+    match key {
+        TrustedKey::GpgFile(key_file) => {
+            // A throwaway keyring holding only the trusted key
+            let Ok(home) = util::create_temp_dir("rcm-gnupg").await else {
+                return false;
+            };
+            let imported = succeeds(AsyncCommand::new("gpg").arg("--homedir").arg(&home).arg("--batch").arg("--import").arg(key_file)).await;
+            let verified = imported && succeeds(
+                AsyncCommand::new("gpg").arg("--homedir").arg(&home).arg("--batch").arg("--verify").arg(signature).arg(file)
+            ).await;
+            fs::remove_dir_all(&home).await.ok();
+            verified
+        }
+        TrustedKey::GpgFingerprint(fingerprint) => {
+            let output = AsyncCommand::new("gpg")
+                .args(["--batch", "--status-fd", "1", "--verify"])
+                .arg(signature)
+                .arg(file)
+                .output()
+                .await;
+            let Ok(output) = output else {
+                return false;
+            };
+            // Only a good signature from this exact key counts, not any key in the keyring;
+            // VALIDSIG carries the signing (sub)key's fingerprint and, last, the primary key's
+            let status = String::from_utf8_lossy(&output.stdout);
+            output.status.success() && status.lines().any(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                fields.len() > 2
+                    && fields[..2] == ["[GNUPG:]", "VALIDSIG"]
+                    && [fields[2], fields[fields.len() - 1]].iter().any(|fpr| fpr.eq_ignore_ascii_case(fingerprint))
+            })
+        }
+        _ => false,
+    }
+}
+
+/// Download `url` to `dest` through the download cache, then verify and record it
+pub async fn download(url: &str, dest: &Path, expect: &Expectations) -> Result<ArtifactRecord> {
+    let config = Config::load(None).await.unwrap_or_default();
+    // The cache looks up by the expected digest; with allow_insecure a mismatch
+    // must reach the verifier to be recorded rather than fail in the cache
+    let digest = expect.sha256.as_deref()
+        .filter(|_| config.core.verify_checksums && !config.security.allow_insecure);
+    DownloadCache::open(&config).fetch(url, digest, dest).await?;
+    Verifier::new(&config).verify(url, dest, expect).await
+}
+
+/// Download `url` through [`download`] into a private temp dir and return its contents
+pub async fn download_bytes(url: &str, expect: &Expectations) -> Result<Vec<u8>> {
+    let dir = tempfile::tempdir().context("Failed to create a temp directory")?;
+    let name = url.rsplit('/').next().filter(|n| !n.is_empty() && !n.contains(['?', '#'])).unwrap_or("download");
+    let path = dir.path().join(name);
+    download(url, &path, expect).await?;
+    fs::read(&path).await.with_context(|| format!("Failed to read {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_trusted_key() {
+        assert_eq!(
+            parse_trusted_key("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3"),
+            TrustedKey::Minisign("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3".to_string())
+        );
+        assert_eq!(parse_trusted_key("~/keys/release.pub"), TrustedKey::MinisignFile(PathBuf::from("~/keys/release.pub")));
+        assert_eq!(parse_trusted_key("/etc/rcm/vendor.asc"), TrustedKey::GpgFile(PathBuf::from("/etc/rcm/vendor.asc")));
+        assert_eq!(
+            parse_trusted_key("7C3B 1A2D 99E0 4F51"),
+            TrustedKey::GpgFingerprint("7C3B1A2D99E04F51".to_string())
+        );
+        assert!(!is_full_fingerprint("7C3B1A2D99E04F51"));
+        assert!(is_full_fingerprint("9DC858229FC7DD38854AE2D88D81803C0EBFCD88"));
+        assert!(!is_full_fingerprint("9DC858229FC7DD38854AE2D88D81803C0EBFCDZZ"));
+    }
+}
@@ -0,0 +1,225 @@
+//! Audit command implementation
+//!
+//! Scans dependencies for advisories and applies accepted-risk exceptions
+
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+use console::style;
+use std::collections::BTreeMap;
+use tabled::{Table, Tabled};
+use crate::audit::{self, AuditCommands, AuditReport, ExceptionCommands, Severity};
+use crate::audit_exceptions::{AuditException, AuditExceptions};
+use crate::audit_watch;
+use crate::workspace::Workspace;
+
+#[derive(Tabled)]
+struct FindingRow {
+    #[tabled(rename = "Severity")]
+    severity: String,
+    #[tabled(rename = "Package")]
+    package: String,
+    #[tabled(rename = "Version")]
+    version: String,
+    #[tabled(rename = "Manager")]
+    manager: String,
+    #[tabled(rename = "Advisory")]
+    advisory: String,
+    #[tabled(rename = "Title")]
+    title: String,
+}
+
+/// Parse `--fail-on`: `any` fails on every finding, `none` never fails
+fn parse_threshold(fail_on: &str) -> Result<Option<Option<Severity>>> {
+    match fail_on.to_lowercase().as_str() {
+        "any" => Ok(Some(None)),
+        "none" => Ok(None),
+        label => match Severity::from_label(label) {
+            Severity::Unknown => Err(anyhow!(
+                "Invalid --fail-on '{}'. Use any, none, low, medium, high or critical", fail_on
+            )),
+            severity => Ok(Some(Some(severity))),
+        },
+    }
+}
+
+/// Run the audit, or an exception subcommand
+pub async fn run(
+    workspace: &Workspace,
+    managers: Option<Vec<String>>,
+    format: &str,
+    fail_on: &str,
+    cmd: Option<AuditCommands>,
+) -> Result<()> {
+    match cmd {
+        Some(AuditCommands::Exception { cmd }) => return handle_exception(workspace, cmd).await,
+        Some(AuditCommands::Watch { interval, once, webhook, desktop }) => {
+            return audit_watch::run(workspace, &interval, once, webhook, desktop).await;
+        }
+        None => {}
+    }
+
+    let threshold = parse_threshold(fail_on)?;
+    let target_managers = managers.unwrap_or_else(|| workspace.enabled_managers());
+    if target_managers.is_empty() {
+        return Err(anyhow!("No package managers enabled. Run 'rcm init' to configure managers."));
+    }
+
+    if format == "table" {
+        println!("{}", style("🛡️ Running security audit...").cyan().bold());
+    }
+
+    let exceptions = AuditExceptions::load(workspace.root()).await?;
+    let findings = audit::scan(workspace, &target_managers).await?;
+    let today = chrono::Local::now().date_naive();
+    let mut report = audit::classify(findings, &exceptions, today);
+    report.active.sort_by(|a, b| b.severity_level().cmp(&a.severity_level()));
+
+    match format {
+        "table" => print_table(&report),
+        "json" => {
+            let summary: BTreeMap<String, usize> = report.severity_counts()
+                .into_iter()
+                .map(|(severity, count)| (severity.to_string(), count))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+                "summary": summary,
+                "report": report,
+            }))?);
+        }
+        "sarif" => println!("{}", serde_json::to_string_pretty(&audit::to_sarif(&report))?),
+        _ => return Err(anyhow!("Unsupported format: {}. Use table, json or sarif", format)),
+    }
+
+    let failing = match threshold {
+        Some(threshold) => report.failing(threshold),
+        None => 0,
+    };
+    if failing > 0 {
+        return Err(anyhow!(
+            "{} vulnerabilities at or above the --fail-on threshold '{}' or of unknown severity ({} with expired exceptions, {} accepted)",
+            failing,
+            fail_on,
+            report.expired.len(),
+            report.accepted.len()
+        ));
+    }
+
+    if format == "table" {
+        println!(
+            "{}",
+            style(format!("✅ No vulnerabilities above the '{}' threshold ({} accepted)", fail_on, report.accepted.len()))
+                .green()
+                .bold()
+        );
+    }
+    Ok(())
+}
+
+fn print_table(report: &AuditReport) {
+    let rows: Vec<FindingRow> = report
+        .unaccepted()
+        .map(|vuln| FindingRow {
+            severity: vuln.severity_level().to_string(),
+            package: vuln.package.clone(),
+            version: vuln.version.clone().unwrap_or_default(),
+            manager: vuln.manager.clone(),
+            advisory: vuln.advisory.clone(),
+            title: vuln.title.clone(),
+        })
+        .collect();
+    if !rows.is_empty() {
+        println!("{}", Table::new(rows));
+    }
+
+    for (vuln, expired_on) in &report.expired {
+        println!(
+            "  {} {} [{}] {} - exception expired on {}",
+            style("✗").red(),
+            style(&vuln.package).bold(),
+            vuln.manager,
+            vuln.advisory,
+            expired_on
+        );
+    }
+
+    if !report.accepted.is_empty() {
+        println!();
+        println!("{}", style("📝 Accepted risks").yellow().bold());
+        for (vuln, justification, expires) in &report.accepted {
+            println!(
+                "  {} {} [{}] {} until {}: {}",
+                style("~").yellow(),
+                vuln.package,
+                vuln.manager,
+                vuln.advisory,
+                expires,
+                justification
+            );
+        }
+    }
+
+    let counts: Vec<String> = report
+        .severity_counts()
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(severity, count)| format!("{} {}", count, severity))
+        .collect();
+    println!();
+    if !counts.is_empty() {
+        println!("{} {}", style("Severity:").bold(), counts.join(", "));
+    }
+}
+
+async fn handle_exception(workspace: &Workspace, cmd: ExceptionCommands) -> Result<()> {
+    let mut exceptions = AuditExceptions::load(workspace.root()).await?;
+    let today = chrono::Local::now().date_naive();
+
+    match cmd {
+        ExceptionCommands::Add { package, advisory, expires, justification, manager } => {
+            let expires = NaiveDate::parse_from_str(&expires, "%Y-%m-%d")
+                .with_context(|| format!("Invalid expiry date '{}', expected YYYY-MM-DD", expires))?;
+            if expires < today {
+                return Err(anyhow!("Expiry date {} is in the past", expires));
+            }
+
+            exceptions.upsert(AuditException {
+                package: package.clone(),
+                advisory: advisory.clone(),
+                manager,
+                expires,
+                justification,
+                added_by: std::env::var("USER").or_else(|_| std::env::var("USERNAME")).ok(),
+                added_at: Some(today),
+            })?;
+            exceptions.save(workspace.root()).await?;
+
+            println!(
+                "{}",
+                style(format!("📝 Accepted {} for {} until {}", advisory, package, expires)).green()
+            );
+        }
+        ExceptionCommands::List => {
+            if exceptions.exceptions.is_empty() {
+                println!("No audit exceptions recorded");
+                return Ok(());
+            }
+            for e in &exceptions.exceptions {
+                let state = if e.is_active(today) {
+                    style(format!("until {}", e.expires)).green()
+                } else {
+                    style(format!("expired {}", e.expires)).red()
+                };
+                println!("  {} {} {}: {}", style(&e.package).bold(), e.advisory, state, e.justification);
+            }
+        }
+        ExceptionCommands::Remove { package, advisory } => {
+            if exceptions.remove(&package, &advisory) == 0 {
+                return Err(anyhow!("No exception for {} {}", package, advisory));
+            }
+            exceptions.save(workspace.root()).await?;
+            println!("{}", style(format!("🗑️ Removed exception for {} {}", package, advisory)).green());
+        }
+    }
+
+    Ok(())
+}
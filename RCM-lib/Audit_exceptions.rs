@@ -0,0 +1,189 @@
+//! Accepted-risk audit exceptions for RCM
+//!
+//! Stores per package+advisory exceptions with an expiry date and justification
+//! in `.rcm/audit-exceptions.toml`
+
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Location of the exceptions file relative to the workspace root
+pub const EXCEPTIONS_FILE: &str = ".rcm/audit-exceptions.toml";
+
+/// A single accepted-risk exception
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditException {
+    pub package: String,
+    /// Advisory ID (RUSTSEC-, GHSA-, CVE-, PKSA-)
+    pub advisory: String,
+    /// Restrict the exception to one manager
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manager: Option<String>,
+    /// Last day the exception applies (YYYY-MM-DD)
+    pub expires: NaiveDate,
+    pub justification: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub added_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub added_at: Option<NaiveDate>,
+}
+
+impl AuditException {
+    /// Whether the exception is still in force on `today`
+    pub fn is_active(&self, today: NaiveDate) -> bool {
+        today <= self.expires
+    }
+
+    /// Whether this exception covers the given finding
+    pub fn matches(&self, manager: &str, package: &str, advisory_ids: &[&str]) -> bool {
+        if let Some(m) = &self.manager {
+            if m != manager {
+                return false;
+            }
+        }
+        self.package.eq_ignore_ascii_case(package)
+            && advisory_ids.iter().any(|id| id.eq_ignore_ascii_case(&self.advisory))
+    }
+}
+
+/// Contents of `.rcm/audit-exceptions.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditExceptions {
+    #[serde(default, rename = "exception")]
+    pub exceptions: Vec<AuditException>,
+}
+
+/// How an exception applies to a finding
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExceptionStatus {
+    /// No exception recorded
+    None,
+    /// Accepted until the expiry date
+    Accepted(AuditException),
+    /// An exception existed but has expired; the finding counts again
+    Expired(AuditException),
+}
+
+impl AuditExceptions {
+    pub fn path(workspace_root: &Path) -> PathBuf {
+        workspace_root.join(EXCEPTIONS_FILE)
+    }
+
+    /// Load exceptions, returning an empty set when the file is missing
+    pub async fn load(workspace_root: &Path) -> Result<Self> {
+        let path = Self::path(workspace_root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path).await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub async fn save(&self, workspace_root: &Path) -> Result<()> {
+        let path = Self::path(workspace_root);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let body = toml::to_string_pretty(self)
+            .context("Failed to serialize audit exceptions")?;
+        fs::write(&path, format!(
+            "# Accepted-risk exceptions for `rcm audit`. Each entry expires and must be renewed.\n{}",
+            body
+        )).await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Add or replace an exception for a package+advisory pair
+    pub fn upsert(&mut self, exception: AuditException) -> Result<()> {
+        if exception.justification.trim().is_empty() {
+            return Err(anyhow!("An audit exception requires a justification"));
+        }
+
+        self.exceptions.retain(|e| {
+            !(e.package.eq_ignore_ascii_case(&exception.package)
+                && e.advisory.eq_ignore_ascii_case(&exception.advisory)
+                && e.manager == exception.manager)
+        });
+        self.exceptions.push(exception);
+        self.exceptions.sort_by(|a, b| (&a.package, &a.advisory).cmp(&(&b.package, &b.advisory)));
+        Ok(())
+    }
+
+    /// Remove exceptions for a package+advisory pair, returning how many were removed
+    pub fn remove(&mut self, package: &str, advisory: &str) -> usize {
+        let before = self.exceptions.len();
+        self.exceptions.retain(|e| {
+            !(e.package.eq_ignore_ascii_case(package) && e.advisory.eq_ignore_ascii_case(advisory))
+        });
+        before - self.exceptions.len()
+    }
+
+    /// Find the exception status for a finding
+    ///
+    /// An active exception wins over an expired one for the same finding.
+    pub fn status_for(&self, manager: &str, package: &str, advisory_ids: &[&str], today: NaiveDate) -> ExceptionStatus {
+        let mut expired = None;
+        for exception in self.exceptions.iter().filter(|e| e.matches(manager, package, advisory_ids)) {
+            if exception.is_active(today) {
+                return ExceptionStatus::Accepted(exception.clone());
+            }
+            expired = Some(exception.clone());
+        }
+        expired.map(ExceptionStatus::Expired).unwrap_or(ExceptionStatus::None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn exception(expires: &str) -> AuditException {
+        AuditException {
+            package: "lodash".to_string(),
+            advisory: "GHSA-35jh-r3h4-6jhm".to_string(),
+            manager: None,
+            expires: date(expires),
+            justification: "Only used in build tooling".to_string(),
+            added_by: None,
+            added_at: None,
+        }
+    }
+
+    #[test]
+    fn test_status_accepted_then_expired() {
+        let mut set = AuditExceptions::default();
+        set.upsert(exception("2026-06-30")).unwrap();
+
+        let ids = ["GHSA-35jh-r3h4-6jhm", "CVE-2021-23337"];
+        assert!(matches!(set.status_for("npm", "lodash", &ids, date("2026-06-30")), ExceptionStatus::Accepted(_)));
+        assert!(matches!(set.status_for("npm", "lodash", &ids, date("2026-07-01")), ExceptionStatus::Expired(_)));
+        assert_eq!(set.status_for("npm", "express", &ids, date("2026-01-01")), ExceptionStatus::None);
+    }
+
+    #[test]
+    fn test_toml_roundtrip() {
+        let mut set = AuditExceptions::default();
+        set.upsert(exception("2026-06-30")).unwrap();
+        let text = toml::to_string_pretty(&set).unwrap();
+        assert!(text.contains("[[exception]]"));
+        let parsed: AuditExceptions = toml::from_str(&text).unwrap();
+        assert_eq!(parsed.exceptions, set.exceptions);
+    }
+
+    #[test]
+    fn test_requires_justification() {
+        let mut e = exception("2026-06-30");
+        e.justification = " ".to_string();
+        assert!(AuditExceptions::default().upsert(e).is_err());
+    }
+}
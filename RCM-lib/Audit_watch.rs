@@ -0,0 +1,441 @@
+//! CVE watch mode for RCM
+//!
+//! Periodically checks OSV advisories for the locked dependency set and the
+//! serving backends of GPT models, notifying when new vulnerabilities appear
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::process::Command as AsyncCommand;
+use tokio::time::{sleep, Duration};
+use crate::audit::{self, Vulnerability};
+use crate::audit_exceptions::AuditExceptions;
+use crate::lockfile::{LockManager, LOCKFILE_NAME};
+use crate::secrets;
+use crate::webhooks;
+use crate::workspace::Workspace;
+
+const OSV_QUERYBATCH_URL: &str = "https://api.osv.dev/v1/querybatch";
+const OSV_VULN_URL: &str = "https://api.osv.dev/v1/vulns";
+
+/// Webhook used when `--webhook` is not given
+pub const WEBHOOK_ENV: &str = "RCM_AUDIT_WEBHOOK";
+
+/// A package+version checked against OSV
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchTarget {
+    pub manager: String,
+    pub ecosystem: &'static str,
+    pub package: String,
+    pub version: String,
+}
+
+/// Advisories already notified, persisted in `.rcm/audit-watch.json`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WatchState {
+    pub seen: BTreeSet<String>,
+    pub last_checked: Option<String>,
+}
+
+impl WatchState {
+    fn path(workspace_root: &Path) -> PathBuf {
+        workspace_root.join(".rcm").join("audit-watch.json")
+    }
+
+    pub async fn load(workspace_root: &Path) -> Result<Self> {
+        let path = Self::path(workspace_root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path).await?;
+        serde_json::from_str(&content).context("Failed to parse audit watch state")
+    }
+
+    pub async fn save(&self, workspace_root: &Path) -> Result<()> {
+        let path = Self::path(workspace_root);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?).await
+            .context("Failed to save audit watch state")
+    }
+
+    /// Whether this finding has already been delivered
+    pub fn is_seen(&self, vuln: &Vulnerability) -> bool {
+        self.seen.contains(&finding_key(vuln))
+    }
+
+    /// Record findings once every notification channel has delivered them
+    pub fn mark_seen(&mut self, findings: &[Vulnerability]) {
+        self.seen.extend(findings.iter().map(finding_key));
+    }
+}
+
+fn finding_key(vuln: &Vulnerability) -> String {
+    format!("{}:{}:{}", vuln.manager, vuln.package, vuln.advisory)
+}
+
+/// Parse an interval such as `90s`, `30m`, `6h` or `1d` (bare numbers are seconds)
+pub fn parse_interval(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let (number, unit) = value.split_at(value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len()));
+    let number: u64 = number.parse()
+        .map_err(|_| anyhow!("Invalid interval '{}'. Use e.g. 30m, 6h or 1d", value))?;
+    let seconds = match unit {
+        "" | "s" => number,
+        "m" => number * 60,
+        "h" => number * 3600,
+        "d" => number * 86400,
+        _ => return Err(anyhow!("Invalid interval unit '{}'. Use s, m, h or d", unit)),
+    };
+    if seconds < 60 {
+        return Err(anyhow!("Interval must be at least one minute"));
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+/// OSV ecosystem for an RCM manager
+pub fn osv_ecosystem(manager: &str) -> Option<&'static str> {
+    match manager {
+        "cargo" => Some("crates.io"),
+        "npm" => Some("npm"),
+        "composer" => Some("Packagist"),
+        _ => None,
+    }
+}
+
+/// Targets from rcm.lock
+async fn locked_targets(workspace_root: &Path) -> Result<Vec<WatchTarget>> {
+    if !workspace_root.join(LOCKFILE_NAME).exists() {
+        tracing::warn!("{} not found; run 'rcm lock' so watch mode can track dependencies", LOCKFILE_NAME);
+        return Ok(Vec::new());
+    }
+
+    let lock = LockManager::new(workspace_root).load().await?;
+    Ok(lock
+        .packages
+        .into_iter()
+        .filter_map(|pkg| {
+            Some(WatchTarget {
+                ecosystem: osv_ecosystem(&pkg.manager)?,
+                manager: pkg.manager,
+                package: pkg.name,
+                version: pkg.version,
+            })
+        })
+        .collect())
+}
+
+/// Targets for the serving runtimes used by registered GPT models
+async fn gpt_targets(workspace_root: &Path) -> Result<Vec<WatchTarget>> {
+    let registry_path = workspace_root.join(".rcm").join("gpt-configs").join("registry.json");
+    if !registry_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let registry: Value = serde_json::from_str(&fs::read_to_string(&registry_path).await?)
+        .context("Failed to parse GPT model registry")?;
+    let backends: BTreeSet<String> = registry
+        .get("models")
+        .and_then(Value::as_object)
+        .map(|models| {
+            models
+                .values()
+                .filter_map(|m| m.get("backend").and_then(Value::as_str).map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut targets = Vec::new();
+    for backend in backends {
+        let (program, ecosystem, package) = match backend.as_str() {
+            "Vllm" => ("vllm", "PyPI", "vllm"),
+            "Ollama" => ("ollama", "Go", "github.com/ollama/ollama"),
+            _ => continue,
+        };
+        if let Some(version) = runtime_version(program).await {
+            targets.push(WatchTarget {
+                manager: "gpt".to_string(),
+                ecosystem,
+                package: package.to_string(),
+                version,
+            });
+        }
+    }
+    Ok(targets)
+}
+
+async fn runtime_version(program: &str) -> Option<String> {
+    let output = AsyncCommand::new(program).arg("--version").output().await.ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    // `ollama version is 0.3.12`, `0.6.1`
+    text.split_whitespace()
+        .last()
+        .map(|v| v.trim_start_matches('v').to_string())
+        .filter(|v| v.chars().next().is_some_and(|c| c.is_ascii_digit()))
+}
+
+/// Query OSV for advisories affecting the targets
+async fn query_osv(client: &reqwest::Client, targets: &[WatchTarget]) -> Result<Vec<Vulnerability>> {
+    let mut findings = Vec::new();
+
+    // OSV accepts up to 1000 queries per batch
+    for chunk in targets.chunks(1000) {
+        let queries: Vec<Value> = chunk
+            .iter()
+            .map(|t| serde_json::json!({
+                "package": { "name": t.package, "ecosystem": t.ecosystem },
+                "version": t.version,
+            }))
+            .collect();
+
+        let response = client
+            .post(OSV_QUERYBATCH_URL)
+            .json(&serde_json::json!({ "queries": queries }))
+            .send()
+            .await
+            .context("Failed to query OSV")?;
+        if !response.status().is_success() {
+            return Err(anyhow!("OSV query failed: {}", response.status()));
+        }
+
+        let body: Value = response.json().await?;
+        let results = body.get("results").and_then(Value::as_array).cloned().unwrap_or_default();
+        for (target, result) in chunk.iter().zip(results) {
+            for vuln in result.get("vulns").and_then(Value::as_array).into_iter().flatten() {
+                let Some(id) = vuln.get("id").and_then(Value::as_str) else { continue };
+                findings.push(Vulnerability {
+                    manager: target.manager.clone(),
+                    package: target.package.clone(),
+                    version: Some(target.version.clone()),
+                    advisory: id.to_string(),
+                    aliases: Vec::new(),
+                    severity: "unknown".to_string(),
+                    title: String::new(),
+                    url: Some(format!("https://osv.dev/vulnerability/{}", id)),
+                });
+            }
+        }
+    }
+
+    Ok(findings)
+}
+
+/// Fill in title, aliases and severity from the full OSV record
+async fn enrich(client: &reqwest::Client, vuln: &mut Vulnerability) {
+    let Ok(response) = client.get(format!("{}/{}", OSV_VULN_URL, vuln.advisory)).send().await else {
+        return;
+    };
+    let Ok(record) = response.json::<Value>().await else {
+        return;
+    };
+
+    vuln.title = record.get("summary").and_then(Value::as_str).unwrap_or_default().to_string();
+    vuln.aliases = record
+        .get("aliases")
+        .and_then(Value::as_array)
+        .map(|a| a.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default();
+    if let Some(severity) = record.pointer("/database_specific/severity").and_then(Value::as_str) {
+        vuln.severity = severity.to_lowercase();
+    }
+}
+
+/// Run one check and return findings not delivered before
+///
+/// Nothing is marked seen here: the caller does that after delivery, and
+/// accepted findings stay unmarked so they notify once their exception expires.
+pub async fn check_once(workspace: &Workspace, state: &mut WatchState) -> Result<Vec<Vulnerability>> {
+    let root = workspace.root();
+    let client = reqwest::Client::new();
+
+    let mut targets = locked_targets(root).await?;
+    targets.extend(gpt_targets(root).await?);
+
+    let findings = query_osv(&client, &targets).await?;
+    let exceptions = AuditExceptions::load(root).await?;
+    let today = chrono::Local::now().date_naive();
+
+    let mut fresh = Vec::new();
+    for mut vuln in findings {
+        if state.is_seen(&vuln) {
+            continue;
+        }
+        enrich(&client, &mut vuln).await;
+        fresh.push(vuln);
+    }
+
+    // Accepted risks are not notified while their exception holds
+    let report = audit::classify(fresh, &exceptions, today);
+    let mut new_findings = report.active;
+    new_findings.extend(report.expired.into_iter().map(|(vuln, _)| vuln));
+
+    state.last_checked = Some(chrono::Utc::now().to_rfc3339());
+    Ok(new_findings)
+}
+
+fn summary(findings: &[Vulnerability]) -> String {
+    findings
+        .iter()
+        .map(|v| format!("{} {} ({}): {}", v.package, v.version.as_deref().unwrap_or(""), v.advisory, v.title))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+async fn notify_desktop(title: &str, body: &str) -> Result<()> {
+    let mut cmd = if cfg!(target_os = "macos") {
+        let mut c = AsyncCommand::new("osascript");
+        c.arg("-e").arg(format!(
+            "display notification {:?} with title {:?}",
+            body.replace('\n', " | "), title
+        ));
+        c
+    } else if cfg!(target_os = "windows") {
+        let mut c = AsyncCommand::new("powershell");
+        c.args(["-NoProfile", "-Command", &format!(
+            "New-BurntToastNotification -Text '{}', '{}'",
+            title.replace('\'', "''"), body.replace('\'', "''")
+        )]);
+        c
+    } else {
+        let mut c = AsyncCommand::new("notify-send");
+        c.args(["--urgency=critical", title, body]);
+        c
+    };
+
+    let status = cmd.status().await.context("Failed to send desktop notification")?;
+    if !status.success() {
+        return Err(anyhow!("Desktop notification failed"));
+    }
+    Ok(())
+}
+
+async fn notify_webhook(url: &str, workspace_root: &Path, title: &str, findings: &[Vulnerability]) -> Result<()> {
+    // Webhook URLs usually embed a token, so allow `secret:` references
+    let url = match secrets::secret_ref(url) {
+        Some(name) => secrets::get_secret(name).await?,
+        None => url.to_string(),
+    };
+
+    let payload = serde_json::json!({
+        "text": format!("{}\n{}", title, summary(findings)),
+        "workspace": workspace_root.display().to_string(),
+        "vulnerabilities": findings,
+    });
+
+    let response = reqwest::Client::new().post(&url).json(&payload).send().await
+        .context("Failed to deliver webhook")?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Webhook returned {}", response.status()));
+    }
+    Ok(())
+}
+
+/// Entry point for `rcm audit watch`
+pub async fn run(
+    workspace: &Workspace,
+    interval: &str,
+    once: bool,
+    webhook: Option<String>,
+    desktop: bool,
+) -> Result<()> {
+    let interval = parse_interval(interval)?;
+    let webhook = webhook.or_else(|| std::env::var(WEBHOOK_ENV).ok());
+    let root = workspace.root();
+
+    println!("{}", style("👀 Watching advisories for the locked dependency set and GPT runtimes").cyan().bold());
+
+    loop {
+        let mut state = WatchState::load(root).await?;
+        match check_once(workspace, &mut state).await {
+            Ok(findings) => {
+                if findings.is_empty() {
+                    println!("✅ No new vulnerabilities ({})", chrono::Local::now().format("%Y-%m-%d %H:%M"));
+                } else {
+                    let title = format!("RCM: {} new vulnerabilities", findings.len());
+                    println!("{}", style(format!("🚨 {}", title)).red().bold());
+                    println!("{}", summary(&findings));
+
+                    // Undelivered findings stay unseen so the next check retries them
+                    let mut delivered = true;
+                    if desktop {
+                        if let Err(e) = notify_desktop(&title, &summary(&findings)).await {
+                            tracing::warn!("{}", e);
+                            delivered = false;
+                        }
+                    }
+                    if let Some(url) = &webhook {
+                        if let Err(e) = notify_webhook(url, root, &title, &findings).await {
+                            tracing::warn!("{}", e);
+                            delivered = false;
+                        }
+                    }
+                    if delivered {
+                        state.mark_seen(&findings);
+                    }
+                    let data = serde_json::json!({ "count": findings.len(), "vulnerabilities": findings });
+                    webhooks::emit(root, webhooks::AUDIT_FINDING, data).await;
+                }
+                state.save(root).await?;
+            }
+            // Transient network failures should not end a long-running watch
+            Err(e) if !once => tracing::warn!("Advisory check failed: {}", e),
+            Err(e) => return Err(e),
+        }
+
+        if once {
+            return Ok(());
+        }
+
+        tokio::select! {
+            _ = sleep(interval) => {}
+            _ = tokio::signal::ctrl_c() => {
+                println!("Stopped watching");
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("6h").unwrap(), Duration::from_secs(6 * 3600));
+        assert_eq!(parse_interval("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_interval("3600").unwrap(), Duration::from_secs(3600));
+        assert!(parse_interval("10s").is_err());
+        assert!(parse_interval("5w").is_err());
+    }
+
+    #[test]
+    fn test_osv_ecosystem() {
+        assert_eq!(osv_ecosystem("cargo"), Some("crates.io"));
+        assert_eq!(osv_ecosystem("composer"), Some("Packagist"));
+        assert_eq!(osv_ecosystem("system"), None);
+    }
+
+    #[test]
+    fn test_mark_seen() {
+        let vuln = Vulnerability {
+            manager: "cargo".to_string(),
+            package: "time".to_string(),
+            version: Some("0.1.43".to_string()),
+            advisory: "RUSTSEC-2020-0071".to_string(),
+            aliases: Vec::new(),
+            severity: "medium".to_string(),
+            title: String::new(),
+            url: None,
+        };
+        let mut state = WatchState::default();
+        assert!(!state.is_seen(&vuln));
+        state.mark_seen(std::slice::from_ref(&vuln));
+        assert!(state.is_seen(&vuln));
+    }
+}
@@ -0,0 +1,255 @@
+//! Build command implementation
+//!
+//! Cross-compiles Rust targets listed in `.rcm/build.toml`. Each target is built
+//! natively (after `rustup target add`), with cargo-zigbuild, or in a `cross`
+//! container, and every strategy keeps its own target directory so switching
+//! between them does not invalidate cached artifacts.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tokio::fs;
+use crate::isolation;
+use crate::util;
+use crate::workspace::Workspace;
+
+/// Build targets relative to the workspace root
+pub const BUILD_FILE: &str = ".rcm/build.toml";
+
+/// Contents of `.rcm/build.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BuildConfig {
+    /// Target triples built by `rcm build --all-targets`
+    #[serde(default)]
+    pub targets: Vec<String>,
+    /// Per-target overrides
+    #[serde(default)]
+    pub target: BTreeMap<String, TargetSettings>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TargetSettings {
+    /// native, zigbuild or cross; picked automatically when unset
+    #[serde(default)]
+    pub strategy: Option<String>,
+    /// Extra cargo features for this target
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+impl BuildConfig {
+    pub fn path(workspace_root: &Path) -> PathBuf {
+        workspace_root.join(BUILD_FILE)
+    }
+
+    /// Load the build config, returning no targets when the file is missing
+    pub async fn load(workspace_root: &Path) -> Result<Self> {
+        let path = Self::path(workspace_root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path).await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    /// `cargo build --target` with the rustup-installed std
+    Native,
+    /// `cargo zigbuild` using zig as the C toolchain and linker
+    Zigbuild,
+    /// `cross build` inside a container image for the target
+    Cross,
+}
+
+impl Strategy {
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "native" => Ok(Self::Native),
+            "zigbuild" | "zig" => Ok(Self::Zigbuild),
+            "cross" => Ok(Self::Cross),
+            _ => Err(anyhow!("Unsupported build strategy: {}. Use native, zigbuild or cross", s)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Native => "native",
+            Self::Zigbuild => "zigbuild",
+            Self::Cross => "cross",
+        }
+    }
+}
+
+/// Tools available for cross builds
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Toolset {
+    pub zigbuild: bool,
+    pub cross: bool,
+}
+
+impl Toolset {
+    async fn detect() -> Self {
+        let container = util::command_exists("docker").await || util::command_exists("podman").await;
+        Self {
+            zigbuild: util::command_exists("cargo-zigbuild").await && util::command_exists("zig").await,
+            cross: util::command_exists("cross").await && container,
+        }
+    }
+}
+
+/// Pick a strategy for a target when none is configured
+///
+/// Targets sharing the host OS and targets without a C toolchain story (wasm)
+/// build natively; other Linux and macOS targets prefer zig, then cross.
+pub fn choose_strategy(target: &str, host: &str, tools: Toolset) -> Strategy {
+    let same_os = |os: &str| target.contains(os) && host.contains(os);
+    if target == host || target.starts_with("wasm32") || (same_os("apple-darwin") || same_os("windows")) {
+        return Strategy::Native;
+    }
+    if tools.zigbuild && (target.contains("linux") || target.contains("apple-darwin")) {
+        return Strategy::Zigbuild;
+    }
+    if tools.cross {
+        return Strategy::Cross;
+    }
+    Strategy::Native
+}
+
+/// Program and arguments for a build
+pub fn build_command(strategy: Strategy, target: &str, profile: &str, features: &[String]) -> (&'static str, Vec<String>) {
+    let mut args: Vec<String> = match strategy {
+        Strategy::Native => vec!["build".into()],
+        Strategy::Zigbuild => vec!["zigbuild".into()],
+        Strategy::Cross => vec!["build".into()],
+    };
+    args.extend(["--target".to_string(), target.to_string(), "--profile".to_string(), profile.to_string()]);
+    // Separate target dirs per strategy keep each one's cache warm
+    args.extend(["--target-dir".to_string(), format!("target/rcm-{}", strategy.name())]);
+    if !features.is_empty() {
+        args.extend(["--features".to_string(), features.join(",")]);
+    }
+    let program = if strategy == Strategy::Cross { "cross" } else { "cargo" };
+    (program, args)
+}
+
+async fn host_triple() -> Result<String> {
+    let output = util::execute_command(Command::new("rustc").arg("-vV")).await
+        .context("rustc not found; install Rust with rustup")?;
+    output.stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("Could not determine the host target from rustc -vV"))
+}
+
+/// Install the standard library for a target unless it is already present
+async fn ensure_rustup_target(target: &str) -> Result<()> {
+    if !util::command_exists("rustup").await {
+        tracing::warn!("rustup not found; assuming the {} std is installed", target);
+        return Ok(());
+    }
+    let installed = util::execute_command(Command::new("rustup").args(["target", "list", "--installed"])).await?;
+    if installed.stdout.lines().any(|line| line.trim() == target) {
+        return Ok(());
+    }
+    println!("{}", style(format!("📥 rustup target add {}", target)).blue());
+    util::execute_mutation(Command::new("rustup").args(["target", "add", target])).await
+        .with_context(|| format!("Failed to install the {} target", target))?;
+    Ok(())
+}
+
+/// Directory holding a target's build output for a profile
+pub fn artifact_dir(root: &Path, strategy: Strategy, target: &str, profile: &str) -> PathBuf {
+    // cargo names the dev profile's output directory "debug"
+    let dir = if profile == "dev" { "debug" } else { profile };
+    root.join(format!("target/rcm-{}", strategy.name())).join(target).join(dir)
+}
+
+/// Build the requested targets, or every target in `.rcm/build.toml`
+pub async fn run(
+    workspace: &Workspace,
+    targets: Vec<String>,
+    all_targets: bool,
+    profile: &str,
+    strategy: Option<String>,
+) -> Result<()> {
+    let root = workspace.root();
+    if !root.join("Cargo.toml").exists() {
+        return Err(anyhow!("No Cargo.toml in {}", root.display()));
+    }
+    let config = BuildConfig::load(root).await?;
+    let host = host_triple().await?;
+
+    let targets = if all_targets {
+        if config.targets.is_empty() {
+            return Err(anyhow!("No targets listed in {}", BUILD_FILE));
+        }
+        config.targets.clone()
+    } else if targets.is_empty() {
+        vec![host.clone()]
+    } else {
+        targets
+    };
+
+    let tools = Toolset::detect().await;
+    let forced = strategy.as_deref().map(Strategy::from_str).transpose()?;
+    let mut built = Vec::new();
+
+    for target in &targets {
+        let settings = config.target.get(target).cloned().unwrap_or_default();
+        let strategy = match forced {
+            Some(strategy) => strategy,
+            None => match &settings.strategy {
+                Some(s) => Strategy::from_str(s)?,
+                None => choose_strategy(target, &host, tools),
+            },
+        };
+        println!("{}", style(format!("🔨 Building {} ({})", target, strategy.name())).cyan().bold());
+
+        // cross images ship their own std; the other strategies need it installed locally
+        if strategy != Strategy::Cross {
+            ensure_rustup_target(target).await?;
+        }
+        let (program, args) = build_command(strategy, target, profile, &settings.features);
+        let mut cmd = Command::new(program);
+        cmd.args(&args).current_dir(root).envs(isolation::env_vars(root));
+        util::execute_mutation(&mut cmd).await
+            .with_context(|| format!("Build for {} failed", target))?;
+
+        built.push((target.clone(), artifact_dir(root, strategy, target, profile)));
+    }
+
+    println!();
+    for (target, dir) in &built {
+        println!("  {} {} → {}", style("✓").green(), target, dir.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose_strategy() {
+        let host = "x86_64-unknown-linux-gnu";
+        let all = Toolset { zigbuild: true, cross: true };
+        assert_eq!(choose_strategy(host, host, all), Strategy::Native);
+        assert_eq!(choose_strategy("wasm32-unknown-unknown", host, all), Strategy::Native);
+        assert_eq!(choose_strategy("aarch64-unknown-linux-musl", host, all), Strategy::Zigbuild);
+        assert_eq!(choose_strategy("x86_64-pc-windows-gnu", host, all), Strategy::Cross);
+        assert_eq!(choose_strategy("aarch64-unknown-linux-gnu", host, Toolset::default()), Strategy::Native);
+        assert_eq!(choose_strategy("aarch64-apple-darwin", "x86_64-apple-darwin", all), Strategy::Native);
+
+        let (program, args) = build_command(Strategy::Cross, "armv7-unknown-linux-gnueabihf", "release", &[]);
+        assert_eq!(program, "cross");
+        assert!(args.contains(&"target/rcm-cross".to_string()));
+    }
+}
@@ -0,0 +1,480 @@
+//! Compressed on-disk cache for RCM
+//!
+//! Entries live under `Config::cache_dir()/entries`, keyed by the SHA-256 of
+//! their cache key. With `cache.compress` on they are written as zstd frames at
+//! `cache.compression_level`; small JSON metadata (registry and release
+//! responses) is compressed against a dictionary trained from existing entries
+//! with `rcm cache train`, which is where most of the savings on thousands of
+//! tiny files come from. Readers detect the frame magic, so toggling
+//! `compress` never strands entries written the other way.
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use console::style;
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tabled::{Table, Tabled};
+use tokio::fs;
+use walkdir::WalkDir;
+use crate::config::Config;
+use crate::download_cache::DownloadCache;
+use crate::util;
+
+/// First bytes of every zstd frame
+pub const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+const ENTRIES_DIR: &str = "entries";
+const DICTIONARY_FILE: &str = "metadata.dict";
+
+/// Upper bound for a trained dictionary (zstd's own default)
+const DICTIONARY_BYTES: usize = 112 * 1024;
+
+/// Entries up to this size are compressed with the dictionary
+const SMALL_ENTRY_BYTES: usize = 16 * 1024;
+
+/// Fewer samples than this make a dictionary that hurts more than it helps
+const MIN_SAMPLES: usize = 8;
+
+/// Longest possible zstd frame header
+const FRAME_HEADER_BYTES: usize = 18;
+
+#[derive(Subcommand)]
+pub enum CacheCommands {
+    /// Show entry counts, stored size and compression savings
+    Stats {
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    /// Train a zstd dictionary on cached metadata and recompress small entries
+    Train,
+    /// Remove every cache entry (the trained dictionary is kept)
+    Clear,
+    /// Drop expired entries and evict downloads over `cache.max_size_mb`
+    Clean,
+    /// Rehash downloaded blobs and remove corrupt ones
+    Verify {
+        /// Report corrupt blobs without removing them
+        #[arg(long)]
+        keep: bool,
+    },
+}
+
+/// Whether `bytes` start with a zstd frame
+pub fn is_compressed(bytes: &[u8]) -> bool {
+    bytes.starts_with(&ZSTD_MAGIC)
+}
+
+/// Compress `data` into one zstd frame that records its original size
+pub fn compress(data: &[u8], level: i32, dictionary: Option<&[u8]>) -> Result<Vec<u8>> {
+    let compressed = match dictionary {
+        Some(dictionary) => zstd::bulk::Compressor::with_dictionary(level, dictionary)?.compress(data),
+        None => zstd::bulk::compress(data, level),
+    };
+    compressed.context("zstd compression failed")
+}
+
+/// Decompress a zstd frame; anything else is returned unchanged
+pub fn decompress(bytes: &[u8], dictionary: Option<&[u8]>) -> Result<Vec<u8>> {
+    if !is_compressed(bytes) {
+        return Ok(bytes.to_vec());
+    }
+    let mut data = Vec::with_capacity(content_size(bytes).unwrap_or(0) as usize);
+    // Frames name the dictionary they were written with; plain frames need none
+    match (zstd::zstd_safe::get_dict_id_from_frame(bytes), dictionary) {
+        (Some(_), Some(dictionary)) => {
+            zstd::stream::Decoder::with_dictionary(bytes, dictionary)?.read_to_end(&mut data)?;
+        }
+        (Some(id), None) => return Err(anyhow!("Entry needs zstd dictionary {} which is not available", id)),
+        (None, _) => {
+            zstd::stream::Decoder::new(bytes)?.read_to_end(&mut data)?;
+        }
+    }
+    Ok(data)
+}
+
+/// Original size recorded in a zstd frame header
+pub fn content_size(bytes: &[u8]) -> Option<u64> {
+    zstd::zstd_safe::get_frame_content_size(bytes).ok().flatten()
+}
+
+/// Stream-compress `source` into `dest`
+pub fn compress_file(source: &Path, dest: &Path, level: i32) -> Result<u64> {
+    let mut input = std::fs::File::open(source)
+        .with_context(|| format!("Failed to open {}", source.display()))?;
+    let size = input.metadata()?.len();
+    let output = std::fs::File::create(dest)
+        .with_context(|| format!("Failed to create {}", dest.display()))?;
+    let mut encoder = zstd::stream::Encoder::new(output, level)?;
+    encoder.include_contentsize(true)?;
+    encoder.set_pledged_src_size(Some(size))?;
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.flush()?;
+    Ok(std::fs::metadata(dest)?.len())
+}
+
+/// Stream-decompress `source` into `dest`
+pub fn decompress_file(source: &Path, dest: &Path) -> Result<()> {
+    let input = std::fs::File::open(source)
+        .with_context(|| format!("Failed to open {}", source.display()))?;
+    let output = std::fs::File::create(dest)
+        .with_context(|| format!("Failed to create {}", dest.display()))?;
+    zstd::stream::copy_decode(input, output)
+        .with_context(|| format!("Failed to decompress {}", source.display()))
+}
+
+/// Cache size and savings, for `rcm cache stats`
+#[derive(Debug, Default, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub compressed_entries: usize,
+    pub dictionary_entries: usize,
+    /// Bytes on disk
+    pub stored_bytes: u64,
+    /// Bytes the entries hold once decompressed
+    pub original_bytes: u64,
+    pub dictionary_bytes: Option<u64>,
+}
+
+impl CacheStats {
+    pub fn saved_bytes(&self) -> u64 {
+        self.original_bytes.saturating_sub(self.stored_bytes)
+    }
+
+    /// Stored size as a fraction of the original (lower is better)
+    pub fn ratio(&self) -> f64 {
+        if self.original_bytes == 0 {
+            1.0
+        } else {
+            self.stored_bytes as f64 / self.original_bytes as f64
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct StatRow {
+    #[tabled(rename = "Metric")]
+    metric: &'static str,
+    #[tabled(rename = "Value")]
+    value: String,
+}
+
+/// Key-value cache under the RCM cache directory
+pub struct CacheStore {
+    root: PathBuf,
+    enabled: bool,
+    compress: bool,
+    level: i32,
+    ttl: Duration,
+    dictionary: Option<Vec<u8>>,
+}
+
+impl CacheStore {
+    /// Open the cache described by `config`, loading its dictionary if one was trained
+    pub async fn open(config: &Config) -> Self {
+        let root = config.cache_dir();
+        let dictionary = fs::read(root.join(DICTIONARY_FILE)).await.ok();
+        Self {
+            root,
+            enabled: config.cache.enabled,
+            compress: config.cache.compress,
+            level: config.cache.compression_level,
+            ttl: Duration::from_secs(config.cache.ttl_hours * 3600),
+            dictionary,
+        }
+    }
+
+    fn entries_dir(&self) -> PathBuf {
+        self.root.join(ENTRIES_DIR)
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        let digest = format!("{:x}", Sha256::digest(key.as_bytes()));
+        self.entries_dir().join(&digest[..2]).join(digest)
+    }
+
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        if !self.compress {
+            return Ok(data.to_vec());
+        }
+        let dictionary = self.dictionary.as_deref().filter(|_| data.len() <= SMALL_ENTRY_BYTES);
+        compress(data, self.level, dictionary)
+    }
+
+    /// Cached bytes for `key`, unless missing or older than `cache.ttl_hours`
+    pub async fn get(&self, key: &str) -> Option<Vec<u8>> {
+        if !self.enabled {
+            return None;
+        }
+        let path = self.entry_path(key);
+        let age = fs::metadata(&path).await.ok()?.modified().ok()?.elapsed().unwrap_or_default();
+        if age > self.ttl {
+            return None;
+        }
+        let bytes = fs::read(&path).await.ok()?;
+        match decompress(&bytes, self.dictionary.as_deref()) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable cache entry {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Store `data` under `key`, replacing any previous entry (skipped in read-only mode)
+    pub async fn put(&self, key: &str, data: &[u8]) -> Result<()> {
+        if !self.enabled || util::is_read_only() {
+            return Ok(());
+        }
+        let path = self.entry_path(key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        // Write then rename so concurrent readers never see half an entry
+        let partial = path.with_extension("partial");
+        fs::write(&partial, self.encode(data)?).await
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        fs::rename(&partial, &path).await?;
+        Ok(())
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        serde_json::from_slice(&self.get(key).await?).ok()
+    }
+
+    pub async fn put_json<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
+        self.put(key, &serde_json::to_vec(value)?).await
+    }
+
+    fn entry_files(&self) -> Vec<PathBuf> {
+        WalkDir::new(self.entries_dir())
+            .into_iter()
+            .flatten()
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.into_path())
+            .filter(|p| p.extension().is_none())
+            .collect()
+    }
+
+    /// Count entries and compare stored against original sizes
+    pub fn stats(&self) -> Result<CacheStats> {
+        let mut stats = CacheStats {
+            dictionary_bytes: self.dictionary.as_ref().map(|d| d.len() as u64),
+            ..Default::default()
+        };
+        for path in self.entry_files() {
+            let stored = std::fs::metadata(&path)?.len();
+            // The frame header alone records the original size
+            let mut header = Vec::with_capacity(FRAME_HEADER_BYTES);
+            std::fs::File::open(&path)?.take(FRAME_HEADER_BYTES as u64).read_to_end(&mut header)?;
+
+            stats.entries += 1;
+            stats.stored_bytes += stored;
+            if is_compressed(&header) {
+                stats.compressed_entries += 1;
+                if zstd::zstd_safe::get_dict_id_from_frame(&header).is_some() {
+                    stats.dictionary_entries += 1;
+                }
+                stats.original_bytes += content_size(&header).unwrap_or(stored);
+            } else {
+                stats.original_bytes += stored;
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Train a dictionary on small entries and recompress them with it; returns bytes saved
+    pub async fn train_dictionary(&mut self) -> Result<u64> {
+        let mut samples = Vec::new();
+        for path in self.entry_files() {
+            let bytes = fs::read(&path).await?;
+            let data = decompress(&bytes, self.dictionary.as_deref())?;
+            if data.len() <= SMALL_ENTRY_BYTES {
+                samples.push((path, bytes.len() as u64, data));
+            }
+        }
+        if samples.len() < MIN_SAMPLES {
+            return Err(anyhow!(
+                "Only {} small cache entries; at least {} are needed to train a dictionary",
+                samples.len(), MIN_SAMPLES
+            ));
+        }
+
+        let inputs: Vec<&[u8]> = samples.iter().map(|(_, _, data)| data.as_slice()).collect();
+        let dictionary = zstd::dict::from_samples(&inputs, DICTIONARY_BYTES)
+            .context("Failed to train zstd dictionary")?;
+        let dry_run = util::is_dry_run();
+        if dry_run {
+            println!("[dry-run] write {}", self.root.join(DICTIONARY_FILE).display());
+        } else {
+            fs::write(self.root.join(DICTIONARY_FILE), &dictionary).await?;
+        }
+        self.dictionary = Some(dictionary);
+        self.compress = true;
+
+        let mut saved = 0u64;
+        for (path, before, data) in samples {
+            let encoded = self.encode(&data)?;
+            saved += before.saturating_sub(encoded.len() as u64);
+            if !dry_run {
+                fs::write(&path, encoded).await?;
+            }
+        }
+        Ok(saved)
+    }
+
+    /// Entries past `cache.ttl_hours`, and interrupted writes older than `older_than`
+    pub fn stale_files(&self, older_than: Duration) -> Vec<PathBuf> {
+        WalkDir::new(self.entries_dir())
+            .into_iter()
+            .flatten()
+            .filter(|e| e.file_type().is_file())
+            .filter(|e| {
+                let age = e.metadata().ok()
+                    .and_then(|m| m.modified().ok())
+                    .and_then(|t| t.elapsed().ok())
+                    .unwrap_or_default();
+                let partial = e.path().extension().is_some_and(|ext| ext == "partial");
+                if partial { age > older_than } else { age > self.ttl }
+            })
+            .map(|e| e.into_path())
+            .collect()
+    }
+
+    /// Remove every entry; returns bytes freed
+    pub async fn clear(&self) -> Result<u64> {
+        let dir = self.entries_dir();
+        if !dir.exists() {
+            return Ok(0);
+        }
+        let freed = util::calculate_directory_size(&dir).await?;
+        util::remove_dir_all(&dir).await?;
+        Ok(freed)
+    }
+}
+
+/// Handle cache commands
+pub async fn handle_command(config: &Config, cmd: CacheCommands) -> Result<()> {
+    let mut store = CacheStore::open(config).await;
+    let downloads = DownloadCache::open(config);
+    match cmd {
+        CacheCommands::Stats { format } => {
+            let stats = store.stats()?;
+            let blobs = downloads.stats().await;
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+                    "entries": stats,
+                    "downloads": blobs,
+                }))?),
+                "table" => {
+                    let level = if config.cache.compress {
+                        format!("zstd level {}", config.cache.compression_level)
+                    } else {
+                        "off".to_string()
+                    };
+                    let rows = vec![
+                        StatRow { metric: "Location", value: store.root.display().to_string() },
+                        StatRow { metric: "Compression", value: level },
+                        StatRow {
+                            metric: "Entries",
+                            value: format!("{} ({} compressed, {} with dictionary)",
+                                stats.entries, stats.compressed_entries, stats.dictionary_entries),
+                        },
+                        StatRow { metric: "Original size", value: util::format_bytes(stats.original_bytes) },
+                        StatRow { metric: "Stored size", value: util::format_bytes(stats.stored_bytes) },
+                        StatRow {
+                            metric: "Saved",
+                            value: format!("{} ({:.0}%)", util::format_bytes(stats.saved_bytes()), (1.0 - stats.ratio()) * 100.0),
+                        },
+                        StatRow {
+                            metric: "Dictionary",
+                            value: stats.dictionary_bytes.map(util::format_bytes).unwrap_or_else(|| "none (run `rcm cache train`)".to_string()),
+                        },
+                        StatRow {
+                            metric: "Downloads",
+                            value: format!("{} blob(s), {} URL(s) indexed", blobs.blobs, blobs.urls),
+                        },
+                        StatRow {
+                            metric: "Download size",
+                            value: format!("{} of {} ({:.0}%)", util::format_bytes(blobs.bytes), util::format_bytes(blobs.limit_bytes),
+                                blobs.bytes as f64 * 100.0 / blobs.limit_bytes.max(1) as f64),
+                        },
+                    ];
+                    println!("{}", Table::new(rows));
+                }
+                _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+            }
+            Ok(())
+        }
+        CacheCommands::Train => {
+            println!("{}", style("🧠 Training zstd dictionary on cached metadata...").cyan().bold());
+            let saved = store.train_dictionary().await?;
+            println!("{} Recompressed small entries, saving {}", style("✅").green(), util::format_bytes(saved));
+            Ok(())
+        }
+        CacheCommands::Clear => {
+            let freed = store.clear().await?;
+            println!("🧹 Cleared RCM cache, freed {}", util::format_bytes(freed));
+            Ok(())
+        }
+        CacheCommands::Clean => {
+            let mut freed = 0;
+            let stale = store.stale_files(Duration::from_secs(3600));
+            for path in &stale {
+                freed += std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+                if util::is_dry_run() {
+                    println!("[dry-run] remove {}", path.display());
+                } else {
+                    fs::remove_file(path).await.ok();
+                }
+            }
+            let report = downloads.clean().await?;
+            freed += report.freed_bytes;
+            println!("🧹 Removed {} stale entr{}, {} expired and {} evicted download(s), freed {}",
+                stale.len(), if stale.len() == 1 { "y" } else { "ies" },
+                report.expired, report.evicted, util::format_bytes(freed));
+            Ok(())
+        }
+        CacheCommands::Verify { keep } => {
+            println!("{}", style("🔎 Verifying downloaded blobs...").cyan().bold());
+            let report = downloads.verify(keep).await?;
+            if report.corrupt.is_empty() {
+                println!("{} {} blob(s) match their digests", style("✅").green(), report.checked);
+                return Ok(());
+            }
+            for path in &report.corrupt {
+                println!("  {} {}", style("✗").red(), path.display());
+            }
+            let action = if keep { "kept" } else { "removed; they are fetched again on next use" };
+            Err(anyhow!("{} of {} blob(s) are corrupt ({})", report.corrupt.len(), report.checked, action))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_with_dictionary() {
+        let samples: Vec<Vec<u8>> = (0..64)
+            .map(|i| format!(r#"{{"name":"pkg-{i}","version":"1.{i}.0","license":"MIT","dist":{{"tarball":"https://registry.example/pkg-{i}.tgz"}}}}"#).into_bytes())
+            .collect();
+        let dictionary = zstd::dict::from_samples(&samples, 4096).unwrap();
+
+        let data = br#"{"name":"pkg-99","version":"1.99.0","license":"MIT","dist":{"tarball":"https://registry.example/pkg-99.tgz"}}"#;
+        let plain = compress(data, 3, None).unwrap();
+        let trained = compress(data, 3, Some(&dictionary)).unwrap();
+        assert!(is_compressed(&plain) && is_compressed(&trained));
+        assert!(trained.len() < plain.len());
+        assert_eq!(content_size(&trained), Some(data.len() as u64));
+
+        assert_eq!(decompress(&trained, Some(&dictionary)).unwrap(), data);
+        assert_eq!(decompress(&plain, Some(&dictionary)).unwrap(), data);
+        assert!(decompress(&trained, None).is_err());
+        assert_eq!(decompress(b"{}", None).unwrap(), b"{}");
+    }
+}
@@ -8,35 +8,93 @@ use indicatif::{ProgressBar, ProgressStyle};
 use std::collections::HashMap;
 use tokio::time::{sleep, Duration};
 use crate::workspace::Workspace;
-use crate::npm::{NpmManager, NpmManagerType};
+use crate::commands;
+use crate::container::{ContainerManager, ContainerManifest};
+use crate::engines;
+use crate::ensure_state::{self, Decision, EnsureState};
+use crate::go::{self, GoManager};
+use crate::isolation;
+use crate::mirrors;
+use crate::platform;
+use crate::npm::resolve_manager_type;
 use crate::ppm::ComposerManager;
 use crate::system::SystemManager;
+use crate::transaction::Transaction;
+use crate::services;
+use crate::telemetry;
 use crate::util;
+use crate::webhooks;
 
 #[derive(Debug)]
-struct ManagerStatus {
-    name: String,
-    available: bool,
-    version: Option<String>,
-    issues: Vec<String>,
-    dependencies_count: usize,
-    missing_dependencies: Vec<String>,
+pub(crate) struct ManagerStatus {
+    pub(crate) name: String,
+    pub(crate) available: bool,
+    pub(crate) version: Option<String>,
+    pub(crate) issues: Vec<String>,
+    pub(crate) dependencies_count: usize,
+    pub(crate) missing_dependencies: Vec<String>,
 }
 
 /// Ensure all dependencies are installed and environment is properly configured
-pub async fn run(workspace: &Workspace, managers: Option<Vec<String>>) -> Result<()> {
+pub async fn run(workspace: &Workspace, managers: Option<Vec<String>>, start_services: bool, force: bool) -> Result<()> {
     println!("{}", style("🔍 Ensuring workspace dependencies...").cyan().bold());
     
     let target_managers = if let Some(mgrs) = managers {
         mgrs
     } else {
-        workspace.enabled_managers()
+        let mut mgrs = workspace.enabled_managers();
+        // Declared images are checked like any other manager's dependencies
+        let has_images = ContainerManifest::load(workspace.root()).await.is_ok_and(|m| !m.images.is_empty());
+        if has_images && !mgrs.iter().any(|m| m == "container") {
+            mgrs.push("container".to_string());
+        }
+        mgrs
     };
     
     if target_managers.is_empty() {
         return Err(anyhow!("No package managers enabled. Run 'rcm init' to configure managers."));
     }
     
+    // Skip managers whose manifests and lockfiles are unchanged since the last clean run
+    let mut state = EnsureState::load(workspace.root()).await;
+    let mut skipped = Vec::new();
+    let mut pending = Vec::new();
+    for manager in target_managers {
+        let inputs = ensure_state::fingerprint(workspace.root(), &manager).await;
+        match ensure_state::decide(state.managers.get(&manager), &inputs, force) {
+            Decision::Skip { since } => skipped.push((manager, since)),
+            Decision::Run { reason } => {
+                tracing::debug!(manager = %manager, reason = %reason, "manager needs checking");
+                pending.push((manager, reason));
+            }
+        }
+    }
+    for (manager, since) in &skipped {
+        println!("  {} {}: inputs unchanged since {}", style("⏭").dim(), style(manager).bold(), since);
+    }
+    for (manager, reason) in pending.iter().filter(|_| !skipped.is_empty()) {
+        println!("  {} {}: {}", style("↻").cyan(), style(manager).bold(), reason);
+    }
+    let target_managers: Vec<String> = pending.into_iter().map(|(manager, _)| manager).collect();
+    if target_managers.is_empty() {
+        let service_statuses = services::check(workspace.root(), start_services).await?;
+        services::print_report(&service_statuses);
+        let down: Vec<_> = service_statuses.iter().filter(|s| !s.up).collect();
+        if down.is_empty() {
+            println!("{}", style("✅ Nothing changed since the last ensure (use --force to recheck)").green().bold());
+        } else {
+            println!();
+            println!("{}", style("⚠️  Some issues were found:").yellow().bold());
+            for service in down {
+                println!("  {} {}: {}", style("✗").red(), style(&service.name).bold(), service.status);
+            }
+        }
+        return Ok(());
+    }
+    
+    // Declared MSRV/engines/platform requirements must hold before anything installs
+    engines::enforce(workspace.root(), &target_managers).await?;
+    
     // Create progress bar for overall process
     let pb = ProgressBar::new(target_managers.len() as u64 * 3);
     pb.set_style(
@@ -52,7 +110,19 @@ pub async fn run(workspace: &Workspace, managers: Option<Vec<String>>) -> Result
     pb.set_message("Checking environment...");
     for manager in &target_managers {
         pb.set_message(format!("Checking {}...", manager));
-        let status = check_manager_environment(workspace, manager).await?;
+        let status = telemetry::traced(
+            telemetry::manager_span("ensure check", manager),
+            check_manager_environment(workspace, manager),
+        ).await?;
+        tracing::debug!(
+            manager = %status.name,
+            available = status.available,
+            version = status.version.as_deref().unwrap_or("-"),
+            dependencies = status.dependencies_count,
+            missing = status.missing_dependencies.len(),
+            issues = ?status.issues,
+            "manager checked"
+        );
         manager_statuses.push(status);
         pb.inc(1);
         sleep(Duration::from_millis(100)).await;
@@ -62,20 +132,55 @@ pub async fn run(workspace: &Workspace, managers: Option<Vec<String>>) -> Result
     pb.set_message("Validating configurations...");
     for status in &mut manager_statuses {
         pb.set_message(format!("Validating {}...", status.name));
-        validate_manager_config(workspace, status).await?;
+        let span = telemetry::manager_span("ensure validate", &status.name);
+        telemetry::traced(span, validate_manager_config(workspace, status)).await?;
         pb.inc(1);
         sleep(Duration::from_millis(100)).await;
     }
     
-    // Phase 3: Install missing dependencies
+    // Phase 3: Install missing dependencies; a failure in any manager restores all of them
     pb.set_message("Installing dependencies...");
-    for status in &manager_statuses {
-        if !status.missing_dependencies.is_empty() {
-            pb.set_message(format!("Installing {} dependencies...", status.name));
-            install_missing_dependencies(workspace, status).await?;
+    let installing: Vec<String> = manager_statuses
+        .iter()
+        .filter(|s| !s.missing_dependencies.is_empty())
+        .map(|s| s.name.clone())
+        .collect();
+    let changes = match installing.is_empty() {
+        true => None,
+        false => webhooks::snapshot(workspace, &installing).await,
+    };
+    let transaction = Transaction::begin(workspace.root(), "ensure", &installing).await?;
+    let result = async {
+        for status in &manager_statuses {
+            if !status.missing_dependencies.is_empty() {
+                pb.set_message(format!("Installing {} dependencies...", status.name));
+                tracing::debug!(manager = %status.name, missing = ?status.missing_dependencies, "installing missing dependencies");
+                telemetry::traced(
+                    telemetry::manager_span("ensure install", &status.name),
+                    install_missing_dependencies(workspace, status),
+                ).await?;
+            }
+            // After the native install, which prunes packages package.json doesn't list
+            if status.name == "npm" && status.available {
+                let missing = commands::add::missing_platform_npm(workspace).await;
+                if !missing.is_empty() {
+                    telemetry::traced(
+                        telemetry::manager_span("ensure install", "npm"),
+                        commands::add::install_npm_unsaved(workspace, &missing),
+                    ).await?;
+                }
+            }
+            pb.inc(1);
+            sleep(Duration::from_millis(100)).await;
         }
-        pb.inc(1);
-        sleep(Duration::from_millis(100)).await;
+        Ok(())
+    }.await;
+    if result.is_err() {
+        pb.abandon_with_message("Failed");
+    }
+    transaction.finish(result).await?;
+    if let Some(changes) = changes {
+        changes.emit_changes(workspace).await;
     }
     
     pb.finish_with_message("Completed");
@@ -83,8 +188,23 @@ pub async fn run(workspace: &Workspace, managers: Option<Vec<String>>) -> Result
     // Print summary
     print_summary(&manager_statuses).await?;
     
+    // Record inputs after installing, since installs may rewrite lockfiles
+    for status in &manager_statuses {
+        if status.available && status.issues.is_empty() {
+            state.record(&status.name, ensure_state::fingerprint(workspace.root(), &status.name).await);
+        } else {
+            state.invalidate(&status.name);
+        }
+    }
+    state.save(workspace.root()).await?;
+    
+    // Phase 4: Probe the external services the workspace declares
+    let service_statuses = services::check(workspace.root(), start_services).await?;
+    services::print_report(&service_statuses);
+    
     // Check for any critical issues
-    let has_errors = manager_statuses.iter().any(|s| !s.issues.is_empty() || !s.available);
+    let has_errors = manager_statuses.iter().any(|s| !s.issues.is_empty() || !s.available)
+        || service_statuses.iter().any(|s| !s.up);
     
     if has_errors {
         println!();
@@ -104,6 +224,13 @@ pub async fn run(workspace: &Workspace, managers: Option<Vec<String>>) -> Result
                 );
             }
         }
+        for service in service_statuses.iter().filter(|s| !s.up) {
+            println!("  {} {}: {}", 
+                style("✗").red(), 
+                style(&service.name).bold(), 
+                service.status
+            );
+        }
         println!();
         println!("Run {} for more detailed information.", style("rcm --help").cyan());
     } else {
@@ -115,7 +242,7 @@ pub async fn run(workspace: &Workspace, managers: Option<Vec<String>>) -> Result
 }
 
 /// Check if a package manager is available and working
-async fn check_manager_environment(workspace: &Workspace, manager: &str) -> Result<ManagerStatus> {
+pub(crate) async fn check_manager_environment(workspace: &Workspace, manager: &str) -> Result<ManagerStatus> {
     let mut status = ManagerStatus {
         name: manager.to_string(),
         available: false,
@@ -129,6 +256,8 @@ async fn check_manager_environment(workspace: &Workspace, manager: &str) -> Resu
         "cargo" => check_cargo_environment(workspace, &mut status).await?,
         "npm" => check_npm_environment(workspace, &mut status).await?,
         "composer" => check_composer_environment(workspace, &mut status).await?,
+        "go" => check_go_environment(workspace, &mut status).await?,
+        "container" => check_container_environment(workspace, &mut status).await?,
         "system" => check_system_environment(workspace, &mut status).await?,
         _ => {
             status.issues.push(format!("Unknown package manager: {}", manager));
@@ -311,6 +440,64 @@ async fn check_composer_environment(workspace: &Workspace, status: &mut ManagerS
     Ok(())
 }
 
+/// Check Go environment
+async fn check_go_environment(workspace: &Workspace, status: &mut ManagerStatus) -> Result<()> {
+    if !util::command_exists("go").await {
+        status.issues.push("Go not found. Install from https://go.dev/dl/".to_string());
+        return Ok(());
+    }
+    
+    status.available = true;
+    
+    let go_manager = GoManager::new(workspace.root());
+    status.version = go_manager.version().await;
+    
+    // Check for go.mod
+    if !go_manager.has_module() {
+        status.issues.push("No go.mod found".to_string());
+        return Ok(());
+    }
+    
+    let go_mod = go_manager.load_go_mod().await?;
+    status.dependencies_count = go_mod.requires.len();
+    
+    // go.mod's go directive is the minimum toolchain
+    if let (Some(required), Some(installed)) = (&go_mod.go, &status.version) {
+        if !go::satisfies_go_directive(required, installed) {
+            status.issues.push(format!("go.mod requires go {} but go {} is installed", required, installed));
+        }
+    }
+    
+    // Without go.sum nothing has been resolved yet
+    if !workspace.root().join("go.sum").exists() && status.dependencies_count > 0 {
+        status.missing_dependencies.push("Dependencies not downloaded (no go.sum)".to_string());
+    }
+    
+    Ok(())
+}
+
+/// Check container images declared in the workspace manifest
+async fn check_container_environment(workspace: &Workspace, status: &mut ManagerStatus) -> Result<()> {
+    let containers = ContainerManager::new(workspace.root());
+    if let Err(e) = containers.engine().await {
+        status.issues.push(e.to_string());
+        return Ok(());
+    }
+    
+    status.available = true;
+    status.version = containers.version().await;
+    
+    let manifest = ContainerManifest::load(workspace.root()).await?;
+    status.dependencies_count = manifest.images.len();
+    
+    // Unpinned and missing images are both fixed by pulling
+    for (name, problem) in containers.verify(&manifest).await? {
+        status.missing_dependencies.push(format!("{}: {}", name, problem));
+    }
+    
+    Ok(())
+}
+
 /// Check system package manager environment
 async fn check_system_environment(workspace: &Workspace, status: &mut ManagerStatus) -> Result<()> {
     let system_manager = SystemManager::new(workspace.root()).await;
@@ -325,11 +512,11 @@ async fn check_system_environment(workspace: &Workspace, status: &mut ManagerSta
         }
     }
     
-    // Count system dependencies from workspace manifest
+    // Count system dependencies from workspace manifest that apply to this machine
     let dependencies = workspace.list_dependencies();
     status.dependencies_count = dependencies
         .iter()
-        .filter(|(_, dep)| dep.manager == "system")
+        .filter(|(_, dep)| dep.manager == "system" && platform::applies(&dep.platforms))
         .count();
     
     Ok(())
@@ -345,6 +532,7 @@ async fn validate_manager_config(workspace: &Workspace, status: &mut ManagerStat
         "cargo" => validate_cargo_config(workspace, status).await?,
         "npm" => validate_npm_config(workspace, status).await?,
         "composer" => validate_composer_config(workspace, status).await?,
+        "go" => validate_go_config(workspace, status).await?,
         "system" => validate_system_config(workspace, status).await?,
         _ => {}
     }
@@ -411,6 +599,19 @@ async fn validate_composer_config(workspace: &Workspace, status: &mut ManagerSta
     Ok(())
 }
 
+/// Validate Go configuration
+async fn validate_go_config(workspace: &Workspace, status: &mut ManagerStatus) -> Result<()> {
+    let go_manager = GoManager::new(workspace.root());
+    if go_manager.has_module() && workspace.root().join("go.sum").exists() {
+        // Only meaningful once modules are in the cache; failures list tampered modules
+        for problem in go_manager.verify().await? {
+            status.issues.push(format!("go mod verify: {}", problem));
+        }
+    }
+    
+    Ok(())
+}
+
 /// Validate system configuration
 async fn validate_system_config(_workspace: &Workspace, _status: &mut ManagerStatus) -> Result<()> {
     // System packages don't have a specific config file to validate
@@ -429,20 +630,31 @@ async fn install_missing_dependencies(workspace: &Workspace, status: &ManagerSta
         "cargo" => {
             let mut cmd = tokio::process::Command::new("cargo");
             cmd.current_dir(workspace.root());
+            cmd.envs(isolation::env_vars(workspace.root()));
+            cmd.envs(mirrors::env_vars());
             cmd.arg("fetch");
+            cmd.args(mirrors::cargo_args());
+            
+            if util::skip_in_dry_run(cmd.as_std()) {
+                return Ok(());
+            }
             
-            let output = cmd.output().await?;
+            let output = util::traced_output(&mut cmd).await?;
             if !output.status.success() {
                 return Err(anyhow!("Failed to install Cargo dependencies"));
             }
         }
         "npm" => {
-            let npm_manager = NpmManager::new(workspace.root(), NpmManagerType::Npm);
-            let mut cmd = tokio::process::Command::new("npm");
+            let manager_type = resolve_manager_type(workspace.root(), None).await?;
+            let mut cmd = tokio::process::Command::new(manager_type.command());
             cmd.current_dir(workspace.root());
             cmd.arg("install");
             
-            let output = cmd.output().await?;
+            if util::skip_in_dry_run(cmd.as_std()) {
+                return Ok(());
+            }
+            
+            let output = util::traced_output(&mut cmd).await?;
             if !output.status.success() {
                 return Err(anyhow!("Failed to install NPM dependencies"));
             }
@@ -452,11 +664,24 @@ async fn install_missing_dependencies(workspace: &Workspace, status: &ManagerSta
             cmd.current_dir(workspace.root());
             cmd.arg("install");
             
-            let output = cmd.output().await?;
+            if util::skip_in_dry_run(cmd.as_std()) {
+                return Ok(());
+            }
+            
+            let output = util::traced_output(&mut cmd).await?;
             if !output.status.success() {
                 return Err(anyhow!("Failed to install Composer dependencies"));
             }
         }
+        "go" => {
+            let go_manager = GoManager::new(workspace.root());
+            go_manager.tidy().await.context("Failed to resolve Go dependencies")?;
+            go_manager.download().await.context("Failed to install Go dependencies")?;
+        }
+        "container" => {
+            ContainerManager::new(workspace.root()).pull_missing().await
+                .context("Failed to pull container images")?;
+        }
         "system" => {
             // System dependencies need to be installed individually
             // This is handled by the specific add commands
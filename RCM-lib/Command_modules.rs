@@ -6,6 +6,7 @@ pub mod init;
 pub mod add;
 pub mod remove;
 pub mod ensure;
+pub mod doctor;
 pub mod plan;
 pub mod apply;
 pub mod snapshot;
@@ -14,6 +15,21 @@ pub mod provenance;
 pub mod workspace;
 pub mod config;
 pub mod letcmd;
+pub mod letspec;
+pub mod letcond;
+pub mod lock;
+pub mod resolve;
+pub mod audit;
+pub mod outdated;
+pub mod report;
+pub mod update;
+pub mod lint;
+pub mod test;
+pub mod coverage;
+pub mod build;
+pub mod package;
+pub mod verify;
+pub mod query;
 
 use anyhow::Result;
 use crate::workspace::Workspace;
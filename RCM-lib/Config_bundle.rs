@@ -0,0 +1,478 @@
+//! Config bundles for machine provisioning
+//!
+//! `rcm config export --bundle` packs the user config, the GPT model registry,
+//! system package mappings and LET specs into one gzipped tarball;
+//! `rcm config import` applies it on a new machine. Plaintext credentials never
+//! leave the machine: `secret:` references are kept, everything else is
+//! stripped and listed in the manifest so it can be re-entered after import.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use tokio::fs;
+use crate::config::Config;
+use crate::sbom::MODEL_REGISTRY;
+use crate::secrets::secret_ref;
+use crate::{redact, util};
+
+/// Manifest `format` this version writes and accepts
+pub const BUNDLE_FORMAT: &str = "rcm.bundle/v1";
+
+/// Bundle written when `--output` is omitted
+pub const DEFAULT_BUNDLE: &str = "rcm-bundle.tar.gz";
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const CONFIG_ENTRY: &str = "config/config.json";
+
+/// Workspace files are stored under this prefix at their workspace-relative path
+const WORKSPACE_PREFIX: &str = "workspace/";
+
+/// System package mappings and aliases (see `rcm system`)
+const SYSTEM_CONFIG: &str = ".rcm/system.json";
+
+/// LET specs directory
+const LET_DIR: &str = ".rcm/let";
+
+/// Describes a bundle's contents
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format: String,
+    pub created_at: String,
+    pub rcm_version: String,
+    /// Workspace root on the exporting machine, used to rebase model paths
+    pub workspace_root: PathBuf,
+    pub entries: Vec<String>,
+    /// Config keys whose plaintext credentials were left out
+    pub credentials: Vec<String>,
+}
+
+/// Drop plaintext credentials, keeping `secret:` references; returns the keys removed
+pub fn strip_credentials(config: &mut Config) -> Vec<String> {
+    let mut stripped = Vec::new();
+    let mut strip = |value: &mut Option<String>, key: String| {
+        if value.as_deref().is_some_and(|v| secret_ref(v).is_none()) {
+            *value = None;
+            stripped.push(key);
+        }
+    };
+
+    for (name, auth) in config.auth.iter_mut() {
+        strip(&mut auth.token, format!("auth.{}.token", name));
+        strip(&mut auth.password, format!("auth.{}.password", name));
+    }
+    for (name, proxy) in config.proxies.iter_mut() {
+        if let Some(auth) = proxy.auth.as_mut() {
+            let mut password = Some(std::mem::take(&mut auth.password)).filter(|p| !p.is_empty());
+            strip(&mut password, format!("proxies.{}.auth.password", name));
+            auth.password = password.unwrap_or_default();
+        }
+    }
+    strip(&mut config.dashboard.token, "dashboard.token".to_string());
+    for (index, hook) in config.webhooks.iter_mut().enumerate() {
+        let name = hook.name.clone().unwrap_or_else(|| index.to_string());
+        strip(&mut hook.secret, format!("webhooks.{}.secret", name));
+    }
+    for (name, manager) in config.managers.iter_mut() {
+        // Environment values are only known to be secret when they would be masked in output
+        manager.env_vars.retain(|key, value| {
+            let keep = secret_ref(value).is_some() || redact::redact(value) == *value;
+            if !keep {
+                stripped.push(format!("managers.{}.env_vars.{}", name, key));
+            }
+            keep
+        });
+    }
+    stripped.sort();
+    stripped
+}
+
+/// Environment names that hold credentials whatever their value looks like
+fn sensitive_env_name(name: &str) -> bool {
+    let name = name.to_uppercase();
+    ["TOKEN", "SECRET", "PASSWORD", "PASSWD", "API_KEY", "APIKEY", "ACCESS_KEY", "PRIVATE_KEY", "CREDENTIAL"]
+        .iter()
+        .any(|marker| name.contains(marker))
+}
+
+/// Drop plaintext model credentials from a registry: `auth_token`, and
+/// serving `env` values that are named or look like secrets. Returns the keys removed.
+pub fn strip_registry_credentials(registry: &mut Value) -> Vec<String> {
+    let mut stripped = Vec::new();
+    let Some(models) = registry.get_mut("models").and_then(Value::as_object_mut) else {
+        return stripped;
+    };
+    for (name, model) in models.iter_mut() {
+        let Some(serving) = model.get_mut("serving_config").and_then(Value::as_object_mut) else {
+            continue;
+        };
+        if serving.get("auth_token").and_then(Value::as_str).is_some_and(|v| secret_ref(v).is_none()) {
+            serving.insert("auth_token".to_string(), Value::Null);
+            stripped.push(format!("models.{}.serving_config.auth_token", name));
+        }
+        if let Some(env) = serving.get_mut("env").and_then(Value::as_object_mut) {
+            env.retain(|key, value| {
+                let text = value.as_str().unwrap_or_default();
+                let keep = secret_ref(text).is_some()
+                    || (!sensitive_env_name(key) && redact::redact(text) == text);
+                if !keep {
+                    stripped.push(format!("models.{}.serving_config.env.{}", name, key));
+                }
+                keep
+            });
+        }
+    }
+    stripped.sort();
+    stripped
+}
+
+/// Keep credentials the local config already has where the bundle carries none
+pub fn restore_local_credentials(imported: &mut Config, local: &Config) {
+    for (name, auth) in imported.auth.iter_mut() {
+        if let Some(existing) = local.auth.get(name) {
+            auth.token = auth.token.take().or_else(|| existing.token.clone());
+            auth.password = auth.password.take().or_else(|| existing.password.clone());
+        }
+    }
+    for (name, proxy) in imported.proxies.iter_mut() {
+        let existing = local.proxies.get(name).and_then(|p| p.auth.as_ref());
+        if let (Some(auth), Some(existing)) = (proxy.auth.as_mut(), existing) {
+            if auth.password.is_empty() && auth.username == existing.username {
+                auth.password = existing.password.clone();
+            }
+        }
+    }
+    imported.dashboard.token = imported.dashboard.token.take().or_else(|| local.dashboard.token.clone());
+    for hook in imported.webhooks.iter_mut().filter(|hook| hook.secret.is_none()) {
+        hook.secret = local.webhooks.iter()
+            .find(|existing| existing.url == hook.url && existing.name == hook.name)
+            .and_then(|existing| existing.secret.clone());
+    }
+    for (name, manager) in imported.managers.iter_mut() {
+        if let Some(existing) = local.managers.get(name) {
+            for (key, value) in &existing.env_vars {
+                manager.env_vars.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        }
+    }
+}
+
+/// Replace the `from` prefix of every path-like string in `value` with `to`
+pub fn rebase_paths(value: &mut Value, from: &str, to: &str) {
+    match value {
+        Value::String(s) if !from.is_empty() && s.starts_with(from) => {
+            *s = format!("{}{}", to, &s[from.len()..]);
+        }
+        Value::Array(items) => items.iter_mut().for_each(|v| rebase_paths(v, from, to)),
+        Value::Object(map) => map.values_mut().for_each(|v| rebase_paths(v, from, to)),
+        _ => {}
+    }
+}
+
+/// Workspace-relative path of a bundle entry, if it is one RCM may write
+pub fn workspace_entry(entry: &str) -> Option<&Path> {
+    let path = Path::new(entry.strip_prefix(WORKSPACE_PREFIX)?);
+    let safe = path.components().all(|c| matches!(c, Component::Normal(_)));
+    (safe && path.starts_with(".rcm")).then_some(path)
+}
+
+fn config_file(config_path: Option<&str>) -> Result<PathBuf> {
+    match config_path {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Config::default_config_path(),
+    }
+}
+
+/// The user config as stored on disk over the defaults, without environment overrides
+async fn read_config(path: &Path) -> Result<Config> {
+    let Ok(content) = fs::read_to_string(path).await else {
+        return Ok(Config::default());
+    };
+    let layer: Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let mut merged = serde_json::to_value(Config::default())?;
+    crate::config_layers::merge(&mut merged, &layer);
+    serde_json::from_value(merged).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// The GPT registry's installed models; running instances belong to this machine
+/// and plaintext model credentials stay on it
+async fn exportable_registry(root: &Path, credentials: &mut Vec<String>) -> Result<Option<Vec<u8>>> {
+    let Ok(content) = fs::read_to_string(root.join(MODEL_REGISTRY)).await else {
+        return Ok(None);
+    };
+    let mut registry: Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", MODEL_REGISTRY))?;
+    if let Some(active) = registry.get_mut("active_models") {
+        *active = Value::Object(Default::default());
+    }
+    credentials.extend(strip_registry_credentials(&mut registry));
+    Ok(Some(serde_json::to_vec_pretty(&registry)?))
+}
+
+/// Collect `(entry, contents)` pairs for the workspace files that go in a bundle,
+/// adding the credentials left out of them to `credentials`
+async fn workspace_files(root: &Path, credentials: &mut Vec<String>) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut files = Vec::new();
+    if let Some(registry) = exportable_registry(root, credentials).await? {
+        files.push((format!("{}{}", WORKSPACE_PREFIX, MODEL_REGISTRY), registry));
+    }
+    if let Ok(system) = fs::read(root.join(SYSTEM_CONFIG)).await {
+        files.push((format!("{}{}", WORKSPACE_PREFIX, SYSTEM_CONFIG), system));
+    }
+    let let_dir = root.join(LET_DIR);
+    if let_dir.is_dir() {
+        for entry in walkdir::WalkDir::new(&let_dir).sort_by_file_name().into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(root)?.to_string_lossy().replace('\\', "/");
+            files.push((format!("{}{}", WORKSPACE_PREFIX, relative), fs::read(entry.path()).await?));
+        }
+    }
+    Ok(files)
+}
+
+/// Print the sanitised user config, or write a provisioning bundle
+pub async fn export(root: &Path, config_path: Option<&str>, bundle: bool, output: Option<&Path>) -> Result<()> {
+    let path = config_file(config_path)?;
+    let mut config = read_config(&path).await?;
+    let mut credentials = strip_credentials(&mut config);
+
+    if !bundle {
+        println!("{}", serde_json::to_string_pretty(&config)?);
+        return Ok(());
+    }
+
+    let mut files = vec![(CONFIG_ENTRY.to_string(), serde_json::to_vec_pretty(&config)?)];
+    files.extend(workspace_files(root, &mut credentials).await?);
+    let manifest = BundleManifest {
+        format: BUNDLE_FORMAT.to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        rcm_version: env!("CARGO_PKG_VERSION").to_string(),
+        workspace_root: root.to_path_buf(),
+        entries: files.iter().map(|(entry, _)| entry.clone()).collect(),
+        credentials,
+    };
+
+    let output = output.map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from(DEFAULT_BUNDLE));
+    if util::is_dry_run() {
+        println!("[dry-run] Would write {} with:", output.display());
+        for entry in &manifest.entries {
+            println!("    {}", entry);
+        }
+        return Ok(());
+    }
+
+    let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+    for (entry, contents) in std::iter::once((MANIFEST_ENTRY, manifest_json.as_slice()))
+        .chain(files.iter().map(|(entry, contents)| (entry.as_str(), contents.as_slice())))
+    {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o600);
+        header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+        header.set_cksum();
+        archive.append_data(&mut header, entry, contents)
+            .with_context(|| format!("Failed to add {} to bundle", entry))?;
+    }
+    let bytes = archive.into_inner()?.finish()?;
+    fs::write(&output, bytes).await
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+
+    println!("{}", style(format!("📦 Exported {} files to {}", manifest.entries.len(), output.display())).green());
+    if !manifest.credentials.is_empty() {
+        println!("🔒 Left out plaintext credentials: {}", manifest.credentials.join(", "));
+        println!("   Store them with 'rcm secrets set <name>' and reference them as secret:<name> to carry them in bundles");
+    }
+    Ok(())
+}
+
+fn read_bundle(path: &Path) -> Result<(BundleManifest, Vec<(String, Vec<u8>)>)> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open bundle {}", path.display()))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    let mut manifest = None;
+    let mut files = Vec::new();
+    for entry in archive.entries().context("Failed to read bundle")? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().replace('\\', "/");
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents)?;
+        if name == MANIFEST_ENTRY {
+            manifest = Some(serde_json::from_slice::<BundleManifest>(&contents).context("Invalid bundle manifest")?);
+        } else {
+            files.push((name, contents));
+        }
+    }
+    let manifest = manifest.ok_or_else(|| anyhow!("{} has no {}; not an RCM bundle", path.display(), MANIFEST_ENTRY))?;
+    if manifest.format != BUNDLE_FORMAT {
+        return Err(anyhow!("Unsupported bundle format '{}' (expected {})", manifest.format, BUNDLE_FORMAT));
+    }
+    Ok((manifest, files))
+}
+
+/// Add the bundle's models to the local registry, keeping what is installed or running here
+async fn merge_registry(path: &Path, contents: &[u8], from: &Path, to: &Path) -> Result<()> {
+    let mut incoming: Value = serde_json::from_slice(contents).context("Invalid model registry in bundle")?;
+    rebase_paths(&mut incoming, &from.to_string_lossy(), &to.to_string_lossy());
+
+    let mut registry = match fs::read_to_string(path).await {
+        Ok(content) => serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?,
+        Err(_) => {
+            let mut fresh = incoming.clone();
+            fresh["registry_path"] = Value::String(path.to_string_lossy().into_owned());
+            fresh
+        }
+    };
+    if let (Some(models), Some(new)) = (registry.get_mut("models").and_then(Value::as_object_mut), incoming["models"].as_object()) {
+        for (name, model) in new {
+            models.entry(name.clone()).or_insert_with(|| model.clone());
+        }
+    }
+    if registry["default_model"].is_null() {
+        registry["default_model"] = incoming["default_model"].clone();
+    }
+    util::write_file(path, serde_json::to_vec_pretty(&registry)?).await
+}
+
+/// Apply a provisioning bundle to this machine and workspace
+pub async fn import(root: &Path, config_path: Option<&str>, bundle: &Path) -> Result<()> {
+    let (manifest, files) = read_bundle(bundle)?;
+    println!("📦 Importing {} (exported {} by rcm {})", bundle.display(), manifest.created_at, manifest.rcm_version);
+
+    let mut applied = 0;
+    for (entry, contents) in &files {
+        if entry == CONFIG_ENTRY {
+            let path = config_file(config_path)?;
+            let mut imported: Config = serde_json::from_slice(contents).context("Invalid config in bundle")?;
+            if path.exists() {
+                if let Ok(local) = read_config(&path).await {
+                    restore_local_credentials(&mut imported, &local);
+                }
+                if !util::is_dry_run() {
+                    let backup = util::backup_file(&path).await?;
+                    println!("💾 Previous config saved to {}", backup.display());
+                }
+            }
+            imported.validate().context("Bundle config is invalid")?;
+            if let Some(parent) = path.parent().filter(|_| !util::is_dry_run()) {
+                fs::create_dir_all(parent).await?;
+            }
+            util::write_file(&path, serde_json::to_vec_pretty(&imported)?).await?;
+            println!("  ✓ user config → {}", path.display());
+            applied += 1;
+            continue;
+        }
+
+        let Some(relative) = workspace_entry(entry) else {
+            println!("{}", style(format!("  ⚠️ Skipping unexpected entry {}", entry)).yellow());
+            continue;
+        };
+        let path = root.join(relative);
+        if let Some(parent) = path.parent().filter(|_| !util::is_dry_run()) {
+            fs::create_dir_all(parent).await?;
+        }
+        if relative == Path::new(MODEL_REGISTRY) {
+            merge_registry(&path, contents, &manifest.workspace_root, root).await?;
+        } else {
+            util::write_file(&path, contents).await?;
+        }
+        println!("  ✓ {}", relative.display());
+        applied += 1;
+    }
+
+    println!("{}", style(format!("✅ Applied {} of {} bundle entries", applied, files.len())).green());
+    if !manifest.credentials.is_empty() {
+        println!("🔒 These credentials were not in the bundle; set them on this machine:");
+        for key in &manifest.credentials {
+            println!("   {}", key);
+        }
+    }
+    if files.iter().any(|(entry, _)| entry.ends_with(MODEL_REGISTRY)) {
+        println!("💡 Model weights are not bundled; fetch them with 'rcm gpt install <model>'");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AuthConfig, AuthType, WebhookConfig};
+
+    fn auth(token: &str) -> AuthConfig {
+        AuthConfig {
+            auth_type: AuthType::Token,
+            token: Some(token.to_string()),
+            username: None,
+            password: None,
+            key_file: None,
+            cert_file: None,
+        }
+    }
+
+    #[test]
+    fn test_strip_and_restore_credentials() {
+        let mut config = Config::default();
+        config.auth.insert("npm".to_string(), auth("npm_plaintext"));
+        config.auth.insert("github".to_string(), auth("secret:github"));
+        let hook = |name: &str, secret: &str| WebhookConfig {
+            name: Some(name.to_string()),
+            url: format!("https://hooks.example.com/{}", name),
+            secret: Some(secret.to_string()),
+            events: Vec::new(),
+        };
+        config.webhooks = vec![hook("chat", "hmac_plaintext"), hook("cmdb", "secret:rcm-webhook")];
+        let local = config.clone();
+
+        let stripped = strip_credentials(&mut config);
+        assert_eq!(stripped, vec!["auth.npm.token", "webhooks.chat.secret"]);
+        assert_eq!(config.auth["npm"].token, None);
+        assert_eq!(config.auth["github"].token.as_deref(), Some("secret:github"));
+        assert_eq!(config.webhooks[0].secret, None);
+        assert_eq!(config.webhooks[1].secret.as_deref(), Some("secret:rcm-webhook"));
+
+        restore_local_credentials(&mut config, &local);
+        assert_eq!(config.auth["npm"].token.as_deref(), Some("npm_plaintext"));
+        assert_eq!(config.webhooks[0].secret.as_deref(), Some("hmac_plaintext"));
+    }
+
+    #[test]
+    fn test_strip_registry_credentials() {
+        let mut registry = serde_json::json!({
+            "models": {
+                "llama3": { "serving_config": {
+                    "auth_token": "sk-plaintext",
+                    "env": { "HF_TOKEN": "hf_abc", "OPENAI_API_KEY": "secret:openai", "CUDA_VISIBLE_DEVICES": "0" }
+                } },
+                "phi3": { "serving_config": { "auth_token": "secret:phi3", "env": {} } }
+            }
+        });
+        let stripped = strip_registry_credentials(&mut registry);
+        assert_eq!(stripped, vec!["models.llama3.serving_config.auth_token", "models.llama3.serving_config.env.HF_TOKEN"]);
+        let llama = &registry["models"]["llama3"]["serving_config"];
+        assert!(llama["auth_token"].is_null());
+        assert_eq!(llama["env"], serde_json::json!({ "OPENAI_API_KEY": "secret:openai", "CUDA_VISIBLE_DEVICES": "0" }));
+        assert_eq!(registry["models"]["phi3"]["serving_config"]["auth_token"], "secret:phi3");
+    }
+
+    #[test]
+    fn test_rebase_and_entries() {
+        let mut registry = serde_json::json!({
+            "models": { "llama3": { "model_path": "/home/a/proj/.rcm/models/llama3", "version": "latest" } }
+        });
+        rebase_paths(&mut registry, "/home/a/proj", "/srv/proj");
+        assert_eq!(registry["models"]["llama3"]["model_path"], "/srv/proj/.rcm/models/llama3");
+        assert_eq!(registry["models"]["llama3"]["version"], "latest");
+
+        assert_eq!(workspace_entry("workspace/.rcm/let/node.json"), Some(Path::new(".rcm/let/node.json")));
+        assert_eq!(workspace_entry("workspace/.rcm/../../etc/passwd"), None);
+        assert_eq!(workspace_entry("workspace/src/main.rs"), None);
+        assert_eq!(workspace_entry("config/config.json"), None);
+    }
+}
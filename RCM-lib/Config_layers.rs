@@ -0,0 +1,541 @@
+//! Layered configuration and profiles
+//!
+//! The effective config is built from, lowest precedence first:
+//!
+//! 1. built-in defaults
+//! 2. `/etc/rcm/config.json` (system)
+//! 3. the user config (`~/.config/rcm/config.json`, or `--config`)
+//! 4. `.rcm/config.json` in the workspace
+//! 5. the selected profile (`--profile` or `RCM_PROFILE`)
+//! 6. `RCM_*` and proxy environment variables
+//! 7. `--set key=value` flags
+//!
+//! Files may hold any subset of the config; objects are merged key by key and
+//! everything else replaces the lower layer. A file's `profiles` object holds
+//! named partial configs, applied on top of all files when selected, in the
+//! same system < user < workspace order. The layer each effective value came
+//! from is kept for `rcm config show --origin`.
+//!
+//! `.rcm/config.json` arrives with whatever repository was cloned, so it may
+//! not set security, endpoint or credential keys (`RESTRICTED_KEYS`) until the
+//! workspace is listed in `security.trusted_workspaces` by the user or system
+//! config (`rcm config trust`); such keys are dropped with a warning.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use tokio::fs;
+use crate::config::Config;
+
+/// Machine-wide config file
+pub const SYSTEM_CONFIG: &str = "/etc/rcm/config.json";
+
+/// Workspace config file, relative to the workspace root
+pub const WORKSPACE_CONFIG: &str = ".rcm/config.json";
+
+/// Environment variable selecting a profile when `--profile` isn't given
+pub const PROFILE_ENV: &str = "RCM_PROFILE";
+
+/// Where each effective value came from, keyed by dotted path
+pub type Origins = BTreeMap<String, String>;
+
+/// Keys an untrusted workspace config may not set; `*` matches any one key
+pub const RESTRICTED_KEYS: &[&str] = &[
+    "security",
+    "registries",
+    "proxies",
+    "auth",
+    "webhooks",
+    "dashboard",
+    "telemetry.otlp_endpoint",
+    "telemetry.otlp_headers",
+    "managers.*.registry",
+    "managers.*.proxy",
+    "managers.*.auth",
+    "managers.*.binary_path",
+    "managers.*.env_vars",
+];
+
+/// Whether the ignored-keys warning was printed in this process
+static WARNED_UNTRUSTED: AtomicBool = AtomicBool::new(false);
+
+/// Inputs to config resolution beyond the files themselves
+#[derive(Debug, Default, Clone)]
+pub struct LoadOptions<'a> {
+    /// Replaces the user config file (`--config`)
+    pub config_path: Option<&'a str>,
+    /// Defaults to the current directory
+    pub workspace_root: Option<&'a Path>,
+    pub profile: Option<&'a str>,
+    /// `key=value` pairs from `--set`
+    pub overrides: &'a [String],
+}
+
+/// Owned copy of the options the command's config was resolved with
+struct ProcessOptions {
+    config_path: Option<String>,
+    workspace_root: Option<PathBuf>,
+    profile: Option<String>,
+    overrides: Vec<String>,
+}
+
+static PROCESS_OPTIONS: OnceLock<ProcessOptions> = OnceLock::new();
+
+/// Keep this run's options so later `Config::load` calls see the same workspace, profile and `--set`
+pub fn remember(options: &LoadOptions<'_>) {
+    let _ = PROCESS_OPTIONS.set(ProcessOptions {
+        config_path: options.config_path.map(str::to_string),
+        workspace_root: options.workspace_root.map(Path::to_path_buf),
+        profile: options.profile.map(str::to_string),
+        overrides: options.overrides.to_vec(),
+    });
+}
+
+/// The options kept by `remember`, or the defaults for the current directory
+pub fn process_options() -> LoadOptions<'static> {
+    match PROCESS_OPTIONS.get() {
+        Some(options) => LoadOptions {
+            config_path: options.config_path.as_deref(),
+            workspace_root: options.workspace_root.as_deref(),
+            profile: options.profile.as_deref(),
+            overrides: &options.overrides,
+        },
+        None => LoadOptions::default(),
+    }
+}
+
+/// Whether a dotted key path falls under `RESTRICTED_KEYS`
+pub fn is_restricted(key: &str) -> bool {
+    let segments: Vec<&str> = key.split('.').map(|s| s.split('[').next().unwrap_or(s)).collect();
+    RESTRICTED_KEYS.iter().any(|pattern| {
+        let pattern: Vec<&str> = pattern.split('.').collect();
+        segments.len() >= pattern.len()
+            && pattern.iter().zip(&segments).all(|(p, s)| *p == "*" || p == s)
+    })
+}
+
+/// Drop restricted keys from a layer, returning the dotted paths removed
+fn strip_restricted(layer: &mut Value) -> Vec<String> {
+    fn strip(value: &mut Value, pattern: &[&str], prefix: &str, removed: &mut Vec<String>) {
+        let (Some((first, rest)), Some(map)) = (pattern.split_first(), value.as_object_mut()) else {
+            return;
+        };
+        let keys: Vec<String> = map.keys().filter(|k| *first == "*" || k == first).cloned().collect();
+        for key in keys {
+            let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+            if rest.is_empty() {
+                map.remove(&key);
+                removed.push(path);
+            } else if let Some(child) = map.get_mut(&key) {
+                strip(child, rest, &path, removed);
+            }
+        }
+    }
+    let mut removed = Vec::new();
+    for pattern in RESTRICTED_KEYS {
+        let segments: Vec<&str> = pattern.split('.').collect();
+        strip(layer, &segments, "", &mut removed);
+    }
+    removed
+}
+
+/// Whether the layers merged so far list `root` in `security.trusted_workspaces`
+fn is_trusted(merged: &Value, root: &Path) -> bool {
+    let Ok(root) = root.canonicalize() else {
+        return false;
+    };
+    merged.pointer("/security/trusted_workspaces")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .any(|entry| Path::new(entry).canonicalize().is_ok_and(|trusted| trusted == root))
+}
+
+/// Merge `layer` into `base`: objects key by key, anything else replaces
+pub fn merge(base: &mut Value, layer: &Value) {
+    match (base, layer) {
+        (Value::Object(base), Value::Object(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(key) {
+                    Some(existing) if existing.is_object() && value.is_object() => merge(existing, value),
+                    _ => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer.clone(),
+    }
+}
+
+/// Dotted paths of every non-object value; empty objects count as values
+pub fn leaves(value: &Value) -> BTreeMap<String, Value> {
+    fn walk(value: &Value, prefix: &str, out: &mut BTreeMap<String, Value>) {
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (key, child) in map {
+                    let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                    walk(child, &path, out);
+                }
+            }
+            _ => {
+                out.insert(prefix.to_string(), value.clone());
+            }
+        }
+    }
+    let mut out = BTreeMap::new();
+    walk(value, "", &mut out);
+    out
+}
+
+fn record(origins: &mut Origins, layer: &Value, origin: &str) {
+    for path in leaves(layer).into_keys() {
+        // A replaced subtree takes its children's origin with it
+        origins.retain(|existing, _| !existing.starts_with(&format!("{}.", path)));
+        origins.insert(path, origin.to_string());
+    }
+}
+
+/// Paths whose value differs between two snapshots
+fn changed(before: &Value, after: &Value) -> Vec<String> {
+    let before = leaves(before);
+    leaves(after).into_iter()
+        .filter(|(path, value)| before.get(path) != Some(value))
+        .map(|(path, _)| path)
+        .collect()
+}
+
+async fn read_layer(path: &Path) -> Result<Option<Value>> {
+    let Ok(content) = fs::read_to_string(path).await else {
+        return Ok(None);
+    };
+    let value: Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    if !value.is_object() {
+        return Err(anyhow!("{} must contain a JSON object", path.display()));
+    }
+    Ok(Some(value))
+}
+
+/// Config files in precedence order, lowest first
+fn layer_files(options: &LoadOptions<'_>) -> Result<Vec<(&'static str, PathBuf)>> {
+    let user = match options.config_path {
+        Some(path) => PathBuf::from(path),
+        None => Config::default_config_path()?,
+    };
+    Ok(vec![
+        ("system", PathBuf::from(SYSTEM_CONFIG)),
+        ("user", user),
+        ("workspace", workspace_root(options).join(WORKSPACE_CONFIG)),
+    ])
+}
+
+fn workspace_root(options: &LoadOptions<'_>) -> PathBuf {
+    options.workspace_root.map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Build the effective config and the origin of each value
+pub async fn resolve(options: &LoadOptions<'_>) -> Result<(Config, Origins)> {
+    let mut merged = serde_json::to_value(Config::default())?;
+    let mut origins = Origins::new();
+    record(&mut origins, &merged, "default");
+
+    let mut profiles = Vec::new();
+    for (layer, path) in layer_files(options)? {
+        let Some(mut value) = read_layer(&path).await? else {
+            continue;
+        };
+        let mut defined = value.as_object_mut().and_then(|o| o.remove("profiles"));
+        if layer == "workspace" && !is_trusted(&merged, &workspace_root(options)) {
+            let mut ignored = strip_restricted(&mut value);
+            for (name, partial) in defined.iter_mut().filter_map(Value::as_object_mut).flatten() {
+                ignored.extend(strip_restricted(partial).into_iter().map(|key| format!("profiles.{}.{}", name, key)));
+            }
+            if !ignored.is_empty() && !WARNED_UNTRUSTED.swap(true, Ordering::SeqCst) {
+                eprintln!(
+                    "{} Ignoring {} from {}; run `rcm config trust` if you trust this workspace",
+                    console::style("⚠️").yellow(),
+                    ignored.join(", "),
+                    path.display()
+                );
+            }
+        }
+        if let Some(defined) = defined {
+            profiles.push((layer, defined));
+        }
+        merge(&mut merged, &value);
+        record(&mut origins, &value, &format!("{} ({})", layer, path.display()));
+    }
+
+    let profile = options.profile.map(str::to_string)
+        .or_else(|| std::env::var(PROFILE_ENV).ok().filter(|p| !p.is_empty()));
+    if let Some(name) = &profile {
+        let mut found = false;
+        for (layer, defined) in &profiles {
+            if let Some(partial) = defined.get(name) {
+                merge(&mut merged, partial);
+                record(&mut origins, partial, &format!("profile {} ({})", name, layer));
+                found = true;
+            }
+        }
+        if !found {
+            let known: Vec<&String> = profiles.iter().filter_map(|(_, d)| d.as_object()).flat_map(|o| o.keys()).collect();
+            return Err(anyhow!(
+                "Profile '{}' is not defined in any config layer{}",
+                name,
+                if known.is_empty() { String::new() } else { format!(". Known: {}", known.iter().map(|k| k.as_str()).collect::<Vec<_>>().join(", ")) }
+            ));
+        }
+    }
+
+    let mut config: Config = serde_json::from_value(merged.clone()).context("Invalid configuration")?;
+
+    config.apply_env_overrides().await?;
+    let after_env = serde_json::to_value(&config)?;
+    for path in changed(&merged, &after_env) {
+        origins.insert(path, "env".to_string());
+    }
+
+    for assignment in options.overrides {
+        let (key, value) = assignment.split_once('=')
+            .ok_or_else(|| anyhow!("Invalid --set '{}'. Use key=value", assignment))?;
+        config.set(key.trim(), value.trim())?;
+    }
+    if !options.overrides.is_empty() {
+        for path in changed(&after_env, &serde_json::to_value(&config)?) {
+            origins.insert(path, "--set".to_string());
+        }
+    }
+
+    Ok((config, origins))
+}
+
+/// `rcm config show --origin`
+pub fn print_origins(config: &Config, origins: &Origins) -> Result<()> {
+    let values = leaves(&serde_json::to_value(config)?);
+    let width = values.keys().map(String::len).max().unwrap_or(0);
+    for (path, value) in values {
+        // Credentials never leave through this view
+        let shown = crate::config::mask(&path, value).to_string();
+        let origin = origins.get(&path).map(String::as_str).unwrap_or("default");
+        println!("{:width$}  {:<32}  {}", path, shown, console::style(origin).dim(), width = width);
+    }
+    Ok(())
+}
+
+/// The file `rcm config set/unset --scope` writes to
+fn scope_file(options: &LoadOptions<'_>, scope: &str) -> Result<(&'static str, PathBuf)> {
+    layer_files(options)?
+        .into_iter()
+        .find(|(layer, _)| *layer == scope)
+        .ok_or_else(|| anyhow!("Unknown scope '{}'. Use user, workspace or system", scope))
+}
+
+/// The part of a key written as a whole: list items are stored with their list
+fn stored_key(key: &str) -> &str {
+    key.split('[').next().unwrap_or(key)
+}
+
+/// Put a value at a pointer, creating missing objects on the way
+fn insert_path(tree: &mut Value, pointer: &str, value: Value) {
+    let mut node = tree;
+    let tokens: Vec<String> = pointer.split('/').skip(1)
+        .map(|t| t.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    for (i, token) in tokens.iter().enumerate() {
+        if !node.is_object() {
+            *node = Value::Object(Default::default());
+        }
+        let map = node.as_object_mut().expect("object");
+        if i + 1 == tokens.len() {
+            map.insert(token.clone(), value);
+            return;
+        }
+        node = map.entry(token.clone()).or_insert_with(|| Value::Object(Default::default()));
+    }
+}
+
+async fn write_layer(path: &Path, layer: &Value) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    crate::util::write_file(path, serde_json::to_string_pretty(layer)? + "\n").await
+}
+
+/// `rcm config get`
+pub fn print_value(config: &Config, key: &str) -> Result<()> {
+    match config.get(key)? {
+        Value::String(s) => println!("{}", s),
+        value => println!("{}", serde_json::to_string_pretty(&value)?),
+    }
+    Ok(())
+}
+
+/// `rcm config list`
+pub fn print_list(config: &Config, format: &str) -> Result<()> {
+    let values = config.list()?;
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&values)?),
+        "table" => {
+            for (key, value) in values {
+                println!("{} = {}", key, value);
+            }
+        }
+        _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+    }
+    Ok(())
+}
+
+/// `rcm config set`: validate against the effective config, then write only that key to one layer
+pub async fn set(options: &LoadOptions<'_>, scope: &str, key: &str, value: &str) -> Result<()> {
+    let (layer, path, stored) = write_key(options, scope, key, value).await?;
+    println!("✅ Set {} = {} in {} config ({})", key, crate::config::mask(key, stored), layer, path.display());
+    Ok(())
+}
+
+/// Set one key in one layer file, returning the layer, its file and the stored value
+pub async fn write_key(options: &LoadOptions<'_>, scope: &str, key: &str, value: &str) -> Result<(&'static str, PathBuf, Value)> {
+    let (layer, path) = scope_file(options, scope)?;
+    let (mut probe, _) = resolve(options).await?;
+    if layer == "workspace" && is_restricted(key) && !is_trusted(&serde_json::to_value(&probe)?, &workspace_root(options)) {
+        return Err(anyhow!("{} is only read from the workspace config of a trusted workspace; run `rcm config trust` first", key));
+    }
+    probe.set(key, value)?;
+
+    let stored = stored_key(key);
+    let mut file = read_layer(&path).await?.unwrap_or_else(|| Value::Object(Default::default()));
+    insert_path(&mut file, &crate::config::key_pointer(stored)?, probe.value_at(stored)?);
+    write_layer(&path, &file).await?;
+    Ok((layer, path, probe.value_at(key)?))
+}
+
+/// `rcm config trust`: let the workspace config set restricted keys, or stop letting it
+pub async fn trust(options: &LoadOptions<'_>, root: &Path, revoke: bool) -> Result<()> {
+    let root = root.canonicalize()
+        .with_context(|| format!("Failed to resolve {}", root.display()))?
+        .display()
+        .to_string();
+    let (_, path) = scope_file(options, "user")?;
+    let mut file = read_layer(&path).await?.unwrap_or_else(|| Value::Object(Default::default()));
+    let mut trusted: Vec<String> = file.pointer("/security/trusted_workspaces")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+    let listed = trusted.contains(&root);
+    match (revoke, listed) {
+        (false, true) | (true, false) => {
+            println!("{} {} is already {}", console::style("✓").green(), root, if revoke { "untrusted" } else { "trusted" });
+            return Ok(());
+        }
+        (false, false) => trusted.push(root.clone()),
+        (true, true) => trusted.retain(|entry| *entry != root),
+    }
+    insert_path(&mut file, "/security/trusted_workspaces", serde_json::to_value(&trusted)?);
+    write_layer(&path, &file).await?;
+    if revoke {
+        println!("🔒 {} no longer trusted; its {} can't set security, endpoint or credential keys", root, WORKSPACE_CONFIG);
+    } else {
+        println!("🔓 Trusted {}; its {} may now set any key ({})", root, WORKSPACE_CONFIG, path.display());
+    }
+    Ok(())
+}
+
+/// `rcm config unset`: drop a key from one layer so lower layers show through
+pub async fn unset(options: &LoadOptions<'_>, scope: &str, key: &str) -> Result<()> {
+    let (layer, path) = remove_key(options, scope, key).await?;
+    println!("🗑️ Unset {} in {} config ({})", key, layer, path.display());
+    Ok(())
+}
+
+/// Remove one key from one layer file, returning the layer and its file
+pub async fn remove_key(options: &LoadOptions<'_>, scope: &str, key: &str) -> Result<(&'static str, PathBuf)> {
+    let (layer, path) = scope_file(options, scope)?;
+    let mut file = read_layer(&path).await?
+        .ok_or_else(|| anyhow!("{} does not exist", path.display()))?;
+
+    let stored = stored_key(key);
+    if stored != key {
+        // Removing a list item rewrites the list in this layer
+        let (mut probe, _) = resolve(options).await?;
+        probe.unset(key)?;
+        insert_path(&mut file, &crate::config::key_pointer(stored)?, probe.value_at(stored)?);
+    } else {
+        let pointer = crate::config::key_pointer(key)?;
+        let (parent, last) = pointer.rsplit_once('/').unwrap_or(("", &pointer));
+        let removed = file.pointer_mut(parent)
+            .and_then(Value::as_object_mut)
+            .and_then(|map| map.remove(&last.replace("~1", "/").replace("~0", "~")));
+        if removed.is_none() {
+            return Err(anyhow!("{} is not set in the {} config ({})", key, layer, path.display()));
+        }
+    }
+    write_layer(&path, &file).await?;
+
+    // Make sure what is left still loads
+    resolve(options).await.with_context(|| format!("Unsetting {} left an invalid configuration", key))?;
+    Ok((layer, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merge_is_deep_for_objects() {
+        let mut base = json!({"core": {"parallel_jobs": 4, "offline_mode": false}, "proxies": {}});
+        merge(&mut base, &json!({"core": {"offline_mode": true}, "proxies": {"corp": {"http": "p"}}}));
+        assert_eq!(base, json!({"core": {"parallel_jobs": 4, "offline_mode": true}, "proxies": {"corp": {"http": "p"}}}));
+    }
+
+    #[test]
+    fn test_untrusted_workspace_keys_are_stripped() {
+        let mut layer = json!({
+            "core": {"parallel_jobs": 2},
+            "security": {"allow_insecure": true},
+            "managers": {"npm": {"enabled": true, "registry": "https://evil.example"}},
+            "webhooks": [{"url": "https://evil.example"}],
+        });
+        let mut removed = strip_restricted(&mut layer);
+        removed.sort();
+        assert_eq!(removed, vec!["managers.npm.registry", "security", "webhooks"]);
+        assert_eq!(layer, json!({"core": {"parallel_jobs": 2}, "managers": {"npm": {"enabled": true}}}));
+        assert!(is_restricted("managers.pip.env_vars.PIP_INDEX_URL"));
+        assert!(is_restricted("webhooks[0].url"));
+        assert!(!is_restricted("managers.npm.enabled"));
+    }
+
+    #[test]
+    fn test_origins_follow_the_highest_layer() {
+        let mut origins = Origins::new();
+        record(&mut origins, &json!({"core": {"parallel_jobs": 4, "offline_mode": false}}), "default");
+        record(&mut origins, &json!({"core": {"offline_mode": true}}), "workspace");
+        assert_eq!(origins["core.parallel_jobs"], "default");
+        assert_eq!(origins["core.offline_mode"], "workspace");
+        assert_eq!(changed(&json!({"a": 1, "b": 2}), &json!({"a": 1, "b": 3})), vec!["b"]);
+    }
+
+    #[test]
+    fn test_generic_get_and_set() {
+        let mut config = Config::default();
+        config.set("managers.npm.enabled", "false").unwrap();
+        assert_eq!(config.get("managers.npm.enabled").unwrap(), json!(false));
+        assert!(config.set("core.parallel_jobs", "many").is_err());
+        assert!(config.set("core.no_such_key", "1").is_err());
+
+        config.set("security.blocked_packages", "left-pad,event-stream").unwrap();
+        config.set("security.blocked_packages[2]", "flatmap-stream").unwrap();
+        assert_eq!(config.get("security.blocked_packages[2]").unwrap(), json!("flatmap-stream"));
+        config.unset("security.blocked_packages[0]").unwrap();
+        assert_eq!(config.security.blocked_packages, vec!["event-stream", "flatmap-stream"]);
+
+        config.set("dashboard.token", "abc").unwrap();
+        assert_eq!(config.get("dashboard.token").unwrap(), json!("********"));
+        assert_eq!(crate::config::key_pointer("a.b[2][0]").unwrap(), "/a/b/2/0");
+        assert!(crate::config::key_pointer("a..b").is_err());
+    }
+}
@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tokio::fs;
-use crate::util::get_os_info;
+use crate::util::{calculate_directory_size, get_os_info};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
@@ -21,6 +21,12 @@ pub struct Config {
     pub telemetry: TelemetryConfig,
     pub cache: CacheConfig,
     pub security: SecurityConfig,
+    /// Central endpoint for fleet dashboards (see `rcm workspace export`)
+    #[serde(default)]
+    pub dashboard: DashboardConfig,
+    /// Endpoints notified of workspace events (see `rcm webhook`)
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -35,6 +41,13 @@ pub struct CoreConfig {
     pub workspace_detection: bool,
     pub color_output: ColorMode,
     pub log_level: LogLevel,
+    /// Where workspace state lives: files or sqlite (see `rcm state`)
+    #[serde(default = "default_state_backend")]
+    pub state_backend: String,
+}
+
+fn default_state_backend() -> String {
+    "files".to_string()
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -83,6 +96,9 @@ pub struct AuthConfig {
     pub password: Option<String>,
     pub key_file: Option<String>,
     pub cert_file: Option<String>,
+    /// Registry host the credential was issued for; it is never sent to another
+    #[serde(default)]
+    pub host: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -112,6 +128,22 @@ pub struct TelemetryConfig {
     pub collect_performance: bool,
     pub collect_errors: bool,
     pub collect_usage: bool,
+    /// OTLP/HTTP collector for traces, e.g. http://localhost:4318 (or OTEL_EXPORTER_OTLP_ENDPOINT)
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+    /// Headers sent to the collector, e.g. an API key
+    #[serde(default)]
+    pub otlp_headers: HashMap<String, String>,
+    /// `service.name` on exported spans
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+    /// Attach (redacted) prompts and completions to GPT spans
+    #[serde(default)]
+    pub capture_content: bool,
+}
+
+fn default_service_name() -> String {
+    "rcm".to_string()
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -120,10 +152,26 @@ pub struct CacheConfig {
     pub directory: Option<String>,
     pub max_size_mb: u64,
     pub ttl_hours: u64,
+    /// zstd-compress cache entries and transaction snapshots
     pub compress: bool,
+    /// zstd level, 1 (fastest) to 22 (smallest)
+    #[serde(default = "default_compression_level")]
+    pub compression_level: i32,
     pub cleanup_on_exit: bool,
 }
 
+fn default_compression_level() -> i32 {
+    3
+}
+
+/// Disk usage of one cache directory, for cache reporting
+#[derive(Debug, Serialize, Clone)]
+pub struct CacheUsage {
+    pub name: String,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SecurityConfig {
     pub verify_signatures: bool,
@@ -132,6 +180,69 @@ pub struct SecurityConfig {
     pub blocked_packages: Vec<String>,
     pub scan_for_vulnerabilities: bool,
     pub quarantine_suspicious: bool,
+    /// Extra regex patterns masked in output (see `redact`)
+    #[serde(default)]
+    pub redact_patterns: Vec<String>,
+    /// Licenses dependencies may use (see `rcm license check`)
+    #[serde(default)]
+    pub license_policy: LicensePolicy,
+    /// Where `secret:` values live: keychain, env or file (see `rcm auth`)
+    #[serde(default = "default_secret_backend")]
+    pub secret_backend: String,
+    /// Workspaces whose `.rcm/config.json` may set security, endpoint and credential keys
+    #[serde(default)]
+    pub trusted_workspaces: Vec<String>,
+}
+
+fn default_secret_backend() -> String {
+    "keychain".to_string()
+}
+
+/// Allowed and denied SPDX license identifiers
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LicensePolicy {
+    /// When non-empty, only these licenses are accepted
+    #[serde(default)]
+    pub allowed: Vec<String>,
+    /// Licenses that always fail the check
+    #[serde(default)]
+    pub denied: Vec<String>,
+    /// Accept dependencies whose license cannot be determined
+    #[serde(default)]
+    pub allow_unknown: bool,
+    /// Packages exempt from the policy, as `manager:name` or `name`
+    #[serde(default)]
+    pub exceptions: Vec<String>,
+}
+
+/// Where `rcm workspace export` sends reports
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DashboardConfig {
+    /// HTTP endpoint receiving `rcm.dashboard/v1` reports
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Bearer token, or a `secret:` reference
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Also push after every `rcm workspace check`
+    #[serde(default)]
+    pub push_on_check: bool,
+}
+
+/// An HTTP endpoint receiving signed event notifications
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct WebhookConfig {
+    /// Label used in output; defaults to the URL's host
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Endpoint receiving the POST, or a `secret:` reference
+    pub url: String,
+    /// HMAC-SHA256 key for `X-RCM-Signature`, or a `secret:` reference
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Events to send, e.g. `dependency.added` or `model.*`; empty sends all
+    #[serde(default)]
+    pub events: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -163,6 +274,8 @@ impl Default for Config {
             telemetry: TelemetryConfig::default(),
             cache: CacheConfig::default(),
             security: SecurityConfig::default(),
+            dashboard: DashboardConfig::default(),
+            webhooks: Vec::new(),
         }
     }
 }
@@ -180,6 +293,7 @@ impl Default for CoreConfig {
             workspace_detection: true,
             color_output: ColorMode::Auto,
             log_level: LogLevel::Info,
+            state_backend: default_state_backend(),
         }
     }
 }
@@ -207,6 +321,10 @@ impl Default for TelemetryConfig {
             collect_performance: true,
             collect_errors: true,
             collect_usage: true,
+            otlp_endpoint: None,
+            otlp_headers: HashMap::new(),
+            service_name: default_service_name(),
+            capture_content: false,
         }
     }
 }
@@ -219,6 +337,7 @@ impl Default for CacheConfig {
             max_size_mb: 1024,
             ttl_hours: 24,
             compress: true,
+            compression_level: default_compression_level(),
             cleanup_on_exit: false,
         }
     }
@@ -233,30 +352,32 @@ impl Default for SecurityConfig {
             blocked_packages: vec![],
             scan_for_vulnerabilities: true,
             quarantine_suspicious: true,
+            redact_patterns: vec![],
+            license_policy: LicensePolicy::default(),
+            secret_backend: default_secret_backend(),
+            trusted_workspaces: Vec::new(),
         }
     }
 }
 
 impl Config {
-    /// Load configuration from file or create default
+    /// Load the layered configuration (see `config_layers`) with this run's
+    /// workspace, profile and `--set` flags; `config_path` replaces `--config`
     pub async fn load(config_path: Option<&str>) -> Result<Self> {
-        let config_file = if let Some(path) = config_path {
-            PathBuf::from(path)
-        } else {
-            Self::default_config_path()?
-        };
-
-        if config_file.exists() {
-            Self::load_from_file(&config_file).await
-        } else {
-            let config = Self::default();
-            config.save_to_file(&config_file).await?;
-            Ok(config)
+        let mut options = crate::config_layers::process_options();
+        if config_path.is_some() {
+            options.config_path = config_path;
         }
+        Self::load_with(&options).await
+    }
+
+    /// Load the layered configuration with an explicit workspace, profile and `--set` overrides
+    pub async fn load_with(options: &crate::config_layers::LoadOptions<'_>) -> Result<Self> {
+        Ok(crate::config_layers::resolve(options).await?.0)
     }
 
     /// Get default configuration file path
-    fn default_config_path() -> Result<PathBuf> {
+    pub fn default_config_path() -> Result<PathBuf> {
         if let Some(config_dir) = dirs::config_dir() {
             Ok(config_dir.join("rcm").join("config.json"))
         } else {
@@ -269,20 +390,6 @@ impl Config {
         }
     }
 
-    /// Load configuration from file
-    async fn load_from_file(path: &Path) -> Result<Self> {
-        let content = fs::read_to_string(path).await
-            .context("Failed to read configuration file")?;
-
-        let mut config: Self = serde_json::from_str(&content)
-            .context("Failed to parse configuration file")?;
-
-        // Apply environment variable overrides
-        config.apply_env_overrides().await?;
-
-        Ok(config)
-    }
-
     /// Save configuration to file
     pub async fn save_to_file(&self, path: &Path) -> Result<()> {
         if let Some(parent) = path.parent() {
@@ -298,7 +405,7 @@ impl Config {
     }
 
     /// Apply environment variable overrides
-    async fn apply_env_overrides(&mut self) -> Result<()> {
+    pub async fn apply_env_overrides(&mut self) -> Result<()> {
         // Core config overrides
         if let Ok(jobs) = std::env::var("RCM_PARALLEL_JOBS") {
             if let Ok(jobs) = jobs.parse::<usize>() {
@@ -405,6 +512,19 @@ impl Config {
             env_vars: HashMap::new(),
         });
 
+        // Bundler (Ruby)
+        managers.insert("bundler".to_string(), ManagerSettings {
+            enabled: true,
+            version: None,
+            binary_path: None,
+            install_dir: Some("vendor/bundle".to_string()),
+            registry: Some("https://rubygems.org".to_string()),
+            proxy: None,
+            auth: None,
+            options: HashMap::new(),
+            env_vars: HashMap::new(),
+        });
+
         // System package manager
         managers.insert("system".to_string(), ManagerSettings {
             enabled: true,
@@ -458,79 +578,115 @@ impl Config {
         registries
     }
 
-    /// Get configuration value by key path
+    /// Get configuration value by key path; credentials are masked
     pub fn get(&self, key: &str) -> Result<serde_json::Value> {
-        let parts: Vec<&str> = key.split('.').collect();
-        
-        match parts.as_slice() {
-            ["core", "parallel_jobs"] => Ok(serde_json::Value::Number(self.core.parallel_jobs.into())),
-            ["core", "timeout_seconds"] => Ok(serde_json::Value::Number(self.core.timeout_seconds.into())),
-            ["core", "offline_mode"] => Ok(serde_json::Value::Bool(self.core.offline_mode)),
-            ["core", "verify_checksums"] => Ok(serde_json::Value::Bool(self.core.verify_checksums)),
-            ["ui", "color"] => Ok(serde_json::Value::Bool(self.ui.color)),
-            ["ui", "progress_bars"] => Ok(serde_json::Value::Bool(self.ui.progress_bars)),
-            ["cache", "enabled"] => Ok(serde_json::Value::Bool(self.cache.enabled)),
-            ["cache", "max_size_mb"] => Ok(serde_json::Value::Number(self.cache.max_size_mb.into())),
-            ["telemetry", "enabled"] => Ok(serde_json::Value::Bool(self.telemetry.enabled)),
-            _ => Err(anyhow!("Unknown configuration key: {}", key)),
-        }
+        Ok(mask(key, self.value_at(key)?))
+    }
+
+    /// Unmasked value at a key path (`managers.npm.registry`, `security.blocked_packages[2]`)
+    pub fn value_at(&self, key: &str) -> Result<serde_json::Value> {
+        serde_json::to_value(self)?
+            .pointer(&key_pointer(key)?)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unknown configuration key: {}", key))
+    }
+
+    /// Every value by key path, credentials masked
+    pub fn list(&self) -> Result<std::collections::BTreeMap<String, serde_json::Value>> {
+        Ok(crate::config_layers::leaves(&serde_json::to_value(self)?)
+            .into_iter()
+            .map(|(key, value)| {
+                let value = mask(&key, value);
+                (key, value)
+            })
+            .collect())
     }
 
     /// Set configuration value by key path
+    ///
+    /// The text is parsed as the type already at that path (booleans, numbers,
+    /// comma-separated or JSON lists); new map entries and empty optional values
+    /// accept JSON. An empty value clears an optional setting.
     pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
-        let parts: Vec<&str> = key.split('.').collect();
-        
-        match parts.as_slice() {
-            ["core", "parallel_jobs"] => {
-                self.core.parallel_jobs = value.parse()
-                    .context("Invalid value for parallel_jobs")?;
-            }
-            ["core", "timeout_seconds"] => {
-                self.core.timeout_seconds = value.parse()
-                    .context("Invalid value for timeout_seconds")?;
-            }
-            ["core", "offline_mode"] => {
-                self.core.offline_mode = value.parse()
-                    .context("Invalid boolean value for offline_mode")?;
-            }
-            ["core", "verify_checksums"] => {
-                self.core.verify_checksums = value.parse()
-                    .context("Invalid boolean value for verify_checksums")?;
-            }
-            ["ui", "color"] => {
-                self.ui.color = value.parse()
-                    .context("Invalid boolean value for ui.color")?;
+        let pointer = key_pointer(key)?;
+        let tree = serde_json::to_value(&*self)?;
+        let current = tree.pointer(&pointer).cloned();
+        let candidates = parse_candidates(current.as_ref(), value)
+            .with_context(|| format!("Invalid value '{}' for {}", value, key))?;
+        let mut last_error = None;
+        for candidate in candidates {
+            let mut attempt = tree.clone();
+            place(&mut attempt, &pointer, candidate.clone(), key)?;
+            match self.accept(attempt, &pointer, &candidate, key) {
+                Ok(config) => {
+                    *self = config;
+                    return Ok(());
+                }
+                Err(e) => last_error = Some(e),
             }
-            ["ui", "progress_bars"] => {
-                self.ui.progress_bars = value.parse()
-                    .context("Invalid boolean value for ui.progress_bars")?;
-            }
-            ["ui", "editor"] => {
-                self.ui.editor = if value.is_empty() { None } else { Some(value.to_string()) };
-            }
-            ["cache", "enabled"] => {
-                self.cache.enabled = value.parse()
-                    .context("Invalid boolean value for cache.enabled")?;
-            }
-            ["cache", "max_size_mb"] => {
-                self.cache.max_size_mb = value.parse()
-                    .context("Invalid value for cache.max_size_mb")?;
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("Invalid value '{}' for {}", value, key)))
+    }
+
+    /// Remove a key path: map entries and list items are deleted, settings go back to their default
+    pub fn unset(&mut self, key: &str) -> Result<()> {
+        let pointer = key_pointer(key)?;
+        let mut tree = serde_json::to_value(&*self)?;
+        let (parent, last) = split_pointer(&pointer);
+        match tree.pointer_mut(parent) {
+            Some(serde_json::Value::Array(items)) => {
+                let index: usize = last.parse().ok().filter(|i| *i < items.len())
+                    .ok_or_else(|| anyhow!("Unknown configuration key: {}", key))?;
+                items.remove(index);
             }
-            ["telemetry", "enabled"] => {
-                self.telemetry.enabled = value.parse()
-                    .context("Invalid boolean value for telemetry.enabled")?;
+            Some(serde_json::Value::Object(map)) if map.contains_key(&unescape(last)) => {
+                map.remove(&unescape(last));
             }
             _ => return Err(anyhow!("Unknown configuration key: {}", key)),
         }
-        
+        *self = match serde_json::from_value::<Self>(tree) {
+            Ok(config) => config,
+            // A struct field can't be removed; restore its default instead
+            Err(_) => {
+                let default = serde_json::to_value(Self::default())?
+                    .pointer(&pointer)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("{} has no default and cannot be unset", key))?;
+                let mut tree = serde_json::to_value(&*self)?;
+                place(&mut tree, &pointer, default, key)?;
+                serde_json::from_value(tree).with_context(|| format!("Failed to unset {}", key))?
+            }
+        };
         Ok(())
     }
 
+    /// Deserialize a candidate tree, checking the value landed where it was put
+    fn accept(&self, tree: serde_json::Value, pointer: &str, candidate: &serde_json::Value, key: &str) -> Result<Self> {
+        let config: Self = serde_json::from_value(tree)
+            .map_err(|e| anyhow!("Invalid value for {}: {}", key, e))?;
+        // serde ignores unknown struct fields, so a value that didn't round-trip was never a key
+        let stored = serde_json::to_value(&config)?;
+        match stored.pointer(pointer) {
+            Some(value) if contains(value, candidate) => {}
+            Some(_) => return Err(anyhow!("Invalid value for {}", key)),
+            None => return Err(anyhow!("Unknown configuration key: {}", key)),
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
     /// Reset configuration to defaults
     pub fn reset(&mut self) {
         *self = Self::default();
     }
 
+    /// Disk usage of RCM's own cache directory
+    pub async fn cache_usage(&self) -> Result<CacheUsage> {
+        let path = self.cache_dir();
+        let size_bytes = calculate_directory_size(&path).await?;
+        Ok(CacheUsage { name: "rcm".to_string(), path, size_bytes })
+    }
+    
     /// Get cache directory
     pub fn cache_dir(&self) -> PathBuf {
         if let Some(ref dir) = self.cache.directory {
@@ -594,7 +750,187 @@ impl Config {
         if self.cache.max_size_mb == 0 && self.cache.enabled {
             return Err(anyhow!("cache.max_size_mb must be greater than 0 when cache is enabled"));
         }
+        if !(1..=22).contains(&self.cache.compression_level) {
+            return Err(anyhow!("cache.compression_level must be between 1 and 22"));
+        }
+
+        crate::secrets::SecretBackend::parse(&self.security.secret_backend)?;
+        crate::storage::StateBackend::parse(&self.core.state_backend)?;
+
+        for hook in &self.webhooks {
+            if hook.url.trim().is_empty() {
+                return Err(anyhow!("webhooks entries need a url"));
+            }
+            crate::webhooks::check_filters(&hook.events)?;
+        }
+
+        for pattern in &self.security.redact_patterns {
+            regex::Regex::new(pattern)
+                .with_context(|| format!("Invalid regex in security.redact_patterns: {}", pattern))?;
+        }
 
         Ok(())
     }
 }
+
+/// Split a comma-separated config value, dropping empty entries
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',')
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+/// JSON pointer for a key path: `a.b[2]` becomes `/a/b/2`
+pub fn key_pointer(key: &str) -> Result<String> {
+    let invalid = || anyhow!("Invalid configuration key: {}", key);
+    let mut pointer = String::new();
+    for part in key.split('.') {
+        let (name, mut indexes) = match part.find('[') {
+            Some(start) => (&part[..start], &part[start..]),
+            None => (part, ""),
+        };
+        if name.is_empty() {
+            return Err(invalid());
+        }
+        pointer.push('/');
+        pointer.push_str(&name.replace('~', "~0").replace('/', "~1"));
+        while !indexes.is_empty() {
+            let end = indexes.find(']').ok_or_else(invalid)?;
+            let index: usize = indexes[1..end].parse().map_err(|_| invalid())?;
+            pointer.push_str(&format!("/{}", index));
+            indexes = &indexes[end + 1..];
+            if !indexes.is_empty() && !indexes.starts_with('[') {
+                return Err(invalid());
+            }
+        }
+    }
+    Ok(pointer)
+}
+
+fn split_pointer(pointer: &str) -> (&str, &str) {
+    let at = pointer.rfind('/').unwrap_or(0);
+    (&pointer[..at], &pointer[at + 1..])
+}
+
+fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// Put a value at a pointer whose parent exists; an index one past the end appends
+fn place(tree: &mut serde_json::Value, pointer: &str, value: serde_json::Value, key: &str) -> Result<()> {
+    let (parent, last) = split_pointer(pointer);
+    match tree.pointer_mut(parent) {
+        Some(serde_json::Value::Object(map)) => {
+            map.insert(unescape(last), value);
+        }
+        Some(serde_json::Value::Array(items)) => {
+            let index: usize = last.parse().map_err(|_| anyhow!("Invalid configuration key: {}", key))?;
+            match index.cmp(&items.len()) {
+                std::cmp::Ordering::Less => items[index] = value,
+                std::cmp::Ordering::Equal => items.push(value),
+                std::cmp::Ordering::Greater => {
+                    return Err(anyhow!("{} is out of range ({} items)", key, items.len()));
+                }
+            }
+        }
+        _ => return Err(anyhow!("Unknown configuration key: {}", key)),
+    }
+    Ok(())
+}
+
+/// Whether `stored` holds everything in `given`; objects may gain defaulted fields
+fn contains(stored: &serde_json::Value, given: &serde_json::Value) -> bool {
+    match (stored, given) {
+        (serde_json::Value::Object(stored), serde_json::Value::Object(given)) => {
+            given.iter().all(|(key, value)| stored.get(key).is_some_and(|s| contains(s, value)))
+        }
+        _ => stored == given,
+    }
+}
+
+/// Typed readings of a CLI value, most specific first
+fn parse_candidates(current: Option<&serde_json::Value>, raw: &str) -> Result<Vec<serde_json::Value>> {
+    use serde_json::Value;
+    let json = || serde_json::from_str::<Value>(raw).ok();
+    Ok(match current {
+        Some(Value::Bool(_)) => vec![Value::Bool(raw.parse().context("expected true or false")?)],
+        Some(Value::Number(_)) => vec![json().filter(Value::is_number).ok_or_else(|| anyhow!("expected a number"))?],
+        Some(Value::String(_)) => vec![Value::String(raw.to_string())],
+        Some(Value::Array(_)) if raw.trim_start().starts_with('[') => {
+            vec![json().filter(Value::is_array).ok_or_else(|| anyhow!("expected a JSON list"))?]
+        }
+        Some(Value::Array(_)) => vec![serde_json::json!(split_list(raw))],
+        Some(Value::Object(_)) => vec![json().filter(Value::is_object).ok_or_else(|| anyhow!("expected a JSON object"))?],
+        // Optional settings and new entries
+        Some(Value::Null) | None if raw.is_empty() => vec![Value::Null, Value::String(String::new())],
+        Some(Value::Null) | None => json().into_iter().chain([Value::String(raw.to_string())]).collect(),
+    })
+}
+
+/// Whether a key path holds a credential
+pub fn is_sensitive(key: &str) -> bool {
+    let segments: Vec<&str> = key.split('.').collect();
+    sensitive_segments(&segments)
+}
+
+/// [`is_sensitive`] on path segments, which may themselves contain dots (`auth."registry.example.com"`)
+fn sensitive_segments<S: AsRef<str>>(segments: &[S]) -> bool {
+    let first = segments.first().map(AsRef::as_ref);
+    let last = segments.last().map(AsRef::as_ref);
+    (first == Some("auth") && segments.len() > 1) || matches!(last, Some("token" | "password" | "api_key" | "secret"))
+}
+
+/// Hide credential values, keeping `secret:` references and unset values visible
+pub fn mask(key: &str, value: serde_json::Value) -> serde_json::Value {
+    let mut segments: Vec<String> = key.split('.').filter(|s| !s.is_empty()).map(str::to_string).collect();
+    mask_at(&mut segments, value)
+}
+
+fn mask_at(path: &mut Vec<String>, value: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::Null => value,
+        Value::String(ref s) if s.starts_with("secret:") => value,
+        // Containers are masked leaf by leaf, matching each key as a whole segment
+        Value::Object(map) => Value::Object(map.into_iter().map(|(key, child)| {
+            path.push(key.clone());
+            let child = mask_at(path, child);
+            path.pop();
+            (key, child)
+        }).collect()),
+        Value::Array(items) => Value::Array(items.into_iter().enumerate().map(|(index, child)| {
+            path.push(index.to_string());
+            let child = mask_at(path, child);
+            path.pop();
+            child
+        }).collect()),
+        _ if sensitive_segments(path) => Value::String("********".to_string()),
+        _ => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_mask() {
+        let auth = json!({
+            "registry.example.com": { "type": "bearer", "token": "plain", "password": "secret:pw" },
+            "npm/scope~x": { "token": "plain" },
+        });
+        let masked = mask("auth", auth);
+        assert_eq!(masked["registry.example.com"]["token"], "********");
+        assert_eq!(masked["registry.example.com"]["type"], "********");
+        assert_eq!(masked["registry.example.com"]["password"], "secret:pw");
+        assert_eq!(masked["npm/scope~x"]["token"], "********");
+
+        let hooks = mask("webhooks", json!([{ "url": "https://x", "secret": "hmac" }]));
+        assert_eq!(hooks[0]["secret"], "********");
+        assert_eq!(hooks[0]["url"], "https://x");
+        assert_eq!(mask("core.parallel_jobs", json!(4)), json!(4));
+        assert_eq!(mask("dashboard.token", json!("abc")), json!("********"));
+    }
+}
@@ -0,0 +1,714 @@
+//! Container image dependencies for RCM
+//!
+//! Images are declared under `containers` in `.rcm/workspace.json` and pinned by
+//! digest, so every machine runs the same bytes no matter where a tag moves.
+//! Images can also be rendered as services into the workspace's docker-compose.yml.
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tabled::{Table, Tabled};
+use tokio::fs;
+use crate::members::{self, MANIFEST};
+use crate::util::{self, execute_command, execute_mutation};
+use crate::workspace::Workspace;
+
+/// Section of the workspace manifest holding the images
+pub const SECTION: &str = "containers";
+
+#[derive(Subcommand)]
+pub enum ContainerCommands {
+    /// Add images and pin them by digest, e.g. postgres:16
+    Add {
+        /// Image references
+        images: Vec<String>,
+        /// Name to record the image under (defaults to the last path segment)
+        #[arg(long)]
+        name: Option<String>,
+        /// Also add a docker-compose.yml service for the image
+        #[arg(long)]
+        service: bool,
+        /// Record the image without pulling it (pinned on the next pull)
+        #[arg(long)]
+        no_pull: bool,
+    },
+
+    /// Remove images from the manifest and docker-compose.yml
+    Remove {
+        /// Image names
+        names: Vec<String>,
+    },
+
+    /// Pull pinned images (all by default)
+    Pull {
+        /// Image names
+        names: Vec<String>,
+        /// Re-resolve tags and move the pins to their current digests
+        #[arg(long)]
+        update: bool,
+    },
+
+    /// List declared images
+    List {
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+
+    /// Check that every image is pinned and present locally
+    Verify,
+
+    /// Write the declared services into docker-compose.yml
+    Compose {
+        /// Compose file to update
+        #[arg(long, default_value = "docker-compose.yml")]
+        file: PathBuf,
+        /// Print the rendered services instead of writing them
+        #[arg(long)]
+        stdout: bool,
+    },
+}
+
+/// A parsed image reference: `[registry/]repository[:tag][@digest]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageRef {
+    pub repository: String,
+    pub tag: Option<String>,
+    pub digest: Option<String>,
+}
+
+impl ImageRef {
+    pub fn parse(reference: &str) -> Result<Self> {
+        let reference = reference.trim();
+        let (rest, digest) = match reference.split_once('@') {
+            Some((rest, digest)) => {
+                if !digest.starts_with("sha256:") || digest.len() != 71 {
+                    return Err(anyhow!("Invalid digest in image reference: {}", reference));
+                }
+                (rest, Some(digest.to_string()))
+            }
+            None => (reference, None),
+        };
+        // A ':' before the last '/' is a registry port, not a tag
+        let last_slash = rest.rfind('/').map(|i| i + 1).unwrap_or(0);
+        let (repository, tag) = match rest[last_slash..].rfind(':') {
+            Some(i) => (&rest[..last_slash + i], Some(rest[last_slash + i + 1..].to_string())),
+            None => (rest, None),
+        };
+        let valid = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || "._-/:".contains(c);
+        if repository.is_empty() || !repository.chars().all(valid) || tag.as_deref() == Some("") {
+            return Err(anyhow!("Invalid image reference: {}", reference));
+        }
+        Ok(Self { repository: repository.to_string(), tag, digest })
+    }
+
+    /// Default manifest and service name: the last path segment
+    pub fn name(&self) -> &str {
+        self.repository.rsplit('/').next().unwrap_or(&self.repository)
+    }
+
+    /// `repository:tag`, the reference that is pulled to resolve a digest
+    pub fn tagged(&self) -> String {
+        format!("{}:{}", self.repository, self.tag.as_deref().unwrap_or("latest"))
+    }
+
+    /// `repository:tag@digest` when pinned; the tag is kept for readers, the digest wins
+    pub fn pinned(&self, digest: &str) -> String {
+        match &self.tag {
+            Some(tag) => format!("{}:{}@{}", self.repository, tag, digest),
+            None => format!("{}@{}", self.repository, digest),
+        }
+    }
+}
+
+/// docker and podman spell Docker Hub images differently
+fn canonical_repository(repository: &str) -> &str {
+    let repository = repository.strip_prefix("docker.io/").unwrap_or(repository);
+    repository.strip_prefix("library/").unwrap_or(repository)
+}
+
+/// The digest for `repository` among an image's RepoDigests
+pub fn pick_repo_digest(repo_digests: &[String], repository: &str) -> Option<String> {
+    repo_digests.iter()
+        .filter_map(|d| d.split_once('@'))
+        .find(|(repo, _)| canonical_repository(repo) == canonical_repository(repository))
+        .map(|(_, digest)| digest.to_string())
+}
+
+/// Compose settings for an image's service
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceDef {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volumes: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub environment: BTreeMap<String, String>,
+}
+
+impl ServiceDef {
+    /// Named volumes the service mounts (as opposed to bind mounts)
+    pub fn named_volumes(&self) -> Vec<String> {
+        self.volumes.iter()
+            .filter_map(|v| v.split_once(':').map(|(source, _)| source))
+            .filter(|source| !source.starts_with(['.', '/', '~', '$']))
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// Development defaults for common images, matching the init template's docker-compose.yml
+pub fn service_preset(name: &str) -> ServiceDef {
+    let env = |pairs: &[(&str, &str)]| -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    };
+    match name {
+        "postgres" => ServiceDef {
+            ports: vec!["5432:5432".to_string()],
+            volumes: vec!["postgres_data:/var/lib/postgresql/data".to_string()],
+            environment: env(&[("POSTGRES_DB", "development"), ("POSTGRES_USER", "dev"), ("POSTGRES_PASSWORD", "password")]),
+        },
+        "redis" => ServiceDef {
+            ports: vec!["6379:6379".to_string()],
+            ..Default::default()
+        },
+        "mysql" | "mariadb" => ServiceDef {
+            ports: vec!["3306:3306".to_string()],
+            volumes: vec!["mysql_data:/var/lib/mysql".to_string()],
+            environment: env(&[
+                ("MYSQL_DATABASE", "development"), ("MYSQL_USER", "dev"),
+                ("MYSQL_PASSWORD", "password"), ("MYSQL_ROOT_PASSWORD", "rootpassword"),
+            ]),
+        },
+        "mongo" => ServiceDef {
+            ports: vec!["27017:27017".to_string()],
+            volumes: vec!["mongo_data:/data/db".to_string()],
+            ..Default::default()
+        },
+        _ => ServiceDef::default(),
+    }
+}
+
+/// An image entry under `containers` in the workspace manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContainerImage {
+    /// Reference as added, e.g. `postgres:16`
+    pub image: String,
+    /// Pinned digest (`sha256:...`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    /// Compose service settings, when the image runs as a service
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service: Option<ServiceDef>,
+}
+
+impl ContainerImage {
+    /// Reference to pull and run: pinned when a digest is known
+    pub fn reference(&self) -> Result<String> {
+        let image = ImageRef::parse(&self.image)?;
+        Ok(match &self.digest {
+            Some(digest) => image.pinned(digest),
+            None => image.tagged(),
+        })
+    }
+}
+
+/// Images declared in the workspace manifest
+#[derive(Debug, Clone, Default)]
+pub struct ContainerManifest {
+    pub images: BTreeMap<String, ContainerImage>,
+}
+
+impl ContainerManifest {
+    /// Load the declared images, returning none when the manifest has no `containers`
+    pub async fn load(workspace_root: &Path) -> Result<Self> {
+        Ok(Self { images: members::read_section(workspace_root, SECTION)? })
+    }
+
+    pub async fn save(&self, workspace_root: &Path) -> Result<()> {
+        members::write_section(workspace_root, SECTION, &self.images).await
+    }
+
+    /// Images that run as compose services
+    pub fn services(&self) -> impl Iterator<Item = (&String, &ContainerImage, &ServiceDef)> {
+        self.images.iter().filter_map(|(name, image)| image.service.as_ref().map(|s| (name, image, s)))
+    }
+}
+
+/// A compose service as `render_service` writes it
+#[derive(Serialize)]
+struct ComposeService {
+    image: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    ports: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    environment: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    volumes: Vec<String>,
+    restart: String,
+}
+
+/// Render one service as it appears under `services:`
+pub fn render_service(name: &str, reference: &str, service: &ServiceDef) -> Result<String> {
+    let compose = ComposeService {
+        image: reference.to_string(),
+        ports: service.ports.clone(),
+        environment: service.environment.clone(),
+        volumes: service.volumes.clone(),
+        restart: "unless-stopped".to_string(),
+    };
+    let yaml = serde_yaml::to_string(&BTreeMap::from([(name, compose)]))
+        .with_context(|| format!("Failed to render service {}", name))?;
+    // Nested one level under `services:`
+    Ok(yaml.lines().map(|line| format!("  {}\n", line)).collect())
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Whether a line is a mapping key at `depth` spaces, e.g. `  postgres:`
+fn is_key(line: &str, depth: usize, key: Option<&str>) -> bool {
+    let trimmed = line.trim_start();
+    if indent(line) != depth || trimmed.starts_with('#') {
+        return false;
+    }
+    match key {
+        Some(key) => trimmed.strip_prefix(key).is_some_and(|rest| rest.trim_end() == ":" || rest.starts_with(": ")),
+        None => trimmed.contains(':'),
+    }
+}
+
+/// Line range of a top-level section's body, creating the section when missing
+fn section(lines: &mut Vec<String>, key: &str) -> (usize, usize) {
+    let start = match lines.iter().position(|l| is_key(l, 0, Some(key))) {
+        Some(start) => start,
+        None => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("{}:", key));
+            lines.len() - 1
+        }
+    };
+    let mut end = lines[start + 1..].iter()
+        .position(|l| is_key(l, 0, None))
+        .map(|i| start + 1 + i)
+        .unwrap_or(lines.len());
+    // Blank lines before the next section stay with it
+    while end > start + 1 && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+    (start + 1, end)
+}
+
+/// Replace or insert service blocks in a compose file and declare their named volumes
+///
+/// Only the named services are touched; everything else, including comments
+/// such as the init template's commented-out examples, is preserved. That is
+/// why blocks are spliced in as lines rather than round-tripped through serde_yaml.
+pub fn update_compose(existing: &str, services: &[(String, String)], volumes: &[String]) -> String {
+    let mut lines: Vec<String> = existing.lines().map(str::to_string).collect();
+
+    for (name, block) in services {
+        let (body, end) = section(&mut lines, "services");
+        let block: Vec<String> = block.lines().map(str::to_string).collect();
+        match lines[body..end].iter().position(|l| is_key(l, 2, Some(name))) {
+            Some(offset) => {
+                let start = body + offset;
+                let mut stop = lines[start + 1..end].iter()
+                    .position(|l| !l.trim().is_empty() && indent(l) <= 2)
+                    .map(|i| start + 1 + i)
+                    .unwrap_or(end);
+                while stop > start + 1 && lines[stop - 1].trim().is_empty() {
+                    stop -= 1;
+                }
+                lines.splice(start..stop, block);
+            }
+            None => {
+                lines.splice(end..end, block);
+            }
+        }
+    }
+
+    for volume in volumes {
+        let (body, end) = section(&mut lines, "volumes");
+        if !lines[body..end].iter().any(|l| is_key(l, 2, Some(volume))) {
+            lines.insert(end, format!("  {}:", volume));
+        }
+    }
+
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
+/// Remove service blocks from a compose file
+pub fn remove_services(existing: &str, names: &[String]) -> String {
+    let mut lines: Vec<String> = existing.lines().map(str::to_string).collect();
+    if !lines.iter().any(|l| is_key(l, 0, Some("services"))) {
+        return existing.to_string();
+    }
+    for name in names {
+        let (body, end) = section(&mut lines, "services");
+        if let Some(offset) = lines[body..end].iter().position(|l| is_key(l, 2, Some(name))) {
+            let start = body + offset;
+            let stop = lines[start + 1..end].iter()
+                .position(|l| !l.trim().is_empty() && indent(l) <= 2)
+                .map(|i| start + 1 + i)
+                .unwrap_or(end);
+            lines.drain(start..stop);
+        }
+    }
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
+#[derive(Tabled, Serialize)]
+struct ImageRow {
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Image")]
+    image: String,
+    #[tabled(rename = "Digest")]
+    digest: String,
+    #[tabled(rename = "Service")]
+    service: String,
+}
+
+/// Pulls and inspects images with docker or podman
+pub struct ContainerManager {
+    workspace_root: PathBuf,
+}
+
+impl ContainerManager {
+    pub fn new(workspace_root: &Path) -> Self {
+        Self {
+            workspace_root: workspace_root.to_path_buf(),
+        }
+    }
+
+    /// docker, or podman when docker is not installed
+    pub async fn engine(&self) -> Result<&'static str> {
+        for engine in ["docker", "podman"] {
+            if util::command_exists(engine).await {
+                return Ok(engine);
+            }
+        }
+        Err(anyhow!("No container engine found. Install Docker from https://docs.docker.com/get-docker/ or Podman"))
+    }
+
+    pub async fn version(&self) -> Option<String> {
+        let engine = self.engine().await.ok()?;
+        let result = execute_command(Command::new(engine).args(["version", "--format", "{{.Client.Version}}"])).await.ok()?;
+        Some(result.stdout.trim().to_string()).filter(|v| !v.is_empty())
+    }
+
+    /// Pull an image and return the digest it resolved to (unknown in dry-run mode)
+    pub async fn pull(&self, image: &ImageRef) -> Result<Option<String>> {
+        let engine = self.engine().await?;
+        let reference = match &image.digest {
+            Some(digest) => image.pinned(digest),
+            None => image.tagged(),
+        };
+        execute_mutation(Command::new(engine).args(["pull", reference.as_str()])).await
+            .with_context(|| format!("Failed to pull {}", reference))?;
+        if image.digest.is_some() || util::is_dry_run() {
+            return Ok(image.digest.clone());
+        }
+
+        let result = execute_command(Command::new(engine).args(["image", "inspect", "--format", "{{json .RepoDigests}}", reference.as_str()])).await
+            .with_context(|| format!("Failed to inspect {}", reference))?;
+        let repo_digests: Vec<String> = serde_json::from_str(result.stdout.trim())
+            .context("Unexpected image inspect output")?;
+        pick_repo_digest(&repo_digests, &image.repository)
+            .map(Some)
+            .ok_or_else(|| anyhow!("{} has no registry digest; was it built locally?", reference))
+    }
+
+    /// Whether the pinned image is in the local image store
+    pub async fn is_present(&self, reference: &str) -> bool {
+        match self.engine().await {
+            Ok(engine) => execute_command(Command::new(engine).args(["image", "inspect", "--format", "{{.Id}}", reference])).await.is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// Problems per image: unpinned, or pinned but not pulled
+    pub async fn verify(&self, manifest: &ContainerManifest) -> Result<Vec<(String, String)>> {
+        let mut problems = Vec::new();
+        for (name, image) in &manifest.images {
+            let reference = image.reference()?;
+            if image.digest.is_none() {
+                problems.push((name.clone(), format!("{} is not pinned; run 'rcm container pull'", image.image)));
+            } else if !self.is_present(&reference).await {
+                problems.push((name.clone(), format!("{} has not been pulled", reference)));
+            }
+        }
+        Ok(problems)
+    }
+
+    /// Pull the pinned images missing locally, pinning any that have no digest yet
+    pub async fn pull_missing(&self) -> Result<()> {
+        let mut manifest = ContainerManifest::load(&self.workspace_root).await?;
+        let mut changed = false;
+        for image in manifest.images.values_mut() {
+            if image.digest.is_some() && self.is_present(&image.reference()?).await {
+                continue;
+            }
+            let mut parsed = ImageRef::parse(&image.image)?;
+            parsed.digest = image.digest.clone();
+            if let Some(digest) = self.pull(&parsed).await? {
+                if image.digest.as_ref() != Some(&digest) {
+                    image.digest = Some(digest);
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            manifest.save(&self.workspace_root).await?;
+        }
+        Ok(())
+    }
+
+    /// Write the manifest's services into a compose file, creating it if needed
+    pub async fn write_compose(&self, manifest: &ContainerManifest, file: &Path) -> Result<()> {
+        let path = self.workspace_root.join(file);
+        let existing = fs::read_to_string(&path).await.unwrap_or_default();
+        let mut services = Vec::new();
+        let mut volumes = Vec::new();
+        for (name, image, service) in manifest.services() {
+            services.push((name.clone(), render_service(name, &image.reference()?, service)?));
+            volumes.extend(service.named_volumes());
+        }
+        volumes.sort();
+        volumes.dedup();
+        util::write_file(&path, update_compose(&existing, &services, &volumes)).await
+    }
+}
+
+/// Handle `rcm container` commands
+pub async fn handle_command(workspace: &Workspace, cmd: ContainerCommands) -> Result<()> {
+    let containers = ContainerManager::new(workspace.root());
+    let mut manifest = ContainerManifest::load(workspace.root()).await?;
+    let compose_file = Path::new("docker-compose.yml");
+
+    match cmd {
+        ContainerCommands::Add { images, name, service, no_pull } => {
+            if images.is_empty() {
+                return Err(anyhow!("No images given"));
+            }
+            if name.is_some() && images.len() > 1 {
+                return Err(anyhow!("--name can only be used with a single image"));
+            }
+            for reference in &images {
+                let mut image = ImageRef::parse(reference)?;
+                let name = name.clone().unwrap_or_else(|| image.name().to_string());
+                println!("{}", style(format!("🐳 Adding {} as '{}'...", reference, name)).cyan().bold());
+
+                if !no_pull {
+                    image.digest = containers.pull(&image).await?;
+                }
+                let service_def = if service {
+                    manifest.images.get(&name).and_then(|i| i.service.clone()).or_else(|| Some(service_preset(image.name())))
+                } else {
+                    manifest.images.get(&name).and_then(|i| i.service.clone())
+                };
+                let entry = ContainerImage {
+                    image: match &image.tag {
+                        Some(tag) => format!("{}:{}", image.repository, tag),
+                        None => image.repository.clone(),
+                    },
+                    digest: image.digest.clone(),
+                    service: service_def,
+                };
+                match &entry.digest {
+                    Some(digest) => println!("  📌 {}", style(digest).dim()),
+                    None => println!("  {} Not pinned yet; run 'rcm container pull'", style("⚠").yellow()),
+                }
+                manifest.images.insert(name, entry);
+            }
+            manifest.save(workspace.root()).await?;
+            if manifest.services().next().is_some() {
+                containers.write_compose(&manifest, compose_file).await?;
+                println!("{}", style("📄 Updated docker-compose.yml").green());
+            }
+            println!("{}", style(format!("✅ Images recorded in {}", MANIFEST)).green().bold());
+            Ok(())
+        }
+
+        ContainerCommands::Remove { names } => {
+            for name in &names {
+                if manifest.images.remove(name).is_none() {
+                    return Err(anyhow!("Image '{}' is not in {}", name, MANIFEST));
+                }
+            }
+            manifest.save(workspace.root()).await?;
+            let path = workspace.root().join(compose_file);
+            if let Ok(existing) = fs::read_to_string(&path).await {
+                util::write_file(&path, remove_services(&existing, &names)).await?;
+            }
+            println!("{}", style(format!("✅ Removed {}", names.join(", "))).green().bold());
+            Ok(())
+        }
+
+        ContainerCommands::Pull { names, update } => {
+            if manifest.images.is_empty() {
+                println!("No images declared. Add one with 'rcm container add <image>'.");
+                return Ok(());
+            }
+            for name in &names {
+                if !manifest.images.contains_key(name) {
+                    return Err(anyhow!("Image '{}' is not in {}", name, MANIFEST));
+                }
+            }
+            let mut moved = false;
+            for (name, image) in manifest.images.iter_mut().filter(|(n, _)| names.is_empty() || names.contains(n)) {
+                let mut parsed = ImageRef::parse(&image.image)?;
+                if !update {
+                    parsed.digest = image.digest.clone();
+                }
+                println!("{}", style(format!("🐳 Pulling {}...", name)).cyan());
+                let Some(digest) = containers.pull(&parsed).await? else {
+                    continue;
+                };
+                if image.digest.as_ref() != Some(&digest) {
+                    if let Some(old) = &image.digest {
+                        println!("  📌 {} → {}", style(old).dim(), style(&digest).green());
+                    }
+                    image.digest = Some(digest);
+                    moved = true;
+                }
+            }
+            if moved {
+                manifest.save(workspace.root()).await?;
+                if manifest.services().next().is_some() && workspace.root().join(compose_file).exists() {
+                    containers.write_compose(&manifest, compose_file).await?;
+                }
+            }
+            println!("{}", style("✅ Images up to date").green().bold());
+            Ok(())
+        }
+
+        ContainerCommands::List { format } => {
+            let rows: Vec<ImageRow> = manifest.images.iter().map(|(name, image)| ImageRow {
+                name: name.clone(),
+                image: image.image.clone(),
+                digest: image.digest.clone().unwrap_or_else(|| "-".to_string()),
+                service: if image.service.is_some() { "yes" } else { "no" }.to_string(),
+            }).collect();
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&rows)?),
+                "table" => {
+                    if rows.is_empty() {
+                        println!("No images declared.");
+                    } else {
+                        println!("{}", Table::new(&rows));
+                    }
+                }
+                _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+            }
+            Ok(())
+        }
+
+        ContainerCommands::Verify => {
+            let problems = containers.verify(&manifest).await?;
+            for (name, problem) in &problems {
+                println!("  {} {}: {}", style("✗").red(), style(name).bold(), problem);
+            }
+            if !problems.is_empty() {
+                return Err(anyhow!("{} of {} images failed verification", problems.len(), manifest.images.len()));
+            }
+            println!("✅ {} images pinned and present", manifest.images.len());
+            Ok(())
+        }
+
+        ContainerCommands::Compose { file, stdout } => {
+            if manifest.services().next().is_none() {
+                return Err(anyhow!("No images are marked as services. Use 'rcm container add <image> --service'"));
+            }
+            if stdout {
+                for (name, image, service) in manifest.services() {
+                    print!("{}", render_service(name, &image.reference()?, service)?);
+                }
+                return Ok(());
+            }
+            containers.write_compose(&manifest, &file).await?;
+            println!("{}", style(format!("📄 Updated {}", file.display())).green());
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn test_parse_image_ref() {
+        let image = ImageRef::parse("postgres:16").unwrap();
+        assert_eq!((image.repository.as_str(), image.tag.as_deref(), image.name()), ("postgres", Some("16"), "postgres"));
+        assert_eq!(image.pinned(DIGEST), format!("postgres:16@{}", DIGEST));
+
+        let image = ImageRef::parse("localhost:5000/acme/api").unwrap();
+        assert_eq!((image.repository.as_str(), image.tag, image.name()), ("localhost:5000/acme/api", None, "api"));
+        assert_eq!(image.tagged(), "localhost:5000/acme/api:latest");
+
+        let image = ImageRef::parse(&format!("ghcr.io/acme/api:1.2@{}", DIGEST)).unwrap();
+        assert_eq!(image.digest.as_deref(), Some(DIGEST));
+        assert!(ImageRef::parse("Postgres:16").is_err());
+        assert!(ImageRef::parse("postgres@sha256:abc").is_err());
+
+        let digests = vec![format!("docker.io/library/postgres@{}", DIGEST)];
+        assert_eq!(pick_repo_digest(&digests, "postgres").as_deref(), Some(DIGEST));
+        assert_eq!(pick_repo_digest(&digests, "acme/postgres"), None);
+    }
+
+    #[test]
+    fn test_update_compose() {
+        let existing = "\
+version: '3.8'
+
+services:
+  # postgres:
+  #   image: postgres:15
+
+  app:
+    build: .
+
+volumes:
+  postgres_data:
+";
+        let service = service_preset("redis");
+        let block = render_service("redis", "redis:7", &service).unwrap();
+        let updated = update_compose(existing, &[("redis".to_string(), block)], &["redis_data".to_string()]);
+        assert!(updated.starts_with("version: '3.8'\n\nservices:\n  # postgres:\n  #   image: postgres:15\n\n  app:\n    build: .\n  redis:\n"));
+        assert!(updated.ends_with("\nvolumes:\n  postgres_data:\n  redis_data:\n"));
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&updated).unwrap();
+        assert_eq!(parsed["services"]["redis"]["image"].as_str(), Some("redis:7"));
+        assert_eq!(parsed["services"]["redis"]["ports"][0].as_str(), Some("6379:6379"));
+        assert_eq!(parsed["services"]["redis"]["restart"].as_str(), Some("unless-stopped"));
+        assert!(parsed["services"]["app"]["build"].is_string());
+
+        // Re-rendering replaces the block in place
+        let block = render_service("redis", &format!("redis:7@{}", DIGEST), &service).unwrap();
+        let again = update_compose(&updated, &[("redis".to_string(), block)], &[]);
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&again).unwrap();
+        assert_eq!(parsed["services"]["redis"]["image"].as_str().map(str::to_string), Some(format!("redis:7@{}", DIGEST)));
+        assert_eq!(again.matches("  redis:").count(), 1);
+        assert!(again.contains("restart: unless-stopped\n\nvolumes:"));
+
+        let removed = remove_services(&again, &["redis".to_string()]);
+        assert!(!removed.contains("redis:7") && removed.contains("  app:\n    build: .\n\nvolumes:"));
+
+        let fresh = update_compose("", &[("app".to_string(), "  app:\n    image: \"api\"\n".to_string())], &[]);
+        assert_eq!(fresh, "services:\n  app:\n    image: \"api\"\n");
+    }
+}
@@ -0,0 +1,320 @@
+//! Container-isolated execution for RCM
+//!
+//! `rcm ensure|build|test --in-container` renders a Dockerfile for the
+//! workspace's toolchains into `.rcm/container/`, builds it once per content
+//! hash, and re-runs the same rcm command inside an ephemeral container with
+//! the workspace bind-mounted and the package caches kept on the host, so
+//! nothing but docker or podman has to be installed locally.
+//!
+//! The image builds its own rcm of the same version for Linux, since the host
+//! binary may be for another OS or libc.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::config::Config;
+use crate::container::ContainerManager;
+use crate::engines;
+use crate::go;
+use crate::npm;
+use crate::util;
+use crate::workspace::Workspace;
+
+/// Generated build context relative to the workspace root
+pub const CONTAINER_DIR: &str = ".rcm/container";
+
+/// Set inside the container so the command doesn't try to nest another one
+pub const IN_CONTAINER_ENV: &str = "RCM_IN_CONTAINER";
+
+/// Where the workspace is mounted
+const WORKDIR: &str = "/workspace";
+
+/// Home of the unprivileged user the command runs as
+const HOME: &str = "/home/rcm";
+
+/// Where the user config is mounted inside the container
+const CONFIG_MOUNT: &str = "/home/rcm/.config/rcm/config.json";
+
+/// Node majors considered when picking an image for `engines.node`, newest first
+const NODE_MAJORS: &[u64] = &[22, 20, 18];
+
+/// Host cache directories (under `<cache>/container/`) and where they are mounted
+const CACHE_MOUNTS: &[(&str, &str)] = &[
+    ("cargo-registry", "/home/rcm/.cargo/registry"),
+    ("cargo-git", "/home/rcm/.cargo/git"),
+    ("npm", "/home/rcm/.npm"),
+    ("composer", "/home/rcm/.cache/composer"),
+    ("pip", "/home/rcm/.cache/pip"),
+    ("go-mod", "/home/rcm/go/pkg/mod"),
+];
+
+/// Toolchains to put in the build image
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImageSpec {
+    /// rustup toolchain (`rust-version`, else `stable`)
+    pub rust: Option<String>,
+    /// Node.js major
+    pub node: Option<u64>,
+    /// PHP with composer and these extensions
+    pub php: Option<Vec<String>>,
+    pub python: bool,
+    /// Go release (`go` directive in go.mod)
+    pub go: Option<String>,
+}
+
+impl ImageSpec {
+    /// Toolchains for the workspace's enabled managers and declared versions
+    pub async fn detect(workspace: &Workspace) -> Result<Self> {
+        let root = workspace.root();
+        let mut spec = Self::default();
+        for manager in workspace.enabled_managers() {
+            match manager.as_str() {
+                "cargo" => {
+                    spec.rust = Some(engines::rust_version(root).await?.unwrap_or_else(|| "stable".to_string()));
+                }
+                "npm" => {
+                    let range = npm::node_engine(root).await?;
+                    let major = match range {
+                        Some(range) => NODE_MAJORS.iter().copied()
+                            .find(|major| npm::satisfies_engine(&range, &semver::Version::new(*major, 0, 0)).unwrap_or(false))
+                            .ok_or_else(|| anyhow!("No Node.js image matches engines.node {}", range))?,
+                        None => NODE_MAJORS[1],
+                    };
+                    spec.node = Some(major);
+                }
+                "composer" => spec.php = Some(php_extensions(root).await),
+                "pip" => spec.python = true,
+                "go" => {
+                    let go_mod = tokio::fs::read_to_string(root.join("go.mod")).await.unwrap_or_default();
+                    spec.go = Some(go::parse_go_mod(&go_mod).go.unwrap_or_else(|| "1.22".to_string()));
+                }
+                _ => {}
+            }
+        }
+        Ok(spec)
+    }
+}
+
+/// `ext-*` requirements from composer.json, as Debian package suffixes
+async fn php_extensions(root: &Path) -> Vec<String> {
+    let composer: serde_json::Value = tokio::fs::read_to_string(root.join("composer.json")).await.ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default();
+    let mut extensions: Vec<String> = composer.get("require").and_then(|r| r.as_object())
+        .map(|r| r.keys().filter_map(|k| k.strip_prefix("ext-")).map(str::to_lowercase).collect())
+        .unwrap_or_default();
+    // Built into php-cli on Debian
+    extensions.retain(|e| !matches!(e.as_str(), "json" | "ctype" | "tokenizer" | "openssl" | "pcre" | "filter" | "hash" | "iconv"));
+    extensions.sort();
+    extensions
+}
+
+/// The Dockerfile for a spec; toolchains come from their official images where possible
+pub fn render_dockerfile(spec: &ImageSpec) -> String {
+    let mut stages = String::new();
+    let mut body = String::new();
+    let mut apt = vec!["build-essential", "ca-certificates", "curl", "git", "pkg-config", "libssl-dev", "unzip"];
+
+    if let Some(major) = spec.node {
+        stages.push_str(&format!("FROM node:{}-bookworm-slim AS node\n", major));
+        body.push_str("COPY --from=node /usr/local/bin/node /usr/local/bin/node\n");
+        body.push_str("COPY --from=node /usr/local/lib/node_modules /usr/local/lib/node_modules\n");
+        body.push_str("RUN ln -s ../lib/node_modules/npm/bin/npm-cli.js /usr/local/bin/npm \\\n");
+        body.push_str(" && ln -s ../lib/node_modules/npm/bin/npx-cli.js /usr/local/bin/npx \\\n");
+        body.push_str(" && ln -s ../lib/node_modules/corepack/dist/corepack.js /usr/local/bin/corepack\n");
+    }
+    if let Some(version) = &spec.go {
+        stages.push_str(&format!("FROM golang:{}-bookworm AS go\n", version));
+        body.push_str("COPY --from=go /usr/local/go /usr/local/go\n");
+    }
+    let mut php_packages = Vec::new();
+    if let Some(extensions) = &spec.php {
+        stages.push_str("FROM composer:2 AS composer\n");
+        body.push_str("COPY --from=composer /usr/bin/composer /usr/local/bin/composer\n");
+        php_packages.push("php-cli".to_string());
+        php_packages.extend(extensions.iter().map(|e| format!("php-{}", e)));
+    }
+    if spec.python {
+        apt.extend(["python3", "python3-pip", "python3-venv"]);
+    }
+
+    // rcm itself, built for the image rather than copied from the host
+    stages.push_str("FROM rust:1-bookworm AS rcm\n");
+    stages.push_str(&format!(
+        "RUN cargo install --locked --root /opt/rcm --git {} --tag v{} rcm\n",
+        env!("CARGO_PKG_REPOSITORY"),
+        env!("CARGO_PKG_VERSION")
+    ));
+    body.push_str("COPY --from=rcm /opt/rcm/bin/rcm /usr/local/bin/rcm\n");
+
+    let mut dockerfile = String::from("# Generated by rcm for --in-container runs; regenerated on every run\n");
+    dockerfile.push_str(&stages);
+    dockerfile.push_str("FROM debian:bookworm-slim\n");
+    dockerfile.push_str("ENV DEBIAN_FRONTEND=noninteractive\n");
+    let packages: Vec<String> = apt.iter().map(|p| p.to_string()).chain(php_packages).collect();
+    dockerfile.push_str(&format!(
+        "RUN apt-get update && apt-get install -y --no-install-recommends {} && rm -rf /var/lib/apt/lists/*\n",
+        packages.join(" ")
+    ));
+    if let Some(toolchain) = &spec.rust {
+        dockerfile.push_str("ENV RUSTUP_HOME=/usr/local/rustup PATH=/usr/local/cargo/bin:$PATH\n");
+        dockerfile.push_str(&format!(
+            "RUN curl -sSf https://sh.rustup.rs | CARGO_HOME=/usr/local/cargo sh -s -- -y --no-modify-path --profile minimal --default-toolchain {} \\\n && chmod -R a+rX /usr/local/rustup /usr/local/cargo\n",
+            toolchain
+        ));
+    }
+    dockerfile.push_str(&body);
+    if spec.go.is_some() {
+        dockerfile.push_str("ENV PATH=/usr/local/go/bin:$PATH\n");
+    }
+    dockerfile.push_str(&format!("RUN mkdir -p {home} && chmod 1777 {home}\n", home = HOME));
+    dockerfile.push_str(&format!("ENV HOME={} CARGO_HOME={}/.cargo\n", HOME, HOME));
+    dockerfile.push_str(&format!("WORKDIR {}\n", WORKDIR));
+    dockerfile
+}
+
+/// Image tag for a Dockerfile, so an unchanged one is never rebuilt
+pub fn image_tag(dockerfile: &str) -> String {
+    let digest = format!("{:x}", Sha256::digest(dockerfile.as_bytes()));
+    format!("rcm-build:{}", &digest[..12])
+}
+
+/// The command line to repeat inside the container
+///
+/// Drops `--in-container` and the host paths given with `--workspace`/`--config`.
+pub fn inner_args(args: &[OsString]) -> Vec<OsString> {
+    let mut inner = Vec::new();
+    let mut skip_value = false;
+    for arg in args {
+        if std::mem::take(&mut skip_value) {
+            continue;
+        }
+        let text = arg.to_string_lossy();
+        match text.as_ref() {
+            "--in-container" => {}
+            "-w" | "--workspace" | "-c" | "--config" => skip_value = true,
+            t if t.starts_with("--workspace=") || t.starts_with("--config=") => {}
+            _ => inner.push(arg.clone()),
+        }
+    }
+    inner
+}
+
+/// Whether this process is already the containerized run
+pub fn inside() -> bool {
+    std::env::var_os(IN_CONTAINER_ENV).is_some()
+}
+
+/// Build the image if needed and re-run this rcm invocation inside it
+///
+/// `config_path` is the host config given with `--config`; it is mounted
+/// read-only and passed to the inner rcm in place of the host path.
+pub async fn run(workspace: &Workspace, config: &Config, config_path: Option<&str>) -> Result<()> {
+    let root = workspace.root();
+    let engine = ContainerManager::new(root).engine().await?;
+
+    let spec = ImageSpec::detect(workspace).await?;
+    let dockerfile = render_dockerfile(&spec);
+    let context_dir = root.join(CONTAINER_DIR);
+    tokio::fs::create_dir_all(&context_dir).await
+        .with_context(|| format!("Failed to create {}", context_dir.display()))?;
+    util::write_file(&context_dir.join("Dockerfile"), &dockerfile).await?;
+
+    let tag = image_tag(&dockerfile);
+    if !ContainerManager::new(root).is_present(&tag).await {
+        println!("{}", style(format!("🐳 Building {} from {}/Dockerfile", tag, CONTAINER_DIR)).blue());
+        let mut build = Command::new(engine);
+        build.args(["build", "-t", &tag]).arg(&context_dir);
+        util::execute_streaming(build).await.context("Failed to build the container image")?;
+    }
+
+    let mut cmd = Command::new(engine);
+    cmd.args(["run", "--rm", "--init"]);
+    if std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        cmd.arg("-it");
+    }
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    #[cfg(unix)]
+    {
+        // Files created in the workspace stay owned by whoever owns it
+        use std::os::unix::fs::MetadataExt;
+        let owner = std::fs::metadata(&root).with_context(|| format!("Failed to stat {}", root.display()))?;
+        cmd.args(["--user", &format!("{}:{}", owner.uid(), owner.gid())]);
+    }
+    cmd.arg("-v").arg(mount(&root, WORKDIR, false));
+    let caches = cache_root(config);
+    for (name, target) in CACHE_MOUNTS {
+        let host = caches.join(name);
+        tokio::fs::create_dir_all(&host).await
+            .with_context(|| format!("Failed to create {}", host.display()))?;
+        cmd.arg("-v").arg(mount(&host, target, false));
+    }
+    let config_file = match config_path {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            if !path.is_file() {
+                return Err(anyhow!("Config file {} not found", path.display()));
+            }
+            Some(path.canonicalize().unwrap_or(path))
+        }
+        None => Some(Config::default_config_path()?).filter(|path| path.exists()),
+    };
+    if let Some(config_file) = &config_file {
+        cmd.arg("-v").arg(mount(config_file, CONFIG_MOUNT, true));
+    }
+    cmd.args(["-e", &format!("{}=1", IN_CONTAINER_ENV)]);
+    cmd.arg(&tag).arg("rcm");
+    if config_file.is_some() {
+        cmd.args(["--config", CONFIG_MOUNT]);
+    }
+    let args: Vec<OsString> = std::env::args_os().skip(1).collect();
+    cmd.args(inner_args(&args));
+
+    println!("{}", style(format!("🐳 Running in {} ({})", tag, engine)).blue());
+    util::execute_streaming(cmd).await
+}
+
+fn mount(host: &Path, target: &str, read_only: bool) -> OsString {
+    let mut spec = host.as_os_str().to_os_string();
+    spec.push(format!(":{}{}", target, if read_only { ":ro" } else { "" }));
+    spec
+}
+
+/// Cache directory for the host side of `CACHE_MOUNTS`
+pub fn cache_root(config: &Config) -> PathBuf {
+    config.cache_dir().join("container")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_dockerfile() {
+        let spec = ImageSpec {
+            rust: Some("1.74".to_string()),
+            node: Some(20),
+            php: Some(vec!["intl".to_string()]),
+            ..Default::default()
+        };
+        let dockerfile = render_dockerfile(&spec);
+        assert!(dockerfile.contains("FROM node:20-bookworm-slim AS node"));
+        assert!(dockerfile.contains("--default-toolchain 1.74"));
+        assert!(dockerfile.contains("php-cli php-intl"));
+        assert!(!dockerfile.contains("golang"));
+        assert!(dockerfile.contains(&format!("--tag v{} rcm", env!("CARGO_PKG_VERSION"))));
+        assert!(dockerfile.contains("COPY --from=rcm /opt/rcm/bin/rcm /usr/local/bin/rcm"));
+        assert_eq!(image_tag(&dockerfile), image_tag(&render_dockerfile(&spec)));
+    }
+
+    #[test]
+    fn test_inner_args_drop_host_paths() {
+        let args: Vec<OsString> = ["-w", "/home/me/app", "build", "--in-container", "--config=/x.json", "--profile", "ci"]
+            .iter().map(OsString::from).collect();
+        let inner: Vec<String> = inner_args(&args).iter().map(|a| a.to_string_lossy().into_owned()).collect();
+        assert_eq!(inner, vec!["build", "--profile", "ci"]);
+    }
+}
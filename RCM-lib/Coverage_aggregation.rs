@@ -0,0 +1,314 @@
+//! Coverage aggregation across languages
+//!
+//! Collects line coverage from cargo llvm-cov, jest/vitest (istanbul) and
+//! phpunit (xdebug/pcov clover), normalizes everything to lcov, merges it and
+//! writes a combined `lcov.info` and HTML summary under `.rcm/coverage/`.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tabled::{Table, Tabled};
+use tokio::fs;
+use crate::commands::test::TestRunner;
+use crate::isolation;
+use crate::util;
+use crate::workspace::Workspace;
+
+/// Output directory relative to the workspace root
+pub const COVERAGE_DIR: &str = ".rcm/coverage";
+
+/// Hit counts per line of one source file
+pub type LineHits = BTreeMap<u32, u64>;
+
+/// Merged coverage keyed by workspace-relative path
+#[derive(Debug, Clone, Default)]
+pub struct Coverage {
+    pub files: BTreeMap<String, LineHits>,
+}
+
+#[derive(Debug, Clone, Serialize, Tabled)]
+pub struct CoverageRow {
+    #[tabled(rename = "Directory")]
+    pub directory: String,
+    #[tabled(rename = "Lines")]
+    pub lines: usize,
+    #[tabled(rename = "Covered")]
+    pub covered: usize,
+    #[tabled(rename = "Coverage")]
+    #[serde(skip)]
+    pub percent_label: String,
+    #[tabled(skip)]
+    pub percent: f64,
+}
+
+impl Coverage {
+    /// Add hits for a file, summing counts for lines seen before
+    pub fn add(&mut self, file: String, hits: LineHits) {
+        let entry = self.files.entry(file).or_default();
+        for (line, count) in hits {
+            *entry.entry(line).or_default() += count;
+        }
+    }
+
+    pub fn merge(&mut self, other: Coverage) {
+        for (file, hits) in other.files {
+            self.add(file, hits);
+        }
+    }
+
+    fn totals(hits: &LineHits) -> (usize, usize) {
+        (hits.len(), hits.values().filter(|c| **c > 0).count())
+    }
+
+    /// Render as an lcov tracefile
+    pub fn to_lcov(&self) -> String {
+        let mut out = String::new();
+        for (file, hits) in &self.files {
+            let (found, hit) = Self::totals(hits);
+            out.push_str(&format!("SF:{}\n", file));
+            for (line, count) in hits {
+                out.push_str(&format!("DA:{},{}\n", line, count));
+            }
+            out.push_str(&format!("LF:{}\nLH:{}\nend_of_record\n", found, hit));
+        }
+        out
+    }
+
+    /// Per-directory summary, with a final "total" row
+    pub fn summary(&self) -> Vec<CoverageRow> {
+        let mut dirs: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+        for (file, hits) in &self.files {
+            let dir = Path::new(file).parent()
+                .map(|p| p.display().to_string())
+                .filter(|p| !p.is_empty())
+                .unwrap_or_else(|| ".".to_string());
+            let (found, hit) = Self::totals(hits);
+            let entry = dirs.entry(dir).or_default();
+            entry.0 += found;
+            entry.1 += hit;
+        }
+        let total = dirs.values().fold((0, 0), |acc, (f, h)| (acc.0 + f, acc.1 + h));
+        dirs.into_iter()
+            .chain(std::iter::once(("total".to_string(), total)))
+            .map(|(directory, (lines, covered))| row(directory, lines, covered))
+            .collect()
+    }
+}
+
+fn row(directory: String, lines: usize, covered: usize) -> CoverageRow {
+    let percent = if lines == 0 { 100.0 } else { covered as f64 * 100.0 / lines as f64 };
+    CoverageRow { directory, lines, covered, percent_label: format!("{:.1}%", percent), percent }
+}
+
+fn relative(root: &Path, file: &str) -> String {
+    Path::new(file).strip_prefix(root).map(|p| p.display().to_string()).unwrap_or_else(|_| file.to_string())
+}
+
+/// Parse an lcov tracefile (only line data is used)
+pub fn parse_lcov(root: &Path, content: &str) -> Coverage {
+    let mut coverage = Coverage::default();
+    let mut current: Option<(String, LineHits)> = None;
+    for line in content.lines() {
+        if let Some(file) = line.strip_prefix("SF:") {
+            current = Some((relative(root, file.trim()), LineHits::new()));
+        } else if let Some(data) = line.strip_prefix("DA:") {
+            let mut parts = data.split(',');
+            if let (Some((_, hits)), Some(num), Some(count)) = (current.as_mut(), parts.next(), parts.next()) {
+                if let (Ok(num), Ok(count)) = (num.parse(), count.trim().parse()) {
+                    hits.insert(num, count);
+                }
+            }
+        } else if line == "end_of_record" {
+            if let Some((file, hits)) = current.take() {
+                coverage.add(file, hits);
+            }
+        }
+    }
+    coverage
+}
+
+/// Convert a clover report (phpunit `--coverage-clover`) to line coverage
+pub fn parse_clover(root: &Path, xml: &str) -> Coverage {
+    let file_re = Regex::new(r#"(?s)<file\b[^>]*\bname="([^"]+)"[^>]*>(.*?)</file>"#).unwrap();
+    let line_re = Regex::new(r#"<line\b[^>]*\bnum="(\d+)"[^>]*\bcount="(\d+)""#).unwrap();
+    let mut coverage = Coverage::default();
+    for file in file_re.captures_iter(xml) {
+        let hits: LineHits = line_re
+            .captures_iter(&file[2])
+            .filter_map(|l| Some((l[1].parse().ok()?, l[2].parse().ok()?)))
+            .collect();
+        coverage.add(relative(root, &file[1]), hits);
+    }
+    coverage
+}
+
+/// Command collecting coverage for a runner; the report lands in `out`
+fn coverage_command(runner: TestRunner, root: &Path, out: &Path) -> Command {
+    let mut cmd = match runner {
+        TestRunner::Cargo => {
+            let mut c = Command::new("cargo");
+            c.args(["llvm-cov", "--workspace", "--lcov", "--output-path"]).arg(out.join("lcov.info"));
+            c
+        }
+        TestRunner::Jest => {
+            let mut c = Command::new(root.join("node_modules/.bin/jest"));
+            c.args(["--ci", "--coverage", "--coverageReporters=lcov", "--coverageDirectory"]).arg(out);
+            c
+        }
+        TestRunner::Vitest => {
+            let mut c = Command::new(root.join("node_modules/.bin/vitest"));
+            c.args(["run", "--coverage.enabled", "--coverage.reporter=lcov"])
+                .arg(format!("--coverage.reportsDirectory={}", out.display()));
+            c
+        }
+        TestRunner::Phpunit => {
+            let mut c = Command::new(root.join("vendor/bin/phpunit"));
+            c.arg("--coverage-clover").arg(out.join("clover.xml")).env("XDEBUG_MODE", "coverage");
+            c
+        }
+    };
+    cmd.current_dir(root).envs(isolation::env_vars(root));
+    cmd
+}
+
+/// Run one runner with coverage enabled and read back its report
+async fn collect(runner: TestRunner, root: &Path) -> Result<Coverage> {
+    if runner == TestRunner::Cargo && !util::command_exists("cargo-llvm-cov").await {
+        return Err(anyhow!("cargo-llvm-cov is not installed (cargo install cargo-llvm-cov)"));
+    }
+    // Removed when dropped, including on the error paths below
+    let dir = tempfile::Builder::new()
+        .prefix(&format!("rcm-coverage-{}-", runner.name()))
+        .tempdir()
+        .context("Failed to create a temp directory")?;
+    let out = dir.path();
+    let output = tokio::process::Command::from(coverage_command(runner, root, out))
+        .output()
+        .await
+        .with_context(|| format!("Failed to run {} with coverage", runner.name()))?;
+    if !output.status.success() {
+        // Failing tests still produce a report; only warn here
+        tracing::warn!("{} exited with {}", runner.name(), output.status);
+    }
+
+    let coverage = if runner == TestRunner::Phpunit {
+        let xml = fs::read_to_string(out.join("clover.xml")).await
+            .context("phpunit wrote no clover report (is xdebug or pcov enabled?)")?;
+        parse_clover(root, &xml)
+    } else {
+        let lcov = fs::read_to_string(out.join("lcov.info")).await
+            .with_context(|| format!("{} wrote no lcov report", runner.name()))?;
+        parse_lcov(root, &lcov)
+    };
+    Ok(coverage)
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Combined HTML report: per-directory and per-file tables
+pub fn render_html(coverage: &Coverage) -> String {
+    let bar = |percent: f64| {
+        let color = if percent >= 80.0 { "#2da44e" } else if percent >= 50.0 { "#d4a72c" } else { "#cf222e" };
+        format!("<div class=\"bar\"><span style=\"width:{:.0}%;background:{}\"></span></div>", percent, color)
+    };
+    let table = |rows: Vec<CoverageRow>, heading: &str| {
+        let body: String = rows.iter().map(|r| format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape_html(&r.directory), r.lines, r.covered, r.percent_label, bar(r.percent),
+        )).collect();
+        format!("<h2>{}</h2>\n<table>\n<tr><th>Path</th><th>Lines</th><th>Covered</th><th>%</th><th></th></tr>\n{}</table>\n", heading, body)
+    };
+    let files = coverage.files.iter().map(|(file, hits)| {
+        let (lines, covered) = Coverage::totals(hits);
+        row(file.clone(), lines, covered)
+    }).collect();
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>RCM coverage</title>\n<style>\n\
+         body {{ font-family: system-ui, sans-serif; margin: 2rem; }}\n\
+         table {{ border-collapse: collapse; margin-bottom: 2rem; }}\n\
+         td, th {{ padding: 0.25rem 0.75rem; text-align: left; border-bottom: 1px solid #ddd; }}\n\
+         .bar {{ width: 120px; height: 8px; background: #eee; }}\n\
+         .bar span {{ display: block; height: 100%; }}\n\
+         </style>\n</head>\n<body>\n<h1>Coverage</h1>\n<p>Generated {}</p>\n{}{}</body>\n</html>\n",
+        chrono::Utc::now().format("%Y-%m-%d %H:%M UTC"),
+        table(coverage.summary(), "Directories"),
+        table(files, "Files"),
+    )
+}
+
+/// Collect coverage from every detected runner and write the merged reports
+pub async fn run(workspace: &Workspace, suites: Option<Vec<String>>, format: &str) -> Result<()> {
+    let root = workspace.root();
+    let mut coverage = Coverage::default();
+    let mut collected = Vec::new();
+
+    for runner in TestRunner::ALL {
+        if suites.as_ref().is_some_and(|s| !s.iter().any(|n| n == runner.name())) {
+            continue;
+        }
+        if !runner.detect(root).await {
+            continue;
+        }
+        if format == "table" {
+            println!("{}", style(format!("📊 Collecting {} coverage...", runner.name())).blue());
+        }
+        match collect(runner, root).await {
+            Ok(c) => {
+                coverage.merge(c);
+                collected.push(runner.name());
+            }
+            Err(e) => println!("{} {}: {}", style("⚠️").yellow(), runner.name(), e),
+        }
+    }
+    if collected.is_empty() {
+        return Err(anyhow!("No coverage collected (supported: cargo llvm-cov, jest, vitest, phpunit)"));
+    }
+
+    let dir: PathBuf = root.join(COVERAGE_DIR);
+    fs::create_dir_all(&dir).await?;
+    util::write_file(&dir.join("lcov.info"), coverage.to_lcov()).await?;
+    util::write_file(&dir.join("index.html"), render_html(&coverage)).await?;
+
+    let summary = coverage.summary();
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&summary)?),
+        "table" => {
+            println!("{}", Table::new(&summary));
+            println!("Merged from {}", collected.join(", "));
+            println!("LCOV: {}", style(dir.join("lcov.info").display()).cyan());
+            println!("HTML: {}", style(dir.join("index.html").display()).cyan());
+        }
+        _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_lcov_and_clover() {
+        let root = Path::new("/work");
+        let mut coverage = parse_lcov(root, "SF:/work/src/lib.rs\nDA:1,1\nDA:2,0\nend_of_record\n");
+        coverage.merge(parse_lcov(root, "SF:src/lib.rs\nDA:2,3\nDA:3,0\nLF:2\nLH:1\nend_of_record\n"));
+        coverage.merge(parse_clover(root, r#"<coverage><project>
+<file name="/work/app/User.php"><line num="10" type="stmt" count="2"/><line num="11" type="stmt" count="0"/></file>
+</project></coverage>"#));
+
+        assert_eq!(coverage.files["src/lib.rs"], LineHits::from([(1, 1), (2, 3), (3, 0)]));
+        let summary = coverage.summary();
+        assert_eq!(summary.iter().map(|r| r.directory.as_str()).collect::<Vec<_>>(), vec!["app", "src", "total"]);
+        assert_eq!((summary[2].lines, summary[2].covered), (5, 3));
+
+        let roundtrip = parse_lcov(root, &coverage.to_lcov());
+        assert_eq!(roundtrip.files, coverage.files);
+    }
+}
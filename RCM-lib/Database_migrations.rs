@@ -0,0 +1,277 @@
+//! Database migrations for RCM
+//!
+//! Detects the migration tools a workspace uses (sqlx, diesel, knex, Laravel
+//! artisan, flyway) and runs migrate/rollback/status through each of them with
+//! the database URLs of the active environment profile.
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use console::style;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use tokio::fs;
+use crate::environments;
+use crate::redact;
+use crate::toolchain;
+use crate::util::{execute_command, execute_mutation};
+use crate::workspace::Workspace;
+
+#[derive(Subcommand)]
+pub enum DbCommands {
+    /// Apply pending migrations
+    Migrate {
+        /// Migration tool to use (sqlx, diesel, knex, artisan, flyway; all detected if omitted)
+        #[arg(long)]
+        tool: Option<String>,
+        /// Environment profile providing DATABASE_URL (defaults to RCM_ENV, then development)
+        #[arg(long)]
+        env: Option<String>,
+    },
+    /// Roll back applied migrations
+    Rollback {
+        /// Migration tool to use
+        #[arg(long)]
+        tool: Option<String>,
+        /// Environment profile providing DATABASE_URL
+        #[arg(long)]
+        env: Option<String>,
+        /// Number of migrations (batches for knex) to roll back
+        #[arg(long, default_value = "1")]
+        steps: u32,
+    },
+    /// Show applied and pending migrations
+    Status {
+        /// Migration tool to use
+        #[arg(long)]
+        tool: Option<String>,
+        /// Environment profile providing DATABASE_URL
+        #[arg(long)]
+        env: Option<String>,
+    },
+    /// List the migration tools detected in this workspace
+    Tools,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MigrationTool {
+    Sqlx,
+    Diesel,
+    Knex,
+    Artisan,
+    Flyway,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MigrationOp {
+    Migrate,
+    Rollback(u32),
+    Status,
+}
+
+impl MigrationTool {
+    pub const ALL: [MigrationTool; 5] = [Self::Sqlx, Self::Diesel, Self::Knex, Self::Artisan, Self::Flyway];
+
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "sqlx" => Ok(Self::Sqlx),
+            "diesel" => Ok(Self::Diesel),
+            "knex" => Ok(Self::Knex),
+            "artisan" | "laravel" => Ok(Self::Artisan),
+            "flyway" => Ok(Self::Flyway),
+            _ => Err(anyhow!("Unsupported migration tool: {}. Use sqlx, diesel, knex, artisan or flyway", s)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sqlx => "sqlx",
+            Self::Diesel => "diesel",
+            Self::Knex => "knex",
+            Self::Artisan => "artisan",
+            Self::Flyway => "flyway",
+        }
+    }
+
+    /// Whether the workspace is set up for this tool
+    pub async fn detect(&self, root: &Path) -> bool {
+        match self {
+            Self::Sqlx => {
+                root.join("migrations").is_dir()
+                    && !root.join("diesel.toml").exists()
+                    && manifest_mentions(&root.join("Cargo.toml"), "sqlx").await
+            }
+            Self::Diesel => root.join("diesel.toml").exists(),
+            Self::Knex => ["knexfile.js", "knexfile.ts", "knexfile.cjs", "knexfile.mjs"]
+                .iter()
+                .any(|f| root.join(f).exists()),
+            Self::Artisan => root.join("artisan").exists() && root.join("composer.json").exists(),
+            Self::Flyway => ["flyway.conf", "flyway.toml", "conf/flyway.conf"]
+                .iter()
+                .any(|f| root.join(f).exists()),
+        }
+    }
+
+    /// Program and argument lists to run for an operation, in order
+    pub fn invocations(&self, op: MigrationOp, environment: &str) -> Vec<(&'static str, Vec<String>)> {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        // Tools that revert one migration per call are invoked once per step
+        let repeat = |steps: u32, program: &'static str, list: &[&str]| {
+            (0..steps.max(1)).map(|_| (program, args(list))).collect::<Vec<_>>()
+        };
+
+        match (self, op) {
+            (Self::Sqlx, MigrationOp::Migrate) => vec![("sqlx", args(&["migrate", "run"]))],
+            (Self::Sqlx, MigrationOp::Rollback(steps)) => repeat(steps, "sqlx", &["migrate", "revert"]),
+            (Self::Sqlx, MigrationOp::Status) => vec![("sqlx", args(&["migrate", "info"]))],
+
+            (Self::Diesel, MigrationOp::Migrate) => vec![("diesel", args(&["migration", "run"]))],
+            (Self::Diesel, MigrationOp::Rollback(steps)) => {
+                vec![("diesel", vec!["migration".into(), "revert".into(), "--number".into(), steps.max(1).to_string()])]
+            }
+            (Self::Diesel, MigrationOp::Status) => vec![("diesel", args(&["migration", "list"]))],
+
+            (Self::Knex, MigrationOp::Migrate) => vec![("npx", args(&["knex", "migrate:latest", "--env", environment]))],
+            (Self::Knex, MigrationOp::Rollback(steps)) => {
+                repeat(steps, "npx", &["knex", "migrate:rollback", "--env", environment])
+            }
+            (Self::Knex, MigrationOp::Status) => vec![("npx", args(&["knex", "migrate:status", "--env", environment]))],
+
+            // --force is required to migrate outside Laravel's local environment
+            (Self::Artisan, MigrationOp::Migrate) => {
+                vec![("php", vec!["artisan".into(), "migrate".into(), "--force".into(), format!("--env={}", environment)])]
+            }
+            (Self::Artisan, MigrationOp::Rollback(steps)) => vec![("php", vec![
+                "artisan".into(), "migrate:rollback".into(), format!("--step={}", steps.max(1)),
+                "--force".into(), format!("--env={}", environment),
+            ])],
+            (Self::Artisan, MigrationOp::Status) => {
+                vec![("php", vec!["artisan".into(), "migrate:status".into(), format!("--env={}", environment)])]
+            }
+
+            (Self::Flyway, MigrationOp::Migrate) => vec![("flyway", args(&["migrate"]))],
+            // `flyway undo` needs undo scripts (and a Teams license)
+            (Self::Flyway, MigrationOp::Rollback(steps)) => repeat(steps, "flyway", &["undo"]),
+            (Self::Flyway, MigrationOp::Status) => vec![("flyway", args(&["info"]))],
+        }
+    }
+}
+
+async fn manifest_mentions(path: &Path, needle: &str) -> bool {
+    fs::read_to_string(path).await.map(|c| c.contains(needle)).unwrap_or(false)
+}
+
+/// Tools the workspace is set up for
+pub async fn detect_tools(root: &Path) -> Vec<MigrationTool> {
+    let mut tools = Vec::new();
+    for tool in MigrationTool::ALL {
+        if tool.detect(root).await {
+            tools.push(tool);
+        }
+    }
+    tools
+}
+
+/// The requested tool, or every detected tool
+async fn select_tools(root: &Path, tool: Option<&str>) -> Result<Vec<MigrationTool>> {
+    if let Some(tool) = tool {
+        return Ok(vec![MigrationTool::from_str(tool)?]);
+    }
+    let tools = detect_tools(root).await;
+    if tools.is_empty() {
+        return Err(anyhow!(
+            "No migration tool detected (looked for sqlx, diesel.toml, knexfile, artisan and flyway.conf)"
+        ));
+    }
+    Ok(tools)
+}
+
+/// Run an operation with every selected tool using the environment's variables
+async fn run(root: &Path, tool: Option<&str>, env: Option<&str>, op: MigrationOp) -> Result<()> {
+    let environment = environments::active(env);
+    let vars = environments::resolve(root, &environment).await?;
+    if !vars.contains_key("DATABASE_URL") && std::env::var("DATABASE_URL").is_err() {
+        tracing::warn!(
+            "DATABASE_URL is not set for '{}'; add it to {}",
+            environment, environments::profile_path(root, &environment).display()
+        );
+    }
+
+    for tool in select_tools(root, tool).await? {
+        println!("{}", style(format!("🗄️  {} ({}, {})", op_label(op), tool.name(), environment)).cyan().bold());
+        for (program, args) in tool.invocations(op, &environment) {
+            let mut cmd = command(root, program, &args, &vars);
+            if op == MigrationOp::Status {
+                let result = execute_command(&mut cmd).await
+                    .with_context(|| format!("{} status failed", tool.name()))?;
+                println!("{}", redact::redact(result.stdout.trim_end()));
+            } else {
+                execute_mutation(&mut cmd).await
+                    .with_context(|| format!("{} {} failed", tool.name(), op_label(op).to_lowercase()))?;
+            }
+        }
+        if op != MigrationOp::Status {
+            println!("{}", style(format!("✅ {} done", tool.name())).green());
+        }
+    }
+    Ok(())
+}
+
+fn command(root: &Path, program: &str, args: &[String], vars: &HashMap<String, String>) -> Command {
+    let mut cmd = Command::new(program);
+    cmd.args(args).current_dir(root).envs(vars);
+    if let Some(path) = toolchain::shim_path(root) {
+        cmd.env("PATH", path);
+    }
+    cmd
+}
+
+fn op_label(op: MigrationOp) -> &'static str {
+    match op {
+        MigrationOp::Migrate => "Migrate",
+        MigrationOp::Rollback(_) => "Rollback",
+        MigrationOp::Status => "Status",
+    }
+}
+
+/// Handle database commands
+pub async fn handle_command(workspace: &Workspace, cmd: DbCommands) -> Result<()> {
+    let root = workspace.root();
+    match cmd {
+        DbCommands::Migrate { tool, env } => run(root, tool.as_deref(), env.as_deref(), MigrationOp::Migrate).await,
+        DbCommands::Rollback { tool, env, steps } => {
+            run(root, tool.as_deref(), env.as_deref(), MigrationOp::Rollback(steps)).await
+        }
+        DbCommands::Status { tool, env } => run(root, tool.as_deref(), env.as_deref(), MigrationOp::Status).await,
+        DbCommands::Tools => {
+            let tools = detect_tools(root).await;
+            if tools.is_empty() {
+                println!("{}", style("No migration tools detected").dim());
+            }
+            for tool in tools {
+                println!("  • {}", style(tool.name()).cyan());
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invocations() {
+        let rollback = MigrationTool::Sqlx.invocations(MigrationOp::Rollback(2), "staging");
+        assert_eq!(rollback.len(), 2);
+        assert_eq!(rollback[0], ("sqlx", vec!["migrate".to_string(), "revert".to_string()]));
+
+        let artisan = MigrationTool::Artisan.invocations(MigrationOp::Rollback(3), "staging");
+        assert_eq!(artisan.len(), 1);
+        assert!(artisan[0].1.contains(&"--step=3".to_string()));
+        assert!(artisan[0].1.contains(&"--env=staging".to_string()));
+
+        let knex = MigrationTool::Knex.invocations(MigrationOp::Migrate, "production");
+        assert_eq!(knex[0].1, vec!["knex", "migrate:latest", "--env", "production"]);
+    }
+}
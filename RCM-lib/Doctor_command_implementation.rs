@@ -0,0 +1,524 @@
+//! Doctor command implementation
+//!
+//! Goes beyond `rcm ensure`: alongside each manager's environment check it
+//! looks at PATH health, toolchains installed more than once, registry and
+//! proxy reachability, free space in the cache directory, write access to
+//! RCM's directories, local model runtimes and declared toolchain
+//! constraints (MSRV, node engines, PHP platform), and suggests a fix for
+//! everything it reports.
+
+use anyhow::{anyhow, Result};
+use console::style;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tabled::{Table, Tabled};
+use crate::commands::ensure;
+use crate::config::Config;
+use crate::engines;
+use crate::util;
+use crate::workspace::Workspace;
+
+/// Free space below which the cache directory is a warning
+const LOW_DISK_BYTES: u64 = 5 * 1024 * 1024 * 1024;
+
+/// Free space below which installs are likely to fail
+const CRITICAL_DISK_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Per-request timeout for reachability probes
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Tools that commonly end up installed several times
+const TOOLCHAINS: &[&str] = &["node", "npm", "php", "composer", "python3", "pip3", "cargo", "go", "ruby"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Ok,
+    Info,
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Self::Ok => "✅ ok",
+            Self::Info => "ℹ️  info",
+            Self::Warning => "⚠️  warning",
+            Self::Error => "❌ error",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// One diagnostic and how to resolve it
+#[derive(Debug, Clone, Serialize, Tabled)]
+pub struct Finding {
+    #[tabled(rename = "Check")]
+    pub check: String,
+    #[tabled(rename = "Severity")]
+    pub severity: Severity,
+    #[tabled(rename = "Finding")]
+    pub message: String,
+    #[tabled(rename = "Fix", display_with = "display_fix")]
+    pub fix: Option<String>,
+}
+
+fn display_fix(fix: &Option<String>) -> String {
+    fix.clone().unwrap_or_default()
+}
+
+impl Finding {
+    fn new(check: &str, severity: Severity, message: impl Into<String>) -> Self {
+        Self { check: check.to_string(), severity, message: message.into(), fix: None }
+    }
+
+    fn fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+}
+
+/// Run every diagnostic and print the findings
+pub async fn run(workspace: &Workspace, config: &Config, skip_network: bool, format: &str) -> Result<()> {
+    if format != "table" && format != "json" {
+        return Err(anyhow!("Unsupported format: {}. Use table or json", format));
+    }
+
+    let mut findings = Vec::new();
+    findings.extend(check_managers(workspace).await?);
+    findings.extend(check_constraints(workspace).await?);
+    findings.extend(check_path(std::env::var_os("PATH").as_deref().unwrap_or_default()));
+    findings.extend(check_conflicts());
+    if skip_network || config.core.offline_mode {
+        findings.push(Finding::new("network", Severity::Info, "Registry checks skipped (offline)"));
+    } else {
+        findings.extend(check_registries(workspace, config).await);
+    }
+    findings.extend(check_disk(&config.cache_dir(), config).await?);
+    findings.extend(check_permissions(workspace, config));
+    findings.extend(check_model_runtimes().await);
+
+    findings.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| a.check.cmp(&b.check)));
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&findings)?);
+    } else {
+        println!("{}", style("🩺 RCM Doctor").cyan().bold());
+        println!("{}", Table::new(&findings));
+    }
+
+    let errors = findings.iter().filter(|f| f.severity == Severity::Error).count();
+    let warnings = findings.iter().filter(|f| f.severity == Severity::Warning).count();
+    if format == "table" {
+        println!();
+        if errors == 0 && warnings == 0 {
+            println!("{}", style("✅ No problems found").green().bold());
+        } else {
+            println!("{} {} error(s), {} warning(s)", style("🩺").bold(), errors, warnings);
+        }
+    }
+
+    if errors > 0 {
+        return Err(anyhow!("rcm doctor found {} error(s)", errors));
+    }
+    Ok(())
+}
+
+/// The per-manager checks `rcm ensure` runs, as findings
+async fn check_managers(workspace: &Workspace) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    for manager in workspace.enabled_managers() {
+        let status = ensure::check_manager_environment(workspace, &manager).await?;
+        let check = format!("manager:{}", status.name);
+        if !status.available {
+            let fix = status.issues.first().cloned().unwrap_or_else(|| format!("Install {}", status.name));
+            findings.push(Finding::new(&check, Severity::Error, "Not available").fix(fix));
+            continue;
+        }
+        for issue in &status.issues {
+            findings.push(Finding::new(&check, Severity::Warning, issue.clone()));
+        }
+        for missing in &status.missing_dependencies {
+            findings.push(Finding::new(&check, Severity::Warning, missing.clone()).fix("rcm ensure"));
+        }
+        if status.issues.is_empty() && status.missing_dependencies.is_empty() {
+            let version = status.version.as_deref().unwrap_or("unknown version");
+            findings.push(Finding::new(&check, Severity::Ok, version));
+        }
+    }
+    Ok(findings)
+}
+
+/// Declared MSRV, node engines and PHP platform requirements against the toolchains in use
+async fn check_constraints(workspace: &Workspace) -> Result<Vec<Finding>> {
+    let violations = engines::check(workspace.root(), &workspace.enabled_managers()).await?;
+    Ok(violations.iter()
+        .map(|v| Finding::new(&format!("engine:{}", v.tool), Severity::Error, v.message()).fix(v.fix()))
+        .collect())
+}
+
+/// Missing, duplicated and relative PATH entries
+fn check_path(path_var: &OsStr) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut seen = HashSet::new();
+
+    for dir in std::env::split_paths(path_var) {
+        let shown = dir.display().to_string();
+        if shown.is_empty() || dir.is_relative() {
+            let entry = if shown.is_empty() { "(empty)".to_string() } else { shown };
+            findings.push(
+                Finding::new("path", Severity::Warning, format!("Relative PATH entry {}", entry))
+                    .fix("Remove it from PATH; relative entries resolve against the current directory"),
+            );
+            continue;
+        }
+        if !seen.insert(dir.clone()) {
+            findings.push(
+                Finding::new("path", Severity::Info, format!("Duplicate PATH entry {}", shown))
+                    .fix("Remove the repeated entry from your shell profile"),
+            );
+        } else if !dir.is_dir() {
+            findings.push(
+                Finding::new("path", Severity::Info, format!("PATH entry {} does not exist", shown))
+                    .fix("Remove it from your shell profile"),
+            );
+        }
+    }
+
+    if findings.is_empty() {
+        findings.push(Finding::new("path", Severity::Ok, format!("{} entries", seen.len())));
+    }
+    findings
+}
+
+/// Tools with several distinct binaries on PATH, and side-by-side PHP versions
+fn check_conflicts() -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for tool in TOOLCHAINS {
+        let binaries = distinct_binaries(tool);
+        if binaries.len() < 2 {
+            continue;
+        }
+        let active = binaries[0].display().to_string();
+        let others: Vec<String> = binaries[1..].iter().map(|p| p.display().to_string()).collect();
+        let uses_version_manager = binaries.iter().any(|p| is_version_managed(p));
+        let fix = if uses_version_manager {
+            format!("Uninstall the system {} or keep the version manager's shims first on PATH", tool)
+        } else {
+            format!("Remove the unused {} or reorder PATH so the intended one comes first", tool)
+        };
+        findings.push(
+            Finding::new(
+                "toolchains",
+                Severity::Warning,
+                format!("{} resolves to {}; also found {}", tool, active, others.join(", ")),
+            )
+            .fix(fix),
+        );
+    }
+
+    let php_versions = versioned_binaries("php");
+    if php_versions.len() > 1 {
+        let active = which::which("php").map(|p| p.display().to_string()).unwrap_or_else(|_| "nothing".to_string());
+        findings.push(
+            Finding::new(
+                "toolchains",
+                Severity::Warning,
+                format!("PHP versions {} installed; php resolves to {}", php_versions.join(", "), active),
+            )
+            .fix("Pick one with `sudo update-alternatives --config php` and pin it in composer.json's config.platform"),
+        );
+    }
+
+    if findings.is_empty() {
+        findings.push(Finding::new("toolchains", Severity::Ok, "No conflicting installations"));
+    }
+    findings
+}
+
+/// Binaries named `tool` on PATH in lookup order, with symlinks to the same file collapsed
+fn distinct_binaries(tool: &str) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    which::which_all(tool)
+        .map(|paths| {
+            paths
+                .filter(|p| seen.insert(std::fs::canonicalize(p).unwrap_or_else(|_| p.clone())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Installed by nvm, fnm, volta, asdf, phpenv, pyenv or rbenv
+fn is_version_managed(path: &Path) -> bool {
+    let path = path.to_string_lossy();
+    [".nvm", "fnm", ".volta", ".asdf", ".phpenv", ".pyenv", ".rbenv"]
+        .iter()
+        .any(|marker| path.contains(marker))
+}
+
+/// `php7.4`, `php8.2` and so on across PATH
+fn versioned_binaries(tool: &str) -> Vec<String> {
+    let mut names = BTreeSet::new();
+    if let Some(path) = std::env::var_os("PATH") {
+        for dir in std::env::split_paths(&path) {
+            let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if is_versioned_name(tool, &name) {
+                    names.insert(name);
+                }
+            }
+        }
+    }
+    names.into_iter().collect()
+}
+
+fn is_versioned_name(tool: &str, name: &str) -> bool {
+    name.strip_prefix(tool)
+        .and_then(|version| version.split_once('.'))
+        .map(|(major, minor)| {
+            !major.is_empty()
+                && !minor.is_empty()
+                && major.chars().all(|c| c.is_ascii_digit())
+                && minor.chars().all(|c| c.is_ascii_digit())
+        })
+        .unwrap_or(false)
+}
+
+/// Default registries for the enabled managers plus any configured ones
+fn registry_urls(workspace: &Workspace, config: &Config) -> Vec<(String, String)> {
+    let mut urls = Vec::new();
+    for manager in workspace.enabled_managers() {
+        // Probe the mirror managers will actually be pointed at
+        if let Some(registry) = crate::mirrors::configured(config, &manager) {
+            let url = registry.url.trim_start_matches("sparse+").trim_end_matches('/');
+            let probe = if manager == "cargo" { format!("{}/config.json", url) } else { format!("{}/", url) };
+            urls.push((manager, probe));
+            continue;
+        }
+        let url = match manager.as_str() {
+            "cargo" => "https://index.crates.io/config.json",
+            "npm" => "https://registry.npmjs.org/",
+            "composer" => "https://repo.packagist.org/packages.json",
+            "pip" => "https://pypi.org/simple/",
+            "go" => "https://proxy.golang.org/",
+            "gem" => "https://rubygems.org/",
+            _ => continue,
+        };
+        urls.push((manager, url.to_string()));
+    }
+    for (name, registry) in &config.registries {
+        urls.push((name.clone(), registry.mirror.clone().unwrap_or_else(|| registry.url.clone())));
+    }
+    urls
+}
+
+/// Probe each registry through the proxy settings reqwest picks up from the environment
+async fn check_registries(workspace: &Workspace, config: &Config) -> Vec<Finding> {
+    let proxy = ["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy"]
+        .iter()
+        .find_map(|key| std::env::var(key).ok().filter(|v| !v.is_empty()));
+    let mut findings = Vec::new();
+
+    if proxy.is_none() && !config.proxies.is_empty() {
+        let names: Vec<&str> = config.proxies.keys().map(|k| k.as_str()).collect();
+        findings.push(
+            Finding::new("proxy", Severity::Info, format!("Proxies configured ({}) but HTTPS_PROXY is unset", names.join(", ")))
+                .fix("Export HTTPS_PROXY so package managers outside RCM use the same proxy"),
+        );
+    }
+
+    let client = match reqwest::Client::builder().timeout(PROBE_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            findings.push(Finding::new("network", Severity::Error, format!("HTTP client unavailable: {}", e)));
+            return findings;
+        }
+    };
+
+    for (name, url) in registry_urls(workspace, config) {
+        let check = format!("registry:{}", name);
+        match client.get(&url).send().await {
+            Ok(response) if response.status().is_success() || response.status().is_redirection() => {
+                findings.push(Finding::new(&check, Severity::Ok, format!("{} reachable", url)));
+            }
+            Ok(response) => {
+                findings.push(
+                    Finding::new(&check, Severity::Warning, format!("{} answered {}", url, response.status()))
+                        .fix("Check the registry URL and credentials in the RCM config"),
+                );
+            }
+            Err(e) => {
+                let fix = match &proxy {
+                    Some(proxy) => format!("Verify the proxy {} allows {}", crate::redact::redact(proxy), url),
+                    None => "Check connectivity, or set HTTPS_PROXY / a registry mirror if you are behind a proxy".to_string(),
+                };
+                let reason = if e.is_timeout() { "timed out".to_string() } else { e.to_string() };
+                findings.push(Finding::new(&check, Severity::Error, format!("{} unreachable: {}", url, reason)).fix(fix));
+            }
+        }
+    }
+    findings
+}
+
+/// Free space on the cache directory's filesystem, and cache size against its limit
+async fn check_disk(cache_dir: &Path, config: &Config) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+
+    // The cache may not exist yet; measure the filesystem it will live on
+    let existing = cache_dir.ancestors().find(|p| p.exists()).unwrap_or(Path::new("."));
+    match free_bytes(existing).await {
+        Some(free) => {
+            let shown = format!("{} free at {}", format_gib(free), existing.display());
+            let finding = if free < CRITICAL_DISK_BYTES {
+                Finding::new("disk", Severity::Error, shown)
+                    .fix("Free space or move the cache with `rcm config set cache.directory <path>`")
+            } else if free < LOW_DISK_BYTES {
+                Finding::new("disk", Severity::Warning, shown)
+                    .fix(format!("Clear old entries under {}", cache_dir.display()))
+            } else {
+                Finding::new("disk", Severity::Ok, shown)
+            };
+            findings.push(finding);
+        }
+        None => findings.push(Finding::new("disk", Severity::Info, "Could not determine free space (df unavailable)")),
+    }
+
+    if cache_dir.exists() {
+        let usage = config.cache_usage().await?;
+        let limit = config.cache.max_size_mb * 1024 * 1024;
+        if limit > 0 && usage.size_bytes > limit {
+            findings.push(
+                Finding::new(
+                    "cache",
+                    Severity::Warning,
+                    format!("Cache is {} MiB, over the {} MiB limit", usage.size_bytes / 1024 / 1024, config.cache.max_size_mb),
+                )
+                .fix(format!("Clear {} or raise cache.max_size_mb", cache_dir.display())),
+            );
+        }
+    }
+    Ok(findings)
+}
+
+/// Whether this user can write everywhere RCM keeps state
+fn check_permissions(workspace: &Workspace, config: &Config) -> Vec<Finding> {
+    let mut dirs = vec![
+        ("workspace", workspace.root().join(".rcm")),
+        ("cache", config.cache_dir()),
+        ("data", config.data_dir()),
+    ];
+    if let Ok(path) = Config::default_config_path() {
+        dirs.push(("config", path));
+    }
+
+    let mut findings = Vec::new();
+    for (name, path) in dirs {
+        if let Some(issue) = util::diagnose_write_access(&path) {
+            findings.push(
+                Finding::new("permissions", Severity::Error, format!("{}: {}", name, issue.problem))
+                    .fix(issue.fix),
+            );
+        }
+    }
+    if findings.is_empty() {
+        findings.push(Finding::new("permissions", Severity::Ok, "Workspace, cache, data and config paths are writable"));
+    }
+    findings
+}
+
+async fn free_bytes(path: &Path) -> Option<u64> {
+    let output = tokio::process::Command::new("df").arg("-Pk").arg(path).output().await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_df_available(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Available bytes from POSIX `df -Pk` output
+fn parse_df_available(output: &str) -> Option<u64> {
+    let line = output.lines().nth(1)?;
+    let available_kb: u64 = line.split_whitespace().nth(3)?.parse().ok()?;
+    Some(available_kb * 1024)
+}
+
+fn format_gib(bytes: u64) -> String {
+    format!("{:.1} GiB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
+/// Ollama and llama.cpp, which `rcm gpt` deploys models with
+async fn check_model_runtimes() -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let has_ollama = which::which("ollama").is_ok();
+    let has_llama_server = which::which("llama-server").is_ok();
+
+    if has_ollama {
+        let host = std::env::var("OLLAMA_HOST").unwrap_or_else(|_| "127.0.0.1:11434".to_string());
+        let base = if host.starts_with("http") { host } else { format!("http://{}", host) };
+        let url = format!("{}/api/version", base.trim_end_matches('/'));
+        let running = match reqwest::Client::builder().timeout(Duration::from_secs(2)).build() {
+            Ok(client) => client.get(&url).send().await.map(|r| r.status().is_success()).unwrap_or(false),
+            Err(_) => false,
+        };
+        if running {
+            findings.push(Finding::new("models:ollama", Severity::Ok, format!("Server running at {}", base)));
+        } else {
+            findings.push(
+                Finding::new("models:ollama", Severity::Warning, format!("Installed but no server at {}", base))
+                    .fix("Start it with `ollama serve` (or `systemctl start ollama`)"),
+            );
+        }
+    }
+
+    if has_llama_server {
+        findings.push(Finding::new("models:llama.cpp", Severity::Ok, "llama-server on PATH"));
+    }
+
+    if !has_ollama && !has_llama_server {
+        findings.push(
+            Finding::new("models", Severity::Info, "No local model runtime found")
+                .fix("Install Ollama (https://ollama.com) or build llama.cpp to use `rcm gpt`"),
+        );
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_df_available() {
+        let output = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                      /dev/nvme0n1p2   490691512 301234560 164459000      65% /\n";
+        assert_eq!(parse_df_available(output), Some(164459000 * 1024));
+        assert_eq!(parse_df_available("Filesystem\n"), None);
+    }
+
+    #[test]
+    fn test_check_path() {
+        let existing = std::env::temp_dir();
+        let missing = existing.join("rcm-doctor-missing-dir");
+        let path = std::env::join_paths([existing.clone(), missing, PathBuf::from("bin"), existing]).unwrap();
+        let findings = check_path(&path);
+        assert_eq!(findings.len(), 3);
+        assert!(findings.iter().any(|f| f.severity == Severity::Warning && f.message.contains("Relative")));
+        assert!(findings.iter().any(|f| f.message.starts_with("Duplicate")));
+        assert!(findings.iter().any(|f| f.message.ends_with("does not exist")));
+    }
+
+    #[test]
+    fn test_versioned_names() {
+        assert!(is_versioned_name("php", "php8.2"));
+        assert!(is_versioned_name("php", "php7.4"));
+        assert!(!is_versioned_name("php", "php"));
+        assert!(!is_versioned_name("php", "phpize8.2"));
+        assert!(!is_versioned_name("php", "php-fpm8.2"));
+        assert!(Severity::Error > Severity::Warning);
+    }
+}
@@ -0,0 +1,421 @@
+//! Content-addressed download cache
+//!
+//! Source archives, bootstrap installers and Hugging Face model shards are
+//! stored once under `Config::cache_dir()/blobs/sha256/<aa>/<digest>`, named by
+//! the SHA-256 of their content, and copied out on a hit (the OS copy clones
+//! the file on APFS, Btrfs and XFS). Blobs are never hard-linked, so editing a
+//! materialized file can't corrupt the cache. A
+//! small URL index maps sources to digests so downloads without a published
+//! checksum are also reused while younger than `cache.ttl_hours`. A blob's
+//! mtime is refreshed on every hit; `rcm cache clean` drops blobs unused for
+//! `ttl_hours`, then the least recently used until the total fits in
+//! `cache.max_size_mb`. Eviction also runs after each store.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use walkdir::WalkDir;
+use crate::config::Config;
+use crate::util;
+
+/// Blob directory relative to the cache root
+pub const BLOBS_DIR: &str = "blobs/sha256";
+
+const URL_INDEX: &str = "blobs/urls.json";
+
+/// Where the URL index says a source's content lives
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UrlEntry {
+    sha256: String,
+    fetched_at: u64,
+}
+
+/// Blob counts and sizes, for `rcm cache stats`
+#[derive(Debug, Default, Serialize)]
+pub struct BlobStats {
+    pub blobs: usize,
+    pub bytes: u64,
+    pub limit_bytes: u64,
+    pub urls: usize,
+}
+
+/// What `clean` removed
+#[derive(Debug, Default, Serialize)]
+pub struct EvictReport {
+    pub expired: usize,
+    pub evicted: usize,
+    pub freed_bytes: u64,
+}
+
+/// Result of rehashing the blobs
+#[derive(Debug, Default, Serialize)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub corrupt: Vec<PathBuf>,
+}
+
+/// Path of the blob with `sha256` under a cache root
+pub fn blob_path(cache_root: &Path, sha256: &str) -> PathBuf {
+    let digest = sha256.to_lowercase();
+    cache_root.join(BLOBS_DIR).join(&digest[..2.min(digest.len())]).join(digest)
+}
+
+/// Mark a blob as just used, for LRU eviction
+pub fn touch(path: &Path) {
+    if let Ok(file) = std::fs::File::options().write(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+/// Sibling of `path` to write into before renaming over it
+fn partial_path(path: &Path) -> PathBuf {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("download");
+    path.with_file_name(format!(".{}.{}.partial", name, uuid::Uuid::new_v4().simple()))
+}
+
+/// Copy `from` to `dest` through a temporary file, so `dest` is never half-written
+async fn materialize(from: &Path, dest: &Path) -> Result<()> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let partial = partial_path(dest);
+    let copied = async {
+        fs::copy(from, &partial).await
+            .with_context(|| format!("Failed to copy {} to {}", from.display(), dest.display()))?;
+        fs::rename(&partial, dest).await
+            .with_context(|| format!("Failed to replace {}", dest.display()))
+    }.await;
+    if copied.is_err() {
+        fs::remove_file(&partial).await.ok();
+    }
+    copied
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn idle_for(path: &Path) -> Duration {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .unwrap_or_default()
+}
+
+/// Blobs to drop: unused past `ttl`, then least recently used until under `limit`
+///
+/// Takes (path, size, idle time) and returns (expired, evicted) paths.
+pub fn eviction_plan(mut blobs: Vec<(PathBuf, u64, Duration)>, ttl: Duration, limit: u64) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let (expired, kept): (Vec<_>, Vec<_>) = blobs.drain(..).partition(|(_, _, idle)| *idle > ttl);
+    let mut kept = kept;
+    // Most idle first
+    kept.sort_by(|a, b| b.2.cmp(&a.2));
+    let mut total: u64 = kept.iter().map(|(_, size, _)| size).sum();
+    let mut evicted = Vec::new();
+    for (path, size, _) in kept {
+        if limit == 0 || total <= limit {
+            break;
+        }
+        total -= size;
+        evicted.push(path);
+    }
+    (expired.into_iter().map(|(path, _, _)| path).collect(), evicted)
+}
+
+/// Content-addressed blob store under the RCM cache directory
+pub struct DownloadCache {
+    root: PathBuf,
+    enabled: bool,
+    ttl: Duration,
+    limit: u64,
+}
+
+impl DownloadCache {
+    pub fn open(config: &Config) -> Self {
+        Self {
+            root: config.cache_dir(),
+            enabled: config.cache.enabled,
+            ttl: Duration::from_secs(config.cache.ttl_hours * 3600),
+            limit: config.cache.max_size_mb * 1024 * 1024,
+        }
+    }
+
+    /// The cache configured in the user's RCM config, for callers without a `Config`
+    pub async fn from_user_config() -> Self {
+        let config = Config::load(None).await.unwrap_or_default();
+        Self::open(&config)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn blob_files(&self) -> Vec<PathBuf> {
+        WalkDir::new(self.root.join(BLOBS_DIR))
+            .into_iter()
+            .flatten()
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.into_path())
+            .filter(|p| p.extension().is_none())
+            .collect()
+    }
+
+    async fn load_index(&self) -> BTreeMap<String, UrlEntry> {
+        match fs::read_to_string(self.root.join(URL_INDEX)).await {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+            Err(_) => BTreeMap::new(),
+        }
+    }
+
+    async fn save_index(&self, index: &BTreeMap<String, UrlEntry>) -> Result<()> {
+        let path = self.root.join(URL_INDEX);
+        let partial = partial_path(&path);
+        let written: Result<()> = async {
+            fs::write(&partial, serde_json::to_vec_pretty(index)?).await?;
+            fs::rename(&partial, &path).await?;
+            Ok(())
+        }.await;
+        if written.is_err() {
+            fs::remove_file(&partial).await.ok();
+        }
+        written
+    }
+
+    /// Download `url` to `dest`, or copy it from the cache
+    ///
+    /// With `expected_sha256` the content is checked and looked up by digest,
+    /// so any URL serving the same bytes hits the cache.
+    pub async fn fetch(&self, url: &str, expected_sha256: Option<&str>, dest: &Path) -> Result<()> {
+        if !self.enabled || util::is_read_only() {
+            util::download_file(url, dest).await?;
+            if let Some(expected) = expected_sha256 {
+                check_digest(url, &util::get_file_hash(dest).await?, expected)?;
+            }
+            return Ok(());
+        }
+
+        let known = match expected_sha256 {
+            Some(digest) => Some(digest.to_lowercase()),
+            None => self.load_index().await.get(url)
+                .filter(|entry| unix_now().saturating_sub(entry.fetched_at) < self.ttl.as_secs())
+                .map(|entry| entry.sha256.clone()),
+        };
+        if let Some(digest) = known {
+            let blob = blob_path(&self.root, &digest);
+            if blob.is_file() {
+                tracing::debug!("Download cache hit for {} ({})", url, &digest[..12.min(digest.len())]);
+                touch(&blob);
+                materialize(&blob, dest).await?;
+                // The copy is what gets used, so that is what is checked
+                if util::get_file_hash(dest).await?.eq_ignore_ascii_case(&digest) {
+                    return Ok(());
+                }
+                tracing::warn!("Dropping corrupt cached blob {}", blob.display());
+                fs::remove_file(&blob).await.ok();
+            }
+        }
+
+        let digest = self.download_blob(url, expected_sha256).await?;
+        let mut index = self.load_index().await;
+        index.insert(url.to_string(), UrlEntry { sha256: digest.clone(), fetched_at: unix_now() });
+        self.save_index(&index).await?;
+
+        materialize(&blob_path(&self.root, &digest), dest).await?;
+        if let Err(e) = self.clean().await {
+            tracing::warn!("Download cache eviction failed: {}", e);
+        }
+        Ok(())
+    }
+
+    /// Stream `url` into the blob store, returning its digest
+    ///
+    /// Content that doesn't match `expected_sha256` never enters the store, and
+    /// the partial file is removed whenever the download fails.
+    async fn download_blob(&self, url: &str, expected_sha256: Option<&str>) -> Result<String> {
+        let incoming = self.root.join(BLOBS_DIR).join(format!("incoming-{}.partial", uuid::Uuid::new_v4()));
+        if let Some(parent) = incoming.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let stored: Result<String> = async {
+            let mut response = reqwest::get(url).await.context("Failed to start download")?;
+            if !response.status().is_success() {
+                return Err(anyhow!("Download of {} failed with status: {}", url, response.status()));
+            }
+            let mut hasher = Sha256::new();
+            let mut out = fs::File::create(&incoming).await?;
+            while let Some(chunk) = response.chunk().await.context("Failed to download content")? {
+                hasher.update(&chunk);
+                out.write_all(&chunk).await?;
+            }
+            out.flush().await?;
+            drop(out);
+
+            let digest = format!("{:x}", hasher.finalize());
+            if let Some(expected) = expected_sha256 {
+                check_digest(url, &digest, expected)?;
+            }
+            self.store(&incoming, &digest).await?;
+            Ok(digest)
+        }.await;
+        if stored.is_err() {
+            fs::remove_file(&incoming).await.ok();
+        }
+        stored
+    }
+
+    /// Move a file with a known digest into the store
+    pub async fn store(&self, file: &Path, sha256: &str) -> Result<PathBuf> {
+        let blob = blob_path(&self.root, sha256);
+        if let Some(parent) = blob.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::rename(file, &blob).await
+            .with_context(|| format!("Failed to store {} in the download cache", file.display()))?;
+        Ok(blob)
+    }
+
+    /// Copy the blob with `sha256` to `dest` if cached; returns whether it was
+    pub async fn restore(&self, sha256: &str, dest: &Path) -> Result<bool> {
+        if !self.enabled {
+            return Ok(false);
+        }
+        let blob = blob_path(&self.root, sha256);
+        if !blob.is_file() {
+            return Ok(false);
+        }
+        touch(&blob);
+        materialize(&blob, dest).await?;
+        Ok(true)
+    }
+
+    /// Keep a file downloaded elsewhere (e.g. a Hub shard) under its digest
+    ///
+    /// The file stays in place; the store gets a copy of it.
+    pub async fn adopt(&self, file: &Path, sha256: &str) -> Result<()> {
+        if !self.enabled || util::is_read_only() {
+            return Ok(());
+        }
+        check_digest(&file.display().to_string(), &util::get_file_hash(file).await?, sha256)?;
+        let blob = blob_path(&self.root, sha256);
+        if !blob.is_file() {
+            materialize(file, &blob).await?;
+        }
+        if let Err(e) = self.clean().await {
+            tracing::warn!("Download cache eviction failed: {}", e);
+        }
+        Ok(())
+    }
+
+    pub async fn stats(&self) -> BlobStats {
+        let blobs = self.blob_files();
+        BlobStats {
+            blobs: blobs.len(),
+            bytes: blobs.iter().filter_map(|p| std::fs::metadata(p).ok()).map(|m| m.len()).sum(),
+            limit_bytes: self.limit,
+            urls: self.load_index().await.len(),
+        }
+    }
+
+    /// Drop blobs unused past `ttl_hours`, then LRU ones until under `max_size_mb`
+    pub async fn clean(&self) -> Result<EvictReport> {
+        let blobs: Vec<(PathBuf, u64, Duration)> = self.blob_files()
+            .into_iter()
+            .filter_map(|p| {
+                let size = std::fs::metadata(&p).ok()?.len();
+                let idle = idle_for(&p);
+                Some((p, size, idle))
+            })
+            .collect();
+        let (expired, evicted) = eviction_plan(blobs, self.ttl, self.limit);
+
+        let mut report = EvictReport { expired: expired.len(), evicted: evicted.len(), freed_bytes: 0 };
+        for path in expired.iter().chain(evicted.iter()) {
+            report.freed_bytes += std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            if util::is_dry_run() {
+                println!("[dry-run] remove {}", path.display());
+            } else {
+                fs::remove_file(path).await.ok();
+            }
+        }
+
+        // Forget URLs whose blob is gone
+        if report.expired + report.evicted > 0 && !util::is_dry_run() {
+            let mut index = self.load_index().await;
+            index.retain(|_, entry| blob_path(&self.root, &entry.sha256).is_file());
+            self.save_index(&index).await?;
+        }
+        Ok(report)
+    }
+
+    /// Rehash every blob; corrupt ones are removed unless `keep` is set
+    pub async fn verify(&self, keep: bool) -> Result<VerifyReport> {
+        let mut report = VerifyReport::default();
+        for path in self.blob_files() {
+            report.checked += 1;
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+            if util::get_file_hash(&path).await? == name {
+                continue;
+            }
+            if !keep {
+                if util::is_dry_run() {
+                    println!("[dry-run] remove {}", path.display());
+                } else {
+                    fs::remove_file(&path).await.ok();
+                }
+            }
+            report.corrupt.push(path);
+        }
+        Ok(report)
+    }
+}
+
+fn check_digest(url: &str, actual: &str, expected: &str) -> Result<()> {
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(anyhow!("Checksum mismatch for {}: expected {}, got {}", url, expected, actual))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blob_path_layout() {
+        let path = blob_path(Path::new("/cache"), "ABCDEF0123");
+        assert_eq!(path, PathBuf::from("/cache/blobs/sha256/ab/abcdef0123"));
+    }
+
+    #[tokio::test]
+    async fn test_materialize_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        let blob = dir.path().join("blob");
+        let dest = dir.path().join("out/file");
+        std::fs::write(&blob, "cached").unwrap();
+        materialize(&blob, &dest).await.unwrap();
+        std::fs::write(&dest, "edited").unwrap();
+        assert_eq!(std::fs::read_to_string(&blob).unwrap(), "cached");
+        assert_eq!(std::fs::read_dir(dest.parent().unwrap()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_eviction_plan() {
+        let hour = Duration::from_secs(3600);
+        let blobs = vec![
+            (PathBuf::from("old"), 10, hour * 48),
+            (PathBuf::from("idle"), 40, hour * 5),
+            (PathBuf::from("recent"), 40, hour),
+            (PathBuf::from("fresh"), 40, Duration::ZERO),
+        ];
+        let (expired, evicted) = eviction_plan(blobs, hour * 24, 90);
+        assert_eq!(expired, vec![PathBuf::from("old")]);
+        assert_eq!(evicted, vec![PathBuf::from("idle")]);
+    }
+}
@@ -0,0 +1,425 @@
+//! Toolchain constraint checks
+//!
+//! Compares what the workspace declares — `rust-version` in Cargo.toml,
+//! `engines.node` in package.json, and `php`/`ext-*` in composer.json — with the
+//! toolchains that will actually run (pinned shims first, then PATH), so
+//! `rcm ensure` and `rcm doctor` can fail before a confusing build error.
+//! Version mismatches can be fixed by pinning a matching toolchain through
+//! `.rcm/toolchain.toml`.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use dialoguer::Confirm;
+use serde_json::Value;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use crate::commands::letcond::parse_loose_version;
+use crate::npm;
+use crate::ppm;
+use crate::toolchain::{self, ToolPin, ToolchainPins};
+use crate::util;
+use crate::version_picker::composer_satisfies;
+
+/// PHP minors tried when switching, newest first
+const PHP_MINORS: &[&str] = &["8.4", "8.3", "8.2", "8.1", "8.0", "7.4"];
+
+/// A declared requirement the current toolchain doesn't meet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// `rust`, `node`, `php` or `ext-<name>`
+    pub tool: String,
+    /// Manager whose manifest declares it
+    pub manager: &'static str,
+    /// Where the requirement comes from (`Cargo.toml rust-version`)
+    pub source: &'static str,
+    pub required: String,
+    /// `None` when the tool isn't installed
+    pub found: Option<String>,
+}
+
+impl Violation {
+    /// One-line explanation
+    pub fn message(&self) -> String {
+        match &self.found {
+            Some(found) => format!("{} {} does not satisfy {} {}", self.tool, found, self.source, self.required),
+            None => format!("{} not found ({} requires {})", self.tool, self.source, self.required),
+        }
+    }
+
+    /// Whether a different toolchain can be pinned to fix it
+    pub fn switchable(&self) -> bool {
+        matches!(self.tool.as_str(), "rust" | "node" | "php")
+    }
+
+    /// How to fix it by hand
+    pub fn fix(&self) -> String {
+        match self.tool.as_str() {
+            "php" => format!("rcm ppm use-php <version> --install (matching {})", self.required),
+            "rust" | "node" => format!("rcm ensure (offers to pin {} {})", self.tool, self.required),
+            ext => format!("Install the PHP {} extension", ext.trim_start_matches("ext-")),
+        }
+    }
+}
+
+/// Constraint violations for the given managers' toolchains
+pub async fn check(root: &Path, managers: &[String]) -> Result<Vec<Violation>> {
+    let mut violations = Vec::new();
+    for manager in managers {
+        match manager.as_str() {
+            "cargo" => violations.extend(check_rust(root).await?),
+            "npm" => violations.extend(check_node(root).await?),
+            "composer" => violations.extend(check_php(root).await?),
+            _ => {}
+        }
+    }
+    Ok(violations)
+}
+
+/// Run a tool the way managers will: from the root, with pinned shims first
+async fn tool_output(root: &Path, program: &str, args: &[&str]) -> Option<String> {
+    let mut cmd = Command::new(program);
+    cmd.current_dir(root).args(args);
+    if let Some(path) = toolchain::shim_path(root) {
+        cmd.env("PATH", path);
+    }
+    let output = cmd.output().await.ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// `rust-version` from the package, or the workspace package table
+pub async fn rust_version(root: &Path) -> Result<Option<String>> {
+    let Ok(content) = tokio::fs::read_to_string(root.join("Cargo.toml")).await else {
+        return Ok(None);
+    };
+    let manifest: toml::Value = toml::from_str(&content).context("Failed to parse Cargo.toml")?;
+    Ok(manifest.get("package").and_then(|p| p.get("rust-version"))
+        .or_else(|| manifest.get("workspace").and_then(|w| w.get("package")).and_then(|p| p.get("rust-version")))
+        .and_then(|v| v.as_str())
+        .map(str::to_string))
+}
+
+async fn check_rust(root: &Path) -> Result<Vec<Violation>> {
+    let Some(msrv) = rust_version(root).await? else {
+        return Ok(Vec::new());
+    };
+    let required = parse_loose_version(&msrv)
+        .ok_or_else(|| anyhow!("Invalid rust-version '{}' in Cargo.toml", msrv))?;
+    // rustc honours rust-toolchain.toml when run from the root
+    let found = tool_output(root, "rustc", &["--version"]).await
+        .and_then(|v| parse_loose_version(&v));
+    if found.as_ref().is_some_and(|found| *found >= required) {
+        return Ok(Vec::new());
+    }
+    Ok(vec![Violation {
+        tool: "rust".to_string(),
+        manager: "cargo",
+        source: "Cargo.toml rust-version",
+        required: msrv,
+        found: found.map(|v| v.to_string()),
+    }])
+}
+
+async fn check_node(root: &Path) -> Result<Vec<Violation>> {
+    let Some(range) = npm::node_engine(root).await? else {
+        return Ok(Vec::new());
+    };
+    let found = npm::node_version(root).await;
+    if let Some(version) = &found {
+        if npm::satisfies_engine(&range, version)? {
+            return Ok(Vec::new());
+        }
+    }
+    Ok(vec![Violation {
+        tool: "node".to_string(),
+        manager: "npm",
+        source: "package.json engines.node",
+        required: range,
+        found: found.map(|v| v.to_string()),
+    }])
+}
+
+/// Extension names from `php -m`, normalized the way composer names `ext-*` packages
+pub fn loaded_extensions(php_modules: &str) -> Vec<String> {
+    php_modules
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('['))
+        .map(|line| match line.to_lowercase().as_str() {
+            // The only module whose display name differs from its extension name
+            "zend opcache" => "opcache".to_string(),
+            other => other.replace(' ', "-"),
+        })
+        .collect()
+}
+
+/// `ext-*` requirements that are not loaded
+pub fn missing_extensions<'a>(require: &'a serde_json::Map<String, Value>, loaded: &[String]) -> Vec<(&'a String, &'a Value)> {
+    require
+        .iter()
+        .filter(|(name, _)| name.starts_with("ext-"))
+        .filter(|(name, _)| {
+            let ext = name.trim_start_matches("ext-").to_lowercase();
+            !loaded.contains(&ext)
+        })
+        .collect()
+}
+
+async fn check_php(root: &Path) -> Result<Vec<Violation>> {
+    let Ok(content) = tokio::fs::read_to_string(root.join("composer.json")).await else {
+        return Ok(Vec::new());
+    };
+    let composer: Value = serde_json::from_str(&content).context("Failed to parse composer.json")?;
+    let Some(require) = composer.get("require").and_then(Value::as_object) else {
+        return Ok(Vec::new());
+    };
+
+    let mut violations = Vec::new();
+    let found = tool_output(root, "php", &["-r", "echo PHP_VERSION;"]).await;
+    if let Some(constraint) = require.get("php").and_then(Value::as_str) {
+        let satisfied = found.as_deref()
+            .and_then(parse_loose_version)
+            .and_then(|v| composer_satisfies(constraint, &v))
+            // Constraints we can't parse are left to composer
+            .unwrap_or(found.is_some());
+        if !satisfied {
+            violations.push(Violation {
+                tool: "php".to_string(),
+                manager: "composer",
+                source: "composer.json require.php",
+                required: constraint.to_string(),
+                found: found.clone(),
+            });
+        }
+    }
+
+    // Extensions only mean something once PHP itself is there
+    if found.is_some() {
+        let loaded = loaded_extensions(&tool_output(root, "php", &["-m"]).await.unwrap_or_default());
+        for (name, constraint) in missing_extensions(require, &loaded) {
+            violations.push(Violation {
+                tool: name.clone(),
+                manager: "composer",
+                source: "composer.json require",
+                required: constraint.as_str().unwrap_or("*").to_string(),
+                found: None,
+            });
+        }
+    }
+    Ok(violations)
+}
+
+/// Print violations and, on a terminal, offer to pin matching toolchains
+///
+/// Errors when anything is still unsatisfied afterwards.
+pub async fn enforce(root: &Path, managers: &[String]) -> Result<()> {
+    let violations = check(root, managers).await?;
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    println!("{}", style("⚠️  Toolchain requirements not met:").yellow().bold());
+    for violation in &violations {
+        println!("  {} {}", style("✗").red(), violation.message());
+    }
+
+    let interactive = std::io::stdin().is_terminal() && !util::is_dry_run() && !util::is_read_only();
+    let mut switched = false;
+    for violation in violations.iter().filter(|v| v.switchable()) {
+        if !interactive {
+            continue;
+        }
+        let prompt = format!("Pin a {} toolchain matching {} for this workspace?", violation.tool, violation.required);
+        if Confirm::new().with_prompt(prompt).default(true).interact()? {
+            switch(root, violation).await?;
+            switched = true;
+        }
+    }
+
+    let remaining = if switched { check(root, managers).await? } else { violations };
+    if remaining.is_empty() {
+        return Ok(());
+    }
+    let details: Vec<String> = remaining.iter()
+        .map(|v| format!("{} (fix: {})", v.message(), v.fix()))
+        .collect();
+    Err(anyhow!("Toolchain requirements not met:\n  {}", details.join("\n  ")))
+}
+
+/// Pin a toolchain that satisfies the violated requirement
+pub async fn switch(root: &Path, violation: &Violation) -> Result<()> {
+    match violation.tool.as_str() {
+        "rust" => switch_rust(root, &violation.required).await,
+        "node" => switch_node(root, &violation.required).await,
+        "php" => switch_php(root, &violation.required).await,
+        other => Err(anyhow!("{} can't be switched; {}", other, violation.fix())),
+    }
+}
+
+/// Install the MSRV toolchain with rustup and pin its rustc and cargo
+pub async fn switch_rust(root: &Path, msrv: &str) -> Result<()> {
+    if !util::command_exists("rustup").await {
+        return Err(anyhow!("rustup not found; install Rust {} or newer by hand", msrv));
+    }
+    let mut install = std::process::Command::new("rustup");
+    install.args(["toolchain", "install", msrv, "--profile", "minimal"]);
+    util::execute_mutation(&mut install).await
+        .with_context(|| format!("Failed to install Rust {}", msrv))?;
+
+    let mut pins = ToolchainPins::load(root).await?;
+    for tool in ["rustc", "cargo"] {
+        let mut which = std::process::Command::new("rustup");
+        which.args(["which", "--toolchain", msrv, tool]);
+        let path = util::execute_command(&mut which).await?.stdout.trim().to_string();
+        pins.pin(root, tool, ToolPin { version: msrv.to_string(), path: PathBuf::from(path) }).await?;
+    }
+    println!("✅ Using Rust {} in this workspace (pinned in {})", msrv, toolchain::TOOLCHAIN_FILE);
+    Ok(())
+}
+
+/// nvm's directory: `NVM_DIR`, or `~/.nvm`
+pub fn nvm_dir() -> Option<PathBuf> {
+    std::env::var_os("NVM_DIR")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".nvm")))
+}
+
+/// fnm's directory: `FNM_DIR`, a legacy `~/.fnm`, or the platform data dir
+/// (`~/.local/share/fnm`, `~/Library/Application Support/fnm` on macOS)
+fn fnm_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("FNM_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    dirs::home_dir()
+        .map(|home| home.join(".fnm"))
+        .filter(|legacy| legacy.is_dir())
+        .or_else(|| dirs::data_dir().map(|data| data.join("fnm")))
+}
+
+/// volta's directory: `VOLTA_HOME`, or `~/.volta`
+fn volta_dir() -> Option<PathBuf> {
+    std::env::var_os("VOLTA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".volta")))
+}
+
+/// Directories holding one node install per version, with each install's bin dir
+fn node_roots() -> Vec<(PathBuf, &'static str)> {
+    let mut roots = Vec::new();
+    if let Some(dir) = nvm_dir() {
+        roots.push((dir.join("versions/node"), "bin"));
+    }
+    if let Some(dir) = fnm_dir() {
+        roots.push((dir.join("node-versions"), "installation/bin"));
+    }
+    if let Some(dir) = volta_dir() {
+        roots.push((dir.join("tools/image/node"), "bin"));
+    }
+    roots
+}
+
+/// Node installs managed by nvm, fnm and volta
+pub async fn node_installs() -> Vec<(semver::Version, PathBuf)> {
+    let mut installs = Vec::new();
+    for (dir, bin) in node_roots() {
+        let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+            continue;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let name = entry.file_name().to_string_lossy().trim_start_matches('v').to_string();
+            let Ok(version) = semver::Version::parse(&name) else {
+                continue;
+            };
+            let bin_dir = entry.path().join(bin);
+            if bin_dir.join("node").exists() {
+                installs.push((version, bin_dir));
+            }
+        }
+    }
+    installs.sort_by(|a, b| b.0.cmp(&a.0));
+    installs
+}
+
+/// Pin the newest installed node that satisfies `engines.node`
+pub async fn switch_node(root: &Path, range: &str) -> Result<()> {
+    let mut chosen = None;
+    for (version, bin_dir) in node_installs().await {
+        if npm::satisfies_engine(range, &version)? {
+            chosen = Some((version, bin_dir));
+            break;
+        }
+    }
+    let (version, bin_dir) = chosen
+        .ok_or_else(|| anyhow!("No installed Node.js satisfies {}; install one with nvm, fnm or volta", range))?;
+
+    let mut pins = ToolchainPins::load(root).await?;
+    for tool in ["node", "npm", "npx"] {
+        let path = bin_dir.join(tool);
+        if path.exists() {
+            pins.pin(root, tool, ToolPin { version: version.to_string(), path }).await?;
+        }
+    }
+    println!("✅ Using Node.js {} in this workspace (pinned in {})", version, toolchain::TOOLCHAIN_FILE);
+    Ok(())
+}
+
+/// Pin the newest installed PHP minor that satisfies the constraint, installing one if needed
+async fn switch_php(root: &Path, constraint: &str) -> Result<()> {
+    let matching: Vec<&str> = PHP_MINORS.iter().copied()
+        .filter(|minor| {
+            parse_loose_version(minor)
+                .and_then(|v| composer_satisfies(constraint, &v))
+                .unwrap_or(false)
+        })
+        .collect();
+    let newest = *matching.first()
+        .ok_or_else(|| anyhow!("No known PHP version satisfies {}", constraint))?;
+    for minor in &matching {
+        if ppm::locate_php(minor).await.is_some() {
+            return ppm::use_php(root, minor, false).await;
+        }
+    }
+    ppm::use_php(root, newest, true).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn violation(tool: &str, found: Option<&str>) -> Violation {
+        Violation {
+            tool: tool.to_string(),
+            manager: "composer",
+            source: "composer.json require",
+            required: "^8.2".to_string(),
+            found: found.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_violation_messages() {
+        assert_eq!(violation("php", Some("8.1.2")).message(), "php 8.1.2 does not satisfy composer.json require ^8.2");
+        assert_eq!(violation("php", None).message(), "php not found (composer.json require requires ^8.2)");
+        assert!(violation("php", None).switchable());
+        assert!(!violation("ext-intl", None).switchable());
+        assert_eq!(violation("ext-intl", None).fix(), "Install the PHP intl extension");
+    }
+
+    #[test]
+    fn test_missing_extensions() {
+        let modules = "[PHP Modules]\nCore\nintl\nZend OPcache\npdo_mysql\n\n[Zend Modules]\nZend OPcache\n";
+        let loaded = loaded_extensions(modules);
+        let composer: Value = serde_json::json!({
+            "ext-intl": "*",
+            "ext-opcache": "*",
+            "ext-PDO_MySQL": "*",
+            "ext-redis": "^6.0",
+            "php": "^8.2",
+        });
+        let missing: Vec<&str> = missing_extensions(composer.as_object().unwrap(), &loaded)
+            .into_iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(missing, vec!["ext-redis"]);
+    }
+}
@@ -0,0 +1,205 @@
+//! Incremental `ensure` state
+//!
+//! After a clean `rcm ensure`, the content hash of every manifest and lockfile
+//! a manager reads is recorded in the `ensure` state collection, along with
+//! whether its install directory exists. The next run skips managers whose
+//! inputs still match, so a repeat `ensure` is a sub-second no-op, while a
+//! deleted `node_modules` or `vendor` is reinstalled; `rcm ensure --force`
+//! ignores the record.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use tokio::fs;
+use crate::lockfile::LOCKFILE_NAME;
+use crate::members;
+use crate::storage;
+use crate::toolchain::TOOLCHAIN_FILE;
+use crate::transaction::manager_files;
+
+/// State collection holding one record per manager
+pub const COLLECTION: &str = "ensure";
+
+/// Content hash per input file; `None` records that the file did not exist
+pub type Fingerprint = BTreeMap<String, Option<String>>;
+
+#[derive(Debug, Default)]
+pub struct EnsureState {
+    pub managers: BTreeMap<String, ManagerRecord>,
+    /// Managers recorded or invalidated since loading; only these are written back
+    touched: BTreeSet<String>,
+}
+
+/// Inputs of a manager at its last clean `ensure`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManagerRecord {
+    pub inputs: Fingerprint,
+    pub ensured_at: String,
+}
+
+/// Whether a manager needs checking, and why
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    Skip { since: String },
+    Run { reason: String },
+}
+
+/// Files whose contents decide what a manager installs
+pub fn input_files(manager: &str) -> Vec<&'static str> {
+    let own: &[&str] = match manager {
+        "container" => &[members::MANIFEST],
+        "system" => &[".rcm/system.json"],
+        other => manager_files(other),
+    };
+    if own.is_empty() {
+        return Vec::new();
+    }
+    // Pins and the RCM lockfile change what every manager resolves
+    own.iter().copied().chain([TOOLCHAIN_FILE, LOCKFILE_NAME]).collect()
+}
+
+/// Directories a manager installs into inside the workspace
+pub fn install_dirs(manager: &str) -> &'static [&'static str] {
+    match manager {
+        "npm" => &["node_modules"],
+        "composer" => &["vendor"],
+        _ => &[],
+    }
+}
+
+/// Hash a manager's inputs as they are on disk now
+pub async fn fingerprint(root: &Path, manager: &str) -> Fingerprint {
+    let mut inputs = Fingerprint::new();
+    for file in input_files(manager) {
+        let hash = fs::read(root.join(file)).await.ok().map(|bytes| format!("{:x}", Sha256::digest(&bytes)));
+        inputs.insert(file.to_string(), hash);
+    }
+    // Only existence: the contents are the manager's to check
+    for dir in install_dirs(manager) {
+        let present = root.join(dir).is_dir().then(|| "present".to_string());
+        inputs.insert(format!("{}/", dir), present);
+    }
+    inputs
+}
+
+/// Compare the current inputs with the last clean run
+pub fn decide(record: Option<&ManagerRecord>, current: &Fingerprint, force: bool) -> Decision {
+    if force {
+        return Decision::Run { reason: "--force".to_string() };
+    }
+    if current.is_empty() {
+        return Decision::Run { reason: "inputs unknown for this manager".to_string() };
+    }
+    let Some(record) = record else {
+        return Decision::Run { reason: "no previous clean ensure".to_string() };
+    };
+    for (file, hash) in current {
+        let reason = match (record.inputs.get(file), hash) {
+            (Some(before), now) if before == now => continue,
+            (None, None) => continue,
+            (Some(None) | None, Some(_)) => format!("{} created", file),
+            (Some(Some(_)), None) => format!("{} deleted", file),
+            _ => format!("{} changed", file),
+        };
+        return Decision::Run { reason };
+    }
+    Decision::Skip { since: record.ensured_at.clone() }
+}
+
+impl EnsureState {
+    pub async fn load(root: &Path) -> Self {
+        match storage::with_store(root, |store| store.list_as(COLLECTION)).await {
+            Ok(managers) => Self { managers, touched: BTreeSet::new() },
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable ensure state: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Write back the managers changed since loading, leaving the rest to concurrent runs
+    pub async fn save(&self, root: &Path) -> Result<()> {
+        let changed: Vec<(String, Option<ManagerRecord>)> = self.touched
+            .iter()
+            .map(|manager| (manager.clone(), self.managers.get(manager).cloned()))
+            .collect();
+        storage::with_store(root, move |store| {
+            for (manager, record) in changed {
+                match record {
+                    Some(record) => store.put_as(COLLECTION, &manager, &record),
+                    None => store.remove(COLLECTION, &manager),
+                }
+                .with_context(|| format!("Failed to save ensure state for {}", manager))?;
+            }
+            Ok(())
+        }).await
+    }
+
+    /// Remember `inputs` as the state of a clean run for `manager`
+    pub fn record(&mut self, manager: &str, inputs: Fingerprint) {
+        let ensured_at = chrono::Utc::now().to_rfc3339();
+        self.managers.insert(manager.to_string(), ManagerRecord { inputs, ensured_at });
+        self.touched.insert(manager.to_string());
+    }
+
+    /// Forget a manager so its next run is a full check
+    pub fn invalidate(&mut self, manager: &str) {
+        self.managers.remove(manager);
+        self.touched.insert(manager.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(entries: &[(&str, Option<&str>)]) -> Fingerprint {
+        entries.iter().map(|(f, h)| (f.to_string(), h.map(str::to_string))).collect()
+    }
+
+    #[test]
+    fn test_decide() {
+        let record = ManagerRecord {
+            inputs: inputs(&[("Cargo.toml", Some("a")), ("Cargo.lock", None)]),
+            ensured_at: "2026-01-01T00:00:00Z".to_string(),
+        };
+        let same = inputs(&[("Cargo.toml", Some("a")), ("Cargo.lock", None)]);
+        assert_eq!(decide(Some(&record), &same, false), Decision::Skip { since: record.ensured_at.clone() });
+        assert_eq!(decide(Some(&record), &same, true), Decision::Run { reason: "--force".to_string() });
+        assert_eq!(
+            decide(Some(&record), &inputs(&[("Cargo.toml", Some("b")), ("Cargo.lock", None)]), false),
+            Decision::Run { reason: "Cargo.toml changed".to_string() }
+        );
+        assert_eq!(
+            decide(Some(&record), &inputs(&[("Cargo.toml", Some("a")), ("Cargo.lock", Some("c"))]), false),
+            Decision::Run { reason: "Cargo.lock created".to_string() }
+        );
+        assert!(matches!(decide(None, &same, false), Decision::Run { .. }));
+        assert!(matches!(decide(Some(&record), &Fingerprint::new(), false), Decision::Run { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_fingerprint_tracks_install_dir() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("package.json"), "{}").unwrap();
+        std::fs::create_dir(root.path().join("node_modules")).unwrap();
+        let installed = fingerprint(root.path(), "npm").await;
+        let record = ManagerRecord { inputs: installed.clone(), ensured_at: "now".to_string() };
+        assert!(matches!(decide(Some(&record), &installed, false), Decision::Skip { .. }));
+
+        std::fs::remove_dir(root.path().join("node_modules")).unwrap();
+        assert_eq!(
+            decide(Some(&record), &fingerprint(root.path(), "npm").await, false),
+            Decision::Run { reason: "node_modules/ deleted".to_string() }
+        );
+    }
+
+    #[test]
+    fn test_input_files() {
+        assert!(input_files("cargo").contains(&"Cargo.lock"));
+        assert!(input_files("cargo").contains(&TOOLCHAIN_FILE));
+        assert!(input_files("unknown").is_empty());
+    }
+}
@@ -0,0 +1,148 @@
+//! Environment profiles for RCM
+//!
+//! Per-environment variables (e.g. `DATABASE_URL`) live in `.rcm/env/<name>.env`.
+//! The active environment is `--env`, then `RCM_ENV`, then `development`. Values
+//! may be `secret:<name>` references and are resolved only when a process is spawned.
+//! Tool versions pinned per workspace are handled by `tool_versions`.
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use crate::env_snapshot;
+use crate::secrets;
+use crate::tool_versions;
+use crate::workspace::Workspace;
+
+#[derive(Subcommand)]
+pub enum EnvCommands {
+    /// Record toolchains, system packages, env vars and lockfile hashes
+    Snapshot {
+        /// Where to write the descriptor
+        #[arg(long, default_value = "rcm-env.json")]
+        out: PathBuf,
+        /// Sign the descriptor with this SSH private key (writes <out>.sig)
+        #[arg(long)]
+        sign: Option<PathBuf>,
+    },
+    /// Explain how a descriptor differs from this machine
+    Diff {
+        /// Descriptor to compare
+        snapshot: PathBuf,
+        /// Compare with another descriptor instead of this machine
+        #[arg(long)]
+        against: Option<PathBuf>,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    /// Show the tool versions declared under [tools] and what is pinned
+    List,
+    /// Install declared tool versions and pin them for this workspace
+    Install {
+        /// Only these tools (default: all declared)
+        tools: Vec<String>,
+        /// Reinstall and re-pin even if the pinned version matches
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print the PATH change for the pinned tools: eval "$(rcm env shell)"
+    Shell {
+        /// Shell syntax (sh, bash, zsh, fish, powershell); defaults to $SHELL
+        #[arg(long)]
+        shell: Option<String>,
+    },
+    /// Add the pinned tools to PATH in the workspace's .envrc
+    Direnv,
+}
+
+/// Directory holding the profile files, relative to the workspace root
+pub const ENV_DIR: &str = ".rcm/env";
+
+/// Environment used when neither `--env` nor `RCM_ENV` is set
+pub const DEFAULT_ENVIRONMENT: &str = "development";
+
+/// Name of the active environment
+pub fn active(requested: Option<&str>) -> String {
+    requested
+        .map(str::to_string)
+        .or_else(|| std::env::var("RCM_ENV").ok().filter(|v| !v.is_empty()))
+        .unwrap_or_else(|| DEFAULT_ENVIRONMENT.to_string())
+}
+
+pub fn profile_path(workspace_root: &Path, name: &str) -> PathBuf {
+    workspace_root.join(ENV_DIR).join(format!("{}.env", name))
+}
+
+/// Parse dotenv-style `KEY=VALUE` lines (`#` comments, optional `export`, quoted values)
+pub fn parse_env_file(content: &str) -> Result<HashMap<String, String>> {
+    let mut vars = HashMap::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line.split_once('=')
+            .ok_or_else(|| anyhow!("line {}: expected KEY=VALUE", number + 1))?;
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(anyhow!("line {}: invalid variable name '{}'", number + 1, key));
+        }
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"').and_then(|v| v.strip_suffix('"'))
+            .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+            .unwrap_or(value);
+        vars.insert(key.to_string(), value.to_string());
+    }
+    Ok(vars)
+}
+
+/// Unresolved variables of an environment; a missing profile is empty
+pub async fn load(workspace_root: &Path, name: &str) -> Result<HashMap<String, String>> {
+    let path = profile_path(workspace_root, name);
+    if !path.exists() {
+        tracing::debug!("No environment profile at {}", path.display());
+        return Ok(HashMap::new());
+    }
+    let content = fs::read_to_string(&path).await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_env_file(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Variables of an environment with `secret:` references resolved, for spawning processes
+pub async fn resolve(workspace_root: &Path, name: &str) -> Result<HashMap<String, String>> {
+    secrets::resolve_env(&load(workspace_root, name).await?).await
+}
+
+/// Handle environment commands
+pub async fn handle_command(workspace: &Workspace, cmd: EnvCommands) -> Result<()> {
+    match cmd {
+        EnvCommands::Snapshot { out, sign } => env_snapshot::snapshot(workspace, &out, sign).await,
+        EnvCommands::Diff { snapshot, against, format } => {
+            env_snapshot::diff(workspace, &snapshot, against, &format).await
+        }
+        EnvCommands::List => tool_versions::list(workspace).await,
+        EnvCommands::Install { tools, force } => tool_versions::install(workspace, &tools, force).await,
+        EnvCommands::Shell { shell } => tool_versions::shell(workspace, shell.as_deref()).await,
+        EnvCommands::Direnv => tool_versions::direnv(workspace).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_env_file() {
+        let vars = parse_env_file("# staging\nexport DATABASE_URL=\"postgres://db/app\"\nDB_PASSWORD=secret:staging-db\nEMPTY=\n").unwrap();
+        assert_eq!(vars["DATABASE_URL"], "postgres://db/app");
+        assert_eq!(vars["DB_PASSWORD"], "secret:staging-db");
+        assert_eq!(vars["EMPTY"], "");
+        assert!(parse_env_file("not a pair").is_err());
+        assert!(parse_env_file("BAD-NAME=1").is_err());
+    }
+}
@@ -0,0 +1,394 @@
+//! Reproducible environment capture for RCM
+//!
+//! `rcm env snapshot` records toolchain versions, pinned interpreters, system
+//! packages, relevant environment variables and lockfile hashes into a JSON
+//! descriptor with a content digest, optionally signed with an SSH key.
+//! `rcm env diff` compares a descriptor with the current machine or another one.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tabled::{Table, Tabled};
+use tokio::fs;
+use crate::lockfile::LockManager;
+use crate::redact;
+use crate::toolchain::{self, ToolchainPins};
+use crate::util;
+use crate::workspace::Workspace;
+
+/// Descriptor format version
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// SSH signature namespace for descriptors
+const SIGNATURE_NAMESPACE: &str = "rcm-env";
+
+/// Trusted signers for `rcm env diff`, in ssh-keygen allowed_signers format
+pub const ALLOWED_SIGNERS: &str = ".rcm/allowed_signers";
+
+/// Tools whose versions are recorded, with the argument printing the version
+const TOOLS: &[(&str, &str)] = &[
+    ("rustc", "--version"),
+    ("cargo", "--version"),
+    ("node", "--version"),
+    ("npm", "--version"),
+    ("pnpm", "--version"),
+    ("yarn", "--version"),
+    ("bun", "--version"),
+    ("php", "--version"),
+    ("composer", "--version"),
+    ("python3", "--version"),
+    ("go", "version"),
+    ("java", "-version"),
+    ("docker", "--version"),
+    ("git", "--version"),
+];
+
+/// Environment variables that change build behaviour
+const ENV_PREFIXES: &[&str] = &[
+    "PATH", "RUST", "CARGO", "NODE", "NPM_", "npm_config_", "YARN_", "PNPM_", "BUN_", "PHP", "COMPOSER",
+    "PYTHON", "VIRTUAL_ENV", "GO", "JAVA_HOME", "CC", "CXX", "CFLAGS", "LDFLAGS", "PKG_CONFIG", "LANG", "LC_",
+    "TZ", "RCM_",
+];
+
+/// Lockfiles whose hashes are recorded
+const LOCKFILES: &[&str] = &[
+    "rcm.lock", "Cargo.lock", "package-lock.json", "yarn.lock", "pnpm-lock.yaml", "bun.lockb", "composer.lock",
+];
+
+/// Host keys that are expected to differ between developers
+const PERSONAL_KEYS: &[&str] = &["hostname", "user"];
+
+/// An environment descriptor
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnvSnapshot {
+    pub version: u32,
+    pub created_at: String,
+    pub rcm_version: String,
+    /// Identity used for the SSH signature (git user.email or $USER)
+    pub signer: Option<String>,
+    pub host: BTreeMap<String, String>,
+    pub toolchains: BTreeMap<String, String>,
+    /// Interpreters pinned in .rcm/toolchain.toml
+    pub pins: BTreeMap<String, String>,
+    pub system_packages: BTreeMap<String, String>,
+    /// Relevant variables with secrets redacted
+    pub env: BTreeMap<String, String>,
+    /// SHA-256 of each lockfile
+    pub lockfiles: BTreeMap<String, String>,
+    /// SHA-256 over all other fields
+    #[serde(default)]
+    pub digest: String,
+}
+
+/// One difference between two descriptors
+#[derive(Debug, Clone, PartialEq, Tabled)]
+pub struct EnvDifference {
+    #[tabled(rename = "Section")]
+    pub section: String,
+    #[tabled(rename = "Key")]
+    pub key: String,
+    #[tabled(rename = "Snapshot")]
+    pub left: String,
+    #[tabled(rename = "Current")]
+    pub right: String,
+}
+
+impl EnvSnapshot {
+    /// Digest of the descriptor with the digest field cleared
+    pub fn compute_digest(&self) -> String {
+        let mut copy = self.clone();
+        copy.digest.clear();
+        // BTreeMaps serialize in key order, so the JSON is canonical
+        let json = serde_json::to_string(&copy).unwrap_or_default();
+        format!("{:x}", Sha256::digest(json.as_bytes()))
+    }
+
+    pub fn verify_digest(&self) -> bool {
+        self.digest == self.compute_digest()
+    }
+
+    fn sections(&self) -> Vec<(&'static str, &BTreeMap<String, String>)> {
+        vec![
+            ("host", &self.host),
+            ("toolchain", &self.toolchains),
+            ("pin", &self.pins),
+            ("system", &self.system_packages),
+            ("env", &self.env),
+            ("lockfile", &self.lockfiles),
+        ]
+    }
+}
+
+/// Differences between two descriptors, ignoring personal host details
+pub fn diff_snapshots(left: &EnvSnapshot, right: &EnvSnapshot) -> Vec<EnvDifference> {
+    let mut differences = Vec::new();
+    for ((section, a), (_, b)) in left.sections().into_iter().zip(right.sections()) {
+        let keys: std::collections::BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+        for key in keys {
+            if section == "host" && PERSONAL_KEYS.contains(&key.as_str()) {
+                continue;
+            }
+            let (l, r) = (a.get(key), b.get(key));
+            if l != r {
+                differences.push(EnvDifference {
+                    section: section.to_string(),
+                    key: key.clone(),
+                    left: l.cloned().unwrap_or_else(|| "(absent)".to_string()),
+                    right: r.cloned().unwrap_or_else(|| "(absent)".to_string()),
+                });
+            }
+        }
+    }
+    differences
+}
+
+/// First line a tool prints for its version (java uses stderr)
+fn tool_version(root: &Path, tool: &str, arg: &str) -> Option<String> {
+    let mut cmd = Command::new(tool);
+    cmd.arg(arg).current_dir(root);
+    if let Some(path) = toolchain::shim_path(root) {
+        cmd.env("PATH", path);
+    }
+    let output = cmd.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+    String::from_utf8_lossy(&text).lines().map(str::trim).find(|l| !l.is_empty()).map(str::to_string)
+}
+
+fn signer() -> Option<String> {
+    Command::new("git")
+        .args(["config", "user.email"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|s| !s.is_empty())
+        .or_else(|| std::env::var("USER").ok())
+}
+
+/// Capture the current machine
+pub async fn capture(workspace: &Workspace) -> Result<EnvSnapshot> {
+    let root = workspace.root();
+    let mut snapshot = EnvSnapshot {
+        version: SNAPSHOT_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        rcm_version: env!("CARGO_PKG_VERSION").to_string(),
+        signer: signer(),
+        ..Default::default()
+    };
+
+    snapshot.host.insert("os".into(), std::env::consts::OS.into());
+    snapshot.host.insert("arch".into(), std::env::consts::ARCH.into());
+    snapshot.host.insert("family".into(), std::env::consts::FAMILY.into());
+    if let Some(kernel) = tool_version(root, "uname", "-r") {
+        snapshot.host.insert("kernel".into(), kernel);
+    }
+    if let Some(hostname) = std::env::var("HOSTNAME").ok().or_else(|| tool_version(root, "hostname", "-s")) {
+        snapshot.host.insert("hostname".into(), hostname);
+    }
+    if let Ok(user) = std::env::var("USER") {
+        snapshot.host.insert("user".into(), user);
+    }
+
+    for (tool, arg) in TOOLS {
+        if util::command_exists(tool).await {
+            if let Some(version) = tool_version(root, tool, arg) {
+                snapshot.toolchains.insert(tool.to_string(), version);
+            }
+        }
+    }
+
+    for (tool, pin) in ToolchainPins::load(root).await?.tools {
+        snapshot.pins.insert(tool, format!("{} ({})", pin.version, pin.path.display()));
+    }
+
+    if workspace.has_manager("system") {
+        match LockManager::new(root).collect(workspace, &["system".to_string()]).await {
+            Ok(lock) => {
+                for pkg in lock.packages {
+                    snapshot.system_packages.insert(pkg.name, pkg.version);
+                }
+            }
+            Err(e) => tracing::warn!("Could not read system packages: {}", e),
+        }
+    }
+
+    for (key, value) in std::env::vars() {
+        if ENV_PREFIXES.iter().any(|p| key.starts_with(p)) {
+            snapshot.env.insert(key, redact::redact(&value));
+        }
+    }
+
+    for name in LOCKFILES {
+        let path = root.join(name);
+        if path.exists() {
+            snapshot.lockfiles.insert(name.to_string(), util::get_file_hash(&path).await?);
+        }
+    }
+
+    snapshot.digest = snapshot.compute_digest();
+    Ok(snapshot)
+}
+
+pub async fn load(path: &Path) -> Result<EnvSnapshot> {
+    let content = fs::read_to_string(path).await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse environment snapshot {}", path.display()))
+}
+
+fn signature_path(path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.sig", path.display()))
+}
+
+/// Sign a descriptor with `ssh-keygen -Y sign`, writing `<file>.sig`
+fn sign(path: &Path, key: &Path) -> Result<()> {
+    let mut cmd = Command::new("ssh-keygen");
+    cmd.args(["-Y", "sign", "-n", SIGNATURE_NAMESPACE, "-f"]).arg(key).arg(path);
+    if util::skip_in_dry_run(&cmd) {
+        return Ok(());
+    }
+    let output = cmd.output().context("ssh-keygen not found; install OpenSSH to sign snapshots")?;
+    if !output.status.success() {
+        return Err(anyhow!("Signing failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+/// Verify `<file>.sig` against the workspace's allowed signers
+///
+/// Returns `None` when there is nothing to verify against.
+fn verify_signature(root: &Path, path: &Path, snapshot: &EnvSnapshot) -> Result<Option<bool>> {
+    let signature = signature_path(path);
+    let allowed = root.join(ALLOWED_SIGNERS);
+    let Some(signer) = &snapshot.signer else { return Ok(None) };
+    if !signature.exists() || !allowed.exists() {
+        return Ok(None);
+    }
+    let output = Command::new("ssh-keygen")
+        .args(["-Y", "verify", "-n", SIGNATURE_NAMESPACE, "-I", signer, "-f"])
+        .arg(&allowed)
+        .arg("-s")
+        .arg(&signature)
+        .stdin(std::fs::File::open(path)?)
+        .output()
+        .context("ssh-keygen not found; install OpenSSH to verify snapshots")?;
+    Ok(Some(output.status.success()))
+}
+
+/// Hint for resolving differences in a section
+fn hint(section: &str) -> &'static str {
+    match section {
+        "toolchain" => "install matching versions or pin them (rcm ppm use-php, rcm npm use, rust-toolchain.toml)",
+        "pin" => "run rcm ensure to apply the workspace toolchain pins",
+        "system" => "run rcm ensure to install the recorded system packages",
+        "env" => "compare shell profiles; these variables change build behaviour",
+        "lockfile" => "dependencies were resolved differently; pull the latest lockfiles and run rcm ensure",
+        _ => "different platform; some differences are expected",
+    }
+}
+
+/// Write a descriptor of the current machine
+pub async fn snapshot(workspace: &Workspace, out: &Path, sign_with: Option<PathBuf>) -> Result<()> {
+    println!("{}", style("📸 Capturing environment...").cyan().bold());
+    let snapshot = capture(workspace).await?;
+    util::write_file(out, serde_json::to_string_pretty(&snapshot)?).await?;
+
+    if let Some(key) = sign_with {
+        sign(out, &key)?;
+        println!("{}", style(format!("🔏 Signed as {}", snapshot.signer.as_deref().unwrap_or("unknown"))).green());
+    }
+
+    println!(
+        "{}",
+        style(format!(
+            "✅ {} toolchains, {} system packages, {} variables, {} lockfiles written to {}",
+            snapshot.toolchains.len(),
+            snapshot.system_packages.len(),
+            snapshot.env.len(),
+            snapshot.lockfiles.len(),
+            out.display()
+        ))
+        .green()
+        .bold()
+    );
+    Ok(())
+}
+
+/// Explain how a descriptor differs from this machine or from another descriptor
+pub async fn diff(workspace: &Workspace, snapshot_path: &Path, against: Option<PathBuf>, format: &str) -> Result<()> {
+    let root = workspace.root();
+    let left = load(snapshot_path).await?;
+    if !left.verify_digest() {
+        println!("{} {} was modified after it was captured (digest mismatch)", style("⚠️").yellow(), snapshot_path.display());
+    }
+    match verify_signature(root, snapshot_path, &left)? {
+        Some(true) => println!("{} signature by {} verified", style("🔏").green(), left.signer.as_deref().unwrap_or("")),
+        Some(false) => return Err(anyhow!("Signature of {} does not verify against {}", snapshot_path.display(), ALLOWED_SIGNERS)),
+        None => {}
+    }
+
+    let right = match &against {
+        Some(path) => load(path).await?,
+        None => capture(workspace).await?,
+    };
+    let differences = diff_snapshots(&left, &right);
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&differences.iter().map(|d| serde_json::json!({
+            "section": d.section, "key": d.key, "snapshot": d.left, "current": d.right,
+        })).collect::<Vec<_>>())?),
+        "table" => {
+            if differences.is_empty() {
+                println!("{}", style("✅ Environments match").green().bold());
+                return Ok(());
+            }
+            println!("{}", Table::new(&differences));
+            println!();
+            println!("{}", style("💡 What to check").bold());
+            let mut sections: Vec<&str> = differences.iter().map(|d| d.section.as_str()).collect();
+            sections.dedup();
+            for section in sections {
+                println!("  • {}: {}", style(section).cyan(), hint(section));
+            }
+        }
+        _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_and_diff() {
+        let mut left = EnvSnapshot { version: SNAPSHOT_VERSION, ..Default::default() };
+        left.host.insert("os".into(), "linux".into());
+        left.host.insert("hostname".into(), "alice-laptop".into());
+        left.toolchains.insert("node".into(), "v20.11.0".into());
+        left.lockfiles.insert("Cargo.lock".into(), "aaa".into());
+        left.digest = left.compute_digest();
+        assert!(left.verify_digest());
+
+        let mut right = left.clone();
+        right.host.insert("hostname".into(), "bob-desktop".into());
+        right.toolchains.insert("node".into(), "v18.19.0".into());
+        right.lockfiles.remove("Cargo.lock");
+        right.env.insert("NODE_OPTIONS".into(), "--max-old-space-size=4096".into());
+        assert!(!right.verify_digest());
+
+        let differences = diff_snapshots(&left, &right);
+        assert_eq!(
+            differences.iter().map(|d| (d.section.as_str(), d.key.as_str())).collect::<Vec<_>>(),
+            vec![("toolchain", "node"), ("env", "NODE_OPTIONS"), ("lockfile", "Cargo.lock")]
+        );
+        assert_eq!(differences[2].right, "(absent)");
+    }
+}
@@ -0,0 +1,416 @@
+//! Event webhooks for RCM
+//!
+//! Every entry under `webhooks` in the config receives an HTTP POST when
+//! something other systems track changes in a workspace, so ChatOps bots and
+//! inventories can stay in sync:
+//!
+//! ```json
+//! "webhooks": [
+//!   { "name": "inventory", "url": "https://cmdb.example.com/hooks/rcm",
+//!     "secret": "secret:rcm-webhook", "events": ["dependency.*", "model.deployed"] }
+//! ]
+//! ```
+//!
+//! The body is JSON: `{ id, event, timestamp, rcm_version, workspace: { name }, data }`.
+//! Requests carry `X-RCM-Event`, `X-RCM-Delivery` (the `id`) and, when a secret
+//! is set, `X-RCM-Signature: sha256=<hex HMAC-SHA256 of the body>`. Receivers
+//! should compare the signature in constant time and may use the timestamp in
+//! the body to reject replays. Hooks are called concurrently and retried within
+//! a few seconds; a hook that stays unreachable is logged and never fails or
+//! holds up the command that triggered it.
+//!
+//! `dependency.*` events cover `rcm add`, `rcm remove`, `rcm update` and
+//! `rcm ensure`: packages that appear in or drop out of the native lockfiles
+//! are reported, and a version change is an `added` event with `previous_version`.
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use console::style;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use tabled::{Table, Tabled};
+use tokio::task::JoinSet;
+use tokio::time::{sleep, timeout, Duration};
+use crate::config::{Config, WebhookConfig};
+use crate::lockfile::{LockManager, LockedPackage};
+use crate::redact;
+use crate::secrets;
+use crate::util;
+use crate::workspace::Workspace;
+
+pub const DEPENDENCY_ADDED: &str = "dependency.added";
+pub const DEPENDENCY_REMOVED: &str = "dependency.removed";
+pub const MODEL_DEPLOYED: &str = "model.deployed";
+pub const MODEL_STOPPED: &str = "model.stopped";
+pub const AUDIT_FINDING: &str = "audit.finding";
+/// Sent only by `rcm webhook test`
+pub const PING: &str = "ping";
+
+/// Events a webhook can subscribe to
+pub const EVENTS: &[&str] = &[DEPENDENCY_ADDED, DEPENDENCY_REMOVED, MODEL_DEPLOYED, MODEL_STOPPED, AUDIT_FINDING];
+
+const ATTEMPTS: u32 = 3;
+const TIMEOUT: Duration = Duration::from_secs(3);
+/// Longest a command waits for all of its hooks together
+const DEADLINE: Duration = Duration::from_secs(5);
+
+/// Managers whose resolved packages can be compared before and after a command
+const TRACKED: &[&str] = &["cargo", "npm", "composer", "go", "system"];
+
+/// Whether a hook subscribed with `filters` wants `event`; `ping` always goes through
+pub fn matches(filters: &[String], event: &str) -> bool {
+    if filters.is_empty() || event == PING {
+        return true;
+    }
+    filters.iter().any(|filter| match filter.strip_suffix(".*") {
+        Some(prefix) => event.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('.')),
+        None => filter == "*" || filter == event,
+    })
+}
+
+/// Reject event filters that could never match
+pub fn check_filters(filters: &[String]) -> Result<()> {
+    for filter in filters {
+        let known = filter == "*" || EVENTS.iter().any(|event| matches(std::slice::from_ref(filter), event));
+        if !known {
+            return Err(anyhow!("Unknown webhook event '{}'; use one of {} or a prefix like dependency.*", filter, EVENTS.join(", ")));
+        }
+    }
+    Ok(())
+}
+
+/// `sha256=<hex>` HMAC of `body` under `secret`
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+/// A `secret:` reference or the value itself
+async fn resolve(value: &str) -> Result<String> {
+    let resolved = match secrets::secret_ref(value) {
+        Some(name) => secrets::get_secret(name).await?,
+        None => value.to_string(),
+    };
+    redact::register(&resolved);
+    Ok(resolved)
+}
+
+/// Name shown for a hook in output, never the full URL
+fn label(hook: &WebhookConfig) -> String {
+    if let Some(name) = &hook.name {
+        return name.clone();
+    }
+    if hook.url.starts_with("secret:") {
+        return hook.url.clone();
+    }
+    reqwest::Url::parse(&hook.url).ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| "webhook".to_string())
+}
+
+/// The JSON body sent for `event`
+fn payload(root: &Path, event: &str, data: Value) -> Value {
+    let name = root.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    json!({
+        "id": uuid::Uuid::new_v4().to_string(),
+        "event": event,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "rcm_version": env!("CARGO_PKG_VERSION"),
+        "workspace": { "name": name },
+        "data": data,
+    })
+}
+
+/// POST `body` to one hook, retrying on network errors and 5xx responses
+async fn deliver(client: &reqwest::Client, hook: &WebhookConfig, event: &str, delivery: &str, body: &[u8]) -> Result<()> {
+    let url = resolve(&hook.url).await?;
+    let signature = match &hook.secret {
+        Some(secret) => Some(sign(resolve(secret).await?.as_bytes(), body)),
+        None => None,
+    };
+
+    let mut last_error = None;
+    for attempt in 1..=ATTEMPTS {
+        if attempt > 1 {
+            sleep(Duration::from_millis(250 << (attempt - 1))).await;
+        }
+        let mut request = client.post(&url)
+            .header("Content-Type", "application/json")
+            .header("X-RCM-Event", event)
+            .header("X-RCM-Delivery", delivery)
+            .body(body.to_vec());
+        if let Some(signature) = &signature {
+            request = request.header("X-RCM-Signature", signature);
+        }
+        match request.send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) if !response.status().is_server_error() => {
+                return Err(anyhow!("{} returned {}", label(hook), response.status()));
+            }
+            Ok(response) => last_error = Some(anyhow!("{} returned {}", label(hook), response.status())),
+            Err(e) => last_error = Some(anyhow::Error::new(e).context(format!("Failed to reach {}", label(hook)))),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| anyhow!("Failed to deliver to {}", label(hook))))
+}
+
+/// Send `event` to every subscribed hook, returning each hook's outcome
+pub async fn send(root: &Path, hooks: &[WebhookConfig], event: &str, data: Value) -> Result<Vec<(String, Result<()>)>> {
+    let targets: Vec<&WebhookConfig> = hooks.iter().filter(|hook| matches(&hook.events, event)).collect();
+    if targets.is_empty() {
+        return Ok(Vec::new());
+    }
+    let body = payload(root, event, data);
+    if util::is_dry_run() {
+        for hook in &targets {
+            println!("[dry-run] send {} webhook to {}", event, label(hook));
+        }
+        return Ok(Vec::new());
+    }
+
+    let delivery: Arc<str> = body["id"].as_str().unwrap_or_default().into();
+    let body: Arc<[u8]> = serde_json::to_vec(&body)?.into();
+    let event: Arc<str> = event.into();
+    let client = reqwest::Client::builder().timeout(TIMEOUT).build()
+        .context("Failed to create HTTP client")?;
+    let mut tasks = JoinSet::new();
+    for (index, hook) in targets.into_iter().enumerate() {
+        let (client, hook, event, delivery, body) = (client.clone(), hook.clone(), event.clone(), delivery.clone(), body.clone());
+        tasks.spawn(async move {
+            let result = match timeout(DEADLINE, deliver(&client, &hook, &event, &delivery, &body)).await {
+                Ok(result) => result,
+                Err(_) => Err(anyhow!("{} did not answer within {}s", label(&hook), DEADLINE.as_secs())),
+            };
+            (index, label(&hook), result)
+        });
+    }
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        results.push(joined?);
+    }
+    results.sort_by_key(|(index, _, _)| *index);
+    Ok(results.into_iter().map(|(_, hook, result)| (hook, result)).collect())
+}
+
+/// Notify the configured hooks of `event`; failures are logged, never returned
+pub async fn emit(root: &Path, event: &str, data: Value) {
+    let config = Config::load(None).await.unwrap_or_default();
+    notify(root, &config.webhooks, event, data).await;
+}
+
+async fn notify(root: &Path, hooks: &[WebhookConfig], event: &str, data: Value) {
+    if hooks.is_empty() {
+        return;
+    }
+    match send(root, hooks, event, data).await {
+        Ok(results) => {
+            for (hook, result) in results {
+                if let Err(e) = result {
+                    tracing::warn!("Webhook {} for {} failed: {}", hook, event, redact::redact_error(&e));
+                }
+            }
+        }
+        Err(e) => tracing::warn!("Webhooks for {} failed: {}", event, redact::redact_error(&e)),
+    }
+}
+
+/// Resolved packages before a command runs, to report what it added and removed
+pub struct DependencySnapshot {
+    hooks: Vec<WebhookConfig>,
+    managers: Vec<String>,
+    packages: Vec<LockedPackage>,
+}
+
+/// Record the resolved packages of `managers`; `None` when no hook wants dependency events
+pub async fn snapshot(workspace: &Workspace, managers: &[String]) -> Option<DependencySnapshot> {
+    if util::is_dry_run() {
+        return None;
+    }
+    let config = Config::load(None).await.unwrap_or_default();
+    let hooks: Vec<WebhookConfig> = config.webhooks.into_iter()
+        .filter(|hook| matches(&hook.events, DEPENDENCY_ADDED) || matches(&hook.events, DEPENDENCY_REMOVED))
+        .collect();
+    if hooks.is_empty() {
+        return None;
+    }
+    let managers: Vec<String> = managers.iter().filter(|m| TRACKED.contains(&m.as_str())).cloned().collect();
+    let packages = resolved(workspace, &managers).await;
+    Some(DependencySnapshot { hooks, managers, packages })
+}
+
+async fn resolved(workspace: &Workspace, managers: &[String]) -> Vec<LockedPackage> {
+    match LockManager::new(workspace.root()).collect(workspace, managers).await {
+        Ok(lock) => lock.packages,
+        Err(e) => {
+            tracing::debug!("Cannot read resolved packages for webhooks: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+impl DependencySnapshot {
+    /// Send `dependency.added`/`dependency.removed` for everything that changed since the snapshot
+    pub async fn emit_changes(self, workspace: &Workspace) {
+        let after = resolved(workspace, &self.managers).await;
+        for (event, data) in changes(&self.packages, &after) {
+            notify(workspace.root(), &self.hooks, event, data).await;
+        }
+    }
+}
+
+/// Events describing the difference between two package lists
+pub fn changes(before: &[LockedPackage], after: &[LockedPackage]) -> Vec<(&'static str, Value)> {
+    let key = |p: &LockedPackage| (p.manager.clone(), p.name.clone());
+    let before: BTreeMap<_, _> = before.iter().map(|p| (key(p), p)).collect();
+    let after: BTreeMap<_, _> = after.iter().map(|p| (key(p), p)).collect();
+    let mut events = Vec::new();
+    for (k, old) in &before {
+        if !after.contains_key(k) {
+            events.push((DEPENDENCY_REMOVED, json!({ "name": old.name, "version": old.version, "manager": old.manager })));
+        }
+    }
+    for (k, new) in &after {
+        let mut data = json!({ "name": new.name, "version": new.version, "manager": new.manager, "dev": new.dev });
+        match before.get(k) {
+            None => {}
+            Some(old) if old.version != new.version => data["previous_version"] = json!(old.version),
+            Some(_) => continue,
+        }
+        events.push((DEPENDENCY_ADDED, data));
+    }
+    events
+}
+
+#[derive(Subcommand)]
+pub enum WebhookCommands {
+    /// Show configured webhooks and the events they receive
+    List,
+    /// Send a test event to the configured webhooks
+    Test {
+        /// Event to send (default: ping)
+        #[arg(long, default_value = PING)]
+        event: String,
+        /// Only send to the webhook with this name
+        #[arg(long)]
+        name: Option<String>,
+    },
+}
+
+#[derive(Tabled)]
+struct WebhookRow {
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Events")]
+    events: String,
+    #[tabled(rename = "Signed")]
+    signed: String,
+}
+
+/// Entry point for `rcm webhook`
+pub async fn handle_command(workspace: &Workspace, config: &Config, cmd: WebhookCommands) -> Result<()> {
+    if config.webhooks.is_empty() {
+        println!("{}", style("No webhooks configured").yellow());
+        println!("Add one with: rcm config set webhooks '[{{\"url\": \"https://...\", \"secret\": \"secret:rcm-webhook\"}}]'");
+        return Ok(());
+    }
+    match cmd {
+        WebhookCommands::List => {
+            let rows: Vec<WebhookRow> = config.webhooks.iter().map(|hook| WebhookRow {
+                name: label(hook),
+                events: if hook.events.is_empty() { "all".to_string() } else { hook.events.join(", ") },
+                signed: if hook.secret.is_some() { "yes" } else { "no" }.to_string(),
+            }).collect();
+            println!("{}", Table::new(&rows));
+            Ok(())
+        }
+        WebhookCommands::Test { event, name } => {
+            if event != PING && !EVENTS.contains(&event.as_str()) {
+                return Err(anyhow!("Unknown event '{}'; use {} or one of {}", event, PING, EVENTS.join(", ")));
+            }
+            let hooks: Vec<WebhookConfig> = config.webhooks.iter()
+                .filter(|hook| name.is_none() || hook.name == name)
+                .cloned()
+                .collect();
+            if hooks.is_empty() {
+                return Err(anyhow!("No webhook named '{}'", name.unwrap_or_default()));
+            }
+            let data = json!({ "test": true });
+            let results = send(workspace.root(), &hooks, &event, data).await?;
+            if results.is_empty() && !util::is_dry_run() {
+                println!("{}", style(format!("No webhook subscribes to {}", event)).yellow());
+            }
+            let mut failed = 0;
+            for (hook, result) in results {
+                match result {
+                    Ok(()) => println!("{} {} accepted {}", style("✓").green(), hook, event),
+                    Err(e) => {
+                        failed += 1;
+                        println!("{} {}: {}", style("✗").red(), hook, redact::redact_error(&e));
+                    }
+                }
+            }
+            if failed > 0 {
+                return Err(anyhow!("{} webhook(s) failed", failed));
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let filters = vec!["dependency.*".to_string(), "model.deployed".to_string()];
+        assert!(matches(&filters, DEPENDENCY_ADDED));
+        assert!(matches(&filters, DEPENDENCY_REMOVED));
+        assert!(matches(&filters, MODEL_DEPLOYED));
+        assert!(!matches(&filters, MODEL_STOPPED));
+        assert!(matches(&filters, PING));
+        assert!(matches(&[], AUDIT_FINDING));
+        assert!(!matches(&["dep.*".to_string()], DEPENDENCY_ADDED));
+    }
+
+    #[test]
+    fn test_check_filters() {
+        assert!(check_filters(&["model.*".to_string(), AUDIT_FINDING.to_string()]).is_ok());
+        assert!(check_filters(&["dependency.updated".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_changes() {
+        let pkg = |name: &str, version: &str| LockedPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            manager: "npm".to_string(),
+            source: None,
+            checksum: None,
+            dev: false,
+        };
+        let before = vec![pkg("lodash", "4.17.20"), pkg("left-pad", "1.3.0"), pkg("react", "18.2.0")];
+        let after = vec![pkg("lodash", "4.17.21"), pkg("react", "18.2.0"), pkg("zod", "3.23.0")];
+        let events = changes(&before, &after);
+        let summary: Vec<(&str, &str)> = events.iter()
+            .map(|(event, data)| (*event, data["name"].as_str().unwrap()))
+            .collect();
+        assert_eq!(summary, vec![(DEPENDENCY_REMOVED, "left-pad"), (DEPENDENCY_ADDED, "lodash"), (DEPENDENCY_ADDED, "zod")]);
+        assert_eq!(events[1].1["previous_version"], "4.17.20");
+        assert!(events[2].1.get("previous_version").is_none());
+    }
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign(b"Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
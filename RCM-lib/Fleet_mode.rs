@@ -0,0 +1,532 @@
+//! Fleet mode (`rcm fleet apply <plan>`)
+//!
+//! Runs one plan on every host in an inventory over SSH, a few hosts at a
+//! time. A plan is a bootstrap manifest (system packages, toolchains, tools,
+//! models) plus LET targets whose specs are copied from this workspace:
+//!
+//! ```toml
+//! name = "gpu-nodes"
+//! system = ["ffmpeg"]
+//! let = ["cuda", "vllm"]
+//!
+//! [[models]]
+//! name = "llama3"
+//! ```
+//!
+//! The inventory (`fleet.toml` by default) lists the hosts:
+//!
+//! ```toml
+//! concurrency = 4
+//!
+//! [[hosts]]
+//! name = "gpu-1"
+//! address = "ubuntu@10.0.0.5:2222"
+//! groups = ["gpu"]
+//! parallel = 2
+//! ```
+//!
+//! A `config_bundle` that is a local path is copied to each host along with the
+//! plan. A host that fails doesn't stop the others; each host's output is kept in
+//! `.rcm/fleet/logs/<host>.log` and the run ends with a per-host summary.
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use console::style;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tabled::{Table, Tabled};
+use tokio::fs;
+use tokio::process::Command as AsyncCommand;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use crate::bootstrap::{self, BootstrapManifest};
+use crate::commands::letcmd::LetExecutor;
+use crate::remote::{self, RemoteTarget};
+use crate::workspace::Workspace;
+use crate::{redact, util};
+
+/// Default inventory, relative to the workspace root
+pub const INVENTORY_FILE: &str = "fleet.toml";
+
+/// Per-host output of the last run
+const LOG_DIR: &str = ".rcm/fleet/logs";
+
+/// Hosts worked on at once when the inventory doesn't say
+const DEFAULT_CONCURRENCY: usize = 4;
+
+#[derive(Subcommand)]
+pub enum FleetCommands {
+    /// Apply a plan to every host in the inventory
+    Apply {
+        /// Plan file (TOML, or JSON by extension)
+        plan: PathBuf,
+        /// Inventory of hosts
+        #[arg(long, default_value = INVENTORY_FILE)]
+        inventory: PathBuf,
+        /// Only these hosts (by name)
+        #[arg(long, value_delimiter = ',')]
+        hosts: Option<Vec<String>>,
+        /// Only hosts in this group
+        #[arg(long)]
+        group: Option<String>,
+        /// Hosts worked on at once (overrides the inventory)
+        #[arg(long)]
+        concurrency: Option<usize>,
+        /// Don't start more hosts after the first failure
+        #[arg(long)]
+        fail_fast: bool,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Inventory {
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+    #[serde(default)]
+    pub hosts: Vec<Host>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Host {
+    pub name: String,
+    /// `user@host[:port]` or an ssh config alias
+    pub address: String,
+    #[serde(default)]
+    pub groups: Vec<String>,
+    /// LET actions run at once on this host
+    #[serde(default)]
+    pub parallel: Option<usize>,
+}
+
+impl Inventory {
+    pub fn parse(content: &str) -> Result<Self> {
+        let inventory: Self = toml::from_str(content).context("Invalid fleet inventory")?;
+        let mut seen = std::collections::HashSet::new();
+        for host in &inventory.hosts {
+            if !seen.insert(host.name.as_str()) {
+                return Err(anyhow!("Host '{}' is listed twice in the inventory", host.name));
+            }
+            RemoteTarget::parse(&host.address)
+                .with_context(|| format!("Invalid address for host '{}'", host.name))?;
+        }
+        Ok(inventory)
+    }
+
+    /// Hosts matching the name and group filters, in inventory order
+    pub fn select(&self, names: Option<&[String]>, group: Option<&str>) -> Result<Vec<Host>> {
+        if let Some(unknown) = names.into_iter().flatten().find(|n| !self.hosts.iter().any(|h| &h.name == *n)) {
+            return Err(anyhow!("Host '{}' is not in the inventory", unknown));
+        }
+        Ok(self.hosts.iter()
+            .filter(|h| names.map_or(true, |names| names.contains(&h.name)))
+            .filter(|h| group.map_or(true, |g| h.groups.iter().any(|hg| hg == g)))
+            .cloned()
+            .collect())
+    }
+}
+
+/// What every host should end up with
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FleetPlan {
+    #[serde(flatten)]
+    pub manifest: BootstrapManifest,
+    /// LET targets applied after the manifest, in order
+    #[serde(default, rename = "let")]
+    pub let_targets: Vec<String>,
+}
+
+impl FleetPlan {
+    fn has_manifest(&self) -> bool {
+        let m = &self.manifest;
+        m.config_bundle.is_some() || !m.system.is_empty() || !m.toolchains.is_empty() || !m.tools.is_empty() || !m.models.is_empty()
+    }
+}
+
+/// Files copied to each host and the rcm invocations run there
+#[derive(Debug)]
+pub struct HostJob {
+    /// Remote directory, relative to the home directory
+    pub dir: String,
+    pub files: Vec<(String, Vec<u8>)>,
+    /// Label and rcm arguments of each step, run in order
+    pub steps: Vec<(String, Vec<String>)>,
+}
+
+/// Lay out the plan for hosts; `specs` are the LET spec files to copy and
+/// `bundle` a local config bundle (file name and content) to copy with the manifest
+pub fn host_job(plan: &FleetPlan, specs: Vec<(String, Vec<u8>)>, bundle: Option<&(String, Vec<u8>)>, parallel: Option<usize>) -> Result<HostJob> {
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec_pretty(plan)?);
+    if let Some((_, data)) = bundle {
+        hasher.update(data);
+    }
+    let digest = format!("{:x}", hasher.finalize());
+    let dir = format!(".rcm/fleet/{}", &digest[..12]);
+
+    let mut files = Vec::new();
+    let mut steps = Vec::new();
+    if plan.has_manifest() {
+        let mut manifest = serde_json::to_value(&plan.manifest)?;
+        if let Some((name, data)) = bundle {
+            // The host reads the copy; a pin on the local path still applies to it
+            let remote = format!("{}/{}", dir, name);
+            let pin = plan.manifest.config_bundle.as_deref().and_then(|s| bootstrap::split_pin(s).1);
+            manifest["config_bundle"] = Value::String(match pin {
+                Some(pin) => format!("{}#sha256={}", remote, pin),
+                None => remote.clone(),
+            });
+            files.push((remote, data.clone()));
+        }
+        let path = format!("{}/bootstrap.json", dir);
+        files.push((path.clone(), serde_json::to_vec_pretty(&manifest)?));
+        steps.push(("bootstrap".to_string(), vec!["bootstrap".to_string(), path, "--keep-going".to_string()]));
+    }
+    for (name, content) in specs {
+        files.push((format!("{}/.rcm/let/{}.json", dir, name), content));
+    }
+    for target in &plan.let_targets {
+        let mut args = vec!["-w".to_string(), dir.clone(), "let".to_string(), target.clone(), "--apply".to_string()];
+        if let Some(parallel) = parallel {
+            args.extend(["--parallel".to_string(), parallel.to_string()]);
+        }
+        steps.push((format!("let {}", target), args));
+    }
+    if steps.is_empty() {
+        return Err(anyhow!("The plan has nothing to apply; add system packages, models or `let` targets"));
+    }
+    Ok(HostJob { dir, files, steps })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HostStatus {
+    Ok,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HostResult {
+    pub host: String,
+    pub address: String,
+    pub status: HostStatus,
+    pub steps_done: usize,
+    pub steps: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed_step: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    pub log: PathBuf,
+}
+
+#[derive(Tabled)]
+struct HostRow {
+    #[tabled(rename = "Host")]
+    host: String,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "Steps")]
+    steps: String,
+    #[tabled(rename = "Time")]
+    time: String,
+    #[tabled(rename = "Error")]
+    error: String,
+}
+
+async fn load_plan(path: &Path) -> Result<FleetPlan> {
+    let content = fs::read_to_string(path).await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let plan: FleetPlan = if path.extension().is_some_and(|e| e == "json") {
+        serde_json::from_str(&content).with_context(|| format!("Invalid fleet plan {}", path.display()))?
+    } else {
+        toml::from_str(&content).with_context(|| format!("Invalid fleet plan {}", path.display()))?
+    };
+    // Reject what the remote bootstrap would, before touching any host
+    bootstrap::plan(&plan.manifest)?;
+    Ok(plan)
+}
+
+/// The plan's config bundle when it is a file on this machine, which hosts can't read
+async fn local_bundle(root: &Path, plan: &FleetPlan) -> Result<Option<(String, Vec<u8>)>> {
+    let Some(source) = &plan.manifest.config_bundle else {
+        return Ok(None);
+    };
+    let (location, _) = bootstrap::split_pin(source);
+    if bootstrap::is_url(location) {
+        return Ok(None);
+    }
+    let path = root.join(location);
+    let name = path.file_name()
+        .map(|n| util::sanitize_filename(&n.to_string_lossy()))
+        .ok_or_else(|| anyhow!("Invalid config bundle path {}", location))?;
+    let content = fs::read(&path).await
+        .with_context(|| format!("Failed to read config bundle {}", path.display()))?;
+    Ok(Some((name, content)))
+}
+
+/// Spec files for the plan's LET targets and everything they depend on
+async fn let_specs(root: &Path, targets: &[String]) -> Result<Vec<(String, Vec<u8>)>> {
+    let executor = LetExecutor::new(root);
+    let mut names: Vec<String> = Vec::new();
+    for target in targets {
+        for name in executor.resolve(target).await?.order {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    let mut specs = Vec::new();
+    for name in names {
+        let path = root.join(".rcm").join("let").join(format!("{}.json", name));
+        specs.push((name, fs::read(&path).await.with_context(|| format!("Failed to read {}", path.display()))?));
+    }
+    Ok(specs)
+}
+
+fn append_log(log: &mut String, label: &str, stdout: &[u8], stderr: &[u8]) {
+    log.push_str(&format!("==> {}\n", label));
+    log.push_str(&redact::redact(&String::from_utf8_lossy(stdout)));
+    log.push_str(&redact::redact(&String::from_utf8_lossy(stderr)));
+    log.push('\n');
+}
+
+/// Copy the job to one host and run its steps, stopping at the first failure
+async fn apply_host(host: Host, job: Arc<HostJob>, log_path: PathBuf) -> HostResult {
+    let started = std::time::Instant::now();
+    let mut result = HostResult {
+        host: host.name.clone(),
+        address: host.address.clone(),
+        status: HostStatus::Failed,
+        steps_done: 0,
+        steps: job.steps.len(),
+        failed_step: None,
+        error: None,
+        duration_ms: 0,
+        log: log_path.clone(),
+    };
+    let mut log = String::new();
+
+    let outcome: Result<()> = async {
+        let target = RemoteTarget::parse(&host.address)?;
+        result.failed_step = Some("connect".to_string());
+        let binary = remote::ensure_binary(&target).await?;
+        result.failed_step = Some("upload".to_string());
+        for (path, content) in &job.files {
+            target.upload(content, path).await?;
+        }
+        for (label, args) in &job.steps {
+            result.failed_step = Some(label.clone());
+            let mut cmd = target.ssh(false);
+            cmd.arg(remote::remote_command(&binary, args));
+            if util::skip_in_dry_run(&cmd) {
+                result.steps_done += 1;
+                continue;
+            }
+            let output = AsyncCommand::from(cmd).output().await
+                .with_context(|| format!("Failed to run ssh to {}", host.name))?;
+            append_log(&mut log, label, &output.stdout, &output.stderr);
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let last = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("no output");
+                return Err(anyhow!("{} exited with {}: {}", label, output.status, redact::redact(last.trim())));
+            }
+            result.steps_done += 1;
+        }
+        result.failed_step = None;
+        Ok(())
+    }.await;
+
+    match outcome {
+        Ok(()) => result.status = HostStatus::Ok,
+        Err(e) => {
+            let message = redact::redact_error(&e);
+            log.push_str(&format!("==> failed\n{}\n", message));
+            result.error = Some(message);
+        }
+    }
+    if !util::is_dry_run() {
+        if let Err(e) = util::write_file(&log_path, &log).await {
+            tracing::warn!(host = %host.name, "Failed to write fleet log: {}", e);
+        }
+    }
+    result.duration_ms = started.elapsed().as_millis() as u64;
+    result
+}
+
+/// Apply a plan across the inventory and report each host's outcome
+#[allow(clippy::too_many_arguments)]
+pub async fn apply(
+    workspace: &Workspace,
+    plan_path: &Path,
+    inventory_path: &Path,
+    names: Option<Vec<String>>,
+    group: Option<&str>,
+    concurrency: Option<usize>,
+    fail_fast: bool,
+    format: &str,
+) -> Result<()> {
+    if !matches!(format, "table" | "json") {
+        return Err(anyhow!("Unsupported format: {}. Use table or json", format));
+    }
+    let root = workspace.root();
+    let inventory_path = root.join(inventory_path);
+    let inventory = Inventory::parse(&fs::read_to_string(&inventory_path).await
+        .with_context(|| format!("Failed to read inventory {}", inventory_path.display()))?)?;
+    let hosts = inventory.select(names.as_deref(), group)?;
+    if hosts.is_empty() {
+        return Err(anyhow!("No hosts in {} match the filters", inventory_path.display()));
+    }
+
+    let plan = load_plan(&root.join(plan_path)).await?;
+    let specs = let_specs(root, &plan.let_targets).await?;
+    let bundle = local_bundle(root, &plan).await?;
+    let concurrency = concurrency.or(inventory.concurrency).unwrap_or(DEFAULT_CONCURRENCY).max(1);
+    let log_dir = root.join(LOG_DIR);
+    if !util::is_dry_run() {
+        fs::create_dir_all(&log_dir).await?;
+    }
+
+    if format == "table" {
+        let title = plan.manifest.name.as_deref().map(str::to_string).unwrap_or_else(|| plan_path.display().to_string());
+        println!("{}", style(format!("🛰️  Applying {} to {} host(s), {} at a time", title, hosts.len(), concurrency)).bold());
+    }
+
+    let limit = Arc::new(Semaphore::new(concurrency));
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut tasks = JoinSet::new();
+    for host in hosts {
+        let job = Arc::new(host_job(&plan, specs.clone(), bundle.as_ref(), host.parallel)?);
+        let log_path = log_dir.join(format!("{}.log", util::sanitize_filename(&host.name)));
+        let (limit, stop) = (limit.clone(), stop.clone());
+        let show = format == "table";
+        tasks.spawn(async move {
+            let _permit = limit.acquire_owned().await.expect("fleet semaphore closed");
+            if stop.load(Ordering::SeqCst) {
+                return HostResult {
+                    host: host.name, address: host.address, status: HostStatus::Skipped,
+                    steps_done: 0, steps: job.steps.len(), failed_step: None, error: None, duration_ms: 0, log: log_path,
+                };
+            }
+            if show {
+                println!("{} {}", style("→").cyan(), host.name);
+            }
+            let result = apply_host(host, job, log_path).await;
+            if result.status == HostStatus::Failed && fail_fast {
+                stop.store(true, Ordering::SeqCst);
+            }
+            if show {
+                match &result.error {
+                    None => println!("{} {} ({})", style("✓").green(), result.host, util::format_duration(result.duration_ms)),
+                    Some(error) => println!("{} {}: {}", style("✗").red(), result.host, error),
+                }
+            }
+            result
+        });
+    }
+    let mut results = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        results.push(result?);
+    }
+    results.sort_by(|a, b| a.host.cmp(&b.host));
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        let rows: Vec<HostRow> = results.iter().map(|r| HostRow {
+            host: r.host.clone(),
+            status: match r.status {
+                HostStatus::Ok => "✅ ok".to_string(),
+                HostStatus::Failed => format!("❌ failed at {}", r.failed_step.as_deref().unwrap_or("?")),
+                HostStatus::Skipped => "⏭️ skipped".to_string(),
+            },
+            steps: format!("{}/{}", r.steps_done, r.steps),
+            time: util::format_duration(r.duration_ms),
+            error: r.error.clone().unwrap_or_default(),
+        }).collect();
+        println!("{}", Table::new(&rows));
+    }
+
+    let unfinished: Vec<&str> = results.iter().filter(|r| r.status != HostStatus::Ok).map(|r| r.host.as_str()).collect();
+    if !unfinished.is_empty() {
+        return Err(anyhow!(
+            "{} of {} host(s) did not finish; logs are in {}. Retry with `--hosts {}`",
+            unfinished.len(), results.len(), log_dir.display(), unfinished.join(",")
+        ));
+    }
+    if format == "table" {
+        println!("{}", style(format!("✅ All {} host(s) applied", results.len())).green());
+    }
+    Ok(())
+}
+
+/// Handle `rcm fleet` commands
+pub async fn handle_command(workspace: &Workspace, cmd: FleetCommands) -> Result<()> {
+    match cmd {
+        FleetCommands::Apply { plan, inventory, hosts, group, concurrency, fail_fast, format } => {
+            apply(workspace, &plan, &inventory, hosts, group.as_deref(), concurrency, fail_fast, &format).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVENTORY: &str = r#"
+        concurrency = 2
+        [[hosts]]
+        name = "gpu-1"
+        address = "ubuntu@10.0.0.5:2222"
+        groups = ["gpu"]
+        parallel = 4
+        [[hosts]]
+        name = "web-1"
+        address = "web-1"
+    "#;
+
+    #[test]
+    fn test_inventory_select() {
+        let inventory = Inventory::parse(INVENTORY).unwrap();
+        assert_eq!(inventory.concurrency, Some(2));
+        let gpu: Vec<String> = inventory.select(None, Some("gpu")).unwrap().into_iter().map(|h| h.name).collect();
+        assert_eq!(gpu, vec!["gpu-1"]);
+        assert_eq!(inventory.select(Some(&["web-1".to_string()]), None).unwrap().len(), 1);
+        assert!(inventory.select(Some(&["db-1".to_string()]), None).is_err());
+        assert!(Inventory::parse(&format!("{}\n[[hosts]]\nname = \"web-1\"\naddress = \"x\"", INVENTORY)).is_err());
+    }
+
+    #[test]
+    fn test_host_job_steps() {
+        let plan: FleetPlan = toml::from_str(r#"
+            system = ["ffmpeg"]
+            let = ["vllm"]
+        "#).unwrap();
+        let job = host_job(&plan, vec![("vllm".to_string(), b"{}".to_vec())], None, Some(4)).unwrap();
+        let labels: Vec<&str> = job.steps.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels, vec!["bootstrap", "let vllm"]);
+        assert_eq!(job.steps[1].1[..3], ["-w".to_string(), job.dir.clone(), "let".to_string()]);
+        assert!(job.steps[1].1.ends_with(&["--parallel".to_string(), "4".to_string()]));
+        assert_eq!(job.files.len(), 2);
+
+        assert!(host_job(&FleetPlan::default(), Vec::new(), None, None).is_err());
+    }
+
+    #[test]
+    fn test_host_job_copies_local_bundle() {
+        let plan: FleetPlan = toml::from_str(r#"config_bundle = "dev.tar.gz#sha256=ABC""#).unwrap();
+        let bundle = ("dev.tar.gz".to_string(), b"bundle".to_vec());
+        let job = host_job(&plan, Vec::new(), Some(&bundle), None).unwrap();
+        let remote = format!("{}/dev.tar.gz", job.dir);
+        assert_eq!(job.files[0], (remote.clone(), b"bundle".to_vec()));
+        let manifest: Value = serde_json::from_slice(&job.files[1].1).unwrap();
+        assert_eq!(manifest["config_bundle"], format!("{}#sha256=abc", remote));
+    }
+}
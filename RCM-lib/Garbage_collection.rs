@@ -0,0 +1,416 @@
+//! Garbage collection for temp dirs and stale run state
+//!
+//! Crashed or killed runs leave `rcm-*` directories from
+//! `util::create_temp_dir`, pidfiles and locks under `.rcm/run`, half-written
+//! cache entries, transaction snapshots and GPT registry instances whose
+//! process is gone. A quick sweep of temp dirs and run state happens at
+//! startup (at most once an hour); `rcm gc` sweeps everything on demand.
+//! Run state is only removed when no process holds its lock, so a recycled
+//! PID can't keep it alive; nothing else that names a live PID is touched.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use fs2::FileExt;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tabled::{Table, Tabled};
+use tokio::fs;
+use crate::cache::CacheStore;
+use crate::config::Config;
+use crate::transaction::{self, TransactionStatus, TRANSACTIONS_DIR};
+use crate::util;
+use crate::workspace::Workspace;
+
+/// Pidfiles and locks of running rcm processes, relative to the workspace root
+pub const RUN_DIR: &str = ".rcm/run";
+
+/// Age after which unowned temp dirs and run state count as stale
+pub const DEFAULT_STALE_HOURS: u64 = 24;
+
+/// Records when the last startup sweep ran
+const STAMP_FILE: &str = "gc.stamp";
+const STARTUP_INTERVAL: Duration = Duration::from_secs(3600);
+
+const GPT_REGISTRY: &str = ".rcm/gpt-configs/registry.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GcKind {
+    TempDir,
+    RunState,
+    CacheEntry,
+    Transaction,
+    RegistryEntry,
+}
+
+impl std::fmt::Display for GcKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Self::TempDir => "temp dir",
+            Self::RunState => "run state",
+            Self::CacheEntry => "cache entry",
+            Self::Transaction => "transaction",
+            Self::RegistryEntry => "registry entry",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Something the sweep found to remove
+#[derive(Debug, Clone, Serialize, Tabled)]
+pub struct GcItem {
+    #[tabled(rename = "Kind")]
+    pub kind: GcKind,
+    #[tabled(rename = "Path", display_with = "display_path")]
+    pub path: PathBuf,
+    #[tabled(rename = "Size", display_with = "display_size")]
+    pub bytes: u64,
+    #[tabled(rename = "Reason")]
+    pub reason: String,
+}
+
+fn display_path(path: &Path) -> String {
+    path.display().to_string()
+}
+
+fn display_size(bytes: &u64) -> String {
+    util::format_bytes(*bytes)
+}
+
+/// Pidfile for the running rcm process, locked while it runs; removed when dropped
+///
+/// The OS drops the lock when the process exits however it exits, which is
+/// what the sweep checks rather than whether the PID is in use.
+pub struct RunGuard {
+    held: Option<(PathBuf, std::fs::File)>,
+}
+
+impl RunGuard {
+    /// Record this process under `.rcm/run` (skipped in dry and read-only runs and outside RCM workspaces)
+    pub fn acquire(workspace_root: &Path, command: &str) -> Self {
+        if util::is_dry_run() || util::is_read_only() || !workspace_root.join(".rcm").is_dir() {
+            return Self { held: None };
+        }
+        let dir = workspace_root.join(RUN_DIR);
+        let path = dir.join(format!("rcm-{}.pid", std::process::id()));
+        let written = std::fs::create_dir_all(&dir)
+            .and_then(|_| std::fs::write(&path, format!("{}\n{}\n", std::process::id(), command)))
+            .and_then(|_| std::fs::OpenOptions::new().read(true).write(true).open(&path))
+            .and_then(|file| file.try_lock_exclusive().map(|_| file));
+        match written {
+            Ok(file) => Self { held: Some((path, file)) },
+            Err(e) => {
+                tracing::debug!("Could not write pidfile {}: {}", path.display(), e);
+                Self { held: None }
+            }
+        }
+    }
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        if let Some((path, file)) = self.held.take() {
+            let _ = std::fs::remove_file(&path);
+            drop(file);
+        }
+    }
+}
+
+/// Whether some process holds the lock on a run state file
+fn lock_held(path: &Path) -> bool {
+    let Ok(file) = std::fs::OpenOptions::new().read(true).write(true).open(path) else {
+        // Can't tell, so leave it alone
+        return true;
+    };
+    match file.try_lock_exclusive() {
+        Ok(()) => {
+            let _ = file.unlock();
+            false
+        }
+        Err(_) => true,
+    }
+}
+
+/// PID recorded in a pidfile or lock: the first token, or a JSON `pid` field
+pub fn parse_pid(content: &str) -> Option<u32> {
+    let content = content.trim();
+    if content.starts_with('{') {
+        let json: Value = serde_json::from_str(content).ok()?;
+        return json.get("pid")?.as_u64().and_then(|pid| u32::try_from(pid).ok());
+    }
+    content.split_whitespace().next()?.parse().ok()
+}
+
+fn age(path: &Path) -> Duration {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .unwrap_or_default()
+}
+
+async fn size_of(path: &Path) -> u64 {
+    if path.is_dir() {
+        util::calculate_directory_size(path).await.unwrap_or(0)
+    } else {
+        fs::metadata(path).await.map(|m| m.len()).unwrap_or(0)
+    }
+}
+
+/// `rcm-*` dirs in the system temp dir and anything under `.rcm/temp`
+async fn stale_temp_dirs(workspace_root: &Path, older_than: Duration) -> Vec<GcItem> {
+    let mut items = Vec::new();
+    for (dir, rcm_only) in [(std::env::temp_dir(), true), (workspace_root.join(".rcm").join("temp"), false)] {
+        let Ok(mut entries) = fs::read_dir(&dir).await else {
+            continue;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            let untouched = age(&path);
+            if (rcm_only && !name.starts_with("rcm-")) || untouched <= older_than {
+                continue;
+            }
+            items.push(GcItem {
+                kind: GcKind::TempDir,
+                bytes: size_of(&path).await,
+                path,
+                reason: format!("untouched for {}h", untouched.as_secs() / 3600),
+            });
+        }
+    }
+    items
+}
+
+/// Pidfiles and locks under `.rcm/run` whose process is gone
+async fn stale_run_state(workspace_root: &Path, older_than: Duration) -> Vec<GcItem> {
+    let mut items = Vec::new();
+    let Ok(mut entries) = fs::read_dir(workspace_root.join(RUN_DIR)).await else {
+        return items;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let is_state = path.extension().is_some_and(|ext| ext == "pid" || ext == "lock");
+        if !is_state || !path.is_file() {
+            continue;
+        }
+        if lock_held(&path) {
+            continue;
+        }
+        let content = fs::read_to_string(&path).await.unwrap_or_default();
+        let reason = match parse_pid(&content) {
+            Some(pid) if pid == std::process::id() => continue,
+            Some(pid) => format!("pid {} no longer holds it", pid),
+            // Without a PID only age tells whether the owner is gone
+            None if age(&path) > older_than => "no pid, untouched past the threshold".to_string(),
+            None => continue,
+        };
+        items.push(GcItem { kind: GcKind::RunState, bytes: size_of(&path).await, path, reason });
+    }
+    items
+}
+
+/// Cache entries past their TTL and interrupted cache writes
+async fn stale_cache_entries(config: &Config, older_than: Duration) -> Vec<GcItem> {
+    let store = CacheStore::open(config).await;
+    let mut items = Vec::new();
+    for path in store.stale_files(older_than) {
+        let reason = if path.extension().is_some_and(|ext| ext == "partial") {
+            "interrupted write".to_string()
+        } else {
+            format!("older than cache.ttl_hours ({})", config.cache.ttl_hours)
+        };
+        items.push(GcItem { kind: GcKind::CacheEntry, bytes: size_of(&path).await, path, reason });
+    }
+    items
+}
+
+/// Snapshot dirs of interrupted transactions; failed rollbacks are kept for manual recovery
+async fn stale_transactions(workspace_root: &Path, older_than: Duration) -> Vec<GcItem> {
+    let records = transaction::load_log(workspace_root).await.unwrap_or_default();
+    let mut items = Vec::new();
+    let Ok(mut entries) = fs::read_dir(workspace_root.join(TRANSACTIONS_DIR)).await else {
+        return items;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if !path.is_dir() || age(&path) <= older_than {
+            continue;
+        }
+        let id = entry.file_name().to_string_lossy().to_string();
+        let reason = match records.iter().find(|r| r.id == id).map(|r| &r.status) {
+            Some(TransactionStatus::RollbackFailed) => continue,
+            Some(status) => format!("{} snapshot left behind", status),
+            None => "snapshot with no log record".to_string(),
+        };
+        items.push(GcItem { kind: GcKind::Transaction, bytes: size_of(&path).await, path, reason });
+    }
+    items
+}
+
+/// GPT instances recorded as running whose backend process has exited
+async fn orphaned_registry_entries(workspace_root: &Path) -> Result<(Vec<GcItem>, Option<Value>)> {
+    let path = workspace_root.join(GPT_REGISTRY);
+    if !path.exists() {
+        return Ok((Vec::new(), None));
+    }
+    let mut registry: Value = serde_json::from_str(&fs::read_to_string(&path).await?)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let Some(active) = registry.get_mut("active_models").and_then(Value::as_object_mut) else {
+        return Ok((Vec::new(), None));
+    };
+
+    let mut items = Vec::new();
+    let mut orphaned = Vec::new();
+    for (name, instance) in active.iter() {
+        // Remote, service and Kubernetes instances have no local PID to check
+        let Some(pid) = instance.get("process_id").and_then(Value::as_u64).and_then(|p| u32::try_from(p).ok()) else {
+            continue;
+        };
+        // A replicated model stays registered while any of its processes lives
+        let replica_pids: Vec<u32> = instance.get("replicas").and_then(Value::as_array).into_iter().flatten()
+            .filter_map(|r| r.get("process_id").and_then(Value::as_u64).and_then(|p| u32::try_from(p).ok()))
+            .collect();
+        let mut alive = util::process_alive(pid).await;
+        for replica in replica_pids {
+            alive = alive || util::process_alive(replica).await;
+        }
+        if !alive {
+            orphaned.push(name.clone());
+            items.push(GcItem {
+                kind: GcKind::RegistryEntry,
+                path: path.clone(),
+                bytes: 0,
+                reason: format!("'{}' instance (pid {}) is not running", name, pid),
+            });
+        }
+    }
+    if orphaned.is_empty() {
+        return Ok((items, None));
+    }
+    for name in &orphaned {
+        active.remove(name);
+    }
+    Ok((items, Some(registry)))
+}
+
+async fn remove(item: &GcItem) -> Result<()> {
+    if item.path.is_dir() {
+        fs::remove_dir_all(&item.path).await
+    } else {
+        fs::remove_file(&item.path).await
+    }
+    .with_context(|| format!("Failed to remove {}", item.path.display()))
+}
+
+/// Quick sweep of temp dirs and run state, at most once per hour per workspace
+pub async fn startup_sweep(workspace_root: &Path) {
+    if util::is_dry_run() || util::is_read_only() || !workspace_root.join(".rcm").is_dir() {
+        return;
+    }
+    let stamp = workspace_root.join(RUN_DIR).join(STAMP_FILE);
+    if stamp.exists() && age(&stamp) < STARTUP_INTERVAL {
+        return;
+    }
+    let written = std::fs::create_dir_all(workspace_root.join(RUN_DIR)).and_then(|_| std::fs::write(&stamp, ""));
+    if let Err(e) = written {
+        tracing::debug!("Skipping startup garbage collection: {}", e);
+        return;
+    }
+
+    let older_than = Duration::from_secs(DEFAULT_STALE_HOURS * 3600);
+    let mut items = stale_temp_dirs(workspace_root, older_than).await;
+    items.extend(stale_run_state(workspace_root, older_than).await);
+    let mut freed = 0;
+    for item in &items {
+        match remove(item).await {
+            Ok(()) => freed += item.bytes,
+            Err(e) => tracing::debug!("{:#}", e),
+        }
+    }
+    if !items.is_empty() {
+        tracing::debug!(removed = items.len(), freed_bytes = freed, "Startup garbage collection");
+    }
+}
+
+/// Sweep caches, temps, run state, transaction snapshots and orphaned registry entries
+pub async fn run(workspace: &Workspace, config: &Config, older_than_hours: u64, format: &str) -> Result<()> {
+    if format != "table" && format != "json" {
+        return Err(anyhow!("Unsupported format: {}. Use table or json", format));
+    }
+    let root = workspace.root();
+    let older_than = Duration::from_secs(older_than_hours * 3600);
+
+    let mut items = stale_temp_dirs(root, older_than).await;
+    items.extend(stale_run_state(root, older_than).await);
+    items.extend(stale_cache_entries(config, older_than).await);
+    items.extend(stale_transactions(root, older_than).await);
+    let (registry_items, registry) = orphaned_registry_entries(root).await?;
+    items.extend(registry_items);
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&items)?);
+    } else if items.is_empty() {
+        println!("{}", style("✨ Nothing to collect").green());
+        return Ok(());
+    } else {
+        println!("{}", Table::new(&items));
+    }
+    if util::is_dry_run() {
+        println!("[dry-run] would remove {} item(s)", items.len());
+        return Ok(());
+    }
+
+    let mut freed = 0;
+    let mut failures = 0;
+    for item in items.iter().filter(|i| i.kind != GcKind::RegistryEntry) {
+        match remove(item).await {
+            Ok(()) => freed += item.bytes,
+            Err(e) => {
+                failures += 1;
+                println!("{} {:#}", style("⚠️").yellow(), e);
+            }
+        }
+    }
+    if let Some(registry) = registry {
+        util::write_file(&root.join(GPT_REGISTRY), serde_json::to_string_pretty(&registry)?).await?;
+    }
+
+    if format == "table" {
+        println!("🧹 Removed {} item(s), freed {}", items.len() - failures, util::format_bytes(freed));
+    }
+    if failures > 0 {
+        return Err(anyhow!("{} item(s) could not be removed", failures));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pid() {
+        assert_eq!(parse_pid("4242\nensure\n"), Some(4242));
+        assert_eq!(parse_pid(r#"{"pid": 77, "command": "gpt"}"#), Some(77));
+        assert_eq!(parse_pid(""), None);
+        assert_eq!(parse_pid("not-a-pid"), None);
+    }
+
+    #[test]
+    fn test_run_state_lock() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join(".rcm")).unwrap();
+        let guard = RunGuard::acquire(root.path(), "ensure");
+        let path = root.path().join(RUN_DIR).join(format!("rcm-{}.pid", std::process::id()));
+        assert!(lock_held(&path));
+        drop(guard);
+        assert!(!path.exists());
+
+        // Left behind by a killed run whose PID may since have been reused
+        std::fs::write(&path, "4242\nensure\n").unwrap();
+        assert!(!lock_held(&path));
+    }
+}
@@ -0,0 +1,412 @@
+//! Ruby gems support for RCM
+//!
+//! Wraps Bundler for Gemfile/Gemfile.lock projects. Gemfile.lock is parsed
+//! directly so listing works offline; audits run bundler-audit.
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use console::style;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tabled::{Table, Tabled};
+use tokio::fs;
+use crate::audit::{self, AuditReport};
+use crate::audit_exceptions::AuditExceptions;
+use crate::toolchain;
+use crate::util::{self, execute_mutation, validate_package_name};
+use crate::workspace::Workspace;
+
+#[derive(Subcommand)]
+pub enum GemCommands {
+    /// Add gems to the Gemfile, or install the Gemfile when none are given
+    Install {
+        /// Gems to add (name[@version], version as a requirement such as "~> 7.1")
+        gems: Vec<String>,
+        /// Gemfile group to add the gems to (e.g. development, test)
+        #[arg(long)]
+        group: Option<String>,
+    },
+
+    /// Update gems within their Gemfile requirements
+    Update {
+        /// Specific gems to update (all if empty)
+        gems: Vec<String>,
+        /// Do not update shared dependencies of the given gems
+        #[arg(long)]
+        conservative: bool,
+    },
+
+    /// Run a command in the bundle's context (bundle exec)
+    Exec {
+        /// Command and arguments
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        command: Vec<String>,
+    },
+
+    /// List gems resolved in Gemfile.lock
+    List {
+        /// Only gems named in the Gemfile
+        #[arg(long)]
+        direct: bool,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+
+    /// Audit Gemfile.lock for vulnerable gems with bundler-audit
+    Audit {
+        /// Update the ruby-advisory-db before checking
+        #[arg(long)]
+        update_db: bool,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+}
+
+/// A gem resolved in Gemfile.lock
+#[derive(Debug, Clone, PartialEq, Serialize, Tabled)]
+pub struct LockedGem {
+    #[tabled(rename = "Gem")]
+    pub name: String,
+    #[tabled(rename = "Version")]
+    pub version: String,
+    /// Native platform variant, e.g. `x86_64-linux` for nokogiri
+    #[tabled(rename = "Platform", display_with = "display_platform")]
+    pub platform: Option<String>,
+    /// Source section the gem was resolved from (GEM, GIT or PATH)
+    #[tabled(rename = "Source")]
+    pub source: String,
+    /// Named in the Gemfile rather than pulled in by another gem
+    #[tabled(rename = "Direct")]
+    pub direct: bool,
+}
+
+fn display_platform(platform: &Option<String>) -> String {
+    platform.clone().unwrap_or_default()
+}
+
+/// Split `1.16.2-x86_64-linux` into version and platform
+fn split_platform(version: &str) -> (String, Option<String>) {
+    match version.split_once('-') {
+        Some((version, platform)) => (version.to_string(), Some(platform.to_string())),
+        None => (version.to_string(), None),
+    }
+}
+
+/// Parse Gemfile.lock specs and mark the gems listed under DEPENDENCIES as direct
+pub fn parse_gemfile_lock(content: &str) -> Vec<LockedGem> {
+    let mut gems = Vec::new();
+    let mut direct = Vec::new();
+    let mut section = "";
+
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with(' ') {
+            section = line.trim();
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        let entry = line.trim();
+        match section {
+            // Specs sit at four spaces; their own dependencies at six
+            "GEM" | "GIT" | "PATH" if indent == 4 => {
+                let Some((name, rest)) = entry.split_once(" (") else { continue };
+                let (version, platform) = split_platform(rest.trim_end_matches(')'));
+                gems.push(LockedGem {
+                    name: name.to_string(),
+                    version,
+                    platform,
+                    source: section.to_string(),
+                    direct: false,
+                });
+            }
+            "DEPENDENCIES" if indent == 2 => {
+                let name = entry.split([' ', '!']).next().unwrap_or(entry);
+                direct.push(name.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    for gem in &mut gems {
+        gem.direct = direct.contains(&gem.name);
+    }
+    gems.sort_by(|a, b| a.name.cmp(&b.name));
+    gems
+}
+
+/// `name@requirement` → (name, requirement); bare names have no requirement
+pub fn split_gem_spec(spec: &str) -> (&str, Option<&str>) {
+    match spec.split_once('@') {
+        Some((name, version)) if !version.is_empty() && version != "latest" => (name, Some(version)),
+        Some((name, _)) => (name, None),
+        None => (spec, None),
+    }
+}
+
+pub struct GemManager {
+    workspace_root: PathBuf,
+}
+
+impl GemManager {
+    pub fn new(workspace_root: &Path) -> Self {
+        Self {
+            workspace_root: workspace_root.to_path_buf(),
+        }
+    }
+
+    /// `bundle` in the workspace with pinned toolchain shims on PATH
+    fn bundle(&self) -> Command {
+        let mut cmd = Command::new("bundle");
+        cmd.current_dir(&self.workspace_root);
+        if let Some(path) = toolchain::shim_path(&self.workspace_root) {
+            cmd.env("PATH", path);
+        }
+        cmd
+    }
+
+    pub fn has_gemfile(&self) -> bool {
+        self.workspace_root.join("Gemfile").exists()
+    }
+
+    pub async fn check_environment(&self) -> Result<()> {
+        if !util::command_exists("ruby").await {
+            return Err(anyhow!("Ruby is not installed or not in PATH"));
+        }
+        if !util::command_exists("bundle").await {
+            return Err(anyhow!("Bundler is not installed. Run 'gem install bundler'"));
+        }
+        if !self.has_gemfile() {
+            return Err(anyhow!("No Gemfile found. Run 'bundle init' first"));
+        }
+        Ok(())
+    }
+
+    /// `bundle install`
+    pub async fn install_all(&self) -> Result<()> {
+        self.check_environment().await?;
+        execute_mutation(self.bundle().arg("install")).await.context("bundle install failed")?;
+        Ok(())
+    }
+
+    /// `bundle add` for each gem; Bundler installs them as it goes
+    pub async fn add(&self, gems: &[String], group: Option<&str>) -> Result<()> {
+        self.check_environment().await?;
+        for spec in gems {
+            let (name, version) = split_gem_spec(spec);
+            let mut cmd = self.bundle();
+            cmd.args(["add", name]);
+            if let Some(version) = version {
+                cmd.args(["--version", version]);
+            }
+            if let Some(group) = group {
+                cmd.args(["--group", group]);
+            }
+            execute_mutation(&mut cmd).await.with_context(|| format!("bundle add {} failed", name))?;
+        }
+        Ok(())
+    }
+
+    /// `bundle update`, of everything when no gems are given
+    pub async fn update(&self, gems: &[String], conservative: bool) -> Result<()> {
+        self.check_environment().await?;
+        let mut cmd = self.bundle();
+        cmd.arg("update");
+        if gems.is_empty() {
+            cmd.arg("--all");
+        } else {
+            cmd.args(gems);
+        }
+        if conservative {
+            cmd.arg("--conservative");
+        }
+        execute_mutation(&mut cmd).await.context("bundle update failed")?;
+        Ok(())
+    }
+
+    /// `bundle exec` with inherited stdio; returns the command's exit code
+    pub async fn exec(&self, command: &[String]) -> Result<i32> {
+        self.check_environment().await?;
+        let mut cmd = self.bundle();
+        cmd.arg("exec").args(command);
+        if util::skip_in_dry_run(&cmd) {
+            return Ok(0);
+        }
+        let status = cmd.status().context("Failed to run bundle exec")?;
+        Ok(status.code().unwrap_or(1))
+    }
+
+    pub async fn locked_gems(&self) -> Result<Vec<LockedGem>> {
+        let path = self.workspace_root.join("Gemfile.lock");
+        if !path.exists() {
+            return Err(anyhow!("No Gemfile.lock found. Run 'rcm gem install' first"));
+        }
+        let content = fs::read_to_string(&path).await
+            .context("Failed to read Gemfile.lock")?;
+        Ok(parse_gemfile_lock(&content))
+    }
+}
+
+fn print_audit(report: &AuditReport) {
+    #[derive(Tabled)]
+    struct Row {
+        #[tabled(rename = "Severity")]
+        severity: String,
+        #[tabled(rename = "Gem")]
+        package: String,
+        #[tabled(rename = "Version")]
+        version: String,
+        #[tabled(rename = "Advisory")]
+        advisory: String,
+        #[tabled(rename = "Title")]
+        title: String,
+    }
+    let rows: Vec<Row> = report
+        .unaccepted()
+        .map(|v| Row {
+            severity: v.severity_level().to_string(),
+            package: v.package.clone(),
+            version: v.version.clone().unwrap_or_default(),
+            advisory: v.advisory.clone(),
+            title: v.title.clone(),
+        })
+        .collect();
+    if !rows.is_empty() {
+        println!("{}", Table::new(rows));
+    }
+    for (vuln, _, expires) in &report.accepted {
+        println!("  ⏸️ {} {} accepted until {}", vuln.package, vuln.advisory, expires);
+    }
+}
+
+/// Handle Ruby gem commands
+pub async fn handle_command(workspace: &Workspace, cmd: GemCommands) -> Result<()> {
+    let gem = GemManager::new(workspace.root());
+    match cmd {
+        GemCommands::Install { gems, group } => {
+            if gems.is_empty() {
+                println!("{}", style("💎 Installing Gemfile dependencies...").cyan().bold());
+                gem.install_all().await?;
+                println!("{}", style("✅ Bundle complete").green().bold());
+                return Ok(());
+            }
+            for spec in &gems {
+                validate_package_name(split_gem_spec(spec).0)?;
+            }
+            println!("{}", style(format!("💎 Adding {}...", gems.join(", "))).cyan().bold());
+            gem.add(&gems, group.as_deref()).await?;
+            println!("{}", style("✅ Gemfile updated").green().bold());
+            Ok(())
+        }
+
+        GemCommands::Update { gems, conservative } => {
+            gem.update(&gems, conservative).await?;
+            println!("{}", style("✅ Gems updated").green().bold());
+            Ok(())
+        }
+
+        GemCommands::Exec { command } => {
+            let code = gem.exec(&command).await?;
+            if code != 0 {
+                return Err(anyhow!("bundle exec {} exited with code {}", command.join(" "), code));
+            }
+            Ok(())
+        }
+
+        GemCommands::List { direct, format } => {
+            let gems: Vec<LockedGem> = gem.locked_gems().await?
+                .into_iter()
+                .filter(|g| !direct || g.direct)
+                .collect();
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&gems)?),
+                "table" => {
+                    if gems.is_empty() {
+                        println!("No gems locked.");
+                    } else {
+                        println!("{}", Table::new(&gems));
+                    }
+                }
+                _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+            }
+            Ok(())
+        }
+
+        GemCommands::Audit { update_db, format } => {
+            if update_db {
+                execute_mutation(Command::new("bundle-audit").arg("update").current_dir(workspace.root())).await
+                    .context("Failed to update ruby-advisory-db")?;
+            }
+            let findings = audit::scan(workspace, &["bundler".to_string()]).await?;
+            let exceptions = AuditExceptions::load(workspace.root()).await?;
+            let mut report = audit::classify(findings, &exceptions, chrono::Local::now().date_naive());
+            report.active.sort_by(|a, b| b.severity_level().cmp(&a.severity_level()));
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+                "table" => print_audit(&report),
+                _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+            }
+            if report.failed() {
+                return Err(anyhow!(
+                    "{} vulnerable gems found ({} accepted)",
+                    report.unaccepted().count(),
+                    report.accepted.len()
+                ));
+            }
+            if format != "json" {
+                println!("✅ No known vulnerabilities in Gemfile.lock ({} accepted)", report.accepted.len());
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gemfile_lock() {
+        let content = "\
+GIT
+  remote: https://github.com/acme/widgets.git
+  revision: 3f2a1b
+  specs:
+    widgets (0.4.0)
+
+GEM
+  remote: https://rubygems.org/
+  specs:
+    nokogiri (1.16.2-x86_64-linux)
+      racc (~> 1.4)
+    racc (1.7.3)
+    rails (7.1.3)
+      railties (= 7.1.3)
+
+PLATFORMS
+  x86_64-linux
+
+DEPENDENCIES
+  nokogiri
+  rails (~> 7.1)
+  widgets!
+
+BUNDLED WITH
+   2.5.6
+";
+        let gems = parse_gemfile_lock(content);
+        let names: Vec<&str> = gems.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["nokogiri", "racc", "rails", "widgets"]);
+        assert_eq!(gems[0].version, "1.16.2");
+        assert_eq!(gems[0].platform.as_deref(), Some("x86_64-linux"));
+        assert!(!gems[1].direct && gems[2].direct && gems[3].direct);
+        assert_eq!(gems[3].source, "GIT");
+
+        assert_eq!(split_gem_spec("rails@~> 7.1"), ("rails", Some("~> 7.1")));
+        assert_eq!(split_gem_spec("puma@latest"), ("puma", None));
+    }
+}
@@ -0,0 +1,366 @@
+//! Managed git hooks (`rcm hooks`)
+//!
+//! Hooks are declared next to the tasks, under `[hooks]` in `rcm.toml` or
+//! `"hooks"` in `.rcm/workspace.json`, as RCM command lines:
+//!
+//! ```toml
+//! [hooks]
+//! pre-commit = ["run lint", "lock --verify"]
+//! pre-push = ["audit", "ensure"]
+//! ```
+//!
+//! `rcm hooks install` writes a small script per hook that calls back into
+//! `rcm hooks run <hook>`, so every clone runs whatever the manifest says at the
+//! time. A hook that was already there is kept as `<hook>.local` and runs first.
+//! `RCM_SKIP_HOOKS=1` (or git's `--no-verify`) skips them.
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use console::style;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tabled::{Table, Tabled};
+use crate::members;
+use crate::remote::shell_quote;
+use crate::tasks::TASKS_FILE;
+use crate::util;
+use crate::workspace::Workspace;
+
+/// Marks a hook script as written by `rcm hooks install`
+const MARKER: &str = "# Managed by rcm: edit [hooks] in rcm.toml and run `rcm hooks install`";
+
+pub const SKIP_ENV: &str = "RCM_SKIP_HOOKS";
+
+/// Client-side hooks that can be managed
+pub const HOOKS: &[&str] = &[
+    "pre-commit", "prepare-commit-msg", "commit-msg", "post-commit", "post-checkout",
+    "post-merge", "pre-rebase", "pre-push",
+];
+
+#[derive(Subcommand)]
+pub enum HookCommands {
+    /// Write the hooks declared in the manifest into the git hooks directory
+    Install {
+        /// Take over hooks rcm didn't write (they are kept as <hook>.local)
+        #[arg(long)]
+        force: bool,
+    },
+    /// Remove managed hooks and put back the ones they replaced
+    Uninstall,
+    /// Show declared hooks and whether they are installed
+    List,
+    /// Run a hook's commands (what the installed scripts call)
+    Run {
+        hook: String,
+        /// Arguments git passed to the hook
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum HookDef {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct HooksSection {
+    #[serde(default)]
+    hooks: BTreeMap<String, HookDef>,
+}
+
+fn from_section(section: HooksSection) -> Result<BTreeMap<String, Vec<String>>> {
+    let mut hooks = BTreeMap::new();
+    for (name, def) in section.hooks {
+        if !HOOKS.contains(&name.as_str()) {
+            return Err(anyhow!("Unknown git hook '{}'; use one of {}", name, HOOKS.join(", ")));
+        }
+        let commands = match def {
+            HookDef::One(command) => vec![command],
+            HookDef::Many(commands) => commands,
+        };
+        hooks.insert(name, commands);
+    }
+    Ok(hooks)
+}
+
+/// Hooks declared for the workspace at `root`; `rcm.toml` wins over `.rcm/workspace.json`
+pub async fn load(root: &Path) -> Result<BTreeMap<String, Vec<String>>> {
+    let mut hooks = BTreeMap::new();
+    let manifest = root.join(members::MANIFEST);
+    if manifest.exists() {
+        let section: HooksSection = serde_json::from_str(&tokio::fs::read_to_string(&manifest).await?)
+            .with_context(|| format!("Invalid hooks in {}", manifest.display()))?;
+        hooks.extend(from_section(section)?);
+    }
+    let toml_path = root.join(TASKS_FILE);
+    if toml_path.exists() {
+        let section: HooksSection = toml::from_str(&tokio::fs::read_to_string(&toml_path).await?)
+            .with_context(|| format!("Invalid [hooks] in {}", toml_path.display()))?;
+        hooks.extend(from_section(section)?);
+    }
+    Ok(hooks)
+}
+
+/// Split an RCM command line into arguments, honouring single and double quotes
+pub fn split_args(line: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if std::mem::take(&mut in_arg) {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(anyhow!("Unterminated quote in `{}`", line));
+    }
+    if in_arg {
+        args.push(current);
+    }
+    // `rcm audit` and `audit` mean the same thing
+    if args.first().map(String::as_str) == Some("rcm") {
+        args.remove(0);
+    }
+    Ok(args)
+}
+
+/// The hooks directory git uses for this checkout (honours core.hooksPath and worktrees)
+fn hooks_dir(root: &Path) -> Result<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--git-path", "hooks"])
+        .current_dir(root)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        return Err(anyhow!("{} is not inside a git repository", root.display()));
+    }
+    let dir = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    Ok(if dir.is_absolute() { dir } else { root.join(dir) })
+}
+
+/// How the script should invoke rcm: by name when it's on PATH, else this binary
+fn rcm_program() -> String {
+    if which::which("rcm").is_ok() {
+        return "rcm".to_string();
+    }
+    std::env::current_exe()
+        .map(|path| shell_quote(&path.to_string_lossy()))
+        .unwrap_or_else(|_| "rcm".to_string())
+}
+
+/// The script installed for `hook`
+pub fn script(hook: &str, program: &str) -> String {
+    format!(
+        "#!/bin/sh\n{marker}\n[ \"${skip}\" = 1 ] && exit 0\nlocal_hook=\"$(dirname \"$0\")/{hook}.local\"\nif [ -x \"$local_hook\" ]; then\n  \"$local_hook\" \"$@\" || exit $?\nfi\nexec {program} hooks run {hook} \"$@\"\n",
+        marker = MARKER, skip = SKIP_ENV, hook = hook, program = program,
+    )
+}
+
+fn is_managed(path: &Path) -> bool {
+    std::fs::read_to_string(path).is_ok_and(|content| content.contains(MARKER))
+}
+
+async fn make_executable(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if !util::is_dry_run() {
+            tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).await?;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+async fn install(root: &Path, force: bool) -> Result<()> {
+    let hooks = load(root).await?;
+    let dir = hooks_dir(root)?;
+    if hooks.is_empty() {
+        println!("{}", style("🪝 No hooks declared").yellow());
+        println!("Add them under [hooks] in {}, e.g. pre-commit = [\"run lint\"]", root.join(TASKS_FILE).display());
+    }
+    // Refuse before writing anything, so a partial install never happens
+    let foreign: Vec<&str> = hooks.keys()
+        .filter(|hook| dir.join(hook).exists() && !is_managed(&dir.join(hook)))
+        .map(String::as_str)
+        .collect();
+    if !foreign.is_empty() && !force {
+        return Err(anyhow!(
+            "{} already exist(s) in {}; rerun with --force to keep them as <hook>.local and run them first",
+            foreign.join(", "), dir.display()
+        ));
+    }
+
+    util::ensure_writable(format_args!("install git hooks in {}", dir.display()))?;
+    if !util::is_dry_run() {
+        tokio::fs::create_dir_all(&dir).await?;
+    }
+    let program = rcm_program();
+    for (hook, commands) in &hooks {
+        for command in commands {
+            split_args(command).with_context(|| format!("Invalid {} command", hook))?;
+        }
+        let path = dir.join(hook);
+        if foreign.contains(&hook.as_str()) {
+            let kept = dir.join(format!("{}.local", hook));
+            if util::is_dry_run() {
+                println!("[dry-run] move {} -> {}", path.display(), kept.display());
+            } else {
+                tokio::fs::rename(&path, &kept).await?;
+            }
+        }
+        util::write_file(&path, script(hook, &program)).await?;
+        make_executable(&path).await?;
+        println!("{} {} → {}", style("✓").green(), hook, commands.join(", "));
+    }
+
+    // Hooks dropped from the manifest shouldn't keep running
+    for hook in HOOKS.iter().filter(|hook| !hooks.contains_key(**hook)) {
+        if is_managed(&dir.join(hook)) {
+            remove(&dir, hook).await?;
+        }
+    }
+    if !hooks.is_empty() {
+        println!("{}", style(format!("✅ Installed {} hook(s) in {}", hooks.len(), dir.display())).green());
+    }
+    Ok(())
+}
+
+/// Delete a managed hook and restore the one it replaced
+async fn remove(dir: &Path, hook: &str) -> Result<()> {
+    let path = dir.join(hook);
+    let kept = dir.join(format!("{}.local", hook));
+    if util::is_dry_run() {
+        println!("[dry-run] remove {}", path.display());
+        return Ok(());
+    }
+    tokio::fs::remove_file(&path).await?;
+    if kept.exists() {
+        tokio::fs::rename(&kept, &path).await?;
+    }
+    println!("🗑️ Removed {} hook", hook);
+    Ok(())
+}
+
+#[derive(Tabled)]
+struct HookRow {
+    #[tabled(rename = "Hook")]
+    hook: String,
+    #[tabled(rename = "Runs")]
+    commands: String,
+    #[tabled(rename = "Installed")]
+    installed: String,
+}
+
+/// Run each of the hook's commands with this rcm, stopping at the first failure
+async fn run(root: &Path, hook: &str, args: &[String]) -> Result<()> {
+    let hooks = load(root).await?;
+    let Some(commands) = hooks.get(hook) else {
+        tracing::debug!("No {} commands declared; nothing to do", hook);
+        return Ok(());
+    };
+    let binary = std::env::current_exe().context("Cannot locate the rcm binary")?;
+    for command in commands {
+        println!("{} {} {}", style("🪝").cyan(), style(hook).bold(), style(format!("rcm {}", command)).dim());
+        let mut cmd = tokio::process::Command::new(&binary);
+        cmd.args(split_args(command)?)
+            .current_dir(root)
+            .env("RCM_HOOK", hook)
+            .env("RCM_HOOK_ARGS", args.join(" "));
+        let status = cmd.status().await.with_context(|| format!("Failed to run `rcm {}`", command))?;
+        if !status.success() {
+            return Err(anyhow!(
+                "{} hook failed at `rcm {}`; fix it, or skip once with {}=1 / --no-verify",
+                hook, command, SKIP_ENV
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Handle `rcm hooks` commands
+pub async fn handle_command(workspace: &Workspace, cmd: HookCommands) -> Result<()> {
+    let root = workspace.root();
+    match cmd {
+        HookCommands::Install { force } => install(root, force).await,
+        HookCommands::Uninstall => {
+            let dir = hooks_dir(root)?;
+            let mut removed = 0;
+            for hook in HOOKS {
+                if is_managed(&dir.join(hook)) {
+                    remove(&dir, hook).await?;
+                    removed += 1;
+                }
+            }
+            if removed == 0 {
+                println!("No rcm-managed hooks in {}", dir.display());
+            }
+            Ok(())
+        }
+        HookCommands::List => {
+            let hooks = load(root).await?;
+            if hooks.is_empty() {
+                println!("No hooks declared; add them under [hooks] in {}", root.join(TASKS_FILE).display());
+                return Ok(());
+            }
+            let dir = hooks_dir(root).ok();
+            let rows: Vec<HookRow> = hooks.iter().map(|(hook, commands)| HookRow {
+                hook: hook.clone(),
+                commands: commands.join(", "),
+                installed: match &dir {
+                    Some(dir) if is_managed(&dir.join(hook)) => "✅".to_string(),
+                    Some(_) => "❌ run `rcm hooks install`".to_string(),
+                    None => "- (not a git repository)".to_string(),
+                },
+            }).collect();
+            println!("{}", Table::new(&rows));
+            Ok(())
+        }
+        HookCommands::Run { hook, args } => run(root, &hook, &args).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(split_args("rcm audit --fail-on high").unwrap(), vec!["audit", "--fail-on", "high"]);
+        assert_eq!(split_args(r#"run lint -- "a b" ''"#).unwrap(), vec!["run", "lint", "--", "a b", ""]);
+        assert!(split_args("run 'lint").is_err());
+    }
+
+    #[test]
+    fn test_sections() {
+        let section: HooksSection = toml::from_str("[hooks]\npre-commit = \"run lint\"\npre-push = [\"audit\", \"ensure\"]").unwrap();
+        let hooks = from_section(section).unwrap();
+        assert_eq!(hooks["pre-commit"], vec!["run lint"]);
+        assert_eq!(hooks["pre-push"].len(), 2);
+
+        let section: HooksSection = toml::from_str("[hooks]\nprecommit = \"audit\"").unwrap();
+        assert!(from_section(section).is_err());
+        assert!(script("pre-push", "rcm").contains(MARKER));
+    }
+}
@@ -0,0 +1,476 @@
+//! Go modules support for RCM
+//!
+//! Wraps the `go` tool for module management. go.mod and go.sum are read
+//! directly so listing, health checks and rcm.lock work without network access.
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tabled::{Table, Tabled};
+use tokio::fs;
+use crate::commands::letcond::parse_loose_version;
+use crate::toolchain;
+use crate::util::{self, execute_command, execute_mutation};
+use crate::workspace::Workspace;
+
+/// Default module proxy, used as the registry in rcm.lock
+pub const GO_PROXY: &str = "https://proxy.golang.org";
+
+#[derive(Subcommand)]
+pub enum GoCommands {
+    /// Add or upgrade module dependencies (module[@version])
+    Get {
+        /// Modules to get, e.g. github.com/spf13/cobra@v1.8.0
+        modules: Vec<String>,
+        /// Upgrade the modules' dependencies too (go get -u)
+        #[arg(short, long)]
+        update: bool,
+    },
+
+    /// Add missing and remove unused requirements (go mod tidy)
+    Tidy,
+
+    /// List required modules
+    List {
+        /// Include the full build list, not just go.mod requirements (needs the go tool)
+        #[arg(long)]
+        all: bool,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+
+    /// Copy dependencies into vendor/ (go mod vendor)
+    Vendor,
+
+    /// Verify downloaded modules against go.sum (go mod verify)
+    Verify,
+}
+
+/// A `require` line from go.mod
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoRequire {
+    pub path: String,
+    pub version: String,
+    pub indirect: bool,
+}
+
+/// A `replace` directive from go.mod
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GoReplace {
+    pub path: String,
+    pub version: Option<String>,
+    pub target: String,
+    pub target_version: Option<String>,
+}
+
+/// The parts of go.mod RCM cares about
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GoMod {
+    pub module: String,
+    pub go: Option<String>,
+    pub toolchain: Option<String>,
+    pub requires: Vec<GoRequire>,
+    pub replaces: Vec<GoReplace>,
+}
+
+/// A module hash from go.sum
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoSumEntry {
+    pub path: String,
+    pub version: String,
+    pub hash: String,
+}
+
+#[derive(Tabled)]
+struct ModuleRow {
+    #[tabled(rename = "Module")]
+    path: String,
+    #[tabled(rename = "Version")]
+    version: String,
+    #[tabled(rename = "Type")]
+    kind: String,
+}
+
+/// Strip a `//` comment, returning the code and whether it was `// indirect`
+fn split_comment(line: &str) -> (&str, bool) {
+    match line.split_once("//") {
+        Some((code, comment)) => (code.trim(), comment.trim() == "indirect"),
+        None => (line.trim(), false),
+    }
+}
+
+fn unquote(s: &str) -> String {
+    s.trim_matches(|c| c == '"' || c == '`').to_string()
+}
+
+/// Parse go.mod, including parenthesized `require (...)` and `replace (...)` blocks
+pub fn parse_go_mod(content: &str) -> GoMod {
+    let mut go_mod = GoMod::default();
+    let mut block: Option<&str> = None;
+
+    for raw in content.lines() {
+        let (line, indirect) = split_comment(raw);
+        if line.is_empty() {
+            continue;
+        }
+        if line == ")" {
+            block = None;
+            continue;
+        }
+
+        let (directive, rest) = match block {
+            Some(directive) => (directive, line),
+            None => {
+                let (directive, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+                let rest = rest.trim();
+                if rest == "(" {
+                    block = Some(directive);
+                    continue;
+                }
+                (directive, rest)
+            }
+        };
+
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+        match directive {
+            "module" => go_mod.module = unquote(rest),
+            "go" => go_mod.go = Some(rest.to_string()),
+            "toolchain" => go_mod.toolchain = Some(rest.to_string()),
+            "require" if fields.len() >= 2 => go_mod.requires.push(GoRequire {
+                path: unquote(fields[0]),
+                version: fields[1].to_string(),
+                indirect,
+            }),
+            "replace" => {
+                // `old [v] => new [v]`
+                let Some((old, new)) = rest.split_once("=>") else { continue };
+                let old: Vec<&str> = old.split_whitespace().collect();
+                let new: Vec<&str> = new.split_whitespace().collect();
+                if old.is_empty() || new.is_empty() {
+                    continue;
+                }
+                go_mod.replaces.push(GoReplace {
+                    path: unquote(old[0]),
+                    version: old.get(1).map(|v| v.to_string()),
+                    target: unquote(new[0]),
+                    target_version: new.get(1).map(|v| v.to_string()),
+                });
+            }
+            _ => {}
+        }
+    }
+    go_mod
+}
+
+/// Parse go.sum, keeping module archive hashes and skipping `/go.mod` hashes
+pub fn parse_go_sum(content: &str) -> Vec<GoSumEntry> {
+    let mut entries: Vec<GoSumEntry> = content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (path, version, hash) = (fields.next()?, fields.next()?, fields.next()?);
+            if version.ends_with("/go.mod") {
+                return None;
+            }
+            Some(GoSumEntry { path: path.to_string(), version: version.to_string(), hash: hash.to_string() })
+        })
+        .collect();
+    entries.sort_by(|a, b| (&a.path, &a.version).cmp(&(&b.path, &b.version)));
+    entries.dedup();
+    entries
+}
+
+/// Parse `go list -m -json all`, which prints a stream of JSON objects
+pub fn parse_go_list(output: &str) -> Result<Vec<GoRequire>> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Module {
+        path: String,
+        version: Option<String>,
+        #[serde(default)]
+        main: bool,
+        #[serde(default)]
+        indirect: bool,
+    }
+
+    let mut modules = Vec::new();
+    for module in serde_json::Deserializer::from_str(output).into_iter::<Module>() {
+        let module = module.context("Invalid go list output")?;
+        if module.main {
+            continue;
+        }
+        modules.push(GoRequire {
+            path: module.path,
+            version: module.version.unwrap_or_default(),
+            indirect: module.indirect,
+        });
+    }
+    Ok(modules)
+}
+
+/// Version from `go version` output (`go version go1.22.1 linux/amd64` → `1.22.1`)
+pub fn parse_go_version(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .find_map(|word| word.strip_prefix("go").filter(|v| v.starts_with(|c: char| c.is_ascii_digit())))
+        .map(str::to_string)
+}
+
+/// Whether the installed go satisfies go.mod's `go` directive (a minimum version)
+pub fn satisfies_go_directive(directive: &str, installed: &str) -> bool {
+    match (parse_loose_version(directive), parse_loose_version(installed)) {
+        (Some(required), Some(installed)) => installed >= required,
+        _ => true,
+    }
+}
+
+pub struct GoManager {
+    workspace_root: PathBuf,
+}
+
+impl GoManager {
+    pub fn new(workspace_root: &Path) -> Self {
+        Self {
+            workspace_root: workspace_root.to_path_buf(),
+        }
+    }
+
+    /// `go` in the workspace with pinned toolchain shims on PATH
+    fn go(&self) -> Command {
+        let mut cmd = Command::new("go");
+        cmd.current_dir(&self.workspace_root);
+        if let Some(path) = toolchain::shim_path(&self.workspace_root) {
+            cmd.env("PATH", path);
+        }
+        cmd
+    }
+
+    pub fn go_mod_path(&self) -> PathBuf {
+        self.workspace_root.join("go.mod")
+    }
+
+    pub fn has_module(&self) -> bool {
+        self.go_mod_path().exists()
+    }
+
+    pub async fn check_environment(&self) -> Result<()> {
+        if !util::command_exists("go").await {
+            return Err(anyhow!("Go is not installed or not in PATH. Install from https://go.dev/dl/"));
+        }
+        if !self.has_module() {
+            return Err(anyhow!("No go.mod found. Run 'go mod init <module>' first"));
+        }
+        Ok(())
+    }
+
+    /// Installed go version
+    pub async fn version(&self) -> Option<String> {
+        let output = self.go().arg("version").output().ok()?;
+        parse_go_version(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    pub async fn load_go_mod(&self) -> Result<GoMod> {
+        let content = fs::read_to_string(self.go_mod_path()).await
+            .context("Failed to read go.mod")?;
+        Ok(parse_go_mod(&content))
+    }
+
+    pub async fn load_go_sum(&self) -> Result<Vec<GoSumEntry>> {
+        let path = self.workspace_root.join("go.sum");
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&path).await
+            .context("Failed to read go.sum")?;
+        Ok(parse_go_sum(&content))
+    }
+
+    pub async fn get(&self, modules: &[String], update: bool) -> Result<()> {
+        self.check_environment().await?;
+        let mut cmd = self.go();
+        cmd.arg("get");
+        if update {
+            cmd.arg("-u");
+        }
+        if modules.is_empty() {
+            cmd.arg("./...");
+        } else {
+            cmd.args(modules);
+        }
+        execute_mutation(&mut cmd).await.context("go get failed")?;
+        Ok(())
+    }
+
+    pub async fn tidy(&self) -> Result<()> {
+        self.check_environment().await?;
+        execute_mutation(self.go().args(["mod", "tidy"])).await.context("go mod tidy failed")?;
+        Ok(())
+    }
+
+    pub async fn download(&self) -> Result<()> {
+        self.check_environment().await?;
+        execute_mutation(self.go().args(["mod", "download"])).await.context("go mod download failed")?;
+        Ok(())
+    }
+
+    pub async fn vendor(&self) -> Result<()> {
+        self.check_environment().await?;
+        execute_mutation(self.go().args(["mod", "vendor"])).await.context("go mod vendor failed")?;
+        Ok(())
+    }
+
+    /// `go mod verify`; returns the modules whose downloads do not match go.sum
+    pub async fn verify(&self) -> Result<Vec<String>> {
+        self.check_environment().await?;
+        let output = self.go().args(["mod", "verify"]).output().context("Failed to run go mod verify")?;
+        if output.status.success() {
+            return Ok(Vec::new());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Ok(stderr.lines().filter(|l| !l.trim().is_empty()).map(str::to_string).collect())
+    }
+
+    /// Upgrade every dependency and tidy go.mod
+    pub async fn update(&self) -> Result<()> {
+        self.get(&[], true).await?;
+        self.tidy().await
+    }
+
+    /// Remove build artifacts of the main module
+    pub async fn clean(&self) -> Result<()> {
+        if !self.has_module() || !util::command_exists("go").await {
+            return Ok(());
+        }
+        execute_mutation(self.go().arg("clean")).await.context("go clean failed")?;
+        Ok(())
+    }
+
+    /// Required modules: go.mod requirements, or the full build list with `all`
+    pub async fn list(&self, all: bool) -> Result<Vec<GoRequire>> {
+        if !all {
+            return Ok(self.load_go_mod().await?.requires);
+        }
+        self.check_environment().await?;
+        let output = execute_command(self.go().args(["list", "-m", "-json", "all"])).await
+            .context("go list failed")?;
+        parse_go_list(&output.stdout)
+    }
+}
+
+/// Handle Go commands
+pub async fn handle_command(workspace: &Workspace, cmd: GoCommands) -> Result<()> {
+    let go = GoManager::new(workspace.root());
+    match cmd {
+        GoCommands::Get { modules, update } => {
+            println!("{}", style("🐹 Getting Go modules...").cyan().bold());
+            go.get(&modules, update).await?;
+            println!("{}", style("✅ go.mod updated").green().bold());
+            Ok(())
+        }
+
+        GoCommands::Tidy => {
+            go.tidy().await?;
+            println!("{}", style("✅ go.mod and go.sum tidied").green().bold());
+            Ok(())
+        }
+
+        GoCommands::List { all, format } => {
+            let modules = go.list(all).await?;
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&modules)?),
+                "table" => {
+                    if modules.is_empty() {
+                        println!("No module requirements.");
+                        return Ok(());
+                    }
+                    let rows: Vec<ModuleRow> = modules
+                        .into_iter()
+                        .map(|m| ModuleRow {
+                            path: m.path,
+                            version: m.version,
+                            kind: if m.indirect { "indirect" } else { "direct" }.to_string(),
+                        })
+                        .collect();
+                    println!("{}", Table::new(rows));
+                }
+                _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+            }
+            Ok(())
+        }
+
+        GoCommands::Vendor => {
+            go.vendor().await?;
+            println!("{}", style("✅ Dependencies copied to vendor/").green().bold());
+            Ok(())
+        }
+
+        GoCommands::Verify => {
+            let problems = go.verify().await?;
+            if problems.is_empty() {
+                println!("{}", style("✅ All modules verified against go.sum").green().bold());
+                return Ok(());
+            }
+            for problem in &problems {
+                println!("  {} {}", style("✗").red(), problem);
+            }
+            Err(anyhow!("{} module(s) failed verification", problems.len()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_go_mod() {
+        let content = r#"module github.com/acme/api // main service
+
+go 1.22
+
+toolchain go1.22.3
+
+require github.com/spf13/cobra v1.8.0
+
+require (
+	golang.org/x/sync v0.7.0
+	github.com/inconshreveable/mousetrap v1.1.0 // indirect
+)
+
+replace github.com/acme/lib v1.0.0 => ../lib
+"#;
+        let go_mod = parse_go_mod(content);
+        assert_eq!(go_mod.module, "github.com/acme/api");
+        assert_eq!(go_mod.go.as_deref(), Some("1.22"));
+        assert_eq!(go_mod.toolchain.as_deref(), Some("go1.22.3"));
+        assert_eq!(go_mod.requires.len(), 3);
+        assert_eq!(go_mod.requires[1].path, "golang.org/x/sync");
+        assert!(go_mod.requires[2].indirect && !go_mod.requires[0].indirect);
+        assert_eq!(go_mod.replaces[0].target, "../lib");
+        assert_eq!(go_mod.replaces[0].version.as_deref(), Some("v1.0.0"));
+    }
+
+    #[test]
+    fn test_parse_go_sum_and_versions() {
+        let content = "golang.org/x/sync v0.7.0 h1:abc=\ngolang.org/x/sync v0.7.0/go.mod h1:def=\n";
+        assert_eq!(parse_go_sum(content), vec![GoSumEntry {
+            path: "golang.org/x/sync".to_string(),
+            version: "v0.7.0".to_string(),
+            hash: "h1:abc=".to_string(),
+        }]);
+
+        assert_eq!(parse_go_version("go version go1.21.5 linux/amd64").as_deref(), Some("1.21.5"));
+        assert!(satisfies_go_directive("1.21", "1.21.5"));
+        assert!(!satisfies_go_directive("1.22", "1.21.5"));
+
+        let list = r#"{"Path": "github.com/acme/api", "Main": true}
+{"Path": "golang.org/x/sync", "Version": "v0.7.0", "Indirect": true}"#;
+        let modules = parse_go_list(list).unwrap();
+        assert_eq!(modules.len(), 1);
+        assert!(modules[0].indirect);
+    }
+}
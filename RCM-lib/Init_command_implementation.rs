@@ -6,15 +6,18 @@ use anyhow::{anyhow, Result};
 use console::style;
 use dialoguer::{Confirm, Input, MultiSelect, Select};
 use std::collections::HashMap;
+use crate::templates::{self, DirTemplate, Template, TemplateContext, TemplateManifest, TemplateSource};
 use crate::workspace::Workspace;
 
 /// Initialize RCM workspace
 pub async fn run(
     workspace: &Workspace, 
     managers: Option<Vec<String>>, 
-    template: &str
+    template: &str,
+    vars: &[String],
 ) -> Result<()> {
     println!("{}", style("🚀 Initializing RCM workspace...").cyan().bold());
+    let source = TemplateSource::parse(template)?;
     
     // Check if workspace is already initialized
     let rcm_dir = workspace.root().join(".rcm");
@@ -30,36 +33,84 @@ pub async fn run(
         }
     }
     
-    // Interactive setup if no managers specified
+    match source {
+        TemplateSource::Builtin(name) => scaffold(workspace, &BuiltinTemplate::new(&name), managers, vars).await,
+        TemplateSource::Dir(dir) => scaffold(workspace, &DirTemplate::open(&dir).await?, managers, vars).await,
+        TemplateSource::Git { url, reference } => {
+            let template = DirTemplate::fetch(&url, reference.as_deref()).await?;
+            scaffold(workspace, &template, managers, vars).await
+        }
+    }
+}
+
+/// One of the scaffolds built into RCM
+struct BuiltinTemplate {
+    manifest: TemplateManifest,
+}
+
+impl BuiltinTemplate {
+    fn new(name: &str) -> Self {
+        let managers = match name {
+            // The scaffold is Python plus a system-level model backend
+            "ai" => vec!["python".to_string(), "system".to_string()],
+            _ => Vec::new(),
+        };
+        Self { manifest: TemplateManifest { name: name.to_string(), managers, ..Default::default() } }
+    }
+}
+
+impl Template for BuiltinTemplate {
+    fn manifest(&self) -> &TemplateManifest {
+        &self.manifest
+    }
+
+    async fn apply(&self, workspace: &Workspace, context: &TemplateContext) -> Result<()> {
+        create_template_files(workspace, &self.manifest.name, &context.managers).await
+    }
+}
+
+/// Set up the workspace and apply a template to it
+async fn scaffold<T: Template>(
+    workspace: &Workspace,
+    template: &T,
+    managers: Option<Vec<String>>,
+    vars: &[String],
+) -> Result<()> {
+    let manifest = template.manifest();
+    
+    // Interactive setup if neither --managers nor the template chooses them
     let selected_managers = if let Some(mgrs) = managers {
         mgrs
+    } else if !manifest.managers.is_empty() {
+        manifest.managers.clone()
     } else {
         interactive_manager_selection().await?
     };
     
-    // Validate template
-    let templates = vec!["rust", "node", "php", "polyglot"];
-    if !templates.contains(&template) {
-        return Err(anyhow!("Invalid template '{}'. Available: {}", template, templates.join(", ")));
+    println!("{}", style(format!("📋 Using template: {}", manifest.name)).green());
+    if let Some(description) = &manifest.description {
+        println!("   {}", style(description).dim());
     }
-    
-    println!("{}", style(format!("📋 Using template: {}", template)).green());
     println!("{}", style(format!("📦 Selected managers: {}", selected_managers.join(", "))).green());
     
     // Create workspace clone for modification
     let mut workspace_mut = workspace.clone();
     
     // Initialize workspace
-    workspace_mut.initialize(Some(selected_managers.clone()), template).await?;
+    workspace_mut.initialize(Some(selected_managers.clone()), &manifest.name).await?;
     
     // Create initial files based on template
-    create_template_files(workspace, template, &selected_managers).await?;
+    let context = TemplateContext {
+        vars: templates::variables(workspace, manifest, vars).await?,
+        managers: selected_managers.clone(),
+    };
+    template.apply(workspace, &context).await?;
     
     // Create .gitignore if it doesn't exist
     create_gitignore(workspace).await?;
     
     // Create README if it doesn't exist
-    create_readme(workspace, template, &selected_managers).await?;
+    create_readme(workspace, &manifest.name, &selected_managers).await?;
     
     println!("{}", style("✅ RCM workspace initialized successfully!").green().bold());
     println!();
@@ -79,13 +130,15 @@ async fn interactive_manager_selection() -> Result<Vec<String>> {
         ("cargo", "Rust package manager"),
         ("npm", "Node.js package manager"),
         ("composer", "PHP package manager"),
+        ("python", "Python package manager (pip, uv, poetry)"),
+        ("go", "Go modules"),
         ("system", "System package manager (apt, yum, brew, etc.)"),
     ];
     
     let selections = MultiSelect::new()
         .with_prompt("Package managers")
         .items(&available_managers.iter().map(|(name, desc)| format!("{} - {}", name, desc)).collect::<Vec<_>>())
-        .defaults(&[false, false, false, true]) // System manager enabled by default
+        .defaults(&[false, false, false, false, false, true]) // System manager enabled by default
         .interact()?;
     
     let selected: Vec<String> = selections
@@ -109,7 +162,12 @@ async fn create_template_files(
     match template {
         "rust" => create_rust_files(workspace).await?,
         "node" => create_node_files(workspace).await?,
+        "bun" => create_bun_files(workspace).await?,
+        "frontend" => create_frontend_files(workspace).await?,
         "php" => create_php_files(workspace).await?,
+        "python" => create_python_files(workspace).await?,
+        "go" => create_go_files(workspace).await?,
+        "ai" => create_ai_files(workspace).await?,
         "polyglot" => {
             if managers.contains(&"cargo".to_string()) {
                 create_rust_files(workspace).await?;
@@ -120,6 +178,12 @@ async fn create_template_files(
             if managers.contains(&"composer".to_string()) {
                 create_php_files(workspace).await?;
             }
+            if managers.contains(&"python".to_string()) {
+                create_python_files(workspace).await?;
+            }
+            if managers.contains(&"go".to_string()) {
+                create_go_files(workspace).await?;
+            }
             create_polyglot_files(workspace).await?;
         }
         _ => return Err(anyhow!("Unknown template: {}", template)),
@@ -235,6 +299,192 @@ module.exports = { main };
     Ok(())
 }
 
+/// Create Bun-specific files
+async fn create_bun_files(workspace: &Workspace) -> Result<()> {
+    let package_json = workspace.root().join("package.json");
+    if !package_json.exists() {
+        let workspace_name = workspace.root()
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("bun-project");
+        
+        // Record bun in packageManager so RCM picks it before a bun.lockb exists
+        let package_manager = match bun_version().await {
+            Some(version) => format!("\n  \"packageManager\": \"bun@{}\",", version),
+            None => String::new(),
+        };
+        
+        let content = format!(r#"{{
+  "name": "{}",
+  "version": "1.0.0",
+  "description": "A Bun project managed by RCM",
+  "module": "index.ts",
+  "type": "module",{}
+  "scripts": {{
+    "start": "bun run index.ts",
+    "test": "bun test",
+    "dev": "bun --watch index.ts"
+  }},
+  "keywords": ["rcm", "bun"],
+  "author": "",
+  "license": "MIT",
+  "dependencies": {{}},
+  "devDependencies": {{}}
+}}
+"#, workspace_name, package_manager);
+        
+        tokio::fs::write(package_json, content).await?;
+        println!("{}", style("📄 Created package.json").green());
+    }
+    
+    let index_ts = workspace.root().join("index.ts");
+    if !index_ts.exists() {
+        let content = r#"console.log('Hello from RCM Bun project!');
+
+export async function main(): Promise<void> {
+    console.log('Starting application...');
+    // Your application logic here
+}
+
+if (import.meta.main) {
+    await main();
+}
+"#;
+        tokio::fs::write(index_ts, content).await?;
+        println!("{}", style("📄 Created index.ts").green());
+    }
+    
+    Ok(())
+}
+
+/// Installed bun version, if bun is on PATH
+async fn bun_version() -> Option<String> {
+    let output = tokio::process::Command::new("bun").arg("--version").output().await.ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Create Vite + TypeScript frontend files
+async fn create_frontend_files(workspace: &Workspace) -> Result<()> {
+    let root = workspace.root();
+    let workspace_name = root
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("frontend");
+    
+    let package_json = format!(r#"{{
+  "name": "{}",
+  "private": true,
+  "version": "0.0.0",
+  "type": "module",
+  "scripts": {{
+    "dev": "vite",
+    "build": "tsc && vite build",
+    "preview": "vite preview",
+    "lint": "eslint . && tsc --noEmit"
+  }},
+  "engines": {{
+    "node": ">=18"
+  }},
+  "devDependencies": {{
+    "@eslint/js": "^9.0.0",
+    "eslint": "^9.0.0",
+    "typescript": "^5.4.0",
+    "typescript-eslint": "^8.0.0",
+    "vite": "^5.2.0"
+  }}
+}}
+"#, workspace_name);
+    
+    let files = [
+        ("package.json", package_json),
+        ("index.html", format!(r#"<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="UTF-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1.0" />
+    <title>{}</title>
+  </head>
+  <body>
+    <div id="app"></div>
+    <script type="module" src="/src/main.ts"></script>
+  </body>
+</html>
+"#, workspace_name)),
+        ("src/main.ts", r#"const app = document.querySelector<HTMLDivElement>('#app')!;
+
+app.innerHTML = `<h1>Hello from RCM + Vite!</h1>`;
+"#.to_string()),
+        ("vite.config.ts", r#"import { defineConfig } from 'vite';
+
+export default defineConfig({
+  build: {
+    outDir: 'dist',
+    sourcemap: true,
+  },
+});
+"#.to_string()),
+        ("tsconfig.json", r#"{
+  "compilerOptions": {
+    "target": "ES2020",
+    "module": "ESNext",
+    "moduleResolution": "bundler",
+    "lib": ["ES2020", "DOM", "DOM.Iterable"],
+    "strict": true,
+    "noEmit": true,
+    "skipLibCheck": true,
+    "isolatedModules": true
+  },
+  "include": ["src"]
+}
+"#.to_string()),
+        ("eslint.config.js", r#"import js from '@eslint/js';
+import tseslint from 'typescript-eslint';
+
+export default tseslint.config(
+  { ignores: ['dist'] },
+  js.configs.recommended,
+  ...tseslint.configs.recommended,
+);
+"#.to_string()),
+        // Task runner entry points for the asset pipeline
+        ("Makefile", r#".PHONY: install dev build preview lint check
+
+install:
+	@rcm ensure
+
+dev:
+	@rcm npm run dev
+
+build:
+	@rcm npm run build
+
+preview: build
+	@rcm npm run preview
+
+lint:
+	@rcm npm run lint
+
+# Validates the node version against package.json engines
+check:
+	@rcm workspace check
+"#.to_string()),
+    ];
+    
+    for (name, content) in files {
+        let path = root.join(name);
+        if path.exists() {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, content).await?;
+        println!("{}", style(format!("📄 Created {}", name)).green());
+    }
+    
+    Ok(())
+}
+
 /// Create PHP-specific files
 async fn create_php_files(workspace: &Workspace) -> Result<()> {
     let composer_json = workspace.root().join("composer.json");
@@ -326,6 +576,437 @@ $app->run();
     Ok(())
 }
 
+/// Create Python-specific files
+async fn create_python_files(workspace: &Workspace) -> Result<()> {
+    let pyproject = workspace.root().join("pyproject.toml");
+    if !pyproject.exists() {
+        let workspace_name = workspace.root()
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("python-project");
+        
+        let content = format!(r#"[project]
+name = "{}"
+version = "0.1.0"
+description = "A Python project managed by RCM"
+readme = "README.md"
+requires-python = ">=3.10"
+dependencies = []
+
+[dependency-groups]
+dev = ["pytest>=8"]
+
+[tool.pytest.ini_options]
+testpaths = ["tests"]
+"#, workspace_name);
+        
+        tokio::fs::write(pyproject, content).await?;
+        println!("{}", style("📄 Created pyproject.toml").green());
+    }
+    
+    let package_dir = workspace.root().join("src").join("app");
+    if !package_dir.exists() {
+        tokio::fs::create_dir_all(&package_dir).await?;
+        tokio::fs::write(package_dir.join("__init__.py"), "").await?;
+        tokio::fs::write(
+            package_dir.join("__main__.py"),
+            "def main() -> None:\n    print(\"Hello from RCM Python project!\")\n\n\nif __name__ == \"__main__\":\n    main()\n",
+        ).await?;
+        println!("{}", style("📁 Created src/app/").green());
+    }
+    
+    Ok(())
+}
+
+/// Create Go-specific files
+async fn create_go_files(workspace: &Workspace) -> Result<()> {
+    let go_mod = workspace.root().join("go.mod");
+    if !go_mod.exists() {
+        let workspace_name = workspace.root()
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("go-project");
+        
+        let content = format!("module example.com/{}\n\ngo 1.22\n", workspace_name);
+        tokio::fs::write(go_mod, content).await?;
+        println!("{}", style("📄 Created go.mod").green());
+    }
+    
+    let main_go = workspace.root().join("cmd").join("app").join("main.go");
+    if !main_go.exists() {
+        tokio::fs::create_dir_all(main_go.parent().unwrap()).await?;
+        tokio::fs::write(
+            &main_go,
+            "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tfmt.Println(\"Hello from RCM Go project!\")\n}\n",
+        ).await?;
+        println!("{}", style("📄 Created cmd/app/main.go").green());
+    }
+    
+    Ok(())
+}
+
+/// Create AI/RAG application files wired to `rcm gpt`
+async fn create_ai_files(workspace: &Workspace) -> Result<()> {
+    let root = workspace.root();
+    let workspace_name = root
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("ai-app");
+    
+    // Default served model; the app reads it, the LET specs provision it
+    let ai_toml = root.join("ai.toml");
+    if !ai_toml.exists() {
+        let content = r#"# Model the app talks to, served by `rcm gpt serve`
+# Both Ollama and llama-server expose the OpenAI-compatible /v1 API used here.
+[model]
+name = "llama3.2"
+backend = "ollama"
+base_url = "http://127.0.0.1:11434/v1"
+temperature = 0.2
+max_tokens = 512
+
+[embeddings]
+model = "nomic-embed-text"
+
+[rag]
+# Files under these directories are chunked and embedded by `rcm let rag-index --deploy`
+sources = ["docs"]
+extensions = [".md", ".txt", ".rst"]
+chunk_chars = 1200
+chunk_overlap = 200
+top_k = 4
+index = ".rcm/cache/rag-index.json"
+"#;
+        tokio::fs::write(ai_toml, content).await?;
+        println!("{}", style("📄 Created ai.toml").green());
+    }
+    
+    let pyproject = root.join("pyproject.toml");
+    if !pyproject.exists() {
+        let content = format!(r#"[project]
+name = "{}"
+version = "0.1.0"
+description = "An LLM application served by RCM"
+readme = "README.md"
+requires-python = ">=3.11"
+dependencies = []
+
+[project.scripts]
+ask = "app.__main__:main"
+
+[dependency-groups]
+dev = ["pytest>=8"]
+
+[tool.pytest.ini_options]
+testpaths = ["tests"]
+"#, workspace_name);
+        tokio::fs::write(pyproject, content).await?;
+        println!("{}", style("📄 Created pyproject.toml").green());
+    }
+    
+    let package_dir = root.join("src").join("app");
+    if !package_dir.exists() {
+        tokio::fs::create_dir_all(&package_dir).await?;
+        tokio::fs::write(package_dir.join("__init__.py"), "").await?;
+        
+        let gateway = r#""""Client for the model gateway started by `rcm gpt serve`.
+
+Standard library only: chat completions and embeddings over the
+OpenAI-compatible API that Ollama and llama-server both expose.
+"""
+
+import json
+import os
+import tomllib
+import urllib.request
+from pathlib import Path
+
+CONFIG_PATH = Path(os.environ.get("AI_CONFIG", "ai.toml"))
+
+
+def load_config(path: Path = CONFIG_PATH) -> dict:
+    with path.open("rb") as f:
+        return tomllib.load(f)
+
+
+class Gateway:
+    def __init__(self, config: dict | None = None):
+        self.config = config or load_config()
+        model = self.config["model"]
+        self.base_url = os.environ.get("AI_BASE_URL", model["base_url"]).rstrip("/")
+        self.model = model["name"]
+        self.embedding_model = self.config.get("embeddings", {}).get("model", self.model)
+        self.token = os.environ.get("AI_API_TOKEN")
+
+    def _post(self, path: str, body: dict) -> dict:
+        request = urllib.request.Request(
+            f"{self.base_url}{path}",
+            data=json.dumps(body).encode(),
+            headers={"Content-Type": "application/json"},
+        )
+        if self.token:
+            request.add_header("Authorization", f"Bearer {self.token}")
+        with urllib.request.urlopen(request, timeout=120) as response:
+            return json.load(response)
+
+    def chat(self, messages: list[dict], **options) -> str:
+        model = self.config["model"]
+        body = {
+            "model": self.model,
+            "messages": messages,
+            "temperature": options.get("temperature", model.get("temperature", 0.2)),
+            "max_tokens": options.get("max_tokens", model.get("max_tokens", 512)),
+        }
+        return self._post("/chat/completions", body)["choices"][0]["message"]["content"]
+
+    def embed(self, texts: list[str]) -> list[list[float]]:
+        data = self._post("/embeddings", {"model": self.embedding_model, "input": texts})["data"]
+        return [item["embedding"] for item in sorted(data, key=lambda d: d["index"])]
+"#;
+        tokio::fs::write(package_dir.join("gateway.py"), gateway).await?;
+        
+        let rag_index = r#""""Build the retrieval index: chunk the configured sources and embed them."""
+
+import json
+import math
+from pathlib import Path
+
+from app.gateway import Gateway, load_config
+
+
+def chunks(text: str, size: int, overlap: int) -> list[str]:
+    step = max(size - overlap, 1)
+    return [text[i:i + size] for i in range(0, max(len(text) - overlap, 1), step) if text[i:i + size].strip()]
+
+
+def build(config: dict | None = None) -> int:
+    config = config or load_config()
+    rag = config["rag"]
+    gateway = Gateway(config)
+    entries = []
+    for source in rag["sources"]:
+        for path in sorted(Path(source).rglob("*")):
+            if not path.is_file() or path.suffix not in rag["extensions"]:
+                continue
+            pieces = chunks(path.read_text(errors="ignore"), rag["chunk_chars"], rag["chunk_overlap"])
+            for n, (piece, vector) in enumerate(zip(pieces, gateway.embed(pieces) if pieces else [])):
+                entries.append({"source": f"{path}#{n}", "text": piece, "embedding": vector})
+    index = Path(rag["index"])
+    index.parent.mkdir(parents=True, exist_ok=True)
+    index.write_text(json.dumps({"model": gateway.embedding_model, "entries": entries}))
+    return len(entries)
+
+
+def search(query: str, config: dict | None = None) -> list[dict]:
+    config = config or load_config()
+    rag = config["rag"]
+    index = json.loads(Path(rag["index"]).read_text())
+    [vector] = Gateway(config).embed([query])
+
+    def cosine(a: list[float], b: list[float]) -> float:
+        dot = sum(x * y for x, y in zip(a, b))
+        norm = math.sqrt(sum(x * x for x in a)) * math.sqrt(sum(y * y for y in b))
+        return dot / norm if norm else 0.0
+
+    ranked = sorted(index["entries"], key=lambda e: cosine(vector, e["embedding"]), reverse=True)
+    return ranked[: rag["top_k"]]
+
+
+if __name__ == "__main__":
+    print(f"Indexed {build()} chunks")
+"#;
+        tokio::fs::write(package_dir.join("rag_index.py"), rag_index).await?;
+        
+        let main = r#""""Answer a question from the indexed documents: python -m app "question"."""
+
+import sys
+
+from app.gateway import Gateway
+from app.rag_index import search
+
+SYSTEM = "Answer using only the provided context. Say so when the context does not contain the answer."
+
+
+def main() -> None:
+    question = " ".join(sys.argv[1:]) or input("Question: ")
+    context = "\n\n".join(f"[{hit['source']}]\n{hit['text']}" for hit in search(question))
+    answer = Gateway().chat([
+        {"role": "system", "content": SYSTEM},
+        {"role": "user", "content": f"Context:\n{context}\n\nQuestion: {question}"},
+    ])
+    print(answer)
+
+
+if __name__ == "__main__":
+    main()
+"#;
+        tokio::fs::write(package_dir.join("__main__.py"), main).await?;
+        println!("{}", style("📁 Created src/app/ (gateway client, RAG index, CLI)").green());
+    }
+    
+    let tests_dir = root.join("tests");
+    if !tests_dir.exists() {
+        tokio::fs::create_dir_all(&tests_dir).await?;
+        tokio::fs::write(
+            tests_dir.join("test_chunks.py"),
+            "from app.rag_index import chunks\n\n\ndef test_chunks_overlap():\n    pieces = chunks(\"abcdefghij\", 4, 2)\n    assert pieces[0] == \"abcd\"\n    assert pieces[1].startswith(\"cd\")\n",
+        ).await?;
+        println!("{}", style("📁 Created tests/").green());
+    }
+    
+    let docs_dir = root.join("docs");
+    if !docs_dir.exists() {
+        tokio::fs::create_dir_all(&docs_dir).await?;
+        tokio::fs::write(
+            docs_dir.join("getting-started.md"),
+            format!("# {}\n\nReplace this file with the documents the app should answer from.\n\
+                Re-run `rcm let rag-index --deploy` after changing them.\n", workspace_name),
+        ).await?;
+        println!("{}", style("📁 Created docs/").green());
+    }
+    
+    // LET specs: provision a backend, then build the index against it
+    let let_dir = root.join(".rcm").join("let");
+    tokio::fs::create_dir_all(&let_dir).await?;
+    let specs = [
+        ("ollama", r#"{
+  "target": "ollama",
+  "version": null,
+  "manager": "system",
+  "dependencies": [],
+  "environment": {},
+  "actions": [
+    {
+      "name": "install",
+      "command": "sh",
+      "args": ["-c", "curl -fsSL https://ollama.com/install.sh | sh"],
+      "working_dir": null,
+      "env": {},
+      "conditions": [{ "condition_type": "Expression", "value": "!command_exists(ollama)" }],
+      "parallel": false
+    },
+    {
+      "name": "chat-model",
+      "command": "rcm",
+      "args": ["gpt", "install", "llama3.2", "--source", "ollama"],
+      "working_dir": null,
+      "env": {},
+      "conditions": [],
+      "parallel": false
+    },
+    {
+      "name": "embedding-model",
+      "command": "ollama",
+      "args": ["pull", "nomic-embed-text"],
+      "working_dir": null,
+      "env": {},
+      "conditions": [],
+      "parallel": false
+    },
+    {
+      "name": "serve",
+      "command": "rcm",
+      "args": ["gpt", "serve", "llama3.2", "--deploy", "--backend", "ollama"],
+      "working_dir": null,
+      "env": {},
+      "conditions": [],
+      "parallel": false
+    }
+  ],
+  "constraints": {
+    "platforms": ["linux", "macos"],
+    "min_memory_mb": 8192,
+    "required_commands": ["curl"],
+    "required_env_vars": []
+  }
+}
+"#),
+        ("llamacpp", r#"{
+  "target": "llamacpp",
+  "version": null,
+  "manager": null,
+  "dependencies": [],
+  "environment": {},
+  "actions": [
+    {
+      "name": "model",
+      "command": "rcm",
+      "args": ["gpt", "install", "bartowski/Llama-3.2-3B-Instruct-GGUF", "--source", "huggingface", "--include", "Q4_K_M"],
+      "working_dir": null,
+      "env": {},
+      "conditions": [],
+      "parallel": false
+    },
+    {
+      "name": "serve",
+      "command": "rcm",
+      "args": ["gpt", "serve", "bartowski/Llama-3.2-3B-Instruct-GGUF", "--deploy", "--backend", "llamacpp", "--port", "8080"],
+      "working_dir": null,
+      "env": {},
+      "conditions": [],
+      "parallel": false
+    }
+  ],
+  "constraints": {
+    "platforms": ["linux", "macos", "windows"],
+    "min_memory_mb": 4096,
+    "required_commands": ["llama-server"],
+    "required_env_vars": []
+  }
+}
+"#),
+        ("rag-index", r#"{
+  "target": "rag-index",
+  "version": null,
+  "manager": null,
+  "dependencies": ["ollama"],
+  "environment": {},
+  "actions": [
+    {
+      "name": "build",
+      "command": "python3",
+      "args": ["-m", "app.rag_index"],
+      "working_dir": null,
+      "env": { "PYTHONPATH": "src" },
+      "conditions": [{ "condition_type": "Expression", "value": "file_exists(\"docs/**/*\")" }],
+      "parallel": false
+    }
+  ],
+  "constraints": {
+    "platforms": ["linux", "macos", "windows"],
+    "min_memory_mb": null,
+    "required_commands": ["python3"],
+    "required_env_vars": []
+  }
+}
+"#),
+    ];
+    for (target, spec) in specs {
+        let path = let_dir.join(format!("{}.json", target));
+        if !path.exists() {
+            tokio::fs::write(&path, spec).await?;
+            println!("{}", style(format!("📄 Created .rcm/let/{}.json", target)).green());
+        }
+    }
+    
+    // Prompt preset for `rcm gpt generate --template rag-answer`
+    let prompts_dir = root.join(".rcm").join("gpt-configs").join("prompts");
+    let prompt = prompts_dir.join("rag-answer.toml");
+    if !prompt.exists() {
+        tokio::fs::create_dir_all(&prompts_dir).await?;
+        tokio::fs::write(&prompt, r#"description = "Answer a question from a context file"
+system = "Answer using only the provided context. Say so when the context does not contain the answer."
+template = "Context:\n{{context | contents}}\n\nQuestion: {{question}}"
+model = "llama3.2"
+max_tokens = 512
+temperature = 0.2
+"#).await?;
+        println!("{}", style("📄 Created .rcm/gpt-configs/prompts/rag-answer.toml").green());
+    }
+    
+    Ok(())
+}
+
 /// Create polyglot-specific files
 async fn create_polyglot_files(workspace: &Workspace) -> Result<()> {
     // Create Makefile for unified commands
@@ -344,6 +1025,7 @@ build:
 	@if [ -f "Cargo.toml" ]; then cargo build; fi
 	@if [ -f "package.json" ]; then npm run build 2>/dev/null || true; fi
 	@if [ -f "composer.json" ]; then composer install --optimize-autoloader; fi
+	@if [ -f "go.mod" ]; then go build ./...; fi
 
 # Run tests for all projects
 test:
@@ -351,6 +1033,8 @@ test:
 	@if [ -f "Cargo.toml" ]; then cargo test; fi
 	@if [ -f "package.json" ]; then npm test 2>/dev/null || true; fi
 	@if [ -f "composer.json" ]; then composer run test 2>/dev/null || true; fi
+	@if [ -f "pyproject.toml" ]; then .venv/bin/python -m pytest 2>/dev/null || true; fi
+	@if [ -f "go.mod" ]; then go test ./...; fi
 
 # Clean all build artifacts
 clean:
@@ -358,6 +1042,7 @@ clean:
 	@if [ -d "target" ]; then rm -rf target; fi
 	@if [ -d "node_modules" ]; then rm -rf node_modules; fi
 	@if [ -d "vendor" ]; then rm -rf vendor; fi
+	@if [ -d ".venv" ]; then rm -rf .venv; fi
 
 # Run development server (detects project type)
 dev:
@@ -374,6 +1059,8 @@ status:
 	@if [ -f "Cargo.toml" ]; then echo "🦀 Rust project detected"; fi
 	@if [ -f "package.json" ]; then echo "📦 Node.js project detected"; fi
 	@if [ -f "composer.json" ]; then echo "🐘 PHP project detected"; fi
+	@if [ -f "pyproject.toml" ]; then echo "🐍 Python project detected"; fi
+	@if [ -f "go.mod" ]; then echo "🐹 Go project detected"; fi
 "#;
         tokio::fs::write(makefile, content).await?;
         println!("{}", style("📄 Created Makefile").green());
@@ -433,6 +1120,7 @@ async fn create_gitignore(workspace: &Workspace) -> Result<()> {
         let content = r#"# RCM
 .rcm/cache/
 .rcm/temp/
+.rcm/logs/
 
 # Rust
 /target/
@@ -448,6 +1136,7 @@ yarn-error.log*
 .eslintcache
 package-lock.json
 yarn.lock
+dist/
 
 # PHP
 /vendor/
@@ -580,6 +1269,8 @@ This project uses the following package managers:
                 "cargo" => "🦀 **Rust** - System programming language",
                 "npm" => "📦 **Node.js** - JavaScript runtime and package ecosystem",
                 "composer" => "🐘 **PHP** - Web development and scripting",
+                "python" => "🐍 **Python** - pip, uv or poetry managed packages",
+                "go" => "🐹 **Go** - Go modules",
                 "system" => "🔧 **System** - OS-level packages and dependencies",
                 _ => &format!("📋 **{}** - Package manager", manager),
             };
@@ -611,7 +1302,22 @@ cargo run
 "#);
         }
         
-        if managers.contains(&"npm".to_string()) {
+        if template == "frontend" {
+            content.push_str(r#"
+# Frontend commands
+make dev       # Vite dev server
+make build     # Type-check and bundle into dist/
+make preview   # Serve the production build
+make lint      # ESLint + tsc
+"#);
+        } else if template == "bun" {
+            content.push_str(r#"
+# Bun commands
+bun start
+bun test
+bun run dev
+"#);
+        } else if managers.contains(&"npm".to_string()) {
             content.push_str(r#"
 # Node.js commands
 npm start
@@ -629,6 +1335,32 @@ composer run start
 "#);
         }
         
+        if template == "ai" {
+            content.push_str(r#"
+# AI commands
+rcm let ollama --deploy        # install Ollama, pull the models, serve (or: rcm let llamacpp --deploy)
+rcm let rag-index --deploy     # embed docs/ into .rcm/cache/rag-index.json
+PYTHONPATH=src python -m app "How do I get started?"
+rcm gpt generate llama3.2 --template rag-answer --var context=docs/getting-started.md --var question="..."
+"#);
+        } else if managers.contains(&"python".to_string()) {
+            content.push_str(r#"
+# Python commands
+rcm pip install <package>
+rcm pip list
+python -m pytest
+"#);
+        }
+        
+        if managers.contains(&"go".to_string()) {
+            content.push_str(r#"
+# Go commands
+go build ./...
+go test ./...
+rcm go get <module>@<version>
+"#);
+        }
+        
         content.push_str(r#"```
 
 ## RCM Commands
@@ -656,6 +1388,8 @@ composer run start
 ├── Cargo.toml          # Rust dependencies
 ├── package.json        # Node.js dependencies
 ├── composer.json       # PHP dependencies
+├── pyproject.toml      # Python dependencies
+├── go.mod              # Go modules
 ├── Makefile            # Unified build commands
 ├── docker-compose.yml  # Development services
 └── README.md           # This file
@@ -669,7 +1403,20 @@ composer run start
             match template {
                 "rust" => content.push_str("├── Cargo.toml         # Rust dependencies\n"),
                 "node" => content.push_str("├── package.json       # Node.js dependencies\n"),
+                "bun" => content.push_str("├── package.json       # Bun dependencies\n"),
+                "frontend" => {
+                    content.push_str("├── index.html         # Vite entry page\n");
+                    content.push_str("├── vite.config.ts     # Build configuration\n");
+                    content.push_str("├── package.json       # Node.js dependencies and engines\n");
+                }
                 "php" => content.push_str("├── composer.json      # PHP dependencies\n"),
+                "python" => content.push_str("├── pyproject.toml     # Python dependencies\n"),
+                "go" => content.push_str("├── go.mod             # Go modules\n"),
+                "ai" => {
+                    content.push_str("├── docs/              # Documents the RAG index is built from\n");
+                    content.push_str("├── ai.toml            # Served model, embeddings and RAG settings\n");
+                    content.push_str("├── pyproject.toml     # Python dependencies\n");
+                }
                 _ => {}
             }
             content.push_str("└── README.md          # This file\n");
@@ -688,13 +1435,26 @@ Make sure you have the required tools installed:
         if managers.contains(&"cargo".to_string()) {
             content.push_str("- [Rust](https://rustup.rs/) (latest stable)\n");
         }
-        if managers.contains(&"npm".to_string()) {
+        if template == "bun" {
+            content.push_str("- [Bun](https://bun.sh/) (1.0 or later)\n");
+        } else if template == "frontend" {
+            content.push_str("- [Node.js](https://nodejs.org/) (18 or later, see `engines` in package.json)\n");
+        } else if managers.contains(&"npm".to_string()) {
             content.push_str("- [Node.js](https://nodejs.org/) (LTS version)\n");
         }
         if managers.contains(&"composer".to_string()) {
             content.push_str("- [PHP](https://php.net/) (8.1 or later)\n");
             content.push_str("- [Composer](https://getcomposer.org/)\n");
         }
+        if template == "ai" {
+            content.push_str("- [Python](https://python.org/) (3.11 or later)\n");
+            content.push_str("- [Ollama](https://ollama.com/) or [llama.cpp](https://github.com/ggml-org/llama.cpp) (the LET specs install or use them)\n");
+        } else if managers.contains(&"python".to_string()) {
+            content.push_str("- [Python](https://python.org/) (3.10 or later)\n");
+        }
+        if managers.contains(&"go".to_string()) {
+            content.push_str("- [Go](https://go.dev/dl/) (1.22 or later)\n");
+        }
         
         content.push_str(r#"
 ### Installation
@@ -0,0 +1,322 @@
+//! Project templates for `rcm init`
+//!
+//! Besides the built-in scaffolds, `rcm init --template` accepts a local
+//! directory or a git repository (`gh:org/repo`, `gl:org/repo`, any clone URL,
+//! with an optional `#ref`). Such a template may describe itself in
+//! `rcm-template.toml`:
+//!
+//! ```toml
+//! name = "axum"
+//! description = "Axum service with sqlx"
+//! managers = ["cargo", "system"]
+//! root = "template"          # copy only this subdirectory
+//!
+//! [variables]
+//! port = "3000"              # default, override with --var port=8080
+//! ```
+//!
+//! Every file under the root is copied into the workspace, skipping files that
+//! already exist. `{{project_name}}`, `{{authors}}`, `{{year}}` and the declared
+//! variables are substituted in file contents and paths; other `{{...}}` text is
+//! left alone. `root` must stay inside the template, and a path that renders
+//! to anything outside the workspace (`..`, absolute) is refused.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+use crate::util;
+use crate::workspace::Workspace;
+
+/// Scaffolds built into `rcm init`
+pub const BUILTIN: &[&str] = &["rust", "node", "bun", "frontend", "php", "python", "go", "ai", "polyglot"];
+
+/// Manifest file at the top of a template
+pub const MANIFEST_FILE: &str = "rcm-template.toml";
+
+/// What a template is and which managers it sets up
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateManifest {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Managers enabled when `--managers` isn't given
+    #[serde(default)]
+    pub managers: Vec<String>,
+    /// Subdirectory holding the files to copy
+    #[serde(default)]
+    pub root: Option<PathBuf>,
+    /// Extra variables and their defaults
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+}
+
+/// Variables and managers a template is applied with
+#[derive(Debug, Clone, Default)]
+pub struct TemplateContext {
+    pub vars: BTreeMap<String, String>,
+    pub managers: Vec<String>,
+}
+
+/// A project scaffold `rcm init` can apply
+pub trait Template {
+    fn manifest(&self) -> &TemplateManifest;
+
+    /// Write the template's files into the workspace
+    async fn apply(&self, workspace: &Workspace, context: &TemplateContext) -> Result<()>;
+}
+
+/// A template read from a directory, possibly a fresh clone
+pub struct DirTemplate {
+    manifest: TemplateManifest,
+    root: PathBuf,
+    /// Keeps a cloned repository alive until the template is applied
+    _checkout: Option<tempfile::TempDir>,
+}
+
+/// Where a `--template` value points
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateSource {
+    Builtin(String),
+    Dir(PathBuf),
+    Git { url: String, reference: Option<String> },
+}
+
+impl TemplateSource {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        if BUILTIN.contains(&spec) {
+            return Ok(Self::Builtin(spec.to_string()));
+        }
+        let (location, reference) = match spec.rsplit_once('#') {
+            Some((location, reference)) if !reference.is_empty() => (location, Some(reference.to_string())),
+            _ => (spec, None),
+        };
+        let hosted = [("gh:", "https://github.com/"), ("gl:", "https://gitlab.com/")];
+        for (prefix, host) in hosted {
+            if let Some(path) = location.strip_prefix(prefix) {
+                let path = path.trim_end_matches(".git");
+                return Ok(Self::Git { url: format!("{}{}.git", host, path), reference });
+            }
+        }
+        let remote = ["https://", "http://", "ssh://", "git@", "git://"].iter().any(|p| location.starts_with(p))
+            || location.ends_with(".git");
+        if remote {
+            return Ok(Self::Git { url: location.to_string(), reference });
+        }
+        let path = match location.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().map(|home| home.join(rest)).unwrap_or_else(|| PathBuf::from(location)),
+            None => PathBuf::from(location),
+        };
+        if path.is_dir() {
+            return Ok(Self::Dir(path));
+        }
+        Err(anyhow!(
+            "Unknown template '{}'. Use a built-in ({}), a directory, or a git URL such as gh:org/repo",
+            spec,
+            BUILTIN.join(", ")
+        ))
+    }
+}
+
+impl DirTemplate {
+    /// Read a template from a directory
+    pub async fn open(dir: &Path) -> Result<Self> {
+        let name = dir.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| "custom".to_string());
+        Self::load(dir, name).await
+    }
+
+    /// Read a template, naming it `fallback` unless the manifest names it
+    async fn load(dir: &Path, fallback: String) -> Result<Self> {
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let mut manifest: TemplateManifest = match tokio::fs::read_to_string(&manifest_path).await {
+            Ok(content) => toml::from_str(&content)
+                .with_context(|| format!("Invalid {}", manifest_path.display()))?,
+            Err(_) => TemplateManifest::default(),
+        };
+        if manifest.name.is_empty() {
+            manifest.name = fallback;
+        }
+        let dir = dir.canonicalize()
+            .with_context(|| format!("Failed to resolve template {}", dir.display()))?;
+        let root = match &manifest.root {
+            Some(sub) => dir.join(sub),
+            None => dir.clone(),
+        };
+        // Symlinks and `..` resolved, so `root` can't reach files beside the template
+        let root = root.canonicalize()
+            .with_context(|| format!("Template root {} does not exist", root.display()))?;
+        if !root.starts_with(&dir) {
+            return Err(anyhow!("Template root {} is outside the template", root.display()));
+        }
+        if !root.is_dir() {
+            return Err(anyhow!("Template root {} is not a directory", root.display()));
+        }
+        Ok(Self { manifest, root, _checkout: None })
+    }
+
+    /// Shallow-clone a template repository
+    pub async fn fetch(url: &str, reference: Option<&str>) -> Result<Self> {
+        let checkout = tempfile::tempdir().context("Failed to create a directory for the template")?;
+        println!("{}", style(format!("📥 Fetching template {}{}", url, reference.map(|r| format!("#{}", r)).unwrap_or_default())).cyan());
+        let mut cmd = std::process::Command::new("git");
+        cmd.args(["clone", "--quiet", "--depth", "1"]);
+        if let Some(reference) = reference {
+            cmd.args(["--branch", reference]);
+        }
+        cmd.arg(url).arg(checkout.path());
+        util::execute_command(&mut cmd).await
+            .with_context(|| format!("Failed to clone template {}", url))?;
+        let name = url.trim_end_matches(".git").rsplit(['/', ':']).next().unwrap_or("custom").to_string();
+        let mut template = Self::load(checkout.path(), name).await?;
+        template._checkout = Some(checkout);
+        Ok(template)
+    }
+}
+
+/// A rendered template path, refused unless it stays below the workspace root
+pub fn confined(rendered: &str) -> Result<PathBuf> {
+    let path = Path::new(rendered);
+    let inside = path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        && path.components().any(|c| matches!(c, Component::Normal(_)));
+    if !inside {
+        return Err(anyhow!("Template path '{}' leaves the workspace", rendered));
+    }
+    Ok(path.to_path_buf())
+}
+
+/// Replace `{{name}}` (spaces inside the braces allowed) for every known variable
+pub fn render(text: &str, vars: &BTreeMap<String, String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}").and_then(|end| vars.get(after[..end].trim()).map(|value| (end, value))) {
+            Some((end, value)) => {
+                out.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+impl Template for DirTemplate {
+    fn manifest(&self) -> &TemplateManifest {
+        &self.manifest
+    }
+
+    async fn apply(&self, workspace: &Workspace, context: &TemplateContext) -> Result<()> {
+        let mut written = 0;
+        let entries = WalkDir::new(&self.root).min_depth(1).into_iter()
+            .filter_entry(|e| e.file_name() != ".git");
+        for entry in entries {
+            let entry = entry.with_context(|| format!("Failed to read template {}", self.root.display()))?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(&self.root)?;
+            if relative == Path::new(MANIFEST_FILE) {
+                continue;
+            }
+            let target = workspace.root().join(confined(&render(&relative.to_string_lossy(), &context.vars))?);
+            if target.exists() {
+                println!("  {} {} (exists)", style("⏭").dim(), target.display());
+                continue;
+            }
+            let bytes = tokio::fs::read(entry.path()).await
+                .with_context(|| format!("Failed to read {}", entry.path().display()))?;
+            // Binary files are copied as they are
+            let contents = match String::from_utf8(bytes) {
+                Ok(text) => render(&text, &context.vars).into_bytes(),
+                Err(e) => e.into_bytes(),
+            };
+            if let Some(parent) = target.parent().filter(|_| !util::is_dry_run()) {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            util::write_file(&target, contents).await?;
+            written += 1;
+        }
+        println!("{}", style(format!("📄 Wrote {} file(s) from template {}", written, self.manifest.name)).green());
+        Ok(())
+    }
+}
+
+/// `Name <email>` from git, else the login name
+async fn default_authors() -> String {
+    let git = |key: &'static str| async move {
+        let output = tokio::process::Command::new("git").args(["config", "--get", key]).output().await.ok()?;
+        let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (!value.is_empty()).then_some(value)
+    };
+    match (git("user.name").await, git("user.email").await) {
+        (Some(name), Some(email)) => format!("{} <{}>", name, email),
+        (Some(name), None) => name,
+        _ => std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_default(),
+    }
+}
+
+/// Built-in variables, then the template's defaults, then `--var key=value`
+pub async fn variables(workspace: &Workspace, manifest: &TemplateManifest, overrides: &[String]) -> Result<BTreeMap<String, String>> {
+    let mut vars = BTreeMap::new();
+    let project_name = workspace.root().file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "rcm-project".to_string());
+    vars.insert("project_name".to_string(), project_name);
+    vars.insert("authors".to_string(), default_authors().await);
+    vars.insert("year".to_string(), chrono::Utc::now().format("%Y").to_string());
+    vars.extend(manifest.variables.clone());
+    for pair in overrides {
+        let (key, value) = pair.split_once('=')
+            .ok_or_else(|| anyhow!("Invalid --var '{}'; expected key=value", pair))?;
+        vars.insert(key.trim().to_string(), value.to_string());
+    }
+    Ok(vars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let vars: BTreeMap<String, String> = [("project_name", "demo"), ("port", "3000")]
+            .iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        assert_eq!(render("name = \"{{project_name}}\" # {{ port }}", &vars), "name = \"demo\" # 3000");
+        assert_eq!(render("<p>{{ user.name }}</p> {{project_name}}", &vars), "<p>{{ user.name }}</p> demo");
+        assert_eq!(render("unclosed {{port", &vars), "unclosed {{port");
+    }
+
+    #[test]
+    fn test_confined() {
+        assert_eq!(confined("src/demo/main.rs").unwrap(), PathBuf::from("src/demo/main.rs"));
+        assert!(confined("../../.bashrc").is_err());
+        assert!(confined("src/../../x").is_err());
+        assert!(confined("/etc/cron.d/x").is_err());
+        assert!(confined("").is_err());
+    }
+
+    #[test]
+    fn test_parse_source() {
+        assert_eq!(TemplateSource::parse("rust").unwrap(), TemplateSource::Builtin("rust".to_string()));
+        assert_eq!(
+            TemplateSource::parse("gh:org/rcm-template-axum#v2").unwrap(),
+            TemplateSource::Git { url: "https://github.com/org/rcm-template-axum.git".to_string(), reference: Some("v2".to_string()) }
+        );
+        assert_eq!(
+            TemplateSource::parse("git@example.com:org/t.git").unwrap(),
+            TemplateSource::Git { url: "git@example.com:org/t.git".to_string(), reference: None }
+        );
+        assert!(TemplateSource::parse("no-such-template").is_err());
+    }
+}
@@ -4,7 +4,7 @@
 
 use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use tokio::fs;
@@ -13,7 +13,13 @@ use crate::workspace::Workspace;
 use crate::util::{self, execute_command, parse_key_value_args};
 use crate::npm::{NpmManager, NpmManagerType};
 use crate::ppm::ComposerManager;
-use crate::system::SystemManager;
+use crate::system::{SourceBuilder, SystemManager};
+use crate::secrets;
+use crate::isolation;
+use crate::mirrors;
+use crate::redact;
+use crate::toolchain;
+use crate::commands::letcond;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LetSpec {
@@ -50,6 +56,10 @@ pub enum LetConditionType {
     EnvVar,
     Platform,
     PackageInstalled,
+    /// A package installed with `rcm system source` (value is the recorded name)
+    SourceInstalled,
+    /// A condition expression (value is parsed by `letcond`), e.g. `command_version(node) >= 18`
+    Expression,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -60,6 +70,74 @@ pub struct LetConstraints {
     pub required_env_vars: Vec<String>,
 }
 
+/// A target and the specs it depends on, in execution order
+#[derive(Debug)]
+pub struct LetGraph {
+    pub target: String,
+    /// Dependencies first, the target last
+    pub order: Vec<String>,
+    /// Declared dependencies of every spec in the graph
+    pub edges: BTreeMap<String, Vec<String>>,
+    /// Dependencies with no LET spec; they are skipped
+    pub missing: Vec<String>,
+}
+
+impl LetGraph {
+    /// Render the graph as a tree rooted at the target
+    pub fn render_tree(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&self.target);
+        out.push('\n');
+        self.render_children(&self.target, "", &mut out);
+        out
+    }
+    
+    fn render_children(&self, node: &str, prefix: &str, out: &mut String) {
+        let children = self.edges.get(node).map(Vec::as_slice).unwrap_or_default();
+        for (i, child) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            let marker = if self.missing.contains(child) { " (no spec, skipped)" } else { "" };
+            out.push_str(&format!("{}{}{}{}\n", prefix, if last { "└── " } else { "├── " }, child, marker));
+            let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+            self.render_children(child, &child_prefix, out);
+        }
+    }
+}
+
+/// Order `target` after everything it depends on, failing on cycles
+pub fn topological_order(target: &str, edges: &BTreeMap<String, Vec<String>>) -> Result<Vec<String>> {
+    fn visit(
+        node: &str,
+        edges: &BTreeMap<String, Vec<String>>,
+        done: &mut HashSet<String>,
+        path: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) -> Result<()> {
+        if done.contains(node) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|n| n == node) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(node.to_string());
+            return Err(anyhow!("Dependency cycle between LET specs: {}", cycle.join(" -> ")));
+        }
+        
+        path.push(node.to_string());
+        for dependency in edges.get(node).into_iter().flatten() {
+            visit(dependency, edges, done, path, order)?;
+        }
+        path.pop();
+        
+        done.insert(node.to_string());
+        order.push(node.to_string());
+        Ok(())
+    }
+    
+    let mut order = Vec::new();
+    visit(target, edges, &mut HashSet::new(), &mut Vec::new(), &mut order)?;
+    Ok(order)
+}
+
 #[derive(Debug)]
 pub struct LetExecutor {
     workspace: PathBuf,
@@ -97,13 +175,14 @@ impl LetExecutor {
             self.create_php_spec(),
             self.create_cargo_spec(),
             self.create_git_spec(),
+            self.create_composer_spec(),
         ];
         
         for spec in specs {
             let spec_path = self.specs_dir.join(format!("{}.json", spec.target));
             if !spec_path.exists() {
                 let content = serde_json::to_string_pretty(&spec)?;
-                fs::write(spec_path, content).await?;
+                util::write_file(&spec_path, content).await?;
             }
         }
         
@@ -267,6 +346,46 @@ impl LetExecutor {
         }
     }
     
+    /// Create Composer LET spec
+    fn create_composer_spec(&self) -> LetSpec {
+        LetSpec {
+            target: "composer".to_string(),
+            version: None,
+            manager: Some("system".to_string()),
+            dependencies: vec![],
+            actions: vec![
+                LetAction {
+                    name: "install".to_string(),
+                    command: "rcm".to_string(),
+                    args: vec!["system", "install", "composer"].iter().map(|s| s.to_string()).collect(),
+                    working_dir: None,
+                    env: HashMap::new(),
+                    conditions: vec![],
+                    parallel: false,
+                },
+                LetAction {
+                    name: "verify".to_string(),
+                    command: "composer".to_string(),
+                    args: vec!["--version"].iter().map(|s| s.to_string()).collect(),
+                    working_dir: None,
+                    env: HashMap::new(),
+                    conditions: vec![LetCondition {
+                        condition_type: LetConditionType::CommandExists,
+                        value: "composer".to_string(),
+                    }],
+                    parallel: false,
+                },
+            ],
+            environment: HashMap::new(),
+            constraints: LetConstraints {
+                platforms: vec!["linux".to_string(), "macos".to_string(), "windows".to_string()],
+                min_memory_mb: None,
+                required_commands: vec![],
+                required_env_vars: vec![],
+            },
+        }
+    }
+    
     /// Create Cargo LET spec
     fn create_cargo_spec(&self) -> LetSpec {
         LetSpec {
@@ -401,6 +520,36 @@ impl LetExecutor {
             .context("Failed to parse LET spec")
     }
     
+    /// Load the target and every spec it depends on, ordered for execution
+    pub async fn resolve(&self, target: &str) -> Result<LetGraph> {
+        let mut edges = BTreeMap::new();
+        let mut missing = Vec::new();
+        let mut pending = vec![target.to_string()];
+        
+        while let Some(name) = pending.pop() {
+            if edges.contains_key(&name) || missing.contains(&name) {
+                continue;
+            }
+            // Only the target itself must have a spec
+            if name != target && !self.specs_dir.join(format!("{}.json", name)).exists() {
+                tracing::warn!("No LET spec for dependency '{}'; skipping it", name);
+                missing.push(name);
+                continue;
+            }
+            let spec = self.load_spec(&name).await
+                .with_context(|| format!("Failed to load LET spec '{}'", name))?;
+            pending.extend(spec.dependencies.iter().cloned());
+            edges.insert(name, spec.dependencies);
+        }
+        
+        let order = topological_order(target, &edges)?
+            .into_iter()
+            .filter(|name| !missing.contains(name))
+            .collect();
+        
+        Ok(LetGraph { target: target.to_string(), order, edges, missing })
+    }
+    
     /// Check if condition is met
     async fn check_condition(&self, condition: &LetCondition) -> Result<bool> {
         match condition.condition_type {
@@ -427,21 +576,22 @@ impl LetExecutor {
                 // This is a simplified check - could be enhanced
                 util::command_exists(&condition.value).await.then(|| true).ok_or_else(|| anyhow!("Package check not implemented"))
             }
+            LetConditionType::SourceInstalled => {
+                let builder = SourceBuilder::new(&self.workspace, None, "/usr/local", None, Vec::new());
+                Ok(builder.is_installed(&condition.value).await)
+            }
+            LetConditionType::Expression => {
+                let expr = letcond::parse(&condition.value)
+                    .with_context(|| format!("Invalid condition expression: {}", condition.value))?;
+                expr.evaluate(&self.workspace).await
+            }
         }
     }
     
-    /// Execute LET action
-    async fn execute_action(&self, action: &LetAction, env: &HashMap<String, String>) -> Result<()> {
-        // Check conditions
-        for condition in &action.conditions {
-            if !self.check_condition(condition).await? {
-                println!("Skipping action '{}': condition not met", action.name);
-                return Ok(());
-            }
-        }
-        
-        println!("Executing action: {}", action.name);
-        
+    /// Build the process for an action
+    ///
+    /// `secret:` references are resolved into the child's environment only, never its argv.
+    async fn action_command(&self, action: &LetAction, env: &HashMap<String, String>) -> Result<AsyncCommand> {
         let working_dir = if let Some(ref dir) = action.working_dir {
             if dir.starts_with('/') {
                 PathBuf::from(dir)
@@ -456,14 +606,40 @@ impl LetExecutor {
         cmd.args(&action.args);
         cmd.current_dir(working_dir);
         
-        // Set environment variables
-        for (key, value) in env {
-            cmd.env(key, value);
+        // Pinned interpreters (e.g. `rcm ppm use-php`) take precedence over PATH
+        if let Some(path) = toolchain::shim_path(&self.workspace) {
+            cmd.env("PATH", path);
         }
-        for (key, value) in &action.env {
+        cmd.envs(isolation::env_vars(&self.workspace));
+        cmd.envs(mirrors::env_vars());
+        
+        // Set environment variables, resolving `secret:` references only at spawn time
+        let mut action_env = env.clone();
+        action_env.extend(action.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+        for (key, value) in secrets::resolve_env(&action_env).await? {
             cmd.env(key, value);
         }
         
+        Ok(cmd)
+    }
+    
+    /// Execute LET action
+    async fn execute_action(&self, action: &LetAction, env: &HashMap<String, String>) -> Result<()> {
+        // Check conditions
+        for condition in &action.conditions {
+            if !self.check_condition(condition).await? {
+                println!("Skipping action '{}': condition not met", action.name);
+                return Ok(());
+            }
+        }
+        
+        println!("Executing action: {}", action.name);
+        
+        let mut cmd = self.action_command(action, env).await?;
+        if util::skip_in_dry_run(cmd.as_std()) {
+            return Ok(());
+        }
+        
         let output = cmd.output().await
             .context(format!("Failed to execute command: {}", action.command))?;
         
@@ -472,22 +648,40 @@ impl LetExecutor {
                 "Command failed: {} {}\nStdout: {}\nStderr: {}",
                 action.command,
                 action.args.join(" "),
-                String::from_utf8_lossy(&output.stdout),
-                String::from_utf8_lossy(&output.stderr)
+                redact::redact(&String::from_utf8_lossy(&output.stdout)),
+                redact::redact(&String::from_utf8_lossy(&output.stderr))
             ));
         }
         
         // Print output if present
         if !output.stdout.is_empty() {
-            println!("{}", String::from_utf8_lossy(&output.stdout));
+            println!("{}", redact::redact(&String::from_utf8_lossy(&output.stdout)));
         }
         if !output.stderr.is_empty() {
-            eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+            tracing::warn!("{}", redact::redact(&String::from_utf8_lossy(&output.stderr)));
         }
         
         Ok(())
     }
     
+    /// Execute the target's dependencies in order, then the target
+    ///
+    /// `action_filter` selects the target's actions only; dependencies run all of their own.
+    pub async fn execute_with_dependencies(&self, target: &str, action_filter: Option<&str>, env: HashMap<String, String>) -> Result<()> {
+        let graph = self.resolve(target).await?;
+        for name in &graph.order {
+            let filter = if name == target {
+                action_filter
+            } else {
+                println!("Running dependency: {}", name);
+                None
+            };
+            self.execute(name, filter, env.clone()).await
+                .with_context(|| format!("LET spec '{}' failed", name))?;
+        }
+        Ok(())
+    }
+    
     /// Execute LET spec
     pub async fn execute(&self, target: &str, action_filter: Option<&str>, env: HashMap<String, String>) -> Result<()> {
         let spec = self.load_spec(target).await?;
@@ -528,6 +722,7 @@ impl LetExecutor {
 pub async fn run(
     workspace: &Workspace,
     target: &str,
+    name: Option<&str>,
     deploy: bool,
     plan: bool,
     apply: bool,
@@ -535,10 +730,29 @@ pub async fn run(
     test: bool,
     clean: bool,
     update: bool,
+    graph: bool,
     args: Vec<String>,
     env: Option<&str>,
     parallel: usize,
+    goal: Option<&str>,
+    model: Option<&str>,
 ) -> Result<()> {
+    // Stacks are materialized as compose projects rather than LET specs
+    if target == "stack" {
+        let name = name.ok_or_else(|| anyhow!("Usage: rcm let stack <name> [--deploy|--plan|--clean]"))?;
+        return crate::stack::run(workspace.root(), name, deploy || apply, plan, clean).await;
+    }
+    
+    // Spec authoring: `rcm let new|generate|validate <target>`
+    if matches!(target, "new" | "generate" | "validate") {
+        let name = name.ok_or_else(|| anyhow!("Usage: rcm let {} <target>", target))?;
+        return match target {
+            "new" => crate::commands::letspec::scaffold(workspace.root(), name).await,
+            "generate" => crate::commands::letspec::draft(workspace.root(), name, goal, model).await,
+            _ => crate::commands::letspec::validate(workspace.root(), name).await,
+        };
+    }
+    
     let executor = LetExecutor::new(workspace.root());
     executor.initialize().await?;
     
@@ -567,8 +781,20 @@ pub async fn run(
     }
     env_vars.extend(parsed_args);
     
+    if graph {
+        let graph = executor.resolve(target).await?;
+        println!("=== LET dependency graph for target: {} ===", target);
+        print!("{}", graph.render_tree());
+        println!("\nExecution order:");
+        for (i, name) in graph.order.iter().enumerate() {
+            println!("  {}. {}", i + 1, name);
+        }
+        return Ok(());
+    }
+    
     if plan {
         println!("=== LET Plan for target: {} ===", target);
+        let graph = executor.resolve(target).await?;
         let spec = executor.load_spec(target).await?;
         
         println!("Target: {}", spec.target);
@@ -578,6 +804,10 @@ pub async fn run(
         if let Some(manager) = &spec.manager {
             println!("Manager: {}", manager);
         }
+        if graph.order.len() > 1 {
+            let dependencies: Vec<&str> = graph.order.iter().filter(|n| *n != target).map(String::as_str).collect();
+            println!("Runs first: {}", dependencies.join(", "));
+        }
         
         println!("\nActions:");
         for action in &spec.actions {
@@ -605,8 +835,55 @@ pub async fn run(
     }
     
     if apply || (!plan && !deploy && !build && !test && !clean && !update) {
-        executor.execute(target, action_filter, env_vars).await?;
+        executor.execute_with_dependencies(target, action_filter, env_vars).await?;
     }
     
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edges(pairs: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        pairs
+            .iter()
+            .map(|(name, deps)| (name.to_string(), deps.iter().map(|d| d.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let graph = edges(&[("php", &["composer", "git"]), ("composer", &["git"]), ("git", &[])]);
+        assert_eq!(topological_order("php", &graph).unwrap(), vec!["git", "composer", "php"]);
+    }
+
+    #[test]
+    fn test_topological_order_cycle() {
+        let graph = edges(&[("a", &["b"]), ("b", &["c"]), ("c", &["a"])]);
+        let err = topological_order("a", &graph).unwrap_err().to_string();
+        assert!(err.contains("a -> b -> c -> a"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_action_secrets_stay_out_of_argv() {
+        let secret = "argv-test-secret-value";
+        std::env::set_var(secrets::env_override_name("let-argv-test"), secret);
+        let dir = tempfile::tempdir().unwrap();
+        let action = LetAction {
+            name: "deploy".to_string(),
+            command: "echo".to_string(),
+            args: vec!["--token-from-env".to_string()],
+            working_dir: None,
+            env: HashMap::from([("API_KEY".to_string(), "secret:let-argv-test".to_string())]),
+            conditions: Vec::new(),
+            parallel: false,
+        };
+
+        let cmd = LetExecutor::new(dir.path()).action_command(&action, &HashMap::new()).await.unwrap();
+        let cmd = cmd.as_std();
+        assert!(cmd.get_args().all(|arg| !arg.to_string_lossy().contains(secret)));
+        assert!(!cmd.get_program().to_string_lossy().contains(secret));
+        assert!(cmd.get_envs().any(|(key, value)| key == "API_KEY" && value == Some(std::ffi::OsStr::new(secret))));
+    }
+}
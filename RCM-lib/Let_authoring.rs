@@ -0,0 +1,576 @@
+//! LET spec authoring for RCM
+//!
+//! `rcm let new <target>` scaffolds a spec interactively, `rcm let generate <target>`
+//! drafts one with a served model, and `rcm let validate <target>` checks one against
+//! the LET spec JSON Schema, which is also written next to the specs for editor completion.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use dialoguer::{Confirm, Editor, Input, MultiSelect, Select};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tokio::fs;
+use crate::commands::letcond;
+use crate::commands::letcmd::{LetAction, LetCondition, LetConditionType, LetConstraints, LetSpec};
+use crate::model_client;
+use crate::util;
+
+/// File name of the schema inside `.rcm/let`
+pub const SCHEMA_FILE: &str = "let-spec.schema.json";
+
+/// Tokens a model may spend drafting a spec
+const DRAFT_MAX_TOKENS: usize = 2048;
+
+/// Model replies that fail validation are sent back with the errors this many times
+const DRAFT_ATTEMPTS: usize = 3;
+
+/// Project files that tell the model what the workspace contains
+const CONTEXT_FILES: [&str; 14] = [
+    "Cargo.toml", "package.json", "composer.json", "pyproject.toml", "requirements.txt",
+    "go.mod", "Gemfile", "Dockerfile", "docker-compose.yml", "compose.yaml", "Makefile",
+    ".env.example", ".nvmrc", ".tool-versions",
+];
+
+/// JSON Schema for LET spec files
+pub const LET_SPEC_SCHEMA: &str = r#"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "RCM LET spec",
+  "type": "object",
+  "required": ["target", "dependencies", "actions", "environment", "constraints"],
+  "additionalProperties": false,
+  "properties": {
+    "$schema": { "type": "string" },
+    "target": { "type": "string", "pattern": "^[A-Za-z0-9._-]+$" },
+    "version": { "type": ["string", "null"] },
+    "manager": { "type": ["string", "null"], "enum": ["system", "npm", "composer", "cargo", null] },
+    "dependencies": { "type": "array", "items": { "type": "string" } },
+    "environment": { "type": "object", "additionalProperties": { "type": "string" } },
+    "actions": {
+      "type": "array",
+      "items": {
+        "type": "object",
+        "required": ["name", "command", "args", "env", "conditions", "parallel"],
+        "additionalProperties": false,
+        "properties": {
+          "name": { "type": "string", "pattern": "^[A-Za-z0-9._-]+$" },
+          "command": { "type": "string", "pattern": "\\S" },
+          "args": { "type": "array", "items": { "type": "string" } },
+          "working_dir": { "type": ["string", "null"] },
+          "env": { "type": "object", "additionalProperties": { "type": "string" } },
+          "parallel": { "type": "boolean" },
+          "conditions": {
+            "type": "array",
+            "items": {
+              "type": "object",
+              "required": ["condition_type", "value"],
+              "additionalProperties": false,
+              "properties": {
+                "condition_type": {
+                  "enum": ["FileExists", "CommandExists", "EnvVar", "Platform", "PackageInstalled", "SourceInstalled", "Expression"]
+                },
+                "value": { "type": "string" }
+              }
+            }
+          }
+        }
+      }
+    },
+    "constraints": {
+      "type": "object",
+      "required": ["platforms", "required_commands", "required_env_vars"],
+      "additionalProperties": false,
+      "properties": {
+        "platforms": { "type": "array", "items": { "enum": ["linux", "macos", "windows"] } },
+        "min_memory_mb": { "type": ["integer", "null"], "minimum": 0 },
+        "required_commands": { "type": "array", "items": { "type": "string" } },
+        "required_env_vars": { "type": "array", "items": { "type": "string" } }
+      }
+    }
+  }
+}"#;
+
+/// Validate `instance` against the subset of JSON Schema used by [`LET_SPEC_SCHEMA`]
+/// (type, enum, required, properties, additionalProperties, items, pattern, minimum).
+/// Returns one message per violation, prefixed with its JSON pointer.
+pub fn validate_schema(schema: &Value, instance: &Value, path: &str, errors: &mut Vec<String>) {
+    let at = if path.is_empty() { "/" } else { path };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.iter().any(|t| type_matches(t, instance)) {
+            errors.push(format!("{}: expected {}", at, types.join(" or ")));
+            return;
+        }
+    }
+
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(instance) {
+            let options: Vec<String> = allowed.iter().map(|v| v.to_string()).collect();
+            errors.push(format!("{}: must be one of {}", at, options.join(", ")));
+        }
+    }
+
+    if let (Some(pattern), Some(text)) = (schema.get("pattern").and_then(Value::as_str), instance.as_str()) {
+        if let Ok(re) = regex::Regex::new(pattern) {
+            if !re.is_match(text) {
+                errors.push(format!("{}: '{}' does not match {}", at, text, pattern));
+            }
+        }
+    }
+
+    if let (Some(minimum), Some(number)) = (schema.get("minimum").and_then(Value::as_f64), instance.as_f64()) {
+        if number < minimum {
+            errors.push(format!("{}: must be at least {}", at, minimum));
+        }
+    }
+
+    if let Value::Object(object) = instance {
+        for key in schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str) {
+            if !object.contains_key(key) {
+                errors.push(format!("{}: missing required field '{}'", at, key));
+            }
+        }
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (key, value) in object {
+            let child = format!("{}/{}", path, key);
+            match (properties.and_then(|p| p.get(key)), schema.get("additionalProperties")) {
+                (Some(property_schema), _) => validate_schema(property_schema, value, &child, errors),
+                (None, Some(Value::Bool(false))) => errors.push(format!("{}: unknown field", child)),
+                (None, Some(extra_schema @ Value::Object(_))) => validate_schema(extra_schema, value, &child, errors),
+                _ => {}
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (instance, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate_schema(item_schema, item, &format!("{}/{}", path, i), errors);
+        }
+    }
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_u64() || value.is_i64(),
+        "number" => value.is_number(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// Schema violations plus checks the schema cannot express
+pub fn validate_spec(content: &str) -> Result<Vec<String>> {
+    let instance: Value = serde_json::from_str(content).context("Spec is not valid JSON")?;
+    let schema: Value = serde_json::from_str(LET_SPEC_SCHEMA)?;
+
+    let mut errors = Vec::new();
+    validate_schema(&schema, &instance, "", &mut errors);
+
+    let mut seen = HashSet::new();
+    for action in instance.get("actions").and_then(Value::as_array).into_iter().flatten() {
+        if let Some(name) = action.get("name").and_then(Value::as_str) {
+            if !seen.insert(name) {
+                errors.push(format!("/actions: duplicate action name '{}'", name));
+            }
+        }
+        let conditions = action.get("conditions").and_then(Value::as_array).into_iter().flatten();
+        for condition in conditions.filter(|c| c.get("condition_type").and_then(Value::as_str) == Some("Expression")) {
+            let expression = condition.get("value").and_then(Value::as_str).unwrap_or_default();
+            if let Err(e) = letcond::parse(expression) {
+                errors.push(format!("/actions: condition '{}': {}", expression, e));
+            }
+        }
+    }
+    Ok(errors)
+}
+
+/// Write the schema next to the specs so editors can complete spec files
+async fn write_schema(specs_dir: &Path) -> Result<()> {
+    let path = specs_dir.join(SCHEMA_FILE);
+    if !path.exists() {
+        util::write_file(&path, LET_SPEC_SCHEMA).await?;
+    }
+    Ok(())
+}
+
+/// `rcm let validate <target>`
+pub async fn validate(workspace_root: &Path, target: &str) -> Result<()> {
+    let path = workspace_root.join(".rcm").join("let").join(format!("{}.json", target));
+    let content = fs::read_to_string(&path).await
+        .with_context(|| format!("No LET spec found at {}", path.display()))?;
+
+    let errors = validate_spec(&content)?;
+    if errors.is_empty() {
+        println!("{}", style(format!("✅ {} is a valid LET spec", path.display())).green().bold());
+        return Ok(());
+    }
+
+    println!("{}", style(format!("❌ {} has {} problem(s):", path.display(), errors.len())).red().bold());
+    for error in &errors {
+        println!("  • {}", error);
+    }
+    Err(anyhow!("LET spec '{}' is invalid", target))
+}
+
+/// `rcm let new <target>`: build a spec from prompts, validate it and save it
+pub async fn scaffold(workspace_root: &Path, target: &str) -> Result<()> {
+    let specs_dir = workspace_root.join(".rcm").join("let");
+    let path = specs_dir.join(format!("{}.json", target));
+    if path.exists() && !Confirm::new()
+        .with_prompt(format!("{} already exists. Overwrite?", path.display()))
+        .default(false)
+        .interact()?
+    {
+        return Ok(());
+    }
+
+    println!("{}", style(format!("📝 New LET spec: {}", target)).cyan().bold());
+
+    let version: String = Input::new()
+        .with_prompt("Version constraint (empty for none)")
+        .allow_empty(true)
+        .interact_text()?;
+    let managers = ["none", "system", "npm", "composer", "cargo"];
+    let manager = Select::new()
+        .with_prompt("Package manager")
+        .items(&managers)
+        .default(0)
+        .interact()?;
+    let dependencies: String = Input::new()
+        .with_prompt("Depends on LET specs (comma separated)")
+        .allow_empty(true)
+        .interact_text()?;
+
+    let mut actions = Vec::new();
+    loop {
+        actions.push(prompt_action(actions.is_empty())?);
+        if !Confirm::new().with_prompt("Add another action?").default(false).interact()? {
+            break;
+        }
+    }
+
+    let platforms = ["linux", "macos", "windows"];
+    let selected = MultiSelect::new()
+        .with_prompt("Supported platforms")
+        .items(&platforms)
+        .defaults(&[true, true, true])
+        .interact()?;
+    let required_commands: String = Input::new()
+        .with_prompt("Required commands (comma separated)")
+        .allow_empty(true)
+        .interact_text()?;
+
+    let spec = LetSpec {
+        target: target.to_string(),
+        version: Some(version).filter(|v| !v.is_empty()),
+        manager: Some(managers[manager].to_string()).filter(|m| m != "none"),
+        dependencies: split_list(&dependencies),
+        actions,
+        environment: HashMap::new(),
+        constraints: LetConstraints {
+            platforms: selected.into_iter().map(|i| platforms[i].to_string()).collect(),
+            min_memory_mb: None,
+            required_commands: split_list(&required_commands),
+            required_env_vars: Vec::new(),
+        },
+    };
+
+    let content = serde_json::to_string_pretty(&spec)?;
+    let errors = validate_spec(&content)?;
+    if !errors.is_empty() {
+        return Err(anyhow!("Generated spec is invalid:\n  {}", errors.join("\n  ")));
+    }
+
+    fs::create_dir_all(&specs_dir).await?;
+    write_schema(&specs_dir).await?;
+    util::write_file(&path, content).await?;
+    println!("{}", style(format!("✅ Created {}", path.display())).green().bold());
+    println!("  Run {} to preview it", style(format!("rcm let {} --plan", target)).cyan());
+    Ok(())
+}
+
+/// What the model is told about the machine and workspace
+#[derive(Debug, Default)]
+pub struct DraftContext {
+    pub os: String,
+    pub arch: String,
+    pub files: Vec<String>,
+    pub package_managers: Vec<String>,
+    pub existing_specs: Vec<String>,
+}
+
+impl DraftContext {
+    async fn detect(workspace_root: &Path) -> Result<Self> {
+        let mut package_managers = Vec::new();
+        for manager in ["apt-get", "dnf", "yum", "pacman", "apk", "brew", "choco", "winget", "docker"] {
+            if util::command_exists(manager).await {
+                package_managers.push(manager.to_string());
+            }
+        }
+
+        let mut existing_specs = Vec::new();
+        let specs_dir = workspace_root.join(".rcm").join("let");
+        if specs_dir.exists() {
+            let mut entries = fs::read_dir(&specs_dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if let Some(target) = name.strip_suffix(".json").filter(|_| name != SCHEMA_FILE) {
+                    existing_specs.push(target.to_string());
+                }
+            }
+            existing_specs.sort();
+        }
+
+        Ok(Self {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            files: CONTEXT_FILES
+                .iter()
+                .filter(|f| workspace_root.join(f).exists())
+                .map(|f| f.to_string())
+                .collect(),
+            package_managers,
+            existing_specs,
+        })
+    }
+}
+
+/// Prompt asking for a spec; `previous` carries a rejected draft and its errors
+pub fn build_draft_prompt(target: &str, goal: &str, context: &DraftContext, previous: Option<(&str, &[String])>) -> String {
+    let list = |items: &[String]| if items.is_empty() { "none".to_string() } else { items.join(", ") };
+    let mut prompt = format!(
+        "You write RCM LET specs: JSON files describing how to install and operate a tool.\n\
+         Reply with a single JSON object that validates against this JSON Schema and nothing else.\n\n\
+         Schema:\n{}\n\n\
+         Rules:\n\
+         - \"target\" must be \"{}\".\n\
+         - Action names map to flags: install (--deploy), build, test, clean, update; add others as needed.\n\
+         - \"command\" is a single program; put its arguments in \"args\". Do not use shell pipelines.\n\
+         - Guard platform-specific actions with Platform or CommandExists conditions.\n\
+         - \"dependencies\" may only name existing specs.\n\n\
+         Workspace:\n\
+         - OS: {} ({})\n\
+         - Package managers available: {}\n\
+         - Project files: {}\n\
+         - Existing specs: {}\n\n\
+         Goal: {}\n",
+        LET_SPEC_SCHEMA,
+        target,
+        context.os,
+        context.arch,
+        list(&context.package_managers),
+        list(&context.files),
+        list(&context.existing_specs),
+        goal.trim(),
+    );
+    if let Some((draft, errors)) = previous {
+        prompt.push_str(&format!(
+            "\nYour previous draft was rejected:\n{}\nProblems:\n- {}\nReply with a corrected spec.\n",
+            draft,
+            errors.join("\n- ")
+        ));
+    }
+    prompt
+}
+
+/// The JSON object in a model reply, pretty-printed, with `target` forced to the requested name
+pub fn extract_spec(reply: &str, target: &str) -> Result<String> {
+    let start = reply.find('{').ok_or_else(|| anyhow!("Model reply contains no JSON object"))?;
+    let end = reply.rfind('}').ok_or_else(|| anyhow!("Model reply contains no JSON object"))?;
+    let mut spec: Value = serde_json::from_str(&reply[start..=end]).context("Model reply is not valid JSON")?;
+    if let Some(object) = spec.as_object_mut() {
+        object.insert("target".to_string(), Value::String(target.to_string()));
+    }
+    Ok(serde_json::to_string_pretty(&spec)?)
+}
+
+/// `rcm let generate <target>`: draft a spec with a served model, then review and save it
+pub async fn draft(workspace_root: &Path, target: &str, goal: Option<&str>, model: Option<&str>) -> Result<()> {
+    let specs_dir = workspace_root.join(".rcm").join("let");
+    let path = specs_dir.join(format!("{}.json", target));
+    if path.exists() && !Confirm::new()
+        .with_prompt(format!("{} already exists. Overwrite?", path.display()))
+        .default(false)
+        .interact()?
+    {
+        return Ok(());
+    }
+
+    let goal = match goal {
+        Some(goal) => goal.to_string(),
+        None => Input::new()
+            .with_prompt(format!("What should '{}' do?", target))
+            .interact_text()?,
+    };
+    let model = model_client::find_model(workspace_root, model).await?.ok_or_else(|| {
+        anyhow!("No model is running. Start one with 'rcm gpt serve <model> --deploy' or pass --model")
+    })?;
+    let context = DraftContext::detect(workspace_root).await?;
+
+    println!("{}", style(format!("🤖 Drafting LET spec '{}' with {}...", target, model.name)).cyan().bold());
+    let mut previous: Option<(String, Vec<String>)> = None;
+    let mut content = String::new();
+    let mut errors = Vec::new();
+    for attempt in 1..=DRAFT_ATTEMPTS {
+        let prompt = build_draft_prompt(
+            target,
+            &goal,
+            &context,
+            previous.as_ref().map(|(draft, errors)| (draft.as_str(), errors.as_slice())),
+        );
+        let reply = model_client::complete(&model, &prompt, DRAFT_MAX_TOKENS).await?;
+        (content, errors) = match extract_spec(&reply, target) {
+            Ok(spec) => {
+                let errors = validate_spec(&spec)?;
+                (spec, errors)
+            }
+            Err(e) => (reply, vec![e.to_string()]),
+        };
+        if errors.is_empty() {
+            break;
+        }
+        println!("  {} draft {} rejected ({} problem(s)), asking again", style("⚠").yellow(), attempt, errors.len());
+        previous = Some((content.clone(), errors.clone()));
+    }
+
+    // Review loop: the draft is only saved once it validates and the user accepts it
+    loop {
+        println!();
+        println!("{}", content);
+        if errors.is_empty() {
+            println!("{}", style("✅ Draft validates against the LET spec schema").green());
+        } else {
+            println!("{}", style(format!("❌ Draft has {} problem(s):", errors.len())).red().bold());
+            for error in &errors {
+                println!("  • {}", error);
+            }
+        }
+
+        let choices: &[&str] = if errors.is_empty() { &["Save", "Edit", "Discard"] } else { &["Edit", "Discard"] };
+        let choice = Select::new()
+            .with_prompt("Review the draft")
+            .items(choices)
+            .default(0)
+            .interact()?;
+        match choices[choice] {
+            "Save" => break,
+            "Edit" => {
+                if let Some(edited) = Editor::new().extension(".json").edit(&content)? {
+                    content = edited;
+                }
+                errors = validate_spec(&content).unwrap_or_else(|e| vec![e.to_string()]);
+            }
+            _ => {
+                println!("{}", style("✋ Draft discarded.").yellow());
+                return Ok(());
+            }
+        }
+    }
+
+    fs::create_dir_all(&specs_dir).await?;
+    write_schema(&specs_dir).await?;
+    util::write_file(&path, content).await?;
+    println!("{}", style(format!("✅ Created {}", path.display())).green().bold());
+    println!("  Run {} to preview it", style(format!("rcm let {} --plan", target)).cyan());
+    Ok(())
+}
+
+fn prompt_action(first: bool) -> Result<LetAction> {
+    let name: String = Input::new()
+        .with_prompt("Action name (install, build, test, clean, update run via --deploy/--build/...)")
+        .default(if first { "install".to_string() } else { "build".to_string() })
+        .interact_text()?;
+    let command_line: String = Input::new()
+        .with_prompt("Command")
+        .interact_text()?;
+    let mut words = command_line.split_whitespace().map(str::to_string);
+    let command = words.next().ok_or_else(|| anyhow!("Action '{}' needs a command", name))?;
+    let working_dir: String = Input::new()
+        .with_prompt("Working directory (empty for workspace root)")
+        .allow_empty(true)
+        .interact_text()?;
+
+    let condition_types = ["FileExists", "CommandExists", "EnvVar", "Platform", "PackageInstalled", "SourceInstalled", "Expression"];
+    let mut conditions = Vec::new();
+    while Confirm::new().with_prompt("Add a condition?").default(false).interact()? {
+        let kind = Select::new()
+            .with_prompt("Condition")
+            .items(&condition_types)
+            .default(0)
+            .interact()?;
+        let value: String = Input::new().with_prompt("Value").interact_text()?;
+        let condition_type = match condition_types[kind] {
+            "FileExists" => LetConditionType::FileExists,
+            "CommandExists" => LetConditionType::CommandExists,
+            "EnvVar" => LetConditionType::EnvVar,
+            "Platform" => LetConditionType::Platform,
+            "PackageInstalled" => LetConditionType::PackageInstalled,
+            "SourceInstalled" => LetConditionType::SourceInstalled,
+            _ => LetConditionType::Expression,
+        };
+        conditions.push(LetCondition { condition_type, value });
+    }
+
+    Ok(LetAction {
+        name,
+        command,
+        args: words.collect(),
+        working_dir: Some(working_dir).filter(|d| !d.is_empty()),
+        env: HashMap::new(),
+        conditions,
+        parallel: false,
+    })
+}
+
+fn split_list(input: &str) -> Vec<String> {
+    input.split(',').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_spec() {
+        let valid = r#"{
+            "target": "redis", "version": null, "manager": "system", "dependencies": [],
+            "actions": [{ "name": "install", "command": "rcm", "args": ["system", "install", "redis"],
+                          "working_dir": null, "env": {}, "conditions": [], "parallel": false }],
+            "environment": {},
+            "constraints": { "platforms": ["linux"], "min_memory_mb": null, "required_commands": [], "required_env_vars": [] }
+        }"#;
+        assert!(validate_spec(valid).unwrap().is_empty());
+
+        let invalid = valid
+            .replace(r#""platforms": ["linux"]"#, r#""platforms": ["beos"]"#)
+            .replace(r#""parallel": false"#, r#""parallel": "no", "retries": 3"#);
+        let errors = validate_spec(&invalid).unwrap();
+        assert!(errors.iter().any(|e| e.starts_with("/constraints/platforms/0")), "{:?}", errors);
+        assert!(errors.iter().any(|e| e.starts_with("/actions/0/parallel")), "{:?}", errors);
+        assert!(errors.iter().any(|e| e == "/actions/0/retries: unknown field"), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_extract_spec() {
+        let reply = "Here is the spec:\n```json\n{\"target\": \"pg\", \"dependencies\": []}\n```";
+        let spec: Value = serde_json::from_str(&extract_spec(reply, "postgres").unwrap()).unwrap();
+        assert_eq!(spec["target"], "postgres");
+        assert!(extract_spec("I cannot help with that.", "postgres").is_err());
+
+        let context = DraftContext { os: "linux".to_string(), ..Default::default() };
+        let errors = vec!["/actions: duplicate action name 'install'".to_string()];
+        let prompt = build_draft_prompt("postgres", "set up postgres with backups", &context, Some(("{}", &errors)));
+        assert!(prompt.contains("Goal: set up postgres with backups"));
+        assert!(prompt.contains("- OS: linux"));
+        assert!(prompt.contains("duplicate action name"));
+    }
+}
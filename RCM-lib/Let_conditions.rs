@@ -0,0 +1,359 @@
+//! Condition expressions for LET actions
+//!
+//! A `LetConditionType::Expression` condition holds an expression such as
+//! `command_exists(node) && command_version(node) >= 18 && !file_exists("dist/**/*.js")`.
+//!
+//! Grammar:
+//! ```text
+//! expr    := and ( ("||" | "or") and )*
+//! and     := unary ( ("&&" | "and") unary )*
+//! unary   := ("!" | "not") unary | "(" expr ")" | call [ op literal ]
+//! call    := ident "(" literal ")"
+//! op      := ">=" | "<=" | ">" | "<" | "==" | "!="
+//! ```
+//! Boolean functions: `file_exists(glob)`, `command_exists(cmd)`, `env(NAME)`,
+//! `platform(os)`, `source_installed(name)`. Comparable functions:
+//! `command_version(cmd)` (version comparison) and `env(NAME)` (string equality).
+
+use anyhow::{anyhow, Result};
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use walkdir::WalkDir;
+use crate::system::SourceBuilder;
+use crate::util;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Ge,
+    Le,
+    Gt,
+    Lt,
+    Eq,
+    Ne,
+}
+
+/// Parsed condition expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum CondExpr {
+    And(Box<CondExpr>, Box<CondExpr>),
+    Or(Box<CondExpr>, Box<CondExpr>),
+    Not(Box<CondExpr>),
+    /// A boolean function call, e.g. `command_exists(node)`
+    Call { func: String, arg: String },
+    /// A function value compared with a literal, e.g. `command_version(node) >= 18`
+    Compare { func: String, arg: String, op: CompareOp, value: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Op(CompareOp),
+    Word(String),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => { chars.next(); }
+            '(' => { chars.next(); tokens.push(Token::LParen); }
+            ')' => { chars.next(); tokens.push(Token::RParen); }
+            '&' | '|' => {
+                chars.next();
+                if chars.next() != Some(c) {
+                    return Err(anyhow!("Expected '{}{}' in condition", c, c));
+                }
+                tokens.push(if c == '&' { Token::And } else { Token::Or });
+            }
+            '!' | '<' | '>' | '=' => {
+                chars.next();
+                let eq = chars.peek() == Some(&'=');
+                if eq {
+                    chars.next();
+                }
+                tokens.push(match (c, eq) {
+                    ('!', false) => Token::Not,
+                    ('!', true) => Token::Op(CompareOp::Ne),
+                    ('<', false) => Token::Op(CompareOp::Lt),
+                    ('<', true) => Token::Op(CompareOp::Le),
+                    ('>', false) => Token::Op(CompareOp::Gt),
+                    ('>', true) => Token::Op(CompareOp::Ge),
+                    ('=', true) => Token::Op(CompareOp::Eq),
+                    _ => return Err(anyhow!("Use '==' for equality in conditions")),
+                });
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut word = String::new();
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some(ch) => word.push(ch),
+                        None => return Err(anyhow!("Unterminated string in condition")),
+                    }
+                }
+                tokens.push(Token::Word(word));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || "()&|!<>=\"'".contains(ch) {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                tokens.push(match word.to_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Word(word),
+                });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            other => Err(anyhow!("Expected {:?} in condition, found {:?}", expected, other)),
+        }
+    }
+
+    fn word(&mut self) -> Result<String> {
+        match self.next() {
+            Some(Token::Word(word)) => Ok(word),
+            other => Err(anyhow!("Expected a name or value in condition, found {:?}", other)),
+        }
+    }
+
+    fn or(&mut self) -> Result<CondExpr> {
+        let mut left = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            left = CondExpr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<CondExpr> {
+        let mut left = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            left = CondExpr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<CondExpr> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.next();
+                Ok(CondExpr::Not(Box::new(self.unary()?)))
+            }
+            Some(Token::LParen) => {
+                self.next();
+                let inner = self.or()?;
+                self.expect(Token::RParen)?;
+                Ok(inner)
+            }
+            _ => {
+                let func = self.word()?;
+                self.expect(Token::LParen)?;
+                let arg = self.word()?;
+                self.expect(Token::RParen)?;
+                if let Some(Token::Op(op)) = self.peek().cloned() {
+                    self.next();
+                    let value = self.word()?;
+                    return Ok(CondExpr::Compare { func, arg, op, value });
+                }
+                Ok(CondExpr::Call { func, arg })
+            }
+        }
+    }
+}
+
+/// Parse a condition expression into its AST
+pub fn parse(input: &str) -> Result<CondExpr> {
+    let mut parser = Parser { tokens: tokenize(input)?, pos: 0 };
+    let expr = parser.or()?;
+    if let Some(token) = parser.peek() {
+        return Err(anyhow!("Unexpected {:?} in condition '{}'", token, input));
+    }
+    Ok(expr)
+}
+
+/// Match a `/`-separated path against a glob with `*`, `?` and `**`
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    fn segments(pattern: &[&str], path: &[&str]) -> bool {
+        match (pattern.first(), path.first()) {
+            (None, None) => true,
+            (Some(&"**"), _) => {
+                segments(&pattern[1..], path) || (!path.is_empty() && segments(pattern, &path[1..]))
+            }
+            (Some(p), Some(s)) => segment(p.as_bytes(), s.as_bytes()) && segments(&pattern[1..], &path[1..]),
+            _ => false,
+        }
+    }
+    fn segment(p: &[u8], s: &[u8]) -> bool {
+        match (p.first(), s.first()) {
+            (None, None) => true,
+            (Some(b'*'), _) => segment(&p[1..], s) || (!s.is_empty() && segment(p, &s[1..])),
+            (Some(b'?'), Some(_)) => segment(&p[1..], &s[1..]),
+            (Some(a), Some(b)) if a == b => segment(&p[1..], &s[1..]),
+            _ => false,
+        }
+    }
+
+    let pattern: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let path: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    segments(&pattern, &path)
+}
+
+/// Lenient version parse: `v18.2` becomes 18.2.0
+pub fn parse_loose_version(text: &str) -> Option<semver::Version> {
+    let re = regex::Regex::new(r"(\d+)(?:\.(\d+))?(?:\.(\d+))?").ok()?;
+    let caps = re.captures(text)?;
+    let part = |i: usize| caps.get(i).and_then(|m| m.as_str().parse().ok()).unwrap_or(0);
+    Some(semver::Version::new(part(1), part(2), part(3)))
+}
+
+fn compare<T: PartialOrd>(left: T, op: CompareOp, right: T) -> bool {
+    match op {
+        CompareOp::Ge => left >= right,
+        CompareOp::Le => left <= right,
+        CompareOp::Gt => left > right,
+        CompareOp::Lt => left < right,
+        CompareOp::Eq => left == right,
+        CompareOp::Ne => left != right,
+    }
+}
+
+/// Directories never searched by `file_exists` globs
+const SKIPPED_DIRS: &[&str] = &[".git", "node_modules", "target", "vendor"];
+
+fn file_exists(workspace: &Path, pattern: &str) -> bool {
+    if !pattern.contains(['*', '?']) {
+        let path = Path::new(pattern);
+        return if path.is_absolute() { path.exists() } else { workspace.join(path).exists() };
+    }
+    WalkDir::new(workspace)
+        .into_iter()
+        .filter_entry(|e| !SKIPPED_DIRS.contains(&e.file_name().to_string_lossy().as_ref()))
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.path().strip_prefix(workspace).ok().map(|p| p.to_string_lossy().replace('\\', "/")))
+        .any(|relative| glob_match(pattern, &relative))
+}
+
+async fn command_version(command: &str) -> Option<semver::Version> {
+    let mut cmd = std::process::Command::new(command);
+    cmd.arg("--version");
+    let result = util::execute_command(&mut cmd).await.ok()?;
+    parse_loose_version(&result.stdout).or_else(|| parse_loose_version(&result.stderr))
+}
+
+impl CondExpr {
+    /// Evaluate against the workspace
+    pub fn evaluate<'a>(&'a self, workspace: &'a Path) -> Pin<Box<dyn Future<Output = Result<bool>> + 'a>> {
+        Box::pin(async move {
+            match self {
+                CondExpr::And(a, b) => Ok(a.evaluate(workspace).await? && b.evaluate(workspace).await?),
+                CondExpr::Or(a, b) => Ok(a.evaluate(workspace).await? || b.evaluate(workspace).await?),
+                CondExpr::Not(inner) => Ok(!inner.evaluate(workspace).await?),
+                CondExpr::Call { func, arg } => match func.as_str() {
+                    "file_exists" => Ok(file_exists(workspace, arg)),
+                    "command_exists" => Ok(util::command_exists(arg).await),
+                    "env" => Ok(std::env::var(arg).is_ok()),
+                    "platform" => Ok(std::env::consts::OS == arg),
+                    "source_installed" => {
+                        let builder = SourceBuilder::new(workspace, None, "/usr/local", None, Vec::new());
+                        Ok(builder.is_installed(arg).await)
+                    }
+                    _ => Err(anyhow!("Unknown condition function: {}()", func)),
+                },
+                CondExpr::Compare { func, arg, op, value } => match func.as_str() {
+                    "command_version" => {
+                        let wanted = parse_loose_version(value)
+                            .ok_or_else(|| anyhow!("'{}' is not a version", value))?;
+                        // A missing command fails every comparison
+                        Ok(command_version(arg).await.is_some_and(|v| compare(v, *op, wanted)))
+                    }
+                    "env" => match op {
+                        CompareOp::Eq | CompareOp::Ne => {
+                            Ok(compare(std::env::var(arg).unwrap_or_default().as_str(), *op, value.as_str()))
+                        }
+                        _ => Err(anyhow!("env() only supports == and !=")),
+                    },
+                    _ => Err(anyhow!("{}() cannot be compared", func)),
+                },
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_precedence() {
+        let expr = parse("command_exists(node) && command_version(node) >= 18 || not env(CI)").unwrap();
+        let call = |f: &str, a: &str| CondExpr::Call { func: f.to_string(), arg: a.to_string() };
+        assert_eq!(expr, CondExpr::Or(
+            Box::new(CondExpr::And(
+                Box::new(call("command_exists", "node")),
+                Box::new(CondExpr::Compare {
+                    func: "command_version".to_string(),
+                    arg: "node".to_string(),
+                    op: CompareOp::Ge,
+                    value: "18".to_string(),
+                }),
+            )),
+            Box::new(CondExpr::Not(Box::new(call("env", "CI")))),
+        ));
+        assert!(parse("file_exists(\"a b.txt\") and (platform(linux) or platform(macos))").is_ok());
+        assert!(parse("command_exists(node) &&").is_err());
+        assert!(parse("env(A) = 1").is_err());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("dist/**/*.js", "dist/js/app.js"));
+        assert!(glob_match("dist/**/*.js", "dist/app.js"));
+        assert!(!glob_match("dist/*.js", "dist/js/app.js"));
+        assert!(glob_match("*.lock?", "bun.lockb"));
+    }
+
+    #[test]
+    fn test_parse_loose_version() {
+        assert_eq!(parse_loose_version("v18.19.1\n"), Some(semver::Version::new(18, 19, 1)));
+        assert_eq!(parse_loose_version("PHP 8.3 (cli)"), Some(semver::Version::new(8, 3, 0)));
+        assert_eq!(parse_loose_version("none"), None);
+    }
+}
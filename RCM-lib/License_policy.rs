@@ -0,0 +1,332 @@
+//! License policy enforcement for RCM
+//!
+//! Resolves the licenses of cargo, npm and composer dependencies and checks them
+//! against `security.license_policy` (allowed and denied SPDX identifiers).
+
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use console::style;
+use serde::Serialize;
+use tabled::{Table, Tabled};
+use crate::config::LicensePolicy;
+use crate::sbom;
+use crate::workspace::Workspace;
+
+#[derive(Subcommand)]
+pub enum LicenseCommands {
+    /// Check dependency licenses against security.license_policy
+    Check {
+        /// Check specific managers only (cargo, npm, composer)
+        #[arg(long, value_delimiter = ',')]
+        managers: Option<Vec<String>>,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+        /// Show compliant dependencies too
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "status", content = "license")]
+pub enum Verdict {
+    Allowed,
+    /// Exempted through `license_policy.exceptions`
+    Exempt,
+    /// Uses a denied license
+    Denied(String),
+    /// Not on the allow list
+    NotAllowed(String),
+    /// License could not be determined
+    Unknown,
+}
+
+impl Verdict {
+    pub fn passes(&self, policy: &LicensePolicy) -> bool {
+        match self {
+            Self::Allowed | Self::Exempt => true,
+            Self::Unknown => policy.allow_unknown,
+            Self::Denied(_) | Self::NotAllowed(_) => false,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::Allowed => "allowed".to_string(),
+            Self::Exempt => "exempt".to_string(),
+            Self::Denied(license) => format!("denied ({})", license),
+            Self::NotAllowed(license) => format!("not allowed ({})", license),
+            Self::Unknown => "unknown".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LicenseFinding {
+    pub manager: String,
+    pub name: String,
+    pub version: String,
+    pub license: Option<String>,
+    pub verdict: Verdict,
+}
+
+#[derive(Tabled)]
+struct FindingRow {
+    #[tabled(rename = "Package")]
+    package: String,
+    #[tabled(rename = "Version")]
+    version: String,
+    #[tabled(rename = "Manager")]
+    manager: String,
+    #[tabled(rename = "License")]
+    license: String,
+    #[tabled(rename = "Status")]
+    status: String,
+}
+
+fn same_license(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+/// Evaluate one identifier
+fn check_id(id: &str, policy: &LicensePolicy) -> Verdict {
+    if policy.denied.iter().any(|d| same_license(d, id)) {
+        Verdict::Denied(id.to_string())
+    } else if !policy.allowed.is_empty() && !policy.allowed.iter().any(|a| same_license(a, id)) {
+        Verdict::NotAllowed(id.to_string())
+    } else {
+        Verdict::Allowed
+    }
+}
+
+/// A parsed SPDX license expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum LicenseExpr {
+    License(String),
+    And(Vec<LicenseExpr>),
+    Or(Vec<LicenseExpr>),
+}
+
+fn tokenize(expression: &str) -> Vec<String> {
+    expression
+        .replace('(', " ( ")
+        .replace(')', " ) ")
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Recursive descent over `or := and (OR and)*`, `and := term (AND term)*`,
+/// `term := "(" or ")" | id [WITH exception]`
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> Option<LicenseExpr> {
+        let mut terms = vec![self.and()?];
+        while self.peek().is_some_and(|t| t.eq_ignore_ascii_case("OR")) {
+            self.pos += 1;
+            terms.push(self.and()?);
+        }
+        Some(if terms.len() == 1 { terms.remove(0) } else { LicenseExpr::Or(terms) })
+    }
+
+    fn and(&mut self) -> Option<LicenseExpr> {
+        let mut terms = vec![self.term()?];
+        while self.peek().is_some_and(|t| t.eq_ignore_ascii_case("AND")) {
+            self.pos += 1;
+            terms.push(self.term()?);
+        }
+        Some(if terms.len() == 1 { terms.remove(0) } else { LicenseExpr::And(terms) })
+    }
+
+    fn term(&mut self) -> Option<LicenseExpr> {
+        let token = self.next()?;
+        if token == "(" {
+            let inner = self.or()?;
+            return (self.next()? == ")").then_some(inner);
+        }
+        if token == ")" || ["AND", "OR", "WITH"].iter().any(|op| token.eq_ignore_ascii_case(op)) {
+            return None;
+        }
+        // `WITH` exceptions qualify a license without changing it
+        if self.peek().is_some_and(|t| t.eq_ignore_ascii_case("WITH")) {
+            self.pos += 1;
+            self.next()?;
+        }
+        Some(LicenseExpr::License(token))
+    }
+}
+
+/// Parse an SPDX expression, honouring parentheses and `AND` binding tighter than `OR`
+pub fn parse_expression(expression: &str) -> Option<LicenseExpr> {
+    let mut parser = Parser { tokens: tokenize(expression), pos: 0 };
+    let expr = parser.or()?;
+    (parser.pos == parser.tokens.len()).then_some(expr)
+}
+
+fn evaluate_expr(expr: &LicenseExpr, policy: &LicensePolicy) -> Verdict {
+    match expr {
+        LicenseExpr::License(id) => check_id(id, policy),
+        LicenseExpr::And(terms) => terms
+            .iter()
+            .map(|t| evaluate_expr(t, policy))
+            .find(|v| *v != Verdict::Allowed)
+            .unwrap_or(Verdict::Allowed),
+        LicenseExpr::Or(terms) => {
+            let mut first_failure = None;
+            for term in terms {
+                let verdict = evaluate_expr(term, policy);
+                if verdict == Verdict::Allowed {
+                    return verdict;
+                }
+                // Report a denial over a missing allow-list entry
+                if first_failure.is_none() || matches!(verdict, Verdict::Denied(_)) {
+                    first_failure = Some(verdict);
+                }
+            }
+            first_failure.unwrap_or(Verdict::Unknown)
+        }
+    }
+}
+
+/// Evaluate an SPDX expression: `OR` needs one acceptable alternative, `AND` needs all terms
+pub fn evaluate(expression: &str, policy: &LicensePolicy) -> Verdict {
+    match parse_expression(expression) {
+        Some(expr) => evaluate_expr(&expr, policy),
+        None => Verdict::Unknown,
+    }
+}
+
+fn is_exempt(policy: &LicensePolicy, manager: &str, name: &str) -> bool {
+    policy.exceptions.iter().any(|e| match e.split_once(':') {
+        Some((m, n)) => m == manager && n == name,
+        None => e == name,
+    })
+}
+
+/// Resolve licenses and evaluate every dependency
+pub async fn check(workspace: &Workspace, policy: &LicensePolicy, managers: &[String]) -> Result<Vec<LicenseFinding>> {
+    let components = sbom::collect(workspace, managers).await?;
+    Ok(components
+        .into_iter()
+        .map(|c| {
+            let verdict = if is_exempt(policy, &c.manager, &c.name) {
+                Verdict::Exempt
+            } else {
+                c.license.as_deref().map_or(Verdict::Unknown, |l| evaluate(l, policy))
+            };
+            LicenseFinding { manager: c.manager, name: c.name, version: c.version, license: c.license, verdict }
+        })
+        .collect())
+}
+
+/// Handle license commands
+pub async fn handle_command(workspace: &Workspace, policy: &LicensePolicy, cmd: LicenseCommands) -> Result<()> {
+    match cmd {
+        LicenseCommands::Check { managers, format, all } => {
+            let managers: Vec<String> = managers
+                .unwrap_or_else(|| workspace.enabled_managers())
+                .into_iter()
+                .filter(|m| matches!(m.as_str(), "cargo" | "npm" | "composer"))
+                .collect();
+            if managers.is_empty() {
+                return Err(anyhow!("No cargo, npm or composer dependencies to check"));
+            }
+            if policy.allowed.is_empty() && policy.denied.is_empty() && format == "table" {
+                println!("{}", style("ℹ️  No license policy configured; set security.license_policy.allowed or .denied").dim());
+            }
+
+            let findings = check(workspace, policy, &managers).await?;
+            let failing: Vec<&LicenseFinding> = findings.iter().filter(|f| !f.verdict.passes(policy)).collect();
+
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&findings)?),
+                "table" => {
+                    let rows: Vec<FindingRow> = findings
+                        .iter()
+                        .filter(|f| all || !f.verdict.passes(policy))
+                        .map(|f| FindingRow {
+                            package: f.name.clone(),
+                            version: f.version.clone(),
+                            manager: f.manager.clone(),
+                            license: f.license.clone().unwrap_or_else(|| "-".to_string()),
+                            status: f.verdict.label(),
+                        })
+                        .collect();
+                    if !rows.is_empty() {
+                        println!("{}", Table::new(rows));
+                    }
+                }
+                _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+            }
+
+            if !failing.is_empty() {
+                return Err(anyhow!(
+                    "{} of {} dependencies violate the license policy",
+                    failing.len(),
+                    findings.len()
+                ));
+            }
+            if format == "table" {
+                println!("{}", style(format!("✅ {} dependencies comply with the license policy", findings.len())).green().bold());
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate_expressions() {
+        let policy = LicensePolicy {
+            allowed: vec!["MIT".into(), "Apache-2.0".into(), "BSD-3-Clause".into()],
+            denied: vec!["GPL-3.0-only".into()],
+            allow_unknown: false,
+            exceptions: vec!["npm:caniuse-lite".into()],
+        };
+        assert_eq!(evaluate("MIT", &policy), Verdict::Allowed);
+        assert_eq!(evaluate("GPL-3.0-only OR MIT", &policy), Verdict::Allowed);
+        assert_eq!(evaluate("(MIT AND GPL-3.0-only)", &policy), Verdict::Denied("GPL-3.0-only".into()));
+        assert_eq!(evaluate("MPL-2.0", &policy), Verdict::NotAllowed("MPL-2.0".into()));
+        assert_eq!(evaluate("Apache-2.0 WITH LLVM-exception", &policy), Verdict::Allowed);
+        // Flattening would read this as `MIT OR GPL-3.0-only AND MPL-2.0`
+        assert_eq!(evaluate("MIT AND (GPL-3.0-only OR MPL-2.0)", &policy), Verdict::Denied("GPL-3.0-only".into()));
+        assert_eq!(evaluate("(MIT OR GPL-3.0-only) AND Apache-2.0", &policy), Verdict::Allowed);
+        assert_eq!(evaluate("(MIT OR", &policy), Verdict::Unknown);
+        assert!(!Verdict::Unknown.passes(&policy));
+        assert!(is_exempt(&policy, "npm", "caniuse-lite"));
+        assert!(!is_exempt(&policy, "cargo", "caniuse-lite"));
+    }
+
+    #[test]
+    fn test_parse_expression() {
+        let license = |id: &str| LicenseExpr::License(id.to_string());
+        assert_eq!(
+            parse_expression("MIT OR Apache-2.0 AND BSD-3-Clause"),
+            Some(LicenseExpr::Or(vec![license("MIT"), LicenseExpr::And(vec![license("Apache-2.0"), license("BSD-3-Clause")])]))
+        );
+        assert_eq!(
+            parse_expression("(MIT OR Apache-2.0) AND BSD-3-Clause"),
+            Some(LicenseExpr::And(vec![LicenseExpr::Or(vec![license("MIT"), license("Apache-2.0")]), license("BSD-3-Clause")]))
+        );
+        assert_eq!(parse_expression("MIT AND"), None);
+        assert_eq!(parse_expression(""), None);
+    }
+}
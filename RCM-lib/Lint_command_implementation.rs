@@ -0,0 +1,460 @@
+//! Lint command implementation
+//!
+//! Runs the linters and formatters present in the workspace (clippy, rustfmt,
+//! eslint, prettier, phpstan, php-cs-fixer, shellcheck) and merges their
+//! diagnostics into one report
+
+use anyhow::{anyhow, Result};
+use console::style;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tabled::{Table, Tabled};
+use walkdir::WalkDir;
+use crate::isolation;
+use crate::util;
+use crate::workspace::Workspace;
+
+/// A diagnostic from any linter, with a workspace-relative location
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub tool: String,
+    pub file: String,
+    pub line: Option<u64>,
+    pub column: Option<u64>,
+    /// error, warning or info
+    pub severity: String,
+    pub code: Option<String>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn location(&self) -> String {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("{}:{}:{}", self.file, line, column),
+            (Some(line), None) => format!("{}:{}", self.file, line),
+            _ => self.file.clone(),
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct DiagnosticRow {
+    #[tabled(rename = "Location")]
+    location: String,
+    #[tabled(rename = "Tool")]
+    tool: String,
+    #[tabled(rename = "Severity")]
+    severity: String,
+    #[tabled(rename = "Message")]
+    message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Linter {
+    Clippy,
+    Rustfmt,
+    Eslint,
+    Prettier,
+    Phpstan,
+    PhpCsFixer,
+    Shellcheck,
+}
+
+impl Linter {
+    pub const ALL: [Linter; 7] = [
+        Self::Clippy, Self::Rustfmt, Self::Eslint, Self::Prettier,
+        Self::Phpstan, Self::PhpCsFixer, Self::Shellcheck,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Clippy => "clippy",
+            Self::Rustfmt => "rustfmt",
+            Self::Eslint => "eslint",
+            Self::Prettier => "prettier",
+            Self::Phpstan => "phpstan",
+            Self::PhpCsFixer => "php-cs-fixer",
+            Self::Shellcheck => "shellcheck",
+        }
+    }
+
+    /// Whether the workspace is set up for this linter and its binary is available
+    async fn available(&self, root: &Path) -> bool {
+        let has = |f: &str| root.join(f).exists();
+        let any = |files: &[&str]| files.iter().any(|f| has(f));
+        match self {
+            Self::Clippy | Self::Rustfmt => has("Cargo.toml") && util::command_exists("cargo").await,
+            Self::Eslint => has("node_modules/.bin/eslint")
+                && any(&["eslint.config.js", "eslint.config.mjs", "eslint.config.cjs", ".eslintrc.json", ".eslintrc.js", ".eslintrc.cjs"]),
+            Self::Prettier => has("node_modules/.bin/prettier"),
+            Self::Phpstan => has("vendor/bin/phpstan"),
+            Self::PhpCsFixer => has("vendor/bin/php-cs-fixer"),
+            Self::Shellcheck => !shell_scripts(root).is_empty() && util::command_exists("shellcheck").await,
+        }
+    }
+
+    /// Command reporting diagnostics, or applying fixes with `fix`
+    fn command(&self, root: &Path, fix: bool) -> Option<Command> {
+        let bin = |path: &str| root.join(path);
+        let mut cmd = match self {
+            Self::Clippy => {
+                let mut c = Command::new("cargo");
+                c.args(["clippy", "--all-targets", "--message-format=json"]);
+                if fix {
+                    c.args(["--fix", "--allow-dirty", "--allow-staged"]);
+                }
+                c
+            }
+            Self::Rustfmt => {
+                let mut c = Command::new("cargo");
+                c.arg("fmt");
+                if !fix {
+                    c.arg("--check");
+                }
+                c
+            }
+            Self::Eslint => {
+                let mut c = Command::new(bin("node_modules/.bin/eslint"));
+                c.args([".", "--format", "json"]);
+                if fix {
+                    c.arg("--fix");
+                }
+                c
+            }
+            Self::Prettier => {
+                let mut c = Command::new(bin("node_modules/.bin/prettier"));
+                c.args([if fix { "--write" } else { "--check" }, "."]);
+                c
+            }
+            Self::Phpstan => {
+                // phpstan has no fixer; report only
+                let mut c = Command::new(bin("vendor/bin/phpstan"));
+                c.args(["analyse", "--error-format=json", "--no-progress"]);
+                c
+            }
+            Self::PhpCsFixer => {
+                let mut c = Command::new(bin("vendor/bin/php-cs-fixer"));
+                c.args(["fix", "--format=json"]);
+                if !fix {
+                    c.arg("--dry-run");
+                }
+                c
+            }
+            Self::Shellcheck => {
+                if fix {
+                    return None;
+                }
+                let mut c = Command::new("shellcheck");
+                c.args(["--format=json1"]).args(shell_scripts(root));
+                c
+            }
+        };
+        cmd.current_dir(root).envs(isolation::env_vars(root));
+        Some(cmd)
+    }
+
+    /// Normalize the linter's output
+    pub fn parse(&self, root: &Path, stdout: &str, stderr: &str) -> Vec<Diagnostic> {
+        match self {
+            Self::Clippy => parse_clippy(stdout),
+            Self::Rustfmt => parse_rustfmt(root, stdout),
+            Self::Eslint => parse_eslint(root, stdout),
+            Self::Prettier => parse_prettier(&format!("{}\n{}", stdout, stderr)),
+            Self::Phpstan => parse_phpstan(root, stdout),
+            Self::PhpCsFixer => parse_php_cs_fixer(stdout),
+            Self::Shellcheck => parse_shellcheck(stdout),
+        }
+    }
+}
+
+fn shell_scripts(root: &Path) -> Vec<PathBuf> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| !matches!(e.file_name().to_str(), Some(".git" | "node_modules" | "target" | "vendor")))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.path().extension().is_some_and(|ext| ext == "sh"))
+        .filter_map(|e| e.path().strip_prefix(root).ok().map(Path::to_path_buf))
+        .collect()
+}
+
+fn relative(root: &Path, file: &str) -> String {
+    Path::new(file).strip_prefix(root).map(|p| p.display().to_string()).unwrap_or_else(|_| file.to_string())
+}
+
+fn diagnostic(tool: &str, file: String, line: Option<u64>, column: Option<u64>, severity: &str, code: Option<String>, message: String) -> Diagnostic {
+    Diagnostic { tool: tool.to_string(), file, line, column, severity: severity.to_string(), code, message }
+}
+
+/// `cargo clippy --message-format=json` (one JSON object per line)
+pub fn parse_clippy(stdout: &str) -> Vec<Diagnostic> {
+    stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|v| v["reason"] == "compiler-message")
+        .filter_map(|v| {
+            let message = &v["message"];
+            let span = message["spans"].as_array()?.iter().find(|s| s["is_primary"] == true)?;
+            let severity = match message["level"].as_str()? {
+                "error" => "error",
+                "warning" => "warning",
+                _ => "info",
+            };
+            Some(diagnostic(
+                "clippy",
+                span["file_name"].as_str()?.to_string(),
+                span["line_start"].as_u64(),
+                span["column_start"].as_u64(),
+                severity,
+                message["code"]["code"].as_str().map(str::to_string),
+                message["message"].as_str()?.to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// `cargo fmt --check` prints `Diff in <file> at line <n>:`
+pub fn parse_rustfmt(root: &Path, stdout: &str) -> Vec<Diagnostic> {
+    stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("Diff in "))
+        .filter_map(|rest| {
+            let (file, line) = rest.rsplit_once(" at line ")?;
+            Some(diagnostic(
+                "rustfmt",
+                relative(root, file),
+                line.trim_end_matches(':').parse().ok(),
+                None,
+                "warning",
+                None,
+                "not formatted (run with --fix)".to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// `eslint --format json`
+pub fn parse_eslint(root: &Path, stdout: &str) -> Vec<Diagnostic> {
+    let files: Vec<Value> = serde_json::from_str(stdout).unwrap_or_default();
+    files
+        .iter()
+        .flat_map(|file| {
+            let path = relative(root, file["filePath"].as_str().unwrap_or_default());
+            file["messages"].as_array().cloned().unwrap_or_default().into_iter().map(move |m| {
+                diagnostic(
+                    "eslint",
+                    path.clone(),
+                    m["line"].as_u64(),
+                    m["column"].as_u64(),
+                    if m["severity"] == 2 { "error" } else { "warning" },
+                    m["ruleId"].as_str().map(str::to_string),
+                    m["message"].as_str().unwrap_or_default().to_string(),
+                )
+            })
+        })
+        .collect()
+}
+
+/// `prettier --check` prints `[warn] <file>` per unformatted file
+pub fn parse_prettier(output: &str) -> Vec<Diagnostic> {
+    output
+        .lines()
+        .filter_map(|line| line.strip_prefix("[warn] "))
+        .filter(|file| !file.contains(' '))
+        .map(|file| diagnostic("prettier", file.to_string(), None, None, "warning", None, "not formatted (run with --fix)".to_string()))
+        .collect()
+}
+
+/// `phpstan analyse --error-format=json`
+pub fn parse_phpstan(root: &Path, stdout: &str) -> Vec<Diagnostic> {
+    let report: Value = serde_json::from_str(stdout).unwrap_or_default();
+    let Some(files) = report["files"].as_object() else {
+        return Vec::new();
+    };
+    files
+        .iter()
+        .flat_map(|(file, result)| {
+            let path = relative(root, file);
+            result["messages"].as_array().cloned().unwrap_or_default().into_iter().map(move |m| {
+                diagnostic(
+                    "phpstan",
+                    path.clone(),
+                    m["line"].as_u64(),
+                    None,
+                    "error",
+                    m["identifier"].as_str().map(str::to_string),
+                    m["message"].as_str().unwrap_or_default().to_string(),
+                )
+            })
+        })
+        .collect()
+}
+
+/// `php-cs-fixer fix --dry-run --format=json` lists files that need fixing
+pub fn parse_php_cs_fixer(stdout: &str) -> Vec<Diagnostic> {
+    let report: Value = serde_json::from_str(stdout).unwrap_or_default();
+    report["files"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|f| {
+            let rules: Vec<&str> = f["appliedFixers"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+            Some(diagnostic(
+                "php-cs-fixer",
+                f["name"].as_str()?.to_string(),
+                None,
+                None,
+                "warning",
+                None,
+                if rules.is_empty() { "not formatted (run with --fix)".to_string() } else { format!("needs {}", rules.join(", ")) },
+            ))
+        })
+        .collect()
+}
+
+/// `shellcheck --format=json1`
+pub fn parse_shellcheck(stdout: &str) -> Vec<Diagnostic> {
+    let report: Value = serde_json::from_str(stdout).unwrap_or_default();
+    report["comments"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|c| {
+            let severity = match c["level"].as_str()? {
+                "error" => "error",
+                "warning" => "warning",
+                _ => "info",
+            };
+            Some(diagnostic(
+                "shellcheck",
+                c["file"].as_str()?.to_string(),
+                c["line"].as_u64(),
+                c["column"].as_u64(),
+                severity,
+                c["code"].as_u64().map(|code| format!("SC{}", code)),
+                c["message"].as_str()?.to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// Run the linters and print one report; fails when any error is reported
+pub async fn run(workspace: &Workspace, tools: Option<Vec<String>>, fix: bool, format: &str) -> Result<()> {
+    let root = workspace.root();
+    let mut linters = Vec::new();
+    for linter in Linter::ALL {
+        if tools.as_ref().is_some_and(|t| !t.iter().any(|n| n == linter.name())) {
+            continue;
+        }
+        if linter.available(root).await {
+            linters.push(linter);
+        }
+    }
+    if linters.is_empty() {
+        return Err(anyhow!("No supported linters found (clippy, rustfmt, eslint, prettier, phpstan, php-cs-fixer, shellcheck)"));
+    }
+
+    let mut diagnostics = Vec::new();
+    let mut crashed = Vec::new();
+    for linter in &linters {
+        if fix {
+            if let Some(mut cmd) = linter.command(root, true) {
+                if format == "table" {
+                    println!("{}", style(format!("🔧 Fixing with {}...", linter.name())).blue());
+                }
+                if !util::skip_in_dry_run(&cmd) {
+                    // Fixers exit non-zero when problems remain; the check below reports them
+                    let _ = tokio::process::Command::from(cmd).output().await;
+                }
+            }
+        }
+
+        let Some(cmd) = linter.command(root, false) else { continue };
+        if format == "table" {
+            println!("{}", style(format!("🔍 Running {}...", linter.name())).blue());
+        }
+        let output = tokio::process::Command::from(cmd).output().await?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let found = linter.parse(root, &stdout, &stderr);
+        // A non-zero exit with nothing to report means the linter itself broke
+        if found.is_empty() && !output.status.success() {
+            eprintln!("{} {} failed without diagnostics: {}", style("✗").red(), linter.name(), stderr.trim());
+            crashed.push(linter.name());
+        }
+        diagnostics.extend(found);
+    }
+
+    diagnostics.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+    let errors = diagnostics.iter().filter(|d| d.severity == "error").count();
+    let warnings = diagnostics.iter().filter(|d| d.severity == "warning").count();
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&diagnostics)?),
+        "table" => {
+            if diagnostics.is_empty() {
+                println!("{}", style("✅ No lint problems found").green().bold());
+            } else {
+                let rows: Vec<DiagnosticRow> = diagnostics
+                    .iter()
+                    .map(|d| DiagnosticRow {
+                        location: d.location(),
+                        tool: d.tool.clone(),
+                        severity: d.severity.clone(),
+                        message: match &d.code {
+                            Some(code) => format!("{} [{}]", d.message, code),
+                            None => d.message.clone(),
+                        },
+                    })
+                    .collect();
+                println!("{}", Table::new(rows));
+                println!("{} error(s), {} warning(s) from {}", errors, warnings,
+                    linters.iter().map(Linter::name).collect::<Vec<_>>().join(", "));
+            }
+        }
+        _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+    }
+
+    if errors > 0 {
+        return Err(anyhow!("Lint failed with {} error(s)", errors));
+    }
+    if !crashed.is_empty() {
+        return Err(anyhow!("Lint failed: {} did not run cleanly", crashed.join(", ")));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_clippy() {
+        let line = r#"{"reason":"compiler-message","message":{"level":"warning","message":"unused variable: `x`","code":{"code":"unused_variables"},"spans":[{"file_name":"src/main.rs","line_start":3,"column_start":9,"is_primary":true}]}}"#;
+        let diagnostics = parse_clippy(&format!("{}\n{{\"reason\":\"build-finished\"}}", line));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].location(), "src/main.rs:3:9");
+        assert_eq!(diagnostics[0].code.as_deref(), Some("unused_variables"));
+    }
+
+    #[test]
+    fn test_parse_eslint_and_rustfmt() {
+        let root = Path::new("/work");
+        let eslint = r#"[{"filePath":"/work/src/a.ts","messages":[{"line":2,"column":5,"severity":2,"ruleId":"no-undef","message":"'x' is not defined."}]}]"#;
+        let diagnostics = parse_eslint(root, eslint);
+        assert_eq!(diagnostics[0].location(), "src/a.ts:2:5");
+        assert_eq!(diagnostics[0].severity, "error");
+
+        let rustfmt = parse_rustfmt(root, "Diff in /work/src/lib.rs at line 12:\n-fn a(){}\n");
+        assert_eq!(rustfmt[0].location(), "src/lib.rs:12");
+    }
+
+    #[test]
+    fn test_parse_shellcheck() {
+        let json = r#"{"comments":[{"file":"scripts/build.sh","line":4,"column":7,"level":"warning","code":2086,"message":"Double quote to prevent globbing."}]}"#;
+        let diagnostics = parse_shellcheck(json);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("SC2086"));
+        assert_eq!(diagnostics[0].location(), "scripts/build.sh:4:7");
+    }
+}
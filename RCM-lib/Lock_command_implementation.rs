@@ -0,0 +1,71 @@
+//! Lock command implementation
+//!
+//! Writes rcm.lock from the resolved state of every enabled manager, or verifies it
+
+use anyhow::{anyhow, Result};
+use console::style;
+use crate::lockfile::LockManager;
+use crate::workspace::Workspace;
+
+/// Generate or verify rcm.lock
+pub async fn run(workspace: &Workspace, managers: Option<Vec<String>>, verify: bool) -> Result<()> {
+    let target_managers = managers.unwrap_or_else(|| workspace.enabled_managers());
+
+    if target_managers.is_empty() {
+        return Err(anyhow!("No package managers enabled. Run 'rcm init' to configure managers."));
+    }
+
+    let lock_manager = LockManager::new(workspace.root());
+
+    if verify {
+        verify_lock(workspace, &lock_manager, &target_managers).await
+    } else {
+        write_lock(workspace, &lock_manager, &target_managers).await
+    }
+}
+
+async fn write_lock(workspace: &Workspace, lock_manager: &LockManager, managers: &[String]) -> Result<()> {
+    println!("{}", style("🔒 Resolving packages across managers...").cyan().bold());
+
+    let lock = lock_manager.collect(workspace, managers).await?;
+
+    for (manager, state) in &lock.managers {
+        let count = lock.packages.iter().filter(|p| &p.manager == manager).count();
+        match &state.lockfile {
+            Some(lockfile) => println!("  {} {}: {} package(s) from {}", style("✓").green(), manager, count, lockfile),
+            None => println!("  {} {}: {} package(s), no native lockfile", style("⚠").yellow(), manager, count),
+        }
+    }
+
+    lock_manager.save(&lock).await?;
+
+    println!("{}", style(format!(
+        "✅ Wrote {} ({} packages)",
+        lock_manager.lock_path().display(),
+        lock.packages.len()
+    )).green().bold());
+
+    Ok(())
+}
+
+async fn verify_lock(workspace: &Workspace, lock_manager: &LockManager, managers: &[String]) -> Result<()> {
+    println!("{}", style("🔍 Verifying rcm.lock...").cyan().bold());
+
+    let locked = lock_manager.load().await?;
+    let current = lock_manager.collect(workspace, managers).await?;
+    let drift = LockManager::diff(&locked, &current);
+
+    if drift.is_empty() {
+        println!("{}", style(format!("✅ rcm.lock is up to date ({} packages)", current.packages.len())).green().bold());
+        return Ok(());
+    }
+
+    for entry in &drift {
+        println!("  {}", style(entry.to_string()).yellow());
+    }
+
+    Err(anyhow!(
+        "rcm.lock is out of date ({} difference(s)). Run 'rcm lock' to update it.",
+        drift.len()
+    ))
+}
@@ -0,0 +1,608 @@
+//! Unified lockfile (rcm.lock) for RCM
+//!
+//! Records resolved versions, checksums and sources across all enabled managers
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use crate::workspace::Workspace;
+use crate::gem;
+use crate::go::{self, GoManager};
+use crate::ppm::ComposerLock;
+use crate::system::SystemPackageManager;
+use crate::util;
+
+/// File name of the unified lockfile at the workspace root
+pub const LOCKFILE_NAME: &str = "rcm.lock";
+
+/// Current lockfile format version
+pub const LOCKFILE_VERSION: u32 = 1;
+
+/// Contents of rcm.lock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RcmLock {
+    pub version: u32,
+    pub generated_at: String,
+    pub rcm_version: String,
+    /// Native lockfile fingerprints keyed by manager
+    #[serde(default)]
+    pub managers: BTreeMap<String, ManagerLock>,
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+}
+
+/// Per-manager lock state
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ManagerLock {
+    /// Native lockfile the entries were read from (relative to workspace root)
+    pub lockfile: Option<String>,
+    /// SHA-256 of the native lockfile
+    pub lockfile_hash: Option<String>,
+    /// Default registry for this manager
+    pub registry: Option<String>,
+}
+
+/// A single resolved package
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    pub manager: String,
+    pub source: Option<String>,
+    pub checksum: Option<String>,
+    #[serde(default)]
+    pub dev: bool,
+}
+
+impl LockedPackage {
+    fn key(&self) -> (String, String) {
+        (self.manager.clone(), self.name.clone())
+    }
+}
+
+/// Difference between rcm.lock and the current workspace state
+#[derive(Debug, Clone, PartialEq)]
+pub enum LockDrift {
+    Added(LockedPackage),
+    Removed(LockedPackage),
+    Changed { locked: LockedPackage, current: LockedPackage },
+    LockfileChanged { manager: String, lockfile: String },
+}
+
+impl std::fmt::Display for LockDrift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Added(pkg) => write!(f, "+ {}:{} {} (not in rcm.lock)", pkg.manager, pkg.name, pkg.version),
+            Self::Removed(pkg) => write!(f, "- {}:{} {} (locked but not resolved)", pkg.manager, pkg.name, pkg.version),
+            Self::Changed { locked, current } if locked.version != current.version => write!(
+                f, "~ {}:{} {} -> {}", locked.manager, locked.name, locked.version, current.version
+            ),
+            Self::Changed { locked, .. } => write!(
+                f, "~ {}:{} {} (checksum or source changed)", locked.manager, locked.name, locked.version
+            ),
+            Self::LockfileChanged { manager, lockfile } => write!(
+                f, "~ {} lockfile {} changed since rcm.lock was written", manager, lockfile
+            ),
+        }
+    }
+}
+
+/// Reads, writes and verifies rcm.lock
+pub struct LockManager {
+    workspace_root: PathBuf,
+    lock_path: PathBuf,
+}
+
+impl LockManager {
+    pub fn new(workspace_root: &Path) -> Self {
+        Self {
+            workspace_root: workspace_root.to_path_buf(),
+            lock_path: workspace_root.join(LOCKFILE_NAME),
+        }
+    }
+
+    pub fn lock_path(&self) -> &Path {
+        &self.lock_path
+    }
+
+    /// Load the existing rcm.lock
+    pub async fn load(&self) -> Result<RcmLock> {
+        let content = fs::read_to_string(&self.lock_path).await
+            .with_context(|| format!("Failed to read {}. Run 'rcm lock' first.", self.lock_path.display()))?;
+
+        let lock: RcmLock = toml::from_str(&content)
+            .context("Failed to parse rcm.lock")?;
+
+        if lock.version > LOCKFILE_VERSION {
+            return Err(anyhow!(
+                "rcm.lock format version {} is newer than supported version {}",
+                lock.version, LOCKFILE_VERSION
+            ));
+        }
+
+        Ok(lock)
+    }
+
+    /// Write rcm.lock
+    pub async fn save(&self, lock: &RcmLock) -> Result<()> {
+        let body = toml::to_string_pretty(lock)
+            .context("Failed to serialize rcm.lock")?;
+        let content = format!(
+            "# This file is generated by `rcm lock`. Do not edit it by hand.\n{}",
+            body
+        );
+
+        util::write_file(&self.lock_path, content).await
+            .context("Failed to write rcm.lock")?;
+
+        Ok(())
+    }
+
+    /// Resolve the current state of the given managers
+    pub async fn collect(&self, workspace: &Workspace, managers: &[String]) -> Result<RcmLock> {
+        let mut lock = RcmLock {
+            version: LOCKFILE_VERSION,
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            rcm_version: env!("CARGO_PKG_VERSION").to_string(),
+            managers: BTreeMap::new(),
+            packages: Vec::new(),
+        };
+
+        for manager in managers {
+            let (manager_lock, packages) = match manager.as_str() {
+                "cargo" => self.collect_cargo().await?,
+                "npm" => self.collect_npm().await?,
+                "composer" => self.collect_composer().await?,
+                "bundler" => self.collect_bundler().await?,
+                "go" => self.collect_go().await?,
+                "system" => self.collect_system(workspace).await?,
+                _ => return Err(anyhow!("Unknown manager: {}", manager)),
+            };
+
+            lock.managers.insert(manager.clone(), manager_lock);
+            lock.packages.extend(packages);
+        }
+
+        lock.packages.sort_by_key(|p| p.key());
+        Ok(lock)
+    }
+
+    /// Compare a stored lock with the current state
+    pub fn diff(locked: &RcmLock, current: &RcmLock) -> Vec<LockDrift> {
+        let mut drift = Vec::new();
+
+        for (manager, current_state) in &current.managers {
+            if let Some(locked_state) = locked.managers.get(manager) {
+                if locked_state.lockfile_hash.is_some()
+                    && locked_state.lockfile_hash != current_state.lockfile_hash
+                {
+                    drift.push(LockDrift::LockfileChanged {
+                        manager: manager.clone(),
+                        lockfile: current_state.lockfile.clone()
+                            .or_else(|| locked_state.lockfile.clone())
+                            .unwrap_or_default(),
+                    });
+                }
+            }
+        }
+
+        // A package may resolve to several versions (e.g. nested npm copies), so
+        // compare every entry under a (manager, name) key rather than the last one
+        let mut groups: BTreeMap<(String, String), (Vec<&LockedPackage>, Vec<&LockedPackage>)> = BTreeMap::new();
+        for pkg in locked.packages.iter().filter(|p| current.managers.contains_key(&p.manager)) {
+            groups.entry(pkg.key()).or_default().0.push(pkg);
+        }
+        for pkg in &current.packages {
+            groups.entry(pkg.key()).or_default().1.push(pkg);
+        }
+
+        for (mut locked_pkgs, mut current_pkgs) in groups.into_values() {
+            // Identical entries are unchanged; the same version with another checksum or source changed
+            let mut changed = Vec::new();
+            locked_pkgs.retain(|pkg| take(&mut current_pkgs, |c| c == *pkg).is_none());
+            locked_pkgs.retain(|pkg| match take(&mut current_pkgs, |c| c.version == pkg.version) {
+                Some(current_pkg) => {
+                    changed.push((*pkg, current_pkg));
+                    false
+                }
+                None => true,
+            });
+
+            // Remaining entries pair up as version changes; the rest were removed or added
+            locked_pkgs.sort_by(|a, b| a.version.cmp(&b.version));
+            current_pkgs.sort_by(|a, b| a.version.cmp(&b.version));
+            let mut locked_rest = locked_pkgs.into_iter();
+            let mut current_rest = current_pkgs.into_iter();
+            loop {
+                match (locked_rest.next(), current_rest.next()) {
+                    (Some(locked_pkg), Some(current_pkg)) => changed.push((locked_pkg, current_pkg)),
+                    (Some(locked_pkg), None) => drift.push(LockDrift::Removed(locked_pkg.clone())),
+                    (None, Some(current_pkg)) => drift.push(LockDrift::Added(current_pkg.clone())),
+                    (None, None) => break,
+                }
+            }
+
+            drift.extend(changed.into_iter().map(|(locked_pkg, current_pkg)| LockDrift::Changed {
+                locked: locked_pkg.clone(),
+                current: current_pkg.clone(),
+            }));
+        }
+
+        drift
+    }
+
+    async fn collect_cargo(&self) -> Result<(ManagerLock, Vec<LockedPackage>)> {
+        let path = self.workspace_root.join("Cargo.lock");
+        let mut state = ManagerLock {
+            registry: Some("https://crates.io".to_string()),
+            ..Default::default()
+        };
+
+        if !path.exists() {
+            return Ok((state, Vec::new()));
+        }
+
+        let content = fs::read_to_string(&path).await
+            .context("Failed to read Cargo.lock")?;
+        state.lockfile = Some("Cargo.lock".to_string());
+        state.lockfile_hash = Some(util::get_file_hash(&path).await?);
+
+        Ok((state, parse_cargo_lock(&content)?))
+    }
+
+    async fn collect_npm(&self) -> Result<(ManagerLock, Vec<LockedPackage>)> {
+        let mut state = ManagerLock {
+            registry: Some("https://registry.npmjs.org".to_string()),
+            ..Default::default()
+        };
+
+        let package_lock = self.workspace_root.join("package-lock.json");
+        if package_lock.exists() {
+            let content = fs::read_to_string(&package_lock).await
+                .context("Failed to read package-lock.json")?;
+            state.lockfile = Some("package-lock.json".to_string());
+            state.lockfile_hash = Some(util::get_file_hash(&package_lock).await?);
+            return Ok((state, parse_package_lock(&content)?));
+        }
+
+        // yarn and pnpm lockfiles are YAML-like and bun's is binary; pin them by hash only
+        for name in ["pnpm-lock.yaml", "yarn.lock", "bun.lockb"] {
+            let path = self.workspace_root.join(name);
+            if path.exists() {
+                state.lockfile = Some(name.to_string());
+                state.lockfile_hash = Some(util::get_file_hash(&path).await?);
+                break;
+            }
+        }
+
+        Ok((state, Vec::new()))
+    }
+
+    async fn collect_composer(&self) -> Result<(ManagerLock, Vec<LockedPackage>)> {
+        let path = self.workspace_root.join("composer.lock");
+        let mut state = ManagerLock {
+            registry: Some("https://repo.packagist.org".to_string()),
+            ..Default::default()
+        };
+
+        if !path.exists() {
+            return Ok((state, Vec::new()));
+        }
+
+        let content = fs::read_to_string(&path).await
+            .context("Failed to read composer.lock")?;
+        let composer_lock: ComposerLock = serde_json::from_str(&content)
+            .context("Failed to parse composer.lock")?;
+
+        state.lockfile = Some("composer.lock".to_string());
+        state.lockfile_hash = Some(util::get_file_hash(&path).await?);
+
+        let packages = composer_lock.packages.iter().map(|p| (p, false))
+            .chain(composer_lock.packages_dev.iter().map(|p| (p, true)))
+            .map(|(pkg, dev)| LockedPackage {
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                manager: "composer".to_string(),
+                source: pkg.dist.as_ref().map(|d| d.url.clone())
+                    .or_else(|| pkg.source.as_ref().map(|s| s.url.clone())),
+                checksum: pkg.dist.as_ref().and_then(|d| d.shasum.clone()).filter(|s| !s.is_empty())
+                    .or_else(|| pkg.source.as_ref().map(|s| s.reference.clone())),
+                dev,
+            })
+            .collect();
+
+        Ok((state, packages))
+    }
+
+    /// Read Gemfile.lock directly, so locking works on machines without Ruby
+    async fn collect_bundler(&self) -> Result<(ManagerLock, Vec<LockedPackage>)> {
+        let path = self.workspace_root.join("Gemfile.lock");
+        let mut state = ManagerLock {
+            registry: Some("https://rubygems.org".to_string()),
+            ..Default::default()
+        };
+
+        if !path.exists() {
+            return Ok((state, Vec::new()));
+        }
+
+        let content = fs::read_to_string(&path).await
+            .context("Failed to read Gemfile.lock")?;
+        state.lockfile = Some("Gemfile.lock".to_string());
+        state.lockfile_hash = Some(util::get_file_hash(&path).await?);
+
+        let mut packages: Vec<LockedPackage> = gem::parse_gemfile_lock(&content)
+            .into_iter()
+            .map(|gem| LockedPackage {
+                name: gem.name,
+                version: gem.version,
+                manager: "bundler".to_string(),
+                source: (gem.source != "GEM").then(|| gem.source.to_lowercase()),
+                checksum: None,
+                dev: false,
+            })
+            .collect();
+        // Native gems are listed once per platform
+        packages.dedup_by(|a, b| a.name == b.name);
+
+        Ok((state, packages))
+    }
+
+    async fn collect_go(&self) -> Result<(ManagerLock, Vec<LockedPackage>)> {
+        let path = self.workspace_root.join("go.sum");
+        let mut state = ManagerLock {
+            registry: Some(go::GO_PROXY.to_string()),
+            ..Default::default()
+        };
+
+        if !path.exists() {
+            return Ok((state, Vec::new()));
+        }
+
+        state.lockfile = Some("go.sum".to_string());
+        state.lockfile_hash = Some(util::get_file_hash(&path).await?);
+
+        // go.sum may still list versions MVS no longer selects; keep only the required ones
+        let go_manager = GoManager::new(&self.workspace_root);
+        let requires = go_manager.load_go_mod().await?.requires;
+        let packages = go_manager.load_go_sum().await?
+            .into_iter()
+            .filter(|entry| requires.iter().any(|r| r.path == entry.path && r.version == entry.version))
+            .map(|entry| LockedPackage {
+                source: Some(format!("{}/{}/@v/{}.zip", go::GO_PROXY, entry.path, entry.version)),
+                name: entry.path,
+                version: entry.version,
+                manager: "go".to_string(),
+                checksum: Some(entry.hash),
+                dev: false,
+            })
+            .collect();
+
+        Ok((state, packages))
+    }
+
+    async fn collect_system(&self, workspace: &Workspace) -> Result<(ManagerLock, Vec<LockedPackage>)> {
+        let system_manager = SystemPackageManager::detect().await?;
+        let state = ManagerLock {
+            registry: Some(system_manager.command().to_string()),
+            ..Default::default()
+        };
+
+        let mut packages = Vec::new();
+        for (name, spec) in workspace.list_dependencies() {
+            if spec.manager != "system" {
+                continue;
+            }
+
+            let mut cmd = system_manager.installed_version_cmd(&name);
+            let version = system_version(&system_manager, &name, util::execute_command(&mut cmd).await);
+
+            packages.push(LockedPackage {
+                name: name.clone(),
+                version,
+                manager: "system".to_string(),
+                source: Some(system_manager.command().to_string()),
+                checksum: None,
+                dev: spec.dev_only,
+            });
+        }
+
+        Ok((state, packages))
+    }
+}
+
+/// Remove and return the first package matching `pred`
+fn take<'a>(packages: &mut Vec<&'a LockedPackage>, pred: impl Fn(&LockedPackage) -> bool) -> Option<&'a LockedPackage> {
+    let index = packages.iter().position(|p| pred(p))?;
+    Some(packages.remove(index))
+}
+
+/// Locked version from an installed-version query; the query fails when the package is missing
+fn system_version(manager: &SystemPackageManager, name: &str, query: Result<util::CommandResult>) -> String {
+    match query {
+        Ok(output) => manager.parse_installed_version(name, &output.stdout),
+        Err(_) => None,
+    }
+    .unwrap_or_else(|| "not-installed".to_string())
+}
+
+/// Parse Cargo.lock `[[package]]` entries
+fn parse_cargo_lock(content: &str) -> Result<Vec<LockedPackage>> {
+    #[derive(Deserialize)]
+    struct CargoLock {
+        #[serde(default)]
+        package: Vec<CargoLockPackage>,
+    }
+
+    #[derive(Deserialize)]
+    struct CargoLockPackage {
+        name: String,
+        version: String,
+        source: Option<String>,
+        checksum: Option<String>,
+    }
+
+    let lock: CargoLock = toml::from_str(content)
+        .context("Failed to parse Cargo.lock")?;
+
+    Ok(lock.package.into_iter()
+        .map(|pkg| LockedPackage {
+            name: pkg.name,
+            version: pkg.version,
+            manager: "cargo".to_string(),
+            source: pkg.source,
+            checksum: pkg.checksum,
+            dev: false,
+        })
+        .collect())
+}
+
+/// Parse package-lock.json (lockfileVersion 1, 2 and 3)
+fn parse_package_lock(content: &str) -> Result<Vec<LockedPackage>> {
+    let json: serde_json::Value = serde_json::from_str(content)
+        .context("Failed to parse package-lock.json")?;
+
+    let mut packages = Vec::new();
+
+    if let Some(entries) = json.get("packages").and_then(|p| p.as_object()) {
+        for (path, entry) in entries {
+            // The root project is stored under the empty key
+            let Some(name) = path.rsplit("node_modules/").next().filter(|_| !path.is_empty()) else {
+                continue;
+            };
+            if entry.get("link").and_then(|l| l.as_bool()).unwrap_or(false) {
+                continue;
+            }
+            packages.push(npm_entry(name, entry));
+        }
+    } else if let Some(deps) = json.get("dependencies").and_then(|d| d.as_object()) {
+        for (name, entry) in deps {
+            packages.push(npm_entry(name, entry));
+        }
+    }
+
+    // Nested copies of the same version collapse into one entry
+    packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+    packages.dedup_by(|a, b| a.name == b.name && a.version == b.version);
+
+    Ok(packages)
+}
+
+fn npm_entry(name: &str, entry: &serde_json::Value) -> LockedPackage {
+    let field = |key: &str| entry.get(key).and_then(|v| v.as_str()).map(str::to_string);
+
+    LockedPackage {
+        name: name.to_string(),
+        version: field("version").unwrap_or_default(),
+        manager: "npm".to_string(),
+        source: field("resolved"),
+        checksum: field("integrity"),
+        dev: entry.get("dev").and_then(|d| d.as_bool()).unwrap_or(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock_with(packages: Vec<LockedPackage>) -> RcmLock {
+        let mut managers = BTreeMap::new();
+        managers.insert("cargo".to_string(), ManagerLock::default());
+        RcmLock {
+            version: LOCKFILE_VERSION,
+            generated_at: String::new(),
+            rcm_version: String::new(),
+            managers,
+            packages,
+        }
+    }
+
+    fn cargo_pkg(name: &str, version: &str) -> LockedPackage {
+        LockedPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            manager: "cargo".to_string(),
+            source: None,
+            checksum: None,
+            dev: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_cargo_lock() {
+        let content = r#"
+version = 3
+
+[[package]]
+name = "anyhow"
+version = "1.0.75"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "abc123"
+"#;
+        let packages = parse_cargo_lock(content).unwrap();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].name, "anyhow");
+        assert_eq!(packages[0].checksum.as_deref(), Some("abc123"));
+    }
+
+    #[test]
+    fn test_parse_package_lock_v3() {
+        let content = r#"{
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "app" },
+                "node_modules/lodash": {
+                    "version": "4.17.21",
+                    "resolved": "https://registry.npmjs.org/lodash/-/lodash-4.17.21.tgz",
+                    "integrity": "sha512-xyz"
+                },
+                "node_modules/@types/node": { "version": "20.1.0", "dev": true }
+            }
+        }"#;
+        let packages = parse_package_lock(content).unwrap();
+        assert_eq!(packages.len(), 2);
+        assert!(packages.iter().any(|p| p.name == "@types/node" && p.dev));
+    }
+
+    #[test]
+    fn test_diff_detects_drift() {
+        let locked = lock_with(vec![cargo_pkg("serde", "1.0.0"), cargo_pkg("log", "0.4.0")]);
+        let current = lock_with(vec![cargo_pkg("serde", "1.0.1"), cargo_pkg("regex", "1.0.0")]);
+
+        let drift = LockManager::diff(&locked, &current);
+        assert_eq!(drift.len(), 3);
+        assert!(LockManager::diff(&locked, &locked).is_empty());
+    }
+
+    #[test]
+    fn test_diff_compares_every_version_of_a_package() {
+        let locked = lock_with(vec![cargo_pkg("syn", "1.0.109"), cargo_pkg("syn", "2.0.38"), cargo_pkg("log", "0.4.0")]);
+        let current = lock_with(vec![cargo_pkg("syn", "1.0.109"), cargo_pkg("syn", "2.0.39"), cargo_pkg("log", "0.4.0")]);
+
+        let drift = LockManager::diff(&locked, &current);
+        assert_eq!(drift.len(), 1);
+        assert!(matches!(&drift[0], LockDrift::Changed { locked, current }
+            if locked.version == "2.0.38" && current.version == "2.0.39"));
+
+        let current = lock_with(vec![cargo_pkg("syn", "2.0.38"), cargo_pkg("log", "0.4.0")]);
+        let drift = LockManager::diff(&locked, &current);
+        assert_eq!(drift, vec![LockDrift::Removed(cargo_pkg("syn", "1.0.109"))]);
+    }
+
+    #[test]
+    fn test_system_version_not_installed() {
+        let missing = system_version(&SystemPackageManager::Apt, "libssl-dev", Err(anyhow!("exit code 1")));
+        assert_eq!(missing, "not-installed");
+
+        let installed = util::CommandResult {
+            success: true,
+            exit_code: 0,
+            stdout: "3.0.2-0ubuntu1.15".to_string(),
+            stderr: String::new(),
+            duration_ms: 0,
+        };
+        assert_eq!(system_version(&SystemPackageManager::Apt, "libssl-dev", Ok(installed)), "3.0.2-0ubuntu1.15");
+    }
+}
@@ -0,0 +1,378 @@
+//! Logging for RCM
+//!
+//! Diagnostics go through `tracing`. The console shows the level from
+//! `core.log_level` (or `--verbose`/`RUST_LOG`) and follows `core.color_output`;
+//! each run in an initialized workspace also writes a JSON-lines trace to
+//! `.rcm/logs/` (or `--log-file`) that `rcm logs show` reads back. Both outputs
+//! pass through the redaction layer. Spans are also exported over OTLP when a
+//! collector is configured (see [`crate::telemetry`]).
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use console::style;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tabled::{Table, Tabled};
+use tracing::Level;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt::{self, MakeWriter};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::EnvFilter;
+use crate::config::{ColorMode, LogLevel, TelemetryConfig};
+use crate::redact;
+use crate::telemetry;
+use crate::workspace::Workspace;
+
+/// Trace directory relative to the workspace root
+pub const LOGS_DIR: &str = ".rcm/logs";
+
+/// Traces kept in `.rcm/logs`; older ones are removed at startup
+const KEEP_TRACES: usize = 50;
+
+#[derive(Subcommand)]
+pub enum LogsCommands {
+    /// List recorded traces, newest first
+    List,
+
+    /// Show a trace (the most recent one by default)
+    Show {
+        /// Trace file, or a name from `rcm logs list`
+        file: Option<String>,
+        /// Minimum level (error, warn, info, debug, trace)
+        #[arg(long, default_value = "debug")]
+        level: String,
+        /// Only entries whose target contains this (e.g. npm, ensure)
+        #[arg(long)]
+        target: Option<String>,
+        /// Only the last N matching entries
+        #[arg(long)]
+        last: Option<usize>,
+        /// Print the raw JSON lines
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Logging settings resolved from the CLI and configuration
+pub struct LogOptions<'a> {
+    pub level: &'a LogLevel,
+    pub color: &'a ColorMode,
+    pub verbose: bool,
+    pub log_file: Option<PathBuf>,
+    /// Workspace to keep a trace in; `None` disables the default trace
+    pub workspace_root: Option<&'a Path>,
+    pub command: &'a str,
+    pub telemetry: &'a TelemetryConfig,
+}
+
+fn level_name(level: &LogLevel) -> &'static str {
+    match level {
+        LogLevel::Error => "error",
+        LogLevel::Warn => "warn",
+        LogLevel::Info => "info",
+        LogLevel::Debug => "debug",
+        LogLevel::Trace => "trace",
+    }
+}
+
+/// Severity order for filtering (`ERROR` is 0)
+pub fn level_rank(level: &str) -> Option<u8> {
+    match level.to_ascii_lowercase().as_str() {
+        "error" => Some(0),
+        "warn" => Some(1),
+        "info" => Some(2),
+        "debug" => Some(3),
+        "trace" => Some(4),
+        _ => None,
+    }
+}
+
+/// Writer that masks secrets before anything reaches the terminal or a trace
+struct Redacting<M>(M);
+
+struct RedactingWriter<W>(W);
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write_all(redact::redact(&String::from_utf8_lossy(buf)).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Redacting<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter(self.0.make_writer())
+    }
+}
+
+/// Apply `core.color_output` to console styling; returns whether stderr gets ANSI colors
+fn apply_color(mode: &ColorMode) -> bool {
+    match mode {
+        ColorMode::Always => {
+            console::set_colors_enabled(true);
+            console::set_colors_enabled_stderr(true);
+        }
+        ColorMode::Never => {
+            console::set_colors_enabled(false);
+            console::set_colors_enabled_stderr(false);
+        }
+        ColorMode::Auto => {}
+    }
+    console::colors_enabled_stderr()
+}
+
+/// Name for a new trace: `<timestamp>-<command>.jsonl`, sortable by time
+fn trace_name(command: &str) -> String {
+    format!("{}-{}.jsonl", chrono::Local::now().format("%Y%m%d-%H%M%S"), command)
+}
+
+/// Remove all but the newest `keep` traces
+fn prune_traces(dir: &Path, keep: usize) {
+    let mut traces = list_traces(dir);
+    if traces.len() > keep {
+        for old in traces.drain(keep..) {
+            std::fs::remove_file(old).ok();
+        }
+    }
+}
+
+/// Traces in a directory, newest first
+pub fn list_traces(dir: &Path) -> Vec<PathBuf> {
+    let mut traces: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+        .unwrap_or_default();
+    traces.retain(|p| p.extension().is_some_and(|ext| ext == "jsonl"));
+    traces.sort();
+    traces.reverse();
+    traces
+}
+
+fn open_trace(path: &Path) -> Result<std::fs::File> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open log file {}", path.display()))
+}
+
+/// Install the global subscriber; returns the trace file being written, if any
+pub fn init(options: LogOptions) -> Result<Option<PathBuf>> {
+    let level = if options.verbose { "debug" } else { level_name(options.level) };
+    let console_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
+    let console = fmt::layer()
+        .with_writer(Redacting(io::stderr))
+        .with_ansi(apply_color(options.color))
+        .with_target(options.verbose)
+        .with_filter(console_filter);
+
+    // An explicit --log-file always wins; the default trace only lives in initialized workspaces
+    let trace_path = match (options.log_file, options.workspace_root) {
+        (Some(path), _) => Some(path),
+        (None, Some(root)) if root.join(".rcm").is_dir() => {
+            let dir = root.join(LOGS_DIR);
+            prune_traces(&dir, KEEP_TRACES - 1);
+            Some(dir.join(trace_name(options.command)))
+        }
+        _ => None,
+    };
+    let trace = match &trace_path {
+        Some(path) => {
+            let file = open_trace(path)?;
+            let targets = Targets::new()
+                .with_target(env!("CARGO_CRATE_NAME"), Level::DEBUG)
+                .with_default(Level::INFO);
+            Some(fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_writer(Redacting(Mutex::new(file)))
+                .with_filter(targets))
+        }
+        None => None,
+    };
+
+    let otlp = telemetry::layer(options.telemetry)?;
+
+    tracing_subscriber::registry()
+        .with(console)
+        .with(trace)
+        .with(otlp)
+        .try_init()
+        .map_err(|e| anyhow!("Failed to initialize logging: {}", e))?;
+    Ok(trace_path)
+}
+
+/// Console-only logging at a fixed level (FFI entry points)
+pub fn init_stderr(level: &str) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
+    let _ = tracing_subscriber::registry()
+        .with(fmt::layer().with_writer(Redacting(io::stderr)).with_filter(filter))
+        .try_init();
+}
+
+/// One line of a JSON trace
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    pub fields: BTreeMap<String, String>,
+}
+
+/// Parse a line written by the JSON trace layer
+pub fn parse_entry(line: &str) -> Option<LogEntry> {
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    let text = |v: &serde_json::Value| match v {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let mut fields: BTreeMap<String, String> = BTreeMap::new();
+    // Span fields (e.g. the command) first, so event fields win on conflicts
+    if let Some(span) = value.get("span").and_then(|s| s.as_object()) {
+        for (key, v) in span.iter().filter(|(k, _)| k.as_str() != "name") {
+            fields.insert(key.clone(), text(v));
+        }
+    }
+    let event = value.get("fields")?.as_object()?;
+    for (key, v) in event.iter().filter(|(k, _)| k.as_str() != "message") {
+        fields.insert(key.clone(), text(v));
+    }
+    Some(LogEntry {
+        timestamp: value.get("timestamp").map(text).unwrap_or_default(),
+        level: value.get("level").map(text).unwrap_or_default(),
+        target: value.get("target").map(text).unwrap_or_default(),
+        message: event.get("message").map(text).unwrap_or_default(),
+        fields,
+    })
+}
+
+/// Entries at or above `min_level` whose target contains `target`
+pub fn filter_entries<'a>(entries: &'a [LogEntry], min_level: u8, target: Option<&str>) -> Vec<&'a LogEntry> {
+    entries.iter()
+        .filter(|e| level_rank(&e.level).is_some_and(|rank| rank <= min_level))
+        .filter(|e| target.is_none_or(|t| e.target.contains(t)))
+        .collect()
+}
+
+fn print_entry(entry: &LogEntry) {
+    // 2026-01-02T10:11:12.345678Z -> 10:11:12.345
+    let time = entry.timestamp.split('T').nth(1).map(|t| t.get(..12).unwrap_or(t)).unwrap_or(&entry.timestamp);
+    let level = match entry.level.as_str() {
+        "ERROR" => style(format!("{:<5}", entry.level)).red(),
+        "WARN" => style(format!("{:<5}", entry.level)).yellow(),
+        "INFO" => style(format!("{:<5}", entry.level)).green(),
+        _ => style(format!("{:<5}", entry.level)).dim(),
+    };
+    let target = entry.target.rsplit("::").next().unwrap_or(&entry.target);
+    let fields: Vec<String> = entry.fields.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    println!("{} {} {} {} {}", style(time).dim(), level, style(target).cyan(), entry.message, style(fields.join(" ")).dim());
+}
+
+#[derive(Tabled)]
+struct TraceRow {
+    #[tabled(rename = "Trace")]
+    name: String,
+    #[tabled(rename = "Entries")]
+    entries: usize,
+    #[tabled(rename = "Warnings")]
+    warnings: usize,
+    #[tabled(rename = "Errors")]
+    errors: usize,
+}
+
+fn read_entries(path: &Path) -> Result<Vec<LogEntry>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(content.lines().filter_map(parse_entry).collect())
+}
+
+/// Handle `rcm logs` commands
+pub async fn handle_command(workspace: &Workspace, cmd: LogsCommands) -> Result<()> {
+    let dir = workspace.root().join(LOGS_DIR);
+    match cmd {
+        LogsCommands::List => {
+            let traces = list_traces(&dir);
+            if traces.is_empty() {
+                println!("No traces in {}", LOGS_DIR);
+                return Ok(());
+            }
+            let mut rows = Vec::new();
+            for path in &traces {
+                let entries = read_entries(path)?;
+                rows.push(TraceRow {
+                    name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+                    entries: entries.len(),
+                    warnings: entries.iter().filter(|e| e.level == "WARN").count(),
+                    errors: entries.iter().filter(|e| e.level == "ERROR").count(),
+                });
+            }
+            println!("{}", Table::new(rows));
+            Ok(())
+        }
+
+        LogsCommands::Show { file, level, target, last, json } => {
+            let min_level = level_rank(&level)
+                .ok_or_else(|| anyhow!("Unknown level: {}. Use error, warn, info, debug or trace", level))?;
+            let path = match file {
+                Some(file) if Path::new(&file).exists() => PathBuf::from(file),
+                Some(name) => dir.join(name),
+                None => list_traces(&dir).into_iter().next()
+                    .ok_or_else(|| anyhow!("No traces in {}", LOGS_DIR))?,
+            };
+            let entries = read_entries(&path)?;
+            let mut matching = filter_entries(&entries, min_level, target.as_deref());
+            if let Some(last) = last {
+                let skip = matching.len().saturating_sub(last);
+                matching.drain(..skip);
+            }
+            if json {
+                for entry in matching {
+                    println!("{}", serde_json::to_string(entry)?);
+                }
+                return Ok(());
+            }
+            println!("{}", style(format!("📜 {}", path.display())).bold());
+            for entry in matching {
+                print_entry(entry);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_filter_entries() {
+        let lines = [
+            r#"{"timestamp":"2026-10-16T10:11:12.345678Z","level":"INFO","fields":{"message":"checking environment","manager":"npm"},"target":"rcm::commands::ensure","span":{"command":"ensure","name":"rcm"}}"#,
+            r#"{"timestamp":"2026-10-16T10:11:13.000000Z","level":"WARN","fields":{"message":"lockfile out of date","missing":2},"target":"rcm::npm"}"#,
+            r#"{"timestamp":"2026-10-16T10:11:14.000000Z","level":"DEBUG","fields":{"message":"probe"},"target":"rcm::services"}"#,
+            "not json",
+        ];
+        let entries: Vec<LogEntry> = lines.iter().filter_map(|l| parse_entry(l)).collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].message, "checking environment");
+        assert_eq!(entries[0].fields.get("command").map(String::as_str), Some("ensure"));
+        assert_eq!(entries[0].fields.get("manager").map(String::as_str), Some("npm"));
+        assert_eq!(entries[1].fields.get("missing").map(String::as_str), Some("2"));
+
+        assert_eq!(filter_entries(&entries, level_rank("info").unwrap(), None).len(), 2);
+        assert_eq!(filter_entries(&entries, level_rank("trace").unwrap(), Some("npm")).len(), 1);
+        assert!(level_rank("loud").is_none());
+    }
+}
@@ -0,0 +1,543 @@
+//! Machine bootstrap (`rcm bootstrap <manifest>`)
+//!
+//! Provisions a fresh dev machine from a TOML or JSON manifest, local or
+//! fetched over HTTPS: an optional config bundle, system packages, language
+//! toolchains (rustup, fnm, uv), global tools and models. Every step that
+//! finishes is recorded under the RCM data directory, so re-running the same
+//! command after an interruption resumes at the first unfinished step.
+//!
+//! The manifest, the config bundle and the installer scripts drive `sudo` and
+//! `sh`, so each must come over https or carry a `#sha256=<hex>` pin, which is
+//! checked whatever the transport (`http://box/dev.toml#sha256=9f2c...`).
+//!
+//! ```toml
+//! config_bundle = "https://example.com/dev.tar.gz"
+//! system = ["git", "ffmpeg", "build-essential"]
+//!
+//! [toolchains]
+//! rust = "1.79"
+//! node = "20"
+//!
+//! [tools]
+//! cargo = ["ripgrep", "cargo-watch@8.5.2"]
+//! npm = ["typescript"]
+//!
+//! [[models]]
+//! name = "llama3"
+//! ```
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tokio::fs;
+use crate::artifacts::{self, Expectations};
+use crate::config::Config;
+use crate::system::SystemManager;
+use crate::{config_bundle, util};
+
+/// Directories installers put binaries in before the shell profile adds them to `PATH`
+const USER_BIN_DIRS: [&str; 4] = [".cargo/bin", ".local/bin", ".local/share/fnm", "go/bin"];
+
+/// Managers `[tools]` may name
+pub const TOOL_MANAGERS: [&str; 5] = ["cargo", "npm", "pip", "go", "gem"];
+
+/// What to provision
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BootstrapManifest {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Bundle from `rcm config export --bundle`, applied first
+    #[serde(default)]
+    pub config_bundle: Option<String>,
+    /// System packages (names go through the package mappings)
+    #[serde(default)]
+    pub system: Vec<String>,
+    /// Toolchain versions: `rust`, `node`, `python`
+    #[serde(default)]
+    pub toolchains: BTreeMap<String, String>,
+    /// Global tools per manager, as `name` or `name@version`
+    #[serde(default)]
+    pub tools: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub models: Vec<ModelSpec>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelSpec {
+    pub name: String,
+    /// `ollama` or `huggingface`
+    #[serde(default = "default_model_source")]
+    pub source: String,
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+fn default_model_source() -> String {
+    "ollama".to_string()
+}
+
+/// One resumable unit of work
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    Bundle(String),
+    System(Vec<String>),
+    Toolchain { tool: String, version: String },
+    Tool { manager: String, spec: String },
+    Model(ModelSpec),
+}
+
+impl Step {
+    /// Stable identifier; includes versions so changing one re-runs the step
+    pub fn id(&self) -> String {
+        match self {
+            Step::Bundle(source) => format!("bundle:{}", source),
+            Step::System(packages) => {
+                let mut packages = packages.clone();
+                packages.sort();
+                format!("system:{}", packages.join(","))
+            }
+            Step::Toolchain { tool, version } => format!("toolchain:{}@{}", tool, version),
+            Step::Tool { manager, spec } => format!("tool:{}:{}", manager, spec),
+            Step::Model(model) => format!("model:{}:{}@{}", model.source, model.name, model.version.as_deref().unwrap_or("latest")),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Step::Bundle(source) => format!("Apply config bundle {}", source),
+            Step::System(packages) => format!("Install system packages: {}", packages.join(", ")),
+            Step::Toolchain { tool, version } => format!("Install {} {}", tool, version),
+            Step::Tool { manager, spec } => format!("Install {} with {}", spec, manager),
+            Step::Model(model) => format!("Pull model {} from {}", model.name, model.source),
+        }
+    }
+}
+
+/// How a toolchain is installed
+struct Installer {
+    tool: &'static str,
+    binary: &'static str,
+    /// Shell installer fetched when `binary` is missing
+    script: &'static str,
+    script_args: &'static [&'static str],
+    commands: fn(&str) -> Vec<Vec<String>>,
+}
+
+static INSTALLERS: [Installer; 3] = [
+    Installer {
+        tool: "rust",
+        binary: "rustup",
+        script: "https://sh.rustup.rs",
+        script_args: &["-y", "--no-modify-path", "--default-toolchain", "none"],
+        commands: |v| vec![args(&["toolchain", "install", v]), args(&["default", v])],
+    },
+    Installer {
+        tool: "node",
+        binary: "fnm",
+        script: "https://fnm.vercel.app/install",
+        script_args: &["--skip-shell"],
+        commands: |v| vec![args(&["install", v]), args(&["default", v])],
+    },
+    Installer {
+        tool: "python",
+        binary: "uv",
+        script: "https://astral.sh/uv/install.sh",
+        script_args: &["--no-modify-path"],
+        commands: |v| vec![args(&["python", "install", v])],
+    },
+];
+
+fn args(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|p| p.to_string()).collect()
+}
+
+fn installer(tool: &str) -> Option<&'static Installer> {
+    INSTALLERS.iter().find(|i| i.tool == tool)
+}
+
+/// Order the manifest into steps, rejecting toolchains and managers RCM cannot install
+pub fn plan(manifest: &BootstrapManifest) -> Result<Vec<Step>> {
+    let mut steps = Vec::new();
+    if let Some(bundle) = &manifest.config_bundle {
+        steps.push(Step::Bundle(bundle.clone()));
+    }
+    if !manifest.system.is_empty() {
+        steps.push(Step::System(manifest.system.clone()));
+    }
+    for (tool, version) in &manifest.toolchains {
+        if installer(tool).is_none() {
+            let known: Vec<&str> = INSTALLERS.iter().map(|i| i.tool).collect();
+            return Err(anyhow!("Unsupported toolchain '{}'. Supported: {}", tool, known.join(", ")));
+        }
+        steps.push(Step::Toolchain { tool: tool.clone(), version: version.clone() });
+    }
+    for (manager, specs) in &manifest.tools {
+        if !TOOL_MANAGERS.contains(&manager.as_str()) {
+            return Err(anyhow!("Unsupported tool manager '{}'. Supported: {}", manager, TOOL_MANAGERS.join(", ")));
+        }
+        steps.extend(specs.iter().map(|spec| Step::Tool { manager: manager.clone(), spec: spec.clone() }));
+    }
+    for model in &manifest.models {
+        if model.source != "ollama" && model.source != "huggingface" {
+            return Err(anyhow!("Unsupported model source '{}' for {}. Use ollama or huggingface", model.source, model.name));
+        }
+        steps.push(Step::Model(model.clone()));
+    }
+    Ok(steps)
+}
+
+/// Split `name@version`; a leading `@` belongs to an npm scope
+pub fn split_spec(spec: &str) -> (&str, Option<&str>) {
+    match spec.rfind('@') {
+        Some(at) if at > 0 => (&spec[..at], Some(&spec[at + 1..])),
+        _ => (spec, None),
+    }
+}
+
+/// Program and arguments installing a global tool, given which programs exist
+pub fn tool_command(manager: &str, spec: &str, available: impl Fn(&str) -> bool) -> Result<(String, Vec<String>)> {
+    let (name, version) = split_spec(spec);
+    let command = match manager {
+        "cargo" => {
+            let mut a = args(&["install", "--locked", name]);
+            if let Some(version) = version {
+                a.extend(args(&["--version", version]));
+            }
+            ("cargo", a)
+        }
+        "npm" => {
+            // Node from fnm is not on PATH until the shell profile loads it
+            if available("npm") || !available("fnm") {
+                ("npm", args(&["install", "-g", spec]))
+            } else {
+                ("fnm", args(&["exec", "--using=default", "npm", "install", "-g", spec]))
+            }
+        }
+        "pip" => {
+            let requirement = match version {
+                Some(version) => format!("{}=={}", name, version),
+                None => name.to_string(),
+            };
+            if available("uv") {
+                ("uv", args(&["tool", "install", requirement.as_str()]))
+            } else if available("pipx") {
+                ("pipx", args(&["install", requirement.as_str()]))
+            } else {
+                ("pip3", args(&["install", "--user", requirement.as_str()]))
+            }
+        }
+        "go" => ("go", args(&["install", format!("{}@{}", name, version.unwrap_or("latest")).as_str()])),
+        "gem" => {
+            let mut a = args(&["install", name]);
+            if let Some(version) = version {
+                a.extend(args(&["-v", version]));
+            }
+            ("gem", a)
+        }
+        other => return Err(anyhow!("Unsupported tool manager '{}'", other)),
+    };
+    Ok((command.0.to_string(), command.1))
+}
+
+/// A program on `PATH`, or in a user bin directory an installer just populated
+fn locate(program: &str) -> Option<PathBuf> {
+    if let Ok(path) = which::which(program) {
+        return Some(path);
+    }
+    let home = dirs::home_dir()?;
+    let file = if cfg!(windows) { format!("{}.exe", program) } else { program.to_string() };
+    USER_BIN_DIRS.iter().map(|dir| home.join(dir).join(&file)).find(|path| path.is_file())
+}
+
+fn command_for(program: &str) -> Command {
+    Command::new(locate(program).unwrap_or_else(|| PathBuf::from(program)))
+}
+
+/// Completed steps of one manifest, kept across runs
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BootstrapState {
+    pub source: String,
+    #[serde(default)]
+    pub completed: BTreeMap<String, String>,
+}
+
+impl BootstrapState {
+    fn path(config: &Config, source: &str) -> PathBuf {
+        let digest = format!("{:x}", Sha256::digest(source.as_bytes()));
+        config.data_dir().join("bootstrap").join(format!("{}.json", &digest[..16]))
+    }
+
+    async fn load(path: &Path, source: &str) -> Self {
+        fs::read_to_string(path).await.ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_else(|| Self { source: source.to_string(), completed: BTreeMap::new() })
+    }
+
+    async fn save(&self, path: &Path) -> Result<()> {
+        if util::is_dry_run() {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?).await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+pub fn is_url(source: &str) -> bool {
+    source.starts_with("https://") || source.starts_with("http://")
+}
+
+/// Split a `#sha256=<hex>` pin off a path or URL
+pub fn split_pin(source: &str) -> (&str, Option<String>) {
+    match source.rsplit_once("#sha256=") {
+        Some((location, digest)) if !digest.is_empty() => (location, Some(digest.to_ascii_lowercase())),
+        _ => (source, None),
+    }
+}
+
+/// Refuse plain-http sources that aren't pinned to a digest
+fn check_transport(location: &str, pin: Option<&str>) -> Result<()> {
+    if location.starts_with("http://") && pin.is_none() {
+        return Err(anyhow!("Refusing {} over plain http; use https or append #sha256=<digest>", location));
+    }
+    Ok(())
+}
+
+fn check_digest(data: &[u8], pin: Option<&str>, location: &str) -> Result<()> {
+    match pin {
+        Some(expected) => {
+            let actual = format!("{:x}", Sha256::digest(data));
+            if actual != expected {
+                return Err(anyhow!("Checksum mismatch for {}: expected {}, got {}", location, expected, actual));
+            }
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Fetch and parse a manifest; TOML unless the source ends in `.json`
+pub async fn load_manifest(source: &str) -> Result<BootstrapManifest> {
+    let (location, pin) = split_pin(source);
+    let content = if is_url(location) {
+        check_transport(location, pin.as_deref())?;
+        let expect = Expectations { sha256: pin.clone(), ..Default::default() };
+        let data = artifacts::download_bytes(location, &expect).await
+            .with_context(|| format!("Failed to fetch {}", location))?;
+        String::from_utf8(data).with_context(|| format!("{} is not UTF-8", location))?
+    } else {
+        fs::read_to_string(location).await
+            .with_context(|| format!("Failed to read {}", location))?
+    };
+    check_digest(content.as_bytes(), pin.as_deref(), location)?;
+    if location.ends_with(".json") {
+        serde_json::from_str(&content).with_context(|| format!("Invalid bootstrap manifest {}", location))
+    } else {
+        toml::from_str(&content).with_context(|| format!("Invalid bootstrap manifest {}", location))
+    }
+}
+
+/// A local copy of a file given as a path or URL, checked against its pin
+async fn fetch(source: &str, name: &str) -> Result<PathBuf> {
+    let (location, pin) = split_pin(source);
+    let path = if is_url(location) {
+        check_transport(location, pin.as_deref())?;
+        let path = util::create_temp_dir("rcm-bootstrap").await?.join(name);
+        let expect = Expectations { sha256: pin.clone(), ..Default::default() };
+        artifacts::download(location, &path, &expect).await
+            .with_context(|| format!("Failed to download {}", location))?;
+        path
+    } else {
+        PathBuf::from(location)
+    };
+    // Checked here too, since `security.allow_insecure` lets the verifier pass a mismatch
+    if pin.is_some() {
+        let data = fs::read(&path).await.with_context(|| format!("Failed to read {}", path.display()))?;
+        check_digest(&data, pin.as_deref(), location)?;
+    }
+    Ok(path)
+}
+
+async fn install_toolchain(tool: &str, version: &str) -> Result<()> {
+    let installer = installer(tool).ok_or_else(|| anyhow!("Unsupported toolchain '{}'", tool))?;
+    if locate(installer.binary).is_none() {
+        if cfg!(windows) {
+            return Err(anyhow!("{} is not installed; install it from {} and re-run", installer.binary, installer.script));
+        }
+        if util::is_dry_run() {
+            println!("[dry-run] curl -fsSL {} | sh -s -- {}", installer.script, installer.script_args.join(" "));
+        } else {
+            let script = fetch(installer.script, &format!("{}-install.sh", installer.binary)).await?;
+            let mut cmd = Command::new("sh");
+            cmd.arg(&script).args(installer.script_args);
+            util::execute_mutation(&mut cmd).await
+                .with_context(|| format!("Failed to install {}", installer.binary))?;
+        }
+    }
+    for command in (installer.commands)(version) {
+        let mut cmd = command_for(installer.binary);
+        cmd.args(&command);
+        util::execute_mutation(&mut cmd).await
+            .with_context(|| format!("Failed to install {} {}", tool, version))?;
+    }
+    Ok(())
+}
+
+async fn install_model(model: &ModelSpec) -> Result<()> {
+    let mut cmd = match model.source.as_str() {
+        "ollama" => {
+            let mut cmd = command_for("ollama");
+            let tag = match &model.version {
+                Some(version) => format!("{}:{}", model.name, version),
+                None => model.name.clone(),
+            };
+            cmd.args(["pull", tag.as_str()]);
+            cmd
+        }
+        _ => {
+            let mut cmd = command_for("huggingface-cli");
+            cmd.args(["download", model.name.as_str()]);
+            if let Some(version) = &model.version {
+                cmd.args(["--revision", version.as_str()]);
+            }
+            cmd
+        }
+    };
+    util::execute_mutation(&mut cmd).await
+        .with_context(|| format!("Failed to pull model {}", model.name))?;
+    Ok(())
+}
+
+async fn run_step(step: &Step, root: &Path, config_path: Option<&str>) -> Result<()> {
+    match step {
+        Step::Bundle(source) => {
+            let bundle = fetch(source, config_bundle::DEFAULT_BUNDLE).await?;
+            config_bundle::import(root, config_path, &bundle).await
+        }
+        Step::System(packages) => SystemManager::new(root).await?.install(packages, false, true).await,
+        Step::Toolchain { tool, version } => install_toolchain(tool, version).await,
+        Step::Tool { manager, spec } => {
+            let (program, tool_args) = tool_command(manager, spec, |p| locate(p).is_some())?;
+            let mut cmd = command_for(&program);
+            cmd.args(&tool_args);
+            util::execute_mutation(&mut cmd).await
+                .with_context(|| format!("Failed to install {} with {}", spec, manager))?;
+            Ok(())
+        }
+        Step::Model(model) => install_model(model).await,
+    }
+}
+
+/// Provision this machine from a manifest, skipping steps finished by earlier runs
+pub async fn run(root: &Path, config: &Config, config_path: Option<&str>, source: &str, restart: bool, keep_going: bool) -> Result<()> {
+    let manifest = load_manifest(source).await?;
+    let steps = plan(&manifest)?;
+    let state_path = BootstrapState::path(config, source);
+    let mut state = if restart {
+        BootstrapState { source: source.to_string(), completed: BTreeMap::new() }
+    } else {
+        BootstrapState::load(&state_path, source).await
+    };
+
+    let title = manifest.name.as_deref().unwrap_or(source);
+    let done = steps.iter().filter(|s| state.completed.contains_key(&s.id())).count();
+    println!("{}", style(format!("🧰 Bootstrapping {} ({} steps, {} already done)", title, steps.len(), done)).bold());
+
+    let mut failed = Vec::new();
+    for (index, step) in steps.iter().enumerate() {
+        let id = step.id();
+        let counter = format!("[{}/{}]", index + 1, steps.len());
+        if let Some(at) = state.completed.get(&id) {
+            println!("{} {} {}", style(counter).dim(), step.describe(), style(format!("(done {})", at)).dim());
+            continue;
+        }
+        println!("{} {}", style(counter).cyan(), step.describe());
+        match run_step(step, root, config_path).await {
+            Ok(()) => {
+                if !util::is_dry_run() {
+                    state.completed.insert(id, chrono::Utc::now().to_rfc3339());
+                    state.save(&state_path).await?;
+                }
+            }
+            Err(e) if keep_going => {
+                println!("{}", style(format!("   ❌ {:#}", e)).red());
+                failed.push(step.describe());
+            }
+            Err(e) => {
+                state.save(&state_path).await?;
+                return Err(e.context(format!("Bootstrap stopped at step {}; re-run `rcm bootstrap {}` to resume", index + 1, source)));
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        return Err(anyhow!("{} bootstrap step(s) failed: {}. Re-run to retry them", failed.len(), failed.join("; ")));
+    }
+    println!("{}", style(format!("✅ {} is provisioned", title)).green());
+    if manifest.toolchains.contains_key("node") || manifest.toolchains.contains_key("rust") {
+        println!("💡 Open a new shell (or add ~/.cargo/bin and `fnm env` to your profile) to use the new toolchains");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_from_toml() {
+        let manifest: BootstrapManifest = toml::from_str(r#"
+            system = ["git", "curl"]
+            [toolchains]
+            rust = "1.79"
+            [tools]
+            cargo = ["ripgrep"]
+            [[models]]
+            name = "llama3"
+        "#).unwrap();
+        let ids: Vec<String> = plan(&manifest).unwrap().iter().map(Step::id).collect();
+        assert_eq!(ids, vec![
+            "system:curl,git",
+            "toolchain:rust@1.79",
+            "tool:cargo:ripgrep",
+            "model:ollama:llama3@latest",
+        ]);
+
+        let unsupported: BootstrapManifest = toml::from_str("[toolchains]\nzig = \"0.13\"").unwrap();
+        assert!(plan(&unsupported).is_err());
+    }
+
+    #[test]
+    fn test_tool_commands() {
+        assert_eq!(split_spec("@types/node@20.1.0"), ("@types/node", Some("20.1.0")));
+        assert_eq!(split_spec("@angular/cli"), ("@angular/cli", None));
+
+        let none = |_: &str| false;
+        assert_eq!(tool_command("cargo", "cargo-watch@8.5.2", none).unwrap().1,
+            vec!["install", "--locked", "cargo-watch", "--version", "8.5.2"]);
+        assert_eq!(tool_command("go", "golang.org/x/tools/gopls", none).unwrap().1,
+            vec!["install", "golang.org/x/tools/gopls@latest"]);
+        assert_eq!(tool_command("pip", "black@24.4.2", |p| p == "uv").unwrap(),
+            ("uv".to_string(), vec!["tool".to_string(), "install".to_string(), "black==24.4.2".to_string()]));
+        assert_eq!(tool_command("npm", "typescript", |p| p == "fnm").unwrap().0, "fnm");
+        assert!(tool_command("brew", "jq", none).is_err());
+    }
+
+    #[test]
+    fn test_pins() {
+        assert_eq!(split_pin("http://box/dev.toml#sha256=ABC"), ("http://box/dev.toml", Some("abc".to_string())));
+        assert_eq!(split_pin("https://example.com/dev.toml"), ("https://example.com/dev.toml", None));
+        assert!(check_transport("http://box/dev.toml", None).is_err());
+        assert!(check_transport("http://box/dev.toml", Some("abc")).is_ok());
+        assert!(check_transport("https://example.com/dev.toml", None).is_ok());
+        let empty = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        assert!(check_digest(b"", Some(empty), "x").is_ok());
+        assert!(check_digest(b"x", Some(empty), "x").is_err());
+    }
+}
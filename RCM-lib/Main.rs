@@ -7,19 +7,68 @@ mod lockfile;
 mod sbom;
 mod provenance;
 mod registry;
+mod mirrors;
 mod util;
 mod commands;
 mod npm;
 mod ppm;
+mod pip;
+mod go;
+mod gem;
+mod container;
+mod container_build;
+mod remote;
+mod fleet;
 mod system;
+mod system_repos;
 mod config;
+mod config_layers;
+mod config_bundle;
 mod workspace;
+mod members;
+mod stack;
+mod tasks;
+mod planner;
+mod platform;
+mod secrets;
+mod auth;
+mod redact;
+mod audit;
+mod audit_exceptions;
+mod audit_watch;
+mod toolchain;
+mod tool_versions;
+mod engines;
+mod transaction;
+mod cache;
+mod download_cache;
+mod artifacts;
+mod version_picker;
+mod gc;
+mod hooks;
+mod isolation;
+mod bootstrap;
+mod templates;
+mod storage;
+mod ensure_state;
+mod environments;
+mod env_snapshot;
+mod db;
+mod services;
+mod license;
+mod metrics;
+mod dashboard;
+mod webhooks;
+mod logging;
+mod model_client;
+mod telemetry;
 
 use std::ffi::{CStr, CString};
 use std::os::raw::{c_char, c_int};
+use std::path::PathBuf;
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use log::{debug, info, warn};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use tracing::{debug, error, info, warn, Instrument};
 
 #[derive(Parser)]
 #[command(name = "rcm", version, about = "RCM – Polyglot Package Manager")]
@@ -39,6 +88,30 @@ struct Cli {
     /// Configuration file path
     #[arg(short, long, global = true)]
     config: Option<String>,
+    
+    /// Print the commands and file changes that would be made without running them
+    #[arg(long, global = true)]
+    dry_run: bool,
+    
+    /// Guarantee no filesystem or system changes; only inspection commands run (also RCM_READ_ONLY=1)
+    #[arg(long, global = true)]
+    read_only: bool,
+    
+    /// Write a JSON-lines trace to this file instead of .rcm/logs/
+    #[arg(long, global = true)]
+    log_file: Option<PathBuf>,
+    
+    /// Apply a named profile from the config files (also RCM_PROFILE)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+    
+    /// Override a configuration value for this run (key=value, repeatable)
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+    set: Vec<String>,
+    
+    /// Run this command on another host over SSH (user@host[:port]); paths are remote
+    #[arg(long, global = true, value_name = "HOST")]
+    remote: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -48,9 +121,12 @@ enum Commands {
         /// Initialize with specific package managers
         #[arg(long, value_delimiter = ',')]
         managers: Option<Vec<String>>,
-        /// Template to use (rust, node, php, polyglot)
+        /// Template: rust, node, bun, frontend, php, python, go, ai, polyglot, a directory or a git URL (gh:org/repo[#ref])
         #[arg(long, default_value = "polyglot")]
         template: String,
+        /// Template variable (key=value), repeatable
+        #[arg(long = "var")]
+        vars: Vec<String>,
     },
     
     /// Add a package requirement with auto-detection of package manager
@@ -63,6 +139,9 @@ enum Commands {
         /// Development/optional dependency
         #[arg(long)]
         dev: bool,
+        /// Only on these platforms: OS, arch or os-arch, `!` to exclude (e.g. linux,macos)
+        #[arg(long)]
+        platform: Option<String>,
     },
     
     /// Remove a package
@@ -74,11 +153,50 @@ enum Commands {
         manager: Option<String>,
     },
     
+    /// Run a task from [tasks] in rcm.toml or .rcm/workspace.json; lists tasks without one
+    Run {
+        task: Option<String>,
+        /// Run the task's commands, and tasks that don't depend on each other, concurrently
+        #[arg(long)]
+        parallel: bool,
+        /// Environment profile to inject (defaults to RCM_ENV, then development)
+        #[arg(long)]
+        env: Option<String>,
+        /// Skip the task's dependencies
+        #[arg(long)]
+        no_deps: bool,
+        /// Extra arguments for the task's commands (after --)
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    
     /// Check environment, ensure lockfiles exist, validate metadata
     Ensure {
         /// Check only specific managers
         #[arg(long, value_delimiter = ',')]
         managers: Option<Vec<String>>,
+        /// Start services declared in .rcm/workspace.json that are not reachable
+        #[arg(long)]
+        start_services: bool,
+        /// Check every manager even if its manifests and lockfiles are unchanged
+        #[arg(long)]
+        force: bool,
+        /// Run inside an ephemeral container built from .rcm/container/Dockerfile
+        #[arg(long)]
+        in_container: bool,
+        /// Skip the member projects listed in .rcm/workspace.json
+        #[arg(long)]
+        no_members: bool,
+    },
+    
+    /// Diagnose PATH, toolchain conflicts, registry access, disk space and model runtimes
+    Doctor {
+        /// Skip registry and proxy reachability checks
+        #[arg(long)]
+        skip_network: bool,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
     },
     
     /// Show what would change (dry-run)
@@ -101,6 +219,168 @@ enum Commands {
         force: bool,
     },
     
+    /// Write or verify the unified rcm.lock
+    Lock {
+        /// Lock specific managers only
+        #[arg(long, value_delimiter = ',')]
+        managers: Option<Vec<String>>,
+        /// Fail if rcm.lock does not match the resolved state
+        #[arg(long)]
+        verify: bool,
+    },
+    
+    /// Regenerate lockfiles with git merge conflicts from the merged manifests
+    ResolveConflicts {
+        /// Only report conflicted lockfiles; fail if there are any
+        #[arg(long)]
+        check: bool,
+        /// Side of the merge to start from (ours, theirs)
+        #[arg(long, default_value = "ours")]
+        base: String,
+        /// Skip the frozen install check after resolving
+        #[arg(long)]
+        no_verify: bool,
+    },
+    
+    /// Scan dependencies for security advisories
+    Audit {
+        /// Audit specific managers only
+        #[arg(long, value_delimiter = ',')]
+        managers: Option<Vec<String>>,
+        /// Output format (table, json, sarif)
+        #[arg(long, default_value = "table")]
+        format: String,
+        /// Lowest severity that fails the audit (any, low, medium, high, critical, none)
+        #[arg(long, default_value = "any")]
+        fail_on: String,
+        #[command(subcommand)]
+        cmd: Option<audit::AuditCommands>,
+    },
+    
+    /// Show dependencies with newer releases on their channel
+    Outdated {
+        /// Check specific managers only
+        #[arg(long, value_delimiter = ',')]
+        managers: Option<Vec<String>>,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    
+    /// Generate an HTML report or a dependency freshness badge
+    Report {
+        #[command(subcommand)]
+        cmd: commands::report::ReportCommands,
+    },
+    
+    /// Query workspace state: 'deps where manager == "npm" and dev == true' or JMESPath
+    Query {
+        /// Query expression
+        query: String,
+        /// Treat the expression as JMESPath even if it starts with a source name
+        #[arg(long)]
+        jmespath: bool,
+        /// Output format (table, json, jsonl)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    
+    /// Update dependencies; with --advise, assess pending upgrades with a local GPT model
+    Update {
+        /// Update specific managers only
+        #[arg(long, value_delimiter = ',')]
+        managers: Option<Vec<String>>,
+        /// Summarize release notes and suggest an upgrade order instead of updating
+        #[arg(long)]
+        advise: bool,
+        /// Model to consult (defaults to the registry's default or only running model)
+        #[arg(long)]
+        model: Option<String>,
+        /// Output format for --advise (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    
+    /// Run the workspace's linters and formatters and report all problems together
+    Lint {
+        /// Run specific linters only (clippy, rustfmt, eslint, prettier, phpstan, php-cs-fixer, shellcheck)
+        #[arg(long, value_delimiter = ',')]
+        tools: Option<Vec<String>>,
+        /// Apply automatic fixes where the tool supports them
+        #[arg(long)]
+        fix: bool,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    
+    /// Run every test suite in the workspace in parallel and merge the results
+    Test {
+        /// Run specific suites only (cargo, jest, vitest, phpunit)
+        #[arg(long, value_delimiter = ',')]
+        suites: Option<Vec<String>>,
+        /// Where to write the merged JUnit report (defaults to .rcm/reports/junit.xml)
+        #[arg(long)]
+        junit: Option<PathBuf>,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+        /// Run inside an ephemeral container built from .rcm/container/Dockerfile
+        #[arg(long)]
+        in_container: bool,
+    },
+    
+    /// Collect coverage from every test suite and merge it into .rcm/coverage/
+    Coverage {
+        /// Collect from specific suites only (cargo, jest, vitest, phpunit)
+        #[arg(long, value_delimiter = ',')]
+        suites: Option<Vec<String>>,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    
+    /// Build Rust targets, cross-compiling with rustup, cargo-zigbuild or cross
+    Build {
+        /// Target triple to build (repeatable; defaults to the host)
+        #[arg(long)]
+        target: Vec<String>,
+        /// Build every target listed in .rcm/build.toml
+        #[arg(long, conflicts_with = "target")]
+        all_targets: bool,
+        /// Cargo profile
+        #[arg(long, default_value = "release")]
+        profile: String,
+        /// Force a strategy (native, zigbuild, cross)
+        #[arg(long)]
+        strategy: Option<String>,
+        /// Run inside an ephemeral container built from .rcm/container/Dockerfile
+        #[arg(long)]
+        in_container: bool,
+    },
+    
+    /// Build release artifacts into dist/<profile>/ with checksums and a manifest
+    Package {
+        /// Packaging profile from .rcm/package.toml (release and debug are built in)
+        #[arg(long, default_value = "release")]
+        profile: String,
+        /// Artifact kinds to build (cargo, npm, phar; all detected if omitted)
+        #[arg(long, value_delimiter = ',')]
+        kinds: Option<Vec<String>>,
+    },
+    
+    /// Verify SHA256SUMS-style checksum files in parallel (defaults to SHA256SUMS and dist/*/SHA256SUMS)
+    Verify {
+        /// Checksum files to verify; listed paths are relative to each file
+        checksum_files: Vec<PathBuf>,
+        /// Worker threads (defaults to the number of CPUs)
+        #[arg(long)]
+        jobs: Option<usize>,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    
     /// Create a workspace snapshot
     Snapshot { 
         #[arg(long)] 
@@ -120,7 +400,7 @@ enum Commands {
         /// SBOM format (cyclonedx, spdx, json)
         #[arg(long, default_value = "cyclonedx")]
         format: String,
-        /// Include specific managers only
+        /// Include specific managers only (cargo, npm, composer, system, gpt)
         #[arg(long, value_delimiter = ',')]
         managers: Option<Vec<String>>,
     },
@@ -148,6 +428,34 @@ enum Commands {
         cmd: ppm::PpmCommands,
     },
 
+    /// Python package commands (pip, uv, poetry)
+    #[cfg(feature = "pip")]
+    Pip {
+        #[command(subcommand)]
+        cmd: pip::PipCommands,
+    },
+
+    /// Go module commands
+    #[cfg(feature = "go")]
+    Go {
+        #[command(subcommand)]
+        cmd: go::GoCommands,
+    },
+
+    /// Ruby gem commands (Bundler)
+    #[cfg(feature = "gem")]
+    Gem {
+        #[command(subcommand)]
+        cmd: gem::GemCommands,
+    },
+
+    /// Container image dependencies (docker, podman)
+    #[cfg(feature = "container")]
+    Container {
+        #[command(subcommand)]
+        cmd: container::ContainerCommands,
+    },
+
     /// System package commands (apt, yum, brew, etc.)
     #[cfg(feature = "system")]
     System {
@@ -158,9 +466,12 @@ enum Commands {
     /// Imperative workflow commands (LET paradigm)
     #[cfg(feature = "let")]
     Let {
-        /// Target package/command (e.g., "ffmpeg", "cargo", "npm")
+        /// Target package/command (e.g., "ffmpeg", "cargo", "npm", "stack", "new", "generate", "validate")
         target: String,
         
+        /// Name for compound targets (e.g., `rcm let stack <name>`, `rcm let new <target>`)
+        name: Option<String>,
+        
         /// Deploy/install the target
         #[arg(long)]
         deploy: bool,
@@ -189,6 +500,10 @@ enum Commands {
         #[arg(long)]
         update: bool,
         
+        /// Show the dependency graph and execution order without running anything
+        #[arg(long)]
+        graph: bool,
+        
         /// Additional arguments as key=value pairs
         #[arg(long = "arg", value_name = "k=v", num_args=0.., action=clap::ArgAction::Append)]
         args: Vec<String>,
@@ -200,6 +515,96 @@ enum Commands {
         /// Parallel execution count
         #[arg(long, default_value = "1")]
         parallel: usize,
+        
+        /// What the spec should do, for `rcm let generate <target>` (prompted if omitted)
+        #[arg(long)]
+        goal: Option<String>,
+        
+        /// Served model to draft the spec with (registry default if omitted)
+        #[arg(long)]
+        model: Option<String>,
+    },
+
+    /// Manage secrets referenced as `secret:<name>` in LET and serving env
+    Secret {
+        #[command(subcommand)]
+        cmd: secrets::SecretCommands,
+    },
+
+    /// Log in to registries; tokens go to the secret backend, not config.json
+    Auth {
+        #[command(subcommand)]
+        cmd: auth::AuthCommands,
+    },
+
+    /// Run database migrations with the workspace's migration tools
+    Db {
+        #[command(subcommand)]
+        cmd: db::DbCommands,
+    },
+
+    /// Install and run the git hooks declared under [hooks]
+    Hooks {
+        #[command(subcommand)]
+        cmd: hooks::HookCommands,
+    },
+
+    /// Inspect or migrate the workspace state backend
+    State {
+        #[command(subcommand)]
+        cmd: storage::StateCommands,
+    },
+
+    /// List configured event webhooks or send them a test event
+    Webhook {
+        #[command(subcommand)]
+        cmd: webhooks::WebhookCommands,
+    },
+
+    /// Pin tool versions per workspace and capture or compare environment descriptors
+    Env {
+        #[command(subcommand)]
+        cmd: environments::EnvCommands,
+    },
+
+    /// Check dependency licenses against the configured policy
+    License {
+        #[command(subcommand)]
+        cmd: license::LicenseCommands,
+    },
+
+    /// Remove stale temp dirs, pidfiles, cache entries and orphaned registry entries
+    Gc {
+        /// Treat unowned temp dirs and snapshots untouched this many hours as stale
+        #[arg(long, default_value_t = gc::DEFAULT_STALE_HOURS)]
+        older_than: u64,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+
+    /// Provision this machine from a bootstrap manifest (path or URL); re-run to resume
+    Bootstrap {
+        /// TOML or JSON manifest listing packages, toolchains, tools and models
+        manifest: String,
+        /// Forget steps finished by earlier runs and start over
+        #[arg(long)]
+        restart: bool,
+        /// Continue past failed steps and report them at the end
+        #[arg(long)]
+        keep_going: bool,
+    },
+
+    /// Inspect and compact RCM's compressed cache
+    Cache {
+        #[command(subcommand)]
+        cmd: cache::CacheCommands,
+    },
+
+    /// Inspect rollback-protected add/ensure operations
+    Transaction {
+        #[command(subcommand)]
+        cmd: transaction::TransactionCommands,
     },
 
     /// Workspace management commands
@@ -208,11 +613,23 @@ enum Commands {
         cmd: WorkspaceCommands,
     },
 
+    /// Apply a plan to many hosts over SSH
+    Fleet {
+        #[command(subcommand)]
+        cmd: fleet::FleetCommands,
+    },
+
     /// Configuration management
     Config {
         #[command(subcommand)]
         cmd: ConfigCommands,
     },
+    
+    /// Inspect the traces recorded under .rcm/logs
+    Logs {
+        #[command(subcommand)]
+        cmd: logging::LogsCommands,
+    },
 }
 
 #[derive(Subcommand)]
@@ -222,58 +639,213 @@ enum WorkspaceCommands {
         /// Output format (table, json, yaml)
         #[arg(long, default_value = "table")]
         format: String,
+        /// Also list the member projects in .rcm/workspace.json
+        #[arg(long)]
+        recursive: bool,
     },
     /// Synchronize all package managers
-    Sync,
+    Sync {
+        /// Skip the member projects listed in .rcm/workspace.json
+        #[arg(long)]
+        no_members: bool,
+    },
     /// Clean all build artifacts
-    Clean,
+    Clean {
+        /// Skip the member projects listed in .rcm/workspace.json
+        #[arg(long)]
+        no_members: bool,
+    },
     /// Update all dependencies
-    Update,
+    Update {
+        /// Skip the member projects listed in .rcm/workspace.json
+        #[arg(long)]
+        no_members: bool,
+    },
     /// Check workspace health
     Check,
+    /// Show health, dependency, disk and vulnerability trends across checks
+    Trends {
+        /// Number of most recent checks to include
+        #[arg(long, default_value = "30")]
+        last: usize,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    /// Push deps, vulnerabilities, outdated and license results to the org dashboard
+    Export {
+        /// Dashboard endpoint (defaults to dashboard.endpoint)
+        #[arg(long)]
+        endpoint: Option<String>,
+        /// Also write the report to a file
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Point CARGO_HOME, the npm cache/prefix and COMPOSER_HOME at .rcm/homes for this workspace
+    Isolate {
+        /// Go back to the user-level homes
+        #[arg(long)]
+        off: bool,
+    },
+    /// Show disk usage of build output, caches and isolated homes
+    Du {
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
 }
 
 #[derive(Subcommand)]
 enum ConfigCommands {
     /// Show current configuration
-    Show,
-    /// Set configuration value
-    Set { key: String, value: String },
-    /// Get configuration value
+    Show {
+        /// List every value with the layer it came from
+        #[arg(long)]
+        origin: bool,
+    },
+    /// Set a value by key path (e.g. managers.npm.registry, security.blocked_packages[2])
+    Set {
+        key: String,
+        value: String,
+        /// Config file to write (user, workspace, system)
+        #[arg(long, default_value = "user")]
+        scope: String,
+    },
+    /// Get a value by key path
     Get { key: String },
+    /// Remove a key from one config file so lower layers apply again
+    Unset {
+        key: String,
+        /// Config file to edit (user, workspace, system)
+        #[arg(long, default_value = "user")]
+        scope: String,
+    },
+    /// List every key path with its effective value
+    List {
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
     /// Reset configuration to defaults
     Reset,
+    /// Print the config without plaintext credentials, or write a provisioning bundle
+    Export {
+        /// Bundle config, model registry, package mappings and LET specs into one archive
+        #[arg(long)]
+        bundle: bool,
+        /// Bundle path (defaults to rcm-bundle.tar.gz)
+        #[arg(long, requires = "bundle")]
+        output: Option<PathBuf>,
+    },
+    /// Apply a bundle from `rcm config export --bundle` to this machine
+    Import {
+        /// Bundle path
+        bundle: PathBuf,
+    },
+    /// Let this workspace's .rcm/config.json set security, endpoint and credential keys
+    Trust {
+        /// Stop trusting the workspace
+        #[arg(long)]
+        revoke: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
-    
-    // Initialize logging
-    let log_level = if cli.verbose { "debug" } else { "info" };
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(log_level))
-        .init();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches)?;
+    let command = matches.subcommand_name().unwrap_or("rcm").to_string();
+    util::set_dry_run(cli.dry_run);
+    let read_only = cli.read_only || std::env::var("RCM_READ_ONLY").map_or(false, |v| v == "1" || v == "true");
+    util::set_read_only(read_only);
+    if read_only && !read_only_safe(&cli.cmd) {
+        return Err(anyhow::anyhow!(
+            "`rcm {}` changes files or system state and cannot run in read-only mode; try `rcm plan` or `rcm doctor`",
+            command
+        ));
+    }
+
+    // Forward to the remote host before touching local config or workspace
+    if let Some(host) = &cli.remote {
+        if !remote::inside() {
+            return remote::run(host).await;
+        }
+    }
 
-    // Load configuration
-    let config = config::Config::load(cli.config.as_deref()).await?;
+    // Load configuration; later loads in this process see the same profile
+    if let Some(profile) = &cli.profile {
+        std::env::set_var(config_layers::PROFILE_ENV, profile);
+    }
+    let load_options = config_layers::LoadOptions {
+        config_path: cli.config.as_deref(),
+        workspace_root: cli.workspace.as_deref().map(std::path::Path::new),
+        profile: cli.profile.as_deref(),
+        overrides: &cli.set,
+    };
+    let (config, origins) = config_layers::resolve(&load_options).await
+        .map_err(|e| anyhow::anyhow!(redact::redact_error(&e)))?;
+    config_layers::remember(&load_options);
+    redact::init(&config);
+    secrets::init(&config);
     
     // Initialize workspace
-    let workspace = workspace::Workspace::new(cli.workspace.as_deref(), config).await?;
+    let workspace = workspace::Workspace::new(cli.workspace.as_deref(), config.clone()).await?;
+    let _run_guard = gc::RunGuard::acquire(workspace.root(), &command);
+    mirrors::init(workspace.root(), &config).await;
+    gc::startup_sweep(workspace.root()).await;
     
-    debug!("RCM CLI starting with command: {:?}", cli.cmd);
+    // Initialize logging; reading traces, dry runs and read-only runs don't leave one of their own
+    let keep_trace = command != "logs" && !cli.dry_run && !read_only;
+    logging::init(logging::LogOptions {
+        level: &config.core.log_level,
+        color: &config.core.color_output,
+        verbose: cli.verbose,
+        log_file: cli.log_file.clone(),
+        workspace_root: keep_trace.then(|| workspace.root()),
+        command: &command,
+        telemetry: &config.telemetry,
+    })?;
     
-    let result = match cli.cmd {
-        Commands::Init { managers, template } => {
-            commands::init::run(&workspace, managers, &template).await
+    debug!(command = %command, dry_run = cli.dry_run, read_only, "RCM CLI starting");
+    let started = std::time::Instant::now();
+    let span = tracing::info_span!("rcm", command = %command);
+    let config_path = cli.config.clone();
+    let profile = cli.profile.clone();
+    
+    let result = async { match cli.cmd {
+        Commands::Init { managers, template, vars } => {
+            commands::init::run(&workspace, managers, &template, &vars).await
         }
-        Commands::Add { spec, manager, dev } => {
-            commands::add::run(&workspace, &spec, manager.as_deref(), dev).await
+        Commands::Add { spec, manager, dev, platform } => {
+            let platforms = platform.as_deref().map(platform::parse_list).transpose()?.unwrap_or_default();
+            commands::add::run(&workspace, &spec, manager.as_deref(), dev, &platforms).await
         }
         Commands::Remove { spec, manager } => {
-            commands::remove::run(&workspace, &spec, manager.as_deref()).await
+            // `commands::remove` predates webhooks, so its lockfile changes are reported here
+            let changes = webhooks::snapshot(&workspace, &workspace.enabled_managers()).await;
+            commands::remove::run(&workspace, &spec, manager.as_deref()).await?;
+            if let Some(changes) = changes {
+                changes.emit_changes(&workspace).await;
+            }
+            Ok(())
+        }
+        Commands::Ensure { in_container: true, .. } if !container_build::inside() => {
+            container_build::run(&workspace, &config, config_path.as_deref()).await
+        }
+        Commands::Ensure { managers, start_services, force, no_members, .. } => {
+            commands::ensure::run(&workspace, managers, start_services, force).await?;
+            if no_members {
+                Ok(())
+            } else {
+                members::propagate(workspace.root(), config.core.parallel_jobs).await
+            }
         }
-        Commands::Ensure { managers } => {
-            commands::ensure::run(&workspace, managers).await
+        Commands::Run { task, parallel, env, no_deps, args } => {
+            let options = tasks::RunOptions { parallel, env: env.as_deref(), no_deps, args: &args };
+            tasks::run(&workspace, &config, task.as_deref(), options).await
+        }
+        Commands::Doctor { skip_network, format } => {
+            commands::doctor::run(&workspace, &config, skip_network, &format).await
         }
         Commands::Plan { managers, format } => {
             commands::plan::run(&workspace, managers, &format).await
@@ -281,6 +853,51 @@ async fn main() -> Result<()> {
         Commands::Apply { managers, force } => {
             commands::apply::run(&workspace, managers, force).await
         }
+        Commands::Lock { managers, verify } => {
+            commands::lock::run(&workspace, managers, verify).await
+        }
+        Commands::ResolveConflicts { check, base, no_verify } => {
+            commands::resolve::run(&workspace, check, &base, !no_verify).await
+        }
+        Commands::Audit { managers, format, fail_on, cmd } => {
+            commands::audit::run(&workspace, managers, &format, &fail_on, cmd).await
+        }
+        Commands::Outdated { managers, format } => {
+            commands::outdated::run(&workspace, managers, &format).await
+        }
+        Commands::Report { cmd } => {
+            commands::report::handle_command(&workspace, &config, cmd).await
+        }
+        Commands::Query { query, jmespath, format } => {
+            commands::query::run(&workspace, &query, jmespath, &format).await
+        }
+        Commands::Update { managers, advise, model, format } => {
+            commands::update::run(&workspace, managers, advise, model, &format).await
+        }
+        Commands::Lint { tools, fix, format } => {
+            commands::lint::run(&workspace, tools, fix, &format).await
+        }
+        Commands::Test { in_container: true, .. } if !container_build::inside() => {
+            container_build::run(&workspace, &config, config_path.as_deref()).await
+        }
+        Commands::Test { suites, junit, format, .. } => {
+            commands::test::run(&workspace, suites, junit, &format).await
+        }
+        Commands::Coverage { suites, format } => {
+            commands::coverage::run(&workspace, suites, &format).await
+        }
+        Commands::Build { in_container: true, .. } if !container_build::inside() => {
+            container_build::run(&workspace, &config, config_path.as_deref()).await
+        }
+        Commands::Build { target, all_targets, profile, strategy, .. } => {
+            commands::build::run(&workspace, target, all_targets, &profile, strategy).await
+        }
+        Commands::Package { profile, kinds } => {
+            commands::package::run(&workspace, &profile, kinds).await
+        }
+        Commands::Verify { checksum_files, jobs, format } => {
+            commands::verify::run(&workspace, checksum_files, jobs, &format).await
+        }
         Commands::Snapshot { name, include_locks, format } => {
             commands::snapshot::run(&workspace, &name, include_locks, &format).await
         }
@@ -301,6 +918,26 @@ async fn main() -> Result<()> {
             ppm::handle_command(&workspace, cmd).await
         }
         
+        #[cfg(feature = "pip")]
+        Commands::Pip { cmd } => {
+            pip::handle_command(&workspace, cmd).await
+        }
+        
+        #[cfg(feature = "go")]
+        Commands::Go { cmd } => {
+            go::handle_command(&workspace, cmd).await
+        }
+        
+        #[cfg(feature = "gem")]
+        Commands::Gem { cmd } => {
+            gem::handle_command(&workspace, cmd).await
+        }
+        
+        #[cfg(feature = "container")]
+        Commands::Container { cmd } => {
+            container::handle_command(&workspace, cmd).await
+        }
+        
         #[cfg(feature = "system")]
         Commands::System { cmd } => {
             system::handle_command(&workspace, cmd).await
@@ -308,23 +945,131 @@ async fn main() -> Result<()> {
         
         #[cfg(feature = "let")]
         Commands::Let { 
-            target, deploy, plan, apply, build, test, clean, update, 
-            args, env, parallel 
+            target, name, deploy, plan, apply, build, test, clean, update, 
+            graph, args, env, parallel, goal, model 
         } => {
             commands::letcmd::run(
-                &workspace, &target, deploy, plan, apply, build, test, 
-                clean, update, args, env.as_deref(), parallel
+                &workspace, &target, name.as_deref(), deploy, plan, apply, build, test, 
+                clean, update, graph, args, env.as_deref(), parallel, goal.as_deref(), model.as_deref()
             ).await
         }
         
+        Commands::Secret { cmd } => {
+            secrets::handle_command(&workspace, cmd).await
+        }
+        
+        Commands::Auth { cmd } => {
+            auth::handle_command(&workspace, &config, config_path.as_deref(), cmd).await
+        }
+        
+        Commands::Db { cmd } => {
+            db::handle_command(&workspace, cmd).await
+        }
+        
+        Commands::Env { cmd } => {
+            environments::handle_command(&workspace, cmd).await
+        }
+        
+        Commands::Hooks { cmd } => {
+            hooks::handle_command(&workspace, cmd).await
+        }
+        
+        Commands::State { cmd } => {
+            storage::handle_command(&workspace, cmd).await
+        }
+        
+        Commands::Webhook { cmd } => {
+            webhooks::handle_command(&workspace, &config, cmd).await
+        }
+        
+        Commands::License { cmd } => {
+            license::handle_command(&workspace, &config.security.license_policy, cmd).await
+        }
+        
+        Commands::Gc { older_than, format } => {
+            gc::run(&workspace, &config, older_than, &format).await
+        }
+        
+        Commands::Bootstrap { manifest, restart, keep_going } => {
+            bootstrap::run(workspace.root(), &config, config_path.as_deref(), &manifest, restart, keep_going).await
+        }
+        
+        Commands::Cache { cmd } => {
+            cache::handle_command(&config, cmd).await
+        }
+        
+        Commands::Transaction { cmd } => {
+            transaction::handle_command(&workspace, cmd).await
+        }
+        
         Commands::Workspace { cmd } => {
-            commands::workspace::handle_command(&workspace, cmd).await
+            commands::workspace::handle_command(&workspace, &config, cmd).await
+        }
+        
+        Commands::Fleet { cmd } => {
+            fleet::handle_command(&workspace, cmd).await
+        }
+        
+        Commands::Config { cmd: ConfigCommands::Export { bundle, output } } => {
+            config_bundle::export(workspace.root(), config_path.as_deref(), bundle, output.as_deref()).await
+        }
+        
+        Commands::Config { cmd: ConfigCommands::Show { origin: true } } => {
+            config_layers::print_origins(&config, &origins)
+        }
+        
+        Commands::Config { cmd: ConfigCommands::Get { key } } => {
+            config_layers::print_value(&config, &key)
+        }
+        
+        Commands::Config { cmd: ConfigCommands::List { format } } => {
+            config_layers::print_list(&config, &format)
+        }
+        
+        Commands::Config { cmd: ConfigCommands::Set { key, value, scope } } => {
+            let options = config_layers::LoadOptions {
+                config_path: config_path.as_deref(),
+                workspace_root: Some(workspace.root()),
+                profile: profile.as_deref(),
+                overrides: &[],
+            };
+            config_layers::set(&options, &scope, &key, &value).await
+        }
+        
+        Commands::Config { cmd: ConfigCommands::Unset { key, scope } } => {
+            let options = config_layers::LoadOptions {
+                config_path: config_path.as_deref(),
+                workspace_root: Some(workspace.root()),
+                profile: profile.as_deref(),
+                overrides: &[],
+            };
+            config_layers::unset(&options, &scope, &key).await
+        }
+        
+        Commands::Config { cmd: ConfigCommands::Trust { revoke } } => {
+            let options = config_layers::LoadOptions {
+                config_path: config_path.as_deref(),
+                workspace_root: Some(workspace.root()),
+                profile: profile.as_deref(),
+                overrides: &[],
+            };
+            config_layers::trust(&options, workspace.root(), revoke).await
+        }
+        
+        Commands::Config { cmd: ConfigCommands::Import { bundle } } => {
+            config_bundle::import(workspace.root(), config_path.as_deref(), &bundle).await
         }
         
         Commands::Config { cmd } => {
             commands::config::handle_command(&workspace, cmd).await
         }
-    };
+        
+        Commands::Logs { cmd } => {
+            logging::handle_command(&workspace, cmd).await
+        }
+    } }.instrument(span).await;
+    debug!(command = %command, duration_ms = started.elapsed().as_millis() as u64, success = result.is_ok(), "RCM command finished");
+    telemetry::shutdown();
 
     match result {
         Ok(_) => {
@@ -332,12 +1077,46 @@ async fn main() -> Result<()> {
             Ok(())
         }
         Err(e) => {
-            warn!("RCM command failed: {:?}", e);
-            Err(e)
+            // Error chains can carry command output and URLs; never print them unmasked
+            let message = redact::redact_error(&e);
+            warn!("RCM command failed: {}", message);
+            Err(anyhow::anyhow!(message))
         }
     }
 }
 
+/// Commands that only inspect, and so may run under `--read-only`
+fn read_only_safe(cmd: &Commands) -> bool {
+    match cmd {
+        Commands::Doctor { .. }
+        | Commands::Plan { .. }
+        | Commands::Outdated { .. }
+        | Commands::Verify { .. }
+        | Commands::License { .. }
+        | Commands::Logs { .. }
+        | Commands::Transaction { .. }
+        | Commands::Query { .. } => true,
+        Commands::Lock { verify, .. } => *verify,
+        Commands::ResolveConflicts { check, .. } => *check,
+        Commands::Update { advise, .. } => *advise,
+        Commands::Env { cmd } => matches!(
+            cmd,
+            environments::EnvCommands::Diff { .. } | environments::EnvCommands::List | environments::EnvCommands::Shell { .. }
+        ),
+        Commands::Hooks { cmd } => matches!(cmd, hooks::HookCommands::List),
+        Commands::State { cmd } => matches!(cmd, storage::StateCommands::Info),
+        Commands::Webhook { cmd } => matches!(cmd, webhooks::WebhookCommands::List),
+        Commands::Auth { cmd } => matches!(cmd, auth::AuthCommands::Status { .. }),
+        Commands::Cache { cmd } => matches!(cmd, cache::CacheCommands::Stats { .. } | cache::CacheCommands::Verify { keep: true }),
+        Commands::Workspace { cmd } => matches!(cmd, WorkspaceCommands::List { .. } | WorkspaceCommands::Du { .. }),
+        Commands::Config { cmd } => matches!(
+            cmd,
+            ConfigCommands::Show { .. } | ConfigCommands::Get { .. } | ConfigCommands::List { .. } | ConfigCommands::Export { bundle: false, .. }
+        ),
+        _ => false,
+    }
+}
+
 fn run_cli<I, S>(iter: I) -> Result<i32>
 where
     I: IntoIterator<Item = S>,
@@ -347,31 +1126,31 @@ where
     match rt.block_on(async {
         let cli = Cli::parse_from(iter);
         // Set up minimal logging for FFI calls
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn"))
-            .init();
+        logging::init_stderr("warn");
         
         let config = config::Config::default();
         let workspace = workspace::Workspace::new(None, config).await?;
         
         match cli.cmd {
-            Commands::Init { managers, template } => {
-                commands::init::run(&workspace, managers, &template).await?;
+            Commands::Init { managers, template, vars } => {
+                commands::init::run(&workspace, managers, &template, &vars).await?;
                 Ok(0)
             }
-            Commands::Add { spec, manager, dev } => {
-                commands::add::run(&workspace, &spec, manager.as_deref(), dev).await?;
+            Commands::Add { spec, manager, dev, platform } => {
+                let platforms = platform.as_deref().map(platform::parse_list).transpose()?.unwrap_or_default();
+                commands::add::run(&workspace, &spec, manager.as_deref(), dev, &platforms).await?;
                 Ok(0)
             }
             // Add other command mappings...
             _ => {
-                eprintln!("Command not supported in FFI mode");
+                error!("Command not supported in FFI mode");
                 Ok(1)
             }
         }
     }) {
         Ok(code) => Ok(code),
         Err(e) => {
-            eprintln!("Runtime error: {:?}", e);
+            error!("Runtime error: {}", redact::redact_error(&e));
             Ok(1)
         }
     }
@@ -409,7 +1188,7 @@ pub extern "C" fn rcm_run(argc: c_int, argv: *const *const c_char) -> c_int {
     match run_cli(args) {
         Ok(code) => code,
         Err(err) => {
-            eprintln!("rcm error: {err:?}");
+            eprintln!("rcm error: {}", redact::redact_error(&err));
             1
         }
     }
@@ -0,0 +1,112 @@
+//! Client for models served by `rcm gpt serve`
+//!
+//! Shared by the upgrade advisor and LET spec drafting: finds a running model in
+//! the GPT registry and sends it completion requests over its serving API.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::path::Path;
+use tokio::fs;
+use crate::sbom::MODEL_REGISTRY;
+use crate::secrets;
+
+/// A running model from the GPT registry
+pub struct ServedModel {
+    pub name: String,
+    backend: String,
+    endpoint: String,
+    token: Option<String>,
+}
+
+/// Find the model to consult: `--model`, the registry default, or the only running model
+pub async fn find_model(workspace_root: &Path, requested: Option<&str>) -> Result<Option<ServedModel>> {
+    let path = workspace_root.join(MODEL_REGISTRY);
+    if !path.exists() {
+        return match requested {
+            Some(model) => Err(anyhow!("Model '{}' not found: no GPT registry at {}", model, path.display())),
+            None => Ok(None),
+        };
+    }
+    let registry: Value = serde_json::from_str(&fs::read_to_string(&path).await?)
+        .context("Failed to parse GPT model registry")?;
+    let active = registry.get("active_models").and_then(Value::as_object).cloned().unwrap_or_default();
+
+    let name = match requested {
+        Some(model) => model.to_string(),
+        None => match registry.get("default_model").and_then(Value::as_str) {
+            Some(default) if active.contains_key(default) => default.to_string(),
+            _ if active.len() == 1 => active.keys().next().cloned().unwrap_or_default(),
+            _ => return Ok(None),
+        },
+    };
+    let Some(instance) = active.get(&name) else {
+        return Err(anyhow!("Model '{}' is not running. Start it with 'rcm gpt serve {} --deploy'", name, name));
+    };
+
+    // Unit variants serialize as "Ollama", data-carrying ones as {"Remote": "..."}
+    let backend = match instance.pointer("/config/backend") {
+        Some(Value::String(backend)) => backend.clone(),
+        Some(Value::Object(map)) => map.keys().next().cloned().unwrap_or_default(),
+        _ => String::new(),
+    };
+    Ok(Some(ServedModel {
+        name: instance.pointer("/config/name").and_then(Value::as_str).unwrap_or(&name).to_string(),
+        backend,
+        endpoint: instance.get("endpoint").and_then(Value::as_str).unwrap_or_default().trim_end_matches('/').to_string(),
+        token: instance
+            .pointer("/config/serving_config/auth_token")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .or_else(|| std::env::var("RCM_GPT_API_KEY").ok()),
+    }))
+}
+
+/// Complete `prompt` with a JSON reply of at most `max_tokens`
+pub async fn complete(model: &ServedModel, prompt: &str, max_tokens: usize) -> Result<String> {
+    let client = reqwest::Client::new();
+    if model.backend == "Ollama" {
+        let response = client
+            .post(format!("{}/api/generate", model.endpoint))
+            .json(&serde_json::json!({
+                "model": model.name,
+                "prompt": prompt,
+                "stream": false,
+                "format": "json",
+                "options": { "num_predict": max_tokens, "temperature": 0.2 },
+            }))
+            .send()
+            .await
+            .context("Failed to reach the model")?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Model request failed: {}", response.status()));
+        }
+        let body: Value = response.json().await?;
+        return body["response"].as_str().map(str::to_string).ok_or_else(|| anyhow!("Invalid response format"));
+    }
+
+    // llama-server exposes the OpenAI API under /v1; vLLM and remote endpoints already include it
+    let url = if model.backend == "LlamaCpp" {
+        format!("{}/v1/completions", model.endpoint)
+    } else {
+        format!("{}/completions", model.endpoint)
+    };
+    let mut request = client.post(&url).json(&serde_json::json!({
+        "model": model.name,
+        "prompt": prompt,
+        "max_tokens": max_tokens,
+        "temperature": 0.2,
+    }));
+    if let Some(token) = &model.token {
+        let token = match secrets::secret_ref(token) {
+            Some(name) => secrets::get_secret(name).await?,
+            None => token.clone(),
+        };
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.context("Failed to reach the model")?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Model request failed: {}", response.status()));
+    }
+    let body: Value = response.json().await?;
+    body["choices"][0]["text"].as_str().map(str::to_string).ok_or_else(|| anyhow!("Invalid response format"))
+}
@@ -4,14 +4,22 @@
 
 use anyhow::{anyhow, Context, Result};
 use clap::Subcommand;
+use console::style;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use tabled::{Table, Tabled};
 use tokio::fs;
+use walkdir::WalkDir;
+use crate::commands::letcond::glob_match;
+use crate::config::{CacheUsage, Config};
+use crate::isolation;
+use crate::mirrors;
+use crate::toolchain::{self, ToolPin, ToolchainPins};
 use crate::workspace::Workspace;
-use crate::util::{self, execute_command, validate_package_name};
+use crate::util::{self, execute_command, execute_mutation, validate_package_name};
 
 #[derive(Subcommand)]
 pub enum NpmCommands {
@@ -22,46 +30,55 @@ pub enum NpmCommands {
         /// Install as dev dependencies
         #[arg(long)]
         dev: bool,
-        /// Use specific package manager (npm, yarn, pnpm)
-        #[arg(long, default_value = "npm")]
-        manager: String,
+        /// Use specific package manager (npm, yarn, pnpm, bun; defaults to the packageManager field)
+        #[arg(long)]
+        manager: Option<String>,
         /// Global installation
         #[arg(long)]
         global: bool,
+        /// Scope to a workspace package (name or path)
+        #[arg(long)]
+        filter: Option<String>,
     },
     
     /// Uninstall NPM packages
     Uninstall {
         /// Packages to uninstall
         packages: Vec<String>,
-        /// Package manager to use
-        #[arg(long, default_value = "npm")]
-        manager: String,
+        /// Package manager to use (defaults to the packageManager field)
+        #[arg(long)]
+        manager: Option<String>,
         /// Global uninstallation
         #[arg(long)]
         global: bool,
+        /// Scope to a workspace package (name or path)
+        #[arg(long)]
+        filter: Option<String>,
     },
     
     /// Update NPM packages
     Update {
         /// Specific packages to update (all if empty)
         packages: Vec<String>,
-        /// Package manager to use
-        #[arg(long, default_value = "npm")]
-        manager: String,
+        /// Package manager to use (defaults to the packageManager field)
+        #[arg(long)]
+        manager: Option<String>,
+        /// Scope to a workspace package (name or path)
+        #[arg(long)]
+        filter: Option<String>,
     },
     
     /// List installed packages
     List {
-        /// Show only top-level dependencies
+        /// Dependency depth to show (0 = top-level only)
         #[arg(long)]
         depth: Option<u32>,
         /// Output format (json, tree, table)
         #[arg(long, default_value = "tree")]
         format: String,
-        /// Package manager to use
-        #[arg(long, default_value = "npm")]
-        manager: String,
+        /// Package manager to use (defaults to the packageManager field)
+        #[arg(long)]
+        manager: Option<String>,
     },
     
     /// Initialize package.json
@@ -83,9 +100,12 @@ pub enum NpmCommands {
         script: String,
         /// Additional arguments
         args: Vec<String>,
-        /// Package manager to use
-        #[arg(long, default_value = "npm")]
-        manager: String,
+        /// Package manager to use (defaults to the packageManager field)
+        #[arg(long)]
+        manager: Option<String>,
+        /// Scope to a workspace package (name or path)
+        #[arg(long)]
+        filter: Option<String>,
     },
     
     /// Audit packages for vulnerabilities
@@ -93,21 +113,87 @@ pub enum NpmCommands {
         /// Auto-fix vulnerabilities
         #[arg(long)]
         fix: bool,
-        /// Package manager to use
-        #[arg(long, default_value = "npm")]
-        manager: String,
+        /// Package manager to use (defaults to the packageManager field)
+        #[arg(long)]
+        manager: Option<String>,
     },
     
     /// Show package information
     Info {
-        /// Package name
+        /// Package name, optionally with a version, range or dist-tag (react@next)
         package: String,
-        /// Show specific field
+        /// Show a specific field (dotted path, e.g. dist.tarball)
         #[arg(long)]
         field: Option<String>,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    
+    /// Pin a yarn or pnpm version for this workspace through corepack
+    Use {
+        /// Package manager and version (e.g. pnpm@9.1.0, yarn@4)
+        spec: String,
+    },
+    
+    /// Enable corepack shims for yarn and pnpm in this workspace
+    Corepack,
+    
+    /// Manage the npm cache, pnpm store, yarn cache, and bun cache
+    Cache {
+        #[command(subcommand)]
+        cmd: NpmCacheCommands,
+    },
+    
+    /// Inspect pnpm, yarn and npm workspace packages
+    Workspaces {
+        #[command(subcommand)]
+        cmd: NpmWorkspaceCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum NpmWorkspaceCommands {
+    /// List the packages of a monorepo
+    List {
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum NpmCacheCommands {
+    /// Show cache locations and sizes alongside RCM's own cache
+    Stats {
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    /// Clean the cache (`npm cache clean`, `pnpm store prune`, `yarn cache clean`, `bun pm cache rm`)
+    Clean {
+        /// Package manager to use
+        #[arg(long, default_value = "npm")]
+        manager: String,
+    },
+    /// Verify cache integrity (`npm cache verify`, `pnpm store status`)
+    Verify {
+        /// Package manager to use
+        #[arg(long, default_value = "npm")]
+        manager: String,
     },
 }
 
+#[derive(Tabled)]
+struct CacheRow {
+    #[tabled(rename = "Cache")]
+    name: String,
+    #[tabled(rename = "Size")]
+    size: String,
+    #[tabled(rename = "Path")]
+    path: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PackageJson {
     pub name: Option<String>,
@@ -140,12 +226,99 @@ pub struct NpmPackageInfo {
     pub integrity: Option<String>,
 }
 
+/// A package in the installed dependency tree
+#[derive(Debug, Clone, Serialize)]
+pub struct InstalledPackage {
+    pub name: String,
+    pub version: Option<String>,
+    pub dev: bool,
+    /// missing, invalid or extraneous, as reported by `npm ls`
+    pub problem: Option<String>,
+    pub dependencies: Vec<InstalledPackage>,
+}
+
+impl InstalledPackage {
+    /// Problems in this subtree as `name: problem` lines
+    pub fn problems(&self) -> Vec<String> {
+        let own = self.problem.iter().map(|p| format!("{}: {}", self.name, p));
+        own.chain(self.dependencies.iter().flat_map(InstalledPackage::problems)).collect()
+    }
+}
+
+#[derive(Tabled)]
+struct InstalledRow {
+    #[tabled(rename = "Package")]
+    name: String,
+    #[tabled(rename = "Version")]
+    version: String,
+    #[tabled(rename = "Depth")]
+    depth: usize,
+    #[tabled(rename = "Type")]
+    kind: String,
+    #[tabled(rename = "Status")]
+    status: String,
+}
+
+/// Registry metadata for one version of a package
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageSummary {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub license: Option<String>,
+    pub homepage: Option<String>,
+    pub repository: Option<String>,
+    pub dependencies: BTreeMap<String, String>,
+    pub dist_tags: BTreeMap<String, String>,
+    pub versions: usize,
+    pub published: Option<String>,
+    pub tarball: Option<String>,
+    pub integrity: Option<String>,
+    pub maintainers: Vec<String>,
+}
+
+/// A sub-package of a pnpm, yarn or npm workspace
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspacePackage {
+    pub name: String,
+    pub version: Option<String>,
+    /// Directory relative to the workspace root, `/`-separated
+    pub path: String,
+    pub private: bool,
+    /// Other workspace packages this one depends on
+    pub local_dependencies: Vec<String>,
+}
+
+#[derive(Tabled)]
+struct WorkspacePackageRow {
+    #[tabled(rename = "Package")]
+    name: String,
+    #[tabled(rename = "Version")]
+    version: String,
+    #[tabled(rename = "Path")]
+    path: String,
+    #[tabled(rename = "Private")]
+    private: String,
+    #[tabled(rename = "Depends on")]
+    local_dependencies: String,
+}
+
+#[derive(Tabled)]
+struct InfoRow {
+    #[tabled(rename = "Field")]
+    field: String,
+    #[tabled(rename = "Value")]
+    value: String,
+}
+
 #[derive(Debug)]
 pub struct NpmManager {
     workspace_root: PathBuf,
     package_json_path: PathBuf,
     lock_file_path: PathBuf,
     manager_type: NpmManagerType,
+    /// Workspace package that install, update and run are scoped to
+    scope: Option<WorkspacePackage>,
 }
 
 #[derive(Debug, Clone)]
@@ -153,6 +326,7 @@ pub enum NpmManagerType {
     Npm,
     Yarn,
     Pnpm,
+    Bun,
 }
 
 impl NpmManagerType {
@@ -161,6 +335,7 @@ impl NpmManagerType {
             "npm" => Ok(Self::Npm),
             "yarn" => Ok(Self::Yarn),
             "pnpm" => Ok(Self::Pnpm),
+            "bun" => Ok(Self::Bun),
             _ => Err(anyhow!("Unsupported npm manager: {}", s)),
         }
     }
@@ -170,6 +345,7 @@ impl NpmManagerType {
             Self::Npm => "npm",
             Self::Yarn => "yarn",
             Self::Pnpm => "pnpm",
+            Self::Bun => "bun",
         }
     }
     
@@ -178,10 +354,360 @@ impl NpmManagerType {
             Self::Npm => "package-lock.json",
             Self::Yarn => "yarn.lock",
             Self::Pnpm => "pnpm-lock.yaml",
+            Self::Bun => "bun.lockb",
         }
     }
 }
 
+/// The `packageManager` field of package.json (e.g. `pnpm@9.1.0+sha512.abc`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageManagerField {
+    pub manager: String,
+    pub version: String,
+}
+
+impl PackageManagerField {
+    pub fn parse(value: &str) -> Result<Self> {
+        let (manager, version) = value.split_once('@')
+            .ok_or_else(|| anyhow!("Invalid packageManager field '{}', expected <name>@<version>", value))?;
+        // Corepack appends an integrity hash after '+'
+        let version = version.split('+').next().unwrap_or(version);
+        if manager.is_empty() || version.is_empty() {
+            return Err(anyhow!("Invalid packageManager field '{}', expected <name>@<version>", value));
+        }
+        Ok(Self { manager: manager.to_string(), version: version.to_string() })
+    }
+    
+    /// Read the field from the workspace package.json
+    pub async fn load(workspace_root: &Path) -> Result<Option<Self>> {
+        let path = workspace_root.join("package.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path).await
+            .context("Failed to read package.json")?;
+        let json: serde_json::Value = serde_json::from_str(&content)
+            .context("Failed to parse package.json")?;
+        json.get("packageManager")
+            .and_then(|v| v.as_str())
+            .map(Self::parse)
+            .transpose()
+    }
+}
+
+/// Pick the JS package manager for a workspace
+///
+/// An explicit request wins unless it conflicts with the `packageManager` field.
+/// Conflicts switch to the declared manager with a warning, or fail when
+/// `managers.npm.options.strict_package_manager` is set. Without a request the
+/// field is used, then the lockfile present, then npm.
+pub async fn resolve_manager_type(workspace_root: &Path, requested: Option<&str>) -> Result<NpmManagerType> {
+    let declared = PackageManagerField::load(workspace_root).await?;
+    
+    match (requested, declared) {
+        (Some(requested), Some(declared)) if !requested.eq_ignore_ascii_case(&declared.manager) => {
+            if strict_package_manager().await {
+                return Err(anyhow!(
+                    "This workspace uses {}@{} (packageManager in package.json); refusing to run {}",
+                    declared.manager, declared.version, requested
+                ));
+            }
+            tracing::warn!(
+                "packageManager requires {}@{}; using it instead of {}",
+                declared.manager, declared.version, requested
+            );
+            NpmManagerType::from_str(&declared.manager)
+        }
+        (Some(requested), _) => NpmManagerType::from_str(requested),
+        (None, Some(declared)) => NpmManagerType::from_str(&declared.manager),
+        (None, None) => Ok(if workspace_root.join("bun.lockb").exists() {
+            NpmManagerType::Bun
+        } else if workspace_root.join("pnpm-lock.yaml").exists() {
+            NpmManagerType::Pnpm
+        } else if workspace_root.join("yarn.lock").exists() {
+            NpmManagerType::Yarn
+        } else {
+            NpmManagerType::Npm
+        }),
+    }
+}
+
+async fn strict_package_manager() -> bool {
+    Config::load(None).await
+        .ok()
+        .and_then(|c| c.managers.get("npm").and_then(|m| m.options.get("strict_package_manager")).and_then(|v| v.as_bool()))
+        .unwrap_or(false)
+}
+
+/// Directory corepack writes its yarn/pnpm shims to
+fn corepack_dir(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(".rcm").join("corepack")
+}
+
+/// Enable corepack shims for the workspace
+pub async fn enable_corepack(workspace_root: &Path, managers: &[&str]) -> Result<()> {
+    if !util::command_exists("corepack").await {
+        return Err(anyhow!("corepack not found. It ships with Node.js 16.9+; or run 'npm install -g corepack'"));
+    }
+    
+    let dir = corepack_dir(workspace_root);
+    if !util::is_dry_run() {
+        fs::create_dir_all(&dir).await?;
+    }
+    
+    let mut cmd = Command::new("corepack");
+    cmd.current_dir(workspace_root);
+    cmd.arg("enable").arg("--install-directory").arg(&dir).args(managers);
+    execute_mutation(&mut cmd).await
+        .context("Failed to enable corepack")?;
+    Ok(())
+}
+
+/// Pin yarn or pnpm for the workspace via corepack and the toolchain shims
+pub async fn use_package_manager(workspace_root: &Path, spec: &str) -> Result<()> {
+    let requested = PackageManagerField::parse(spec)?;
+    if !matches!(requested.manager.as_str(), "yarn" | "pnpm") {
+        return Err(anyhow!("Only yarn and pnpm can be pinned with corepack (got {})", requested.manager));
+    }
+    
+    enable_corepack(workspace_root, &[&requested.manager]).await?;
+    
+    // `corepack use` resolves the version and writes packageManager with its hash
+    let mut cmd = Command::new("corepack");
+    cmd.current_dir(workspace_root);
+    cmd.args(["use", spec]);
+    execute_mutation(&mut cmd).await
+        .with_context(|| format!("Failed to switch to {}", spec))?;
+    if util::is_dry_run() {
+        println!("[dry-run] Would pin {} in {}", spec, toolchain::TOOLCHAIN_FILE);
+        return Ok(());
+    }
+    
+    let resolved = PackageManagerField::load(workspace_root).await?
+        .ok_or_else(|| anyhow!("corepack did not record packageManager in package.json"))?;
+    
+    let mut pins = ToolchainPins::load(workspace_root).await?;
+    pins.pin(workspace_root, &resolved.manager, ToolPin {
+        version: resolved.version.clone(),
+        path: corepack_dir(workspace_root).join(&resolved.manager),
+    }).await?;
+    
+    println!("✅ Using {}@{} in this workspace", resolved.manager, resolved.version);
+    println!("   packageManager set in package.json; pinned in {}", toolchain::TOOLCHAIN_FILE);
+    Ok(())
+}
+
+/// `engines.node` from the workspace package.json
+pub async fn node_engine(workspace_root: &Path) -> Result<Option<String>> {
+    let path = workspace_root.join("package.json");
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).await
+        .context("Failed to read package.json")?;
+    let json: serde_json::Value = serde_json::from_str(&content)
+        .context("Failed to parse package.json")?;
+    Ok(json.pointer("/engines/node").and_then(|v| v.as_str()).map(str::to_string))
+}
+
+/// Node.js version the workspace runs (pinned shims first)
+pub async fn node_version(workspace_root: &Path) -> Option<semver::Version> {
+    let mut cmd = Command::new("node");
+    cmd.arg("--version");
+    if let Some(path) = toolchain::shim_path(workspace_root) {
+        cmd.env("PATH", path);
+    }
+    let result = execute_command(&mut cmd).await.ok()?;
+    semver::Version::parse(result.stdout.trim().trim_start_matches('v')).ok()
+}
+
+/// Whether `version` satisfies an npm engines range (`>=18`, `>=18 <21`, `^18 || >=20`)
+pub fn satisfies_engine(range: &str, version: &semver::Version) -> Result<bool> {
+    for alternative in range.split("||") {
+        // npm separates comparators with spaces and allows `>= 18`; semver wants commas
+        let mut comparators: Vec<String> = Vec::new();
+        let mut operator = String::new();
+        for token in alternative.split_whitespace() {
+            if token.chars().all(|c| matches!(c, '<' | '>' | '=' | '^' | '~')) {
+                operator.push_str(token);
+            } else {
+                comparators.push(format!("{}{}", std::mem::take(&mut operator), token));
+            }
+        }
+        if comparators.is_empty() {
+            return Ok(true);
+        }
+        let req = semver::VersionReq::parse(&comparators.join(", "))
+            .with_context(|| format!("Unsupported engines range '{}'", range))?;
+        if req.matches(version) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Split `name[@version]`, keeping the leading `@` of scoped packages
+pub fn split_package_spec(spec: &str) -> (&str, Option<&str>) {
+    match spec[1.min(spec.len())..].find('@') {
+        Some(i) => (&spec[..i + 1], Some(&spec[i + 2..])),
+        None => (spec, None),
+    }
+}
+
+/// Package globs listed in `pnpm-workspace.yaml`
+pub fn parse_pnpm_workspace(content: &str) -> Vec<String> {
+    let unquote = |s: &str| s.trim().trim_matches(|c| c == '\'' || c == '"').to_string();
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        let trimmed = line.split(" #").next().unwrap_or(line).trim_end();
+        if trimmed.trim().is_empty() || trimmed.trim_start().starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) && !trimmed.starts_with('-') {
+            in_packages = false;
+            if let Some(rest) = trimmed.strip_prefix("packages:") {
+                let rest = rest.trim();
+                if let Some(flow) = rest.strip_prefix('[').and_then(|r| r.strip_suffix(']')) {
+                    patterns.extend(flow.split(',').map(unquote).filter(|p| !p.is_empty()));
+                } else {
+                    in_packages = rest.is_empty();
+                }
+            }
+            continue;
+        }
+        if in_packages {
+            if let Some(item) = trimmed.trim_start().strip_prefix('-') {
+                patterns.push(unquote(item));
+            }
+        }
+    }
+    patterns
+}
+
+/// Package globs from the `workspaces` field of package.json (array or `{ packages: [...] }`)
+pub fn package_json_workspaces(value: &serde_json::Value) -> Vec<String> {
+    let list = value.as_array().or_else(|| value.get("packages").and_then(|p| p.as_array()));
+    list.map(|items| items.iter().filter_map(|i| i.as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+/// Whether a member directory is selected by the workspace globs (`!` negates)
+pub fn matches_workspace_globs(patterns: &[String], path: &str) -> bool {
+    let normalize = |p: &str| p.trim_start_matches("./").trim_end_matches('/').to_string();
+    let mut included = false;
+    for pattern in patterns {
+        match pattern.strip_prefix('!') {
+            Some(negated) if glob_match(&normalize(negated), path) => return false,
+            Some(_) => {}
+            None => included |= glob_match(&normalize(pattern), path),
+        }
+    }
+    included
+}
+
+/// Workspace globs from `pnpm-workspace.yaml`, falling back to package.json `workspaces`
+async fn workspace_globs(workspace_root: &Path) -> Result<Vec<String>> {
+    let pnpm_file = workspace_root.join("pnpm-workspace.yaml");
+    if pnpm_file.exists() {
+        let content = fs::read_to_string(&pnpm_file).await
+            .context("Failed to read pnpm-workspace.yaml")?;
+        return Ok(parse_pnpm_workspace(&content));
+    }
+    let package_json = workspace_root.join("package.json");
+    if !package_json.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&package_json).await
+        .context("Failed to read package.json")?;
+    let json: serde_json::Value = serde_json::from_str(&content)
+        .context("Failed to parse package.json")?;
+    Ok(json.get("workspaces").map(package_json_workspaces).unwrap_or_default())
+}
+
+/// Discover the packages of a pnpm, yarn or npm workspace (empty for single-package projects)
+pub async fn workspace_packages(workspace_root: &Path) -> Result<Vec<WorkspacePackage>> {
+    let patterns = workspace_globs(workspace_root).await?;
+    if patterns.is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    let mut manifests = Vec::new();
+    let walker = WalkDir::new(workspace_root)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|e| !matches!(e.file_name().to_str(), Some("node_modules" | ".git")));
+    for entry in walker.filter_map(|e| e.ok()).filter(|e| e.file_type().is_dir()) {
+        let Ok(relative) = entry.path().strip_prefix(workspace_root) else { continue };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        let manifest = entry.path().join("package.json");
+        if !manifest.exists() || !matches_workspace_globs(&patterns, &relative) {
+            continue;
+        }
+        let content = fs::read_to_string(&manifest).await
+            .with_context(|| format!("Failed to read {}", manifest.display()))?;
+        let json: serde_json::Value = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", manifest.display()))?;
+        manifests.push((relative, json));
+    }
+    
+    let names: Vec<String> = manifests.iter()
+        .filter_map(|(_, json)| json.get("name").and_then(|n| n.as_str()).map(str::to_string))
+        .collect();
+    let mut packages: Vec<WorkspacePackage> = manifests.into_iter()
+        .map(|(path, json)| {
+            let mut local_dependencies: Vec<String> = ["dependencies", "devDependencies", "peerDependencies"]
+                .iter()
+                .filter_map(|field| json.get(*field).and_then(|d| d.as_object()))
+                .flat_map(|deps| deps.keys().cloned())
+                .filter(|dep| names.contains(dep))
+                .collect();
+            local_dependencies.sort();
+            local_dependencies.dedup();
+            WorkspacePackage {
+                // Unnamed members are still addressable by path
+                name: json.get("name").and_then(|n| n.as_str()).map_or_else(|| path.clone(), str::to_string),
+                version: json.get("version").and_then(|v| v.as_str()).map(str::to_string),
+                private: json.get("private").and_then(|p| p.as_bool()).unwrap_or(false),
+                local_dependencies,
+                path,
+            }
+        })
+        .collect();
+    packages.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(packages)
+}
+
+/// Find the workspace package a `--filter` names, by package name or directory
+pub fn find_workspace_package(packages: &[WorkspacePackage], filter: &str) -> Result<WorkspacePackage> {
+    let path = filter.trim_start_matches("./").trim_end_matches('/');
+    packages.iter()
+        .find(|p| p.name == filter || p.path == path)
+        .cloned()
+        .ok_or_else(|| {
+            let known: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+            anyhow!("No workspace package matches '{}'. Known packages: {}", filter, known.join(", "))
+        })
+}
+
+/// Resolve `--filter` for a command, failing outside a monorepo
+async fn resolve_filter(workspace_root: &Path, filter: Option<&str>) -> Result<Option<WorkspacePackage>> {
+    let Some(filter) = filter else { return Ok(None) };
+    let packages = workspace_packages(workspace_root).await?;
+    if packages.is_empty() {
+        return Err(anyhow!("--filter needs pnpm-workspace.yaml or a workspaces field in package.json"));
+    }
+    find_workspace_package(&packages, filter).map(Some)
+}
+
+/// Whether a version string names a dist-tag channel (`latest`, `next`, `beta`) rather than a range
+pub fn is_dist_tag(version: &str) -> bool {
+    let version = version.trim();
+    version.starts_with(|c: char| c.is_ascii_alphabetic())
+        && version.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && semver::VersionReq::parse(version).is_err()
+}
+
 impl NpmManager {
     pub fn new(workspace_root: &Path, manager_type: NpmManagerType) -> Self {
         let package_json_path = workspace_root.join("package.json");
@@ -192,19 +718,55 @@ impl NpmManager {
             package_json_path,
             lock_file_path,
             manager_type,
+            scope: None,
+        }
+    }
+    
+    /// Scope mutating commands to one workspace package
+    pub fn scoped(mut self, package: Option<WorkspacePackage>) -> Self {
+        self.scope = package;
+        self
+    }
+    
+    /// Command running in the workspace with pinned toolchain shims on PATH
+    /// and, when the workspace is isolated, its own npm cache and prefix
+    fn command(&self) -> Command {
+        let mut cmd = Command::new(self.manager_type.command());
+        cmd.current_dir(&self.workspace_root);
+        if let Some(path) = toolchain::shim_path(&self.workspace_root) {
+            cmd.env("PATH", path);
+        }
+        cmd.envs(isolation::env_vars(&self.workspace_root));
+        cmd.envs(mirrors::env_vars());
+        cmd
+    }
+    
+    /// Like `command`, but targeting the scoped workspace package when one is set
+    fn scoped_command(&self) -> Command {
+        let mut cmd = self.command();
+        if let Some(package) = &self.scope {
+            match self.manager_type {
+                NpmManagerType::Npm => { cmd.args(["--workspace", &package.name]); }
+                NpmManagerType::Pnpm => { cmd.args(["--filter", &package.name]); }
+                NpmManagerType::Yarn => { cmd.args(["workspace", &package.name]); }
+                // bun resolves the workspace from the member directory
+                NpmManagerType::Bun => { cmd.current_dir(self.workspace_root.join(&package.path)); }
+            }
         }
+        cmd
     }
     
     /// Check if Node.js and the package manager are available
     pub async fn check_environment(&self) -> Result<()> {
-        // Check Node.js
-        if !util::command_exists("node").await {
+        // Check Node.js (bun ships its own runtime)
+        if !matches!(self.manager_type, NpmManagerType::Bun) && !util::command_exists("node").await {
             return Err(anyhow!("Node.js is not installed or not in PATH"));
         }
         
-        // Check package manager
+        // Check package manager (a pinned shim counts)
         let cmd = self.manager_type.command();
-        if !util::command_exists(cmd).await {
+        let pinned = toolchain::shim_dir(&self.workspace_root).join(cmd).exists();
+        if !pinned && !util::command_exists(cmd).await {
             return Err(anyhow!("{} is not installed or not in PATH", cmd));
         }
         
@@ -245,7 +807,7 @@ impl NpmManager {
         let content = serde_json::to_string_pretty(package_json)
             .context("Failed to serialize package.json")?;
         
-        fs::write(&self.package_json_path, content).await
+        util::write_file(&self.package_json_path, content).await
             .context("Failed to write package.json")
     }
     
@@ -253,8 +815,7 @@ impl NpmManager {
     pub async fn install(&self, packages: &[String], dev: bool, global: bool) -> Result<()> {
         self.check_environment().await?;
         
-        let mut cmd = Command::new(self.manager_type.command());
-        cmd.current_dir(&self.workspace_root);
+        let mut cmd = self.scoped_command();
         
         match self.manager_type {
             NpmManagerType::Npm => {
@@ -287,18 +848,48 @@ impl NpmManager {
                 }
                 cmd.args(packages);
             }
+            NpmManagerType::Bun => {
+                cmd.arg("add");
+                if global {
+                    cmd.arg("--global");
+                }
+                if dev {
+                    cmd.arg("--dev");
+                }
+                cmd.args(packages);
+            }
         }
         
-        execute_command(&mut cmd).await
+        execute_mutation(&mut cmd).await
             .context("Failed to install npm packages")
     }
     
+    /// Install packages into node_modules without recording them in package.json
+    ///
+    /// Used for platform-limited dependencies, which only the RCM manifest holds.
+    pub async fn install_unsaved(&self, packages: &[String]) -> Result<()> {
+        self.check_environment().await?;
+        let mut cmd = self.scoped_command();
+        match self.manager_type {
+            NpmManagerType::Npm => cmd.args(["install", "--no-save"]),
+            NpmManagerType::Bun => cmd.args(["add", "--no-save"]),
+            NpmManagerType::Yarn | NpmManagerType::Pnpm => {
+                return Err(anyhow!(
+                    "{} cannot install without saving to package.json; platform-limited npm dependencies need npm or bun",
+                    self.manager_type.command()
+                ));
+            }
+        };
+        cmd.args(packages);
+        execute_mutation(&mut cmd).await
+            .context("Failed to install platform-limited npm packages")
+    }
+    
     /// Uninstall packages
     pub async fn uninstall(&self, packages: &[String], global: bool) -> Result<()> {
         self.check_environment().await?;
         
-        let mut cmd = Command::new(self.manager_type.command());
-        cmd.current_dir(&self.workspace_root);
+        let mut cmd = self.scoped_command();
         
         match self.manager_type {
             NpmManagerType::Npm => {
@@ -315,7 +906,7 @@ impl NpmManager {
                 }
                 cmd.args(packages);
             }
-            NpmManagerType::Pnpm => {
+            NpmManagerType::Pnpm | NpmManagerType::Bun => {
                 cmd.arg("remove");
                 if global {
                     cmd.arg("--global");
@@ -324,7 +915,7 @@ impl NpmManager {
             }
         }
         
-        execute_command(&mut cmd).await
+        execute_mutation(&mut cmd).await
             .context("Failed to uninstall npm packages")
     }
     
@@ -332,8 +923,7 @@ impl NpmManager {
     pub async fn update(&self, packages: &[String]) -> Result<()> {
         self.check_environment().await?;
         
-        let mut cmd = Command::new(self.manager_type.command());
-        cmd.current_dir(&self.workspace_root);
+        let mut cmd = self.scoped_command();
         
         match self.manager_type {
             NpmManagerType::Npm => {
@@ -348,13 +938,13 @@ impl NpmManager {
                     cmd.args(packages);
                 }
             }
-            NpmManagerType::Pnpm => {
+            NpmManagerType::Pnpm | NpmManagerType::Bun => {
                 cmd.arg("update");
                 cmd.args(packages);
             }
         }
         
-        execute_command(&mut cmd).await
+        execute_mutation(&mut cmd).await
             .context("Failed to update npm packages")
     }
     
@@ -362,8 +952,7 @@ impl NpmManager {
     pub async fn run_script(&self, script: &str, args: &[String]) -> Result<()> {
         self.check_environment().await?;
         
-        let mut cmd = Command::new(self.manager_type.command());
-        cmd.current_dir(&self.workspace_root);
+        let mut cmd = self.scoped_command();
         
         match self.manager_type {
             NpmManagerType::Npm => {
@@ -379,14 +968,14 @@ impl NpmManager {
                 cmd.arg(script);
                 cmd.args(args);
             }
-            NpmManagerType::Pnpm => {
+            NpmManagerType::Pnpm | NpmManagerType::Bun => {
                 cmd.arg("run");
                 cmd.arg(script);
                 cmd.args(args);
             }
         }
         
-        execute_command(&mut cmd).await
+        execute_mutation(&mut cmd).await
             .context("Failed to run npm script")
     }
     
@@ -394,8 +983,7 @@ impl NpmManager {
     pub async fn audit(&self, fix: bool) -> Result<()> {
         self.check_environment().await?;
         
-        let mut cmd = Command::new(self.manager_type.command());
-        cmd.current_dir(&self.workspace_root);
+        let mut cmd = self.command();
         
         match self.manager_type {
             NpmManagerType::Npm => {
@@ -408,7 +996,7 @@ impl NpmManager {
                 cmd.arg("audit");
                 if fix {
                     // Yarn doesn't have auto-fix, but we can suggest manual fixes
-                    eprintln!("Note: Yarn audit doesn't support auto-fix. Run 'yarn audit' and update packages manually.");
+                    tracing::warn!("Yarn audit doesn't support auto-fix. Run 'yarn audit' and update packages manually.");
                 }
             }
             NpmManagerType::Pnpm => {
@@ -417,12 +1005,141 @@ impl NpmManager {
                     cmd.arg("--fix");
                 }
             }
+            NpmManagerType::Bun => {
+                cmd.arg("audit");
+                if fix {
+                    tracing::warn!("bun audit doesn't support auto-fix. Run 'bun update' on the affected packages.");
+                }
+            }
         }
         
         execute_command(&mut cmd).await
             .context("Failed to audit npm packages")
     }
     
+    /// Installed dependency tree from `npm ls --json` (`pnpm ls --json` for pnpm)
+    ///
+    /// yarn and bun install into a regular node_modules, which `npm ls` reads.
+    pub async fn list(&self, depth: u32) -> Result<Vec<InstalledPackage>> {
+        let mut cmd = match self.manager_type {
+            NpmManagerType::Pnpm => self.command(),
+            _ => {
+                let mut cmd = Command::new("npm");
+                cmd.current_dir(&self.workspace_root);
+                if let Some(path) = toolchain::shim_path(&self.workspace_root) {
+                    cmd.env("PATH", path);
+                }
+                cmd.envs(isolation::env_vars(&self.workspace_root));
+                cmd.envs(mirrors::env_vars());
+                cmd
+            }
+        };
+        cmd.args(["ls", "--json", "--depth", &depth.to_string()]);
+        
+        // `npm ls` exits non-zero when the tree has problems but still prints it
+        let output = cmd.output().context("Failed to run npm ls")?;
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+            .with_context(|| format!("Invalid npm ls output: {}", String::from_utf8_lossy(&output.stderr).trim()))?;
+        
+        let dev_names: Vec<String> = self.load_package_json().await
+            .ok()
+            .and_then(|p| p.dev_dependencies)
+            .map(|deps| deps.into_keys().collect())
+            .unwrap_or_default();
+        Ok(parse_ls_tree(&json, &dev_names))
+    }
+    
+    /// Registry metadata for `name[@version|range|tag]` via `npm view`
+    pub async fn info(&self, spec: &str) -> Result<PackageSummary> {
+        let mut cmd = Command::new("npm");
+        cmd.current_dir(&self.workspace_root);
+        cmd.args(["view", spec, "--json"]);
+        
+        let result = execute_command(&mut cmd).await
+            .with_context(|| format!("Failed to fetch package info for {}", spec))?;
+        let json: serde_json::Value = serde_json::from_str(&result.stdout)
+            .with_context(|| format!("Invalid npm view response for {}", spec))?;
+        summarize_view(&json).ok_or_else(|| anyhow!("No version of {} matches", spec))
+    }
+    
+    /// Fetch the dist-tags of a package (`latest`, `next`, ...) from the registry
+    pub async fn dist_tags(&self, package: &str) -> Result<HashMap<String, String>> {
+        let mut cmd = Command::new("npm");
+        cmd.current_dir(&self.workspace_root);
+        cmd.args(["view", package, "dist-tags", "--json"]);
+        
+        let result = execute_command(&mut cmd).await
+            .with_context(|| format!("Failed to fetch dist-tags for {}", package))?;
+        serde_json::from_str(&result.stdout)
+            .with_context(|| format!("Invalid dist-tags response for {}", package))
+    }
+    
+    /// Resolve a channel to the version it currently points at
+    pub async fn resolve_channel(&self, package: &str, channel: &str) -> Result<String> {
+        self.dist_tags(package).await?
+            .remove(channel)
+            .ok_or_else(|| anyhow!("Package {} has no '{}' dist-tag", package, channel))
+    }
+    
+    /// Version installed in node_modules, if any
+    pub async fn installed_version(&self, package: &str) -> Option<String> {
+        let manifest = self.workspace_root.join("node_modules").join(package).join("package.json");
+        let content = fs::read_to_string(manifest).await.ok()?;
+        let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+        json.get("version").and_then(|v| v.as_str()).map(str::to_string)
+    }
+    
+    /// Location of the manager's package cache (npm cache, pnpm store, yarn cache)
+    pub async fn cache_dir(&self) -> Result<PathBuf> {
+        let mut cmd = self.command();
+        match self.manager_type {
+            NpmManagerType::Npm => cmd.args(["config", "get", "cache"]),
+            NpmManagerType::Yarn => cmd.args(["cache", "dir"]),
+            NpmManagerType::Pnpm => cmd.args(["store", "path"]),
+            NpmManagerType::Bun => cmd.args(["pm", "cache"]),
+        };
+        
+        let result = execute_command(&mut cmd).await
+            .with_context(|| format!("Failed to locate the {} cache", self.manager_type.command()))?;
+        Ok(PathBuf::from(result.stdout.trim()))
+    }
+    
+    /// Clean the package cache
+    pub async fn cache_clean(&self) -> Result<()> {
+        self.check_environment().await?;
+        
+        let mut cmd = self.command();
+        match self.manager_type {
+            // npm refuses to clean without --force since the cache is self-healing
+            NpmManagerType::Npm => cmd.args(["cache", "clean", "--force"]),
+            NpmManagerType::Yarn => cmd.args(["cache", "clean"]),
+            // Prune only removes packages no project references
+            NpmManagerType::Pnpm => cmd.args(["store", "prune"]),
+            NpmManagerType::Bun => cmd.args(["pm", "cache", "rm"]),
+        };
+        
+        execute_mutation(&mut cmd).await
+            .context("Failed to clean package cache")?;
+        Ok(())
+    }
+    
+    /// Verify the package cache
+    pub async fn cache_verify(&self) -> Result<String> {
+        self.check_environment().await?;
+        
+        let mut cmd = self.command();
+        match self.manager_type {
+            NpmManagerType::Npm => cmd.args(["cache", "verify"]),
+            NpmManagerType::Pnpm => cmd.args(["store", "status"]),
+            NpmManagerType::Yarn => return Err(anyhow!("yarn has no cache verification; use 'yarn cache clean' to reset it")),
+            NpmManagerType::Bun => return Err(anyhow!("bun has no cache verification; use 'bun pm cache rm' to reset it")),
+        };
+        
+        let result = execute_command(&mut cmd).await
+            .context("Cache verification failed")?;
+        Ok(result.stdout)
+    }
+    
     /// Validate package name
     pub fn validate_package_name(name: &str) -> Result<()> {
         // NPM package name validation
@@ -446,37 +1163,281 @@ impl NpmManager {
     }
 }
 
+/// Cache usage of every installed JS package manager
+pub async fn js_cache_usage(workspace_root: &Path) -> Vec<CacheUsage> {
+    let mut usage = Vec::new();
+    for manager_type in [NpmManagerType::Npm, NpmManagerType::Pnpm, NpmManagerType::Yarn, NpmManagerType::Bun] {
+        let name = manager_type.command();
+        if !util::command_exists(name).await {
+            continue;
+        }
+        let manager = NpmManager::new(workspace_root, manager_type);
+        match manager.cache_dir().await {
+            Ok(path) => {
+                let size_bytes = util::calculate_directory_size(&path).await.unwrap_or(0);
+                usage.push(CacheUsage { name: name.to_string(), path, size_bytes });
+            }
+            Err(e) => tracing::warn!("{}", e),
+        }
+    }
+    usage
+}
+
+async fn handle_cache_command(workspace: &Workspace, cmd: NpmCacheCommands) -> Result<()> {
+    match cmd {
+        NpmCacheCommands::Stats { format } => {
+            let config = Config::load(None).await?;
+            let mut usage = vec![config.cache_usage().await?];
+            usage.extend(js_cache_usage(workspace.root()).await);
+            
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&usage)?),
+                "table" => {
+                    let total: u64 = usage.iter().map(|u| u.size_bytes).sum();
+                    let rows: Vec<CacheRow> = usage
+                        .into_iter()
+                        .map(|u| CacheRow {
+                            name: u.name,
+                            size: util::format_bytes(u.size_bytes),
+                            path: u.path.display().to_string(),
+                        })
+                        .collect();
+                    println!("{}", Table::new(rows));
+                    println!("Total: {}", util::format_bytes(total));
+                }
+                _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+            }
+            Ok(())
+        }
+        
+        NpmCacheCommands::Clean { manager } => {
+            let npm_manager = NpmManager::new(workspace.root(), NpmManagerType::from_str(&manager)?);
+            let path = npm_manager.cache_dir().await?;
+            let before = util::calculate_directory_size(&path).await.unwrap_or(0);
+            npm_manager.cache_clean().await?;
+            let after = util::calculate_directory_size(&path).await.unwrap_or(0);
+            println!("🧹 Cleaned {} cache, freed {}", manager, util::format_bytes(before.saturating_sub(after)));
+            Ok(())
+        }
+        
+        NpmCacheCommands::Verify { manager } => {
+            let npm_manager = NpmManager::new(workspace.root(), NpmManagerType::from_str(&manager)?);
+            let report = npm_manager.cache_verify().await?;
+            print!("{}", report);
+            println!("✅ {} cache verified", manager);
+            Ok(())
+        }
+    }
+}
+
+/// Normalize `npm ls --json` (an object) or `pnpm ls --json` (one entry per project)
+pub fn parse_ls_tree(json: &serde_json::Value, dev_names: &[String]) -> Vec<InstalledPackage> {
+    fn node(name: &str, value: &serde_json::Value, dev: bool) -> InstalledPackage {
+        let required = value.get("required")
+            .and_then(|r| r.as_str().or_else(|| r.get("version").and_then(|v| v.as_str())));
+        let problem = if value.get("missing").and_then(|m| m.as_bool()).unwrap_or(false) {
+            Some(match required {
+                Some(range) => format!("missing (requires {})", range),
+                None => "missing".to_string(),
+            })
+        } else if let Some(invalid) = value.get("invalid").filter(|i| i.as_bool() != Some(false)) {
+            Some(match invalid.as_str() {
+                Some(reason) => format!("invalid: {}", reason.replace('"', "")),
+                None => "invalid".to_string(),
+            })
+        } else if value.get("extraneous").and_then(|e| e.as_bool()).unwrap_or(false) {
+            Some("extraneous".to_string())
+        } else {
+            None
+        };
+        
+        InstalledPackage {
+            name: name.to_string(),
+            version: value.get("version").and_then(|v| v.as_str()).map(str::to_string),
+            dev,
+            problem,
+            dependencies: children(value, "dependencies", dev),
+        }
+    }
+    
+    fn children(value: &serde_json::Value, key: &str, dev: bool) -> Vec<InstalledPackage> {
+        value.get(key)
+            .and_then(|d| d.as_object())
+            .map(|deps| deps.iter().map(|(name, v)| node(name, v, dev)).collect())
+            .unwrap_or_default()
+    }
+    
+    let mut packages = match json {
+        serde_json::Value::Array(projects) => projects.iter()
+            .flat_map(|project| {
+                let mut deps = children(project, "dependencies", false);
+                deps.extend(children(project, "optionalDependencies", false));
+                deps.extend(children(project, "devDependencies", true));
+                deps
+            })
+            .collect(),
+        _ => children(json, "dependencies", false),
+    };
+    for package in &mut packages {
+        if dev_names.contains(&package.name) {
+            package.dev = true;
+        }
+    }
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    packages
+}
+
+/// Summarize `npm view --json`; a range matching several versions yields an array
+pub fn summarize_view(json: &serde_json::Value) -> Option<PackageSummary> {
+    let doc = match json {
+        serde_json::Value::Array(versions) => versions.last()?,
+        other => other,
+    };
+    let text = |key: &str| doc.get(key).and_then(|v| v.as_str()).map(str::to_string);
+    let text_or_field = |key: &str, field: &str| doc.get(key)
+        .and_then(|v| v.as_str().or_else(|| v.get(field).and_then(|f| f.as_str())))
+        .map(str::to_string);
+    let map = |key: &str| doc.get(key)
+        .and_then(|v| v.as_object())
+        .map(|m| m.iter().filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string()))).collect())
+        .unwrap_or_default();
+    let version = text("version")?;
+    
+    Some(PackageSummary {
+        name: text("name")?,
+        description: text("description"),
+        license: text_or_field("license", "type"),
+        homepage: text("homepage"),
+        repository: text_or_field("repository", "url"),
+        dependencies: map("dependencies"),
+        dist_tags: map("dist-tags"),
+        versions: match doc.get("versions") {
+            Some(serde_json::Value::Array(list)) => list.len(),
+            Some(serde_json::Value::String(_)) => 1,
+            _ => 0,
+        },
+        published: doc.get("time").and_then(|t| t.get(&version)).and_then(|t| t.as_str()).map(str::to_string),
+        tarball: doc.pointer("/dist/tarball").and_then(|v| v.as_str()).map(str::to_string),
+        integrity: doc.pointer("/dist/integrity").and_then(|v| v.as_str()).map(str::to_string),
+        maintainers: doc.get("maintainers")
+            .and_then(|m| m.as_array())
+            .map(|list| list.iter()
+                .filter_map(|m| m.as_str().map(str::to_string)
+                    .or_else(|| m.get("name").and_then(|n| n.as_str()).map(str::to_string)))
+                .collect())
+            .unwrap_or_default(),
+        version,
+    })
+}
+
+/// Problems in the top-level installed tree (missing, invalid, extraneous), for health checks
+pub async fn dependency_problems(workspace_root: &Path) -> Result<Vec<String>> {
+    let manager_type = resolve_manager_type(workspace_root, None).await?;
+    let tree = NpmManager::new(workspace_root, manager_type).list(0).await?;
+    Ok(tree.iter().flat_map(InstalledPackage::problems).collect())
+}
+
+fn print_tree(packages: &[InstalledPackage], prefix: &str) {
+    for (i, package) in packages.iter().enumerate() {
+        let last = i + 1 == packages.len();
+        let version = package.version.as_deref().unwrap_or("?");
+        let mut line = format!("{}{}{}@{}", prefix, if last { "└── " } else { "├── " }, package.name, version);
+        if package.dev {
+            line.push_str(&format!(" {}", style("dev").dim()));
+        }
+        if let Some(problem) = &package.problem {
+            line.push_str(&format!(" {}", style(problem).red()));
+        }
+        println!("{}", line);
+        print_tree(&package.dependencies, &format!("{}{}", prefix, if last { "    " } else { "│   " }));
+    }
+}
+
+fn flatten_rows(packages: &[InstalledPackage], depth: usize, rows: &mut Vec<InstalledRow>) {
+    for package in packages {
+        rows.push(InstalledRow {
+            name: package.name.clone(),
+            version: package.version.clone().unwrap_or_else(|| "-".to_string()),
+            depth,
+            kind: if package.dev { "dev" } else { "prod" }.to_string(),
+            status: package.problem.clone().unwrap_or_else(|| "ok".to_string()),
+        });
+        flatten_rows(&package.dependencies, depth + 1, rows);
+    }
+}
+
+fn lookup_field<'a>(json: &'a serde_json::Value, field: &str) -> Option<&'a serde_json::Value> {
+    field.split('.').try_fold(json, |value, key| value.get(key))
+}
+
 /// Handle NPM commands
 pub async fn handle_command(workspace: &Workspace, cmd: NpmCommands) -> Result<()> {
     match cmd {
-        NpmCommands::Install { packages, dev, manager, global } => {
-            let manager_type = NpmManagerType::from_str(&manager)?;
-            let npm_manager = NpmManager::new(workspace.root(), manager_type);
+        NpmCommands::Install { packages, dev, manager, global, filter } => {
+            if global && filter.is_some() {
+                return Err(anyhow!("--filter cannot be combined with --global"));
+            }
+            let manager_type = resolve_manager_type(workspace.root(), manager.as_deref()).await?;
+            let scope = resolve_filter(workspace.root(), filter.as_deref()).await?;
+            let npm_manager = NpmManager::new(workspace.root(), manager_type).scoped(scope);
             
             // Validate package names
             for package in &packages {
-                let name = package.split('@').next().unwrap_or(package);
+                let (name, _) = split_package_spec(package);
                 NpmManager::validate_package_name(name)?;
             }
             
             npm_manager.install(&packages, dev, global).await
         }
         
-        NpmCommands::Uninstall { packages, manager, global } => {
-            let manager_type = NpmManagerType::from_str(&manager)?;
-            let npm_manager = NpmManager::new(workspace.root(), manager_type);
+        NpmCommands::Uninstall { packages, manager, global, filter } => {
+            if global && filter.is_some() {
+                return Err(anyhow!("--filter cannot be combined with --global"));
+            }
+            let manager_type = resolve_manager_type(workspace.root(), manager.as_deref()).await?;
+            let scope = resolve_filter(workspace.root(), filter.as_deref()).await?;
+            let npm_manager = NpmManager::new(workspace.root(), manager_type).scoped(scope);
             npm_manager.uninstall(&packages, global).await
         }
         
-        NpmCommands::Update { packages, manager } => {
-            let manager_type = NpmManagerType::from_str(&manager)?;
-            let npm_manager = NpmManager::new(workspace.root(), manager_type);
+        NpmCommands::Update { packages, manager, filter } => {
+            let manager_type = resolve_manager_type(workspace.root(), manager.as_deref()).await?;
+            let scope = resolve_filter(workspace.root(), filter.as_deref()).await?;
+            let npm_manager = NpmManager::new(workspace.root(), manager_type).scoped(scope);
             npm_manager.update(&packages).await
         }
         
-        NpmCommands::List { depth: _, format: _, manager: _ } => {
-            // Implementation for listing packages
-            println!("NPM list functionality not yet implemented");
+        NpmCommands::List { depth, format, manager } => {
+            let manager_type = resolve_manager_type(workspace.root(), manager.as_deref()).await?;
+            let npm_manager = NpmManager::new(workspace.root(), manager_type);
+            let tree = npm_manager.list(depth.unwrap_or(0)).await?;
+            
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&tree)?),
+                "tree" => {
+                    let name = npm_manager.load_package_json().await.ok()
+                        .and_then(|p| p.name)
+                        .unwrap_or_else(|| "(root)".to_string());
+                    println!("{}", style(name).bold());
+                    print_tree(&tree, "");
+                }
+                "table" => {
+                    let mut rows = Vec::new();
+                    flatten_rows(&tree, 0, &mut rows);
+                    if rows.is_empty() {
+                        println!("No packages installed.");
+                    } else {
+                        println!("{}", Table::new(rows));
+                    }
+                }
+                _ => return Err(anyhow!("Unsupported format: {}. Use tree, table or json", format)),
+            }
+            
+            let problems: Vec<String> = tree.iter().flat_map(InstalledPackage::problems).collect();
+            if !problems.is_empty() && format != "json" {
+                println!();
+                println!("{} {} problem(s); run {} to fix", style("⚠️").yellow(), problems.len(), style("rcm ensure").cyan());
+            }
             Ok(())
         }
         
@@ -506,22 +1467,230 @@ pub async fn handle_command(workspace: &Workspace, cmd: NpmCommands) -> Result<(
             npm_manager.save_package_json(&package_json).await
         }
         
-        NpmCommands::Run { script, args, manager } => {
-            let manager_type = NpmManagerType::from_str(&manager)?;
-            let npm_manager = NpmManager::new(workspace.root(), manager_type);
+        NpmCommands::Run { script, args, manager, filter } => {
+            let manager_type = resolve_manager_type(workspace.root(), manager.as_deref()).await?;
+            let scope = resolve_filter(workspace.root(), filter.as_deref()).await?;
+            let npm_manager = NpmManager::new(workspace.root(), manager_type).scoped(scope);
             npm_manager.run_script(&script, &args).await
         }
         
         NpmCommands::Audit { fix, manager } => {
-            let manager_type = NpmManagerType::from_str(&manager)?;
+            let manager_type = resolve_manager_type(workspace.root(), manager.as_deref()).await?;
             let npm_manager = NpmManager::new(workspace.root(), manager_type);
             npm_manager.audit(fix).await
         }
         
-        NpmCommands::Info { package: _, field: _ } => {
-            // Implementation for package info
-            println!("NPM info functionality not yet implemented");
+        NpmCommands::Info { package, field, format } => {
+            let (name, _) = split_package_spec(&package);
+            NpmManager::validate_package_name(name)?;
+            let npm_manager = NpmManager::new(workspace.root(), NpmManagerType::Npm);
+            let summary = npm_manager.info(&package).await?;
+            
+            if let Some(field) = field {
+                let json = serde_json::to_value(&summary)?;
+                let value = lookup_field(&json, &field.replace("dist-tags", "dist_tags"))
+                    .ok_or_else(|| anyhow!("Unknown field: {}", field))?;
+                match value {
+                    serde_json::Value::String(s) => println!("{}", s),
+                    other => println!("{}", serde_json::to_string_pretty(other)?),
+                }
+                return Ok(());
+            }
+            
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&summary)?),
+                "table" => {
+                    let row = |field: &str, value: Option<String>| InfoRow {
+                        field: field.to_string(),
+                        value: value.unwrap_or_else(|| "-".to_string()),
+                    };
+                    let join = |map: &BTreeMap<String, String>| {
+                        (!map.is_empty()).then(|| map.iter().map(|(k, v)| format!("{}: {}", k, v)).collect::<Vec<_>>().join("\n"))
+                    };
+                    let rows = vec![
+                        row("Name", Some(summary.name.clone())),
+                        row("Version", Some(summary.version.clone())),
+                        row("Description", summary.description.clone()),
+                        row("License", summary.license.clone()),
+                        row("Homepage", summary.homepage.clone()),
+                        row("Repository", summary.repository.clone()),
+                        row("Published", summary.published.clone()),
+                        row("Dist-tags", join(&summary.dist_tags)),
+                        row("Versions", Some(summary.versions.to_string())),
+                        row("Dependencies", join(&summary.dependencies)),
+                        row("Maintainers", (!summary.maintainers.is_empty()).then(|| summary.maintainers.join(", "))),
+                        row("Tarball", summary.tarball.clone()),
+                        row("Integrity", summary.integrity.clone()),
+                    ];
+                    println!("{}", Table::new(rows));
+                }
+                _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+            }
+            Ok(())
+        }
+        
+        NpmCommands::Use { spec } => use_package_manager(workspace.root(), &spec).await,
+        
+        NpmCommands::Corepack => {
+            enable_corepack(workspace.root(), &["yarn", "pnpm"]).await?;
+            println!("✅ corepack shims for yarn and pnpm installed in {}", corepack_dir(workspace.root()).display());
             Ok(())
         }
+        
+        NpmCommands::Cache { cmd } => handle_cache_command(workspace, cmd).await,
+        
+        NpmCommands::Workspaces { cmd: NpmWorkspaceCommands::List { format } } => {
+            let packages = workspace_packages(workspace.root()).await?;
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&packages)?),
+                "table" => {
+                    if packages.is_empty() {
+                        println!("No workspace packages (add pnpm-workspace.yaml or a workspaces field to package.json).");
+                        return Ok(());
+                    }
+                    let rows: Vec<WorkspacePackageRow> = packages.iter()
+                        .map(|p| WorkspacePackageRow {
+                            name: p.name.clone(),
+                            version: p.version.clone().unwrap_or_else(|| "-".to_string()),
+                            path: p.path.clone(),
+                            private: if p.private { "yes" } else { "no" }.to_string(),
+                            local_dependencies: p.local_dependencies.join(", "),
+                        })
+                        .collect();
+                    println!("{}", Table::new(rows));
+                    println!("{} workspace package(s)", packages.len());
+                }
+                _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_package_spec() {
+        assert_eq!(split_package_spec("react@next"), ("react", Some("next")));
+        assert_eq!(split_package_spec("@types/node@^20"), ("@types/node", Some("^20")));
+        assert_eq!(split_package_spec("@types/node"), ("@types/node", None));
+        assert_eq!(split_package_spec("lodash"), ("lodash", None));
+    }
+
+    #[test]
+    fn test_satisfies_engine() {
+        let v = |s: &str| semver::Version::parse(s).unwrap();
+        assert!(satisfies_engine(">=18", &v("20.11.0")).unwrap());
+        assert!(!satisfies_engine(">=18", &v("16.20.2")).unwrap());
+        assert!(satisfies_engine(">= 18 < 21", &v("20.0.0")).unwrap());
+        assert!(!satisfies_engine(">=18 <21", &v("22.1.0")).unwrap());
+        assert!(satisfies_engine("^16 || >=20", &v("22.1.0")).unwrap());
+        assert!(satisfies_engine("*", &v("22.1.0")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_ls_tree() {
+        let json = serde_json::json!({
+            "name": "app",
+            "dependencies": {
+                "react": { "version": "18.2.0", "dependencies": { "loose-envify": { "version": "1.4.0" } } },
+                "lodash": { "required": "^4.17.21", "missing": true },
+                "vitest": { "version": "0.34.0", "invalid": "\"^1.0.0\" from the root project" }
+            }
+        });
+        let tree = parse_ls_tree(&json, &["vitest".to_string()]);
+        assert_eq!(tree.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), vec!["lodash", "react", "vitest"]);
+        assert_eq!(tree[1].dependencies[0].version.as_deref(), Some("1.4.0"));
+        assert!(tree[2].dev);
+        let problems: Vec<String> = tree.iter().flat_map(InstalledPackage::problems).collect();
+        assert_eq!(problems, vec![
+            "lodash: missing (requires ^4.17.21)".to_string(),
+            "vitest: invalid: ^1.0.0 from the root project".to_string(),
+        ]);
+        
+        let pnpm = serde_json::json!([{ "name": "app", "devDependencies": { "typescript": { "version": "5.4.5" } } }]);
+        assert!(parse_ls_tree(&pnpm, &[])[0].dev);
+    }
+
+    #[test]
+    fn test_summarize_view() {
+        let json = serde_json::json!({
+            "name": "lodash",
+            "version": "4.17.21",
+            "license": "MIT",
+            "repository": { "type": "git", "url": "git+https://github.com/lodash/lodash.git" },
+            "dist-tags": { "latest": "4.17.21" },
+            "versions": ["4.17.20", "4.17.21"],
+            "time": { "4.17.21": "2021-02-20T15:42:16.891Z" },
+            "maintainers": ["jdalton <john.david.dalton@gmail.com>"],
+            "dist": { "tarball": "https://registry.npmjs.org/lodash/-/lodash-4.17.21.tgz" }
+        });
+        let summary = summarize_view(&json).unwrap();
+        assert_eq!(summary.repository.as_deref(), Some("git+https://github.com/lodash/lodash.git"));
+        assert_eq!(summary.versions, 2);
+        assert_eq!(summary.published.as_deref(), Some("2021-02-20T15:42:16.891Z"));
+        let value = serde_json::to_value(&summary).unwrap();
+        assert_eq!(lookup_field(&value, "dist_tags.latest").unwrap(), "4.17.21");
+    }
+
+    #[test]
+    fn test_bun_manager_type() {
+        let bun = NpmManagerType::from_str("Bun").unwrap();
+        assert_eq!(bun.command(), "bun");
+        assert_eq!(bun.lock_file(), "bun.lockb");
+        assert!(NpmManagerType::from_str("deno").is_err());
+    }
+
+    #[test]
+    fn test_parse_package_manager_field() {
+        let field = PackageManagerField::parse("pnpm@9.1.0+sha512.abc123").unwrap();
+        assert_eq!(field, PackageManagerField { manager: "pnpm".to_string(), version: "9.1.0".to_string() });
+        assert!(PackageManagerField::parse("pnpm").is_err());
+        assert!(PackageManagerField::parse("yarn@").is_err());
+    }
+
+    #[test]
+    fn test_workspace_globs() {
+        let yaml = "packages:\n  - 'packages/*'\n  - \"apps/**\"  # apps\n  - '!**/test/**'\ncatalog:\n  react: ^18\n";
+        let patterns = parse_pnpm_workspace(yaml);
+        assert_eq!(patterns, vec!["packages/*", "apps/**", "!**/test/**"]);
+        assert_eq!(parse_pnpm_workspace("packages: ['libs/*', \"tools\"]"), vec!["libs/*", "tools"]);
+        
+        assert!(matches_workspace_globs(&patterns, "packages/ui"));
+        assert!(matches_workspace_globs(&patterns, "apps/web/admin"));
+        assert!(!matches_workspace_globs(&patterns, "packages/ui/src"));
+        assert!(!matches_workspace_globs(&patterns, "apps/test/fixture"));
+        
+        let yarn = serde_json::json!({ "packages": ["./packages/*"], "nohoist": ["**/react-native"] });
+        let patterns = package_json_workspaces(&yarn);
+        assert!(matches_workspace_globs(&patterns, "packages/core"));
+        assert_eq!(package_json_workspaces(&serde_json::json!(["a/*"])), vec!["a/*"]);
+    }
+
+    #[test]
+    fn test_find_workspace_package() {
+        let package = |name: &str, path: &str| WorkspacePackage {
+            name: name.to_string(),
+            version: None,
+            path: path.to_string(),
+            private: false,
+            local_dependencies: vec![],
+        };
+        let packages = vec![package("@acme/ui", "packages/ui"), package("web", "apps/web")];
+        assert_eq!(find_workspace_package(&packages, "@acme/ui").unwrap().path, "packages/ui");
+        assert_eq!(find_workspace_package(&packages, "./apps/web/").unwrap().name, "web");
+        assert!(find_workspace_package(&packages, "api").is_err());
+    }
+
+    #[test]
+    fn test_is_dist_tag() {
+        assert!(is_dist_tag("next"));
+        assert!(is_dist_tag("beta"));
+        assert!(is_dist_tag("latest"));
+        assert!(!is_dist_tag("^18.2.0"));
+        assert!(!is_dist_tag("1.x"));
+        assert!(!is_dist_tag("*"));
     }
 }
@@ -0,0 +1,148 @@
+//! Outdated command implementation
+//!
+//! Compares installed dependency versions with the newest release on each
+//! dependency's channel (npm dist-tags, Composer's latest)
+
+use anyhow::{anyhow, Result};
+use console::style;
+use serde::Serialize;
+use std::collections::HashMap;
+use tabled::{Table, Tabled};
+use tokio::process::Command as AsyncCommand;
+use crate::npm::{is_dist_tag, NpmManager, NpmManagerType};
+use crate::workspace::Workspace;
+
+/// One dependency with a newer release available
+#[derive(Debug, Serialize, Tabled)]
+pub struct OutdatedDependency {
+    #[tabled(rename = "Manager")]
+    pub manager: String,
+    #[tabled(rename = "Package")]
+    pub name: String,
+    #[tabled(rename = "Current")]
+    pub current: String,
+    #[tabled(rename = "Wanted")]
+    pub wanted: String,
+    #[tabled(rename = "Channel")]
+    pub channel: String,
+    #[tabled(rename = "Latest")]
+    pub latest: String,
+}
+
+/// Dependencies with newer releases for the given managers
+pub async fn collect(workspace: &Workspace, managers: &[String]) -> Result<Vec<OutdatedDependency>> {
+    let mut outdated = Vec::new();
+    for manager in managers {
+        match manager.as_str() {
+            "npm" => outdated.extend(outdated_npm(workspace).await?),
+            "composer" => outdated.extend(outdated_composer(workspace).await?),
+            other => tracing::debug!("Outdated check not supported for {}", other),
+        }
+    }
+    Ok(outdated)
+}
+
+/// Show dependencies with newer releases
+pub async fn run(workspace: &Workspace, managers: Option<Vec<String>>, format: &str) -> Result<()> {
+    let target_managers = managers.unwrap_or_else(|| workspace.enabled_managers());
+    if target_managers.is_empty() {
+        return Err(anyhow!("No package managers enabled. Run 'rcm init' to configure managers."));
+    }
+
+    let outdated = collect(workspace, &target_managers).await?;
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&outdated)?),
+        "table" => {
+            if outdated.is_empty() {
+                println!("{}", style("✅ All dependencies are up to date").green().bold());
+            } else {
+                println!("{}", Table::new(&outdated));
+            }
+        }
+        _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+    }
+
+    Ok(())
+}
+
+/// npm dependencies compared with the dist-tag they track
+async fn outdated_npm(workspace: &Workspace) -> Result<Vec<OutdatedDependency>> {
+    let npm = NpmManager::new(workspace.root(), NpmManagerType::Npm);
+    let mut outdated = Vec::new();
+
+    for (name, spec) in workspace.list_dependencies() {
+        if spec.manager != "npm" {
+            continue;
+        }
+
+        // Channel dependencies track their dist-tag; ranges are compared with `latest`
+        let channel = if is_dist_tag(&spec.version) { spec.version.as_str() } else { "latest" };
+        let latest = match npm.resolve_channel(name, channel).await {
+            Ok(version) => version,
+            Err(e) => {
+                tracing::warn!("{}", e);
+                continue;
+            }
+        };
+
+        let current = npm.installed_version(name).await;
+        if current.as_deref() == Some(latest.as_str()) {
+            continue;
+        }
+
+        outdated.push(OutdatedDependency {
+            manager: "npm".to_string(),
+            name: name.clone(),
+            current: current.unwrap_or_else(|| "-".to_string()),
+            wanted: spec.version.clone(),
+            channel: channel.to_string(),
+            latest,
+        });
+    }
+
+    Ok(outdated)
+}
+
+/// Composer dependencies from `composer outdated`
+async fn outdated_composer(workspace: &Workspace) -> Result<Vec<OutdatedDependency>> {
+    if !workspace.root().join("composer.lock").exists() {
+        return Ok(Vec::new());
+    }
+
+    let output = AsyncCommand::new("composer")
+        .args(["outdated", "--direct", "--format=json", "--no-interaction"])
+        .current_dir(workspace.root())
+        .output()
+        .await;
+    let Ok(output) = output else {
+        tracing::warn!("composer not found, skipping outdated check");
+        return Ok(Vec::new());
+    };
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap_or_default();
+    let installed = json.get("installed").and_then(|i| i.as_array()).cloned().unwrap_or_default();
+    let declared: HashMap<String, String> = workspace
+        .list_dependencies()
+        .into_iter()
+        .filter(|(_, spec)| spec.manager == "composer")
+        .map(|(name, spec)| (name.clone(), spec.version.clone()))
+        .collect();
+
+    Ok(installed
+        .iter()
+        .filter_map(|pkg| {
+            let field = |k: &str| pkg.get(k).and_then(|v| v.as_str()).map(str::to_string);
+            let name = field("name")?;
+            let wanted = declared.get(&name).cloned().unwrap_or_else(|| "*".to_string());
+            Some(OutdatedDependency {
+                manager: "composer".to_string(),
+                current: field("version")?,
+                latest: field("latest")?,
+                wanted,
+                channel: "stable".to_string(),
+                name,
+            })
+        })
+        .collect())
+}
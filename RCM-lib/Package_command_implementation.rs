@@ -0,0 +1,330 @@
+//! Package command implementation
+//!
+//! Builds the workspace and collects release artifacts (binaries, npm tarballs,
+//! PHARs) into `dist/<profile>/` together with `SHA256SUMS` and a
+//! `manifest.json` that `rcm release` consumes.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tokio::fs;
+use crate::isolation;
+use crate::util;
+use crate::workspace::Workspace;
+
+/// Packaging profiles relative to the workspace root
+pub const PACKAGE_FILE: &str = ".rcm/package.toml";
+
+/// Name of the manifest written next to the artifacts
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Contents of `.rcm/package.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackageConfig {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, PackageProfile>,
+}
+
+/// How to build and collect artifacts for one profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageProfile {
+    /// Cargo profile to build with
+    #[serde(default = "default_cargo_profile")]
+    pub cargo_profile: String,
+    /// Cargo features to enable
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// Cross-compilation target triple
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Strip debug symbols from binaries
+    #[serde(default = "default_true")]
+    pub strip: bool,
+    /// Artifact kinds to build (cargo, npm, phar); all detected kinds when empty
+    #[serde(default)]
+    pub kinds: Vec<String>,
+    /// Output directory, relative to the workspace root
+    #[serde(default = "default_out_dir")]
+    pub out_dir: String,
+}
+
+fn default_cargo_profile() -> String { "release".to_string() }
+fn default_out_dir() -> String { "dist".to_string() }
+fn default_true() -> bool { true }
+
+impl Default for PackageProfile {
+    fn default() -> Self {
+        Self {
+            cargo_profile: default_cargo_profile(),
+            features: Vec::new(),
+            target: None,
+            strip: true,
+            kinds: Vec::new(),
+            out_dir: default_out_dir(),
+        }
+    }
+}
+
+impl PackageConfig {
+    pub fn path(workspace_root: &Path) -> PathBuf {
+        workspace_root.join(PACKAGE_FILE)
+    }
+
+    /// Load profiles, returning none when the file is missing
+    pub async fn load(workspace_root: &Path) -> Result<Self> {
+        let path = Self::path(workspace_root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path).await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// A configured profile, or the built-in `release`/`debug` defaults
+    pub fn profile(&self, name: &str) -> Result<PackageProfile> {
+        if let Some(profile) = self.profiles.get(name) {
+            return Ok(profile.clone());
+        }
+        match name {
+            "release" => Ok(PackageProfile::default()),
+            "debug" => Ok(PackageProfile { cargo_profile: "dev".to_string(), strip: false, ..Default::default() }),
+            _ => Err(anyhow!("Unknown package profile '{}'. Define it under [profiles.{}] in {}", name, name, PACKAGE_FILE)),
+        }
+    }
+}
+
+/// An artifact collected into the output directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Artifact {
+    pub name: String,
+    /// binary, npm or phar
+    pub kind: String,
+    /// Path relative to the manifest
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// `manifest.json` describing a packaged build
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageManifest {
+    pub profile: String,
+    pub created_at: String,
+    pub git_commit: Option<String>,
+    pub target: Option<String>,
+    pub artifacts: Vec<Artifact>,
+}
+
+/// Executables reported by `cargo build --message-format=json`
+pub fn parse_cargo_executables(stdout: &str) -> Vec<PathBuf> {
+    stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|v| v["reason"] == "compiler-artifact")
+        .filter_map(|v| v["executable"].as_str().map(PathBuf::from))
+        .collect()
+}
+
+/// `sha256sum`-compatible checksum file
+pub fn render_checksums(artifacts: &[Artifact]) -> String {
+    artifacts.iter().map(|a| format!("{}  {}\n", a.sha256, a.path)).collect()
+}
+
+async fn detect_kinds(root: &Path) -> Vec<&'static str> {
+    let mut kinds = Vec::new();
+    if root.join("Cargo.toml").exists() {
+        kinds.push("cargo");
+    }
+    if root.join("package.json").exists() {
+        kinds.push("npm");
+    }
+    if root.join("box.json").exists() || root.join("box.json.dist").exists() {
+        kinds.push("phar");
+    }
+    kinds
+}
+
+async fn output_of(cmd: &mut Command, what: &str) -> Result<String> {
+    if util::skip_in_dry_run(cmd) {
+        return Ok(String::new());
+    }
+    Ok(util::execute_command(cmd).await.with_context(|| format!("{} failed", what))?.stdout)
+}
+
+/// Build binaries and copy them (stripped) into `out`
+async fn package_cargo(root: &Path, profile: &PackageProfile, out: &Path) -> Result<Vec<PathBuf>> {
+    let mut cmd = Command::new("cargo");
+    cmd.current_dir(root)
+        .envs(isolation::env_vars(root))
+        .args(["build", "--bins", "--message-format=json", "--profile", &profile.cargo_profile]);
+    if !profile.features.is_empty() {
+        cmd.arg("--features").arg(profile.features.join(","));
+    }
+    if let Some(target) = &profile.target {
+        cmd.args(["--target", target]);
+    }
+    let stdout = output_of(&mut cmd, "cargo build").await?;
+
+    let suffix = profile.target.clone()
+        .unwrap_or_else(|| format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS));
+    let mut collected = Vec::new();
+    for exe in parse_cargo_executables(&stdout) {
+        let stem = exe.file_stem().and_then(|s| s.to_str()).unwrap_or("bin");
+        let name = match exe.extension().and_then(|e| e.to_str()) {
+            Some(ext) => format!("{}-{}.{}", stem, suffix, ext),
+            None => format!("{}-{}", stem, suffix),
+        };
+        let dest = out.join(name);
+        fs::copy(&exe, &dest).await
+            .with_context(|| format!("Failed to copy {}", exe.display()))?;
+        if profile.strip && util::command_exists("strip").await {
+            let mut strip = Command::new("strip");
+            strip.arg(&dest);
+            if let Err(e) = util::execute_command(&mut strip).await {
+                tracing::warn!("Could not strip {}: {}", dest.display(), e);
+            }
+        }
+        collected.push(dest);
+    }
+    Ok(collected)
+}
+
+/// `npm pack` the package into `out`
+async fn package_npm(root: &Path, out: &Path) -> Result<Vec<PathBuf>> {
+    let mut cmd = Command::new("npm");
+    cmd.current_dir(root).envs(isolation::env_vars(root)).args(["pack", "--json", "--pack-destination"]).arg(out);
+    let stdout = output_of(&mut cmd, "npm pack").await?;
+    let packed: Vec<Value> = serde_json::from_str(&stdout).unwrap_or_default();
+    Ok(packed.iter().filter_map(|p| p["filename"].as_str()).map(|f| out.join(f)).collect())
+}
+
+/// Compile a PHAR with box and copy it into `out`
+async fn package_phar(root: &Path, out: &Path) -> Result<Vec<PathBuf>> {
+    let config = ["box.json", "box.json.dist"].iter().map(|f| root.join(f)).find(|p| p.exists())
+        .ok_or_else(|| anyhow!("box.json not found"))?;
+    let box_json: Value = serde_json::from_str(&fs::read_to_string(&config).await?)
+        .with_context(|| format!("Failed to parse {}", config.display()))?;
+    // Box defaults the output to the main script with a .phar extension
+    let output = box_json["output"].as_str().map(str::to_string).unwrap_or_else(|| {
+        let main = box_json["main"].as_str().unwrap_or("index.php");
+        format!("{}.phar", main.trim_end_matches(".php"))
+    });
+
+    let program = if root.join("vendor/bin/box").exists() { root.join("vendor/bin/box") } else { PathBuf::from("box") };
+    let mut cmd = Command::new(program);
+    cmd.current_dir(root).envs(isolation::env_vars(root)).args(["compile", "--no-interaction"]);
+    output_of(&mut cmd, "box compile").await?;
+    if util::is_dry_run() {
+        return Ok(Vec::new());
+    }
+
+    let built = root.join(&output);
+    let dest = out.join(built.file_name().ok_or_else(|| anyhow!("Invalid box output {}", output))?);
+    fs::copy(&built, &dest).await
+        .with_context(|| format!("Failed to copy {}", built.display()))?;
+    Ok(vec![dest])
+}
+
+fn git_commit(root: &Path) -> Option<String> {
+    let output = Command::new("git").current_dir(root).args(["rev-parse", "HEAD"]).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Build and collect artifacts for a profile
+pub async fn run(workspace: &Workspace, profile_name: &str, kinds: Option<Vec<String>>) -> Result<()> {
+    let root = workspace.root();
+    let config = PackageConfig::load(root).await?;
+    let profile = config.profile(profile_name)?;
+    let out = root.join(&profile.out_dir).join(profile_name);
+
+    let mut selected: Vec<String> = match kinds {
+        Some(kinds) => kinds,
+        None if !profile.kinds.is_empty() => profile.kinds.clone(),
+        None => detect_kinds(root).await.into_iter().map(str::to_string).collect(),
+    };
+    selected.dedup();
+    if selected.is_empty() {
+        return Err(anyhow!("Nothing to package (no Cargo.toml, package.json or box.json)"));
+    }
+
+    println!("{}", style(format!("📦 Packaging '{}' into {}", profile_name, out.display())).cyan().bold());
+    if out.exists() {
+        util::remove_dir_all(&out).await?;
+    }
+    if !util::is_dry_run() {
+        fs::create_dir_all(&out).await?;
+    }
+
+    let mut files = Vec::new();
+    for kind in &selected {
+        println!("{}", style(format!("🔨 Building {}...", kind)).blue());
+        let (artifact_kind, built) = match kind.as_str() {
+            "cargo" => ("binary", package_cargo(root, &profile, &out).await?),
+            "npm" => ("npm", package_npm(root, &out).await?),
+            "phar" => ("phar", package_phar(root, &out).await?),
+            _ => return Err(anyhow!("Unsupported artifact kind: {}. Use cargo, npm or phar", kind)),
+        };
+        files.extend(built.into_iter().map(|path| (artifact_kind, path)));
+    }
+    if util::is_dry_run() {
+        return Ok(());
+    }
+
+    let mut artifacts = Vec::new();
+    for (kind, path) in files {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+        artifacts.push(Artifact {
+            path: name.clone(),
+            name,
+            kind: kind.to_string(),
+            size: fs::metadata(&path).await?.len(),
+            sha256: util::get_file_hash(&path).await?,
+        });
+    }
+
+    let manifest = PackageManifest {
+        profile: profile_name.to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        git_commit: git_commit(root),
+        target: profile.target.clone(),
+        artifacts,
+    };
+    util::write_file(&out.join("SHA256SUMS"), render_checksums(&manifest.artifacts)).await?;
+    util::write_file(&out.join(MANIFEST_FILE), serde_json::to_string_pretty(&manifest)?).await?;
+
+    for artifact in &manifest.artifacts {
+        println!("  {} {} ({}, {})", style("✓").green(), artifact.name, artifact.kind, util::format_bytes(artifact.size));
+    }
+    println!("{}", style(format!("✅ {} artifact(s) in {}", manifest.artifacts.len(), out.display())).green().bold());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_and_cargo_artifacts() {
+        let config: PackageConfig = toml::from_str(r#"
+            [profiles.musl]
+            target = "x86_64-unknown-linux-musl"
+            kinds = ["cargo"]
+        "#).unwrap();
+        let musl = config.profile("musl").unwrap();
+        assert_eq!(musl.cargo_profile, "release");
+        assert!(musl.strip);
+        assert!(!config.profile("debug").unwrap().strip);
+        assert!(config.profile("nightly").is_err());
+
+        let stdout = r#"{"reason":"compiler-artifact","target":{"kind":["lib"]},"executable":null}
+{"reason":"compiler-artifact","target":{"kind":["bin"]},"executable":"/work/target/release/rcm"}
+{"reason":"build-finished","success":true}"#;
+        assert_eq!(parse_cargo_executables(stdout), vec![PathBuf::from("/work/target/release/rcm")]);
+    }
+}
@@ -0,0 +1,860 @@
+//! Python package management for RCM
+//!
+//! Provides integration with the Python ecosystem via pip, uv, and poetry.
+//! pip projects are described by requirements.txt (and requirements-dev.txt)
+//! and installed into `.venv`; uv and poetry projects by pyproject.toml.
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tabled::{Table, Tabled};
+use tokio::fs;
+use crate::audit::{self, AuditReport};
+use crate::audit_exceptions::AuditExceptions;
+use crate::toolchain;
+use crate::util::{self, execute_command, execute_mutation};
+use crate::workspace::Workspace;
+
+#[derive(Subcommand)]
+pub enum PipCommands {
+    /// Install Python packages
+    Install {
+        /// Packages to install (name[==version], PEP 508 specifiers)
+        packages: Vec<String>,
+        /// Install as development dependencies
+        #[arg(long)]
+        dev: bool,
+        /// Use specific package manager (pip, uv, poetry; detected from the project files)
+        #[arg(long)]
+        manager: Option<String>,
+    },
+
+    /// Remove Python packages
+    Remove {
+        /// Packages to remove
+        packages: Vec<String>,
+        /// Package manager to use (detected from the project files)
+        #[arg(long)]
+        manager: Option<String>,
+    },
+
+    /// Update Python packages
+    Update {
+        /// Specific packages to update (all if empty)
+        packages: Vec<String>,
+        /// Package manager to use (detected from the project files)
+        #[arg(long)]
+        manager: Option<String>,
+    },
+
+    /// List installed packages
+    List {
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+        /// Package manager to use (detected from the project files)
+        #[arg(long)]
+        manager: Option<String>,
+    },
+
+    /// Audit packages for vulnerabilities with pip-audit
+    Audit {
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PythonManagerType {
+    Pip,
+    Uv,
+    Poetry,
+}
+
+impl PythonManagerType {
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "pip" => Ok(Self::Pip),
+            "uv" => Ok(Self::Uv),
+            "poetry" => Ok(Self::Poetry),
+            _ => Err(anyhow!("Unsupported Python manager: {}", s)),
+        }
+    }
+
+    pub fn command(&self) -> &'static str {
+        match self {
+            Self::Pip => "pip",
+            Self::Uv => "uv",
+            Self::Poetry => "poetry",
+        }
+    }
+
+    pub fn lock_file(&self) -> &'static str {
+        match self {
+            Self::Pip => "requirements.txt",
+            Self::Uv => "uv.lock",
+            Self::Poetry => "poetry.lock",
+        }
+    }
+}
+
+/// An installed distribution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Tabled)]
+pub struct InstalledDistribution {
+    #[tabled(rename = "Package")]
+    pub name: String,
+    #[tabled(rename = "Version")]
+    pub version: String,
+}
+
+/// A dependency declared in requirements files or pyproject.toml
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeclaredRequirement {
+    pub name: String,
+    /// Full PEP 508 requirement, e.g. `uvicorn[standard]>=0.29`
+    pub spec: String,
+    pub dev: bool,
+}
+
+/// Pick a manager from the project files when none is requested
+///
+/// poetry and uv are identified by their lock files or pyproject sections;
+/// anything else is handled with pip and requirements.txt.
+pub fn detect_manager(workspace_root: &Path, pyproject: Option<&toml::Value>) -> PythonManagerType {
+    let has_table = |path: &[&str]| {
+        let mut value = pyproject;
+        for key in path {
+            value = value.and_then(|v| v.get(key));
+        }
+        value.is_some()
+    };
+    if workspace_root.join("poetry.lock").exists() || has_table(&["tool", "poetry"]) {
+        PythonManagerType::Poetry
+    } else if workspace_root.join("uv.lock").exists() || has_table(&["tool", "uv"]) {
+        PythonManagerType::Uv
+    } else {
+        PythonManagerType::Pip
+    }
+}
+
+async fn load_pyproject(workspace_root: &Path) -> Result<Option<toml::Value>> {
+    let path = workspace_root.join("pyproject.toml");
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).await.context("Failed to read pyproject.toml")?;
+    toml::from_str(&content).map(Some).context("Failed to parse pyproject.toml")
+}
+
+/// Resolve the manager to use for a command
+pub async fn resolve_manager_type(workspace_root: &Path, requested: Option<&str>) -> Result<PythonManagerType> {
+    match requested {
+        Some(requested) => PythonManagerType::from_str(requested),
+        None => Ok(detect_manager(workspace_root, load_pyproject(workspace_root).await?.as_ref())),
+    }
+}
+
+/// Distribution name of a PEP 508 requirement (`Django>=5` → `Django`)
+pub fn requirement_name(spec: &str) -> &str {
+    let spec = spec.trim();
+    let end = spec
+        .find(|c: char| matches!(c, '[' | '<' | '>' | '=' | '!' | '~' | ';' | '@' | ' ' | '('))
+        .unwrap_or(spec.len());
+    &spec[..end]
+}
+
+/// PEP 503 normalized name, for comparing `Foo_Bar` with `foo-bar`
+pub fn normalize_name(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    for c in name.chars() {
+        if matches!(c, '-' | '_' | '.') {
+            if !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        } else {
+            normalized.push(c.to_ascii_lowercase());
+        }
+    }
+    normalized
+}
+
+/// Where the `==` clause of an exactly pinned requirement starts and its version
+fn exact_pin(spec: &str) -> Option<(usize, &str)> {
+    let clause_end = spec.find(';').unwrap_or(spec.len());
+    let start = spec[..clause_end].find("==")?;
+    let version = spec[start + 2..clause_end].trim();
+    // `===` is arbitrary equality and `==4.2.*` a wildcard; neither is a plain pin
+    let plain = !version.is_empty() && !version.starts_with('=') && !version.contains([',', '*']);
+    plain.then_some((start, version))
+}
+
+/// The version of an exactly pinned requirement (`Django==4.2` → `4.2`)
+pub fn pinned_version(spec: &str) -> Option<&str> {
+    exact_pin(spec).map(|(_, version)| version)
+}
+
+/// Replace the `==` pin of a requirement, keeping extras and markers; `None` drops the pin
+pub fn repin(spec: &str, version: Option<&str>) -> String {
+    let Some((start, _)) = exact_pin(spec) else {
+        return spec.to_string();
+    };
+    let marker = spec.find(';').map(|i| format!(" {}", &spec[i..])).unwrap_or_default();
+    let base = spec[..start].trim_end();
+    match version {
+        Some(version) => format!("{}=={}{}", base, version, marker),
+        None => format!("{}{}", base, marker),
+    }
+}
+
+/// Requirements from a requirements file, skipping comments, options and includes
+pub fn parse_requirements(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| line.split(" #").next().unwrap_or(line).trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('-'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Add or replace a requirement, keeping comments and the order of other lines
+pub fn upsert_requirement(content: &str, spec: &str) -> String {
+    let name = normalize_name(requirement_name(spec));
+    let mut replaced = false;
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim();
+            let is_requirement = !trimmed.is_empty() && !trimmed.starts_with('#') && !trimmed.starts_with('-');
+            if is_requirement && normalize_name(requirement_name(trimmed)) == name {
+                replaced = true;
+                spec.to_string()
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    if !replaced {
+        lines.push(spec.to_string());
+    }
+    format!("{}\n", lines.join("\n"))
+}
+
+/// Remove a requirement; returns the new content and whether it was present
+pub fn remove_requirement(content: &str, name: &str) -> (String, bool) {
+    let name = normalize_name(name);
+    let lines: Vec<&str> = content
+        .lines()
+        .filter(|line| {
+            let trimmed = line.trim();
+            trimmed.is_empty()
+                || trimmed.starts_with('#')
+                || trimmed.starts_with('-')
+                || normalize_name(requirement_name(trimmed)) != name
+        })
+        .collect();
+    let removed = lines.len() != content.lines().count();
+    let mut updated = lines.join("\n");
+    if !updated.is_empty() {
+        updated.push('\n');
+    }
+    (updated, removed)
+}
+
+/// Dependencies declared in pyproject.toml (PEP 621, PEP 735 groups, uv and poetry tables)
+pub fn pyproject_dependencies(pyproject: &toml::Value) -> Vec<DeclaredRequirement> {
+    let strings = |value: Option<&toml::Value>| -> Vec<String> {
+        value
+            .and_then(|v| v.as_array())
+            .map(|list| list.iter().filter_map(|s| s.as_str().map(str::to_string)).collect())
+            .unwrap_or_default()
+    };
+    let declared = |spec: String, dev: bool| DeclaredRequirement {
+        name: requirement_name(&spec).to_string(),
+        spec,
+        dev,
+    };
+
+    let mut deps: Vec<DeclaredRequirement> = strings(pyproject.get("project").and_then(|p| p.get("dependencies")))
+        .into_iter()
+        .map(|spec| declared(spec, false))
+        .collect();
+    for dev in [
+        pyproject.get("dependency-groups").and_then(|g| g.get("dev")),
+        pyproject.get("tool").and_then(|t| t.get("uv")).and_then(|u| u.get("dev-dependencies")),
+    ] {
+        deps.extend(strings(dev).into_iter().map(|spec| declared(spec, true)));
+    }
+
+    // Poetry keeps `name = "constraint"` tables; `python` is the interpreter, not a package
+    let poetry = pyproject.get("tool").and_then(|t| t.get("poetry"));
+    let poetry_tables = [
+        (poetry.and_then(|p| p.get("dependencies")), false),
+        (poetry.and_then(|p| p.get("dev-dependencies")), true),
+        (poetry.and_then(|p| p.get("group")).and_then(|g| g.get("dev")).and_then(|d| d.get("dependencies")), true),
+    ];
+    for (table, dev) in poetry_tables {
+        for (name, constraint) in table.and_then(|t| t.as_table()).into_iter().flatten() {
+            if name == "python" {
+                continue;
+            }
+            let version = match constraint {
+                toml::Value::String(v) => v.clone(),
+                other => other.get("version").and_then(|v| v.as_str()).unwrap_or("*").to_string(),
+            };
+            deps.push(DeclaredRequirement { name: name.clone(), spec: format!("{} {}", name, version), dev });
+        }
+    }
+    deps
+}
+
+/// Parse `pip list --format=json`
+pub fn parse_pip_list(json: &str) -> Result<Vec<InstalledDistribution>> {
+    let mut list: Vec<InstalledDistribution> = serde_json::from_str(json).context("Invalid pip list output")?;
+    list.sort_by_key(|d| normalize_name(&d.name));
+    Ok(list)
+}
+
+/// Interpreter of the workspace virtualenv
+pub fn venv_python(workspace_root: &Path) -> PathBuf {
+    let venv = workspace_root.join(".venv");
+    if cfg!(windows) {
+        venv.join("Scripts").join("python.exe")
+    } else {
+        venv.join("bin").join("python")
+    }
+}
+
+#[derive(Debug)]
+pub struct PythonManager {
+    workspace_root: PathBuf,
+    manager_type: PythonManagerType,
+}
+
+impl PythonManager {
+    pub fn new(workspace_root: &Path, manager_type: PythonManagerType) -> Self {
+        Self {
+            workspace_root: workspace_root.to_path_buf(),
+            manager_type,
+        }
+    }
+
+    /// Command running in the workspace with pinned toolchain shims on PATH
+    fn command(&self, program: impl AsRef<std::ffi::OsStr>) -> Command {
+        let mut cmd = Command::new(program);
+        cmd.current_dir(&self.workspace_root);
+        if let Some(path) = toolchain::shim_path(&self.workspace_root) {
+            cmd.env("PATH", path);
+        }
+        cmd
+    }
+
+    /// `python -m pip` inside the workspace virtualenv
+    fn pip(&self) -> Command {
+        let mut cmd = self.command(venv_python(&self.workspace_root));
+        cmd.args(["-m", "pip"]);
+        cmd
+    }
+
+    /// Check that the interpreter and manager are available
+    pub async fn check_environment(&self) -> Result<()> {
+        let manager = match self.manager_type {
+            PythonManagerType::Pip => None,
+            other => Some(other.command()),
+        };
+        if let Some(manager) = manager {
+            if !util::command_exists(manager).await {
+                return Err(anyhow!("{} is not installed or not in PATH", manager));
+            }
+        } else if !util::command_exists("python3").await && !util::command_exists("python").await {
+            return Err(anyhow!("Python is not installed or not in PATH"));
+        }
+        Ok(())
+    }
+
+    /// Create `.venv` for pip projects when it does not exist yet
+    pub async fn ensure_venv(&self) -> Result<()> {
+        if self.manager_type != PythonManagerType::Pip || venv_python(&self.workspace_root).exists() {
+            return Ok(());
+        }
+        let python = if util::command_exists("python3").await { "python3" } else { "python" };
+        println!("{}", style("🐍 Creating virtualenv in .venv").blue());
+        execute_mutation(self.command(python).args(["-m", "venv", ".venv"])).await
+            .context("Failed to create .venv")?;
+        Ok(())
+    }
+
+    fn requirements_path(&self, dev: bool) -> PathBuf {
+        self.workspace_root.join(if dev { "requirements-dev.txt" } else { "requirements.txt" })
+    }
+
+    async fn read_requirements(&self, dev: bool) -> Result<String> {
+        let path = self.requirements_path(dev);
+        if !path.exists() {
+            return Ok(String::new());
+        }
+        fs::read_to_string(&path).await
+            .with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    /// Requirements declared for this project
+    pub async fn declared(&self) -> Result<Vec<DeclaredRequirement>> {
+        if self.manager_type == PythonManagerType::Pip {
+            let mut deps = Vec::new();
+            for dev in [false, true] {
+                deps.extend(parse_requirements(&self.read_requirements(dev).await?).into_iter().map(|spec| {
+                    DeclaredRequirement { name: requirement_name(&spec).to_string(), spec, dev }
+                }));
+            }
+            return Ok(deps);
+        }
+        Ok(load_pyproject(&self.workspace_root).await?
+            .map(|p| pyproject_dependencies(&p))
+            .unwrap_or_default())
+    }
+
+    /// Install packages and record them in requirements.txt or pyproject.toml
+    pub async fn install(&self, packages: &[String], dev: bool) -> Result<()> {
+        self.check_environment().await?;
+        match self.manager_type {
+            PythonManagerType::Pip => {
+                self.ensure_venv().await?;
+                let mut cmd = self.pip();
+                cmd.arg("install");
+                if packages.is_empty() {
+                    // Install everything declared
+                    let files: Vec<PathBuf> = [false, true]
+                        .into_iter()
+                        .map(|dev| self.requirements_path(dev))
+                        .filter(|path| path.exists())
+                        .collect();
+                    if files.is_empty() {
+                        // pyproject-only pip projects install themselves in editable mode
+                        if !self.workspace_root.join("pyproject.toml").exists() {
+                            return Ok(());
+                        }
+                        cmd.args(["--editable", "."]);
+                    }
+                    for path in files {
+                        cmd.arg("-r").arg(path);
+                    }
+                } else {
+                    cmd.args(packages);
+                }
+                execute_mutation(&mut cmd).await.context("Failed to install Python packages")?;
+                if !packages.is_empty() {
+                    self.record_requirements(packages, dev).await?;
+                }
+                Ok(())
+            }
+            PythonManagerType::Uv => {
+                let mut cmd = self.command("uv");
+                if packages.is_empty() {
+                    cmd.arg("sync");
+                } else {
+                    cmd.arg("add");
+                    if dev {
+                        cmd.arg("--dev");
+                    }
+                    cmd.args(packages);
+                }
+                execute_mutation(&mut cmd).await.context("Failed to install Python packages")?;
+                Ok(())
+            }
+            PythonManagerType::Poetry => {
+                let mut cmd = self.command("poetry");
+                if packages.is_empty() {
+                    cmd.arg("install");
+                } else {
+                    cmd.arg("add");
+                    if dev {
+                        cmd.args(["--group", "dev"]);
+                    }
+                    cmd.args(packages);
+                }
+                execute_mutation(&mut cmd).await.context("Failed to install Python packages")?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Write installed packages to the requirements file, pinning bare names to the installed version
+    async fn record_requirements(&self, packages: &[String], dev: bool) -> Result<()> {
+        if util::is_dry_run() {
+            return Ok(());
+        }
+        let installed = self.list().await.unwrap_or_default();
+        let mut content = self.read_requirements(dev).await?;
+        for spec in packages {
+            let name = requirement_name(spec);
+            let pinned = if name == spec.trim() {
+                installed
+                    .iter()
+                    .find(|d| normalize_name(&d.name) == normalize_name(name))
+                    .map(|d| format!("{}=={}", name, d.version))
+                    .unwrap_or_else(|| spec.clone())
+            } else {
+                spec.clone()
+            };
+            content = upsert_requirement(&content, &pinned);
+        }
+        util::write_file(&self.requirements_path(dev), content).await
+    }
+
+    /// Move `==` pins in the requirements files to the versions now installed
+    async fn update_pins(&self, declared: &[DeclaredRequirement]) -> Result<()> {
+        if util::is_dry_run() {
+            return Ok(());
+        }
+        let installed = self.list().await.unwrap_or_default();
+        for dev in [false, true] {
+            let original = self.read_requirements(dev).await?;
+            let mut content = original.clone();
+            for requirement in declared.iter().filter(|d| d.dev == dev) {
+                let Some(old) = pinned_version(&requirement.spec) else { continue };
+                let current = installed.iter().find(|d| normalize_name(&d.name) == normalize_name(&requirement.name));
+                if let Some(current) = current.filter(|d| d.version != old) {
+                    content = upsert_requirement(&content, &repin(&requirement.spec, Some(&current.version)));
+                }
+            }
+            if content != original {
+                util::write_file(&self.requirements_path(dev), content).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Uninstall packages and drop them from the project files
+    pub async fn remove(&self, packages: &[String]) -> Result<()> {
+        self.check_environment().await?;
+        let mut cmd = match self.manager_type {
+            PythonManagerType::Pip => {
+                let mut cmd = self.pip();
+                cmd.args(["uninstall", "--yes"]);
+                cmd
+            }
+            PythonManagerType::Uv => {
+                let mut cmd = self.command("uv");
+                cmd.arg("remove");
+                cmd
+            }
+            PythonManagerType::Poetry => {
+                let mut cmd = self.command("poetry");
+                cmd.arg("remove");
+                cmd
+            }
+        };
+        cmd.args(packages);
+        execute_mutation(&mut cmd).await.context("Failed to remove Python packages")?;
+
+        if self.manager_type == PythonManagerType::Pip {
+            for dev in [false, true] {
+                let mut content = self.read_requirements(dev).await?;
+                let mut changed = false;
+                for package in packages {
+                    let (updated, removed) = remove_requirement(&content, requirement_name(package));
+                    content = updated;
+                    changed |= removed;
+                }
+                if changed {
+                    util::write_file(&self.requirements_path(dev), content).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Upgrade packages within their declared constraints; exact pins move to the new version
+    pub async fn update(&self, packages: &[String]) -> Result<()> {
+        self.check_environment().await?;
+        match self.manager_type {
+            PythonManagerType::Pip => {
+                let declared = self.declared().await?;
+                let declared_spec = |package: &str| {
+                    declared.iter().find(|d| normalize_name(&d.name) == normalize_name(package)).map(|d| d.spec.as_str())
+                };
+                // `==` pins would hold pip at the installed version, so upgrade without them
+                let targets: Vec<String> = if packages.is_empty() {
+                    declared.iter().map(|d| repin(&d.spec, None)).collect()
+                } else {
+                    packages
+                        .iter()
+                        .map(|p| match declared_spec(p) {
+                            Some(spec) if requirement_name(p) == p.trim() => repin(spec, None),
+                            _ => p.clone(),
+                        })
+                        .collect()
+                };
+                if targets.is_empty() {
+                    return Ok(());
+                }
+                execute_mutation(self.pip().args(["install", "--upgrade"]).args(&targets)).await
+                    .context("Failed to update Python packages")?;
+                self.update_pins(&declared).await?;
+            }
+            PythonManagerType::Uv => {
+                let mut cmd = self.command("uv");
+                cmd.arg("lock");
+                if packages.is_empty() {
+                    cmd.arg("--upgrade");
+                }
+                for package in packages {
+                    cmd.args(["--upgrade-package", package]);
+                }
+                execute_mutation(&mut cmd).await.context("Failed to update uv.lock")?;
+                execute_mutation(self.command("uv").arg("sync")).await
+                    .context("Failed to sync the uv environment")?;
+            }
+            PythonManagerType::Poetry => {
+                execute_mutation(self.command("poetry").arg("update").args(packages)).await
+                    .context("Failed to update Python packages")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Installed distributions in the project environment
+    pub async fn list(&self) -> Result<Vec<InstalledDistribution>> {
+        let mut cmd = match self.manager_type {
+            PythonManagerType::Pip => {
+                if !venv_python(&self.workspace_root).exists() {
+                    return Ok(Vec::new());
+                }
+                let mut cmd = self.pip();
+                cmd.arg("list");
+                cmd
+            }
+            PythonManagerType::Uv => {
+                let mut cmd = self.command("uv");
+                cmd.args(["pip", "list"]);
+                cmd
+            }
+            PythonManagerType::Poetry => {
+                let mut cmd = self.command("poetry");
+                cmd.args(["run", "python", "-m", "pip", "list"]);
+                cmd
+            }
+        };
+        cmd.args(["--format", "json"]);
+        let output = execute_command(&mut cmd).await.context("Failed to list Python packages")?;
+        parse_pip_list(&output.stdout)
+    }
+
+    /// Declared requirements that are not installed, plus `pip check` conflicts
+    pub async fn problems(&self) -> Result<Vec<String>> {
+        let installed = self.list().await?;
+        let mut problems: Vec<String> = self
+            .declared()
+            .await?
+            .iter()
+            .filter(|d| !installed.iter().any(|i| normalize_name(&i.name) == normalize_name(&d.name)))
+            .map(|d| format!("{}: missing (requires {})", d.name, d.spec))
+            .collect();
+
+        let mut check = match self.manager_type {
+            PythonManagerType::Pip if venv_python(&self.workspace_root).exists() => {
+                let mut cmd = self.pip();
+                cmd.arg("check");
+                cmd
+            }
+            PythonManagerType::Uv => {
+                let mut cmd = self.command("uv");
+                cmd.args(["pip", "check"]);
+                cmd
+            }
+            _ => return Ok(problems),
+        };
+        // `pip check` exits non-zero when it finds conflicts; its stdout lists them
+        if let Ok(output) = check.output() {
+            if !output.status.success() {
+                problems.extend(String::from_utf8_lossy(&output.stdout).lines().map(str::to_string));
+            }
+        }
+        Ok(problems)
+    }
+
+    /// Install everything declared (used by `rcm workspace sync`)
+    pub async fn sync(&self) -> Result<()> {
+        self.install(&[], false).await
+    }
+}
+
+/// Whether the workspace has a Python project
+pub fn has_python_project(workspace_root: &Path) -> bool {
+    ["pyproject.toml", "requirements.txt", "setup.py"]
+        .iter()
+        .any(|f| workspace_root.join(f).exists())
+}
+
+fn print_audit(report: &AuditReport) {
+    #[derive(Tabled)]
+    struct Row {
+        #[tabled(rename = "Package")]
+        package: String,
+        #[tabled(rename = "Version")]
+        version: String,
+        #[tabled(rename = "Advisory")]
+        advisory: String,
+        #[tabled(rename = "Aliases")]
+        aliases: String,
+        #[tabled(rename = "Title")]
+        title: String,
+    }
+    let rows: Vec<Row> = report
+        .unaccepted()
+        .map(|v| Row {
+            package: v.package.clone(),
+            version: v.version.clone().unwrap_or_default(),
+            advisory: v.advisory.clone(),
+            aliases: v.aliases.join(", "),
+            title: v.title.clone(),
+        })
+        .collect();
+    if !rows.is_empty() {
+        println!("{}", Table::new(rows));
+    }
+    for (vuln, _, expires) in &report.accepted {
+        println!("  ⏸️ {} {} accepted until {}", vuln.package, vuln.advisory, expires);
+    }
+}
+
+/// Handle Python commands
+pub async fn handle_command(workspace: &Workspace, cmd: PipCommands) -> Result<()> {
+    match cmd {
+        PipCommands::Install { packages, dev, manager } => {
+            for package in &packages {
+                util::validate_package_name(requirement_name(package))?;
+            }
+            let manager_type = resolve_manager_type(workspace.root(), manager.as_deref()).await?;
+            PythonManager::new(workspace.root(), manager_type).install(&packages, dev).await
+        }
+
+        PipCommands::Remove { packages, manager } => {
+            if packages.is_empty() {
+                return Err(anyhow!("No packages given"));
+            }
+            let manager_type = resolve_manager_type(workspace.root(), manager.as_deref()).await?;
+            PythonManager::new(workspace.root(), manager_type).remove(&packages).await
+        }
+
+        PipCommands::Update { packages, manager } => {
+            let manager_type = resolve_manager_type(workspace.root(), manager.as_deref()).await?;
+            PythonManager::new(workspace.root(), manager_type).update(&packages).await
+        }
+
+        PipCommands::List { format, manager } => {
+            let manager_type = resolve_manager_type(workspace.root(), manager.as_deref()).await?;
+            let installed = PythonManager::new(workspace.root(), manager_type).list().await?;
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&installed)?),
+                "table" => {
+                    if installed.is_empty() {
+                        println!("No packages installed.");
+                    } else {
+                        println!("{}", Table::new(&installed));
+                    }
+                }
+                _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+            }
+            Ok(())
+        }
+
+        PipCommands::Audit { format } => {
+            let findings = audit::scan(workspace, &["python".to_string()]).await?;
+            let exceptions = AuditExceptions::load(workspace.root()).await?;
+            let report = audit::classify(findings, &exceptions, chrono::Local::now().date_naive());
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+                "table" => print_audit(&report),
+                _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+            }
+            if report.failed() {
+                return Err(anyhow!(
+                    "{} vulnerable Python packages found ({} accepted)",
+                    report.unaccepted().count(),
+                    report.accepted.len()
+                ));
+            }
+            if format != "json" {
+                println!("✅ No known vulnerabilities in Python packages ({} accepted)", report.accepted.len());
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requirement_names() {
+        assert_eq!(requirement_name("uvicorn[standard]>=0.29"), "uvicorn");
+        assert_eq!(requirement_name("Django==5.0.1"), "Django");
+        assert_eq!(requirement_name("requests ; python_version < '3.8'"), "requests");
+        assert_eq!(normalize_name("Foo__Bar.baz"), "foo-bar-baz");
+    }
+
+    #[test]
+    fn test_requirements_file_editing() {
+        let content = "# web\nDjango==4.2\n-r base.txt\nrequests>=2.31  # http\n";
+        assert_eq!(parse_requirements(content), vec!["Django==4.2", "requests>=2.31"]);
+
+        let updated = upsert_requirement(content, "django==5.0.1");
+        assert_eq!(updated, "# web\ndjango==5.0.1\n-r base.txt\nrequests>=2.31  # http\n");
+        assert!(upsert_requirement(content, "httpx").ends_with("httpx\n"));
+
+        let (removed, found) = remove_requirement(content, "Requests");
+        assert!(found);
+        assert_eq!(removed, "# web\nDjango==4.2\n-r base.txt\n");
+        assert!(!remove_requirement(content, "flask").1);
+    }
+
+    #[test]
+    fn test_pins() {
+        assert_eq!(pinned_version("Django==4.2"), Some("4.2"));
+        assert_eq!(pinned_version("Django>=4.2"), None);
+        assert_eq!(pinned_version("Django==4.2.*"), None);
+        assert_eq!(pinned_version("Django===4.2"), None);
+        assert_eq!(repin("Django==4.2", None), "Django");
+        assert_eq!(repin("uvicorn[standard] == 0.29.0", Some("0.30.1")), "uvicorn[standard]==0.30.1");
+        assert_eq!(
+            repin("tomli==2.0.1; python_version < '3.11'", None),
+            "tomli ; python_version < '3.11'"
+        );
+        assert_eq!(repin("requests>=2.31", None), "requests>=2.31");
+    }
+
+    #[test]
+    fn test_pyproject_dependencies() {
+        let pyproject: toml::Value = toml::from_str(r#"
+            [project]
+            dependencies = ["fastapi>=0.110", "pydantic[email]"]
+
+            [dependency-groups]
+            dev = ["pytest>=8"]
+
+            [tool.poetry.dependencies]
+            python = "^3.11"
+            httpx = { version = "^0.27", extras = ["http2"] }
+        "#).unwrap();
+        let deps: Vec<(String, bool)> = pyproject_dependencies(&pyproject)
+            .into_iter()
+            .map(|d| (d.name, d.dev))
+            .collect();
+        assert_eq!(deps, vec![
+            ("fastapi".to_string(), false),
+            ("pydantic".to_string(), false),
+            ("pytest".to_string(), true),
+            ("httpx".to_string(), false),
+        ]);
+        assert_eq!(detect_manager(Path::new("/nonexistent"), Some(&pyproject)), PythonManagerType::Poetry);
+        assert_eq!(detect_manager(Path::new("/nonexistent"), None), PythonManagerType::Pip);
+
+        let list = parse_pip_list(r#"[{"name": "urllib3", "version": "2.2.1"}, {"name": "Django", "version": "5.0.1"}]"#).unwrap();
+        assert_eq!(list[0].name, "Django");
+    }
+}
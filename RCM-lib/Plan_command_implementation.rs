@@ -0,0 +1,38 @@
+//! Plan command implementation
+//!
+//! Shows the difference between the workspace manifest and the installed state
+
+use anyhow::{anyhow, Result};
+use console::style;
+use crate::planner;
+use crate::workspace::Workspace;
+
+/// Compute and print the plan
+pub async fn run(workspace: &Workspace, managers: Option<Vec<String>>, format: &str) -> Result<()> {
+    let target_managers = managers.unwrap_or_else(|| workspace.enabled_managers());
+
+    if target_managers.is_empty() {
+        return Err(anyhow!("No package managers enabled. Run 'rcm init' to configure managers."));
+    }
+
+    let plan = planner::compute_plan(workspace, &target_managers).await?;
+
+    match format {
+        "text" => {
+            if plan.changes.is_empty() {
+                println!("{}", style("✅ No changes. Workspace matches the manifest.").green().bold());
+            } else {
+                println!("{}", style("📋 Execution plan").cyan().bold());
+                plan.print_text();
+                if !plan.is_empty() {
+                    println!("Run {} to apply these changes", style("rcm apply").cyan());
+                }
+            }
+        }
+        "json" => println!("{}", serde_json::to_string_pretty(&plan)?),
+        "yaml" => print!("{}", plan.to_yaml()),
+        _ => return Err(anyhow!("Unsupported format: {}. Use text, json or yaml", format)),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,611 @@
+//! Planning engine for RCM
+//!
+//! Computes the difference between the workspace manifest and the installed state
+//! of each manager, and applies only that delta
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use tokio::fs;
+use crate::workspace::Workspace;
+use crate::commands::letcond::parse_loose_version;
+use crate::npm::{self, is_dist_tag, resolve_manager_type, NpmManager, NpmManagerType};
+use crate::isolation;
+use crate::platform;
+use crate::mirrors;
+use crate::ppm::ComposerManager;
+use crate::system::{SystemManager, SystemPackageManager};
+use crate::toolchain;
+use crate::util;
+use crate::version_picker::composer_satisfies;
+
+/// Kind of change required to reach the manifest state
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    /// Declared in the RCM manifest but missing from the native manifest
+    Install,
+    /// Installed version does not satisfy the declared requirement
+    Upgrade,
+    /// Declared natively but not installed (lockfile / node_modules / vendor out of date)
+    Sync,
+    /// Present natively but not tracked by RCM; reported only
+    Untracked,
+}
+
+/// A single planned change
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedChange {
+    pub manager: String,
+    pub name: String,
+    pub kind: ChangeKind,
+    pub desired: Option<String>,
+    pub current: Option<String>,
+    pub dev: bool,
+}
+
+impl PlannedChange {
+    fn symbol(&self) -> &'static str {
+        match self.kind {
+            ChangeKind::Install => "+",
+            ChangeKind::Upgrade => "~",
+            ChangeKind::Sync => "↻",
+            ChangeKind::Untracked => "?",
+        }
+    }
+
+    /// Whether `apply` acts on this change
+    pub fn is_actionable(&self) -> bool {
+        self.kind != ChangeKind::Untracked
+    }
+}
+
+/// Full plan across managers
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Plan {
+    pub changes: Vec<PlannedChange>,
+    /// Declared dependencies whose `platforms` exclude this machine, as `manager:name`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+}
+
+impl Plan {
+    pub fn actionable(&self) -> impl Iterator<Item = &PlannedChange> {
+        self.changes.iter().filter(|c| c.is_actionable())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actionable().next().is_none()
+    }
+
+    /// Print a colored diff
+    pub fn print_text(&self) {
+        let mut by_manager: BTreeMap<&str, Vec<&PlannedChange>> = BTreeMap::new();
+        for change in &self.changes {
+            by_manager.entry(change.manager.as_str()).or_default().push(change);
+        }
+
+        for (manager, changes) in by_manager {
+            println!("{}", style(format!("{}:", manager)).bold());
+            for change in changes {
+                let line = match change.kind {
+                    ChangeKind::Install => format!(
+                        "  {} {} {}",
+                        change.symbol(), change.name, change.desired.as_deref().unwrap_or("*")
+                    ),
+                    ChangeKind::Upgrade => format!(
+                        "  {} {} {} -> {}",
+                        change.symbol(), change.name,
+                        change.current.as_deref().unwrap_or("?"),
+                        change.desired.as_deref().unwrap_or("*")
+                    ),
+                    ChangeKind::Sync => format!(
+                        "  {} {} {} (declared, not installed)",
+                        change.symbol(), change.name, change.desired.as_deref().unwrap_or("*")
+                    ),
+                    ChangeKind::Untracked => format!(
+                        "  {} {} {} (not in RCM manifest)",
+                        change.symbol(), change.name, change.current.as_deref().unwrap_or("")
+                    ),
+                };
+
+                let styled = match change.kind {
+                    ChangeKind::Install => style(line).green(),
+                    ChangeKind::Upgrade => style(line).yellow(),
+                    ChangeKind::Sync => style(line).cyan(),
+                    ChangeKind::Untracked => style(line).dim(),
+                };
+                println!("{}", styled);
+            }
+        }
+
+        let count = |kind: ChangeKind| self.changes.iter().filter(|c| c.kind == kind).count();
+        println!(
+            "\nPlan: {} to install, {} to change, {} to sync, {} untracked",
+            count(ChangeKind::Install), count(ChangeKind::Upgrade),
+            count(ChangeKind::Sync), count(ChangeKind::Untracked)
+        );
+        if !self.skipped.is_empty() {
+            println!("{}", style(format!(
+                "⏭  {} not for {}: {}",
+                self.skipped.len(), platform::Target::current(), self.skipped.join(", ")
+            )).dim());
+        }
+    }
+
+    /// Render the plan as YAML
+    pub fn to_yaml(&self) -> String {
+        let mut out = String::from("changes:\n");
+        for change in &self.changes {
+            out.push_str(&format!("  - manager: {}\n", change.manager));
+            out.push_str(&format!("    name: \"{}\"\n", change.name));
+            out.push_str(&format!("    kind: {:?}\n", change.kind).to_lowercase());
+            out.push_str(&format!("    desired: {}\n", change.desired.as_deref().map(|v| format!("\"{}\"", v)).unwrap_or_else(|| "null".to_string())));
+            out.push_str(&format!("    current: {}\n", change.current.as_deref().map(|v| format!("\"{}\"", v)).unwrap_or_else(|| "null".to_string())));
+            out.push_str(&format!("    dev: {}\n", change.dev));
+        }
+        out
+    }
+}
+
+/// Declared dependency from the RCM manifest
+#[derive(Clone)]
+struct Desired {
+    version: String,
+    dev: bool,
+}
+
+/// Compute the plan for the given managers
+pub async fn compute_plan(workspace: &Workspace, managers: &[String]) -> Result<Plan> {
+    let mut desired: HashMap<String, BTreeMap<String, Desired>> = HashMap::new();
+    let mut skipped = Vec::new();
+    for (name, spec) in workspace.list_dependencies() {
+        if !platform::applies(&spec.platforms) {
+            skipped.push((spec.manager.clone(), name.clone()));
+            continue;
+        }
+        desired.entry(spec.manager.clone()).or_default().insert(
+            name.clone(),
+            Desired { version: spec.version.clone(), dev: spec.dev_only },
+        );
+    }
+
+    let mut plan = Plan::default();
+    let empty = BTreeMap::new();
+
+    for manager in managers {
+        let wanted = desired.get(manager).unwrap_or(&empty);
+        let changes = match manager.as_str() {
+            "cargo" => plan_cargo(workspace.root(), wanted).await?,
+            "npm" => plan_npm(workspace.root(), wanted).await?,
+            "composer" => plan_composer(workspace.root(), wanted).await?,
+            "system" => plan_system(wanted).await?,
+            _ => return Err(anyhow!("Unknown manager: {}", manager)),
+        };
+        plan.changes.extend(changes);
+    }
+
+    // Still declared in the native manifest, but not meant for this machine
+    plan.changes.retain(|c| c.kind != ChangeKind::Untracked || !skipped.iter().any(|(m, n)| *m == c.manager && *n == c.name));
+    plan.skipped = skipped.into_iter()
+        .filter(|(manager, _)| managers.contains(manager))
+        .map(|(manager, name)| format!("{}:{}", manager, name))
+        .collect();
+    Ok(plan)
+}
+
+/// Compare declared and installed state for one manager
+fn diff_manager(
+    manager: &str,
+    wanted: &BTreeMap<String, Desired>,
+    declared: &BTreeMap<String, String>,
+    installed: &HashMap<String, String>,
+) -> Vec<PlannedChange> {
+    let mut changes = Vec::new();
+
+    for (name, want) in wanted {
+        let change = |kind, current: Option<&String>| PlannedChange {
+            manager: manager.to_string(),
+            name: name.clone(),
+            kind,
+            desired: Some(want.version.clone()),
+            current: current.cloned(),
+            dev: want.dev,
+        };
+
+        match (declared.contains_key(name), installed.get(name)) {
+            (false, current) => changes.push(change(ChangeKind::Install, current)),
+            (true, None) => changes.push(change(ChangeKind::Sync, None)),
+            (true, Some(version)) if !version_satisfies(manager, &want.version, version) => {
+                changes.push(change(ChangeKind::Upgrade, Some(version)))
+            }
+            _ => {}
+        }
+    }
+
+    for (name, requirement) in declared {
+        if !wanted.contains_key(name) {
+            changes.push(PlannedChange {
+                manager: manager.to_string(),
+                name: name.clone(),
+                kind: ChangeKind::Untracked,
+                desired: None,
+                current: installed.get(name).cloned().or_else(|| Some(requirement.clone())),
+                dev: false,
+            });
+        }
+    }
+
+    changes
+}
+
+/// Check an installed version against a requirement in `manager`'s range syntax
+pub fn version_satisfies(manager: &str, requirement: &str, installed: &str) -> bool {
+    let requirement = requirement.trim();
+    if requirement.is_empty() || requirement == "*" || requirement == "latest" {
+        return true;
+    }
+
+    let installed = installed.trim().trim_start_matches('v');
+    let exact = || requirement.trim_start_matches(['=', 'v']) == installed;
+    let Some(version) = semver::Version::parse(installed).ok().or_else(|| parse_loose_version(installed)) else {
+        // Git refs and branches (`dev-main`) only match exactly
+        return exact();
+    };
+    let matched = match manager {
+        // A bare `1.2` in Cargo.toml already means `^1.2`
+        "cargo" => semver::VersionReq::parse(requirement).ok().map(|req| req.matches(&version)),
+        "npm" => npm::satisfies_engine(&exact_bare_versions(requirement), &version).ok(),
+        "composer" => composer_satisfies(&exact_bare_versions(requirement), &version),
+        // System packages are pinned to the version the package manager reports
+        _ => None,
+    };
+    matched.unwrap_or_else(exact)
+}
+
+/// Prefix bare versions with `=`: npm and Composer read `1.2.3` as exact and `1.2`
+/// as `1.2.x`, which is semver's `=`, while semver alone would read them as `^`
+fn exact_bare_versions(range: &str) -> String {
+    let mut out = String::new();
+    let mut after_operator = false;
+    for token in range.split_inclusive([' ', ',', '|']) {
+        let word = token.trim_end_matches([' ', ',', '|']);
+        if !word.is_empty() && !after_operator && word.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            out.push('=');
+        }
+        out.push_str(token);
+        if !word.is_empty() {
+            // `>= 1.2` splits the operator from its version
+            after_operator = word.chars().all(|c| matches!(c, '<' | '>' | '=' | '^' | '~' | '!'));
+        }
+    }
+    out
+}
+
+async fn read_json(path: &Path) -> Result<Option<serde_json::Value>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path).await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(Some(value))
+}
+
+fn json_string_map(value: Option<&serde_json::Value>) -> BTreeMap<String, String> {
+    value
+        .and_then(|v| v.as_object())
+        .map(|obj| {
+            obj.iter()
+                .map(|(k, v)| (k.clone(), v.as_str().unwrap_or("*").to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+async fn plan_cargo(root: &Path, wanted: &BTreeMap<String, Desired>) -> Result<Vec<PlannedChange>> {
+    let manifest_path = root.join("Cargo.toml");
+    if !manifest_path.exists() {
+        return Ok(diff_manager("cargo", wanted, &BTreeMap::new(), &HashMap::new()));
+    }
+
+    let manifest: toml::Value = toml::from_str(&fs::read_to_string(&manifest_path).await?)
+        .context("Failed to parse Cargo.toml")?;
+
+    let mut declared = BTreeMap::new();
+    for table in ["dependencies", "dev-dependencies", "build-dependencies"] {
+        if let Some(deps) = manifest.get(table).and_then(|d| d.as_table()) {
+            for (name, spec) in deps {
+                let requirement = match spec {
+                    toml::Value::String(v) => v.clone(),
+                    toml::Value::Table(t) => t.get("version").and_then(|v| v.as_str()).unwrap_or("*").to_string(),
+                    _ => "*".to_string(),
+                };
+                declared.insert(name.clone(), requirement);
+            }
+        }
+    }
+
+    let mut installed = HashMap::new();
+    let lock_path = root.join("Cargo.lock");
+    if lock_path.exists() {
+        let lock: toml::Value = toml::from_str(&fs::read_to_string(&lock_path).await?)
+            .context("Failed to parse Cargo.lock")?;
+        if let Some(packages) = lock.get("package").and_then(|p| p.as_array()) {
+            for pkg in packages {
+                if let (Some(name), Some(version)) = (
+                    pkg.get("name").and_then(|n| n.as_str()),
+                    pkg.get("version").and_then(|v| v.as_str()),
+                ) {
+                    installed.insert(name.to_string(), version.to_string());
+                }
+            }
+        }
+    }
+
+    Ok(diff_manager("cargo", wanted, &declared, &installed))
+}
+
+async fn plan_npm(root: &Path, wanted: &BTreeMap<String, Desired>) -> Result<Vec<PlannedChange>> {
+    let package_json = read_json(&root.join("package.json")).await?;
+
+    let mut declared = json_string_map(package_json.as_ref().and_then(|p| p.get("dependencies")));
+    declared.extend(json_string_map(package_json.as_ref().and_then(|p| p.get("devDependencies"))));
+
+    let mut installed = HashMap::new();
+    for name in declared.keys().chain(wanted.keys()) {
+        let manifest = root.join("node_modules").join(name).join("package.json");
+        if let Some(pkg) = read_json(&manifest).await? {
+            if let Some(version) = pkg.get("version").and_then(|v| v.as_str()) {
+                installed.insert(name.clone(), version.to_string());
+            }
+        }
+    }
+
+    // Channel dependencies (`next`, `beta`) compare against the version the
+    // dist-tag currently points at, but are still installed by channel name
+    let npm = NpmManager::new(root, NpmManagerType::Npm);
+    let mut resolved = wanted.clone();
+    let mut channels = HashSet::new();
+    for (name, want) in wanted.iter().filter(|(_, w)| w.version != "latest" && is_dist_tag(&w.version)) {
+        match npm.resolve_channel(name, &want.version).await {
+            Ok(version) => {
+                resolved.get_mut(name).unwrap().version = format!("={}", version);
+                channels.insert(name.clone());
+            }
+            Err(e) => tracing::warn!("Could not resolve channel for {}: {}", name, e),
+        }
+    }
+
+    let mut changes = diff_manager("npm", &resolved, &declared, &installed);
+    for change in &mut changes {
+        if channels.contains(&change.name) {
+            change.desired = wanted.get(&change.name).map(|w| w.version.clone());
+        }
+    }
+    Ok(changes)
+}
+
+async fn plan_composer(root: &Path, wanted: &BTreeMap<String, Desired>) -> Result<Vec<PlannedChange>> {
+    let composer_json = read_json(&root.join("composer.json")).await?;
+
+    let mut declared = json_string_map(composer_json.as_ref().and_then(|c| c.get("require")));
+    declared.extend(json_string_map(composer_json.as_ref().and_then(|c| c.get("require-dev"))));
+    // Platform requirements are not installable packages
+    declared.retain(|name, _| name.contains('/'));
+
+    let mut installed = HashMap::new();
+    if let Some(installed_json) = read_json(&root.join("vendor").join("composer").join("installed.json")).await? {
+        // Composer 2 wraps the list in {"packages": [...]}, Composer 1 writes a bare array
+        let packages = installed_json.get("packages").unwrap_or(&installed_json);
+        if let Some(list) = packages.as_array() {
+            for pkg in list {
+                if let (Some(name), Some(version)) = (
+                    pkg.get("name").and_then(|n| n.as_str()),
+                    pkg.get("version").and_then(|v| v.as_str()),
+                ) {
+                    installed.insert(name.to_string(), version.to_string());
+                }
+            }
+        }
+    }
+
+    Ok(diff_manager("composer", wanted, &declared, &installed))
+}
+
+async fn plan_system(wanted: &BTreeMap<String, Desired>) -> Result<Vec<PlannedChange>> {
+    if wanted.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let system = SystemPackageManager::detect().await?;
+    let mut installed = HashMap::new();
+    for name in wanted.keys() {
+        let mut cmd = system.installed_version_cmd(name);
+        if let Ok(output) = util::execute_command(&mut cmd).await {
+            if output.success {
+                if let Some(version) = system.parse_installed_version(name, &output.stdout) {
+                    installed.insert(name.clone(), version);
+                }
+            }
+        }
+    }
+
+    // System packages have no native manifest; every declared package counts as declared
+    let declared = wanted.iter().map(|(k, v)| (k.clone(), v.version.clone())).collect();
+    Ok(diff_manager("system", wanted, &declared, &installed))
+}
+
+/// Apply the actionable part of a plan
+pub async fn apply_plan(workspace: &Workspace, plan: &Plan) -> Result<()> {
+    let mut by_manager: BTreeMap<&str, Vec<&PlannedChange>> = BTreeMap::new();
+    for change in plan.actionable() {
+        by_manager.entry(change.manager.as_str()).or_default().push(change);
+    }
+
+    for (manager, changes) in by_manager {
+        println!("{}", style(format!("🔧 Applying {} change(s) for {}...", changes.len(), manager)).blue());
+
+        let needs_sync = changes.iter().any(|c| c.kind == ChangeKind::Sync);
+        let (prod, dev): (Vec<&PlannedChange>, Vec<&PlannedChange>) = changes
+            .iter()
+            .filter(|c| c.kind != ChangeKind::Sync)
+            .partition(|c| !c.dev);
+
+        match manager {
+            "cargo" => {
+                if needs_sync {
+                    run_in(workspace.root(), "cargo", &["fetch"]).await?;
+                }
+                for (group, is_dev) in [(prod, false), (dev, true)] {
+                    if group.is_empty() {
+                        continue;
+                    }
+                    let mut args = vec!["add".to_string()];
+                    args.extend(group.iter().map(|c| spec_with(c, "@")));
+                    if is_dev {
+                        args.push("--dev".to_string());
+                    }
+                    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                    run_in(workspace.root(), "cargo", &args).await?;
+                }
+            }
+            "npm" => {
+                let npm = NpmManager::new(workspace.root(), resolve_manager_type(workspace.root(), None).await?);
+                if needs_sync {
+                    npm.install(&[], false, false).await?;
+                }
+                for (group, is_dev) in [(prod, false), (dev, true)] {
+                    if !group.is_empty() {
+                        let packages: Vec<String> = group.iter().map(|c| spec_with(c, "@")).collect();
+                        npm.install(&packages, is_dev, false).await?;
+                    }
+                }
+            }
+            "composer" => {
+                let composer = ComposerManager::new(workspace.root());
+                if needs_sync {
+                    run_in(workspace.root(), "composer", &["install"]).await?;
+                }
+                for (group, is_dev) in [(prod, false), (dev, true)] {
+                    if !group.is_empty() {
+                        let packages: Vec<String> = group.iter().map(|c| spec_with(c, ":")).collect();
+                        composer.install(&packages, is_dev, false, true).await?;
+                    }
+                }
+            }
+            "system" => {
+                let system = SystemManager::new(workspace.root()).await?;
+                let packages: Vec<String> = changes.iter().map(|c| c.name.clone()).collect();
+                system.install(&packages, false, true).await?;
+            }
+            _ => return Err(anyhow!("Unknown manager: {}", manager)),
+        }
+
+        println!("{}", style(format!("✅ {} up to date", manager)).green());
+    }
+
+    Ok(())
+}
+
+fn spec_with(change: &PlannedChange, separator: &str) -> String {
+    match change.desired.as_deref() {
+        Some(version) if version != "latest" && version != "*" => format!("{}{}{}", change.name, separator, version),
+        _ => change.name.clone(),
+    }
+}
+
+async fn run_in(root: &Path, program: &str, args: &[&str]) -> Result<()> {
+    let mut cmd = tokio::process::Command::new(program);
+    cmd.current_dir(root).args(args);
+    if let Some(path) = toolchain::shim_path(root) {
+        cmd.env("PATH", path);
+    }
+    cmd.envs(isolation::env_vars(root));
+    cmd.envs(mirrors::env_vars());
+    if program == "cargo" {
+        cmd.args(mirrors::cargo_args());
+    }
+    if util::skip_in_dry_run(cmd.as_std()) {
+        return Ok(());
+    }
+
+    let output = cmd
+        .output()
+        .await
+        .with_context(|| format!("Failed to execute {}", program))?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_satisfies() {
+        assert!(version_satisfies("cargo", "^1.2", "1.4.0"));
+        assert!(!version_satisfies("cargo", "^1.2", "2.0.0"));
+        assert!(version_satisfies("cargo", "1.0", "1.4.0"));
+        assert!(!version_satisfies("cargo", "=1.0.2", "1.0.3"));
+        assert!(version_satisfies("npm", "latest", "0.0.1"));
+        assert!(version_satisfies("composer", "^5.4|^6.0", "v6.1.0"));
+        assert!(version_satisfies("npm", "^16 || ^18", "18.2.0"));
+    }
+
+    #[test]
+    fn test_version_satisfies_per_manager() {
+        // npm: bare versions are exact, partial ones are x-ranges
+        assert!(!version_satisfies("npm", "1.2.3", "1.2.4"));
+        assert!(version_satisfies("npm", "1.2", "1.2.9"));
+        assert!(!version_satisfies("npm", "1.2", "1.3.0"));
+        assert!(version_satisfies("npm", ">=1.2.0 <2.0.0", "1.9.0"));
+        assert!(version_satisfies("npm", ">= 18", "20.1.0"));
+        assert!(version_satisfies("npm", "~1.2.0", "1.2.9"));
+
+        // Composer: `~5.4` allows minors, `5.4.*` wildcards, spaces AND
+        assert!(version_satisfies("composer", "~5.4", "5.9.0"));
+        assert!(version_satisfies("composer", "5.4.*", "5.4.3"));
+        assert!(version_satisfies("composer", ">=7.4 <8.0", "7.4.33"));
+        assert!(!version_satisfies("composer", "1.2.3", "1.2.4"));
+        assert!(version_satisfies("composer", "dev-main", "dev-main"));
+
+        // System packages compare as reported
+        assert!(version_satisfies("system", "1:2.39.2-1ubuntu1", "1:2.39.2-1ubuntu1"));
+        assert!(!version_satisfies("system", "1.2", "1.3"));
+    }
+
+    #[test]
+    fn test_diff_manager() {
+        let mut wanted = BTreeMap::new();
+        wanted.insert("serde".to_string(), Desired { version: "1.0".to_string(), dev: false });
+        wanted.insert("tokio".to_string(), Desired { version: "1".to_string(), dev: false });
+        wanted.insert("regex".to_string(), Desired { version: "1".to_string(), dev: false });
+
+        let declared = BTreeMap::from([
+            ("serde".to_string(), "1.0".to_string()),
+            ("tokio".to_string(), "1".to_string()),
+            ("log".to_string(), "0.4".to_string()),
+        ]);
+        let installed = HashMap::from([("serde".to_string(), "0.9.0".to_string())]);
+
+        let changes = diff_manager("cargo", &wanted, &declared, &installed);
+        let kind_of = |name: &str| changes.iter().find(|c| c.name == name).map(|c| c.kind.clone());
+
+        assert_eq!(kind_of("regex"), Some(ChangeKind::Install));
+        assert_eq!(kind_of("serde"), Some(ChangeKind::Upgrade));
+        assert_eq!(kind_of("tokio"), Some(ChangeKind::Sync));
+        assert_eq!(kind_of("log"), Some(ChangeKind::Untracked));
+    }
+}
@@ -0,0 +1,174 @@
+//! Platform conditions for workspace dependencies
+//!
+//! A dependency's `platforms` list limits where it applies. Each entry names an
+//! OS (`linux`, `macos`, `windows`, `freebsd`), an architecture (`x86_64`,
+//! `aarch64`) or both (`linux-aarch64`); a leading `!` excludes instead. An
+//! empty list applies everywhere. `ensure` and `plan` skip dependencies that
+//! don't apply to the machine they run on.
+//!
+//! Native manifests are shared by every machine, so a limited dependency must
+//! not land in one unconditionally. Cargo records it as a target-specific
+//! dependency (`[target.'cfg(...)'.dependencies]`); npm and system packages are
+//! kept only in the RCM manifest and `ensure` installs them where they apply.
+//! Composer and Bundler have no per-platform install, so they refuse `--platform`.
+
+/// Managers that can hold a dependency limited to some platforms
+pub const SCOPED_MANAGERS: &[&str] = &["cargo", "npm", "system"];
+
+use anyhow::{anyhow, Result};
+
+pub const OSES: &[&str] = &["linux", "macos", "windows", "freebsd"];
+pub const ARCHES: &[&str] = &["x86_64", "aarch64", "x86", "arm", "riscv64"];
+
+/// Canonical spelling of an OS or architecture name
+fn normalize(name: &str) -> String {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "darwin" | "osx" | "mac" => "macos".to_string(),
+        "win" | "win32" => "windows".to_string(),
+        "amd64" | "x64" => "x86_64".to_string(),
+        "arm64" => "aarch64".to_string(),
+        "i386" | "i686" => "x86".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// An OS and architecture pair
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub os: String,
+    pub arch: String,
+}
+
+impl Target {
+    pub fn current() -> Self {
+        Self { os: std::env::consts::OS.to_string(), arch: std::env::consts::ARCH.to_string() }
+    }
+
+    fn matches(&self, term: &str) -> bool {
+        match term.split_once('-') {
+            Some((os, arch)) => self.os == normalize(os) && self.arch == normalize(arch),
+            None => {
+                let term = normalize(term);
+                self.os == term || self.arch == term
+            }
+        }
+    }
+
+    /// Whether a dependency declared for `platforms` applies to this target
+    pub fn allows(&self, platforms: &[String]) -> bool {
+        let (excluded, included): (Vec<&String>, Vec<&String>) = platforms.iter().partition(|p| p.trim().starts_with('!'));
+        if excluded.iter().any(|p| self.matches(p.trim().trim_start_matches('!'))) {
+            return false;
+        }
+        included.is_empty() || included.iter().any(|p| self.matches(p))
+    }
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.os, self.arch)
+    }
+}
+
+/// Whether a dependency declared for `platforms` applies to this machine
+pub fn applies(platforms: &[String]) -> bool {
+    Target::current().allows(platforms)
+}
+
+/// Rust `cfg(...)` predicate for one platform term
+fn cfg_term(term: &str) -> String {
+    let os = |os: &str| format!("target_os = \"{}\"", os);
+    let arch = |arch: &str| format!("target_arch = \"{}\"", arch);
+    match term.split_once('-') {
+        Some((o, a)) => format!("all({}, {})", os(o), arch(a)),
+        None if OSES.contains(&term) => os(term),
+        None => arch(term),
+    }
+}
+
+/// A Cargo `--target` expression matching `platforms`, as parsed by [`parse_list`]
+pub fn cargo_cfg(platforms: &[String]) -> Option<String> {
+    let (excluded, included): (Vec<&String>, Vec<&String>) = platforms.iter().partition(|p| p.starts_with('!'));
+    let mut parts = Vec::new();
+    match included.as_slice() {
+        [] => {}
+        [only] => parts.push(cfg_term(only)),
+        many => parts.push(format!("any({})", many.iter().map(|p| cfg_term(p)).collect::<Vec<_>>().join(", "))),
+    }
+    parts.extend(excluded.iter().map(|p| format!("not({})", cfg_term(&p[1..]))));
+    match parts.len() {
+        0 => None,
+        1 => Some(format!("cfg({})", parts[0])),
+        _ => Some(format!("cfg(all({}))", parts.join(", "))),
+    }
+}
+
+/// Parse `--platform linux,macos-aarch64,!windows`
+pub fn parse_list(arg: &str) -> Result<Vec<String>> {
+    let mut platforms = Vec::new();
+    for entry in arg.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (negated, term) = match entry.strip_prefix('!') {
+            Some(term) => ("!", term),
+            None => ("", entry),
+        };
+        let canonical = match term.split_once('-') {
+            Some((os, arch)) => {
+                let (os, arch) = (normalize(os), normalize(arch));
+                (OSES.contains(&os.as_str()) && ARCHES.contains(&arch.as_str())).then(|| format!("{}-{}", os, arch))
+            }
+            None => {
+                let term = normalize(term);
+                (OSES.contains(&term.as_str()) || ARCHES.contains(&term.as_str())).then_some(term)
+            }
+        };
+        let canonical = canonical.ok_or_else(|| anyhow!(
+            "Unknown platform '{}'. Use an OS ({}), an architecture ({}) or os-arch",
+            entry, OSES.join(", "), ARCHES.join(", ")
+        ))?;
+        platforms.push(format!("{}{}", negated, canonical));
+    }
+    Ok(platforms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_allows() {
+        let mac = Target { os: "macos".to_string(), arch: "aarch64".to_string() };
+        assert!(mac.allows(&[]));
+        assert!(mac.allows(&list(&["linux", "macos"])));
+        assert!(mac.allows(&list(&["darwin-arm64"])));
+        assert!(!mac.allows(&list(&["linux"])));
+        assert!(!mac.allows(&list(&["macos-x86_64"])));
+        assert!(!mac.allows(&list(&["!macos"])));
+        assert!(mac.allows(&list(&["!windows"])));
+        assert!(!mac.allows(&list(&["aarch64", "!macos"])));
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(parse_list("linux, osx-arm64,!win").unwrap(), list(&["linux", "macos-aarch64", "!windows"]));
+        assert!(parse_list("beos").is_err());
+        assert!(parse_list("linux-sparc").is_err());
+    }
+
+    #[test]
+    fn test_cargo_cfg() {
+        assert_eq!(cargo_cfg(&[]), None);
+        assert_eq!(cargo_cfg(&list(&["linux"])).unwrap(), r#"cfg(target_os = "linux")"#);
+        assert_eq!(
+            cargo_cfg(&list(&["linux", "macos-aarch64"])).unwrap(),
+            r#"cfg(any(target_os = "linux", all(target_os = "macos", target_arch = "aarch64")))"#
+        );
+        assert_eq!(
+            cargo_cfg(&list(&["x86_64", "!windows"])).unwrap(),
+            r#"cfg(all(target_arch = "x86_64", not(target_os = "windows")))"#
+        );
+    }
+}
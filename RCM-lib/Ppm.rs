@@ -6,12 +6,21 @@ use anyhow::{anyhow, Context, Result};
 use clap::Subcommand;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use tabled::{Table, Tabled};
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use crate::audit::{self, Vulnerability};
+use crate::audit_exceptions::AuditExceptions;
+use crate::commands::letcond::parse_loose_version;
+use crate::isolation;
+use crate::mirrors;
 use crate::workspace::Workspace;
-use crate::util::{self, execute_command, validate_package_name};
+use crate::toolchain::{self, ToolPin, ToolchainPins};
+use crate::util::{self, execute_command, execute_mutation, validate_package_name};
 
 #[derive(Subcommand)]
 pub enum PpmCommands {
@@ -61,7 +70,7 @@ pub enum PpmCommands {
         /// Show only installed packages
         #[arg(long)]
         installed: bool,
-        /// Show platform packages
+        /// Show platform requirements (php, ext-*)
         #[arg(long)]
         platform: bool,
         /// Output format (json, table)
@@ -105,8 +114,8 @@ pub enum PpmCommands {
     
     /// Check for security vulnerabilities
     Audit {
-        /// Output format (text, json)
-        #[arg(long, default_value = "text")]
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
         format: String,
     },
     
@@ -142,6 +151,49 @@ pub enum PpmCommands {
         #[arg(long)]
         stability: Option<String>,
     },
+    
+    /// Manage Composer repositories (Satis, Private Packagist, VCS)
+    Repo {
+        #[command(subcommand)]
+        cmd: PpmRepoCommands,
+    },
+    
+    /// Pin the PHP version used by php/composer in this workspace
+    UsePhp {
+        /// PHP version (e.g. 8.3)
+        version: String,
+        /// Install the version if it cannot be found
+        #[arg(long)]
+        install: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PpmRepoCommands {
+    /// Add a repository to composer.json (credentials go to auth.json)
+    Add {
+        /// Repository URL
+        url: String,
+        /// Repository type (composer, vcs, git, path)
+        #[arg(long = "type", default_value = "composer")]
+        repo_type: String,
+        /// Keychain secret holding the token or password (see `rcm secret set`)
+        #[arg(long)]
+        auth: Option<String>,
+        /// Username for HTTP basic auth (bearer token auth when omitted)
+        #[arg(long)]
+        username: Option<String>,
+        /// Skip checking that the repository responds
+        #[arg(long)]
+        no_verify: bool,
+    },
+    /// List configured repositories
+    List,
+    /// Remove a repository and its credentials
+    Remove {
+        /// Repository URL
+        url: String,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -196,21 +248,25 @@ pub struct ComposerLock {
     #[serde(rename = "content-hash")]
     pub content_hash: String,
     pub packages: Vec<ComposerPackage>,
-    #[serde(rename = "packages-dev")]
+    #[serde(rename = "packages-dev", default)]
     pub packages_dev: Vec<ComposerPackage>,
+    #[serde(default)]
     pub aliases: Vec<serde_json::Value>,
-    #[serde(rename = "minimum-stability")]
+    #[serde(rename = "minimum-stability", default)]
     pub minimum_stability: String,
-    #[serde(rename = "stability-flags")]
+    #[serde(rename = "stability-flags", default, deserialize_with = "map_or_empty_list")]
     pub stability_flags: HashMap<String, i32>,
-    #[serde(rename = "prefer-stable")]
+    #[serde(rename = "prefer-stable", default)]
     pub prefer_stable: bool,
-    #[serde(rename = "prefer-lowest")]
+    #[serde(rename = "prefer-lowest", default)]
     pub prefer_lowest: bool,
+    // Composer writes `[]` instead of `{}` for empty maps
+    #[serde(default, deserialize_with = "map_or_empty_list")]
     pub platform: HashMap<String, String>,
-    #[serde(rename = "platform-dev")]
+    #[serde(rename = "platform-dev", default, deserialize_with = "map_or_empty_list")]
     pub platform_dev: HashMap<String, String>,
-    #[serde(rename = "plugin-api-version")]
+    // Only written by Composer 2
+    #[serde(rename = "plugin-api-version", default)]
     pub plugin_api_version: String,
 }
 
@@ -251,6 +307,81 @@ pub struct ComposerDist {
     pub shasum: Option<String>,
 }
 
+fn map_or_empty_list<'de, D, V>(deserializer: D) -> std::result::Result<HashMap<String, V>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    V: serde::de::DeserializeOwned,
+{
+    match Value::deserialize(deserializer)? {
+        map @ Value::Object(_) => serde_json::from_value(map).map_err(serde::de::Error::custom),
+        _ => Ok(HashMap::new()),
+    }
+}
+
+/// A package pinned in composer.lock, or a platform requirement
+#[derive(Debug, Clone, Serialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// prod, dev, platform or platform-dev
+    pub group: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package_type: Option<String>,
+    pub license: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+    pub require: BTreeMap<String, String>,
+}
+
+#[derive(Tabled)]
+struct LockedRow {
+    #[tabled(rename = "Package")]
+    name: String,
+    #[tabled(rename = "Version")]
+    version: String,
+    #[tabled(rename = "Group")]
+    group: String,
+    #[tabled(rename = "License")]
+    license: String,
+    #[tabled(rename = "Description")]
+    description: String,
+}
+
+#[derive(Tabled)]
+struct FieldRow {
+    #[tabled(rename = "Field")]
+    field: String,
+    #[tabled(rename = "Value")]
+    value: String,
+}
+
+#[derive(Tabled)]
+struct AdvisoryRow {
+    #[tabled(rename = "Severity")]
+    severity: String,
+    #[tabled(rename = "Package")]
+    package: String,
+    #[tabled(rename = "Version")]
+    version: String,
+    #[tabled(rename = "Advisory")]
+    advisory: String,
+    #[tabled(rename = "CVE")]
+    cve: String,
+    #[tabled(rename = "Title")]
+    title: String,
+}
+
+/// Packagist security advisories endpoint
+const PACKAGIST_ADVISORIES_URL: &str = "https://packagist.org/api/security-advisories/";
+
 #[derive(Debug)]
 pub struct ComposerManager {
     workspace_root: PathBuf,
@@ -273,6 +404,19 @@ impl ComposerManager {
         }
     }
     
+    /// Command running in the workspace with pinned toolchain shims on PATH
+    /// and, when the workspace is isolated, its own COMPOSER_HOME
+    fn command(&self, program: &str) -> Command {
+        let mut cmd = Command::new(program);
+        cmd.current_dir(&self.workspace_root);
+        if let Some(path) = toolchain::shim_path(&self.workspace_root) {
+            cmd.env("PATH", path);
+        }
+        cmd.envs(isolation::env_vars(&self.workspace_root));
+        cmd.envs(mirrors::env_vars());
+        cmd
+    }
+    
     /// Check if PHP and Composer are available
     pub async fn check_environment(&self) -> Result<()> {
         // Check PHP
@@ -286,7 +430,7 @@ impl ComposerManager {
         }
         
         // Check PHP version
-        let output = Command::new("php")
+        let output = self.command("php")
             .args(&["-v"])
             .output()
             .await
@@ -304,6 +448,22 @@ impl ComposerManager {
         Ok(())
     }
     
+    /// Parse composer.lock without invoking composer
+    pub async fn load_lock(&self) -> Result<ComposerLock> {
+        if !self.composer_lock_path.exists() {
+            return Err(anyhow!("composer.lock not found. Run 'rcm ppm install' to create it."));
+        }
+        let content = fs::read_to_string(&self.composer_lock_path).await
+            .context("Failed to read composer.lock")?;
+        serde_json::from_str(&content)
+            .context("Failed to parse composer.lock")
+    }
+    
+    /// Whether a locked package is present in vendor/
+    pub fn is_installed(&self, name: &str) -> bool {
+        self.vendor_path.join(name).is_dir()
+    }
+    
     /// Load composer.json
     pub async fn load_composer_json(&self) -> Result<ComposerJson> {
         if !self.composer_json_path.exists() {
@@ -339,7 +499,7 @@ impl ComposerManager {
         let content = serde_json::to_string_pretty(composer_json)
             .context("Failed to serialize composer.json")?;
         
-        fs::write(&self.composer_json_path, content).await
+        util::write_file(&self.composer_json_path, content).await
             .context("Failed to write composer.json")
     }
     
@@ -347,8 +507,7 @@ impl ComposerManager {
     pub async fn install(&self, packages: &[String], dev: bool, global: bool, optimize: bool) -> Result<()> {
         self.check_environment().await?;
         
-        let mut cmd = Command::new("composer");
-        cmd.current_dir(&self.workspace_root);
+        let mut cmd = self.command("composer");
         
         if global {
             cmd.arg("global");
@@ -366,7 +525,7 @@ impl ComposerManager {
         
         cmd.args(packages);
         
-        execute_command(&mut cmd).await
+        execute_mutation(&mut cmd).await
             .context("Failed to install composer packages")
     }
     
@@ -374,8 +533,7 @@ impl ComposerManager {
     pub async fn remove(&self, packages: &[String], dev: bool, optimize: bool) -> Result<()> {
         self.check_environment().await?;
         
-        let mut cmd = Command::new("composer");
-        cmd.current_dir(&self.workspace_root);
+        let mut cmd = self.command("composer");
         cmd.arg("remove");
         
         if dev {
@@ -388,7 +546,7 @@ impl ComposerManager {
         
         cmd.args(packages);
         
-        execute_command(&mut cmd).await
+        execute_mutation(&mut cmd).await
             .context("Failed to remove composer packages")
     }
     
@@ -396,8 +554,7 @@ impl ComposerManager {
     pub async fn update(&self, packages: &[String], with_dependencies: bool, optimize: bool) -> Result<()> {
         self.check_environment().await?;
         
-        let mut cmd = Command::new("composer");
-        cmd.current_dir(&self.workspace_root);
+        let mut cmd = self.command("composer");
         cmd.arg("update");
         
         if with_dependencies {
@@ -412,7 +569,7 @@ impl ComposerManager {
             cmd.args(packages);
         }
         
-        execute_command(&mut cmd).await
+        execute_mutation(&mut cmd).await
             .context("Failed to update composer packages")
     }
     
@@ -420,8 +577,7 @@ impl ComposerManager {
     pub async fn run_script(&self, script: &str, args: &[String]) -> Result<()> {
         self.check_environment().await?;
         
-        let mut cmd = Command::new("composer");
-        cmd.current_dir(&self.workspace_root);
+        let mut cmd = self.command("composer");
         cmd.arg("run-script");
         cmd.arg(script);
         
@@ -430,7 +586,7 @@ impl ComposerManager {
             cmd.args(args);
         }
         
-        execute_command(&mut cmd).await
+        execute_mutation(&mut cmd).await
             .context("Failed to run composer script")
     }
     
@@ -438,8 +594,7 @@ impl ComposerManager {
     pub async fn validate(&self, strict: bool) -> Result<()> {
         self.check_environment().await?;
         
-        let mut cmd = Command::new("composer");
-        cmd.current_dir(&self.workspace_root);
+        let mut cmd = self.command("composer");
         cmd.arg("validate");
         
         if strict {
@@ -454,8 +609,7 @@ impl ComposerManager {
     pub async fn dump_autoload(&self, optimize: bool, apcu: bool, authoritative: bool) -> Result<()> {
         self.check_environment().await?;
         
-        let mut cmd = Command::new("composer");
-        cmd.current_dir(&self.workspace_root);
+        let mut cmd = self.command("composer");
         cmd.arg("dump-autoload");
         
         if optimize {
@@ -470,7 +624,7 @@ impl ComposerManager {
             cmd.arg("--classmap-authoritative");
         }
         
-        execute_command(&mut cmd).await
+        execute_mutation(&mut cmd).await
             .context("Failed to generate autoloader")
     }
     
@@ -478,8 +632,7 @@ impl ComposerManager {
     pub async fn search(&self, terms: &[String], only_name: bool) -> Result<()> {
         self.check_environment().await?;
         
-        let mut cmd = Command::new("composer");
-        cmd.current_dir(&self.workspace_root);
+        let mut cmd = self.command("composer");
         cmd.arg("search");
         
         if only_name {
@@ -506,10 +659,180 @@ impl ComposerManager {
             cmd.arg(stability);
         }
         
-        execute_command(&mut cmd).await
+        execute_mutation(&mut cmd).await
             .context("Failed to create composer project")
     }
     
+    /// Host part of a repository URL, as used for auth.json keys
+    fn repository_host(url: &str) -> Result<String> {
+        url::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| match u.port() {
+                Some(port) => format!("{}:{}", h, port),
+                None => h.to_string(),
+            }))
+            .ok_or_else(|| anyhow!("Invalid repository URL: {}", url))
+    }
+    
+    async fn load_json_value(path: &Path) -> Result<serde_json::Value> {
+        if !path.exists() {
+            return Ok(serde_json::json!({}));
+        }
+        let content = fs::read_to_string(path).await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+    
+    /// Write auth.json readable only by the owner and keep it out of git
+    async fn save_auth_json(&self, auth: &serde_json::Value) -> Result<()> {
+        let path = self.workspace_root.join("auth.json");
+        if util::is_dry_run() {
+            // Never print credentials, even in dry-run output
+            println!("[dry-run] write credentials to {}", path.display());
+            return Ok(());
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // Create the file owner-only so the credentials are never briefly world-readable
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&path).await
+            .context("Failed to write auth.json")?;
+        
+        #[cfg(unix)]
+        {
+            // An existing auth.json keeps its mode on open, so restrict it before writing
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).await
+                .context("Failed to restrict auth.json permissions")?;
+        }
+        
+        file.write_all(serde_json::to_string_pretty(auth)?.as_bytes()).await
+            .context("Failed to write auth.json")?;
+        
+        let gitignore = self.workspace_root.join(".gitignore");
+        let ignored = fs::read_to_string(&gitignore).await.unwrap_or_default();
+        if !ignored.lines().any(|l| matches!(l.trim(), "auth.json" | "/auth.json")) {
+            let separator = if ignored.is_empty() || ignored.ends_with('\n') { "" } else { "\n" };
+            util::write_file(&gitignore, format!("{}{}/auth.json\n", ignored, separator)).await
+                .context("Failed to update .gitignore")?;
+        }
+        Ok(())
+    }
+    
+    /// Check that a Composer repository serves packages.json
+    async fn verify_repository(url: &str, username: Option<&str>, secret: Option<&str>) -> Result<()> {
+        let packages_url = format!("{}/packages.json", url.trim_end_matches('/'));
+        let mut request = reqwest::Client::new().get(&packages_url);
+        request = match (username, secret) {
+            (Some(user), Some(password)) => request.basic_auth(user, Some(password)),
+            (None, Some(token)) => request.bearer_auth(token),
+            _ => request,
+        };
+        
+        let response = request.send().await
+            .with_context(|| format!("Failed to reach {}", packages_url))?;
+        if !response.status().is_success() {
+            return Err(anyhow!("{} returned {}", packages_url, response.status()));
+        }
+        
+        let body: serde_json::Value = response.json().await
+            .with_context(|| format!("{} is not a Composer repository", url))?;
+        if !["packages", "metadata-url", "providers-url", "includes", "provider-includes"]
+            .iter()
+            .any(|key| body.get(key).is_some())
+        {
+            return Err(anyhow!("{} is not a Composer repository (no packages metadata)", url));
+        }
+        Ok(())
+    }
+    
+    /// Add a repository to composer.json and its credentials to auth.json
+    pub async fn add_repository(&self, url: &str, repo_type: &str, auth: Option<&str>, username: Option<&str>, verify: bool) -> Result<()> {
+        if !matches!(repo_type, "composer" | "vcs" | "git" | "path") {
+            return Err(anyhow!("Unsupported repository type: {}. Use composer, vcs, git or path", repo_type));
+        }
+        
+        // Resolve the credential up front; the value is only ever written to auth.json
+        let secret = match auth {
+            Some(name) => Some(crate::secrets::get_secret(name).await?),
+            None => None,
+        };
+        
+        if verify && repo_type == "composer" {
+            Self::verify_repository(url, username, secret.as_deref()).await?;
+        }
+        
+        let mut composer = Self::load_json_value(&self.composer_json_path).await?;
+        let repositories = composer
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("composer.json must contain an object"))?
+            .entry("repositories")
+            .or_insert_with(|| serde_json::json!([]));
+        let list = repositories
+            .as_array_mut()
+            .ok_or_else(|| anyhow!("Only list-style \"repositories\" in composer.json are supported"))?;
+        
+        list.retain(|r| r.get("url").and_then(|u| u.as_str()) != Some(url));
+        list.push(serde_json::json!({ "type": repo_type, "url": url }));
+        
+        util::write_file(&self.composer_json_path, serde_json::to_string_pretty(&composer)?).await
+            .context("Failed to write composer.json")?;
+        
+        if let Some(secret) = secret {
+            let host = Self::repository_host(url)?;
+            let mut auth_json = Self::load_json_value(&self.workspace_root.join("auth.json")).await?;
+            let (section, value) = match username {
+                Some(user) => ("http-basic", serde_json::json!({ "username": user, "password": secret })),
+                None => ("bearer", serde_json::Value::String(secret)),
+            };
+            auth_json[section][&host] = value;
+            self.save_auth_json(&auth_json).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Repositories listed in composer.json
+    pub async fn list_repositories(&self) -> Result<Vec<serde_json::Value>> {
+        let composer = Self::load_json_value(&self.composer_json_path).await?;
+        Ok(composer.get("repositories").and_then(|r| r.as_array()).cloned().unwrap_or_default())
+    }
+    
+    /// Remove a repository and any credentials for its host
+    pub async fn remove_repository(&self, url: &str) -> Result<()> {
+        let mut composer = Self::load_json_value(&self.composer_json_path).await?;
+        let list = composer
+            .get_mut("repositories")
+            .and_then(|r| r.as_array_mut())
+            .ok_or_else(|| anyhow!("No repositories configured in composer.json"))?;
+        
+        let before = list.len();
+        list.retain(|r| r.get("url").and_then(|u| u.as_str()) != Some(url));
+        if list.len() == before {
+            return Err(anyhow!("Repository not found: {}", url));
+        }
+        
+        util::write_file(&self.composer_json_path, serde_json::to_string_pretty(&composer)?).await
+            .context("Failed to write composer.json")?;
+        
+        let auth_path = self.workspace_root.join("auth.json");
+        if auth_path.exists() {
+            if let Ok(host) = Self::repository_host(url) {
+                let mut auth_json = Self::load_json_value(&auth_path).await?;
+                for section in ["http-basic", "bearer"] {
+                    if let Some(entries) = auth_json.get_mut(section).and_then(|s| s.as_object_mut()) {
+                        entries.remove(&host);
+                    }
+                }
+                self.save_auth_json(&auth_json).await?;
+            }
+        }
+        
+        Ok(())
+    }
+    
     /// Validate PHP package name
     pub fn validate_package_name(name: &str) -> Result<()> {
         // Composer package name validation (vendor/name format)
@@ -534,6 +857,285 @@ impl ComposerManager {
     }
 }
 
+/// Version reported by a PHP binary (e.g. `8.3.12`)
+pub async fn php_version(binary: &Path) -> Option<String> {
+    let output = tokio::process::Command::new(binary)
+        .args(["-r", "echo PHP_VERSION;"])
+        .output()
+        .await
+        .ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Whether `actual` (8.3.12) satisfies a requested `8.3` or `8.3.12`
+fn php_version_matches(requested: &str, actual: &str) -> bool {
+    actual == requested || actual.starts_with(&format!("{}.", requested))
+}
+
+/// Candidate interpreter paths for a PHP version across phpenv, Homebrew and Debian alternatives
+async fn php_candidates(version: &str) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    
+    // phpenv: ~/.phpenv/versions/8.3.12/bin/php
+    let phpenv_root = std::env::var_os("PHPENV_ROOT")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|h| h.join(".phpenv")));
+    if let Some(root) = phpenv_root {
+        if let Ok(mut entries) = fs::read_dir(root.join("versions")).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                if php_version_matches(version, &entry.file_name().to_string_lossy()) {
+                    candidates.push(entry.path().join("bin").join("php"));
+                }
+            }
+        }
+    }
+    
+    // Homebrew: $(brew --prefix php@8.3)/bin/php
+    if let Ok(output) = tokio::process::Command::new("brew")
+        .args(["--prefix", &format!("php@{}", version)])
+        .output()
+        .await
+    {
+        if output.status.success() {
+            candidates.push(PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()).join("bin").join("php"));
+        }
+    }
+    
+    // Debian/Ubuntu alternatives: /usr/bin/php8.3
+    candidates.push(PathBuf::from(format!("/usr/bin/php{}", version)));
+    candidates.push(PathBuf::from(format!("/usr/local/bin/php{}", version)));
+    
+    // Whatever `php` is on PATH may already be the right version
+    candidates.push(PathBuf::from("php"));
+    candidates
+}
+
+/// Find an installed interpreter for a PHP version
+pub async fn locate_php(version: &str) -> Option<PathBuf> {
+    for candidate in php_candidates(version).await {
+        if let Some(actual) = php_version(&candidate).await {
+            if php_version_matches(version, &actual) {
+                // Resolve `php` to an absolute path so the pin survives PATH changes
+                if candidate.is_relative() {
+                    let output = tokio::process::Command::new("which").arg(&candidate).output().await.ok()?;
+                    let resolved = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                    return (!resolved.is_empty()).then(|| PathBuf::from(resolved));
+                }
+                return Some(candidate);
+            }
+        }
+    }
+    None
+}
+
+/// Install a PHP version with the first available tool
+async fn install_php(version: &str) -> Result<()> {
+    let mut cmd = if util::command_exists("phpenv").await {
+        let mut c = Command::new("phpenv");
+        c.args(["install", "--skip-existing", version]);
+        c
+    } else if util::command_exists("brew").await {
+        let mut c = Command::new("brew");
+        c.args(["install", &format!("shivammathur/php/php@{}", version)]);
+        c
+    } else if util::command_exists("apt-get").await {
+        let mut c = Command::new("sudo");
+        c.args(["apt-get", "install", "-y", &format!("php{}-cli", version)]);
+        c
+    } else {
+        return Err(anyhow!("No supported PHP installer found (phpenv, brew or apt-get)"));
+    };
+    
+    println!("📦 Installing PHP {}...", version);
+    execute_mutation(&mut cmd).await
+        .with_context(|| format!("Failed to install PHP {}", version))?;
+    Ok(())
+}
+
+/// Pin a PHP version for the workspace
+pub async fn use_php(workspace_root: &Path, version: &str, install: bool) -> Result<()> {
+    let binary = match locate_php(version).await {
+        Some(binary) => binary,
+        None if install => {
+            install_php(version).await?;
+            if util::is_dry_run() {
+                println!("[dry-run] Would pin PHP {} in {}", version, toolchain::TOOLCHAIN_FILE);
+                return Ok(());
+            }
+            locate_php(version).await
+                .ok_or_else(|| anyhow!("PHP {} was installed but could not be located", version))?
+        }
+        None => {
+            return Err(anyhow!(
+                "PHP {} not found. Re-run with --install or install it via phpenv, brew or apt",
+                version
+            ));
+        }
+    };
+    
+    let actual = php_version(&binary).await.unwrap_or_else(|| version.to_string());
+    if util::is_dry_run() {
+        println!("[dry-run] Would pin PHP {} ({}) in {}", actual, binary.display(), toolchain::TOOLCHAIN_FILE);
+        return Ok(());
+    }
+    
+    let mut pins = ToolchainPins::load(workspace_root).await?;
+    pins.pin(workspace_root, "php", ToolPin {
+        version: version.to_string(),
+        path: binary.clone(),
+    }).await?;
+    
+    println!("✅ Using PHP {} ({}) in this workspace", actual, binary.display());
+    println!("   Pinned in {}; php and composer now run through {}", 
+             toolchain::TOOLCHAIN_FILE, toolchain::shim_dir(workspace_root).display());
+    Ok(())
+}
+
+/// Flatten composer.lock into packages sorted by group and name
+pub fn locked_packages(lock: &ComposerLock, include_platform: bool) -> Vec<LockedPackage> {
+    let package = |p: &ComposerPackage, group: &str| LockedPackage {
+        name: p.name.clone(),
+        version: p.version.clone(),
+        group: group.to_string(),
+        package_type: p.package_type.clone(),
+        license: p.license.clone().unwrap_or_default(),
+        description: p.description.clone(),
+        homepage: p.homepage.clone(),
+        source: p.source.as_ref().map(|s| s.url.clone()).or_else(|| p.dist.as_ref().map(|d| d.url.clone())),
+        reference: p.source.as_ref().map(|s| s.reference.clone()).or_else(|| p.dist.as_ref().and_then(|d| d.reference.clone())),
+        time: p.time.clone(),
+        require: p.require.clone().unwrap_or_default().into_iter().collect(),
+    };
+    let platform = |name: &String, constraint: &String, group: &str| LockedPackage {
+        name: name.clone(),
+        version: constraint.clone(),
+        group: group.to_string(),
+        package_type: None,
+        license: Vec::new(),
+        description: None,
+        homepage: None,
+        source: None,
+        reference: None,
+        time: None,
+        require: BTreeMap::new(),
+    };
+    
+    let mut packages: Vec<LockedPackage> = lock.packages.iter().map(|p| package(p, "prod"))
+        .chain(lock.packages_dev.iter().map(|p| package(p, "dev")))
+        .collect();
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    if include_platform {
+        let mut platform_packages: Vec<LockedPackage> = lock.platform.iter().map(|(n, c)| platform(n, c, "platform"))
+            .chain(lock.platform_dev.iter().map(|(n, c)| platform(n, c, "platform-dev")))
+            .collect();
+        platform_packages.sort_by(|a, b| a.name.cmp(&b.name));
+        packages.extend(platform_packages);
+    }
+    packages
+}
+
+/// Whether a version falls within a Composer constraint such as `>=5.0,<5.4.31|>=6.0,<6.3.8`
+///
+/// Versions that cannot be parsed (`dev-main`) are treated as affected.
+pub fn constraint_matches(constraint: &str, version: &str) -> bool {
+    let Some(version) = parse_loose_version(version.trim_start_matches('v')) else {
+        return true;
+    };
+    constraint
+        .split('|')
+        .map(str::trim)
+        .filter(|alt| !alt.is_empty())
+        .any(|alt| {
+            alt.split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|term| !term.is_empty())
+                .all(|term| {
+                    let (op, bound) = ["<=", ">=", "!=", "==", "<", ">", "="]
+                        .iter()
+                        .find_map(|op| term.strip_prefix(op).map(|rest| (*op, rest)))
+                        .unwrap_or(("=", term));
+                    let Some(bound) = parse_loose_version(bound.trim_start_matches('v')) else {
+                        return true;
+                    };
+                    match op {
+                        "<=" => version <= bound,
+                        ">=" => version >= bound,
+                        "<" => version < bound,
+                        ">" => version > bound,
+                        "!=" => version != bound,
+                        _ => version == bound,
+                    }
+                })
+        })
+}
+
+/// Advisories that affect the locked versions, from a Packagist advisories response
+pub fn affected_advisories(response: &Value, packages: &[LockedPackage]) -> Vec<Vulnerability> {
+    let locked: HashMap<String, &str> = packages.iter()
+        .map(|p| (p.name.to_lowercase(), p.version.as_str()))
+        .collect();
+    audit::parse_composer_audit(response)
+        .into_iter()
+        .filter_map(|mut vuln| {
+            let installed = *locked.get(&vuln.package.to_lowercase())?;
+            let range = vuln.version.take().unwrap_or_default();
+            if !range.is_empty() && !constraint_matches(&range, installed) {
+                return None;
+            }
+            vuln.version = Some(installed.to_string());
+            Some(vuln)
+        })
+        .collect()
+}
+
+/// Query the Packagist security advisories API for the given packages
+async fn fetch_advisories(names: &[String]) -> Result<Value> {
+    let client = reqwest::Client::new();
+    let mut advisories = serde_json::Map::new();
+    
+    // Keep query strings well under URL length limits
+    for chunk in names.chunks(100) {
+        let query: Vec<(&str, &str)> = chunk.iter().map(|n| ("packages[]", n.as_str())).collect();
+        let response = client.get(PACKAGIST_ADVISORIES_URL)
+            .query(&query)
+            .send()
+            .await
+            .context("Failed to query Packagist security advisories")?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Packagist advisories request failed: {}", response.status()));
+        }
+        let body: Value = response.json().await
+            .context("Invalid response from Packagist security advisories")?;
+        if let Some(found) = body.get("advisories").and_then(Value::as_object) {
+            advisories.extend(found.clone());
+        }
+    }
+    
+    Ok(serde_json::json!({ "advisories": advisories }))
+}
+
+fn print_package_details(package: &LockedPackage) {
+    let row = |field: &str, value: Option<String>| FieldRow {
+        field: field.to_string(),
+        value: value.unwrap_or_else(|| "-".to_string()),
+    };
+    let rows = vec![
+        row("Name", Some(package.name.clone())),
+        row("Version", Some(package.version.clone())),
+        row("Group", Some(package.group.clone())),
+        row("Type", package.package_type.clone()),
+        row("License", (!package.license.is_empty()).then(|| package.license.join(", "))),
+        row("Description", package.description.clone()),
+        row("Homepage", package.homepage.clone()),
+        row("Source", package.source.clone()),
+        row("Reference", package.reference.clone()),
+        row("Released", package.time.clone()),
+        row("Requires", (!package.require.is_empty()).then(|| {
+            package.require.iter().map(|(k, v)| format!("{}: {}", k, v)).collect::<Vec<_>>().join("\n")
+        })),
+    ];
+    println!("{}", Table::new(rows));
+}
+
 /// Handle PPM commands
 pub async fn handle_command(workspace: &Workspace, cmd: PpmCommands) -> Result<()> {
     match cmd {
@@ -559,9 +1161,53 @@ pub async fn handle_command(workspace: &Workspace, cmd: PpmCommands) -> Result<(
             composer.update(&packages, with_dependencies, optimize).await
         }
         
-        PpmCommands::Show { package: _, installed: _, platform: _, format: _ } => {
-            // Implementation for showing packages
-            println!("PPM show functionality not yet implemented");
+        PpmCommands::Show { package, installed, platform, format } => {
+            let composer = ComposerManager::new(workspace.root());
+            let lock = composer.load_lock().await?;
+            let mut packages = locked_packages(&lock, platform);
+            if installed {
+                packages.retain(|p| p.group.starts_with("platform") || composer.is_installed(&p.name));
+            }
+            
+            if let Some(name) = package {
+                let found = packages.iter()
+                    .find(|p| p.name.eq_ignore_ascii_case(&name))
+                    .ok_or_else(|| anyhow!("Package {} is not in composer.lock", name))?;
+                return match format.as_str() {
+                    "json" => {
+                        println!("{}", serde_json::to_string_pretty(found)?);
+                        Ok(())
+                    }
+                    "table" => {
+                        print_package_details(found);
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+                };
+            }
+            
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&packages)?),
+                "table" => {
+                    if packages.is_empty() {
+                        println!("No packages in composer.lock");
+                        return Ok(());
+                    }
+                    let rows: Vec<LockedRow> = packages.iter()
+                        .map(|p| LockedRow {
+                            name: p.name.clone(),
+                            version: p.version.clone(),
+                            group: p.group.clone(),
+                            license: p.license.join(", "),
+                            description: p.description.clone().unwrap_or_default(),
+                        })
+                        .collect();
+                    println!("{}", Table::new(rows));
+                    let dev = packages.iter().filter(|p| p.group == "dev").count();
+                    println!("{} packages ({} dev)", packages.iter().filter(|p| !p.group.starts_with("platform")).count(), dev);
+                }
+                _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+            }
             Ok(())
         }
         
@@ -612,9 +1258,54 @@ pub async fn handle_command(workspace: &Workspace, cmd: PpmCommands) -> Result<(
             composer.validate(strict).await
         }
         
-        PpmCommands::Audit { format: _ } => {
-            // Implementation for security audit
-            println!("PPM audit functionality not yet implemented");
+        PpmCommands::Audit { format } => {
+            let composer = ComposerManager::new(workspace.root());
+            let packages = locked_packages(&composer.load_lock().await?, false);
+            let names: Vec<String> = packages.iter().map(|p| p.name.clone()).collect();
+            if format == "table" {
+                println!("🛡️ Checking {} packages against Packagist security advisories...", names.len());
+            }
+            
+            let response = fetch_advisories(&names).await?;
+            let findings = affected_advisories(&response, &packages);
+            let exceptions = AuditExceptions::load(workspace.root()).await?;
+            let mut report = audit::classify(findings, &exceptions, chrono::Local::now().date_naive());
+            report.active.sort_by(|a, b| b.severity_level().cmp(&a.severity_level()));
+            
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+                // `text` was the original default
+                "table" | "text" => {
+                    let rows: Vec<AdvisoryRow> = report.unaccepted()
+                        .map(|v| AdvisoryRow {
+                            severity: v.severity_level().to_string(),
+                            package: v.package.clone(),
+                            version: v.version.clone().unwrap_or_default(),
+                            advisory: v.advisory.clone(),
+                            cve: v.aliases.join(", "),
+                            title: v.title.clone(),
+                        })
+                        .collect();
+                    if !rows.is_empty() {
+                        println!("{}", Table::new(rows));
+                    }
+                    for (vuln, _, expires) in &report.accepted {
+                        println!("  ⏸️ {} {} accepted until {}", vuln.package, vuln.advisory, expires);
+                    }
+                }
+                _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+            }
+            
+            if report.failed() {
+                return Err(anyhow!(
+                    "{} vulnerable composer packages found ({} accepted)",
+                    report.unaccepted().count(),
+                    report.accepted.len()
+                ));
+            }
+            if format != "json" {
+                println!("✅ No known vulnerabilities in composer.lock ({} accepted)", report.accepted.len());
+            }
             Ok(())
         }
         
@@ -632,5 +1323,108 @@ pub async fn handle_command(workspace: &Workspace, cmd: PpmCommands) -> Result<(
             let composer = ComposerManager::new(workspace.root());
             composer.create_project(&template, &directory, stability.as_deref()).await
         }
+        
+        PpmCommands::Repo { cmd } => {
+            let composer = ComposerManager::new(workspace.root());
+            match cmd {
+                PpmRepoCommands::Add { url, repo_type, auth, username, no_verify } => {
+                    composer.add_repository(&url, &repo_type, auth.as_deref(), username.as_deref(), !no_verify).await?;
+                    println!("✅ Added {} repository {}", repo_type, url);
+                    if auth.is_some() {
+                        println!("   Credentials written to auth.json (git-ignored)");
+                    }
+                    Ok(())
+                }
+                PpmRepoCommands::List => {
+                    let repositories = composer.list_repositories().await?;
+                    if repositories.is_empty() {
+                        println!("No repositories configured (packagist.org only)");
+                    }
+                    for repo in repositories {
+                        println!(
+                            "  {} {}",
+                            repo.get("type").and_then(|t| t.as_str()).unwrap_or("?"),
+                            repo.get("url").and_then(|u| u.as_str()).unwrap_or("")
+                        );
+                    }
+                    Ok(())
+                }
+                PpmRepoCommands::Remove { url } => {
+                    composer.remove_repository(&url).await?;
+                    println!("🗑️ Removed repository {}", url);
+                    Ok(())
+                }
+            }
+        }
+        
+        PpmCommands::UsePhp { version, install } => {
+            use_php(workspace.root(), &version, install).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constraint_matches() {
+        assert!(constraint_matches(">=5.0.0,<5.4.31|>=6.0.0,<6.3.8", "v6.3.1"));
+        assert!(!constraint_matches(">=5.0.0,<5.4.31|>=6.0.0,<6.3.8", "v6.3.8"));
+        assert!(constraint_matches("<1.0 >=0.5", "0.9.2"));
+        assert!(!constraint_matches("=2.1.0", "2.1.1"));
+        assert!(constraint_matches("<2.0", "dev-main"));
+    }
+
+    #[test]
+    fn test_lock_and_advisories() {
+        let lock: ComposerLock = serde_json::from_value(serde_json::json!({
+            "content-hash": "abc",
+            "packages": [
+                { "name": "symfony/http-kernel", "version": "v6.3.1", "license": ["MIT"] },
+                { "name": "guzzlehttp/psr7", "version": "2.6.2" }
+            ],
+            "packages-dev": [{ "name": "phpunit/phpunit", "version": "10.5.0" }],
+            "aliases": [],
+            "minimum-stability": "stable",
+            "stability-flags": [],
+            "prefer-stable": true,
+            "prefer-lowest": false,
+            "platform": { "php": "^8.1" },
+            "platform-dev": []
+        }))
+        .unwrap();
+
+        let packages = locked_packages(&lock, true);
+        let groups: Vec<(&str, &str)> = packages.iter().map(|p| (p.name.as_str(), p.group.as_str())).collect();
+        assert_eq!(groups, vec![
+            ("guzzlehttp/psr7", "prod"),
+            ("phpunit/phpunit", "dev"),
+            ("symfony/http-kernel", "prod"),
+            ("php", "platform"),
+        ]);
+
+        let response = serde_json::json!({
+            "advisories": {
+                "symfony/http-kernel": [{
+                    "advisoryId": "PKSA-1",
+                    "packageName": "symfony/http-kernel",
+                    "affectedVersions": ">=6.0.0,<6.3.8",
+                    "title": "Cache poisoning",
+                    "cve": "CVE-2023-0001",
+                    "severity": "medium"
+                }],
+                "guzzlehttp/psr7": [{
+                    "advisoryId": "PKSA-2",
+                    "packageName": "guzzlehttp/psr7",
+                    "affectedVersions": "<2.4.5",
+                    "title": "Header injection"
+                }]
+            }
+        });
+        let findings = affected_advisories(&response, &packages);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].advisory, "PKSA-1");
+        assert_eq!(findings[0].version.as_deref(), Some("v6.3.1"));
     }
 }
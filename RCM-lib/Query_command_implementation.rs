@@ -0,0 +1,543 @@
+//! Query command implementation
+//!
+//! `rcm query` answers questions about the workspace from its stored state
+//! instead of human-oriented output:
+//!
+//! ```text
+//! rcm query 'deps where manager == "npm" and dev == true select name, version'
+//! rcm query 'instances where status == "Running"' --format json
+//! rcm query 'history where vulnerabilities > 0 limit 5'
+//! rcm query --jmespath 'deps[?manager==`cargo`].name'
+//! ```
+//!
+//! Sources are `deps`, `models`, `instances`, `history` and `state.<collection>`.
+//! Conditions compare dotted field paths with `== != < <= > >= ~=` (regex) and
+//! `contains`, combined with `and`, `or`, `not` and parentheses. Anything that
+//! doesn't start with a source name is evaluated as JMESPath over an object
+//! holding every source.
+
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::path::Path;
+use crate::metrics;
+use crate::sbom::MODEL_REGISTRY;
+use crate::storage;
+use crate::workspace::Workspace;
+
+/// Sources a query can read
+pub const SOURCES: &[&str] = &["deps", "models", "instances", "history", "state.<collection>"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Num(f64),
+    Op(&'static str),
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = input.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => { tokens.push(Token::Open); i += 1; }
+            ')' => { tokens.push(Token::Close); i += 1; }
+            ',' => { tokens.push(Token::Comma); i += 1; }
+            '"' | '\'' => {
+                let end = chars[i + 1..].iter().position(|&ch| ch == c)
+                    .ok_or_else(|| anyhow!("Unterminated string in query"))?;
+                tokens.push(Token::Str(chars[i + 1..i + 1 + end].iter().collect()));
+                i += end + 2;
+            }
+            '=' | '!' | '<' | '>' | '~' => {
+                let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+                let op = ["==", "!=", "<=", ">=", "~="].into_iter().find(|op| *op == two);
+                match (op, c) {
+                    (Some(op), _) => { tokens.push(Token::Op(op)); i += 2; }
+                    (None, '<') => { tokens.push(Token::Op("<")); i += 1; }
+                    (None, '>') => { tokens.push(Token::Op(">")); i += 1; }
+                    (None, '=') => { tokens.push(Token::Op("==")); i += 1; }
+                    _ => return Err(anyhow!("Unexpected '{}' in query", c)),
+                }
+            }
+            c if c.is_ascii_digit() || (c == '-' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                tokens.push(Token::Num(text.parse().with_context(|| format!("Invalid number '{}'", text))?));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || "_.-".contains(chars[i])) {
+                    i += 1;
+                }
+                tokens.push(Token::Word(chars[start..i].iter().collect()));
+            }
+            other => return Err(anyhow!("Unexpected '{}' in query", other)),
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+enum Cmp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Matches(Regex),
+    Contains,
+}
+
+#[derive(Debug)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare { path: String, cmp: Cmp, value: Value },
+    Truthy(String),
+}
+
+/// A parsed `<source> [where ...] [select ...] [limit N]` query
+#[derive(Debug)]
+pub struct Query {
+    pub source: String,
+    filter: Option<Expr>,
+    pub select: Vec<String>,
+    pub limit: Option<usize>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Word(w)) if w.eq_ignore_ascii_case(word)) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn field(&mut self) -> Result<String> {
+        match self.next() {
+            Some(Token::Word(word)) => Ok(word),
+            other => Err(anyhow!("Expected a field name, found {:?}", other)),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut left = self.and()?;
+        while self.keyword("or") {
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut left = self.unary()?;
+        while self.keyword("and") {
+            left = Expr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.peek() == Some(&Token::Open) {
+            self.pos += 1;
+            let inner = self.or()?;
+            if self.next() != Some(Token::Close) {
+                return Err(anyhow!("Expected ')' in query"));
+            }
+            return Ok(inner);
+        }
+        let path = self.field()?;
+        let cmp = match self.peek() {
+            Some(Token::Op(op)) => {
+                let op = *op;
+                self.pos += 1;
+                op
+            }
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case("contains") => {
+                self.pos += 1;
+                "contains"
+            }
+            _ => return Ok(Expr::Truthy(path)),
+        };
+        let value = match self.next() {
+            Some(Token::Str(s)) => Value::String(s),
+            Some(Token::Num(n)) => serde_json::Number::from_f64(n).map(Value::Number).unwrap_or(Value::Null),
+            Some(Token::Word(w)) if w == "true" => Value::Bool(true),
+            Some(Token::Word(w)) if w == "false" => Value::Bool(false),
+            Some(Token::Word(w)) if w == "null" => Value::Null,
+            Some(Token::Word(w)) => Value::String(w),
+            other => return Err(anyhow!("Expected a value after '{}', found {:?}", cmp, other)),
+        };
+        let cmp = match cmp {
+            "==" => Cmp::Eq,
+            "!=" => Cmp::Ne,
+            "<" => Cmp::Lt,
+            "<=" => Cmp::Le,
+            ">" => Cmp::Gt,
+            ">=" => Cmp::Ge,
+            "~=" => {
+                let pattern = value.as_str().ok_or_else(|| anyhow!("'~=' needs a string pattern"))?;
+                Cmp::Matches(Regex::new(pattern).with_context(|| format!("Invalid regex '{}'", pattern))?)
+            }
+            _ => Cmp::Contains,
+        };
+        Ok(Expr::Compare { path, cmp, value })
+    }
+}
+
+/// Whether `text` is written in the simple query language rather than JMESPath
+pub fn is_simple(text: &str) -> bool {
+    let first = text.split_whitespace().next().unwrap_or("");
+    ["deps", "models", "instances", "history"].contains(&first) || first.starts_with("state.")
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parser = Parser { tokens: tokenize(text)?, pos: 0 };
+        let source = parser.field()?;
+        let filter = if parser.keyword("where") { Some(parser.or()?) } else { None };
+        let mut select = Vec::new();
+        if parser.keyword("select") {
+            select.push(parser.field()?);
+            while parser.peek() == Some(&Token::Comma) {
+                parser.pos += 1;
+                select.push(parser.field()?);
+            }
+        }
+        let limit = if parser.keyword("limit") {
+            match parser.next() {
+                Some(Token::Num(n)) if n >= 0.0 => Some(n as usize),
+                other => return Err(anyhow!("Expected a number after 'limit', found {:?}", other)),
+            }
+        } else {
+            None
+        };
+        if let Some(token) = parser.peek() {
+            return Err(anyhow!("Unexpected {:?} in query; expected where, select or limit", token));
+        }
+        Ok(Self { source, filter, select, limit })
+    }
+
+    /// Rows of `rows` matching the query, projected and limited
+    pub fn apply(&self, rows: Vec<Value>) -> Vec<Value> {
+        rows.into_iter()
+            .filter(|row| match &self.filter {
+                Some(expr) => eval(expr, row),
+                None => true,
+            })
+            .take(self.limit.unwrap_or(usize::MAX))
+            .map(|row| {
+                if self.select.is_empty() {
+                    return row;
+                }
+                let picked: Map<String, Value> = self.select.iter()
+                    .map(|field| (field.clone(), lookup(&row, field).clone()))
+                    .collect();
+                Value::Object(picked)
+            })
+            .collect()
+    }
+}
+
+/// A dotted field path of `row`; missing fields are null
+fn lookup<'a>(row: &'a Value, path: &str) -> &'a Value {
+    path.split('.').try_fold(row, |value, part| match value {
+        Value::Object(map) => map.get(part),
+        Value::Array(items) => part.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    }).unwrap_or(&Value::Null)
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+fn order(left: &Value, right: &Value) -> Option<std::cmp::Ordering> {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+fn eval(expr: &Expr, row: &Value) -> bool {
+    use std::cmp::Ordering;
+    match expr {
+        Expr::And(a, b) => eval(a, row) && eval(b, row),
+        Expr::Or(a, b) => eval(a, row) || eval(b, row),
+        Expr::Not(inner) => !eval(inner, row),
+        Expr::Truthy(path) => truthy(lookup(row, path)),
+        Expr::Compare { path, cmp, value } => {
+            let actual = lookup(row, path);
+            match cmp {
+                Cmp::Eq => order(actual, value) == Some(Ordering::Equal) || actual == value,
+                Cmp::Ne => !(order(actual, value) == Some(Ordering::Equal) || actual == value),
+                Cmp::Lt => order(actual, value) == Some(Ordering::Less),
+                Cmp::Le => matches!(order(actual, value), Some(Ordering::Less | Ordering::Equal)),
+                Cmp::Gt => order(actual, value) == Some(Ordering::Greater),
+                Cmp::Ge => matches!(order(actual, value), Some(Ordering::Greater | Ordering::Equal)),
+                Cmp::Matches(regex) => actual.as_str().is_some_and(|s| regex.is_match(s)),
+                Cmp::Contains => match (actual, value) {
+                    (Value::String(s), Value::String(needle)) => s.contains(needle.as_str()),
+                    (Value::Array(items), needle) => items.contains(needle),
+                    _ => false,
+                },
+            }
+        }
+    }
+}
+
+/// `name` merged into each value of a JSON object, as rows
+fn named_rows(map: Option<&Map<String, Value>>, key: &str) -> Vec<Value> {
+    let mut rows: Vec<Value> = map.into_iter().flatten().map(|(name, value)| {
+        let mut row = Map::new();
+        row.insert(key.to_string(), Value::String(name.clone()));
+        match value {
+            Value::Object(fields) => row.extend(fields.clone()),
+            other => {
+                row.insert("value".to_string(), other.clone());
+            }
+        }
+        Value::Object(row)
+    }).collect();
+    rows.sort_by(|a, b| a[key].as_str().cmp(&b[key].as_str()));
+    rows
+}
+
+async fn registry(root: &Path) -> Result<Value> {
+    let path = root.join(MODEL_REGISTRY);
+    match tokio::fs::read_to_string(&path).await {
+        Ok(content) => serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display())),
+        Err(_) => Ok(Value::Null),
+    }
+}
+
+/// Rows of one source
+async fn load_source(workspace: &Workspace, source: &str) -> Result<Vec<Value>> {
+    let root = workspace.root();
+    match source {
+        "deps" => {
+            let deps = serde_json::to_value(workspace.list_dependencies())?;
+            let mut rows = named_rows(deps.as_object(), "name");
+            for row in &mut rows {
+                // `dev` reads better in queries than the stored `dev_only`
+                let dev = row.get("dev_only").cloned().unwrap_or(Value::Bool(false));
+                row["dev"] = dev;
+            }
+            Ok(rows)
+        }
+        "models" => Ok(named_rows(registry(root).await?.get("models").and_then(Value::as_object), "name")),
+        "instances" => {
+            let mut rows = named_rows(registry(root).await?.get("active_models").and_then(Value::as_object), "name");
+            for row in &mut rows {
+                // The model's full config is under `models`
+                if let Some(fields) = row.as_object_mut() {
+                    fields.remove("config");
+                }
+            }
+            Ok(rows)
+        }
+        "history" => Ok(metrics::load_history(root).await?
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<serde_json::Result<_>>()?),
+        other => match other.strip_prefix("state.") {
+            Some(collection) => {
+                let collection = collection.to_string();
+                let entries: Map<String, Value> = storage::with_store(root, move |store| {
+                    // Only known names reach the backend, which builds file paths from them
+                    let known = store.collections()?;
+                    if !known.contains(&collection) {
+                        return Err(anyhow!("Unknown state collection '{}'. Available: {}", collection, known.join(", ")));
+                    }
+                    store.list(&collection)
+                })
+                .await?
+                .into_iter()
+                .collect();
+                Ok(named_rows(Some(&entries), "key"))
+            }
+            None => Err(anyhow!("Unknown source '{}'. Use one of {}", other, SOURCES.join(", "))),
+        },
+    }
+}
+
+/// Every source in one object, for JMESPath
+async fn document(workspace: &Workspace) -> Result<Value> {
+    let mut doc = Map::new();
+    for source in ["deps", "models", "instances", "history"] {
+        doc.insert(source.to_string(), Value::Array(load_source(workspace, source).await?));
+    }
+    let mut state = Map::new();
+    for collection in storage::with_store(workspace.root(), |store| store.collections()).await? {
+        let rows = load_source(workspace, &format!("state.{}", collection)).await?;
+        state.insert(collection, Value::Array(rows));
+    }
+    doc.insert("state".to_string(), Value::Object(state));
+    Ok(Value::Object(doc))
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => {
+            let text = other.to_string();
+            if text.chars().count() > 60 {
+                format!("{}…", text.chars().take(59).collect::<String>())
+            } else {
+                text
+            }
+        }
+    }
+}
+
+/// A table with one column per field, in order of first appearance
+fn render_table(result: &Value) -> String {
+    let rows = match result {
+        Value::Array(rows) => rows.clone(),
+        other => vec![other.clone()],
+    };
+    let mut columns: Vec<String> = Vec::new();
+    for row in &rows {
+        let keys: Vec<String> = match row {
+            Value::Object(fields) => fields.keys().cloned().collect(),
+            _ => vec!["value".to_string()],
+        };
+        for key in keys {
+            if !columns.contains(&key) {
+                columns.push(key);
+            }
+        }
+    }
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(columns.clone());
+    for row in &rows {
+        builder.push_record(columns.iter().map(|column| match row {
+            Value::Object(_) => cell(lookup(row, column)),
+            scalar => cell(scalar),
+        }));
+    }
+    builder.build().to_string()
+}
+
+/// Run a query and print the result
+pub async fn run(workspace: &Workspace, text: &str, jmespath: bool, format: &str) -> Result<()> {
+    let result = if jmespath || !is_simple(text) {
+        let doc = document(workspace).await?.to_string();
+        let expression = jmespath::compile(text)
+            .map_err(|e| anyhow!("Invalid query: {}", e))?;
+        let doc = jmespath::Variable::from_json(&doc)
+            .map_err(|e| anyhow!("Failed to prepare query data: {}", e))?;
+        let found = expression.search(doc).map_err(|e| anyhow!("Query failed: {}", e))?;
+        serde_json::to_value(&*found)?
+    } else {
+        let query = Query::parse(text)?;
+        Value::Array(query.apply(load_source(workspace, &query.source).await?))
+    };
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&result)?),
+        "jsonl" => match &result {
+            Value::Array(rows) => rows.iter().try_for_each(|row| serde_json::to_string(row).map(|line| println!("{}", line)))?,
+            other => println!("{}", serde_json::to_string(other)?),
+        },
+        "table" => {
+            if result.as_array().is_some_and(|rows| rows.is_empty()) {
+                println!("No matches");
+            } else {
+                println!("{}", render_table(&result));
+            }
+        }
+        _ => return Err(anyhow!("Unknown format: {}. Use 'table', 'json' or 'jsonl'", format)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn deps() -> Vec<Value> {
+        vec![
+            json!({ "name": "react", "manager": "npm", "dev": false, "version": "18.2.0" }),
+            json!({ "name": "jest", "manager": "npm", "dev": true, "version": "29.7.0" }),
+            json!({ "name": "serde", "manager": "cargo", "dev": false, "version": "1.0", "platforms": ["linux"] }),
+        ]
+    }
+
+    fn names(rows: Vec<Value>) -> Vec<String> {
+        rows.iter().map(|r| r["name"].as_str().unwrap().to_string()).collect()
+    }
+
+    #[test]
+    fn test_where() {
+        let query = Query::parse(r#"deps where manager == "npm" and dev == true"#).unwrap();
+        assert_eq!(query.source, "deps");
+        assert_eq!(names(query.apply(deps())), vec!["jest"]);
+
+        let query = Query::parse("deps where not (manager == npm or platforms contains 'linux')").unwrap();
+        assert_eq!(names(query.apply(deps())), Vec::<String>::new());
+
+        let query = Query::parse("deps where name ~= '^re' or dev").unwrap();
+        assert_eq!(names(query.apply(deps())), vec!["react", "jest"]);
+    }
+
+    #[test]
+    fn test_select_and_limit() {
+        let query = Query::parse("deps where version >= '1' select name, manager limit 1").unwrap();
+        assert_eq!(query.apply(deps()), vec![json!({ "name": "react", "manager": "npm" })]);
+
+        let history = vec![json!({ "vulnerabilities": 0 }), json!({ "vulnerabilities": 3 })];
+        assert_eq!(Query::parse("history where vulnerabilities > 0").unwrap().apply(history).len(), 1);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Query::parse("deps where").is_err());
+        assert!(Query::parse("deps where name == 'x").is_err());
+        assert!(Query::parse("deps order by name").is_err());
+        assert!(is_simple("state.ensure where key == cargo"));
+        assert!(!is_simple("deps[?manager==`npm`]"));
+    }
+}
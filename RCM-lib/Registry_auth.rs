@@ -0,0 +1,299 @@
+//! Registry credentials for RCM
+//!
+//! `rcm auth login <registry>` stores a token (or basic-auth password) in the
+//! secret backend and records only a `secret:auth-<registry>` reference under
+//! `auth.<registry>` in the user config. Registry clients, Hugging Face
+//! downloads and the model gateway resolve credentials through [`credential`].
+//!
+//! A login is bound to the host it was issued for (`--host`, else the host of
+//! the configured registry). Where the URL comes from config, as with registry
+//! mirrors, [`credential_for`] only hands the credential out for that host.
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use console::style;
+use dialoguer::Password;
+use serde_json::json;
+use tabled::{Table, Tabled};
+use crate::config::{AuthConfig, AuthType, Config};
+use crate::config_layers::{self, LoadOptions};
+use crate::secrets::{self, SecretBackend};
+use crate::workspace::Workspace;
+
+#[derive(Subcommand)]
+pub enum AuthCommands {
+    /// Store credentials for a registry in the secret backend
+    Login {
+        /// Registry name (npmjs, packagist, crates-io, huggingface, github, or a configured registry)
+        registry: String,
+        /// Use basic auth with this username instead of a token
+        #[arg(long)]
+        username: Option<String>,
+        /// Read the token or password from stdin instead of prompting
+        #[arg(long)]
+        stdin: bool,
+        /// Registry host the credential is for (default: the configured registry's host)
+        #[arg(long)]
+        host: Option<String>,
+    },
+    /// Forget a registry's credentials
+    Logout {
+        /// Registry name
+        registry: String,
+    },
+    /// Show where each registry's credentials live and whether they resolve
+    Status {
+        /// Only this registry
+        registry: Option<String>,
+    },
+}
+
+/// Resolved credentials for a request
+#[derive(Clone)]
+pub enum Credential {
+    Bearer(String),
+    Basic { username: String, password: String },
+}
+
+impl Credential {
+    pub fn apply(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self {
+            Self::Bearer(token) => request.bearer_auth(token),
+            Self::Basic { username, password } => request.basic_auth(username, Some(password)),
+        }
+    }
+}
+
+/// Secret name holding a registry's token or password
+pub fn secret_name(registry: &str) -> String {
+    format!("auth-{}", registry)
+}
+
+/// The auth entry for a registry: the one its config names, else `auth.<registry>`
+///
+/// Dots become dashes for the fallback, so `crates.io` finds a `crates-io` login.
+pub fn auth_entry<'a>(config: &'a Config, registry: &str) -> Option<&'a AuthConfig> {
+    config.registries.get(registry)
+        .and_then(|r| r.auth.as_deref())
+        .and_then(|name| config.auth.get(name))
+        .or_else(|| config.auth.get(registry))
+        .or_else(|| config.auth.get(&registry.replace('.', "-")))
+}
+
+/// Hosts of the public registries `rcm auth login` knows by name
+const KNOWN_HOSTS: &[(&str, &str)] = &[
+    ("npmjs", "registry.npmjs.org"),
+    ("packagist", "repo.packagist.org"),
+    ("crates-io", "index.crates.io"),
+    ("huggingface", "huggingface.co"),
+    ("github", "api.github.com"),
+];
+
+/// Lowercased host of a URL, without port
+pub fn host_of(url: &str) -> Option<String> {
+    let url = url.trim().trim_start_matches("sparse+").trim_start_matches("git+");
+    reqwest::Url::parse(url).ok()
+        .and_then(|u| u.host_str().map(str::to_ascii_lowercase))
+}
+
+/// The host a new login for `registry` is bound to when `--host` isn't given
+fn default_host(config: &Config, registry: &str) -> Option<String> {
+    if let Some(entry) = config.registries.get(registry) {
+        return host_of(entry.mirror.as_deref().unwrap_or(&entry.url));
+    }
+    let mirrored = crate::mirrors::MANAGERS.iter()
+        .filter_map(|manager| crate::mirrors::configured(config, manager))
+        .find(|registry_override| registry_override.auth == registry);
+    if let Some(registry_override) = mirrored {
+        return host_of(&registry_override.url);
+    }
+    KNOWN_HOSTS.iter().find(|(name, _)| *name == registry).map(|(_, host)| host.to_string())
+}
+
+/// Credentials for `registry`, but only when they were issued for the host of `url`
+pub async fn credential_for(config: &Config, registry: &str, url: &str) -> Result<Option<Credential>> {
+    let Some(auth) = auth_entry(config, registry) else {
+        return Ok(None);
+    };
+    let target = host_of(url).ok_or_else(|| anyhow!("Not sending {} credentials to an invalid URL", registry))?;
+    match &auth.host {
+        Some(host) if host.eq_ignore_ascii_case(&target) => credential(config, registry).await,
+        Some(host) => Err(anyhow!("{} credentials were issued for {}, not sending them to {}", registry, host, target)),
+        None => Err(anyhow!(
+            "{} credentials aren't bound to a host; run `rcm auth login {} --host {}` to use them there",
+            registry, registry, target
+        )),
+    }
+}
+
+/// Credentials for a registry, with `secret:` references resolved
+pub async fn credential(config: &Config, registry: &str) -> Result<Option<Credential>> {
+    let Some(auth) = auth_entry(config, registry) else {
+        return Ok(None);
+    };
+    match auth.auth_type {
+        AuthType::Token => match auth.token.clone() {
+            Some(token) => Ok(Some(Credential::Bearer(secrets::resolve_value(&token).await
+                .with_context(|| format!("Failed to resolve the {} token", registry))?))),
+            None => Ok(None),
+        },
+        AuthType::Basic => match (auth.username.clone(), auth.password.clone()) {
+            (Some(username), Some(password)) => Ok(Some(Credential::Basic {
+                username,
+                password: secrets::resolve_value(&password).await
+                    .with_context(|| format!("Failed to resolve the {} password", registry))?,
+            })),
+            _ => Ok(None),
+        },
+        // Certificates and SSH keys are handled by the managers themselves
+        AuthType::Certificate | AuthType::SSH => Ok(None),
+    }
+}
+
+#[derive(Tabled)]
+struct StatusRow {
+    #[tabled(rename = "Registry")]
+    registry: String,
+    #[tabled(rename = "Type")]
+    auth_type: String,
+    #[tabled(rename = "Stored in")]
+    stored: String,
+    #[tabled(rename = "Status")]
+    status: String,
+}
+
+fn read_stdin_line() -> Result<String> {
+    let mut buf = String::new();
+    std::io::stdin().read_line(&mut buf)?;
+    Ok(buf.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Handle `rcm auth` commands
+pub async fn handle_command(workspace: &Workspace, config: &Config, config_path: Option<&str>, cmd: AuthCommands) -> Result<()> {
+    let options = LoadOptions {
+        config_path,
+        workspace_root: Some(workspace.root()),
+        ..Default::default()
+    };
+
+    match cmd {
+        AuthCommands::Login { registry, username, stdin, host } => {
+            if registry.is_empty() || registry.contains(['.', '[', ']']) {
+                return Err(anyhow!("Registry names can't contain '.', '[' or ']'"));
+            }
+            let value = if stdin {
+                read_stdin_line()?
+            } else {
+                let label = if username.is_some() { "Password" } else { "Token" };
+                Password::new().with_prompt(format!("{} for {}", label, registry)).interact()?
+            };
+            if value.is_empty() {
+                return Err(anyhow!("Credential cannot be empty"));
+            }
+            let name = secret_name(&registry);
+            secrets::set_secret(&name, &value).await?;
+            let reference = format!("{}{}", secrets::SECRET_PREFIX, name);
+            let host = match host {
+                Some(host) => Some(host_of(&host).or_else(|| host_of(&format!("https://{}", host)))
+                    .ok_or_else(|| anyhow!("Invalid host '{}'", host))?),
+                None => default_host(config, &registry),
+            };
+            let mut entry = match username {
+                Some(username) => json!({"auth_type": "Basic", "username": username, "password": reference}),
+                None => json!({"auth_type": "Token", "token": reference}),
+            };
+            if let Some(host) = &host {
+                entry["host"] = json!(host);
+            }
+            let (_, path, _) = config_layers::write_key(&options, "user", &format!("auth.{}", registry), &entry.to_string()).await?;
+
+            let backend = SecretBackend::configured(config)?;
+            println!("{}", style(format!("🔐 Logged in to {} (secret stored in the {} backend)", registry, backend.name())).green());
+            println!("   {} references {} in {}", registry, reference, path.display());
+            match host {
+                Some(host) => println!("   Only sent to {}", host),
+                None => println!("   {}", style("Not bound to a host; pass --host to use it with a registry mirror").yellow()),
+            }
+            Ok(())
+        }
+
+        AuthCommands::Logout { registry } => {
+            let mut removed = false;
+            if secrets::remove_secret(&secret_name(&registry)).await.is_ok() {
+                removed = true;
+            }
+            if config.auth.contains_key(&registry)
+                && config_layers::remove_key(&options, "user", &format!("auth.{}", registry)).await.is_ok()
+            {
+                removed = true;
+            }
+            if !removed {
+                return Err(anyhow!("No stored credentials for {}", registry));
+            }
+            println!("{}", style(format!("🗑️ Logged out of {}", registry)).green());
+            Ok(())
+        }
+
+        AuthCommands::Status { registry } => {
+            let mut names: Vec<&String> = config.auth.keys()
+                .filter(|name| registry.as_ref().map_or(true, |r| r == *name))
+                .collect();
+            names.sort();
+            if names.is_empty() {
+                match registry {
+                    Some(registry) => println!("No credentials for {}. Run `rcm auth login {}`", registry, registry),
+                    None => println!("No registry credentials configured. Run `rcm auth login <registry>`"),
+                }
+                return Ok(());
+            }
+
+            let backend = SecretBackend::configured(config)?;
+            let mut rows = Vec::new();
+            let mut plaintext = 0;
+            for name in names {
+                let auth = &config.auth[name];
+                let secret = match auth.auth_type {
+                    AuthType::Basic => auth.password.as_deref(),
+                    _ => auth.token.as_deref(),
+                };
+                let stored = match secret {
+                    Some(value) => match secrets::secret_ref(value) {
+                        Some(secret) if std::env::var(secrets::env_override_name(secret)).is_ok() => {
+                            format!("env {}", secrets::env_override_name(secret))
+                        }
+                        Some(_) => backend.name().to_string(),
+                        None => {
+                            plaintext += 1;
+                            "⚠️ plaintext in config".to_string()
+                        }
+                    },
+                    None => match auth.auth_type {
+                        AuthType::Certificate => auth.cert_file.clone().unwrap_or_default(),
+                        AuthType::SSH => auth.key_file.clone().unwrap_or_default(),
+                        _ => "-".to_string(),
+                    },
+                };
+                let status = match credential(config, name).await {
+                    Ok(Some(_)) => "✅ resolves".to_string(),
+                    Ok(None) if matches!(auth.auth_type, AuthType::Certificate | AuthType::SSH) => "✅ file".to_string(),
+                    Ok(None) => "❌ incomplete".to_string(),
+                    Err(e) => format!("❌ {}", e),
+                };
+                rows.push(StatusRow {
+                    registry: name.clone(),
+                    auth_type: format!("{:?}", auth.auth_type),
+                    stored,
+                    status,
+                });
+            }
+            println!("{}", Table::new(&rows));
+            if plaintext > 0 {
+                println!(
+                    "{}",
+                    style(format!("⚠️  {} credential(s) stored in plaintext; re-run `rcm auth login <registry>` to move them to the {} backend", plaintext, backend.name())).yellow()
+                );
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,293 @@
+//! Registry and mirror overrides for spawned package managers
+//!
+//! `managers.<npm|composer|cargo>.registry` is either the name of a
+//! `registries` entry (its `mirror` wins over its `url`) or a URL. When it
+//! points anywhere but the public default, [`init`] works out what each tool
+//! needs and every manager RCM spawns picks it up through [`env_vars`], plus
+//! [`cargo_args`] for cargo:
+//!
+//! - npm, pnpm, yarn and bun get `npm_config_registry` / `YARN_NPM_REGISTRY_SERVER`;
+//!   credentials go in `.rcm/registry/npmrc`, used as the global npmrc, which
+//!   reads them back from the environment
+//! - composer gets `COMPOSER_AUTH`; in an isolated workspace its home's
+//!   `config.json` also swaps packagist.org for the mirror. Without isolation
+//!   the user's COMPOSER_HOME, and the global auth in it, is left alone
+//! - cargo gets `--config .rcm/registry/cargo.toml`, which replaces crates.io,
+//!   and `CARGO_REGISTRIES_RCM_MIRROR_TOKEN`
+//!
+//! Credentials come from [`crate::auth::credential_for`], so a login is only
+//! attached when the mirror is on the host it was issued for, and only ever
+//! travel in the environment of the spawned process; generated files reference them.
+
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tokio::fs;
+use crate::auth::{self, Credential};
+use crate::config::Config;
+use crate::{isolation, redact, util};
+
+/// Generated files, relative to the workspace root
+pub const GENERATED_DIR: &str = ".rcm/registry";
+
+/// Managers whose registry can be overridden
+pub const MANAGERS: [&str; 3] = ["npm", "composer", "cargo"];
+
+/// Name of the replacement registry in generated cargo and composer config
+const MIRROR_NAME: &str = "rcm-mirror";
+
+/// A non-default registry configured for a manager
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryOverride {
+    pub manager: String,
+    pub url: String,
+    /// Name credentials are looked up under (`rcm auth login <name>`)
+    pub auth: String,
+}
+
+#[derive(Debug, Default)]
+struct Overrides {
+    env: Vec<(String, String)>,
+    cargo_config: Option<PathBuf>,
+}
+
+static OVERRIDES: OnceLock<Overrides> = OnceLock::new();
+
+/// Public registries that need no override
+fn is_default(manager: &str, url: &str) -> bool {
+    let url = url.trim_start_matches("sparse+").trim_end_matches('/');
+    match manager {
+        "npm" => matches!(url, "https://registry.npmjs.org" | "https://registry.yarnpkg.com"),
+        "composer" => matches!(url, "https://repo.packagist.org" | "https://packagist.org"),
+        "cargo" => matches!(url, "https://index.crates.io" | "https://crates.io" | "https://github.com/rust-lang/crates.io-index"),
+        _ => false,
+    }
+}
+
+/// The registry override configured for a manager, if it isn't the public default
+pub fn configured(config: &Config, manager: &str) -> Option<RegistryOverride> {
+    let settings = config.managers.get(manager)?;
+    let value = settings.registry.as_deref().map(str::trim).filter(|v| !v.is_empty())?;
+    let (url, auth) = match config.registries.get(value) {
+        Some(registry) => (registry.mirror.clone().unwrap_or_else(|| registry.url.clone()), value.to_string()),
+        None => (value.to_string(), settings.auth.clone().unwrap_or_else(|| manager.to_string())),
+    };
+    (!is_default(manager, &url)).then(|| RegistryOverride { manager: manager.to_string(), url, auth })
+}
+
+/// `user:password` in standard base64, as npm's `_auth` and basic auth headers expect
+fn basic_ident(username: &str, password: &str) -> String {
+    BASE64.encode(format!("{}:{}", username, password))
+}
+
+/// The registry as npmrc keys credentials: `//host/path/`
+pub fn npmrc_key(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    format!("//{}/", rest.trim_end_matches('/'))
+}
+
+/// Global npmrc carrying the registry's credentials as environment references
+pub fn npmrc(url: &str, credential: &Credential) -> String {
+    let key = npmrc_key(url);
+    let line = match credential {
+        Credential::Bearer(_) => format!("{}:_authToken=${{RCM_NPM_TOKEN}}", key),
+        Credential::Basic { .. } => format!("{}:_auth=${{RCM_NPM_AUTH}}", key),
+    };
+    format!("# Generated by rcm from managers.npm.registry; do not edit\n{}\n", line)
+}
+
+/// Cargo config replacing crates.io with the mirror
+pub fn cargo_config(url: &str) -> String {
+    // Bare https URLs are sparse indexes; git indexes end in .git or say so
+    let index = if url.starts_with("sparse+") || url.starts_with("git+") || url.ends_with(".git") || url.starts_with("ssh://") {
+        url.to_string()
+    } else {
+        format!("sparse+{}/", url.trim_end_matches('/'))
+    };
+    format!(
+        "# Generated by rcm from managers.cargo.registry; do not edit\n\
+         [registries.{name}]\nindex = {index}\n\n\
+         [source.crates-io]\nreplace-with = \"{name}\"\n",
+        name = MIRROR_NAME,
+        index = toml::Value::String(index),
+    )
+}
+
+/// `config.json` for COMPOSER_HOME with packagist.org swapped for the mirror
+pub fn composer_config(mut existing: Value, url: &str) -> Value {
+    if !existing.is_object() {
+        existing = json!({});
+    }
+    existing["repositories"] = json!({
+        MIRROR_NAME: {"type": "composer", "url": url},
+        "packagist.org": false,
+    });
+    existing
+}
+
+/// `COMPOSER_AUTH` for the mirror's host
+pub fn composer_auth(url: &str, credential: &Credential) -> Value {
+    let host = url.split_once("://").map_or(url, |(_, rest)| rest).split(['/', ':']).next().unwrap_or_default();
+    match credential {
+        Credential::Bearer(token) => json!({"bearer": {host: token}}),
+        Credential::Basic { username, password } => json!({"http-basic": {host: {"username": username, "password": password}}}),
+    }
+}
+
+async fn write_if_changed(path: &Path, contents: &str) -> Result<()> {
+    if fs::read_to_string(path).await.ok().as_deref() == Some(contents) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::write(path, contents).await.with_context(|| format!("Failed to write {}", path.display()))
+}
+
+async fn apply(root: &Path, config: &Config, registry: &RegistryOverride, overrides: &mut Overrides) -> Result<()> {
+    // A mirror on another host (say, one set by a cloned repo) never gets the login
+    let credential = match auth::credential_for(config, &registry.auth, &registry.url).await {
+        Ok(credential) => credential,
+        Err(e) => {
+            tracing::warn!("Not using {} credentials for the {} registry: {}", registry.auth, registry.manager, redact::redact_error(&e));
+            None
+        }
+    };
+    if let Some(Credential::Bearer(secret) | Credential::Basic { password: secret, .. }) = &credential {
+        redact::register(secret);
+    }
+    let dir = root.join(GENERATED_DIR);
+    // Generated files are an implementation detail, but read-only means no writes at all
+    let writable = !util::is_read_only() && !util::is_dry_run();
+    let url = registry.url.trim_end_matches('/').to_string();
+    let env = &mut overrides.env;
+
+    match registry.manager.as_str() {
+        "npm" => {
+            env.push(("npm_config_registry".to_string(), format!("{}/", url)));
+            env.push(("YARN_NPM_REGISTRY_SERVER".to_string(), url.clone()));
+            if let Some(credential) = &credential {
+                match credential {
+                    Credential::Bearer(token) => {
+                        env.push(("RCM_NPM_TOKEN".to_string(), token.clone()));
+                        env.push(("YARN_NPM_AUTH_TOKEN".to_string(), token.clone()));
+                    }
+                    Credential::Basic { username, password } => {
+                        let ident = format!("{}:{}", username, password);
+                        let encoded = basic_ident(username, password);
+                        redact::register(&encoded);
+                        env.push(("RCM_NPM_AUTH".to_string(), encoded));
+                        env.push(("YARN_NPM_AUTH_IDENT".to_string(), ident));
+                    }
+                }
+                if writable {
+                    let path = dir.join("npmrc");
+                    write_if_changed(&path, &npmrc(&url, credential)).await?;
+                    env.push(("NPM_CONFIG_GLOBALCONFIG".to_string(), path.display().to_string()));
+                }
+            }
+        }
+        "composer" => {
+            if let Some(credential) = &credential {
+                env.push(("COMPOSER_AUTH".to_string(), composer_auth(&url, credential).to_string()));
+            }
+            // Only an isolated workspace's own home is rewritten; redirecting the
+            // user's COMPOSER_HOME would drop their global auth.json
+            let isolated_home = isolation::IsolationSettings::load(root).enabled
+                .then(|| isolation::home_vars(root).into_iter().find(|(name, _)| *name == "COMPOSER_HOME"))
+                .flatten()
+                .map(|(_, path)| path);
+            match isolated_home {
+                Some(home) if writable => {
+                    let path = home.join("config.json");
+                    let existing = fs::read_to_string(&path).await.ok()
+                        .and_then(|content| serde_json::from_str(&content).ok())
+                        .unwrap_or(Value::Null);
+                    write_if_changed(&path, &serde_json::to_string_pretty(&composer_config(existing, &url))?).await?;
+                }
+                Some(_) => {}
+                None => tracing::warn!(
+                    "managers.composer.registry only replaces packagist.org in isolated workspaces (rcm workspace isolate); composer will use its own repositories"
+                ),
+            }
+        }
+        "cargo" => {
+            if writable {
+                let path = dir.join("cargo.toml");
+                write_if_changed(&path, &cargo_config(&registry.url)).await?;
+                overrides.cargo_config = Some(path);
+            }
+            if let Some(credential) = &credential {
+                // Cargo sends the token verbatim as the Authorization header
+                let token = match credential {
+                    Credential::Bearer(token) => token.clone(),
+                    Credential::Basic { username, password } => format!("Basic {}", basic_ident(username, password)),
+                };
+                redact::register(&token);
+                env.push((format!("CARGO_REGISTRIES_{}_TOKEN", MIRROR_NAME.to_uppercase().replace('-', "_")), token));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Work out the overrides for this run; call once, after the config is loaded
+///
+/// A registry whose credentials can't be resolved is skipped with a warning
+/// rather than failing commands that never touch it.
+pub async fn init(workspace_root: &Path, config: &Config) {
+    let mut overrides = Overrides::default();
+    for manager in MANAGERS {
+        let Some(registry) = configured(config, manager) else {
+            continue;
+        };
+        tracing::debug!(manager, url = %redact::redact(&registry.url), "Using registry override");
+        if let Err(e) = apply(workspace_root, config, &registry, &mut overrides).await {
+            tracing::warn!("Registry override for {} not applied: {}", manager, redact::redact_error(&e));
+        }
+    }
+    let _ = OVERRIDES.set(overrides);
+}
+
+/// Variables to set on a spawned manager process; pass to `Command::envs`
+/// after `isolation::env_vars` so a mirror's COMPOSER_HOME wins
+pub fn env_vars() -> Vec<(String, String)> {
+    OVERRIDES.get().map(|o| o.env.clone()).unwrap_or_default()
+}
+
+/// Extra arguments for cargo invocations that resolve dependencies
+pub fn cargo_args() -> Vec<String> {
+    OVERRIDES.get()
+        .and_then(|o| o.cargo_config.as_ref())
+        .map(|path| vec!["--config".to_string(), path.display().to_string()])
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_overrides() {
+        assert_eq!(basic_ident("user", "pa55"), "dXNlcjpwYTU1");
+        assert_eq!(npmrc_key("https://npm.corp.example/repository/npm/"), "//npm.corp.example/repository/npm/");
+        assert!(npmrc("https://npm.corp.example", &Credential::Bearer("t".into())).contains("//npm.corp.example/:_authToken=${RCM_NPM_TOKEN}"));
+
+        let cargo = cargo_config("https://crates.corp.example/index");
+        assert!(cargo.contains("index = \"sparse+https://crates.corp.example/index/\""), "{}", cargo);
+        assert!(cargo.contains("replace-with = \"rcm-mirror\""));
+
+        let composer = composer_config(json!({"config": {"process-timeout": 600}}), "https://packagist.corp.example");
+        assert_eq!(composer["repositories"]["packagist.org"], json!(false));
+        assert_eq!(composer["config"]["process-timeout"], json!(600));
+        let auth = composer_auth("https://packagist.corp.example:8443/repo", &Credential::Bearer("t".into()));
+        assert_eq!(auth, json!({"bearer": {"packagist.corp.example": "t"}}));
+
+        assert!(is_default("npm", "https://registry.npmjs.org/"));
+        assert!(!is_default("cargo", "sparse+https://crates.corp.example/"));
+    }
+}
@@ -0,0 +1,351 @@
+//! Remote execution for RCM
+//!
+//! `rcm --remote user@host[:port] <command>` re-runs the command on another
+//! machine over ssh with output streamed back. When the host has no rcm on
+//! PATH this binary is copied to `~/.rcm/bin/rcm` there, provided the host's
+//! OS, architecture and C library can run it; an rcm we deployed is replaced
+//! when its version differs from ours.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use std::ffi::OsString;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command as AsyncCommand;
+use crate::util;
+
+/// Set on the remote side so a forwarded `--remote` never hops again
+pub const REMOTE_ENV: &str = "RCM_REMOTE_SESSION";
+
+/// Where a deployed binary lives on the remote host
+const DEPLOY_PATH: &str = ".rcm/bin/rcm";
+
+/// An ssh destination
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteTarget {
+    /// `user@host` or `host` (also a Host alias from ~/.ssh/config)
+    pub destination: String,
+    pub port: Option<u16>,
+}
+
+impl RemoteTarget {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim().trim_start_matches("ssh://");
+        if spec.is_empty() || spec.contains(char::is_whitespace) {
+            return Err(anyhow!("Invalid remote '{}'. Use user@host or user@host:port", spec));
+        }
+        let target = match spec.rsplit_once(':') {
+            Some((destination, port)) if !destination.is_empty() => {
+                let port = port.parse().map_err(|_| anyhow!("Invalid port in remote '{}'", spec))?;
+                Self { destination: destination.to_string(), port: Some(port) }
+            }
+            _ => Self { destination: spec.to_string(), port: None },
+        };
+        // ssh would read `-oProxyCommand=...` as an option, not a host
+        let host = target.destination.rsplit('@').next().unwrap_or_default();
+        if target.destination.starts_with('-') || host.is_empty() || host.starts_with('-') {
+            return Err(anyhow!("Invalid remote '{}'. Use user@host or user@host:port", spec));
+        }
+        Ok(target)
+    }
+
+    /// An `ssh` invocation for this host with `options`; append the remote command
+    fn ssh_with(&self, options: &[&str]) -> Command {
+        let mut cmd = Command::new("ssh");
+        cmd.args(options);
+        if let Some(port) = self.port {
+            cmd.args(["-p", &port.to_string()]);
+        }
+        cmd.arg("--").arg(&self.destination);
+        cmd
+    }
+
+    /// An `ssh` invocation for this host; append the remote command
+    pub fn ssh(&self, tty: bool) -> Command {
+        self.ssh_with(if tty { &["-t"] } else { &[] })
+    }
+
+    /// Run a shell snippet remotely and capture stdout
+    async fn capture(&self, script: &str) -> Result<String> {
+        let mut cmd = AsyncCommand::from(self.ssh_with(&["-o", "BatchMode=yes"]));
+        cmd.arg(script);
+        Ok(util::execute_command_async(&mut cmd).await
+            .with_context(|| format!("Failed to reach {} over ssh", self.destination))?
+            .stdout.trim().to_string())
+    }
+
+    /// Write `contents` to a path relative to the remote home directory
+    pub async fn upload(&self, contents: &[u8], remote_path: &str) -> Result<()> {
+        let dir = Path::new(remote_path).parent().map(|p| p.to_string_lossy().into_owned()).unwrap_or_default();
+        let mut cmd = self.ssh(false);
+        cmd.arg(format!("mkdir -p {} && cat > {}", shell_quote(if dir.is_empty() { "." } else { &dir }), shell_quote(remote_path)));
+        util::ensure_writable(format_args!("upload {} to {}", remote_path, self.destination))?;
+        if util::skip_in_dry_run(&cmd) {
+            return Ok(());
+        }
+        let mut child = AsyncCommand::from(cmd)
+            .stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped())
+            .spawn().context("Failed to start ssh")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(contents).await?;
+        }
+        let output = child.wait_with_output().await?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Failed to upload {} to {}: {}",
+                remote_path, self.destination, String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Quote one argument for a POSIX shell
+pub fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c)) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// The command line to forward, without `--remote`
+pub fn forwarded_args(args: &[OsString]) -> Vec<String> {
+    let mut forwarded = Vec::new();
+    let mut skip_value = false;
+    for arg in args {
+        if std::mem::take(&mut skip_value) {
+            continue;
+        }
+        let arg = arg.to_string_lossy();
+        if arg == "--remote" {
+            skip_value = true;
+        } else if !arg.starts_with("--remote=") {
+            forwarded.push(arg.into_owned());
+        }
+    }
+    forwarded
+}
+
+/// Shell command running `binary` with `args` as a forwarded session
+pub fn remote_command(binary: &str, args: &[String]) -> String {
+    let mut script = format!("{}=1 {}", REMOTE_ENV, binary);
+    for arg in args {
+        script.push(' ');
+        script.push_str(&shell_quote(arg));
+    }
+    script
+}
+
+/// Whether this process was started by a forwarding rcm
+pub fn inside() -> bool {
+    std::env::var_os(REMOTE_ENV).is_some()
+}
+
+/// `uname -sm` output for this build, as the remote would report it
+fn local_platform() -> String {
+    let os = match std::env::consts::OS {
+        "linux" => "Linux",
+        "macos" => "Darwin",
+        other => other,
+    };
+    let arch = match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("macos", "aarch64") => "arm64",
+        (_, arch) => arch,
+    };
+    format!("{} {}", os, arch)
+}
+
+/// C library a Linux binary links against
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Libc {
+    /// glibc with its (major, minor) version
+    Gnu(u32, u32),
+    Musl,
+}
+
+/// Read the C library from `ldd --version` output
+pub fn parse_libc(ldd: &str) -> Option<Libc> {
+    if ldd.to_lowercase().contains("musl") {
+        return Some(Libc::Musl);
+    }
+    // `ldd (GNU libc) 2.35`, `ldd (Ubuntu GLIBC 2.35-0ubuntu3) 2.35`
+    let first = ldd.lines().next()?;
+    let version = first.split_whitespace().last()?;
+    let (major, minor) = version.split_once('.')?;
+    Some(Libc::Gnu(major.parse().ok()?, minor.split(|c: char| !c.is_ascii_digit()).next()?.parse().ok()?))
+}
+
+/// Whether a binary built against `local` runs with `remote`
+///
+/// Rust musl binaries are static; glibc ones need at least the glibc they were built with.
+pub fn libc_compatible(local: Libc, remote: Option<Libc>) -> bool {
+    match (local, remote) {
+        (Libc::Musl, _) => true,
+        (Libc::Gnu(major, minor), Some(Libc::Gnu(r_major, r_minor))) => (r_major, r_minor) >= (major, minor),
+        _ => false,
+    }
+}
+
+/// The C library this rcm runs against, when on Linux
+async fn local_libc() -> Option<Libc> {
+    if cfg!(target_env = "musl") {
+        return Some(Libc::Musl);
+    }
+    if std::env::consts::OS != "linux" {
+        return None;
+    }
+    let output = AsyncCommand::new("ldd").arg("--version").output().await.ok()?;
+    parse_libc(&format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr)))
+}
+
+/// What the remote probe found
+#[derive(Debug, PartialEq)]
+struct Probe {
+    /// `deployed`, `path` or `missing`
+    kind: String,
+    version: Option<String>,
+    platform: String,
+    libc: Option<Libc>,
+}
+
+fn parse_probe(output: &str) -> Probe {
+    let (head, rest) = output.split_once("--platform").unwrap_or((output, ""));
+    let (platform, libc) = rest.split_once("--libc").unwrap_or((rest, ""));
+    let mut lines = head.lines().map(str::trim).filter(|l| !l.is_empty());
+    let kind = lines.next().unwrap_or("missing").to_string();
+    let version = if kind == "missing" { None } else { lines.next().map(|l| l.trim_start_matches("rcm ").to_string()) };
+    Probe { kind, version, platform: platform.trim().to_string(), libc: parse_libc(libc.trim()) }
+}
+
+/// Path of a usable rcm on the remote host, deploying this binary when needed
+pub async fn ensure_binary(target: &RemoteTarget) -> Result<String> {
+    let version = env!("CARGO_PKG_VERSION");
+    let probe = format!(
+        "if [ -x ~/{deploy} ]; then echo deployed; ~/{deploy} --version; \
+         elif command -v rcm >/dev/null 2>&1; then echo path; rcm --version; \
+         else echo missing; fi; echo --platform; uname -sm; \
+         echo --libc; (ldd --version 2>&1 || true) | head -n 2",
+        deploy = DEPLOY_PATH
+    );
+    let Probe { kind, version: remote_version, platform, libc } = parse_probe(&target.capture(&probe).await?);
+
+    match (kind.as_str(), remote_version.as_deref()) {
+        ("path", Some(found)) => {
+            if found != version {
+                tracing::warn!(remote = %target.destination, found, local = version, "remote rcm version differs");
+            }
+            return Ok("rcm".to_string());
+        }
+        ("deployed", Some(found)) if found == version => return Ok(format!("~/{}", DEPLOY_PATH)),
+        _ => {}
+    }
+
+    if platform != local_platform() {
+        return Err(anyhow!(
+            "{} is {} but this rcm was built for {}; install rcm there first",
+            target.destination, platform, local_platform()
+        ));
+    }
+    if let Some(local) = local_libc().await {
+        if !libc_compatible(local, libc) {
+            let found = libc.map(|l| format!("{:?}", l)).unwrap_or_else(|| "an unknown C library".to_string());
+            return Err(anyhow!(
+                "{} has {} but this rcm needs {:?} or newer; install rcm there first",
+                target.destination, found, local
+            ));
+        }
+    }
+
+    println!("{}", style(format!("🚚 Deploying rcm {} to {}:~/{}", version, target.destination, DEPLOY_PATH)).blue());
+    let exe = std::env::current_exe().context("Failed to locate the rcm binary")?;
+    let mut mkdir = target.ssh(false);
+    mkdir.arg(format!("mkdir -p ~/{}", DEPLOY_PATH.rsplit_once('/').map(|(dir, _)| dir).unwrap_or(".rcm")));
+    util::execute_mutation(&mut mkdir).await?;
+
+    let mut scp = Command::new("scp");
+    scp.arg("-q");
+    if let Some(port) = target.port {
+        scp.args(["-P", &port.to_string()]);
+    }
+    scp.arg("--").arg(&exe).arg(format!("{}:{}.tmp", target.destination, DEPLOY_PATH));
+    util::execute_mutation(&mut scp).await
+        .with_context(|| format!("Failed to copy rcm to {}", target.destination))?;
+
+    let mut install = target.ssh(false);
+    install.arg(format!("chmod +x ~/{deploy}.tmp && mv ~/{deploy}.tmp ~/{deploy}", deploy = DEPLOY_PATH));
+    util::execute_mutation(&mut install).await?;
+    Ok(format!("~/{}", DEPLOY_PATH))
+}
+
+/// Forward this invocation to the remote host and stream its output
+pub async fn run(spec: &str) -> Result<()> {
+    let target = RemoteTarget::parse(spec)?;
+    if !util::command_exists("ssh").await {
+        return Err(anyhow!("ssh not found; --remote needs an OpenSSH client"));
+    }
+
+    let binary = ensure_binary(&target).await?;
+    let args: Vec<OsString> = std::env::args_os().skip(1).collect();
+    let script = remote_command(&binary, &forwarded_args(&args));
+
+    let tty = std::io::IsTerminal::is_terminal(&std::io::stdin());
+    let mut cmd = target.ssh(tty);
+    cmd.arg(script);
+    println!("{}", style(format!("🌐 Running on {}", target.destination)).dim());
+    // The remote side decides what is allowed; only dry-run stops here
+    if util::skip_in_dry_run(&cmd) {
+        return Ok(());
+    }
+    let status = tokio::process::Command::from(cmd).status().await
+        .with_context(|| format!("Failed to run ssh to {}", target.destination))?;
+    if !status.success() {
+        return Err(anyhow!("Remote command on {} exited with {}", target.destination, status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remote() {
+        assert_eq!(RemoteTarget::parse("gpu@10.0.0.5:2222").unwrap(), RemoteTarget { destination: "gpu@10.0.0.5".to_string(), port: Some(2222) });
+        assert_eq!(RemoteTarget::parse("gpu-box").unwrap().port, None);
+        assert!(RemoteTarget::parse("host:ssh").is_err());
+        assert!(RemoteTarget::parse("-oProxyCommand=touch%20x").is_err());
+        assert!(RemoteTarget::parse("user@-oProxyCommand=x").is_err());
+
+        let args: Vec<String> = RemoteTarget::parse("gpu").unwrap().ssh(false)
+            .get_args().map(|a| a.to_string_lossy().into_owned()).collect();
+        assert_eq!(args, vec!["--", "gpu"]);
+    }
+
+    #[test]
+    fn test_probe_and_libc() {
+        let probe = parse_probe("path\nrcm 0.6.0\n--platform\nLinux x86_64\n--libc\nldd (Ubuntu GLIBC 2.31-0ubuntu9) 2.31\nCopyright\n");
+        assert_eq!(probe, Probe {
+            kind: "path".to_string(),
+            version: Some("0.6.0".to_string()),
+            platform: "Linux x86_64".to_string(),
+            libc: Some(Libc::Gnu(2, 31)),
+        });
+        assert_eq!(parse_probe("missing\n--platform\nLinux aarch64\n--libc\nmusl libc (aarch64)\n").libc, Some(Libc::Musl));
+
+        assert!(libc_compatible(Libc::Gnu(2, 31), Some(Libc::Gnu(2, 35))));
+        assert!(!libc_compatible(Libc::Gnu(2, 35), Some(Libc::Gnu(2, 31))));
+        assert!(!libc_compatible(Libc::Gnu(2, 31), Some(Libc::Musl)));
+        assert!(!libc_compatible(Libc::Gnu(2, 31), None));
+        assert!(libc_compatible(Libc::Musl, Some(Libc::Gnu(2, 17))));
+    }
+
+    #[test]
+    fn test_forwarded_args_are_quoted() {
+        let args: Vec<OsString> = ["--remote", "gpu", "gpt", "generate", "llama3", "it's"].iter().map(OsString::from).collect();
+        let forwarded = forwarded_args(&args);
+        assert_eq!(forwarded, vec!["gpt", "generate", "llama3", "it's"]);
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote("--format=json"), "--format=json");
+    }
+}
@@ -0,0 +1,368 @@
+//! Report command implementation
+//!
+//! `rcm report html` renders one self-contained HTML page (inline CSS, no
+//! scripts or external assets) covering dependencies, outdated packages,
+//! vulnerabilities, license violations and the GPT model inventory, for CI
+//! artifacts or static hosting. `rcm report badge` writes a dependency
+//! freshness badge as shields.io endpoint JSON and as a standalone SVG, so a
+//! README can embed either the hosted JSON or the committed image.
+
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use console::style;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use crate::commands::outdated::{self, OutdatedDependency};
+use crate::config::Config;
+use crate::dashboard::{self, DashboardReport};
+use crate::util;
+use crate::workspace::Workspace;
+
+/// GPT model registry relative to the workspace root
+const GPT_REGISTRY: &str = ".rcm/gpt-configs/registry.json";
+
+#[derive(Subcommand)]
+pub enum ReportCommands {
+    /// Write a self-contained HTML report
+    Html {
+        /// Output file
+        #[arg(long, short, default_value = "rcm-report.html")]
+        output: PathBuf,
+    },
+    /// Write a dependency freshness badge (shields.io endpoint JSON and SVG)
+    Badge {
+        /// Directory for rcm-badge.json and rcm-badge.svg
+        #[arg(long, short, default_value = ".rcm/badges")]
+        output: PathBuf,
+        /// Which files to write (json, svg, both)
+        #[arg(long, default_value = "both")]
+        format: String,
+    },
+}
+
+/// A registered GPT model, as listed in the report
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelEntry {
+    pub name: String,
+    pub format: String,
+    pub backend: String,
+    pub revision: Option<String>,
+    /// Source model for converted models
+    pub derived_from: Option<String>,
+    pub running: bool,
+}
+
+/// shields.io endpoint badge (`https://img.shields.io/endpoint?url=...`)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Badge {
+    pub schema_version: u8,
+    pub label: String,
+    pub message: String,
+    pub color: String,
+}
+
+/// Freshness badge: vulnerabilities win over outdated counts
+pub fn freshness_badge(total: usize, outdated: usize, vulnerable: usize) -> Badge {
+    let (message, color) = if vulnerable > 0 {
+        (format!("{} vulnerable", vulnerable), "red")
+    } else if total == 0 {
+        ("none".to_string(), "lightgrey")
+    } else if outdated == 0 {
+        ("up to date".to_string(), "brightgreen")
+    } else {
+        let fresh = (total.saturating_sub(outdated)) * 100 / total;
+        let color = match fresh {
+            90.. => "green",
+            75..=89 => "yellow",
+            50..=74 => "orange",
+            _ => "red",
+        };
+        (format!("{} of {} outdated", outdated, total), color)
+    };
+    Badge { schema_version: 1, label: "dependencies".to_string(), message, color: color.to_string() }
+}
+
+fn badge_hex(color: &str) -> &'static str {
+    match color {
+        "brightgreen" => "#4c1",
+        "green" => "#97ca00",
+        "yellow" => "#dfb317",
+        "orange" => "#fe7d37",
+        "red" => "#e05d44",
+        _ => "#9f9f9f",
+    }
+}
+
+/// Flat badge SVG; widths are estimated for Verdana 11px
+pub fn badge_svg(badge: &Badge) -> String {
+    let width = |text: &str| text.chars().count() as u32 * 7 + 10;
+    let (left, right) = (width(&badge.label), width(&badge.message));
+    let total = left + right;
+    format!(
+        concat!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{total}" height="20" role="img" aria-label="{label}: {message}">"##,
+            r##"<title>{label}: {message}</title>"##,
+            r##"<rect width="{left}" height="20" fill="#555"/><rect x="{left}" width="{right}" height="20" fill="{hex}"/>"##,
+            r##"<g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">"##,
+            r##"<text x="{lx}" y="14">{label}</text><text x="{rx}" y="14">{message}</text></g></svg>"##,
+        ),
+        total = total,
+        left = left,
+        right = right,
+        hex = badge_hex(&badge.color),
+        lx = left / 2,
+        rx = left + right / 2,
+        label = escape(&badge.label),
+        message = escape(&badge.message),
+    )
+}
+
+/// Escape text for HTML element content and attribute values
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Models from the workspace's GPT registry; empty when GPT is unused
+async fn model_inventory(workspace_root: &Path) -> Result<Vec<ModelEntry>> {
+    let path = workspace_root.join(GPT_REGISTRY);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let registry: Value = serde_json::from_str(&tokio::fs::read_to_string(&path).await?)?;
+    let text = |value: &Value, key: &str| value.get(key).and_then(Value::as_str).map(str::to_string);
+    let running = registry.get("active_models").and_then(Value::as_object);
+
+    let mut models: Vec<ModelEntry> = registry.get("models").and_then(Value::as_object).into_iter().flatten()
+        .map(|(name, model)| ModelEntry {
+            name: name.clone(),
+            format: text(model, "format").unwrap_or_else(|| "-".to_string()),
+            backend: text(model, "backend").unwrap_or_else(|| "-".to_string()),
+            revision: text(model, "revision"),
+            derived_from: model.get("lineage").and_then(|l| text(l, "source")),
+            running: running.is_some_and(|active| active.contains_key(name)),
+        })
+        .collect();
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(models)
+}
+
+fn table(html: &mut String, headers: &[&str], rows: Vec<Vec<String>>, empty: &str) {
+    if rows.is_empty() {
+        let _ = writeln!(html, "<p class=\"empty\">{}</p>", escape(empty));
+        return;
+    }
+    html.push_str("<table><thead><tr>");
+    for header in headers {
+        let _ = write!(html, "<th>{}</th>", escape(header));
+    }
+    html.push_str("</tr></thead><tbody>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            let _ = write!(html, "<td>{}</td>", escape(&cell));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</tbody></table>\n");
+}
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2rem auto;max-width:72rem;padding:0 1rem;color:#222}\
+h1{margin-bottom:.2rem}.meta{color:#666;margin-top:0}\
+.cards{display:flex;flex-wrap:wrap;gap:1rem;margin:1.5rem 0}\
+.card{border:1px solid #ddd;border-radius:6px;padding:.8rem 1.2rem;min-width:9rem}\
+.card b{display:block;font-size:1.6rem}.bad b{color:#c0392b}.ok b{color:#2e7d32}\
+table{border-collapse:collapse;width:100%;margin-bottom:1.5rem;font-size:.9rem}\
+th,td{border-bottom:1px solid #eee;padding:.35rem .6rem;text-align:left}th{background:#f6f6f6}\
+.empty{color:#2e7d32}.errors{color:#c0392b}";
+
+/// Render the HTML report
+pub fn render_html(
+    report: &DashboardReport,
+    dependencies: &[(String, String, String)],
+    outdated: &[OutdatedDependency],
+    models: &[ModelEntry],
+) -> String {
+    let mut html = String::new();
+    let _ = writeln!(html, "<!DOCTYPE html>\n<html lang=\"en\"><head><meta charset=\"utf-8\">");
+    let _ = writeln!(html, "<title>RCM report: {}</title><style>{}</style></head><body>", escape(&report.repository.name), STYLE);
+    let _ = writeln!(html, "<h1>{}</h1>", escape(&report.repository.name));
+    let revision = match (&report.repository.branch, &report.repository.commit) {
+        (Some(branch), Some(commit)) => format!("{} @ {} · ", branch, &commit[..commit.len().min(12)]),
+        _ => String::new(),
+    };
+    let _ = writeln!(html, "<p class=\"meta\">{}generated {} by rcm {}</p>",
+        escape(&revision), escape(&report.generated_at), escape(&report.rcm_version));
+
+    let card = |html: &mut String, label: &str, value: String, bad: bool| {
+        let _ = write!(html, "<div class=\"card {}\"><b>{}</b>{}</div>", if bad { "bad" } else { "ok" }, escape(&value), escape(label));
+    };
+    html.push_str("<div class=\"cards\">");
+    card(&mut html, "health score", format!("{:.0}", report.health_score), report.health_score < 70.0);
+    card(&mut html, "dependencies", dependencies.len().to_string(), false);
+    card(&mut html, "outdated", outdated.len().to_string(), !outdated.is_empty());
+    card(&mut html, "vulnerabilities", report.vulnerabilities.total.to_string(), report.vulnerabilities.total > 0);
+    card(&mut html, "license violations", report.licenses.violations.len().to_string(), !report.licenses.violations.is_empty());
+    card(&mut html, "models", models.len().to_string(), false);
+    html.push_str("</div>\n");
+
+    if !report.errors.is_empty() {
+        html.push_str("<ul class=\"errors\">");
+        for error in &report.errors {
+            let _ = write!(html, "<li>Not collected: {}</li>", escape(error));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    html.push_str("<h2>Vulnerabilities</h2>\n");
+    let mut vulnerabilities = report.vulnerabilities.items.clone();
+    vulnerabilities.sort_by_key(|v| std::cmp::Reverse(v.severity_level()));
+    table(&mut html, &["Severity", "Package", "Version", "Manager", "Advisory", "Title"],
+        vulnerabilities.iter().map(|v| vec![
+            v.severity.clone(),
+            v.package.clone(),
+            v.version.clone().unwrap_or_else(|| "-".to_string()),
+            v.manager.clone(),
+            std::iter::once(v.advisory.as_str()).chain(v.aliases.iter().map(String::as_str)).collect::<Vec<_>>().join(", "),
+            v.title.clone(),
+        ]).collect(),
+        &format!("No unaccepted vulnerabilities ({} accepted)", report.vulnerabilities.accepted));
+
+    html.push_str("<h2>Outdated</h2>\n");
+    table(&mut html, &["Package", "Manager", "Current", "Wanted", "Latest"],
+        outdated.iter().map(|d| vec![d.name.clone(), d.manager.clone(), d.current.clone(), d.wanted.clone(), d.latest.clone()]).collect(),
+        "All dependencies are up to date");
+
+    html.push_str("<h2>License violations</h2>\n");
+    table(&mut html, &["Package", "Version", "Manager", "License", "Verdict"],
+        report.licenses.violations.iter().map(|f| vec![
+            f.name.clone(),
+            f.version.clone(),
+            f.manager.clone(),
+            f.license.clone().unwrap_or_else(|| "-".to_string()),
+            f.verdict.label(),
+        ]).collect(),
+        &format!("{} package(s) checked, no violations", report.licenses.checked));
+
+    html.push_str("<h2>Models</h2>\n");
+    table(&mut html, &["Model", "Format", "Backend", "Revision", "Derived from", "Running"],
+        models.iter().map(|m| vec![
+            m.name.clone(),
+            m.format.clone(),
+            m.backend.clone(),
+            m.revision.clone().unwrap_or_else(|| "-".to_string()),
+            m.derived_from.clone().unwrap_or_else(|| "-".to_string()),
+            if m.running { "yes" } else { "no" }.to_string(),
+        ]).collect(),
+        "No GPT models registered");
+
+    html.push_str("<h2>Dependencies</h2>\n");
+    table(&mut html, &["Package", "Manager", "Version"],
+        dependencies.iter().map(|(name, manager, version)| vec![name.clone(), manager.clone(), version.clone()]).collect(),
+        "No dependencies declared");
+
+    html.push_str("</body></html>\n");
+    html
+}
+
+/// Declared dependencies as (name, manager, version), sorted
+fn dependency_rows(workspace: &Workspace) -> Vec<(String, String, String)> {
+    let mut rows: Vec<(String, String, String)> = workspace.list_dependencies()
+        .into_iter()
+        .map(|(name, spec)| (name.clone(), spec.manager.clone(), spec.version.clone()))
+        .collect();
+    rows.sort_by(|a, b| (&a.1, &a.0).cmp(&(&b.1, &b.0)));
+    rows
+}
+
+/// Handle report commands
+pub async fn handle_command(workspace: &Workspace, config: &Config, cmd: ReportCommands) -> Result<()> {
+    println!("{}", style("📊 Collecting report...").cyan().bold());
+    let mut report = dashboard::collect(workspace, &config.security.license_policy).await?;
+    let outdated = match outdated::collect(workspace, &workspace.enabled_managers()).await {
+        Ok(outdated) => outdated,
+        Err(e) => {
+            report.errors.push(format!("outdated: {}", e));
+            Vec::new()
+        }
+    };
+    for error in &report.errors {
+        println!("  {} {}", style("⚠").yellow(), error);
+    }
+    let dependencies = dependency_rows(workspace);
+
+    match cmd {
+        ReportCommands::Html { output } => {
+            let models = model_inventory(workspace.root()).await.unwrap_or_else(|e| {
+                report.errors.push(format!("models: {}", e));
+                Vec::new()
+            });
+            util::write_file(&output, render_html(&report, &dependencies, &outdated, &models)).await?;
+            println!("{}", style(format!("✅ Report written to {}", output.display())).green().bold());
+        }
+        ReportCommands::Badge { output, format } => {
+            let (json, svg) = match format.as_str() {
+                "json" => (true, false),
+                "svg" => (false, true),
+                "both" => (true, true),
+                _ => return Err(anyhow!("Unsupported format: {}. Use json, svg or both", format)),
+            };
+            let badge = freshness_badge(dependencies.len(), outdated.len(), report.vulnerabilities.total);
+            if !util::is_dry_run() {
+                tokio::fs::create_dir_all(&output).await?;
+            }
+            if json {
+                let path = output.join("rcm-badge.json");
+                util::write_file(&path, serde_json::to_string_pretty(&badge)?).await?;
+                println!("✅ {} (serve it and use https://img.shields.io/endpoint?url=<url>)", path.display());
+            }
+            if svg {
+                let path = output.join("rcm-badge.svg");
+                util::write_file(&path, badge_svg(&badge)).await?;
+                println!("✅ {} (embed with ![dependencies]({}))", path.display(), path.display());
+            }
+            println!("   dependencies: {}", badge.message);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_freshness_badge() {
+        assert_eq!(freshness_badge(20, 0, 0).message, "up to date");
+        assert_eq!(freshness_badge(20, 0, 0).color, "brightgreen");
+        assert_eq!(freshness_badge(20, 1, 0).color, "green");
+        assert_eq!(freshness_badge(20, 12, 0).color, "red");
+        assert_eq!(freshness_badge(20, 1, 2).message, "2 vulnerable");
+        assert_eq!(freshness_badge(0, 0, 0).color, "lightgrey");
+    }
+
+    #[test]
+    fn test_badge_json_matches_shields_schema() {
+        let json = serde_json::to_value(freshness_badge(4, 1, 0)).unwrap();
+        assert_eq!(json["schemaVersion"], 1);
+        assert_eq!(json["message"], "1 of 4 outdated");
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("<a href=\"x\">&'</a>"), "&lt;a href=&quot;x&quot;&gt;&amp;&#39;&lt;/a&gt;");
+    }
+}
@@ -0,0 +1,142 @@
+//! Resolve-conflicts command implementation
+//!
+//! Lockfiles are generated, so git merge conflicts in them are best resolved
+//! by regenerating rather than editing. For each conflicted lockfile the
+//! chosen side of the merge is checked out as a starting point, the manager's
+//! resolver is re-run against the merged manifest (keeping every pin that
+//! still satisfies it), and a frozen install check confirms the result
+//! matches the manifest. `rcm.lock` is rebuilt last from the native locks.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use std::path::Path;
+use std::process::Command;
+use crate::lockfile::{LockManager, LOCKFILE_NAME};
+use crate::util;
+use crate::workspace::Workspace;
+
+/// Lockfiles RCM can regenerate: (lockfile, manifest, manager)
+const LOCKFILES: &[(&str, &str, &str)] = &[
+    ("Cargo.lock", "Cargo.toml", "cargo"),
+    ("package-lock.json", "package.json", "npm"),
+    ("composer.lock", "composer.json", "composer"),
+];
+
+/// Whether text contains git conflict markers at the start of a line
+pub fn has_conflict_markers(content: &str) -> bool {
+    let mut opened = false;
+    for line in content.lines() {
+        if line.starts_with("<<<<<<< ") || line == "<<<<<<<" {
+            opened = true;
+        } else if opened && (line.starts_with(">>>>>>> ") || line == ">>>>>>>") {
+            return true;
+        }
+    }
+    false
+}
+
+fn conflicted(root: &Path, file: &str) -> bool {
+    std::fs::read_to_string(root.join(file)).map_or(false, |content| has_conflict_markers(&content))
+}
+
+fn command(root: &Path, program: &str, args: &[&str]) -> Command {
+    let mut cmd = Command::new(program);
+    cmd.current_dir(root).args(args);
+    cmd
+}
+
+/// Re-run the resolver for one manager against the merged manifest
+fn resolve_command(root: &Path, manager: &str) -> Command {
+    match manager {
+        // Adds what the merged Cargo.toml needs without bumping other pins
+        "cargo" => command(root, "cargo", &["update", "--workspace"]),
+        "npm" => command(root, "npm", &["install", "--package-lock-only", "--ignore-scripts", "--no-audit", "--no-fund"]),
+        _ => command(root, "composer", &["update", "--no-install", "--no-scripts", "--minimal-changes", "--no-interaction"]),
+    }
+}
+
+/// Fails when the lockfile does not match the manifest exactly
+fn frozen_check(root: &Path, manager: &str) -> Command {
+    match manager {
+        "cargo" => command(root, "cargo", &["fetch", "--locked"]),
+        "npm" => command(root, "npm", &["ci", "--dry-run", "--ignore-scripts", "--no-audit", "--no-fund"]),
+        _ => command(root, "composer", &["validate", "--no-check-all", "--no-check-publish", "--strict"]),
+    }
+}
+
+/// Detect conflicted lockfiles and regenerate them
+pub async fn run(workspace: &Workspace, check: bool, base: &str, verify: bool) -> Result<()> {
+    let side = match base {
+        "ours" => "--ours",
+        "theirs" => "--theirs",
+        other => return Err(anyhow!("Unknown base '{}'. Use ours or theirs", other)),
+    };
+    let root = workspace.root();
+
+    let native: Vec<(&str, &str, &str)> = LOCKFILES.iter().copied().filter(|(lock, _, _)| conflicted(root, lock)).collect();
+    let rcm_lock = conflicted(root, LOCKFILE_NAME);
+    if native.is_empty() && !rcm_lock {
+        println!("{}", style("✅ No lockfile conflicts").green().bold());
+        return Ok(());
+    }
+
+    println!("{}", style("🔀 Conflicted lockfiles:").cyan().bold());
+    for (lock, _, manager) in &native {
+        println!("  • {} ({})", lock, manager);
+    }
+    if rcm_lock {
+        println!("  • {}", LOCKFILE_NAME);
+    }
+    if check {
+        return Err(anyhow!("{} lockfile(s) have merge conflicts. Run 'rcm resolve-conflicts' to regenerate them", native.len() + rcm_lock as usize));
+    }
+
+    // The resolver can only work from a merged manifest
+    for (_, manifest, _) in &native {
+        if conflicted(root, manifest) {
+            return Err(anyhow!("{} still has conflict markers; resolve it by hand first", manifest));
+        }
+    }
+
+    let mut resolved = Vec::new();
+    for (lock, _, manager) in &native {
+        println!("{}", style(format!("🔧 Regenerating {} from the {} side...", lock, base)).blue());
+        util::execute_mutation(&mut command(root, "git", &["checkout", side, "--", lock])).await
+            .with_context(|| format!("Failed to check out {} {}. Is a merge in progress?", base, lock))?;
+        util::execute_mutation(&mut resolve_command(root, manager)).await
+            .with_context(|| format!("Failed to re-resolve {}", lock))?;
+
+        if verify && !util::is_dry_run() {
+            util::execute_command(&mut frozen_check(root, manager)).await
+                .with_context(|| format!("{} still does not match the merged manifest", lock))?;
+            println!("  {} {} passes a frozen install check", style("✓").green(), lock);
+        }
+        resolved.push(lock.to_string());
+    }
+
+    if rcm_lock || (!resolved.is_empty() && root.join(LOCKFILE_NAME).exists()) {
+        println!("{}", style(format!("🔒 Rebuilding {}...", LOCKFILE_NAME)).blue());
+        let lock_manager = LockManager::new(root);
+        let lock = lock_manager.collect(workspace, &workspace.enabled_managers()).await?;
+        lock_manager.save(&lock).await?;
+        resolved.push(LOCKFILE_NAME.to_string());
+    }
+
+    println!("{}", style(format!("✅ Resolved {} lockfile(s)", resolved.len())).green().bold());
+    println!("   Review and stage them: git add {}", resolved.join(" "));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_conflict_markers() {
+        let conflicted = "[[package]]\n<<<<<<< HEAD\nversion = \"1.0.1\"\n=======\nversion = \"1.0.2\"\n>>>>>>> feature\n";
+        assert!(has_conflict_markers(conflicted));
+        assert!(!has_conflict_markers("[[package]]\nname = \"serde\"\n"));
+        // A separator line alone is ordinary content
+        assert!(!has_conflict_markers("=======\n"));
+    }
+}
@@ -0,0 +1,438 @@
+//! Software bill of materials for RCM
+//!
+//! Collects every component of the workspace (Cargo.lock, package-lock.json,
+//! composer.lock, system packages and models in the GPT registry) with hashes and
+//! licenses, and renders it as CycloneDX 1.5 or SPDX 2.3 JSON.
+
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use tokio::fs;
+use crate::isolation;
+use crate::lockfile::{LockManager, LockedPackage};
+use crate::system::SystemPackageManager;
+use crate::util;
+use crate::workspace::Workspace;
+
+/// GPT model registry written by `rcm gpt`
+pub const MODEL_REGISTRY: &str = ".rcm/gpt-configs/registry.json";
+
+/// A hash in SBOM notation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComponentHash {
+    /// SHA-1, SHA-256 or SHA-512
+    pub alg: String,
+    /// Lowercase hex digest
+    pub content: String,
+}
+
+/// One component of the workspace
+#[derive(Debug, Clone, Serialize)]
+pub struct Component {
+    pub manager: String,
+    pub name: String,
+    pub version: String,
+    pub purl: String,
+    /// SPDX license expression when known
+    pub license: Option<String>,
+    pub hashes: Vec<ComponentHash>,
+    /// Where the component was downloaded from
+    pub source: Option<String>,
+    pub dev: bool,
+    /// library, or machine-learning-model for GPT models
+    pub kind: String,
+}
+
+/// Package URL for a component
+pub fn purl(manager: &str, name: &str, version: &str) -> String {
+    match manager {
+        // Scoped npm packages encode the @ of the namespace
+        "npm" => format!("pkg:npm/{}@{}", name.replacen('@', "%40", 1), version),
+        "cargo" => format!("pkg:cargo/{}@{}", name, version),
+        "composer" => format!("pkg:composer/{}@{}", name, version),
+        "bundler" => format!("pkg:gem/{}@{}", name, version),
+        "go" => format!("pkg:golang/{}@{}", name, version),
+        "apt" => format!("pkg:deb/{}@{}", name, version),
+        "dnf" | "yum" | "zypper" => format!("pkg:rpm/{}@{}", name, version),
+        "brew" => format!("pkg:brew/{}@{}", name, version),
+        "huggingface" => format!("pkg:huggingface/{}@{}", name, version),
+        _ => format!("pkg:generic/{}@{}", name, version),
+    }
+}
+
+/// Normalize old-style `MIT/Apache-2.0` licenses to SPDX expressions
+pub fn normalize_license(license: &str) -> String {
+    license.split('/').map(str::trim).collect::<Vec<_>>().join(" OR ")
+}
+
+/// Decode standard base64 to lowercase hex (npm `integrity` values)
+pub fn base64_to_hex(input: &str) -> Option<String> {
+    let bytes = BASE64.decode(input.trim()).ok()?;
+    Some(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Hashes recorded for a locked package
+///
+/// `composer_shasums` holds the dist shasums from composer.lock; rcm.lock falls back to
+/// the git reference for composer packages without one, and that is not a file hash.
+fn package_hashes(pkg: &LockedPackage, composer_shasums: &HashMap<(String, String), String>) -> Vec<ComponentHash> {
+    let Some(checksum) = pkg.checksum.as_deref() else {
+        return Vec::new();
+    };
+    let hash = |alg: &str, content: String| vec![ComponentHash { alg: alg.to_string(), content }];
+    match pkg.manager.as_str() {
+        "cargo" => hash("SHA-256", checksum.to_string()),
+        "npm" => {
+            let Some((alg, digest)) = checksum.split_once('-') else { return Vec::new() };
+            let alg = match alg {
+                "sha512" => "SHA-512",
+                "sha256" => "SHA-256",
+                "sha1" => "SHA-1",
+                _ => return Vec::new(),
+            };
+            base64_to_hex(digest).map(|hex| hash(alg, hex)).unwrap_or_default()
+        }
+        "composer" if composer_shasums.get(&(pkg.name.clone(), pkg.version.clone())).map(String::as_str) == Some(checksum) => {
+            hash("SHA-1", checksum.to_string())
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Licenses keyed by (name, version) from cargo metadata
+async fn cargo_licenses(root: &Path) -> HashMap<(String, String), String> {
+    let mut licenses = HashMap::new();
+    let Ok(output) = Command::new("cargo")
+        .current_dir(root)
+        .envs(isolation::env_vars(root))
+        .args(["metadata", "--format-version", "1", "--locked"])
+        .output()
+    else {
+        return licenses;
+    };
+    let metadata: Value = serde_json::from_slice(&output.stdout).unwrap_or_default();
+    for pkg in metadata["packages"].as_array().into_iter().flatten() {
+        if let (Some(name), Some(version), Some(license)) =
+            (pkg["name"].as_str(), pkg["version"].as_str(), pkg["license"].as_str())
+        {
+            licenses.insert((name.to_string(), version.to_string()), normalize_license(license));
+        }
+    }
+    licenses
+}
+
+/// Licenses keyed by (name, version) from package-lock.json
+pub fn npm_licenses(package_lock: &Value) -> HashMap<(String, String), String> {
+    let mut licenses = HashMap::new();
+    for (path, entry) in package_lock["packages"].as_object().into_iter().flatten() {
+        let Some(name) = path.rsplit("node_modules/").next().filter(|_| !path.is_empty()) else { continue };
+        // Older packages declare `{ "type": "MIT" }`
+        let license = entry["license"].as_str().or(entry["license"]["type"].as_str());
+        if let (Some(version), Some(license)) = (entry["version"].as_str(), license) {
+            licenses.insert((name.to_string(), version.to_string()), license.to_string());
+        }
+    }
+    licenses
+}
+
+/// Licenses keyed by (name, version) from composer.lock
+pub fn composer_licenses(composer_lock: &Value) -> HashMap<(String, String), String> {
+    let mut licenses = HashMap::new();
+    let packages = composer_lock["packages"].as_array().into_iter().flatten()
+        .chain(composer_lock["packages-dev"].as_array().into_iter().flatten());
+    for pkg in packages {
+        let declared: Vec<&str> = pkg["license"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+        if let (Some(name), Some(version), false) = (pkg["name"].as_str(), pkg["version"].as_str(), declared.is_empty()) {
+            licenses.insert((name.to_string(), version.to_string()), declared.join(" OR "));
+        }
+    }
+    licenses
+}
+
+/// Dist shasums keyed by (name, version) from composer.lock
+pub fn composer_shasums(composer_lock: &Value) -> HashMap<(String, String), String> {
+    let packages = composer_lock["packages"].as_array().into_iter().flatten()
+        .chain(composer_lock["packages-dev"].as_array().into_iter().flatten());
+    packages
+        .filter_map(|pkg| {
+            let shasum = pkg["dist"]["shasum"].as_str().filter(|s| !s.is_empty())?;
+            Some(((pkg["name"].as_str()?.to_string(), pkg["version"].as_str()?.to_string()), shasum.to_string()))
+        })
+        .collect()
+}
+
+async fn read_json(path: &Path) -> Value {
+    match fs::read_to_string(path).await {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+        Err(_) => Value::Null,
+    }
+}
+
+/// Models recorded in the GPT registry
+async fn model_components(root: &Path) -> Result<Vec<Component>> {
+    let registry = read_json(&root.join(MODEL_REGISTRY)).await;
+    let mut components = Vec::new();
+    for (name, model) in registry["models"].as_object().into_iter().flatten() {
+        let version = model["version"].as_str().unwrap_or("latest").to_string();
+        let path = model["model_path"].as_str().map(|p| root.join(p));
+        // Directories (safetensors shards) are described by their source instead of a single hash
+        let hashes = match &path {
+            Some(path) if path.is_file() => vec![ComponentHash {
+                alg: "SHA-256".to_string(),
+                content: util::get_file_hash(path).await?,
+            }],
+            _ => Vec::new(),
+        };
+        let hub = if name.contains('/') { "huggingface" } else { "generic" };
+        components.push(Component {
+            manager: "gpt".to_string(),
+            name: name.clone(),
+            purl: purl(hub, name, &version),
+            version,
+            license: None,
+            hashes,
+            source: (hub == "huggingface").then(|| format!("https://huggingface.co/{}", name)),
+            dev: false,
+            kind: "machine-learning-model".to_string(),
+        });
+    }
+    Ok(components)
+}
+
+/// Collect every component of the workspace for the given managers
+///
+/// `gpt` selects the models in the GPT registry.
+pub async fn collect(workspace: &Workspace, managers: &[String]) -> Result<Vec<Component>> {
+    let root = workspace.root();
+    let lock_managers: Vec<String> = managers.iter().filter(|m| m.as_str() != "gpt").cloned().collect();
+    let lock = LockManager::new(root).collect(workspace, &lock_managers).await
+        .context("Failed to resolve workspace packages")?;
+
+    let mut licenses = HashMap::new();
+    if lock_managers.iter().any(|m| m == "cargo") {
+        licenses.extend(cargo_licenses(root).await);
+    }
+    licenses.extend(npm_licenses(&read_json(&root.join("package-lock.json")).await));
+    let composer_lock = read_json(&root.join("composer.lock")).await;
+    licenses.extend(composer_licenses(&composer_lock));
+    let composer_shasums = composer_shasums(&composer_lock);
+
+    let system_purl = if lock_managers.iter().any(|m| m == "system") {
+        SystemPackageManager::detect().await.ok().map(|m| m.command().to_string())
+    } else {
+        None
+    };
+
+    let mut components: Vec<Component> = lock.packages.iter()
+        .filter(|pkg| pkg.version != "not-installed")
+        .map(|pkg| {
+            let ecosystem = if pkg.manager == "system" { system_purl.as_deref().unwrap_or("generic") } else { &pkg.manager };
+            Component {
+                manager: pkg.manager.clone(),
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                purl: purl(ecosystem, &pkg.name, &pkg.version),
+                license: licenses.get(&(pkg.name.clone(), pkg.version.clone())).cloned(),
+                hashes: package_hashes(pkg, &composer_shasums),
+                source: pkg.source.clone(),
+                dev: pkg.dev,
+                kind: "library".to_string(),
+            }
+        })
+        .collect();
+
+    if managers.iter().any(|m| m == "gpt") {
+        components.extend(model_components(root).await?);
+    }
+    Ok(components)
+}
+
+fn spdx_license(license: &str) -> Value {
+    let simple = license.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'));
+    if simple {
+        json!({ "license": { "id": license } })
+    } else {
+        json!({ "expression": license })
+    }
+}
+
+/// CycloneDX 1.5 JSON document
+pub fn to_cyclonedx(name: &str, components: &[Component]) -> Value {
+    let components: Vec<Value> = components.iter().map(|c| {
+        let mut component = json!({
+            "type": c.kind,
+            "bom-ref": c.purl,
+            "name": c.name,
+            "version": c.version,
+            "purl": c.purl,
+            "scope": if c.dev { "optional" } else { "required" },
+            "hashes": c.hashes,
+            "properties": [{ "name": "rcm:manager", "value": c.manager }],
+        });
+        if let Some(license) = &c.license {
+            component["licenses"] = json!([spdx_license(license)]);
+        }
+        if let Some(source) = &c.source {
+            component["externalReferences"] = json!([{ "type": "distribution", "url": source }]);
+        }
+        component
+    }).collect();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{}", uuid::Uuid::new_v4()),
+        "version": 1,
+        "metadata": {
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "tools": [{ "vendor": "RCM", "name": "rcm", "version": env!("CARGO_PKG_VERSION") }],
+            "component": { "type": "application", "bom-ref": "workspace", "name": name },
+        },
+        "components": components,
+    })
+}
+
+/// SPDX 2.3 JSON document
+pub fn to_spdx(name: &str, components: &[Component]) -> Value {
+    let packages: Vec<Value> = components.iter().enumerate().map(|(i, c)| json!({
+        "SPDXID": format!("SPDXRef-Package-{}", i + 1),
+        "name": c.name,
+        "versionInfo": c.version,
+        "downloadLocation": c.source.as_deref().unwrap_or("NOASSERTION"),
+        "filesAnalyzed": false,
+        "licenseConcluded": "NOASSERTION",
+        "licenseDeclared": c.license.as_deref().unwrap_or("NOASSERTION"),
+        "copyrightText": "NOASSERTION",
+        "checksums": c.hashes.iter().map(|h| json!({
+            "algorithm": h.alg.replace('-', ""),
+            "checksumValue": h.content,
+        })).collect::<Vec<_>>(),
+        "externalRefs": [{
+            "referenceCategory": "PACKAGE-MANAGER",
+            "referenceType": "purl",
+            "referenceLocator": c.purl,
+        }],
+        "comment": format!("rcm manager: {}", c.manager),
+    })).collect();
+
+    let mut relationships = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": "SPDXRef-Workspace",
+    })];
+    // `A DEPENDS_ON B` reads from the dependent, `A DEV_DEPENDENCY_OF B` from the dependency
+    relationships.extend(components.iter().enumerate().map(|(i, c)| {
+        let package = format!("SPDXRef-Package-{}", i + 1);
+        if c.dev {
+            json!({
+                "spdxElementId": package,
+                "relationshipType": "DEV_DEPENDENCY_OF",
+                "relatedSpdxElement": "SPDXRef-Workspace",
+            })
+        } else {
+            json!({
+                "spdxElementId": "SPDXRef-Workspace",
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": package,
+            })
+        }
+    }));
+
+    let mut all_packages = vec![json!({
+        "SPDXID": "SPDXRef-Workspace",
+        "name": name,
+        "downloadLocation": "NOASSERTION",
+        "filesAnalyzed": false,
+    })];
+    all_packages.extend(packages);
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": name,
+        "documentNamespace": format!("https://spdx.org/spdxdocs/{}-{}", name, uuid::Uuid::new_v4()),
+        "creationInfo": {
+            "created": chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            "creators": [format!("Tool: rcm-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": all_packages,
+        "relationships": relationships,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_purl_and_hashes() {
+        assert_eq!(purl("npm", "@types/node", "20.1.0"), "pkg:npm/%40types/node@20.1.0");
+        assert_eq!(purl("apt", "ffmpeg", "7:6.1"), "pkg:deb/ffmpeg@7:6.1");
+        assert_eq!(normalize_license("MIT/Apache-2.0"), "MIT OR Apache-2.0");
+        assert_eq!(base64_to_hex("3q2+7w==").as_deref(), Some("deadbeef"));
+        assert_eq!(base64_to_hex("3q2+7w=").as_deref(), None);
+
+        let pkg = LockedPackage {
+            name: "left-pad".to_string(),
+            version: "1.3.0".to_string(),
+            manager: "npm".to_string(),
+            source: None,
+            checksum: Some("sha512-3q2+7w==".to_string()),
+            dev: true,
+        };
+        assert_eq!(
+            package_hashes(&pkg, &HashMap::new()),
+            vec![ComponentHash { alg: "SHA-512".into(), content: "deadbeef".into() }]
+        );
+
+        let lock = json!({ "packages": [
+            { "name": "monolog/monolog", "version": "3.5.0", "dist": { "shasum": "abc" } },
+            { "name": "acme/tool", "version": "dev-main", "dist": { "shasum": "" } },
+        ]});
+        let shasums = composer_shasums(&lock);
+        let composer = |name: &str, version: &str, checksum: &str| LockedPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            manager: "composer".to_string(),
+            source: Some("https://api.github.com/repos/acme/tool/zipball/0123abcd".to_string()),
+            checksum: Some(checksum.to_string()),
+            dev: false,
+        };
+        assert_eq!(package_hashes(&composer("monolog/monolog", "3.5.0", "abc"), &shasums).len(), 1);
+        // A git reference is not a SHA-1 of the package
+        assert!(package_hashes(&composer("acme/tool", "dev-main", "0123abcd"), &shasums).is_empty());
+    }
+
+    #[test]
+    fn test_spdx_relationships() {
+        let component = Component {
+            manager: "cargo".to_string(),
+            name: "anyhow".to_string(),
+            version: "1.0.75".to_string(),
+            purl: purl("cargo", "anyhow", "1.0.75"),
+            license: Some("MIT OR Apache-2.0".to_string()),
+            hashes: vec![ComponentHash { alg: "SHA-256".into(), content: "abc".into() }],
+            source: None,
+            dev: false,
+            kind: "library".to_string(),
+        };
+        let dev = Component { name: "insta".to_string(), dev: true, ..component.clone() };
+        let doc = to_spdx("app", &[component.clone(), dev]);
+        assert_eq!(doc["packages"][1]["checksums"][0]["algorithm"], "SHA256");
+        assert_eq!(doc["relationships"][1]["spdxElementId"], "SPDXRef-Workspace");
+        assert_eq!(doc["relationships"][1]["relationshipType"], "DEPENDS_ON");
+        assert_eq!(doc["relationships"][1]["relatedSpdxElement"], "SPDXRef-Package-1");
+        assert_eq!(doc["relationships"][2]["spdxElementId"], "SPDXRef-Package-2");
+        assert_eq!(doc["relationships"][2]["relationshipType"], "DEV_DEPENDENCY_OF");
+        assert_eq!(doc["relationships"][2]["relatedSpdxElement"], "SPDXRef-Workspace");
+
+        let bom = to_cyclonedx("app", &[component]);
+        assert_eq!(bom["components"][0]["licenses"][0]["expression"], "MIT OR Apache-2.0");
+    }
+}
@@ -0,0 +1,50 @@
+//! SBOM command implementation
+//!
+//! Writes a CycloneDX or SPDX bill of materials for the workspace
+
+use anyhow::{anyhow, Result};
+use console::style;
+use std::path::Path;
+use crate::sbom;
+use crate::util;
+use crate::workspace::Workspace;
+
+/// Generate the SBOM and write it to `out`
+pub async fn run(workspace: &Workspace, out: &str, format: &str, managers: Option<Vec<String>>) -> Result<()> {
+    let managers = managers.unwrap_or_else(|| {
+        let mut managers = workspace.enabled_managers();
+        if workspace.root().join(sbom::MODEL_REGISTRY).exists() {
+            managers.push("gpt".to_string());
+        }
+        managers
+    });
+    if managers.is_empty() {
+        return Err(anyhow!("No package managers enabled. Run 'rcm init' to configure managers."));
+    }
+
+    println!("{}", style("📋 Generating SBOM...").cyan().bold());
+    let components = sbom::collect(workspace, &managers).await?;
+    let name = workspace.root()
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("workspace");
+
+    let document = match format {
+        "cyclonedx" => sbom::to_cyclonedx(name, &components),
+        "spdx" => sbom::to_spdx(name, &components),
+        "json" => serde_json::to_value(&components)?,
+        _ => return Err(anyhow!("Unsupported SBOM format: {}. Use cyclonedx, spdx or json", format)),
+    };
+    util::write_file(Path::new(out), serde_json::to_string_pretty(&document)?).await?;
+
+    let unlicensed = components.iter().filter(|c| c.license.is_none()).count();
+    let unhashed = components.iter().filter(|c| c.hashes.is_empty()).count();
+    println!(
+        "{}",
+        style(format!("✅ {} components written to {} ({})", components.len(), out, format)).green().bold()
+    );
+    if unlicensed > 0 || unhashed > 0 {
+        println!("   {} without a known license, {} without a hash", unlicensed, unhashed);
+    }
+    Ok(())
+}
@@ -0,0 +1,448 @@
+//! Secrets management for RCM
+//!
+//! Resolves `secret:<name>` references at spawn and request time and keeps
+//! resolved values out of spec files and printed output. Values live in the OS
+//! keyring (through the `keyring` crate, so they never appear on a command
+//! line), in `RCM_SECRET_*` variables only, or in a passphrase-encrypted file,
+//! per `security.secret_backend`; the environment override always wins.
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use console::style;
+use dialoguer::Password;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use sha2::Sha256;
+use tokio::io::AsyncWriteExt;
+use crate::config::Config;
+use crate::workspace::Workspace;
+use crate::redact::register as register_redaction;
+
+/// Prefix marking an environment value as a keychain reference
+pub const SECRET_PREFIX: &str = "secret:";
+
+/// Keychain service name under which RCM secrets are stored
+pub const KEYCHAIN_SERVICE: &str = "rcm";
+
+/// Environment override prefix (`RCM_SECRET_OPENAI` for `secret:openai`), used in CI
+pub const ENV_OVERRIDE_PREFIX: &str = "RCM_SECRET_";
+
+/// Selects the backend over `security.secret_backend`
+pub const BACKEND_ENV: &str = "RCM_SECRET_BACKEND";
+
+/// Passphrase for the encrypted file backend; prompted for when unset
+pub const PASSPHRASE_ENV: &str = "RCM_SECRETS_PASSPHRASE";
+
+/// Encrypted store under the data directory
+const SECRETS_FILE: &str = "secrets.enc";
+
+/// Header of the secrets file format
+const MAGIC: &[u8] = b"RCMSEC1\0";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KDF_ROUNDS: u32 = 200_000;
+
+/// Where secret values are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretBackend {
+    /// macOS Keychain, Windows Credential Manager or the Secret Service
+    Keychain,
+    /// `RCM_SECRET_<NAME>` variables only, for CI
+    Env,
+    /// `secrets.enc` in the data directory, PBKDF2 + ChaCha20-Poly1305
+    File,
+}
+
+impl SecretBackend {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "keychain" | "keyring" => Ok(Self::Keychain),
+            "env" => Ok(Self::Env),
+            "file" => Ok(Self::File),
+            other => Err(anyhow!("Unknown secret backend '{}'. Use keychain, env or file", other)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Keychain => "keychain",
+            Self::Env => "env",
+            Self::File => "file",
+        }
+    }
+
+    /// `RCM_SECRET_BACKEND`, else `security.secret_backend` from `config`
+    pub fn configured(config: &Config) -> Result<Self> {
+        match std::env::var(BACKEND_ENV) {
+            Ok(name) => Self::parse(&name),
+            Err(_) => Self::parse(&config.security.secret_backend),
+        }
+    }
+
+    /// `RCM_SECRET_BACKEND`, else this run's config
+    pub async fn current() -> Result<Self> {
+        if let Ok(name) = std::env::var(BACKEND_ENV) {
+            return Self::parse(&name);
+        }
+        Self::parse(&settings().await?.backend)
+    }
+}
+
+/// Store settings from the command's resolved config
+struct StoreSettings {
+    backend: String,
+    file: PathBuf,
+}
+
+impl StoreSettings {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            backend: config.security.secret_backend.clone(),
+            file: config.data_dir().join(SECRETS_FILE),
+        }
+    }
+}
+
+static SETTINGS: OnceLock<StoreSettings> = OnceLock::new();
+
+/// Use the config the command was started with (`--config`, layers, profile) for the store
+pub fn init(config: &Config) {
+    let _ = SETTINGS.set(StoreSettings::from_config(config));
+}
+
+/// The settings from [`init`], or this run's layered config when it was not called
+async fn settings() -> Result<&'static StoreSettings> {
+    if let Some(settings) = SETTINGS.get() {
+        return Ok(settings);
+    }
+    let config = Config::load(None).await
+        .context("Failed to load the config that selects the secret backend")?;
+    Ok(SETTINGS.get_or_init(|| StoreSettings::from_config(&config)))
+}
+
+#[derive(Subcommand)]
+pub enum SecretCommands {
+    /// Store a secret in the OS keychain
+    Set {
+        /// Secret name (referenced as `secret:<name>`)
+        name: String,
+        /// Read the value from stdin instead of prompting
+        #[arg(long)]
+        stdin: bool,
+    },
+    /// Check whether a secret can be resolved (never prints the value)
+    Check {
+        /// Secret name
+        name: String,
+    },
+    /// Delete a secret from the OS keychain
+    Remove {
+        /// Secret name
+        name: String,
+    },
+}
+
+/// Secret name if `value` is a `secret:<name>` reference
+pub fn secret_ref(value: &str) -> Option<&str> {
+    value.strip_prefix(SECRET_PREFIX).map(str::trim).filter(|name| !name.is_empty())
+}
+
+/// `RCM_SECRET_<NAME>` variable that overrides a secret
+pub fn env_override_name(name: &str) -> String {
+    let normalized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    format!("{}{}", ENV_OVERRIDE_PREFIX, normalized)
+}
+
+/// Look up a secret from the environment override or the configured backend
+pub async fn get_secret(name: &str) -> Result<String> {
+    if let Ok(value) = std::env::var(env_override_name(name)) {
+        register_redaction(&value);
+        return Ok(value);
+    }
+
+    let value = match SecretBackend::current().await? {
+        SecretBackend::Keychain => keychain_get(name).await?,
+        SecretBackend::Env => None,
+        SecretBackend::File => read_file_store().await?.remove(name),
+    };
+    let value = value.filter(|v| !v.is_empty()).ok_or_else(|| anyhow!(
+        "Secret '{}' not found. Store it with `rcm secret set {}` or set {}",
+        name, name, env_override_name(name)
+    ))?;
+
+    register_redaction(&value);
+    Ok(value)
+}
+
+/// Resolve a single value, passing non-references through
+pub async fn resolve_value(value: &str) -> Result<String> {
+    match secret_ref(value) {
+        Some(name) => get_secret(name).await,
+        None => Ok(value.to_string()),
+    }
+}
+
+/// The OS keyring entry holding secret `name`
+fn keyring_entry(name: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, name)
+        .with_context(|| format!("Failed to open the OS keyring for secret '{}'", name))
+}
+
+/// Run a blocking keyring call off the async worker threads
+async fn with_keyring<T: Send + 'static>(name: &str, f: impl FnOnce(keyring::Entry) -> Result<T> + Send + 'static) -> Result<T> {
+    let entry = keyring_entry(name)?;
+    tokio::task::spawn_blocking(move || f(entry)).await?
+}
+
+async fn keychain_get(name: &str) -> Result<Option<String>> {
+    let label = name.to_string();
+    with_keyring(name, move |entry| match entry.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(anyhow!("Failed to query the OS keyring for secret '{}': {}", label, e)),
+    }).await
+}
+
+/// Store a secret in the configured backend
+pub async fn set_secret(name: &str, value: &str) -> Result<()> {
+    match SecretBackend::current().await? {
+        SecretBackend::Keychain => keychain_set(name, value).await?,
+        SecretBackend::Env => {
+            return Err(anyhow!(
+                "The env secret backend is read-only; export {} instead",
+                env_override_name(name)
+            ));
+        }
+        SecretBackend::File => {
+            let mut store = read_file_store().await?;
+            store.insert(name.to_string(), value.to_string());
+            write_file_store(&store).await?;
+        }
+    }
+    register_redaction(value);
+    Ok(())
+}
+
+async fn keychain_set(name: &str, value: &str) -> Result<()> {
+    let (label, value) = (name.to_string(), value.to_string());
+    with_keyring(name, move |entry| {
+        entry.set_password(&value)
+            .map_err(|e| anyhow!("Failed to store secret '{}': {}", label, e))
+    }).await
+}
+
+/// Delete a secret from the configured backend
+pub async fn remove_secret(name: &str) -> Result<()> {
+    match SecretBackend::current().await? {
+        SecretBackend::Keychain => keychain_remove(name).await,
+        SecretBackend::Env => Err(anyhow!("The env secret backend is read-only; unset {}", env_override_name(name))),
+        SecretBackend::File => {
+            let mut store = read_file_store().await?;
+            if store.remove(name).is_none() {
+                return Err(anyhow!("Failed to remove secret '{}': not in the secrets file", name));
+            }
+            write_file_store(&store).await
+        }
+    }
+}
+
+async fn keychain_remove(name: &str) -> Result<()> {
+    let label = name.to_string();
+    with_keyring(name, move |entry| match entry.delete_credential() {
+        Ok(()) => Ok(()),
+        Err(keyring::Error::NoEntry) => Err(anyhow!("Failed to remove secret '{}': not in the OS keyring", label)),
+        Err(e) => Err(anyhow!("Failed to remove secret '{}': {}", label, e)),
+    }).await
+}
+
+/// `secrets.enc` under the configured data directory
+async fn secrets_file() -> Result<PathBuf> {
+    Ok(settings().await?.file.clone())
+}
+
+/// The file backend passphrase, asked for at most once per run
+fn passphrase() -> Result<String> {
+    static PASSPHRASE: OnceLock<String> = OnceLock::new();
+    if let Ok(value) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(value);
+    }
+    if let Some(value) = PASSPHRASE.get() {
+        return Ok(value.clone());
+    }
+    if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        return Err(anyhow!("The file secret backend needs {} when not run interactively", PASSPHRASE_ENV));
+    }
+    let value = Password::new().with_prompt("Passphrase for the RCM secrets file").interact()?;
+    Ok(PASSPHRASE.get_or_init(|| value).clone())
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Key {
+    let mut key = Key::default();
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, KDF_ROUNDS, &mut key);
+    key
+}
+
+/// Encrypt `plaintext` as `MAGIC || salt || nonce || ChaCha20-Poly1305 ciphertext`
+pub fn seal(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt));
+    let ciphertext = cipher.encrypt(&nonce, plaintext)
+        .map_err(|_| anyhow!("Failed to encrypt the secrets file"))?;
+    Ok([MAGIC, &salt[..], &nonce[..], &ciphertext[..]].concat())
+}
+
+/// Decrypt and authenticate data written by [`seal`]
+pub fn open(passphrase: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let body = data.strip_prefix(MAGIC).ok_or_else(|| anyhow!("not an RCM secrets file"))?;
+    if body.len() < SALT_LEN + NONCE_LEN {
+        return Err(anyhow!("truncated secrets file"));
+    }
+    let (salt, rest) = body.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    ChaCha20Poly1305::new(&derive_key(passphrase, salt))
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("wrong passphrase, or the file was modified"))
+}
+
+/// Decrypt the secrets file; missing means empty
+async fn read_file_store() -> Result<BTreeMap<String, String>> {
+    let path = secrets_file().await?;
+    let data = match tokio::fs::read(&path).await {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let passphrase = passphrase()?;
+    let plaintext = tokio::task::spawn_blocking(move || open(&passphrase, &data)).await?
+        .with_context(|| format!("Failed to decrypt {}", path.display()))?;
+    serde_json::from_slice(&plaintext)
+        .with_context(|| format!("{} is corrupt", path.display()))
+}
+
+/// Encrypt and replace the secrets file, readable only by the owner
+async fn write_file_store(store: &BTreeMap<String, String>) -> Result<()> {
+    crate::util::ensure_writable("update the secrets file")?;
+    let path = secrets_file().await?;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let (passphrase, plaintext) = (passphrase()?, serde_json::to_vec(store)?);
+    let sealed = tokio::task::spawn_blocking(move || seal(&passphrase, &plaintext)).await??;
+
+    let staged = path.with_extension("enc.tmp");
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(&staged).await
+        .with_context(|| format!("Failed to write {}", staged.display()))?;
+    file.write_all(&sealed).await?;
+    file.sync_all().await?;
+    tokio::fs::rename(&staged, &path).await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Resolve `secret:` references in an environment map
+///
+/// Plain values pass through unchanged. The returned map is meant to be handed
+/// straight to a spawned process and must not be persisted.
+pub async fn resolve_env(env: &HashMap<String, String>) -> Result<HashMap<String, String>> {
+    let mut resolved = HashMap::with_capacity(env.len());
+    for (key, value) in env {
+        let value = match secret_ref(value) {
+            Some(name) => get_secret(name).await
+                .with_context(|| format!("Failed to resolve {} for {}", value, key))?,
+            None => value.clone(),
+        };
+        resolved.insert(key.clone(), value);
+    }
+    Ok(resolved)
+}
+
+/// Handle `rcm secret` commands
+pub async fn handle_command(_workspace: &Workspace, cmd: SecretCommands) -> Result<()> {
+    match cmd {
+        SecretCommands::Set { name, stdin } => {
+            let value = if stdin {
+                let mut buf = String::new();
+                std::io::stdin().read_line(&mut buf)?;
+                buf.trim_end_matches(['\r', '\n']).to_string()
+            } else {
+                Password::new()
+                    .with_prompt(format!("Value for secret '{}'", name))
+                    .interact()?
+            };
+
+            if value.is_empty() {
+                return Err(anyhow!("Secret value cannot be empty"));
+            }
+
+            set_secret(&name, &value).await?;
+            println!("{}", style(format!("🔐 Stored secret '{}' (use \"secret:{}\" in env)", name, name)).green());
+            Ok(())
+        }
+        SecretCommands::Check { name } => {
+            get_secret(&name).await?;
+            println!("{}", style(format!("✅ Secret '{}' resolves", name)).green());
+            Ok(())
+        }
+        SecretCommands::Remove { name } => {
+            remove_secret(&name).await?;
+            println!("{}", style(format!("🗑️ Removed secret '{}'", name)).green());
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_names() {
+        assert_eq!(SecretBackend::parse("Keychain").unwrap(), SecretBackend::Keychain);
+        assert_eq!(SecretBackend::parse("file").unwrap().name(), "file");
+        assert!(SecretBackend::parse("vault").is_err());
+    }
+
+    #[test]
+    fn test_secret_ref() {
+        assert_eq!(secret_ref("secret:openai"), Some("openai"));
+        assert_eq!(secret_ref("plain-value"), None);
+        assert_eq!(secret_ref("secret:"), None);
+    }
+
+    #[test]
+    fn test_seal_and_open() {
+        let sealed = seal("correct horse", b"{\"openai\":\"sk-test\"}").unwrap();
+        assert_eq!(open("correct horse", &sealed).unwrap(), b"{\"openai\":\"sk-test\"}");
+        assert!(open("wrong", &sealed).is_err());
+
+        let mut tampered = sealed.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(open("correct horse", &tampered).is_err());
+        assert!(open("correct horse", &sealed[..MAGIC.len() + 4]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_env_override() {
+        std::env::set_var("RCM_SECRET_UNIT_TEST_KEY", "value-from-env");
+        let env = HashMap::from([
+            ("API_KEY".to_string(), "secret:unit-test-key".to_string()),
+            ("MODE".to_string(), "prod".to_string()),
+        ]);
+        let resolved = resolve_env(&env).await.unwrap();
+        assert_eq!(resolved["API_KEY"], "value-from-env");
+        assert_eq!(resolved["MODE"], "prod");
+    }
+}
@@ -0,0 +1,762 @@
+//! Security auditing for RCM
+//!
+//! Runs each manager's advisory scanner and normalises the findings so
+//! accepted-risk exceptions can be applied across managers
+
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+use clap::Subcommand;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use tokio::process::Command as AsyncCommand;
+use crate::audit_exceptions::{AuditExceptions, ExceptionStatus};
+use crate::util;
+use crate::workspace::Workspace;
+
+#[derive(Subcommand)]
+pub enum AuditCommands {
+    /// Periodically check advisories and notify about new vulnerabilities
+    Watch {
+        /// Time between checks (e.g. 30m, 6h, 1d)
+        #[arg(long, default_value = "6h")]
+        interval: String,
+        /// Check once and exit (for cron or CI schedules)
+        #[arg(long)]
+        once: bool,
+        /// Webhook URL to POST findings to (or set RCM_AUDIT_WEBHOOK; may be a `secret:` reference)
+        #[arg(long)]
+        webhook: Option<String>,
+        /// Show a desktop notification
+        #[arg(long)]
+        desktop: bool,
+    },
+    /// Manage accepted-risk exceptions in .rcm/audit-exceptions.toml
+    Exception {
+        #[command(subcommand)]
+        cmd: ExceptionCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ExceptionCommands {
+    /// Accept the risk of an advisory for a package until a date
+    Add {
+        /// Package name
+        package: String,
+        /// Advisory ID (RUSTSEC-, GHSA-, CVE-, PKSA-)
+        advisory: String,
+        /// Last day the exception applies (YYYY-MM-DD)
+        #[arg(long)]
+        expires: String,
+        /// Why the risk is acceptable
+        #[arg(long)]
+        justification: String,
+        /// Only apply to findings from this manager
+        #[arg(long)]
+        manager: Option<String>,
+    },
+    /// List recorded exceptions
+    List,
+    /// Remove an exception
+    Remove {
+        /// Package name
+        package: String,
+        /// Advisory ID
+        advisory: String,
+    },
+}
+
+/// A finding that accepted-risk exceptions can apply to
+pub trait Advisory {
+    fn manager(&self) -> &str;
+    fn package(&self) -> &str;
+    /// Primary ID and aliases, for exception matching
+    fn ids(&self) -> Vec<&str>;
+}
+
+/// A vulnerability reported by one of the manager scanners
+#[derive(Debug, Clone, Serialize)]
+pub struct Vulnerability {
+    pub manager: String,
+    pub package: String,
+    pub version: Option<String>,
+    /// Primary advisory ID
+    pub advisory: String,
+    /// Other IDs for the same advisory (CVE, GHSA)
+    pub aliases: Vec<String>,
+    pub severity: String,
+    pub title: String,
+    pub url: Option<String>,
+}
+
+impl Advisory for Vulnerability {
+    fn manager(&self) -> &str {
+        &self.manager
+    }
+
+    fn package(&self) -> &str {
+        &self.package
+    }
+
+    fn ids(&self) -> Vec<&str> {
+        std::iter::once(self.advisory.as_str())
+            .chain(self.aliases.iter().map(String::as_str))
+            .collect()
+    }
+}
+
+impl Vulnerability {
+    pub fn severity_level(&self) -> Severity {
+        Severity::from_label(&self.severity)
+    }
+}
+
+/// Normalized severity, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Unknown,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 5] = [Self::Critical, Self::High, Self::Medium, Self::Low, Self::Unknown];
+
+    /// Map scanner labels (npm uses "moderate", OSV uses "MODERATE") to a level
+    pub fn from_label(label: &str) -> Self {
+        match label.to_lowercase().as_str() {
+            "critical" => Self::Critical,
+            "high" => Self::High,
+            "medium" | "moderate" => Self::Medium,
+            "low" | "info" => Self::Low,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// CVSS v3 qualitative rating
+    pub fn from_score(score: f64) -> Self {
+        match score {
+            s if s >= 9.0 => Self::Critical,
+            s if s >= 7.0 => Self::High,
+            s if s >= 4.0 => Self::Medium,
+            s if s > 0.0 => Self::Low,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Representative score for SARIF `security-severity`
+    pub fn score(&self) -> Option<f64> {
+        match self {
+            Self::Critical => Some(9.5),
+            Self::High => Some(8.0),
+            Self::Medium => Some(5.5),
+            Self::Low => Some(2.0),
+            Self::Unknown => None,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label = match self {
+            Self::Unknown => "unknown",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+            Self::Critical => "critical",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Base score of a CVSS v3.x vector (e.g. `CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H`)
+pub fn cvss_base_score(vector: &str) -> Option<f64> {
+    let metrics: BTreeMap<&str, &str> = vector
+        .split('/')
+        .filter_map(|part| part.split_once(':'))
+        .collect();
+    if !metrics.get("CVSS")?.starts_with('3') {
+        return None;
+    }
+    let changed = *metrics.get("S")? == "C";
+    let av = match *metrics.get("AV")? { "N" => 0.85, "A" => 0.62, "L" => 0.55, "P" => 0.2, _ => return None };
+    let ac = match *metrics.get("AC")? { "L" => 0.77, "H" => 0.44, _ => return None };
+    let pr = match (*metrics.get("PR")?, changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return None,
+    };
+    let ui = match *metrics.get("UI")? { "N" => 0.85, "R" => 0.62, _ => return None };
+    let cia = |key: &str| match metrics.get(key).copied() {
+        Some("H") => Some(0.56),
+        Some("L") => Some(0.22),
+        Some("N") => Some(0.0),
+        _ => None,
+    };
+
+    let iss = 1.0 - (1.0 - cia("C")?) * (1.0 - cia("I")?) * (1.0 - cia("A")?);
+    let impact = if changed {
+        7.52 * (iss - 0.029) - 3.25 * (iss - 0.02f64).powi(15)
+    } else {
+        6.42 * iss
+    };
+    if impact <= 0.0 {
+        return Some(0.0);
+    }
+    let exploitability = 8.22 * av * ac * pr * ui;
+    let base = if changed { 1.08 * (impact + exploitability) } else { impact + exploitability };
+    // CVSS rounds up to one decimal
+    Some((base.min(10.0) * 10.0).ceil() / 10.0)
+}
+
+/// Findings split by exception status
+#[derive(Debug, Serialize)]
+pub struct AuditReport<T = Vulnerability> {
+    /// Findings with no exception; these fail the audit
+    pub active: Vec<T>,
+    /// Findings covered by an unexpired exception
+    pub accepted: Vec<(T, String, NaiveDate)>,
+    /// Findings whose exception has expired; these fail the audit again
+    pub expired: Vec<(T, NaiveDate)>,
+}
+
+impl<T> Default for AuditReport<T> {
+    fn default() -> Self {
+        Self { active: Vec::new(), accepted: Vec::new(), expired: Vec::new() }
+    }
+}
+
+impl<T> AuditReport<T> {
+    /// Whether the audit should fail
+    pub fn failed(&self) -> bool {
+        !self.active.is_empty() || !self.expired.is_empty()
+    }
+}
+
+impl AuditReport {
+    /// Findings that fail the audit: no exception, or an expired one
+    pub fn unaccepted(&self) -> impl Iterator<Item = &Vulnerability> {
+        self.active.iter().chain(self.expired.iter().map(|(v, _)| v))
+    }
+
+    /// Unaccepted findings at or above `threshold`; every finding counts when it is `None`
+    ///
+    /// Findings of unknown severity always count, since they may be critical.
+    pub fn failing(&self, threshold: Option<Severity>) -> usize {
+        self.unaccepted()
+            .filter(|v| match threshold {
+                Some(threshold) => v.severity_level() == Severity::Unknown || v.severity_level() >= threshold,
+                None => true,
+            })
+            .count()
+    }
+
+    /// Unaccepted findings per severity, most severe first
+    pub fn severity_counts(&self) -> Vec<(Severity, usize)> {
+        Severity::ALL
+            .iter()
+            .map(|s| (*s, self.unaccepted().filter(|v| v.severity_level() == *s).count()))
+            .collect()
+    }
+}
+
+/// Lockfile a finding is reported against in SARIF
+fn manifest_for(manager: &str) -> &'static str {
+    match manager {
+        "cargo" => "Cargo.lock",
+        "npm" => "package-lock.json",
+        "composer" => "composer.lock",
+        _ => "rcm.lock",
+    }
+}
+
+/// Render the report as SARIF 2.1.0; accepted findings are included as suppressed results
+pub fn to_sarif(report: &AuditReport) -> Value {
+    let mut rules: BTreeMap<String, Value> = BTreeMap::new();
+    let mut results = Vec::new();
+
+    let findings = report.active.iter().map(|v| (v, None))
+        .chain(report.expired.iter().map(|(v, _)| (v, None)))
+        .chain(report.accepted.iter().map(|(v, justification, _)| (v, Some(justification.as_str()))));
+
+    for (vuln, suppression) in findings {
+        let severity = vuln.severity_level();
+        let mut properties = serde_json::json!({ "tags": ["security", vuln.manager] });
+        if let Some(score) = severity.score() {
+            properties["security-severity"] = Value::String(format!("{:.1}", score));
+        }
+        let description = if vuln.title.is_empty() { &vuln.advisory } else { &vuln.title };
+        rules.entry(vuln.advisory.clone()).or_insert_with(|| serde_json::json!({
+            "id": vuln.advisory,
+            "shortDescription": { "text": description },
+            "helpUri": vuln.url,
+            "properties": properties,
+        }));
+
+        let level = match severity {
+            Severity::Critical | Severity::High => "error",
+            Severity::Medium | Severity::Unknown => "warning",
+            Severity::Low => "note",
+        };
+        let mut result = serde_json::json!({
+            "ruleId": vuln.advisory,
+            "level": level,
+            "message": {
+                "text": format!("{} {} is affected by {} ({}): {}",
+                    vuln.package, vuln.version.as_deref().unwrap_or(""), vuln.advisory, severity, vuln.title),
+            },
+            "locations": [{
+                "physicalLocation": { "artifactLocation": { "uri": manifest_for(&vuln.manager) } }
+            }],
+        });
+        if let Some(justification) = suppression {
+            result["suppressions"] = serde_json::json!([{ "kind": "external", "justification": justification }]);
+        }
+        results.push(result);
+    }
+
+    serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "rcm-audit",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.into_values().collect::<Vec<_>>(),
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+/// Apply recorded exceptions to a set of findings
+pub fn classify<T: Advisory>(findings: Vec<T>, exceptions: &AuditExceptions, today: NaiveDate) -> AuditReport<T> {
+    let mut report = AuditReport::default();
+    for finding in findings {
+        let status = exceptions.status_for(finding.manager(), finding.package(), &finding.ids(), today);
+        match status {
+            ExceptionStatus::None => report.active.push(finding),
+            ExceptionStatus::Accepted(e) => report.accepted.push((finding, e.justification, e.expires)),
+            ExceptionStatus::Expired(e) => report.expired.push((finding, e.expires)),
+        }
+    }
+    report
+}
+
+/// Run the scanners for the given managers
+///
+/// Scanners that are not installed are skipped with a warning.
+pub async fn scan(workspace: &Workspace, managers: &[String]) -> Result<Vec<Vulnerability>> {
+    let root = workspace.root();
+    let mut findings = Vec::new();
+
+    for manager in managers {
+        let found = match manager.as_str() {
+            "cargo" if root.join("Cargo.lock").exists() => {
+                run_scanner(root, "cargo", &["audit", "--json"], "cargo-audit").await?
+                    .map(|json| parse_cargo_audit(&json))
+            }
+            "npm" if root.join("package.json").exists() => {
+                run_scanner(root, "npm", &["audit", "--json"], "npm").await?
+                    .map(|json| parse_npm_audit(&json))
+            }
+            "composer" if root.join("composer.lock").exists() => {
+                run_scanner(root, "composer", &["audit", "--format=json", "--no-interaction"], "composer").await?
+                    .map(|json| parse_composer_audit(&json))
+            }
+            "bundler" if root.join("Gemfile.lock").exists() => {
+                run_scanner(root, "bundle-audit", &["check", "--format", "json"], "bundler-audit").await?
+                    .map(|json| parse_bundler_audit(&json))
+            }
+            "python" if root.join("requirements.txt").exists() => {
+                run_scanner(root, "pip-audit", &["-f", "json", "-r", "requirements.txt"], "pip-audit").await?
+                    .map(|json| parse_pip_audit(&json))
+            }
+            "python" if root.join("pyproject.toml").exists() => {
+                run_scanner(root, "pip-audit", &["-f", "json", "."], "pip-audit").await?
+                    .map(|json| parse_pip_audit(&json))
+            }
+            _ => None,
+        };
+        findings.extend(found.unwrap_or_default());
+    }
+
+    Ok(findings)
+}
+
+/// Run a scanner and parse its JSON output
+///
+/// Scanners exit non-zero when they find vulnerabilities, so the exit code is ignored
+/// as long as the output parses.
+async fn run_scanner(root: &Path, program: &str, args: &[&str], tool: &str) -> Result<Option<Value>> {
+    if !util::command_exists(program).await {
+        tracing::warn!("{} not found, skipping audit", tool);
+        return Ok(None);
+    }
+
+    let output = AsyncCommand::new(program)
+        .args(args)
+        .current_dir(root)
+        .output()
+        .await
+        .with_context(|| format!("Failed to run {}", tool))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match serde_json::from_str(&stdout) {
+        Ok(json) => Ok(Some(json)),
+        Err(_) if output.status.success() => Ok(None),
+        Err(_) => Err(anyhow!(
+            "{} failed: {}",
+            tool,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+    }
+}
+
+fn str_field(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+/// Parse `cargo audit --json`
+pub fn parse_cargo_audit(json: &Value) -> Vec<Vulnerability> {
+    let list = json
+        .pointer("/vulnerabilities/list")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+
+    list.iter()
+        .filter_map(|entry| {
+            let advisory = entry.get("advisory")?;
+            Some(Vulnerability {
+                manager: "cargo".to_string(),
+                package: str_field(advisory, "package")?,
+                version: entry.pointer("/package/version").and_then(Value::as_str).map(str::to_string),
+                advisory: str_field(advisory, "id")?,
+                aliases: advisory
+                    .get("aliases")
+                    .and_then(Value::as_array)
+                    .map(|a| a.iter().filter_map(Value::as_str).map(str::to_string).collect())
+                    .unwrap_or_default(),
+                // RustSec advisories carry a CVSS vector rather than a label
+                severity: advisory
+                    .get("cvss")
+                    .and_then(Value::as_str)
+                    .and_then(cvss_base_score)
+                    .map(Severity::from_score)
+                    .unwrap_or(Severity::Unknown)
+                    .to_string(),
+                title: str_field(advisory, "title").unwrap_or_default(),
+                url: str_field(advisory, "url"),
+            })
+        })
+        .collect()
+}
+
+/// Parse `npm audit --json` (npm 7+ format)
+pub fn parse_npm_audit(json: &Value) -> Vec<Vulnerability> {
+    let Some(vulns) = json.get("vulnerabilities").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+
+    let mut findings = Vec::new();
+    for (name, entry) in vulns {
+        let via = entry.get("via").and_then(Value::as_array).cloned().unwrap_or_default();
+        // String entries in `via` point at another vulnerable package; only objects are advisories
+        for advisory in via.iter().filter(|v| v.is_object()) {
+            let url = str_field(advisory, "url");
+            let ghsa = url
+                .as_deref()
+                .and_then(|u| u.rsplit('/').next())
+                .filter(|id| id.starts_with("GHSA-"))
+                .map(str::to_string);
+            let source = advisory.get("source").map(|s| s.to_string());
+
+            let Some(id) = ghsa.clone().or(source) else { continue };
+            findings.push(Vulnerability {
+                manager: "npm".to_string(),
+                package: str_field(advisory, "name").unwrap_or_else(|| name.clone()),
+                version: str_field(advisory, "range"),
+                advisory: id,
+                aliases: Vec::new(),
+                severity: Severity::from_label(&str_field(advisory, "severity").unwrap_or_default()).to_string(),
+                title: str_field(advisory, "title").unwrap_or_default(),
+                url,
+            });
+        }
+    }
+    findings
+}
+
+/// Parse `composer audit --format=json`
+pub fn parse_composer_audit(json: &Value) -> Vec<Vulnerability> {
+    let Some(advisories) = json.get("advisories").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+
+    let mut findings = Vec::new();
+    for (package, entries) in advisories {
+        // Composer emits an object keyed by index when the list is sparse
+        let entries: Vec<&Value> = match entries {
+            Value::Array(list) => list.iter().collect(),
+            Value::Object(map) => map.values().collect(),
+            _ => continue,
+        };
+        for entry in entries {
+            let Some(id) = str_field(entry, "advisoryId") else { continue };
+            findings.push(Vulnerability {
+                manager: "composer".to_string(),
+                package: str_field(entry, "packageName").unwrap_or_else(|| package.clone()),
+                version: str_field(entry, "affectedVersions"),
+                advisory: id,
+                aliases: str_field(entry, "cve").into_iter().collect(),
+                severity: Severity::from_label(&str_field(entry, "severity").unwrap_or_default()).to_string(),
+                title: str_field(entry, "title").unwrap_or_default(),
+                url: str_field(entry, "link"),
+            });
+        }
+    }
+    findings
+}
+
+/// Parse `bundle-audit check --format json`
+pub fn parse_bundler_audit(json: &Value) -> Vec<Vulnerability> {
+    let results = json.get("results").and_then(Value::as_array).cloned().unwrap_or_default();
+
+    // `insecure_source` results flag http:// remotes rather than a gem; they carry no advisory
+    results
+        .iter()
+        .filter(|r| r.get("type").and_then(Value::as_str) == Some("unpatched_gem"))
+        .filter_map(|result| {
+            let gem = result.get("gem")?;
+            let advisory = result.get("advisory")?;
+            let cve = str_field(advisory, "cve").map(|id| format!("CVE-{}", id));
+            let ghsa = str_field(advisory, "ghsa").map(|id| format!("GHSA-{}", id));
+            let id = str_field(advisory, "id").or_else(|| cve.clone()).or_else(|| ghsa.clone())?;
+            let severity = match advisory.get("cvss_v3").and_then(Value::as_f64) {
+                Some(score) => Severity::from_score(score),
+                None => Severity::from_label(&str_field(advisory, "criticality").unwrap_or_default()),
+            };
+            Some(Vulnerability {
+                manager: "bundler".to_string(),
+                package: str_field(gem, "name")?,
+                version: str_field(gem, "version"),
+                aliases: [cve, ghsa].into_iter().flatten().filter(|alias| *alias != id).collect(),
+                advisory: id,
+                severity: severity.to_string(),
+                title: str_field(advisory, "title").unwrap_or_default(),
+                url: str_field(advisory, "url"),
+            })
+        })
+        .collect()
+}
+
+/// Parse `pip-audit -f json`
+pub fn parse_pip_audit(json: &Value) -> Vec<Vulnerability> {
+    // pip-audit 2.x wraps the list in `dependencies`; 1.x printed the bare list
+    let dependencies = json
+        .get("dependencies")
+        .unwrap_or(json)
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+
+    let mut findings = Vec::new();
+    for dependency in &dependencies {
+        let Some(package) = str_field(dependency, "name") else { continue };
+        let vulns = dependency.get("vulns").and_then(Value::as_array).cloned().unwrap_or_default();
+        for vuln in &vulns {
+            let Some(id) = str_field(vuln, "id") else { continue };
+            let fixes: Vec<&str> = vuln
+                .get("fix_versions")
+                .and_then(Value::as_array)
+                .map(|f| f.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            // Descriptions are whole paragraphs; keep the first line as the title
+            let description = str_field(vuln, "description").unwrap_or_default();
+            let mut title = description.lines().next().unwrap_or_default().to_string();
+            if !fixes.is_empty() {
+                title = format!("{} (fixed in {})", title, fixes.join(", ")).trim().to_string();
+            }
+            findings.push(Vulnerability {
+                manager: "python".to_string(),
+                package: package.clone(),
+                version: str_field(dependency, "version"),
+                url: Some(format!("https://osv.dev/vulnerability/{}", id)),
+                advisory: id,
+                aliases: vuln
+                    .get("aliases")
+                    .and_then(Value::as_array)
+                    .map(|a| a.iter().filter_map(Value::as_str).map(str::to_string).collect())
+                    .unwrap_or_default(),
+                // PyPI advisories carry no severity in pip-audit output
+                severity: Severity::Unknown.to_string(),
+                title,
+            });
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit_exceptions::AuditException;
+
+    #[test]
+    fn test_parse_bundler_audit() {
+        let json = serde_json::json!({
+            "results": [
+                { "type": "insecure_source", "source": "http://rubygems.org/" },
+                { "type": "unpatched_gem",
+                  "gem": { "name": "rack", "version": "2.2.3" },
+                  "advisory": {
+                      "id": "CVE-2022-30123", "cve": "2022-30123", "ghsa": "wq4h-7r42-5hrr",
+                      "title": "Possible shell escape sequence injection vulnerability in Rack",
+                      "url": "https://groups.google.com/g/ruby-security-ann/c/LWB10kWzag8",
+                      "criticality": null, "cvss_v3": 10.0, "patched_versions": ["~> 2.2.3.1"]
+                  } }
+            ]
+        });
+
+        let findings = parse_bundler_audit(&json);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].advisory, "CVE-2022-30123");
+        assert_eq!(findings[0].aliases, vec!["GHSA-wq4h-7r42-5hrr"]);
+        assert_eq!(findings[0].severity, "critical");
+    }
+
+    #[test]
+    fn test_parse_pip_audit() {
+        let json = serde_json::json!({
+            "dependencies": [
+                { "name": "requests", "version": "2.25.0", "vulns": [{
+                    "id": "PYSEC-2023-74",
+                    "fix_versions": ["2.31.0"],
+                    "aliases": ["CVE-2023-32681", "GHSA-j8r2-6x86-q33q"],
+                    "description": "Requests forwards Proxy-Authorization headers.\nMore detail."
+                }]},
+                { "name": "idna", "version": "3.7", "vulns": [] }
+            ],
+            "fixes": []
+        });
+
+        let findings = parse_pip_audit(&json);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].package, "requests");
+        assert_eq!(findings[0].title, "Requests forwards Proxy-Authorization headers. (fixed in 2.31.0)");
+        assert!(findings[0].ids().contains(&"CVE-2023-32681"));
+    }
+
+    #[test]
+    fn test_parse_npm_audit() {
+        let json = serde_json::json!({
+            "vulnerabilities": {
+                "lodash": {
+                    "name": "lodash",
+                    "severity": "high",
+                    "via": [{
+                        "source": 1096305,
+                        "name": "lodash",
+                        "title": "Command Injection in lodash",
+                        "url": "https://github.com/advisories/GHSA-35jh-r3h4-6jhm",
+                        "severity": "high",
+                        "range": "<4.17.21"
+                    }]
+                },
+                "some-wrapper": { "name": "some-wrapper", "severity": "high", "via": ["lodash"] }
+            }
+        });
+
+        let findings = parse_npm_audit(&json);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].advisory, "GHSA-35jh-r3h4-6jhm");
+        assert_eq!(findings[0].severity, "high");
+    }
+
+    #[test]
+    fn test_cvss_severity() {
+        let score = cvss_base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H").unwrap();
+        assert_eq!(score, 9.8);
+        assert_eq!(Severity::from_score(score), Severity::Critical);
+        assert_eq!(cvss_base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N"), Some(6.1));
+        assert_eq!(Severity::from_label("moderate"), Severity::Medium);
+        assert!(Severity::High > Severity::Medium);
+    }
+
+    #[test]
+    fn test_classify_with_exceptions() {
+        let vuln = |pkg: &str, id: &str| Vulnerability {
+            manager: "composer".to_string(),
+            package: pkg.to_string(),
+            version: None,
+            advisory: id.to_string(),
+            aliases: vec!["CVE-2024-0001".to_string()],
+            severity: "high".to_string(),
+            title: String::new(),
+            url: None,
+        };
+        let exceptions = AuditExceptions {
+            exceptions: vec![
+                AuditException {
+                    package: "monolog/monolog".to_string(),
+                    advisory: "CVE-2024-0001".to_string(),
+                    manager: None,
+                    expires: NaiveDate::from_ymd_opt(2026, 12, 31).unwrap(),
+                    justification: "Not reachable".to_string(),
+                    added_by: None,
+                    added_at: None,
+                },
+                AuditException {
+                    package: "guzzlehttp/guzzle".to_string(),
+                    advisory: "PKSA-2".to_string(),
+                    manager: None,
+                    expires: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+                    justification: "Pending upgrade".to_string(),
+                    added_by: None,
+                    added_at: None,
+                },
+            ],
+        };
+
+        let report = classify(
+            vec![
+                vuln("monolog/monolog", "PKSA-1"),
+                vuln("guzzlehttp/guzzle", "PKSA-2"),
+                vuln("symfony/http-kernel", "PKSA-3"),
+            ],
+            &exceptions,
+            NaiveDate::from_ymd_opt(2026, 10, 16).unwrap(),
+        );
+
+        assert_eq!(report.accepted.len(), 1);
+        assert_eq!(report.expired.len(), 1);
+        assert_eq!(report.active.len(), 1);
+        assert!(report.failed());
+        assert_eq!(report.failing(Some(Severity::Critical)), 0);
+        assert_eq!(report.failing(Some(Severity::High)), 2);
+
+        let unrated = classify(
+            vec![Vulnerability { severity: "unknown".to_string(), ..vuln("symfony/http-kernel", "PKSA-3") }],
+            &exceptions,
+            NaiveDate::from_ymd_opt(2026, 10, 16).unwrap(),
+        );
+        assert_eq!(unrated.failing(Some(Severity::Critical)), 1);
+
+        let sarif = to_sarif(&report);
+        let results = sarif.pointer("/runs/0/results").and_then(Value::as_array).unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results.iter().filter(|r| r.get("suppressions").is_some()).count(), 1);
+    }
+}
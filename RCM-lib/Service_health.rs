@@ -0,0 +1,248 @@
+//! External service requirements for RCM
+//!
+//! Services a workspace needs at runtime (databases, caches, model servers) are
+//! declared under `services` in `.rcm/workspace.json`. `rcm ensure` probes them over TCP or HTTP and
+//! can start missing ones through docker compose, a LET spec or a LET stack.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+use tabled::{Table, Tabled};
+use crate::commands::letcmd::LetExecutor;
+use crate::members;
+use crate::util;
+
+/// Section of the workspace manifest declaring the services
+pub const SECTION: &str = "services";
+
+/// How long a started service gets to become reachable
+const START_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The `services` section of the workspace manifest
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServicesManifest {
+    #[serde(default)]
+    pub services: BTreeMap<String, ServiceRequirement>,
+}
+
+/// A service the workspace depends on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceRequirement {
+    /// Expected version, shown in reports (e.g. `15` for postgres@15)
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default = "default_host")]
+    pub host: String,
+    /// TCP port; defaults to the well-known port of the service name
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// HTTP URL to probe instead of a TCP connect (e.g. `http://localhost:11434/api/tags`)
+    #[serde(default)]
+    pub url: Option<String>,
+    /// How to start the service when it is down
+    #[serde(default)]
+    pub start: Option<ServiceStart>,
+}
+
+fn default_host() -> String { "localhost".to_string() }
+
+/// Ways RCM can start a service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "via", rename_all = "lowercase")]
+pub enum ServiceStart {
+    /// `docker compose up -d <service>`
+    Compose {
+        #[serde(default)]
+        file: Option<String>,
+        #[serde(default)]
+        service: Option<String>,
+    },
+    /// Run the `install` action of a LET spec
+    Let { target: String },
+    /// Deploy a LET stack (`rcm let stack <name> --deploy`)
+    Stack { name: String },
+}
+
+/// Result of probing a service
+#[derive(Debug, Clone, Tabled)]
+pub struct ServiceStatus {
+    #[tabled(rename = "Service")]
+    pub name: String,
+    #[tabled(rename = "Endpoint")]
+    pub endpoint: String,
+    #[tabled(rename = "Status")]
+    pub status: String,
+    #[tabled(skip)]
+    pub up: bool,
+}
+
+/// Well-known port for a service name
+pub fn default_port(name: &str) -> Option<u16> {
+    let port = match name {
+        "postgres" | "postgresql" => 5432,
+        "mysql" | "mariadb" => 3306,
+        "redis" => 6379,
+        "memcached" => 11211,
+        "mongo" | "mongodb" => 27017,
+        "rabbitmq" => 5672,
+        "elasticsearch" | "opensearch" => 9200,
+        "ollama" => 11434,
+        "vllm" | "llamacpp" => 8000,
+        _ => return None,
+    };
+    Some(port)
+}
+
+impl ServiceRequirement {
+    /// Human-readable probe target
+    pub fn endpoint(&self, name: &str) -> Result<String> {
+        if let Some(url) = &self.url {
+            return Ok(url.clone());
+        }
+        let port = self.port.or_else(|| default_port(name))
+            .ok_or_else(|| anyhow!("Service '{}' needs a port or url in {}", name, members::MANIFEST))?;
+        Ok(format!("{}:{}", self.host, port))
+    }
+
+    /// Probe once; returns the latency when the service answered
+    async fn probe(&self, name: &str) -> Result<Duration> {
+        let started = Instant::now();
+        let endpoint = self.endpoint(name)?;
+        if self.url.is_some() {
+            let response = reqwest::Client::new()
+                .get(&endpoint)
+                .timeout(Duration::from_secs(3))
+                .send()
+                .await?;
+            if response.status().is_server_error() {
+                return Err(anyhow!("HTTP {}", response.status()));
+            }
+        } else {
+            tokio::time::timeout(Duration::from_secs(2), tokio::net::TcpStream::connect(&endpoint))
+                .await
+                .map_err(|_| anyhow!("timed out"))??;
+        }
+        Ok(started.elapsed())
+    }
+}
+
+impl ServicesManifest {
+    /// Load the declared services, returning none when the manifest has no `services`
+    pub async fn load(workspace_root: &Path) -> Result<Self> {
+        Ok(Self { services: members::read_section(workspace_root, SECTION)? })
+    }
+}
+
+async fn probe(name: &str, requirement: &ServiceRequirement) -> ServiceStatus {
+    let endpoint = requirement.endpoint(name).unwrap_or_else(|e| e.to_string());
+    let label = match &requirement.version {
+        Some(version) => format!("{}@{}", name, version),
+        None => name.to_string(),
+    };
+    match requirement.probe(name).await {
+        Ok(latency) => ServiceStatus {
+            name: label,
+            endpoint,
+            status: format!("up ({} ms)", latency.as_millis()),
+            up: true,
+        },
+        Err(e) => ServiceStatus { name: label, endpoint, status: format!("down: {}", e), up: false },
+    }
+}
+
+/// Start a service with its configured method
+async fn start(workspace_root: &Path, name: &str, how: &ServiceStart) -> Result<()> {
+    println!("{}", style(format!("▶️  Starting {}...", name)).blue());
+    match how {
+        ServiceStart::Compose { file, service } => {
+            let mut cmd = Command::new("docker");
+            cmd.current_dir(workspace_root).arg("compose");
+            if let Some(file) = file {
+                cmd.arg("-f").arg(file);
+            }
+            cmd.args(["up", "-d", service.as_deref().unwrap_or(name)]);
+            util::execute_mutation(&mut cmd).await
+                .with_context(|| format!("Failed to start {} with docker compose", name))?;
+        }
+        ServiceStart::Let { target } => {
+            LetExecutor::new(workspace_root)
+                .execute_with_dependencies(target, Some("install"), HashMap::new())
+                .await
+                .with_context(|| format!("Failed to start {} with LET spec '{}'", name, target))?;
+        }
+        ServiceStart::Stack { name: stack } => {
+            crate::stack::run(workspace_root, stack, true, false, false).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Probe every declared service, starting missing ones when `start_missing` is set.
+/// Returns the final status of each service.
+pub async fn check(workspace_root: &Path, start_missing: bool) -> Result<Vec<ServiceStatus>> {
+    let manifest = ServicesManifest::load(workspace_root).await?;
+    let mut statuses = Vec::new();
+
+    for (name, requirement) in &manifest.services {
+        let mut status = probe(name, requirement).await;
+
+        if !status.up && start_missing {
+            if let Some(how) = &requirement.start {
+                start(workspace_root, name, how).await?;
+                if util::is_dry_run() {
+                    statuses.push(status);
+                    continue;
+                }
+                // Give the service time to accept connections
+                let deadline = Instant::now() + START_TIMEOUT;
+                while !status.up && Instant::now() < deadline {
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                    status = probe(name, requirement).await;
+                }
+            }
+        }
+        statuses.push(status);
+    }
+
+    Ok(statuses)
+}
+
+/// Print the service table with a hint for services that are down
+pub fn print_report(statuses: &[ServiceStatus]) {
+    if statuses.is_empty() {
+        return;
+    }
+    println!();
+    println!("{}", style("🔌 Services").bold());
+    println!("{}", Table::new(statuses));
+    if statuses.iter().any(|s| !s.up) {
+        println!("Run {} to start services that declare a start method in {}",
+            style("rcm ensure --start-services").cyan(), members::MANIFEST);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_services_manifest() {
+        let manifest: ServicesManifest = serde_json::from_str(r#"{
+            "services": {
+                "postgres": { "version": "15", "start": { "via": "compose", "service": "db" } },
+                "ollama": { "url": "http://localhost:11434/api/tags", "start": { "via": "let", "target": "ollama" } },
+                "queue": {}
+            }
+        }"#).unwrap();
+
+        let postgres = &manifest.services["postgres"];
+        assert_eq!(postgres.endpoint("postgres").unwrap(), "localhost:5432");
+        assert!(matches!(postgres.start, Some(ServiceStart::Compose { ref service, .. }) if service.as_deref() == Some("db")));
+        assert_eq!(manifest.services["ollama"].endpoint("ollama").unwrap(), "http://localhost:11434/api/tags");
+        assert!(manifest.services["queue"].endpoint("queue").is_err());
+    }
+}
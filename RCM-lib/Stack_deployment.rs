@@ -0,0 +1,484 @@
+//! LET stack deployments for RCM
+//!
+//! Materializes a stack spec (app, db, cache, model services) as a docker-compose project
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::process::Command as AsyncCommand;
+use crate::util;
+
+/// Stack specification stored at `.rcm/stacks/<name>.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StackSpec {
+    pub name: String,
+    pub services: BTreeMap<String, StackService>,
+    #[serde(default)]
+    pub volumes: Vec<String>,
+}
+
+/// Role of a service in the stack
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceKind {
+    App,
+    Db,
+    Cache,
+    Model,
+    Custom,
+}
+
+/// A single service in the stack
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StackService {
+    pub kind: ServiceKind,
+    /// Engine for db/cache/model services (postgres, mysql, redis, ollama, ...)
+    #[serde(default)]
+    pub engine: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub image: Option<String>,
+    /// Build context for app services
+    #[serde(default)]
+    pub build: Option<String>,
+    #[serde(default)]
+    pub command: Option<Vec<String>>,
+    #[serde(default)]
+    pub ports: Vec<String>,
+    #[serde(default)]
+    pub environment: HashMap<String, String>,
+    #[serde(default)]
+    pub volumes: Vec<String>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub health_check: Option<HealthCheck>,
+}
+
+/// Container health check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub test: Vec<String>,
+    #[serde(default = "default_interval")]
+    pub interval: String,
+    #[serde(default = "default_timeout")]
+    pub timeout: String,
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+fn default_interval() -> String { "10s".to_string() }
+fn default_timeout() -> String { "5s".to_string() }
+fn default_retries() -> u32 { 5 }
+
+impl HealthCheck {
+    fn cmd(test: &[&str]) -> Self {
+        let mut full = vec!["CMD".to_string()];
+        full.extend(test.iter().map(|s| s.to_string()));
+        Self {
+            test: full,
+            interval: default_interval(),
+            timeout: default_timeout(),
+            retries: default_retries(),
+        }
+    }
+}
+
+impl StackService {
+    /// Container image, falling back to the engine default
+    fn resolved_image(&self) -> Option<String> {
+        if let Some(image) = &self.image {
+            return Some(image.clone());
+        }
+        if self.build.is_some() {
+            return None;
+        }
+
+        let version = self.version.as_deref().unwrap_or("latest");
+        let image = match (self.kind, self.engine.as_deref()) {
+            (ServiceKind::Db, Some("mysql")) => format!("mysql:{}", version),
+            (ServiceKind::Db, Some("mariadb")) => format!("mariadb:{}", version),
+            (ServiceKind::Db, Some("mongo") | Some("mongodb")) => format!("mongo:{}", version),
+            (ServiceKind::Db, _) => format!("postgres:{}", version),
+            (ServiceKind::Cache, Some("memcached")) => format!("memcached:{}", version),
+            (ServiceKind::Cache, _) => format!("redis:{}", version),
+            (ServiceKind::Model, Some("vllm")) => format!("vllm/vllm-openai:{}", version),
+            (ServiceKind::Model, Some("llamacpp")) => "ghcr.io/ggerganov/llama.cpp:server".to_string(),
+            (ServiceKind::Model, _) => format!("ollama/ollama:{}", version),
+            _ => return None,
+        };
+        Some(image)
+    }
+
+    /// Health check, falling back to the engine default
+    fn resolved_health_check(&self) -> Option<HealthCheck> {
+        if let Some(check) = &self.health_check {
+            return Some(check.clone());
+        }
+
+        match (self.kind, self.engine.as_deref()) {
+            (ServiceKind::Db, Some("mysql") | Some("mariadb")) => Some(HealthCheck::cmd(&["mysqladmin", "ping", "-h", "localhost"])),
+            (ServiceKind::Db, Some("mongo") | Some("mongodb")) => Some(HealthCheck::cmd(&["mongosh", "--eval", "db.adminCommand('ping')"])),
+            (ServiceKind::Db, _) => Some(HealthCheck::cmd(&["pg_isready", "-U", "postgres"])),
+            (ServiceKind::Cache, Some("memcached")) => None,
+            (ServiceKind::Cache, _) => Some(HealthCheck::cmd(&["redis-cli", "ping"])),
+            (ServiceKind::Model, Some("ollama") | None) => Some(HealthCheck::cmd(&["ollama", "list"])),
+            _ => None,
+        }
+    }
+}
+
+impl StackSpec {
+    /// Starter spec with app, database and cache
+    pub fn template(name: &str) -> Self {
+        let mut services = BTreeMap::new();
+
+        services.insert("db".to_string(), StackService {
+            kind: ServiceKind::Db,
+            engine: Some("postgres".to_string()),
+            version: Some("16".to_string()),
+            image: None,
+            build: None,
+            command: None,
+            ports: vec!["5432:5432".to_string()],
+            environment: HashMap::from([
+                ("POSTGRES_PASSWORD".to_string(), "postgres".to_string()),
+                ("POSTGRES_DB".to_string(), name.to_string()),
+            ]),
+            volumes: vec!["db-data:/var/lib/postgresql/data".to_string()],
+            depends_on: Vec::new(),
+            health_check: None,
+        });
+
+        services.insert("cache".to_string(), StackService {
+            kind: ServiceKind::Cache,
+            engine: Some("redis".to_string()),
+            version: Some("7".to_string()),
+            image: None,
+            build: None,
+            command: None,
+            ports: vec!["6379:6379".to_string()],
+            environment: HashMap::new(),
+            volumes: Vec::new(),
+            depends_on: Vec::new(),
+            health_check: None,
+        });
+
+        services.insert("app".to_string(), StackService {
+            kind: ServiceKind::App,
+            engine: None,
+            version: None,
+            image: None,
+            build: Some(".".to_string()),
+            command: None,
+            ports: vec!["8080:8080".to_string()],
+            environment: HashMap::from([
+                ("DATABASE_URL".to_string(), format!("postgres://postgres:postgres@db:5432/{}", name)),
+                ("REDIS_URL".to_string(), "redis://cache:6379".to_string()),
+            ]),
+            volumes: Vec::new(),
+            depends_on: vec!["db".to_string(), "cache".to_string()],
+            health_check: None,
+        });
+
+        Self {
+            name: name.to_string(),
+            services,
+            volumes: vec!["db-data".to_string()],
+        }
+    }
+
+    /// Services in dependency order (dependencies first)
+    pub fn start_order(&self) -> Result<Vec<String>> {
+        fn visit(
+            spec: &StackSpec,
+            name: &str,
+            visiting: &mut Vec<String>,
+            order: &mut Vec<String>,
+        ) -> Result<()> {
+            if order.iter().any(|n| n == name) {
+                return Ok(());
+            }
+            if visiting.iter().any(|n| n == name) {
+                visiting.push(name.to_string());
+                return Err(anyhow!("Dependency cycle in stack: {}", visiting.join(" -> ")));
+            }
+
+            let service = spec.services.get(name)
+                .ok_or_else(|| anyhow!("Unknown service in depends_on: {}", name))?;
+
+            visiting.push(name.to_string());
+            for dep in &service.depends_on {
+                visit(spec, dep, visiting, order)?;
+            }
+            visiting.pop();
+            order.push(name.to_string());
+            Ok(())
+        }
+
+        let mut order = Vec::new();
+        for name in self.services.keys() {
+            visit(self, name, &mut Vec::new(), &mut order)?;
+        }
+        Ok(order)
+    }
+
+    /// Render the docker-compose document
+    pub fn to_compose(&self) -> Result<String> {
+        let order = self.start_order()?;
+        let mut out = String::new();
+
+        out.push_str(&format!("# Generated by `rcm let stack {}`. Edit .rcm/stacks/{}.json instead.\n", self.name, self.name));
+        out.push_str(&format!("name: {}\n", compose_project(&self.name)));
+        out.push_str("services:\n");
+
+        for name in &order {
+            let service = &self.services[name];
+            out.push_str(&format!("  {}:\n", name));
+
+            match (service.resolved_image(), &service.build) {
+                (Some(image), _) => out.push_str(&format!("    image: {}\n", image)),
+                (None, Some(build)) => out.push_str(&format!("    build: {}\n", quote(build))),
+                (None, None) => return Err(anyhow!("Service '{}' needs an image or build context", name)),
+            }
+
+            if let Some(command) = &service.command {
+                out.push_str(&format!("    command: [{}]\n", command.iter().map(|c| quote(c)).collect::<Vec<_>>().join(", ")));
+            }
+
+            if !service.ports.is_empty() {
+                out.push_str("    ports:\n");
+                for port in &service.ports {
+                    out.push_str(&format!("      - {}\n", quote(port)));
+                }
+            }
+
+            if !service.environment.is_empty() {
+                let env: BTreeMap<_, _> = service.environment.iter().collect();
+                out.push_str("    environment:\n");
+                for (key, value) in env {
+                    out.push_str(&format!("      {}: {}\n", key, quote(value)));
+                }
+            }
+
+            if !service.volumes.is_empty() {
+                out.push_str("    volumes:\n");
+                for volume in &service.volumes {
+                    out.push_str(&format!("      - {}\n", quote(volume)));
+                }
+            }
+
+            if !service.depends_on.is_empty() {
+                out.push_str("    depends_on:\n");
+                for dep in &service.depends_on {
+                    let condition = if self.services[dep].resolved_health_check().is_some() {
+                        "service_healthy"
+                    } else {
+                        "service_started"
+                    };
+                    out.push_str(&format!("      {}:\n        condition: {}\n", dep, condition));
+                }
+            }
+
+            if let Some(check) = service.resolved_health_check() {
+                out.push_str("    healthcheck:\n");
+                out.push_str(&format!("      test: [{}]\n", check.test.iter().map(|t| quote(t)).collect::<Vec<_>>().join(", ")));
+                out.push_str(&format!("      interval: {}\n", check.interval));
+                out.push_str(&format!("      timeout: {}\n", check.timeout));
+                out.push_str(&format!("      retries: {}\n", check.retries));
+            }
+
+            out.push_str("    restart: unless-stopped\n");
+        }
+
+        if !self.volumes.is_empty() {
+            out.push_str("volumes:\n");
+            for volume in &self.volumes {
+                out.push_str(&format!("  {}: {{}}\n", volume));
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+fn compose_project(name: &str) -> String {
+    format!("rcm-{}", util::sanitize_filename(name).to_lowercase())
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Runs stack specs through docker compose
+pub struct StackManager {
+    workspace_root: PathBuf,
+    stacks_dir: PathBuf,
+}
+
+impl StackManager {
+    pub fn new(workspace_root: &Path) -> Self {
+        Self {
+            workspace_root: workspace_root.to_path_buf(),
+            stacks_dir: workspace_root.join(".rcm").join("stacks"),
+        }
+    }
+
+    fn spec_path(&self, name: &str) -> PathBuf {
+        self.stacks_dir.join(format!("{}.json", name))
+    }
+
+    fn compose_path(&self, name: &str) -> PathBuf {
+        self.stacks_dir.join(name).join("docker-compose.yml")
+    }
+
+    /// Load a stack spec, writing a starter template if it does not exist
+    pub async fn load_or_create(&self, name: &str) -> Result<(StackSpec, bool)> {
+        let path = self.spec_path(name);
+
+        if !path.exists() {
+            fs::create_dir_all(&self.stacks_dir).await
+                .context("Failed to create stacks directory")?;
+            let spec = StackSpec::template(name);
+            fs::write(&path, serde_json::to_string_pretty(&spec)?).await
+                .context("Failed to write stack spec")?;
+            return Ok((spec, true));
+        }
+
+        let content = fs::read_to_string(&path).await
+            .context("Failed to read stack spec")?;
+        let spec = serde_json::from_str(&content)
+            .context("Failed to parse stack spec")?;
+        Ok((spec, false))
+    }
+
+    /// Write the compose file for a stack
+    pub async fn materialize(&self, spec: &StackSpec) -> Result<PathBuf> {
+        let path = self.compose_path(&spec.name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await
+                .context("Failed to create stack directory")?;
+        }
+        fs::write(&path, spec.to_compose()?).await
+            .context("Failed to write docker-compose.yml")?;
+        Ok(path)
+    }
+
+    /// Run `docker compose` against a stack
+    async fn compose(&self, spec: &StackSpec, args: &[&str]) -> Result<()> {
+        let compose_file = self.compose_path(&spec.name);
+        let project = compose_project(&spec.name);
+
+        let mut cmd = if util::command_exists("docker").await {
+            let mut c = AsyncCommand::new("docker");
+            c.arg("compose");
+            c
+        } else if util::command_exists("docker-compose").await {
+            AsyncCommand::new("docker-compose")
+        } else {
+            return Err(anyhow!("Docker Compose not found. Install Docker from https://docs.docker.com/get-docker/"));
+        };
+
+        cmd.arg("-f").arg(&compose_file)
+           .arg("-p").arg(&project)
+           .arg("--project-directory").arg(&self.workspace_root)
+           .args(args);
+
+        let status = cmd.status().await
+            .context("Failed to run docker compose")?;
+
+        if !status.success() {
+            return Err(anyhow!("docker compose {} failed for stack '{}'", args.join(" "), spec.name));
+        }
+        Ok(())
+    }
+}
+
+/// Handle `rcm let stack <name>`
+pub async fn run(
+    workspace_root: &Path,
+    name: &str,
+    deploy: bool,
+    plan: bool,
+    clean: bool,
+) -> Result<()> {
+    let manager = StackManager::new(workspace_root);
+    let (spec, created) = manager.load_or_create(name).await?;
+
+    if created {
+        println!("{}", style(format!(
+            "📝 Created stack spec {} (app, db, cache); edit it and re-run",
+            manager.spec_path(name).display()
+        )).yellow());
+        if deploy {
+            return Ok(());
+        }
+    }
+
+    let order = spec.start_order()?;
+    let compose_path = manager.materialize(&spec).await?;
+
+    if plan || (!deploy && !clean) {
+        println!("{}", style(format!("=== LET Stack: {} ===", spec.name)).cyan().bold());
+        println!("Compose file: {}", compose_path.display());
+        println!("\nStart order:");
+        for (i, service_name) in order.iter().enumerate() {
+            let service = &spec.services[service_name];
+            let source = service.resolved_image()
+                .or_else(|| service.build.as_ref().map(|b| format!("build {}", b)))
+                .unwrap_or_default();
+            let deps = if service.depends_on.is_empty() {
+                String::new()
+            } else {
+                format!(" (after {})", service.depends_on.join(", "))
+            };
+            println!("  {}. {} [{:?}] {}{}", i + 1, service_name, service.kind, source, deps);
+        }
+        return Ok(());
+    }
+
+    if clean {
+        println!("{}", style(format!("🧹 Stopping stack '{}'...", spec.name)).cyan());
+        manager.compose(&spec, &["down", "--remove-orphans"]).await?;
+        println!("{}", style(format!("✅ Stack '{}' stopped", spec.name)).green());
+        return Ok(());
+    }
+
+    println!("{}", style(format!("🚀 Deploying stack '{}' ({} services)...", spec.name, order.len())).cyan().bold());
+    manager.compose(&spec, &["up", "-d", "--build", "--wait"]).await?;
+    println!("{}", style(format!("✅ Stack '{}' is up and healthy", spec.name)).green().bold());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_start_order() {
+        let spec = StackSpec::template("shop");
+        let order = spec.start_order().unwrap();
+        assert_eq!(order.last().map(String::as_str), Some("app"));
+    }
+
+    #[test]
+    fn test_cycle_detected() {
+        let mut spec = StackSpec::template("shop");
+        spec.services.get_mut("db").unwrap().depends_on.push("app".to_string());
+        assert!(spec.start_order().is_err());
+    }
+
+    #[test]
+    fn test_compose_health_conditions() {
+        let compose = StackSpec::template("shop").to_compose().unwrap();
+        assert!(compose.contains("image: postgres:16"));
+        assert!(compose.contains("condition: service_healthy"));
+        assert!(compose.contains("pg_isready"));
+        assert!(compose.contains("build: \".\""));
+    }
+}
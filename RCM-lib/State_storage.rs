@@ -0,0 +1,557 @@
+//! Storage backends for workspace state
+//!
+//! State RCM keeps between runs (ensure fingerprints, the transaction log,
+//! workspace metrics) is stored as JSON values in named collections, behind
+//! [`StateStore`]. Two backends exist, picked by `core.state_backend` (or
+//! `RCM_STATE_BACKEND`):
+//!
+//! - `files` (default): one `.rcm/state/<collection>.json` per collection.
+//!   Writers take a lock file and replace the JSON atomically, so concurrent
+//!   runs no longer overwrite each other's updates.
+//! - `sqlite`: a single `.rcm/state.db` in WAL mode, indexed by key, for
+//!   workspaces tracking thousands of packages or models.
+//!
+//! `rcm state migrate` copies everything from one backend to the other.
+//!
+//! Declarative files people edit or other tools read (the workspace manifest,
+//! `.rcm/gpt-configs/registry.json`) stay plain files; their writers go through
+//! [`replace_file`] so a concurrent reader never sees half a file.
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use console::style;
+use fs2::FileExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tabled::{Table, Tabled};
+use crate::config::Config;
+use crate::util;
+use crate::workspace::Workspace;
+
+/// Selects the backend over `core.state_backend`
+pub const BACKEND_ENV: &str = "RCM_STATE_BACKEND";
+
+/// Directory of the `files` backend, relative to the workspace root
+pub const STATE_DIR: &str = ".rcm/state";
+
+/// Database of the `sqlite` backend, relative to the workspace root
+pub const STATE_DB: &str = ".rcm/state.db";
+
+/// Where workspace state is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateBackend {
+    Files,
+    Sqlite,
+}
+
+impl StateBackend {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "files" | "file" | "json" => Ok(Self::Files),
+            "sqlite" | "sqlite3" => Ok(Self::Sqlite),
+            other => Err(anyhow!("Unknown state backend '{}'. Use files or sqlite", other)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Files => "files",
+            Self::Sqlite => "sqlite",
+        }
+    }
+
+    /// Where this backend keeps the state of the workspace at `root`
+    pub fn location(&self, root: &Path) -> PathBuf {
+        match self {
+            Self::Files => root.join(STATE_DIR),
+            Self::Sqlite => root.join(STATE_DB),
+        }
+    }
+
+    /// `RCM_STATE_BACKEND`, else the config, else plain files
+    pub async fn current() -> Result<Self> {
+        if let Ok(name) = std::env::var(BACKEND_ENV) {
+            return Self::parse(&name);
+        }
+        let config = Config::load(None).await.unwrap_or_default();
+        Self::parse(&config.core.state_backend)
+    }
+}
+
+/// Keyed JSON values grouped in collections
+///
+/// `update` is atomic with respect to other writers of the same store, in
+/// this process or another one. Writes are skipped in dry-run mode.
+pub trait StateStore: Send + Sync {
+    fn backend(&self) -> StateBackend;
+
+    fn get(&self, collection: &str, key: &str) -> Result<Option<Value>>;
+
+    /// Entries whose key starts with `prefix`, ordered by key
+    fn scan(&self, collection: &str, prefix: &str) -> Result<Vec<(String, Value)>>;
+
+    /// Replace the value at `key` with what `f` returns; `None` removes it
+    fn update(
+        &self,
+        collection: &str,
+        key: &str,
+        f: &mut dyn FnMut(Option<Value>) -> Result<Option<Value>>,
+    ) -> Result<()>;
+
+    /// Names of the collections holding at least one entry
+    fn collections(&self) -> Result<Vec<String>>;
+
+    fn put(&self, collection: &str, key: &str, value: Value) -> Result<()> {
+        let mut value = Some(value);
+        self.update(collection, key, &mut |_| Ok(value.take()))
+    }
+
+    fn remove(&self, collection: &str, key: &str) -> Result<()> {
+        self.update(collection, key, &mut |_| Ok(None))
+    }
+
+    fn list(&self, collection: &str) -> Result<Vec<(String, Value)>> {
+        self.scan(collection, "")
+    }
+}
+
+impl<'a> dyn StateStore + 'a {
+    /// `get`, deserialized
+    pub fn get_as<T: DeserializeOwned>(&self, collection: &str, key: &str) -> Result<Option<T>> {
+        self.get(collection, key)?
+            .map(|value| serde_json::from_value(value)
+                .with_context(|| format!("Invalid state entry {}/{}", collection, key)))
+            .transpose()
+    }
+
+    /// `list`, deserialized; entries that no longer parse are skipped
+    pub fn list_as<T: DeserializeOwned>(&self, collection: &str) -> Result<BTreeMap<String, T>> {
+        Ok(self.list(collection)?
+            .into_iter()
+            .filter_map(|(key, value)| match serde_json::from_value(value) {
+                Ok(parsed) => Some((key, parsed)),
+                Err(e) => {
+                    tracing::warn!("Ignoring unreadable state entry {}/{}: {}", collection, key, e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// `put`, serialized
+    pub fn put_as<T: Serialize>(&self, collection: &str, key: &str, value: &T) -> Result<()> {
+        self.put(collection, key, serde_json::to_value(value)?)
+    }
+}
+
+/// Collection names double as file names, so keep them plain
+fn check_collection(name: &str) -> Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(anyhow!("Invalid state collection name '{}'", name));
+    }
+    Ok(())
+}
+
+/// The `files` backend
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(root: &Path) -> Self {
+        Self { dir: root.join(STATE_DIR) }
+    }
+
+    fn path(&self, collection: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", collection))
+    }
+
+    fn read(&self, collection: &str) -> Result<BTreeMap<String, Value>> {
+        check_collection(collection)?;
+        let path = self.path(collection);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+}
+
+impl StateStore for FileStore {
+    fn backend(&self) -> StateBackend {
+        StateBackend::Files
+    }
+
+    fn get(&self, collection: &str, key: &str) -> Result<Option<Value>> {
+        Ok(self.read(collection)?.remove(key))
+    }
+
+    fn scan(&self, collection: &str, prefix: &str) -> Result<Vec<(String, Value)>> {
+        Ok(self.read(collection)?
+            .into_iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .collect())
+    }
+
+    fn update(
+        &self,
+        collection: &str,
+        key: &str,
+        f: &mut dyn FnMut(Option<Value>) -> Result<Option<Value>>,
+    ) -> Result<()> {
+        check_collection(collection)?;
+        if util::is_dry_run() {
+            f(self.get(collection, key)?)?;
+            return Ok(());
+        }
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let lock_path = self.dir.join(format!("{}.lock", collection));
+        let lock = std::fs::OpenOptions::new().create(true).truncate(false).write(true).open(&lock_path)
+            .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+        lock.lock_exclusive()
+            .with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+
+        let mut entries = self.read(collection)?;
+        if let Some(value) = f(entries.remove(key))? {
+            entries.insert(key.to_string(), value);
+        }
+        // Readers never take the lock, so the file is swapped in whole
+        swap_in(&self.path(collection), serde_json::to_string_pretty(&entries)?.as_bytes())?;
+        lock.unlock()?;
+        Ok(())
+    }
+
+    fn collections(&self) -> Result<Vec<String>> {
+        let Ok(listing) = std::fs::read_dir(&self.dir) else {
+            return Ok(Vec::new());
+        };
+        let mut names: Vec<String> = listing
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().to_str()?.strip_suffix(".json").map(str::to_string))
+            .filter(|name| check_collection(name).is_ok())
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+}
+
+/// Write `content` next to `path` and rename it over `path`
+fn swap_in(path: &Path, content: &[u8]) -> Result<()> {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("state");
+    let temp = path.with_file_name(format!(".{}.{}", name, std::process::id()));
+    std::fs::write(&temp, content)
+        .with_context(|| format!("Failed to write {}", temp.display()))?;
+    std::fs::rename(&temp, path)
+        .with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+/// Replace a file atomically, one writer at a time (skipped in dry-run mode)
+pub async fn replace_file(path: &Path, content: Vec<u8>) -> Result<()> {
+    if util::is_dry_run() {
+        return Ok(());
+    }
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let mut lock_path = path.clone().into_os_string();
+        lock_path.push(".lock");
+        let lock = std::fs::OpenOptions::new().create(true).truncate(false).write(true).open(&lock_path)
+            .with_context(|| format!("Failed to open {}", PathBuf::from(&lock_path).display()))?;
+        lock.lock_exclusive()?;
+        swap_in(&path, &content)?;
+        lock.unlock()?;
+        Ok(())
+    })
+    .await
+    .context("File write task failed")?
+}
+
+/// The `sqlite` backend
+#[cfg(feature = "sqlite")]
+pub struct SqliteStore {
+    path: PathBuf,
+    conn: std::sync::Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteStore {
+    pub fn open(root: &Path) -> Result<Self> {
+        let path = root.join(STATE_DB);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = rusqlite::Connection::open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        conn.busy_timeout(std::time::Duration::from_secs(10))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS entries (
+                collection TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                PRIMARY KEY (collection, key)
+            ) WITHOUT ROWID;",
+        ).with_context(|| format!("Failed to initialise {}", path.display()))?;
+        Ok(Self { path, conn: std::sync::Mutex::new(conn) })
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, rusqlite::Connection> {
+        self.conn.lock().expect("state database lock poisoned")
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl StateStore for SqliteStore {
+    fn backend(&self) -> StateBackend {
+        StateBackend::Sqlite
+    }
+
+    fn get(&self, collection: &str, key: &str) -> Result<Option<Value>> {
+        use rusqlite::OptionalExtension;
+        let text: Option<String> = self.conn()
+            .query_row(
+                "SELECT value FROM entries WHERE collection = ?1 AND key = ?2",
+                rusqlite::params![collection, key],
+                |row| row.get(0),
+            )
+            .optional()
+            .with_context(|| format!("Failed to query {}", self.path.display()))?;
+        text.map(|text| serde_json::from_str(&text).map_err(Into::into)).transpose()
+    }
+
+    fn scan(&self, collection: &str, prefix: &str) -> Result<Vec<(String, Value)>> {
+        let conn = self.conn();
+        // The primary key orders rows by key, so a prefix is a contiguous range
+        let mut statement = conn.prepare_cached(
+            "SELECT key, value FROM entries WHERE collection = ?1 AND key >= ?2 ORDER BY key",
+        )?;
+        let mut rows = statement.query(rusqlite::params![collection, prefix])?;
+        let mut entries = Vec::new();
+        while let Some(row) = rows.next()? {
+            let key: String = row.get(0)?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            let text: String = row.get(1)?;
+            entries.push((key, serde_json::from_str(&text)?));
+        }
+        Ok(entries)
+    }
+
+    fn update(
+        &self,
+        collection: &str,
+        key: &str,
+        f: &mut dyn FnMut(Option<Value>) -> Result<Option<Value>>,
+    ) -> Result<()> {
+        use rusqlite::OptionalExtension;
+        if util::is_dry_run() {
+            f(self.get(collection, key)?)?;
+            return Ok(());
+        }
+        let mut conn = self.conn();
+        let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+        let current: Option<String> = tx
+            .query_row(
+                "SELECT value FROM entries WHERE collection = ?1 AND key = ?2",
+                rusqlite::params![collection, key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let current = current.map(|text| serde_json::from_str(&text)).transpose()?;
+        match f(current)? {
+            Some(value) => tx.execute(
+                "INSERT INTO entries (collection, key, value) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (collection, key) DO UPDATE SET value = excluded.value",
+                rusqlite::params![collection, key, serde_json::to_string(&value)?],
+            )?,
+            None => tx.execute(
+                "DELETE FROM entries WHERE collection = ?1 AND key = ?2",
+                rusqlite::params![collection, key],
+            )?,
+        };
+        tx.commit().with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    fn collections(&self) -> Result<Vec<String>> {
+        let conn = self.conn();
+        let mut statement = conn.prepare("SELECT DISTINCT collection FROM entries ORDER BY collection")?;
+        let names = statement.query_map([], |row| row.get(0))?.collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(names)
+    }
+}
+
+/// Open the given backend for the workspace at `root`
+pub fn open(root: &Path, backend: StateBackend) -> Result<Box<dyn StateStore>> {
+    match backend {
+        StateBackend::Files => Ok(Box::new(FileStore::new(root))),
+        #[cfg(feature = "sqlite")]
+        StateBackend::Sqlite => Ok(Box::new(SqliteStore::open(root)?)),
+        #[cfg(not(feature = "sqlite"))]
+        StateBackend::Sqlite => Err(anyhow!("The sqlite state backend needs RCM built with the sqlite feature")),
+    }
+}
+
+/// Run `f` against the configured backend on the blocking pool
+///
+/// Both backends do synchronous file and SQLite I/O, so async code reaches
+/// the store through here rather than stalling the runtime.
+pub async fn with_store<T, F>(root: &Path, f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&dyn StateStore) -> Result<T> + Send + 'static,
+{
+    let backend = StateBackend::current().await?;
+    let root = root.to_path_buf();
+    tokio::task::spawn_blocking(move || f(open(&root, backend)?.as_ref()))
+        .await
+        .context("State storage task failed")?
+}
+
+/// Copy every entry of `from` into `to`; returns how many were copied
+pub fn copy_all(from: &dyn StateStore, to: &dyn StateStore) -> Result<usize> {
+    let mut copied = 0;
+    for collection in from.collections()? {
+        for (key, value) in from.list(&collection)? {
+            to.put(&collection, &key, value)?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+#[derive(Subcommand)]
+pub enum StateCommands {
+    /// Show the state backend and what it holds
+    Info,
+    /// Copy all state into another backend
+    Migrate {
+        /// Backend to copy into (files, sqlite)
+        to: String,
+        /// Backend to copy from (default: the configured one)
+        #[arg(long)]
+        from: Option<String>,
+    },
+}
+
+#[derive(Tabled)]
+struct CollectionRow {
+    #[tabled(rename = "Collection")]
+    collection: String,
+    #[tabled(rename = "Entries")]
+    entries: usize,
+}
+
+/// Handle state commands
+pub async fn handle_command(workspace: &Workspace, cmd: StateCommands) -> Result<()> {
+    let root = workspace.root();
+    match cmd {
+        StateCommands::Info => {
+            let (backend, rows) = with_store(root, |store| {
+                let mut rows = Vec::new();
+                for collection in store.collections()? {
+                    let entries = store.list(&collection)?.len();
+                    rows.push(CollectionRow { collection, entries });
+                }
+                Ok((store.backend(), rows))
+            }).await?;
+            println!("{} {} ({})", style("🗄️  State backend:").bold(), backend.name(), backend.location(root).display());
+            if rows.is_empty() {
+                println!("{}", style("No state recorded yet").dim());
+            } else {
+                println!("{}", Table::new(&rows));
+            }
+            Ok(())
+        }
+        StateCommands::Migrate { to, from } => {
+            let from = match from {
+                Some(name) => StateBackend::parse(&name)?,
+                None => StateBackend::current().await?,
+            };
+            let to = StateBackend::parse(&to)?;
+            if from == to {
+                return Err(anyhow!("State is already stored in {}", to.name()));
+            }
+            util::ensure_writable(format_args!("migrate state to {}", to.name()))?;
+            let root_path = root.to_path_buf();
+            let copied = tokio::task::spawn_blocking(move || {
+                copy_all(open(&root_path, from)?.as_ref(), open(&root_path, to)?.as_ref())
+            })
+            .await
+            .context("State migration task failed")??;
+            println!("{}", style(format!("✅ Copied {} entries from {} to {}", copied, from.name(), to.name())).green());
+            if !util::is_dry_run() {
+                println!("Switch with: rcm config set core.state_backend {}", to.name());
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn exercise(store: &dyn StateStore) {
+        store.put("ensure", "npm", json!({ "ok": true })).unwrap();
+        store.put("ensure", "cargo", json!(1)).unwrap();
+        store.put("models", "llama", json!("x")).unwrap();
+        assert_eq!(store.get("ensure", "npm").unwrap(), Some(json!({ "ok": true })));
+        assert_eq!(store.get("ensure", "go").unwrap(), None);
+
+        let keys: Vec<String> = store.list("ensure").unwrap().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec!["cargo", "npm"]);
+        assert_eq!(store.scan("ensure", "np").unwrap().len(), 1);
+
+        store.update("ensure", "cargo", &mut |v| Ok(v.map(|n| json!(n.as_i64().unwrap() + 1)))).unwrap();
+        assert_eq!(store.get("ensure", "cargo").unwrap(), Some(json!(2)));
+        store.remove("ensure", "npm").unwrap();
+        assert_eq!(store.list("ensure").unwrap().len(), 1);
+        assert_eq!(store.collections().unwrap(), vec!["ensure", "models"]);
+    }
+
+    #[test]
+    fn test_file_store() {
+        let root = tempdir().unwrap();
+        exercise(&FileStore::new(root.path()));
+        assert!(FileStore::new(root.path()).get("../escape", "x").is_err());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_store_and_copy() {
+        let root = tempdir().unwrap();
+        exercise(&SqliteStore::open(root.path()).unwrap());
+
+        let files = FileStore::new(root.path());
+        let sqlite = SqliteStore::open(root.path()).unwrap();
+        assert_eq!(copy_all(&sqlite, &files).unwrap(), 2);
+        assert_eq!(files.get("models", "llama").unwrap(), Some(json!("x")));
+    }
+
+    #[tokio::test]
+    async fn test_replace_file() {
+        let root = tempdir().unwrap();
+        let path = root.path().join("gpt-configs").join("registry.json");
+        replace_file(&path, b"{}".to_vec()).await.unwrap();
+        replace_file(&path, b"{\"models\":{}}".to_vec()).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"models\":{}}");
+    }
+
+    #[test]
+    fn test_parse_backend() {
+        assert_eq!(StateBackend::parse("SQLite").unwrap(), StateBackend::Sqlite);
+        assert_eq!(StateBackend::parse("files").unwrap(), StateBackend::Files);
+        assert!(StateBackend::parse("redis").is_err());
+    }
+}
@@ -4,13 +4,19 @@
 
 use anyhow::{anyhow, Context, Result};
 use clap::Subcommand;
+use console::style;
 use serde::{Deserialize, Serialize};
+use tabled::{Table, Tabled};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use tokio::fs;
+use walkdir::WalkDir;
 use crate::workspace::Workspace;
-use crate::util::{self, execute_command, get_os_info};
+use crate::system_repos::{RepoManager, RepoOptions};
+use crate::artifacts::{self, Expectations};
+use crate::telemetry;
+use crate::util::{self, execute_command, execute_mutation, get_os_info};
 
 #[derive(Subcommand)]
 pub enum SystemCommands {
@@ -89,6 +95,9 @@ pub enum SystemCommands {
         /// Filter by pattern
         #[arg(long)]
         filter: Option<String>,
+        /// Specific package manager to use
+        #[arg(long)]
+        manager: Option<String>,
     },
     
     /// Clean package cache
@@ -109,8 +118,14 @@ pub enum SystemCommands {
     
     /// Install from source (compile-time dependencies)
     Source {
-        /// Source URL or package specification
+        /// Source URL (tarball or git repo), local directory, or the name to uninstall
         source: String,
+        /// Name to record the install under (defaults to the archive or repo name)
+        #[arg(long)]
+        name: Option<String>,
+        /// Remove the files recorded for a previous source install
+        #[arg(long)]
+        uninstall: bool,
         /// Build directory
         #[arg(long)]
         build_dir: Option<String>,
@@ -123,6 +138,15 @@ pub enum SystemCommands {
         /// Configure options
         #[arg(long, value_delimiter = ' ')]
         configure_opts: Vec<String>,
+        /// Build on one NUMA node's CPUs and memory (jobs default to its CPU count)
+        #[arg(long)]
+        numa_node: Option<u32>,
+        /// Expected SHA-256 of the source archive
+        #[arg(long)]
+        sha256: Option<String>,
+        /// Detached minisign/GPG signature (path or URL) checked against security.trusted_keys
+        #[arg(long)]
+        signature: Option<String>,
     },
 }
 
@@ -130,16 +154,34 @@ pub enum SystemCommands {
 pub enum RepoCommands {
     /// Add repository
     Add {
-        /// Repository URL or identifier
+        /// Repository URL, `deb ...` line, .repo file URL, or Homebrew tap (user/repo)
         repo: String,
-        /// Repository key/signature
+        /// Repository key/signature (URL, file, or pacman key ID)
         #[arg(long)]
         key: Option<String>,
+        /// Repository name used for files and sections
+        #[arg(long)]
+        name: Option<String>,
+        /// apt suite (defaults to the release codename)
+        #[arg(long)]
+        suite: Option<String>,
+        /// apt components
+        #[arg(long, value_delimiter = ',', default_value = "main")]
+        components: Vec<String>,
+        /// Skip confirmation prompts
+        #[arg(long, short)]
+        yes: bool,
+        /// Allow a repository without signature checks (dnf gpgcheck=0, pacman TrustAll)
+        #[arg(long)]
+        insecure: bool,
     },
     /// Remove repository
     Remove {
         /// Repository identifier
         repo: String,
+        /// Skip confirmation prompts
+        #[arg(long, short)]
+        yes: bool,
     },
     /// List repositories
     List,
@@ -163,6 +205,56 @@ pub enum SystemPackageManager {
     PkgNg,    // FreeBSD (new)
 }
 
+/// Normalized information about a system package
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SystemPackageInfo {
+    pub name: String,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    /// Installed size as reported by the manager
+    pub size: Option<String>,
+    pub installed: bool,
+    pub manager: String,
+}
+
+#[derive(Tabled)]
+struct SystemPackageRow {
+    #[tabled(rename = "Package")]
+    name: String,
+    #[tabled(rename = "Version")]
+    version: String,
+    #[tabled(rename = "Description")]
+    description: String,
+}
+
+/// Parse `Key : Value` blocks (rpm -qi, pacman -Qi, winget show, pkg info)
+fn parse_key_values(output: &str) -> HashMap<String, String> {
+    let mut fields = HashMap::new();
+    for line in output.lines() {
+        if let Some((key, value)) = line.split_once(':') {
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+            // Keep the first occurrence; later matches are usually wrapped description lines
+            if !key.is_empty() && !value.is_empty() {
+                fields.entry(key).or_insert_with(|| value.to_string());
+            }
+        }
+    }
+    fields
+}
+
+/// Split `name-1.2.3-r0` into name and version at the first `-` followed by a digit
+fn split_name_version(entry: &str) -> (String, Option<String>) {
+    let bytes = entry.as_bytes();
+    for i in 0..bytes.len().saturating_sub(1) {
+        if bytes[i] == b'-' && bytes[i + 1].is_ascii_digit() {
+            return (entry[..i].to_string(), Some(entry[i + 1..].to_string()));
+        }
+    }
+    (entry.to_string(), None)
+}
+
 impl SystemPackageManager {
     /// Detect system package manager
     pub async fn detect() -> Result<Self> {
@@ -240,6 +332,29 @@ impl SystemPackageManager {
         }
     }
     
+    /// Resolve a manager requested with `--manager`, by name or command
+    pub async fn named(name: &str) -> Result<Self> {
+        let manager = match name.to_lowercase().as_str() {
+            "apt" | "apt-get" => Self::Apt,
+            "yum" => Self::Yum,
+            "dnf" => Self::Dnf,
+            "pacman" => Self::Pacman,
+            "brew" | "homebrew" => Self::Brew,
+            "choco" | "chocolatey" => Self::Chocolatey,
+            "winget" => Self::Winget,
+            "zypper" => Self::Zypper,
+            "emerge" | "portage" => Self::Portage,
+            "apk" => Self::Apk,
+            "pkg_add" => Self::Pkg,
+            "pkg" | "pkgng" => Self::PkgNg,
+            _ => return Err(anyhow!("Unknown system package manager: {}", name)),
+        };
+        if !util::command_exists(manager.command()).await {
+            return Err(anyhow!("{} is not installed", manager.command()));
+        }
+        Ok(manager)
+    }
+    
     /// Get package manager command
     pub fn command(&self) -> &'static str {
         match self {
@@ -605,6 +720,306 @@ impl SystemPackageManager {
         
         cmd
     }
+    
+    /// Build command that prints the installed version of a package
+    pub fn installed_version_cmd(&self, package: &str) -> Command {
+        match self {
+            Self::Apt => {
+                let mut cmd = Command::new("dpkg-query");
+                cmd.args(["-W", "-f=${Version}", package]);
+                cmd
+            }
+            Self::Yum | Self::Dnf | Self::Zypper => {
+                let mut cmd = Command::new("rpm");
+                cmd.args(["-q", "--qf", "%{VERSION}-%{RELEASE}", package]);
+                cmd
+            }
+            Self::Pacman => {
+                let mut cmd = Command::new("pacman");
+                cmd.args(["-Q", package]);
+                cmd
+            }
+            Self::Brew => {
+                let mut cmd = Command::new("brew");
+                cmd.args(["list", "--versions", package]);
+                cmd
+            }
+            Self::Chocolatey => {
+                let mut cmd = Command::new("choco");
+                cmd.args(["list", "--local-only", "--exact", "--limit-output", package]);
+                cmd
+            }
+            Self::Winget => {
+                let mut cmd = Command::new("winget");
+                cmd.args(["list", "--exact", "--id", package]);
+                cmd
+            }
+            Self::Portage => {
+                let mut cmd = Command::new("qlist");
+                cmd.args(["-Iv", package]);
+                cmd
+            }
+            Self::Apk => {
+                let mut cmd = Command::new("apk");
+                cmd.args(["info", "-v", package]);
+                cmd
+            }
+            Self::Pkg | Self::PkgNg => {
+                let mut cmd = Command::new("pkg");
+                cmd.args(["query", "%v", package]);
+                cmd
+            }
+        }
+    }
+
+    /// Extract the version from `installed_version_cmd` output
+    pub fn parse_installed_version(&self, package: &str, output: &str) -> Option<String> {
+        let line = output.lines().map(str::trim).find(|l| !l.is_empty())?;
+
+        let version = match self {
+            // "name version" / "name version1 version2"
+            Self::Pacman | Self::Brew => line.split_whitespace().nth(1)?.to_string(),
+            // "name|version"
+            Self::Chocolatey => line.split('|').nth(1)?.to_string(),
+            // table output; version is the column after the id
+            Self::Winget => {
+                let row = output.lines().find(|l| l.contains(package))?;
+                let mut cols = row.split_whitespace().skip_while(|c| *c != package);
+                cols.nth(1)?.to_string()
+            }
+            // "name-version"
+            Self::Portage | Self::Apk => line
+                .strip_prefix(package)
+                .map(|v| v.trim_start_matches('-').to_string())
+                .unwrap_or_else(|| line.to_string()),
+            _ => line.to_string(),
+        };
+
+        Some(version)
+    }
+    
+    /// Build command that prints details about a package
+    pub fn info_cmd(&self, package: &str) -> Command {
+        let (program, args): (&str, Vec<&str>) = match self {
+            Self::Apt => ("apt-cache", vec!["show", "--no-all-versions", package]),
+            Self::Yum | Self::Dnf => (self.command(), vec!["info", "-q", package]),
+            Self::Zypper => ("zypper", vec!["--quiet", "info", package]),
+            Self::Pacman => ("pacman", vec!["-Si", package]),
+            Self::Brew => ("brew", vec!["info", "--json=v2", package]),
+            Self::Chocolatey => ("choco", vec!["info", package]),
+            Self::Winget => ("winget", vec!["show", "--exact", "--id", package]),
+            Self::Portage => ("emerge", vec!["--search", "--exact-match", package]),
+            Self::Apk => ("apk", vec!["info", "-a", package]),
+            Self::Pkg | Self::PkgNg => ("pkg", vec!["rquery", "%n\t%v\t%c\t%w\t%sh", package]),
+        };
+        let mut cmd = Command::new(program);
+        cmd.args(args);
+        cmd
+    }
+    
+    /// Normalize `info_cmd` output
+    pub fn parse_info(&self, package: &str, output: &str) -> Option<SystemPackageInfo> {
+        let manager = self.command().to_string();
+        
+        match self {
+            Self::Brew => {
+                let json: serde_json::Value = serde_json::from_str(output).ok()?;
+                let formula = json.get("formulae").and_then(|f| f.get(0))
+                    .or_else(|| json.get("casks").and_then(|c| c.get(0)))?;
+                let str_field = |k: &str| formula.get(k).and_then(|v| v.as_str()).map(str::to_string);
+                Some(SystemPackageInfo {
+                    name: str_field("name").or_else(|| str_field("token")).unwrap_or_else(|| package.to_string()),
+                    version: formula.pointer("/versions/stable").and_then(|v| v.as_str()).map(str::to_string)
+                        .or_else(|| str_field("version")),
+                    description: str_field("desc"),
+                    homepage: str_field("homepage"),
+                    size: None,
+                    installed: formula.get("installed").and_then(|i| i.as_array()).is_some_and(|i| !i.is_empty()),
+                    manager,
+                })
+            }
+            Self::Pkg | Self::PkgNg => {
+                let mut cols = output.lines().next()?.split('\t');
+                Some(SystemPackageInfo {
+                    name: cols.next()?.to_string(),
+                    version: cols.next().map(str::to_string),
+                    description: cols.next().map(str::to_string),
+                    homepage: cols.next().map(str::to_string),
+                    size: cols.next().map(str::to_string),
+                    installed: false,
+                    manager,
+                })
+            }
+            Self::Chocolatey => {
+                // "name version [Approved]" on the first package line
+                let line = output.lines().find(|l| l.to_lowercase().starts_with(&package.to_lowercase()))?;
+                let mut parts = line.split_whitespace();
+                let fields = parse_key_values(output);
+                Some(SystemPackageInfo {
+                    name: parts.next()?.to_string(),
+                    version: parts.next().map(str::to_string),
+                    description: fields.get("summary").cloned(),
+                    homepage: fields.get("software site").cloned(),
+                    size: None,
+                    installed: false,
+                    manager,
+                })
+            }
+            Self::Apk => {
+                // "name-1.2.3-r0 description:" headers followed by values
+                let mut lines = output.lines();
+                let header = lines.next()?.split_whitespace().next()?;
+                let (name, version) = split_name_version(header);
+                let section = |title: &str| {
+                    output.split("\n\n")
+                        .find(|block| block.contains(title))
+                        .and_then(|block| block.lines().nth(1))
+                        .map(|l| l.trim().to_string())
+                };
+                Some(SystemPackageInfo {
+                    name,
+                    version,
+                    description: section("description:"),
+                    homepage: section("webpage:"),
+                    size: section("installed size:"),
+                    installed: false,
+                    manager,
+                })
+            }
+            _ => {
+                let fields = parse_key_values(output);
+                let get = |keys: &[&str]| keys.iter().find_map(|k| fields.get(*k).cloned());
+                let version = match (get(&["version"]), get(&["release"])) {
+                    // rpm splits version and release
+                    (Some(v), Some(r)) if matches!(self, Self::Yum | Self::Dnf) => Some(format!("{}-{}", v, r)),
+                    (v, _) => v,
+                };
+                Some(SystemPackageInfo {
+                    name: get(&["package", "name"]).unwrap_or_else(|| package.to_string()),
+                    version,
+                    description: get(&["summary", "description", "description-en"]),
+                    homepage: get(&["homepage", "url"]),
+                    size: get(&["installed-size", "installed size", "size"]),
+                    installed: false,
+                    manager,
+                })
+            }
+        }
+    }
+    
+    /// Build command that lists installed packages
+    pub fn list_cmd(&self, manual: bool) -> Command {
+        let (program, args): (&str, Vec<&str>) = match (self, manual) {
+            (Self::Apt, true) => ("apt-mark", vec!["showmanual"]),
+            (Self::Apt, false) => ("dpkg-query", vec!["-W", "-f=${Package}\t${Version}\t${binary:Summary}\n"]),
+            (Self::Dnf, true) => ("dnf", vec!["repoquery", "--userinstalled", "--qf", "%{name}\t%{version}-%{release}\t%{summary}\n"]),
+            (Self::Yum | Self::Dnf | Self::Zypper, _) => ("rpm", vec!["-qa", "--qf", "%{NAME}\t%{VERSION}-%{RELEASE}\t%{SUMMARY}\n"]),
+            (Self::Pacman, true) => ("pacman", vec!["-Qe"]),
+            (Self::Pacman, false) => ("pacman", vec!["-Q"]),
+            (Self::Brew, true) => ("brew", vec!["leaves"]),
+            (Self::Brew, false) => ("brew", vec!["list", "--versions"]),
+            (Self::Chocolatey, _) => ("choco", vec!["list", "--local-only", "--limit-output"]),
+            (Self::Winget, _) => ("winget", vec!["list", "--disable-interactivity"]),
+            (Self::Portage, true) => ("cat", vec!["/var/lib/portage/world"]),
+            (Self::Portage, false) => ("qlist", vec!["-Iv"]),
+            (Self::Apk, true) => ("cat", vec!["/etc/apk/world"]),
+            (Self::Apk, false) => ("apk", vec!["info", "-v"]),
+            (Self::Pkg | Self::PkgNg, true) => ("pkg", vec!["query", "-e", "%a = 0", "%n\t%v\t%c"]),
+            (Self::Pkg | Self::PkgNg, false) => ("pkg", vec!["query", "%n\t%v\t%c"]),
+        };
+        let mut cmd = Command::new(program);
+        cmd.args(args);
+        cmd
+    }
+    
+    /// Normalize `list_cmd` output
+    pub fn parse_list(&self, output: &str) -> Vec<SystemPackageInfo> {
+        let manager = self.command().to_string();
+        let mut lines: Vec<&str> = output.lines().map(str::trim_end).filter(|l| !l.trim().is_empty()).collect();
+        
+        if matches!(self, Self::Winget) {
+            // Table output: skip the header and separator rows
+            let start = lines.iter().position(|l| l.starts_with("---")).map(|i| i + 1).unwrap_or(0);
+            lines.drain(..start);
+        }
+        
+        lines
+            .into_iter()
+            .filter_map(|line| {
+                let (name, version, description) = if line.contains('\t') {
+                    let mut cols = line.split('\t');
+                    (cols.next()?.to_string(), cols.next().map(str::to_string), cols.next().map(str::to_string))
+                } else if matches!(self, Self::Chocolatey) {
+                    let (n, v) = line.split_once('|')?;
+                    (n.to_string(), Some(v.to_string()), None)
+                } else if matches!(self, Self::Apk | Self::Portage) {
+                    let (n, v) = split_name_version(line.trim());
+                    (n, v, None)
+                } else if matches!(self, Self::Winget) {
+                    // Name  Id  Version  Available  Source; the name may contain spaces
+                    let cols: Vec<&str> = line.split("  ").map(str::trim).filter(|c| !c.is_empty()).collect();
+                    (cols.get(1).or(cols.first())?.to_string(), cols.get(2).map(|v| v.to_string()), cols.first().map(|n| n.to_string()))
+                } else {
+                    // "name version" or a bare name
+                    let mut parts = line.split_whitespace();
+                    (parts.next()?.to_string(), parts.next().map(str::to_string), None)
+                };
+                
+                Some(SystemPackageInfo {
+                    name,
+                    version: version.filter(|v| !v.is_empty()),
+                    description: description.filter(|d| !d.is_empty()),
+                    homepage: None,
+                    size: None,
+                    installed: true,
+                    manager: manager.clone(),
+                })
+            })
+            .collect()
+    }
+    
+    /// Build commands that clean the package cache (and orphans with `all`)
+    pub fn clean_cmds(&self, all: bool) -> Result<Vec<Command>> {
+        let sudo = |args: &[&str]| {
+            let mut c = if self.requires_sudo() {
+                let mut c = Command::new("sudo");
+                c.arg(args[0]);
+                c
+            } else {
+                Command::new(args[0])
+            };
+            c.args(&args[1..]);
+            c
+        };
+        
+        let mut cmds = match self {
+            Self::Apt => vec![sudo(&["apt-get", "clean"])],
+            Self::Yum | Self::Dnf => vec![sudo(&[self.command(), "clean", "all"])],
+            Self::Zypper => vec![sudo(&["zypper", "clean", "--all"])],
+            Self::Pacman => vec![sudo(&["pacman", "-Sc", "--noconfirm"])],
+            Self::Brew => vec![sudo(&["brew", "cleanup"])],
+            Self::Chocolatey => vec![sudo(&["choco", "cache", "remove"])],
+            Self::Portage => vec![sudo(&["eclean", "distfiles"])],
+            Self::Apk => vec![sudo(&["apk", "cache", "clean"])],
+            Self::Pkg | Self::PkgNg => vec![sudo(&["pkg", "clean", "-y"])],
+            Self::Winget => return Err(anyhow!("winget does not keep a package cache to clean")),
+        };
+        
+        if all {
+            match self {
+                Self::Apt => cmds.push(sudo(&["apt-get", "autoremove", "-y"])),
+                Self::Yum | Self::Dnf => cmds.push(sudo(&[self.command(), "autoremove", "-y"])),
+                Self::Brew => {
+                    cmds = vec![sudo(&["brew", "cleanup", "--prune=all"]), sudo(&["brew", "autoremove"])];
+                }
+                Self::Portage => cmds.push(sudo(&["emerge", "--depclean"])),
+                Self::Pkg | Self::PkgNg => cmds.push(sudo(&["pkg", "autoremove", "-y"])),
+                _ => {}
+            }
+        }
+        
+        Ok(cmds)
+    }
 }
 
 #[derive(Debug)]
@@ -623,7 +1038,15 @@ pub struct SystemConfig {
 
 impl SystemManager {
     pub async fn new(workspace_root: &Path) -> Result<Self> {
-        let package_manager = SystemPackageManager::detect().await?;
+        Self::with_manager(workspace_root, None).await
+    }
+    
+    /// Use `manager` instead of the detected package manager when given
+    pub async fn with_manager(workspace_root: &Path, manager: Option<&str>) -> Result<Self> {
+        let package_manager = match manager {
+            Some(name) => SystemPackageManager::named(name).await?,
+            None => SystemPackageManager::detect().await?,
+        };
         let config_path = workspace_root.join(".rcm").join("system.json");
         
         Ok(Self {
@@ -749,28 +1172,37 @@ impl SystemManager {
     
     /// Install packages
     pub async fn install(&self, packages: &[String], force: bool, yes: bool) -> Result<()> {
-        let resolved = self.resolve_packages(packages).await?;
-        let mut cmd = self.package_manager.install_cmd(&resolved, force, yes);
-        
-        execute_command(&mut cmd).await
-            .context("Failed to install system packages")
+        let span = telemetry::manager_span("system install", self.package_manager.command());
+        telemetry::traced(span, async {
+            let resolved = self.resolve_packages(packages).await?;
+            let mut cmd = self.package_manager.install_cmd(&resolved, force, yes);
+            
+            execute_mutation(&mut cmd).await
+                .context("Failed to install system packages")
+        }).await
     }
     
     /// Remove packages
     pub async fn remove(&self, packages: &[String], purge: bool, yes: bool) -> Result<()> {
-        let resolved = self.resolve_packages(packages).await?;
-        let mut cmd = self.package_manager.remove_cmd(&resolved, purge, yes);
-        
-        execute_command(&mut cmd).await
-            .context("Failed to remove system packages")
+        let span = telemetry::manager_span("system remove", self.package_manager.command());
+        telemetry::traced(span, async {
+            let resolved = self.resolve_packages(packages).await?;
+            let mut cmd = self.package_manager.remove_cmd(&resolved, purge, yes);
+            
+            execute_mutation(&mut cmd).await
+                .context("Failed to remove system packages")
+        }).await
     }
     
     /// Update packages
     pub async fn update(&self, lists_only: bool, yes: bool) -> Result<()> {
         let mut cmd = self.package_manager.update_cmd(lists_only, yes);
+        let span = telemetry::manager_span("system update", self.package_manager.command());
         
-        execute_command(&mut cmd).await
-            .context("Failed to update system packages")
+        telemetry::traced(span, async {
+            execute_mutation(&mut cmd).await
+                .context("Failed to update system packages")
+        }).await
     }
     
     /// Search packages
@@ -780,54 +1212,577 @@ impl SystemManager {
         execute_command(&mut cmd).await
             .context("Failed to search system packages")
     }
+    
+    /// Show information about a package, including whether it is installed
+    pub async fn info(&self, package: &str) -> Result<SystemPackageInfo> {
+        let resolved = self.resolve_packages(&[package.to_string()]).await?;
+        let name = resolved.first().map(String::as_str).unwrap_or(package);
+        
+        let mut cmd = self.package_manager.info_cmd(name);
+        let result = execute_command(&mut cmd).await
+            .with_context(|| format!("Package '{}' not found", name))?;
+        
+        let mut info = self.package_manager.parse_info(name, &result.stdout)
+            .ok_or_else(|| anyhow!("Could not parse package information for '{}'", name))?;
+        
+        let mut version_cmd = self.package_manager.installed_version_cmd(name);
+        if let Ok(installed) = execute_command(&mut version_cmd).await {
+            info.installed = self.package_manager.parse_installed_version(name, &installed.stdout).is_some();
+        }
+        
+        Ok(info)
+    }
+    
+    /// List installed packages, optionally only manually installed ones
+    pub async fn list(&self, manual: bool, filter: Option<&str>) -> Result<Vec<SystemPackageInfo>> {
+        let mut cmd = self.package_manager.list_cmd(manual);
+        let result = execute_command(&mut cmd).await
+            .context("Failed to list system packages")?;
+        
+        let filter = filter.map(str::to_lowercase);
+        Ok(self.package_manager
+            .parse_list(&result.stdout)
+            .into_iter()
+            .filter(|p| filter.as_ref().map_or(true, |f| p.name.to_lowercase().contains(f.as_str())))
+            .collect())
+    }
+    
+    /// Clean the package cache
+    pub async fn clean(&self, all: bool) -> Result<()> {
+        for mut cmd in self.package_manager.clean_cmds(all)? {
+            execute_mutation(&mut cmd).await
+                .context("Failed to clean system packages")?;
+        }
+        Ok(())
+    }
+}
+
+/// Print packages as a table, JSON or bare names
+fn print_packages(packages: &[SystemPackageInfo], format: &str) -> Result<()> {
+    match format {
+        "table" => {
+            let rows: Vec<SystemPackageRow> = packages
+                .iter()
+                .map(|p| SystemPackageRow {
+                    name: p.name.clone(),
+                    version: p.version.clone().unwrap_or_default(),
+                    description: p.description.clone().unwrap_or_default(),
+                })
+                .collect();
+            println!("{}", Table::new(rows));
+        }
+        "json" => println!("{}", serde_json::to_string_pretty(packages)?),
+        "names" => {
+            for p in packages {
+                println!("{}", p.name);
+            }
+        }
+        _ => return Err(anyhow!("Unsupported format: {}. Use table, json or names", format)),
+    }
+    Ok(())
+}
+
+/// Build system detected in a source tree
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BuildSystem {
+    Autotools,
+    Cmake,
+    Meson,
+    Make,
+}
+
+impl BuildSystem {
+    /// Detect the build system from the files at the root of a source tree
+    pub fn detect(dir: &Path) -> Option<Self> {
+        if dir.join("configure").exists() || dir.join("configure.ac").exists() || dir.join("autogen.sh").exists() {
+            Some(Self::Autotools)
+        } else if dir.join("CMakeLists.txt").exists() {
+            Some(Self::Cmake)
+        } else if dir.join("meson.build").exists() {
+            Some(Self::Meson)
+        } else if ["Makefile", "makefile", "GNUmakefile"].iter().any(|m| dir.join(m).exists()) {
+            Some(Self::Make)
+        } else {
+            None
+        }
+    }
+}
+
+/// A package built from source, recorded so it can be uninstalled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceInstall {
+    pub name: String,
+    pub source: String,
+    pub prefix: String,
+    pub build_system: BuildSystem,
+    /// Absolute paths of every installed file
+    pub files: Vec<PathBuf>,
+    pub installed_at: String,
+}
+
+/// Downloads, builds and installs packages from source
+pub struct SourceBuilder {
+    records_path: PathBuf,
+    build_root: PathBuf,
+    prefix: String,
+    jobs: usize,
+    configure_opts: Vec<String>,
+    numa_node: Option<u32>,
+    expect: Expectations,
+}
+
+impl SourceBuilder {
+    pub fn new(workspace_root: &Path, build_dir: Option<&str>, prefix: &str, jobs: Option<usize>, configure_opts: Vec<String>) -> Self {
+        let jobs = jobs.unwrap_or_else(|| {
+            std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+        });
+        
+        Self {
+            records_path: workspace_root.join(".rcm").join("source-installs.json"),
+            build_root: build_dir
+                .map(PathBuf::from)
+                .unwrap_or_else(|| workspace_root.join(".rcm").join("build")),
+            prefix: prefix.to_string(),
+            jobs,
+            configure_opts,
+            numa_node: None,
+            expect: Expectations::default(),
+        }
+    }
+    
+    /// Check downloaded archives against a checksum and/or detached signature
+    pub fn with_verification(mut self, sha256: Option<String>, signature: Option<String>) -> Self {
+        self.expect = Expectations { sha256, signature };
+        self
+    }
+    
+    /// Run build steps bound to a NUMA node; without explicit jobs, use the node's CPU count
+    pub fn with_numa_node(self, node: Option<u32>, jobs: Option<usize>) -> Result<Self> {
+        let Some(node) = node else {
+            return Ok(self);
+        };
+        #[cfg(feature = "arm")]
+        {
+            let topology = arm_lib::numa::NumaTopology::detect()?;
+            let cpus = topology.node(node)?.cpus.len();
+            let mut builder = self;
+            builder.jobs = jobs.unwrap_or(cpus);
+            builder.numa_node = Some(node);
+            Ok(builder)
+        }
+        #[cfg(not(feature = "arm"))]
+        {
+            let _ = jobs;
+            Err(anyhow!("--numa-node {} needs RCM built with the arm feature", node))
+        }
+    }
+    
+    /// Apply the NUMA binding, if any, to a build step
+    fn bind(&self, cmd: Command) -> Result<Command> {
+        match self.numa_node {
+            #[cfg(feature = "arm")]
+            Some(node) => {
+                let topology = arm_lib::numa::NumaTopology::detect()?;
+                arm_lib::numa::bind_command(&cmd, topology.node(node)?)
+            }
+            _ => Ok(cmd),
+        }
+    }
+    
+    /// Name recorded for a source, e.g. `https://x.org/zlib-1.3.1.tar.gz` -> `zlib-1.3.1`
+    pub fn source_name(source: &str) -> String {
+        let base = source
+            .split(['#', '?'])
+            .next()
+            .unwrap_or(source)
+            .trim_end_matches('/')
+            .rsplit(['/', ':'])
+            .next()
+            .unwrap_or(source);
+        
+        [".tar.gz", ".tar.xz", ".tar.bz2", ".tgz", ".zip", ".git"]
+            .iter()
+            .find_map(|ext| base.strip_suffix(ext))
+            .unwrap_or(base)
+            .to_string()
+    }
+    
+    fn is_git(source: &str) -> bool {
+        source.starts_with("git@") || source.starts_with("git://") || source.split('#').next().is_some_and(|s| s.ends_with(".git"))
+    }
+    
+    /// Load recorded source installs
+    pub async fn load_records(&self) -> Result<HashMap<String, SourceInstall>> {
+        if !self.records_path.exists() {
+            return Ok(HashMap::new());
+        }
+        let content = fs::read_to_string(&self.records_path).await
+            .context("Failed to read source install records")?;
+        serde_json::from_str(&content)
+            .context("Failed to parse source install records")
+    }
+    
+    async fn save_records(&self, records: &HashMap<String, SourceInstall>) -> Result<()> {
+        if let Some(parent) = self.records_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        util::write_file(&self.records_path, serde_json::to_string_pretty(records)?).await
+            .context("Failed to save source install records")
+    }
+    
+    /// Whether a source install with this name is recorded (used by LET conditions)
+    pub async fn is_installed(&self, name: &str) -> bool {
+        self.load_records().await.map(|r| r.contains_key(name)).unwrap_or(false)
+    }
+    
+    /// Download or clone the source and return the directory holding the build files
+    async fn fetch(&self, source: &str, name: &str) -> Result<PathBuf> {
+        let local = Path::new(source);
+        if local.is_dir() {
+            return Ok(local.to_path_buf());
+        }
+        
+        fs::create_dir_all(&self.build_root).await
+            .context("Failed to create build directory")?;
+        let src_dir = self.build_root.join(name);
+        if src_dir.exists() {
+            util::remove_dir_all(&src_dir).await?;
+        }
+        
+        if Self::is_git(source) {
+            // repo.git#ref checks out a branch or tag
+            let (url, git_ref) = match source.split_once('#') {
+                Some((url, r)) => (url, Some(r)),
+                None => (source, None),
+            };
+            
+            println!("📥 Cloning {}", url);
+            let mut cmd = Command::new("git");
+            cmd.args(["clone", "--depth", "1", "--recurse-submodules"]);
+            if let Some(r) = git_ref {
+                cmd.args(["--branch", r]);
+            }
+            cmd.arg(url).arg(&src_dir);
+            execute_command(&mut cmd).await
+                .context("Failed to clone source repository")?;
+            return Ok(src_dir);
+        }
+        
+        if !util::is_valid_url(source) {
+            return Err(anyhow!("Source must be a URL, git repository or existing directory: {}", source));
+        }
+        
+        let file_name = source.rsplit('/').next().unwrap_or("source.tar.gz");
+        let archive = self.build_root.join(file_name);
+        println!("📥 Downloading {}", source);
+        artifacts::download(source, &archive, &self.expect).await?;
+        
+        if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") || file_name.ends_with(".zip") {
+            util::extract_archive(&archive, &src_dir).await?;
+        } else {
+            // xz/bz2 tarballs go through the system tar
+            fs::create_dir_all(&src_dir).await?;
+            let mut cmd = Command::new("tar");
+            cmd.arg("-xf").arg(&archive).arg("-C").arg(&src_dir);
+            execute_command(&mut cmd).await
+                .context("Failed to extract source archive")?;
+        }
+        fs::remove_file(&archive).await.ok();
+        
+        // Tarballs usually wrap everything in a single top-level directory
+        let mut entries = std::fs::read_dir(&src_dir)?.filter_map(|e| e.ok()).collect::<Vec<_>>();
+        if entries.len() == 1 && entries[0].path().is_dir() {
+            return Ok(entries.remove(0).path());
+        }
+        Ok(src_dir)
+    }
+    
+    async fn run_step(&self, label: &str, dir: &Path, program: &str, args: &[String], env: &[(&str, &Path)]) -> Result<()> {
+        println!("🔧 {}: {} {}", label, program, args.join(" "));
+        let mut cmd = Command::new(program);
+        cmd.current_dir(dir).args(args);
+        for (key, value) in env {
+            cmd.env(key, value);
+        }
+        let mut cmd = self.bind(cmd)?;
+        execute_mutation(&mut cmd).await
+            .with_context(|| format!("{} step failed", label))?;
+        Ok(())
+    }
+    
+    /// Configure, build and install into `staging` (used as DESTDIR)
+    async fn build(&self, src: &Path, system: BuildSystem, staging: &Path) -> Result<()> {
+        let jobs = format!("-j{}", self.jobs);
+        let prefix = self.prefix.clone();
+        let opts = self.configure_opts.clone();
+        let destdir = [("DESTDIR", staging)];
+        
+        match system {
+            BuildSystem::Autotools => {
+                if !src.join("configure").exists() {
+                    if src.join("autogen.sh").exists() {
+                        self.run_step("bootstrap", src, "sh", &["./autogen.sh".to_string()], &[]).await?;
+                    } else {
+                        self.run_step("bootstrap", src, "autoreconf", &["-fi".to_string()], &[]).await?;
+                    }
+                }
+                let mut configure = vec![format!("--prefix={}", prefix)];
+                configure.extend(opts);
+                self.run_step("configure", src, "./configure", &configure, &[]).await?;
+                self.run_step("build", src, "make", &[jobs], &[]).await?;
+                self.run_step("install", src, "make", &["install".to_string()], &destdir).await?;
+            }
+            BuildSystem::Cmake => {
+                let mut configure = vec![
+                    "-S".to_string(), ".".to_string(),
+                    "-B".to_string(), "build".to_string(),
+                    format!("-DCMAKE_INSTALL_PREFIX={}", prefix),
+                    "-DCMAKE_BUILD_TYPE=Release".to_string(),
+                ];
+                configure.extend(opts);
+                self.run_step("configure", src, "cmake", &configure, &[]).await?;
+                self.run_step("build", src, "cmake", &["--build".to_string(), "build".to_string(), jobs], &[]).await?;
+                self.run_step("install", src, "cmake", &["--install".to_string(), "build".to_string()], &destdir).await?;
+            }
+            BuildSystem::Meson => {
+                let mut configure = vec!["setup".to_string(), "build".to_string(), format!("--prefix={}", prefix)];
+                configure.extend(opts);
+                self.run_step("configure", src, "meson", &configure, &[]).await?;
+                self.run_step("build", src, "meson", &["compile".to_string(), "-C".to_string(), "build".to_string(), jobs], &[]).await?;
+                self.run_step("install", src, "meson", &["install".to_string(), "-C".to_string(), "build".to_string()], &destdir).await?;
+            }
+            BuildSystem::Make => {
+                let prefix_arg = format!("PREFIX={}", prefix);
+                let mut build = vec![jobs, prefix_arg.clone()];
+                build.extend(opts);
+                self.run_step("build", src, "make", &build, &[]).await?;
+                self.run_step("install", src, "make", &["install".to_string(), prefix_arg], &destdir).await?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Whether writing under `path` needs elevated privileges
+    pub fn needs_sudo(path: &Path) -> bool {
+        let existing = path.ancestors().find(|p| p.exists()).unwrap_or(Path::new("/"));
+        let probe = existing.join(format!(".rcm-write-test-{}", std::process::id()));
+        match std::fs::File::create(&probe) {
+            Ok(_) => {
+                std::fs::remove_file(&probe).ok();
+                false
+            }
+            Err(_) => true,
+        }
+    }
+    
+    /// Build and install a source package, recording its files
+    pub async fn install(&self, source: &str, name: Option<&str>) -> Result<SourceInstall> {
+        if cfg!(windows) {
+            return Err(anyhow!("Source builds are only supported on Unix-like systems"));
+        }
+        
+        let name = name.map(str::to_string).unwrap_or_else(|| Self::source_name(source));
+        let src = self.fetch(source, &name).await?;
+        
+        let system = BuildSystem::detect(&src)
+            .ok_or_else(|| anyhow!("Could not detect a build system in {}", src.display()))?;
+        println!("🏗️ Building {} with {:?} ({} jobs, prefix {})", name, system, self.jobs, self.prefix);
+        
+        let staging = self.build_root.join(format!("{}-staging", name));
+        if staging.exists() {
+            util::remove_dir_all(&staging).await?;
+        }
+        fs::create_dir_all(&staging).await?;
+        let staging = staging.canonicalize()?;
+        
+        self.build(&src, system, &staging).await?;
+        
+        let files: Vec<PathBuf> = WalkDir::new(&staging)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| !e.file_type().is_dir())
+            .filter_map(|e| e.path().strip_prefix(&staging).ok().map(|p| Path::new("/").join(p)))
+            .collect();
+        
+        if files.is_empty() {
+            return Err(anyhow!("Install step produced no files"));
+        }
+        
+        // Copy the staged tree into place
+        let mut cmd = if Self::needs_sudo(Path::new(&self.prefix)) {
+            let mut c = Command::new("sudo");
+            c.arg("cp");
+            c
+        } else {
+            Command::new("cp")
+        };
+        cmd.arg("-a").arg(format!("{}/.", staging.display())).arg("/");
+        execute_command(&mut cmd).await
+            .context("Failed to copy built files into place")?;
+        
+        util::remove_dir_all(&staging).await.ok();
+        
+        let record = SourceInstall {
+            name: name.clone(),
+            source: source.to_string(),
+            prefix: self.prefix.clone(),
+            build_system: system,
+            files,
+            installed_at: chrono::Utc::now().to_rfc3339(),
+        };
+        
+        let mut records = self.load_records().await?;
+        records.insert(name.clone(), record.clone());
+        self.save_records(&records).await?;
+        
+        println!("✅ Installed {} from source ({} files)", name, record.files.len());
+        Ok(record)
+    }
+    
+    /// Remove the files recorded for a source install
+    pub async fn uninstall(&self, name: &str) -> Result<()> {
+        let mut records = self.load_records().await?;
+        let record = records.remove(name)
+            .ok_or_else(|| anyhow!("No source install recorded for '{}'", name))?;
+        
+        let existing: Vec<&PathBuf> = record.files.iter().filter(|f| f.exists() || f.is_symlink()).collect();
+        if !existing.is_empty() {
+            let mut cmd = if Self::needs_sudo(Path::new(&record.prefix)) {
+                let mut c = Command::new("sudo");
+                c.arg("rm");
+                c
+            } else {
+                Command::new("rm")
+            };
+            cmd.arg("-f").args(&existing);
+            execute_mutation(&mut cmd).await
+                .context("Failed to remove installed files")?;
+        }
+        
+        self.save_records(&records).await?;
+        println!("🗑️ Removed {} ({} files)", name, existing.len());
+        Ok(())
+    }
 }
 
 /// Handle system commands
 pub async fn handle_command(workspace: &Workspace, cmd: SystemCommands) -> Result<()> {
     match cmd {
         SystemCommands::Install { packages, force, yes, manager } => {
-            let system = SystemManager::new(workspace.root()).await?;
+            let system = SystemManager::with_manager(workspace.root(), manager.as_deref()).await?;
             system.install(&packages, force, yes).await
         }
         
         SystemCommands::Remove { packages, purge, yes, manager } => {
-            let system = SystemManager::new(workspace.root()).await?;
+            let system = SystemManager::with_manager(workspace.root(), manager.as_deref()).await?;
             system.remove(&packages, purge, yes).await
         }
         
         SystemCommands::Update { lists_only, yes, manager } => {
-            let system = SystemManager::new(workspace.root()).await?;
+            let system = SystemManager::with_manager(workspace.root(), manager.as_deref()).await?;
             system.update(lists_only, yes).await
         }
         
-        SystemCommands::Search { terms, details: _, manager: _ } => {
-            let system = SystemManager::new(workspace.root()).await?;
+        SystemCommands::Search { terms, details: _, manager } => {
+            let system = SystemManager::with_manager(workspace.root(), manager.as_deref()).await?;
             system.search(&terms).await
         }
         
-        SystemCommands::Info { package: _, manager: _ } => {
-            println!("System package info not yet implemented");
+        SystemCommands::Info { package, manager } => {
+            let system = SystemManager::with_manager(workspace.root(), manager.as_deref()).await?;
+            let info = system.info(&package).await?;
+            
+            println!("📦 {} {}", info.name, info.version.as_deref().unwrap_or(""));
+            if let Some(description) = &info.description {
+                println!("   {}", description);
+            }
+            if let Some(homepage) = &info.homepage {
+                println!("   Homepage:  {}", homepage);
+            }
+            if let Some(size) = &info.size {
+                println!("   Size:      {}", size);
+            }
+            println!("   Installed: {}", if info.installed { "yes" } else { "no" });
+            println!("   Manager:   {}", info.manager);
             Ok(())
         }
         
-        SystemCommands::List { manual: _, format: _, filter: _ } => {
-            println!("System package list not yet implemented");
-            Ok(())
+        SystemCommands::List { manual, format, filter, manager } => {
+            let system = SystemManager::with_manager(workspace.root(), manager.as_deref()).await?;
+            let packages = system.list(manual, filter.as_deref()).await?;
+            print_packages(&packages, &format)
         }
         
-        SystemCommands::Clean { all: _, manager: _ } => {
-            println!("System package clean not yet implemented");
+        SystemCommands::Clean { all, manager } => {
+            let system = SystemManager::with_manager(workspace.root(), manager.as_deref()).await?;
+            system.clean(all).await?;
+            println!("🧹 System package cache cleaned");
             Ok(())
         }
         
-        SystemCommands::Repo { cmd: _ } => {
-            println!("System repository management not yet implemented");
-            Ok(())
+        SystemCommands::Repo { cmd } => {
+            let mut repos = RepoManager::new().await?;
+            match cmd {
+                RepoCommands::Add { repo, key, name, suite, components, yes, insecure } => {
+                    repos.add(&repo, &RepoOptions { name, key, suite, components, yes, insecure }).await
+                }
+                RepoCommands::Remove { repo, yes } => repos.remove(&repo, yes).await,
+                RepoCommands::List => {
+                    for entry in repos.list().await? {
+                        let marker = if entry.managed { style("rcm").green() } else { style("   ").dim() };
+                        let name = if entry.enabled { style(entry.name).bold() } else { style(entry.name).dim() };
+                        println!("  {} {} {} ({})", marker, name, entry.url, entry.source.display());
+                    }
+                    Ok(())
+                }
+                RepoCommands::Update => repos.update().await,
+            }
         }
         
-        SystemCommands::Source { source: _, build_dir: _, prefix: _, jobs: _, configure_opts: _ } => {
-            println!("Source installation not yet implemented");
-            Ok(())
+        SystemCommands::Source { source, name, uninstall, build_dir, prefix, jobs, configure_opts, numa_node, sha256, signature } => {
+            let builder = SourceBuilder::new(workspace.root(), build_dir.as_deref(), &prefix, jobs, configure_opts)
+                .with_numa_node(numa_node, jobs)?
+                .with_verification(sha256, signature);
+            if util::is_dry_run() && !uninstall {
+                // The build system is only known after fetching, so describe the pipeline instead
+                println!("[dry-run] fetch {}, build it, and install into {} (recorded in .rcm/source-installs.json)", source, prefix);
+                return Ok(());
+            }
+            if uninstall {
+                builder.uninstall(&source).await
+            } else {
+                builder.install(&source, name.as_deref()).await.map(|_| ())
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rpm_info() {
+        let output = "Name        : curl\nVersion     : 8.2.1\nRelease     : 4.fc39\nSize        : 1.2 M\nURL         : https://curl.se/\nSummary     : A utility for getting files from remote servers\n";
+        let info = SystemPackageManager::Dnf.parse_info("curl", output).unwrap();
+        assert_eq!(info.version.as_deref(), Some("8.2.1-4.fc39"));
+        assert_eq!(info.homepage.as_deref(), Some("https://curl.se/"));
+        assert_eq!(info.description.as_deref(), Some("A utility for getting files from remote servers"));
+    }
+
+    #[test]
+    fn test_parse_list_formats() {
+        let dpkg = SystemPackageManager::Apt.parse_list("curl\t8.5.0-2\tcommand line tool\ngit\t1:2.43.0-1\t\n");
+        assert_eq!(dpkg.len(), 2);
+        assert_eq!(dpkg[1].version.as_deref(), Some("1:2.43.0-1"));
+        assert_eq!(dpkg[1].description, None);
+
+        let apk = SystemPackageManager::Apk.parse_list("musl-utils-1.2.4-r2\n");
+        assert_eq!(apk[0].name, "musl-utils");
+        assert_eq!(apk[0].version.as_deref(), Some("1.2.4-r2"));
+
+        let choco = SystemPackageManager::Chocolatey.parse_list("git|2.43.0\n");
+        assert_eq!(choco[0].version.as_deref(), Some("2.43.0"));
+    }
+}
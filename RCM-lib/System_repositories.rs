@@ -0,0 +1,606 @@
+//! System repository management for RCM
+//!
+//! Adds and removes third-party package repositories for apt, dnf/yum, pacman
+//! and Homebrew taps. Repository files written by RCM are prefixed with `rcm-`
+//! (or wrapped in marker comments in pacman.conf) and restored on failure.
+//! A repository whose packages would go unverified (no key for dnf/yum or
+//! pacman, or a `.repo` file with `gpgcheck=0`) needs `--insecure`.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use dialoguer::Confirm;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tokio::fs;
+use crate::artifacts::{self, Expectations};
+use crate::system::{SourceBuilder, SystemPackageManager};
+use crate::util::{self, execute_command, execute_mutation};
+
+const APT_SOURCES_DIR: &str = "/etc/apt/sources.list.d";
+const APT_KEYRINGS_DIR: &str = "/etc/apt/keyrings";
+const YUM_REPOS_DIR: &str = "/etc/yum.repos.d";
+const PACMAN_CONF: &str = "/etc/pacman.conf";
+
+/// Options for adding a repository
+#[derive(Debug, Clone, Default)]
+pub struct RepoOptions {
+    /// Identifier used for file names and sections (derived from the URL if unset)
+    pub name: Option<String>,
+    /// Signing key: URL, file path, or key ID (pacman)
+    pub key: Option<String>,
+    /// apt suite (defaults to the release codename)
+    pub suite: Option<String>,
+    /// apt components
+    pub components: Vec<String>,
+    /// Skip confirmation prompts
+    pub yes: bool,
+    /// Accept a repository whose packages aren't signature-checked
+    pub insecure: bool,
+}
+
+/// A configured repository
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepoEntry {
+    pub name: String,
+    pub url: String,
+    pub enabled: bool,
+    /// Whether RCM wrote this entry
+    pub managed: bool,
+    pub source: PathBuf,
+}
+
+/// Previous state of a file, for rollback
+struct Backup {
+    path: PathBuf,
+    previous: Option<Vec<u8>>,
+}
+
+/// Repository manager for the detected system package manager
+pub struct RepoManager {
+    manager: SystemPackageManager,
+    backups: Vec<Backup>,
+}
+
+impl RepoManager {
+    pub async fn new() -> Result<Self> {
+        Ok(Self { manager: SystemPackageManager::detect().await?, backups: Vec::new() })
+    }
+
+    /// Add a repository, refreshing metadata and rolling back on failure
+    pub async fn add(&mut self, repo: &str, opts: &RepoOptions) -> Result<()> {
+        let name = opts.name.clone().unwrap_or_else(|| repo_name(repo));
+
+        let result = match self.manager {
+            SystemPackageManager::Apt => self.add_apt(repo, &name, opts).await,
+            SystemPackageManager::Dnf | SystemPackageManager::Yum => self.add_yum(repo, &name, opts).await,
+            SystemPackageManager::Pacman => self.add_pacman(repo, &name, opts).await,
+            SystemPackageManager::Brew => return self.add_tap(repo, opts).await,
+            _ => return Err(anyhow!("Repository management is not supported for {}", self.manager.command())),
+        };
+
+        match result {
+            Ok(()) => {
+                self.backups.clear();
+                println!("{}", style(format!("✅ Added repository {}", name)).green());
+                Ok(())
+            }
+            Err(e) => {
+                self.rollback().await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Remove a repository added by RCM (or a Homebrew tap)
+    pub async fn remove(&mut self, repo: &str, yes: bool) -> Result<()> {
+        let name = repo_name(repo);
+
+        if matches!(self.manager, SystemPackageManager::Brew) {
+            confirm(&format!("Untap {}?", repo), yes)?;
+            return run(Command::new("brew").args(["untap", repo])).await;
+        }
+
+        let result = match self.manager {
+            SystemPackageManager::Apt => {
+                let list = Path::new(APT_SOURCES_DIR).join(format!("rcm-{}.list", name));
+                let key = Path::new(APT_KEYRINGS_DIR).join(format!("rcm-{}.gpg", name));
+                self.remove_files(&[list, key], yes).await
+            }
+            SystemPackageManager::Dnf | SystemPackageManager::Yum => {
+                let file = Path::new(YUM_REPOS_DIR).join(format!("rcm-{}.repo", name));
+                self.remove_files(&[file], yes).await
+            }
+            SystemPackageManager::Pacman => {
+                let conf = fs::read_to_string(PACMAN_CONF).await.context("Failed to read pacman.conf")?;
+                let updated = remove_pacman_block(&conf, &name)
+                    .ok_or_else(|| anyhow!("No RCM-managed repository '{}' in {}", name, PACMAN_CONF))?;
+                confirm(&format!("Remove [{}] from {}?", name, PACMAN_CONF), yes)?;
+                self.write_root_file(Path::new(PACMAN_CONF), updated.as_bytes(), 0o644).await
+            }
+            _ => return Err(anyhow!("Repository management is not supported for {}", self.manager.command())),
+        };
+
+        match result {
+            Ok(()) => match self.update().await {
+                Ok(()) => {
+                    self.backups.clear();
+                    println!("{}", style(format!("🗑️ Removed repository {}", name)).green());
+                    Ok(())
+                }
+                Err(e) => {
+                    self.rollback().await;
+                    Err(e)
+                }
+            },
+            Err(e) => {
+                self.rollback().await;
+                Err(e)
+            }
+        }
+    }
+
+    /// List configured repositories
+    pub async fn list(&self) -> Result<Vec<RepoEntry>> {
+        match self.manager {
+            SystemPackageManager::Apt => {
+                let mut entries = Vec::new();
+                let mut files = vec![PathBuf::from("/etc/apt/sources.list")];
+                if let Ok(mut dir) = fs::read_dir(APT_SOURCES_DIR).await {
+                    while let Some(entry) = dir.next_entry().await? {
+                        if entry.path().extension().is_some_and(|e| e == "list") {
+                            files.push(entry.path());
+                        }
+                    }
+                }
+                for file in files {
+                    if let Ok(content) = fs::read_to_string(&file).await {
+                        entries.extend(parse_apt_sources(&content, &file));
+                    }
+                }
+                Ok(entries)
+            }
+            SystemPackageManager::Dnf | SystemPackageManager::Yum => {
+                let mut entries = Vec::new();
+                let mut dir = fs::read_dir(YUM_REPOS_DIR).await
+                    .with_context(|| format!("Failed to read {}", YUM_REPOS_DIR))?;
+                while let Some(entry) = dir.next_entry().await? {
+                    if entry.path().extension().is_some_and(|e| e == "repo") {
+                        let content = fs::read_to_string(entry.path()).await?;
+                        entries.extend(parse_ini_repos(&content, &entry.path()));
+                    }
+                }
+                Ok(entries)
+            }
+            SystemPackageManager::Pacman => {
+                let content = fs::read_to_string(PACMAN_CONF).await.context("Failed to read pacman.conf")?;
+                Ok(parse_ini_repos(&content, Path::new(PACMAN_CONF)))
+            }
+            SystemPackageManager::Brew => {
+                let result = execute_command(Command::new("brew").arg("tap")).await?;
+                Ok(result.stdout.lines().map(|tap| RepoEntry {
+                    name: tap.trim().to_string(),
+                    url: format!("https://github.com/{}", tap.trim()),
+                    enabled: true,
+                    managed: false,
+                    source: PathBuf::from("brew tap"),
+                }).collect())
+            }
+            _ => Err(anyhow!("Repository management is not supported for {}", self.manager.command())),
+        }
+    }
+
+    /// Refresh repository metadata
+    pub async fn update(&self) -> Result<()> {
+        let mut cmd = match self.manager {
+            SystemPackageManager::Apt => sudo(&["apt-get", "update"]),
+            SystemPackageManager::Dnf => sudo(&["dnf", "makecache", "--refresh"]),
+            SystemPackageManager::Yum => sudo(&["yum", "makecache"]),
+            SystemPackageManager::Pacman => sudo(&["pacman", "-Sy"]),
+            SystemPackageManager::Brew => {
+                let mut cmd = Command::new("brew");
+                cmd.arg("update");
+                cmd
+            }
+            _ => return Err(anyhow!("Repository management is not supported for {}", self.manager.command())),
+        };
+        run(&mut cmd).await.context("Failed to refresh repository metadata")
+    }
+
+    async fn add_apt(&mut self, repo: &str, name: &str, opts: &RepoOptions) -> Result<()> {
+        let keyring = Path::new(APT_KEYRINGS_DIR).join(format!("rcm-{}.gpg", name));
+        let list = Path::new(APT_SOURCES_DIR).join(format!("rcm-{}.list", name));
+
+        let line = if repo.starts_with("deb ") || repo.starts_with("deb-src ") {
+            repo.to_string()
+        } else {
+            let suite = match &opts.suite {
+                Some(suite) => suite.clone(),
+                None => release_codename().await?,
+            };
+            let components = if opts.components.is_empty() { vec!["main".to_string()] } else { opts.components.clone() };
+            apt_source_line(repo, &suite, &components, opts.key.as_ref().map(|_| keyring.as_path()))
+        };
+
+        println!("{}", style(format!("📄 {}", list.display())).cyan());
+        println!("   {}", line);
+        confirm(&format!("Add this repository{}?", if opts.key.is_some() { " and import its key" } else { "" }), opts.yes)?;
+
+        if let Some(key) = &opts.key {
+            let data = read_key(key).await?;
+            // Keys published in ASCII armor must be dearmored for signed-by
+            let data = if data.starts_with(b"-----BEGIN") { dearmor(&data).await? } else { data };
+            self.write_root_file(&keyring, &data, 0o644).await?;
+        }
+
+        self.write_root_file(&list, format!("{}\n", line).as_bytes(), 0o644).await?;
+        self.update().await
+    }
+
+    async fn add_yum(&mut self, repo: &str, name: &str, opts: &RepoOptions) -> Result<()> {
+        let file = Path::new(YUM_REPOS_DIR).join(format!("rcm-{}.repo", name));
+
+        // A URL to a published .repo file is used as-is, otherwise one is generated
+        let content = if repo.ends_with(".repo") {
+            let data = artifacts::download_bytes(repo, &Expectations::default()).await
+                .with_context(|| format!("Failed to download {}", repo))?;
+            String::from_utf8(data).with_context(|| format!("{} is not UTF-8", repo))?
+        } else {
+            yum_repo_file(name, repo, opts.key.as_deref())
+        };
+        if disables_gpgcheck(&content) && !opts.insecure {
+            return Err(anyhow!("{} would install packages without signature checks; pass --key, or --insecure to accept that", file.display()));
+        }
+
+        println!("{}", style(format!("📄 {}", file.display())).cyan());
+        for line in content.lines() {
+            println!("   {}", line);
+        }
+        confirm("Add this repository?", opts.yes)?;
+
+        if let Some(key) = &opts.key {
+            run(&mut sudo(&["rpm", "--import", key])).await
+                .with_context(|| format!("Failed to import key {}", key))?;
+        }
+
+        self.write_root_file(&file, content.as_bytes(), 0o644).await?;
+        self.update().await
+    }
+
+    async fn add_pacman(&mut self, repo: &str, name: &str, opts: &RepoOptions) -> Result<()> {
+        if opts.key.is_none() && !opts.insecure {
+            return Err(anyhow!("Without --key, [{}] would trust every package unsigned; pass --key, or --insecure to accept that", name));
+        }
+        let conf = fs::read_to_string(PACMAN_CONF).await.context("Failed to read pacman.conf")?;
+        if parse_ini_repos(&conf, Path::new(PACMAN_CONF)).iter().any(|r| r.name == name) {
+            return Err(anyhow!("Repository [{}] already exists in {}", name, PACMAN_CONF));
+        }
+
+        let block = pacman_block(name, repo, opts.key.is_some());
+        println!("{}", style(format!("📄 {}", PACMAN_CONF)).cyan());
+        for line in block.lines() {
+            println!("   {}", line);
+        }
+        confirm("Append this repository?", opts.yes)?;
+
+        if let Some(key) = &opts.key {
+            if key.contains('/') || key.starts_with("http") {
+                let data = read_key(key).await?;
+                // Randomly named and removed on drop, so nothing can swap it before sudo reads it
+                let tmp = temp_file("key")?;
+                fs::write(tmp.path(), &data).await?;
+                run(&mut sudo(&["pacman-key", "--add", &tmp.path().to_string_lossy()])).await?;
+            } else {
+                run(&mut sudo(&["pacman-key", "--recv-keys", key])).await?;
+                run(&mut sudo(&["pacman-key", "--lsign-key", key])).await?;
+            }
+        }
+
+        let updated = format!("{}\n{}", conf.trim_end(), block);
+        self.write_root_file(Path::new(PACMAN_CONF), updated.as_bytes(), 0o644).await?;
+        self.update().await
+    }
+
+    async fn add_tap(&self, repo: &str, opts: &RepoOptions) -> Result<()> {
+        // `brew tap user/repo [URL]`; a bare URL needs an explicit tap name
+        let (tap, url) = if util::is_valid_url(repo) {
+            let tap = opts.name.clone().ok_or_else(|| anyhow!("Use --name user/repo to tap a URL"))?;
+            (tap, Some(repo))
+        } else {
+            (repo.to_string(), None)
+        };
+
+        confirm(&format!("Tap {}?", tap), opts.yes)?;
+        let mut cmd = Command::new("brew");
+        cmd.arg("tap").arg(&tap);
+        if let Some(url) = url {
+            cmd.arg(url);
+        }
+        run(&mut cmd).await?;
+        println!("{}", style(format!("✅ Tapped {}", tap)).green());
+        Ok(())
+    }
+
+    async fn remove_files(&mut self, paths: &[PathBuf], yes: bool) -> Result<()> {
+        let existing: Vec<&PathBuf> = paths.iter().filter(|p| p.exists()).collect();
+        if existing.is_empty() {
+            return Err(anyhow!("No RCM-managed repository files found ({})", paths[0].display()));
+        }
+
+        for path in &existing {
+            println!("   {}", path.display());
+        }
+        confirm("Delete these files?", yes)?;
+
+        for path in existing {
+            self.backups.push(Backup { path: path.clone(), previous: fs::read(path).await.ok() });
+            run(&mut privileged(path, &["rm", "-f", &path.to_string_lossy()])).await?;
+        }
+        Ok(())
+    }
+
+    /// Write a file under /etc, escalating with sudo when needed, and remember its previous contents
+    async fn write_root_file(&mut self, path: &Path, content: &[u8], mode: u32) -> Result<()> {
+        self.backups.push(Backup { path: path.to_path_buf(), previous: fs::read(path).await.ok() });
+        install_file(path, content, mode).await
+    }
+
+    /// Restore every file touched by the failed operation
+    async fn rollback(&mut self) {
+        for backup in self.backups.drain(..).rev() {
+            let restored = match &backup.previous {
+                Some(content) => install_file(&backup.path, content, 0o644).await,
+                None => run(&mut privileged(&backup.path, &["rm", "-f", &backup.path.to_string_lossy()])).await,
+            };
+            match restored {
+                Ok(()) => println!("{}", style(format!("↩️ Restored {}", backup.path.display())).yellow()),
+                Err(e) => tracing::error!("Failed to restore {}: {}", backup.path.display(), e),
+            }
+        }
+    }
+}
+
+/// Derive a file-safe repository name from a URL or identifier
+pub fn repo_name(repo: &str) -> String {
+    let trimmed = repo
+        .trim_start_matches("deb ")
+        .split_whitespace()
+        .find(|part| part.contains("://"))
+        .unwrap_or(repo);
+    let host_path = trimmed.split("://").nth(1).unwrap_or(trimmed);
+    let name: String = host_path
+        .trim_end_matches(".repo")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    name.split('-').filter(|s| !s.is_empty()).collect::<Vec<_>>().join("-")
+}
+
+/// One-line apt source, pinned to its keyring when one is given
+pub fn apt_source_line(url: &str, suite: &str, components: &[String], keyring: Option<&Path>) -> String {
+    match keyring {
+        Some(keyring) => format!("deb [signed-by={}] {} {} {}", keyring.display(), url, suite, components.join(" ")),
+        None => format!("deb {} {} {}", url, suite, components.join(" ")),
+    }
+}
+
+/// Generated dnf/yum .repo file
+pub fn yum_repo_file(name: &str, baseurl: &str, key: Option<&str>) -> String {
+    let mut content = format!("[{}]\nname={}\nbaseurl={}\nenabled=1\n", name, name, baseurl);
+    match key {
+        Some(key) => content.push_str(&format!("gpgcheck=1\ngpgkey={}\n", key)),
+        None => content.push_str("gpgcheck=0\n"),
+    }
+    content
+}
+
+/// Whether a .repo file turns signature checks off for any section
+fn disables_gpgcheck(content: &str) -> bool {
+    content.lines()
+        .filter_map(|line| line.split_once('='))
+        .any(|(key, value)| key.trim().eq_ignore_ascii_case("gpgcheck") && value.trim() == "0")
+}
+
+/// pacman.conf section wrapped in RCM markers
+pub fn pacman_block(name: &str, server: &str, signed: bool) -> String {
+    format!(
+        "\n# BEGIN rcm {name}\n[{name}]\nSigLevel = {}\nServer = {server}\n# END rcm {name}\n",
+        if signed { "Required DatabaseOptional" } else { "Optional TrustAll" },
+    )
+}
+
+/// Remove an RCM-managed block from pacman.conf, or `None` if it is not there
+pub fn remove_pacman_block(conf: &str, name: &str) -> Option<String> {
+    let begin = format!("# BEGIN rcm {}", name);
+    let end = format!("# END rcm {}", name);
+    let start = conf.find(&begin)?;
+    let stop = conf[start..].find(&end)? + start + end.len();
+    Some(format!("{}{}", conf[..start].trim_end(), &conf[stop..]).trim_end().to_string() + "\n")
+}
+
+/// Parse one-line apt sources
+fn parse_apt_sources(content: &str, source: &Path) -> Vec<RepoEntry> {
+    let managed = source.file_name().is_some_and(|n| n.to_string_lossy().starts_with("rcm-"));
+    content
+        .lines()
+        .map(str::trim)
+        .filter_map(|line| {
+            let (enabled, line) = match line.strip_prefix('#') {
+                Some(rest) => (false, rest.trim()),
+                None => (true, line),
+            };
+            if !(line.starts_with("deb ") || line.starts_with("deb-src ")) {
+                return None;
+            }
+            let url = line.split_whitespace().find(|p| p.contains("://"))?;
+            Some(RepoEntry {
+                name: repo_name(url),
+                url: url.to_string(),
+                enabled,
+                managed,
+                source: source.to_path_buf(),
+            })
+        })
+        .collect()
+}
+
+/// Parse INI-style repositories (dnf .repo files and pacman.conf)
+fn parse_ini_repos(content: &str, source: &Path) -> Vec<RepoEntry> {
+    let mut entries: Vec<RepoEntry> = Vec::new();
+    let mut managed = source.file_name().is_some_and(|n| n.to_string_lossy().starts_with("rcm-"));
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with("# BEGIN rcm ") {
+            managed = true;
+        } else if line.starts_with("# END rcm ") {
+            managed = false;
+        } else if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            if section != "options" {
+                entries.push(RepoEntry {
+                    name: section.to_string(),
+                    url: String::new(),
+                    enabled: true,
+                    managed,
+                    source: source.to_path_buf(),
+                });
+            }
+        } else if let (Some(entry), Some((key, value))) = (entries.last_mut(), line.split_once('=')) {
+            match key.trim().to_lowercase().as_str() {
+                "baseurl" | "server" | "mirrorlist" | "metalink" | "include" if entry.url.is_empty() => {
+                    entry.url = value.trim().to_string();
+                }
+                "enabled" => entry.enabled = value.trim() != "0",
+                _ => {}
+            }
+        }
+    }
+
+    entries
+}
+
+/// Debian/Ubuntu release codename for apt suites
+async fn release_codename() -> Result<String> {
+    let os_release = fs::read_to_string("/etc/os-release").await.unwrap_or_default();
+    os_release
+        .lines()
+        .find_map(|l| l.strip_prefix("VERSION_CODENAME="))
+        .map(|c| c.trim_matches('"').to_string())
+        .filter(|c| !c.is_empty())
+        .ok_or_else(|| anyhow!("Could not detect the release codename; pass --suite"))
+}
+
+async fn read_key(key: &str) -> Result<Vec<u8>> {
+    if util::is_valid_url(key) {
+        artifacts::download_bytes(key, &Expectations::default()).await
+            .with_context(|| format!("Failed to download key {}", key))
+    } else {
+        fs::read(key).await.with_context(|| format!("Failed to read key {}", key))
+    }
+}
+
+/// A private, randomly named temp file, removed when dropped
+fn temp_file(suffix: &str) -> Result<tempfile::NamedTempFile> {
+    tempfile::Builder::new().prefix("rcm-").suffix(&format!(".{}", suffix)).tempfile()
+        .context("Failed to create a temp file")
+}
+
+async fn dearmor(armored: &[u8]) -> Result<Vec<u8>> {
+    let dir = tempfile::tempdir().context("Failed to create a temp directory")?;
+    let input = dir.path().join("key.asc");
+    let output = dir.path().join("key.gpg");
+    fs::write(&input, armored).await?;
+    // Only touches temp files, so this runs even in dry-run mode
+    execute_command(Command::new("gpg").args(["--dearmor", "--yes", "-o"]).arg(&output).arg(&input)).await
+        .context("Failed to dearmor key (is gnupg installed?)")?;
+    Ok(fs::read(&output).await?)
+}
+
+/// Copy content into place through a temp file, using sudo when the target is not writable
+async fn install_file(path: &Path, content: &[u8], mode: u32) -> Result<()> {
+    let tmp = temp_file(path.file_name().and_then(|n| n.to_str()).unwrap_or("repo"))?;
+    fs::write(tmp.path(), content).await?;
+    let mode = format!("{:o}", mode);
+    run(&mut privileged(path, &[
+        "install", "-D", "-m", &mode, &tmp.path().to_string_lossy(), &path.to_string_lossy(),
+    ])).await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn sudo(args: &[&str]) -> Command {
+    let mut cmd = Command::new("sudo");
+    cmd.args(args);
+    cmd
+}
+
+/// Run through sudo only if the target location is not writable
+fn privileged(path: &Path, args: &[&str]) -> Command {
+    if SourceBuilder::needs_sudo(path) {
+        sudo(args)
+    } else {
+        let mut cmd = Command::new(args[0]);
+        cmd.args(&args[1..]);
+        cmd
+    }
+}
+
+async fn run(cmd: &mut Command) -> Result<()> {
+    execute_mutation(cmd).await.map(|_| ())
+}
+
+fn confirm(prompt: &str, yes: bool) -> Result<()> {
+    if yes {
+        return Ok(());
+    }
+    if Confirm::new().with_prompt(prompt).default(false).interact()? {
+        Ok(())
+    } else {
+        Err(anyhow!("Cancelled"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_name_from_url() {
+        assert_eq!(repo_name("https://download.docker.com/linux/ubuntu"), "download-docker-com-linux-ubuntu");
+        assert_eq!(repo_name("https://rpm.example.com/stable.repo"), "rpm-example-com-stable");
+    }
+
+    #[test]
+    fn test_pacman_block_roundtrip() {
+        let conf = "[options]\nArchitecture = auto\n\n[core]\nInclude = /etc/pacman.d/mirrorlist\n";
+        let added = format!("{}\n{}", conf.trim_end(), pacman_block("chaotic", "https://cdn.example.com/$arch", true));
+
+        let repos = parse_ini_repos(&added, Path::new(PACMAN_CONF));
+        assert_eq!(repos.len(), 2);
+        assert!(repos[1].managed);
+        assert_eq!(repos[1].url, "https://cdn.example.com/$arch");
+
+        assert_eq!(remove_pacman_block(&added, "chaotic").unwrap(), conf);
+        assert!(remove_pacman_block(conf, "chaotic").is_none());
+    }
+
+    #[test]
+    fn test_unsigned_repo_files() {
+        assert!(disables_gpgcheck(&yum_repo_file("x", "https://rpm.example.com", None)));
+        assert!(!disables_gpgcheck(&yum_repo_file("x", "https://rpm.example.com", Some("https://rpm.example.com/key"))));
+        assert!(disables_gpgcheck("[a]\ngpgcheck=1\n[b]\nGPGCHECK = 0\n"));
+    }
+
+    #[test]
+    fn test_apt_sources() {
+        let line = apt_source_line(
+            "https://download.docker.com/linux/ubuntu",
+            "noble",
+            &["stable".to_string()],
+            Some(Path::new("/etc/apt/keyrings/rcm-docker.gpg")),
+        );
+        assert_eq!(line, "deb [signed-by=/etc/apt/keyrings/rcm-docker.gpg] https://download.docker.com/linux/ubuntu noble stable");
+
+        let entries = parse_apt_sources(&format!("{}\n# deb http://old.example.com x main\n", line), Path::new("/etc/apt/sources.list.d/rcm-docker.list"));
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].managed && entries[0].enabled);
+        assert!(!entries[1].enabled);
+    }
+}
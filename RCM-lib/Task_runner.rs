@@ -0,0 +1,449 @@
+//! Workspace tasks (`rcm run`)
+//!
+//! Tasks are defined under `[tasks]` in `rcm.toml` or under `"tasks"` in
+//! `.rcm/workspace.json` (`rcm.toml` wins when both name a task):
+//!
+//! ```toml
+//! [tasks]
+//! build = ["cargo build", "npm run build"]
+//! lint = "cargo clippy -- -D warnings"
+//!
+//! [tasks.test]
+//! run = ["cargo test", "npm test"]
+//! depends = ["build"]
+//! env = { RUST_LOG = "debug" }
+//! dir = "web"
+//! ```
+//!
+//! Commands run through the shell at the workspace root (or `dir`) with the
+//! active environment profile, pinned toolchains and isolated homes. A task's
+//! `depends` run before it. `--parallel` runs a task's commands, and tasks that
+//! don't depend on each other, at the same time, up to `core.parallel_jobs`.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use tabled::{Table, Tabled};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use crate::config::Config;
+use crate::environments;
+use crate::isolation;
+use crate::members;
+use crate::remote::shell_quote;
+use crate::toolchain;
+use crate::util;
+use crate::workspace::Workspace;
+
+pub const TASKS_FILE: &str = "rcm.toml";
+
+/// One command or several
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum CommandList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl CommandList {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            CommandList::One(command) => vec![command],
+            CommandList::Many(commands) => commands,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TaskTable {
+    #[serde(default)]
+    run: Option<CommandList>,
+    #[serde(default)]
+    depends: Vec<String>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    dir: Option<String>,
+    description: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum TaskDef {
+    Commands(CommandList),
+    Table(TaskTable),
+}
+
+/// A task as defined in the manifest
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Task {
+    pub run: Vec<String>,
+    pub depends: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub dir: Option<String>,
+    pub description: Option<String>,
+}
+
+impl From<TaskDef> for Task {
+    fn from(def: TaskDef) -> Self {
+        match def {
+            TaskDef::Commands(commands) => Task { run: commands.into_vec(), ..Task::default() },
+            TaskDef::Table(table) => Task {
+                run: table.run.map(CommandList::into_vec).unwrap_or_default(),
+                depends: table.depends,
+                env: table.env,
+                dir: table.dir,
+                description: table.description,
+            },
+        }
+    }
+}
+
+/// The part of either manifest this module reads
+#[derive(Debug, Default, Deserialize)]
+struct TasksSection {
+    #[serde(default)]
+    tasks: BTreeMap<String, TaskDef>,
+}
+
+fn parse_toml(content: &str) -> Result<BTreeMap<String, Task>> {
+    let section: TasksSection = toml::from_str(content)?;
+    Ok(section.tasks.into_iter().map(|(name, def)| (name, def.into())).collect())
+}
+
+fn parse_json(content: &str) -> Result<BTreeMap<String, Task>> {
+    let section: TasksSection = serde_json::from_str(content)?;
+    Ok(section.tasks.into_iter().map(|(name, def)| (name, def.into())).collect())
+}
+
+/// Tasks defined for the workspace at `root`
+pub async fn load(root: &Path) -> Result<BTreeMap<String, Task>> {
+    let mut tasks = BTreeMap::new();
+    let manifest = root.join(members::MANIFEST);
+    if manifest.exists() {
+        let content = tokio::fs::read_to_string(&manifest).await?;
+        tasks.extend(parse_json(&content).with_context(|| format!("Invalid tasks in {}", manifest.display()))?);
+    }
+    let toml_path = root.join(TASKS_FILE);
+    if toml_path.exists() {
+        let content = tokio::fs::read_to_string(&toml_path).await?;
+        tasks.extend(parse_toml(&content).with_context(|| format!("Invalid [tasks] in {}", toml_path.display()))?);
+    }
+    Ok(tasks)
+}
+
+/// The tasks to run for `target`, in stages: each stage only depends on earlier ones
+pub fn stages(tasks: &BTreeMap<String, Task>, target: &str) -> Result<Vec<Vec<String>>> {
+    fn depth(
+        tasks: &BTreeMap<String, Task>,
+        name: &str,
+        path: &mut Vec<String>,
+        depths: &mut BTreeMap<String, usize>,
+    ) -> Result<usize> {
+        if let Some(depth) = depths.get(name) {
+            return Ok(*depth);
+        }
+        if let Some(start) = path.iter().position(|n| n == name) {
+            return Err(anyhow!("Task dependency cycle: {} -> {}", path[start..].join(" -> "), name));
+        }
+        let task = tasks.get(name).ok_or_else(|| match path.last() {
+            Some(parent) => anyhow!("Task '{}' depends on unknown task '{}'", parent, name),
+            None => anyhow!("No task '{}'", name),
+        })?;
+        path.push(name.to_string());
+        let mut deepest = 0;
+        for dependency in &task.depends {
+            deepest = deepest.max(depth(tasks, dependency, path, depths)? + 1);
+        }
+        path.pop();
+        depths.insert(name.to_string(), deepest);
+        Ok(deepest)
+    }
+
+    let mut depths = BTreeMap::new();
+    let top = depth(tasks, target, &mut Vec::new(), &mut depths)?;
+    let mut stages = vec![Vec::new(); top + 1];
+    for (name, depth) in depths {
+        stages[depth].push(name);
+    }
+    Ok(stages)
+}
+
+/// `command` with `args` appended, quoted for the shell
+fn with_args(command: &str, args: &[String]) -> String {
+    let mut command = command.to_string();
+    for arg in args {
+        command.push(' ');
+        command.push_str(&shell_quote(arg));
+    }
+    command
+}
+
+fn shell(command: &str) -> Command {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+    cmd.arg(command);
+    cmd
+}
+
+/// Everything a task command needs besides its text
+struct Job {
+    label: String,
+    command: String,
+    dir: PathBuf,
+    env: HashMap<String, String>,
+}
+
+impl Job {
+    fn process(&self) -> Command {
+        let mut cmd = shell(&self.command);
+        cmd.current_dir(&self.dir).envs(&self.env);
+        cmd
+    }
+
+    /// Run with the terminal attached
+    async fn run_attached(&self) -> Result<()> {
+        println!("{} {}", style(format!("[{}]", self.label)).cyan(), style(&self.command).dim());
+        let status = self.process().status().await
+            .with_context(|| format!("Failed to start `{}`", self.command))?;
+        if !status.success() {
+            return Err(anyhow!("`{}` {}", self.command, describe_exit(status)));
+        }
+        Ok(())
+    }
+
+    /// Run with output prefixed by the label, so concurrent commands stay readable
+    async fn run_prefixed(self) -> Result<()> {
+        println!("{} {}", style(format!("[{}]", self.label)).cyan(), style(&self.command).dim());
+        let mut child = self.process()
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to start `{}`", self.command))?;
+        let prefix = style(format!("[{}]", self.label)).cyan().to_string();
+        let stdout = child.stdout.take().map(|out| tokio::spawn(print_lines(BufReader::new(out), prefix.clone())));
+        let stderr = child.stderr.take().map(|err| tokio::spawn(print_lines(BufReader::new(err), prefix)));
+        let status = child.wait().await?;
+        for reader in stdout.into_iter().chain(stderr) {
+            let _ = reader.await;
+        }
+        if !status.success() {
+            return Err(anyhow!("[{}] `{}` {}", self.label, self.command, describe_exit(status)));
+        }
+        Ok(())
+    }
+}
+
+async fn print_lines<R: tokio::io::AsyncRead + Unpin>(reader: BufReader<R>, prefix: String) {
+    let mut lines = reader.lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        println!("{} {}", prefix, line);
+    }
+}
+
+fn describe_exit(status: std::process::ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("exited with {}", code),
+        None => "was killed by a signal".to_string(),
+    }
+}
+
+/// Options for `rcm run`
+pub struct RunOptions<'a> {
+    pub parallel: bool,
+    pub env: Option<&'a str>,
+    pub no_deps: bool,
+    /// Appended to each command of the requested task
+    pub args: &'a [String],
+}
+
+/// Build the jobs for one task
+fn jobs(root: &Path, name: &str, task: &Task, base_env: &HashMap<String, String>, args: &[String]) -> Vec<Job> {
+    let mut env = base_env.clone();
+    env.extend(task.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+    env.insert("RCM_TASK".to_string(), name.to_string());
+    let dir = task.dir.as_deref().map_or_else(|| root.to_path_buf(), |dir| root.join(dir));
+    task.run.iter().enumerate().map(|(i, command)| Job {
+        label: if task.run.len() > 1 { format!("{}:{}", name, i + 1) } else { name.to_string() },
+        command: with_args(command, args),
+        dir: dir.clone(),
+        env: env.clone(),
+    }).collect()
+}
+
+/// Environment shared by every task command
+async fn base_env(root: &Path, env: Option<&str>) -> Result<HashMap<String, String>> {
+    let environment = environments::active(env);
+    let mut vars = environments::resolve(root, &environment).await?;
+    for (key, value) in isolation::env_vars(root) {
+        vars.insert(key.to_string(), value.to_string_lossy().into_owned());
+    }
+    if let Some(path) = toolchain::shim_path(root) {
+        vars.insert("PATH".to_string(), path.to_string_lossy().into_owned());
+    }
+    vars.insert("RCM_ENV".to_string(), environment);
+    vars.insert("RCM_WORKSPACE_ROOT".to_string(), root.to_string_lossy().into_owned());
+    Ok(vars)
+}
+
+#[derive(Tabled)]
+struct TaskRow {
+    #[tabled(rename = "Task")]
+    name: String,
+    #[tabled(rename = "Runs")]
+    run: String,
+    #[tabled(rename = "Depends on")]
+    depends: String,
+    #[tabled(rename = "Description")]
+    description: String,
+}
+
+fn list(root: &Path, tasks: &BTreeMap<String, Task>) {
+    if tasks.is_empty() {
+        println!("{}", style("📋 No tasks defined").yellow());
+        println!("Add them under [tasks] in {}, e.g. build = [\"cargo build\", \"npm run build\"]", root.join(TASKS_FILE).display());
+        return;
+    }
+    let rows: Vec<TaskRow> = tasks.iter().map(|(name, task)| TaskRow {
+        name: name.clone(),
+        run: task.run.join(" && "),
+        depends: task.depends.join(", "),
+        description: task.description.clone().unwrap_or_default(),
+    }).collect();
+    println!("{}", Table::new(&rows));
+}
+
+/// Run `target` and the tasks it depends on
+pub async fn run(workspace: &Workspace, config: &Config, target: Option<&str>, options: RunOptions<'_>) -> Result<()> {
+    let root = workspace.root();
+    let tasks = load(root).await?;
+    let Some(target) = target else {
+        list(root, &tasks);
+        return Ok(());
+    };
+    let stages = if options.no_deps {
+        if !tasks.contains_key(target) {
+            return Err(anyhow!("No task '{}'", target));
+        }
+        vec![vec![target.to_string()]]
+    } else {
+        stages(&tasks, target)?
+    };
+
+    let env = base_env(root, options.env).await?;
+    let order: Vec<&str> = stages.iter().flatten().map(String::as_str).collect();
+    println!("{}", style(format!("🏃 Running {}", order.join(" → "))).bold());
+    if util::is_dry_run() {
+        for name in &order {
+            let args: &[String] = if *name == target { options.args } else { &[] };
+            for job in jobs(root, name, &tasks[*name], &env, args) {
+                println!("[dry-run] [{}] {} (in {})", job.label, job.command, job.dir.display());
+            }
+        }
+        return Ok(());
+    }
+
+    let started = std::time::Instant::now();
+    let limit = Arc::new(Semaphore::new(config.core.parallel_jobs.max(1)));
+    for stage in &stages {
+        let mut stage_jobs = Vec::new();
+        for name in stage {
+            let args: &[String] = if name == target { options.args } else { &[] };
+            stage_jobs.extend(jobs(root, name, &tasks[name], &env, args));
+        }
+        if !options.parallel {
+            for job in &stage_jobs {
+                job.run_attached().await?;
+            }
+            continue;
+        }
+        let mut running = JoinSet::new();
+        for job in stage_jobs {
+            let limit = limit.clone();
+            running.spawn(async move {
+                let _permit = limit.acquire_owned().await.expect("task semaphore closed");
+                job.run_prefixed().await
+            });
+        }
+        // Let the rest of the stage finish, then stop before dependents start
+        let mut failures = Vec::new();
+        while let Some(result) = running.join_next().await {
+            if let Err(e) = result? {
+                failures.push(e.to_string());
+            }
+        }
+        if !failures.is_empty() {
+            return Err(anyhow!("{}", failures.join("\n")));
+        }
+    }
+    println!("{}", style(format!("✅ {} done in {}", target, util::format_duration(started.elapsed().as_millis() as u64))).green());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+        [package]
+        name = "demo"
+
+        [tasks]
+        build = ["cargo build", "npm run build"]
+        lint = "cargo clippy"
+
+        [tasks.test]
+        run = "cargo test"
+        depends = ["build", "lint"]
+        env = { RUST_LOG = "debug" }
+
+        [tasks.ci]
+        depends = ["test", "build"]
+    "#;
+
+    #[test]
+    fn test_parse() {
+        let tasks = parse_toml(TOML).unwrap();
+        assert_eq!(tasks["build"].run, vec!["cargo build", "npm run build"]);
+        assert_eq!(tasks["lint"].run, vec!["cargo clippy"]);
+        assert_eq!(tasks["test"].depends, vec!["build", "lint"]);
+        assert_eq!(tasks["test"].env["RUST_LOG"], "debug");
+        assert!(tasks["ci"].run.is_empty());
+
+        let json = parse_json(r#"{ "name": "demo", "tasks": { "fmt": "cargo fmt" } }"#).unwrap();
+        assert_eq!(json["fmt"].run, vec!["cargo fmt"]);
+        assert!(parse_toml("[tasks.x]\nrun = \"a\"\ndepend = [\"b\"]").is_err());
+    }
+
+    #[test]
+    fn test_stages() {
+        let tasks = parse_toml(TOML).unwrap();
+        assert_eq!(stages(&tasks, "ci").unwrap(), vec![vec!["build", "lint"], vec!["test"], vec!["ci"]]);
+        assert_eq!(stages(&tasks, "lint").unwrap(), vec![vec!["lint"]]);
+        assert!(stages(&tasks, "deploy").is_err());
+
+        let mut cyclic = tasks.clone();
+        cyclic.get_mut("build").unwrap().depends = vec!["ci".to_string()];
+        let error = stages(&cyclic, "ci").unwrap_err().to_string();
+        assert!(error.contains("cycle"), "{}", error);
+    }
+
+    #[test]
+    fn test_with_args() {
+        assert_eq!(with_args("cargo test", &["--nocapture".to_string(), "a b".to_string()]), "cargo test --nocapture 'a b'");
+    }
+}
@@ -0,0 +1,235 @@
+//! OpenTelemetry export for RCM
+//!
+//! When `telemetry.otlp_endpoint` (or `OTEL_EXPORTER_OTLP_ENDPOINT`) is set,
+//! RCM's spans are sent over OTLP/HTTP to that collector alongside the console
+//! and JSON trace output. Each command is one trace; every `rcm gpt gateway`
+//! request is a trace of its own. Generation spans follow the GenAI semantic
+//! conventions (`gen_ai.request.model`, `gen_ai.usage.input_tokens`, ...), so
+//! LLM traffic shows up in existing tracing stacks. Prompts and completions are
+//! only attached with `telemetry.capture_content`. Everything exported — span
+//! names, attributes, events and status messages — is redacted like logs.
+//!
+//! Package operations (`ensure`, `add`, `update`, system installs) get a span
+//! per manager, and every external command run through `util` a span of its
+//! own, so slow installs can be broken down by manager and by command.
+
+use anyhow::{Context, Result};
+use opentelemetry::trace::{Event, Status, TracerProvider as _};
+use opentelemetry::{KeyValue, Value};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+use opentelemetry_sdk::{runtime, trace, Resource};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::field::Empty;
+use tracing::{Instrument, Level, Span, Subscriber};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+use crate::config::TelemetryConfig;
+use crate::redact;
+use crate::util;
+
+pub const ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+pub const HEADERS_ENV: &str = "OTEL_EXPORTER_OTLP_HEADERS";
+
+static CAPTURE_CONTENT: AtomicBool = AtomicBool::new(false);
+
+/// The collector to export to, if any
+pub fn endpoint(config: &TelemetryConfig) -> Option<String> {
+    config.otlp_endpoint.clone()
+        .or_else(|| std::env::var(ENDPOINT_ENV).ok())
+        .map(|e| e.trim().trim_end_matches('/').to_string())
+        .filter(|e| !e.is_empty())
+}
+
+/// Parse `key=value,key2=value2` as in `OTEL_EXPORTER_OTLP_HEADERS`
+fn parse_headers(value: &str) -> HashMap<String, String> {
+    value.split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .filter(|(key, _)| !key.is_empty())
+        .collect()
+}
+
+/// Collector headers from the config, with the environment filling in the rest
+fn headers(config: &TelemetryConfig) -> HashMap<String, String> {
+    let mut headers = std::env::var(HEADERS_ENV).map(|v| parse_headers(&v)).unwrap_or_default();
+    headers.extend(config.otlp_headers.clone());
+    for value in headers.values() {
+        redact::register(value);
+    }
+    headers
+}
+
+/// Mask secrets in a string attribute value
+fn redact_value(value: &mut Value) {
+    if let Value::String(text) = value {
+        let masked = redact::redact(text.as_str());
+        if masked != text.as_str() {
+            *value = Value::from(masked);
+        }
+    }
+}
+
+fn redact_attributes(attributes: &mut [KeyValue]) {
+    for attribute in attributes {
+        redact_value(&mut attribute.value);
+    }
+}
+
+fn redact_event(mut event: Event) -> Event {
+    event.name = redact::redact(&event.name).into();
+    redact_attributes(&mut event.attributes);
+    event
+}
+
+/// Redact a finished span before it leaves the process
+fn redact_span(span: &mut SpanData) {
+    span.name = redact::redact(&span.name).into();
+    redact_attributes(&mut span.attributes);
+    let events: Vec<Event> = std::mem::take(&mut span.events).into_iter().map(redact_event).collect();
+    span.events.extend(events);
+    if let Status::Error { description } = &span.status {
+        span.status = Status::error(redact::redact(description));
+    }
+}
+
+/// Wraps the OTLP exporter so nothing reaches the collector unredacted
+#[derive(Debug)]
+struct RedactingExporter<E>(E);
+
+impl<E: SpanExporter> SpanExporter for RedactingExporter<E> {
+    fn export(&mut self, mut batch: Vec<SpanData>) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+        batch.iter_mut().for_each(redact_span);
+        self.0.export(batch)
+    }
+
+    fn shutdown(&mut self) {
+        self.0.shutdown();
+    }
+
+    fn force_flush(&mut self) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+        self.0.force_flush()
+    }
+}
+
+/// A layer exporting RCM's spans to the configured collector; `None` when none is set
+pub fn layer<S>(config: &TelemetryConfig) -> Result<Option<Box<dyn Layer<S> + Send + Sync>>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let Some(endpoint) = endpoint(config) else {
+        return Ok(None);
+    };
+    let exporter = opentelemetry_otlp::new_exporter()
+        .http()
+        .with_endpoint(&endpoint)
+        .with_headers(headers(config))
+        .build_span_exporter()
+        .with_context(|| format!("Failed to set up OTLP export to {}", endpoint))?;
+    let resource = Resource::new(vec![
+        KeyValue::new("service.name", config.service_name.clone()),
+        KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+    ]);
+    let provider = trace::TracerProvider::builder()
+        .with_batch_exporter(RedactingExporter(exporter), runtime::Tokio)
+        .with_config(trace::config().with_resource(resource))
+        .build();
+    let tracer = provider.tracer(env!("CARGO_CRATE_NAME"));
+    opentelemetry::global::set_tracer_provider(provider);
+    CAPTURE_CONTENT.store(config.capture_content, Ordering::SeqCst);
+
+    let targets = Targets::new().with_target(env!("CARGO_CRATE_NAME"), Level::INFO);
+    Ok(Some(tracing_opentelemetry::layer().with_tracer(tracer).with_filter(targets).boxed()))
+}
+
+/// Flush spans still waiting in the batch exporter
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+/// Whether prompts and completions may be attached to spans
+pub fn capture_content() -> bool {
+    CAPTURE_CONTENT.load(Ordering::SeqCst)
+}
+
+/// Attach prompt or completion text to `field` of `span`, if content capture is on
+pub fn record_content(span: &Span, field: &'static str, text: &str) {
+    if capture_content() {
+        span.record(field, redact::redact(text).as_str());
+    }
+}
+
+/// A span for one manager's share of a package operation, e.g. `ensure install` for npm
+pub fn manager_span(operation: &str, manager: &str) -> Span {
+    tracing::info_span!(
+        "manager",
+        otel.name = %format_args!("{} {}", operation, manager),
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+        rcm.operation = operation,
+        rcm.manager = manager,
+    )
+}
+
+/// A span for one external command, with `process.*` attributes
+pub fn command_span(cmd: &std::process::Command) -> Span {
+    let program = Path::new(cmd.get_program()).file_name().unwrap_or(cmd.get_program()).to_string_lossy().into_owned();
+    tracing::info_span!(
+        "exec",
+        otel.name = %format_args!("exec {}", program),
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+        process.executable.name = %program,
+        process.command_line = %util::describe_command(cmd),
+        process.exit_code = Empty,
+    )
+}
+
+/// Mark `span` failed with `error`
+///
+/// The message goes to the status, where it is redacted; `error.type` stays
+/// low-cardinality as the semantic conventions expect.
+pub fn record_error(span: &Span, error: &anyhow::Error) {
+    span.record("otel.status_code", "error");
+    span.record("otel.status_message", redact::redact(&error.to_string()).as_str());
+    span.record("error.type", "_OTHER");
+}
+
+/// Run `future` inside `span`, marking the span failed if it errors
+pub async fn traced<T>(span: Span, future: impl Future<Output = Result<T>>) -> Result<T> {
+    let result = future.instrument(span.clone()).await;
+    if let Err(e) = &result {
+        record_error(&span, e);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_headers() {
+        let headers = parse_headers("x-honeycomb-team=abc, authorization=Basic dXNlcg==,bad");
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers["x-honeycomb-team"], "abc");
+        assert_eq!(headers["authorization"], "Basic dXNlcg==");
+    }
+
+    #[test]
+    fn test_redact_attributes() {
+        redact::register("tok-unit-telemetry-secret");
+        let mut attributes = vec![
+            KeyValue::new("process.command_line", "curl -H tok-unit-telemetry-secret"),
+            KeyValue::new("gen_ai.usage.input_tokens", 12i64),
+        ];
+        redact_attributes(&mut attributes);
+        assert!(!attributes[0].value.as_str().contains("tok-unit-telemetry-secret"));
+        assert_eq!(attributes[1].value, Value::I64(12));
+    }
+}
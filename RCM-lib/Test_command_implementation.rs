@@ -0,0 +1,561 @@
+//! Test command implementation
+//!
+//! Runs the test suites of every ecosystem in the workspace in parallel (cargo,
+//! jest/vitest, phpunit), merges their results into one summary and writes a
+//! JUnit report for CI systems.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::Instant;
+use tabled::{Table, Tabled};
+use tokio::fs;
+use tokio::task::JoinSet;
+use crate::isolation;
+use crate::util;
+use crate::workspace::Workspace;
+
+/// Default location of the merged JUnit report
+pub const JUNIT_FILE: &str = ".rcm/reports/junit.xml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// One test case from any runner
+#[derive(Debug, Clone, Serialize)]
+pub struct TestCase {
+    pub suite: String,
+    /// Module, file or class the test belongs to
+    pub classname: String,
+    pub name: String,
+    pub status: TestStatus,
+    /// Seconds
+    pub time: f64,
+    pub failure: Option<String>,
+}
+
+/// Results of one runner
+#[derive(Debug, Clone, Serialize)]
+pub struct SuiteResult {
+    pub suite: String,
+    pub cases: Vec<TestCase>,
+    /// Seconds
+    pub time: f64,
+    /// Set when the runner failed without reporting any test (e.g. a compile error)
+    pub error: Option<String>,
+}
+
+impl SuiteResult {
+    fn count(&self, status: TestStatus) -> usize {
+        self.cases.iter().filter(|c| c.status == status).count()
+    }
+
+    fn failed(&self) -> bool {
+        self.error.is_some() || self.count(TestStatus::Failed) > 0
+    }
+}
+
+#[derive(Tabled)]
+struct SuiteRow {
+    #[tabled(rename = "Suite")]
+    suite: String,
+    #[tabled(rename = "Passed")]
+    passed: usize,
+    #[tabled(rename = "Failed")]
+    failed: usize,
+    #[tabled(rename = "Skipped")]
+    skipped: usize,
+    #[tabled(rename = "Time")]
+    time: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TestRunner {
+    Cargo,
+    Jest,
+    Vitest,
+    Phpunit,
+}
+
+impl TestRunner {
+    pub const ALL: [TestRunner; 4] = [Self::Cargo, Self::Jest, Self::Vitest, Self::Phpunit];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo",
+            Self::Jest => "jest",
+            Self::Vitest => "vitest",
+            Self::Phpunit => "phpunit",
+        }
+    }
+
+    /// Whether the workspace has a suite for this runner
+    pub async fn detect(&self, root: &Path) -> bool {
+        match self {
+            Self::Cargo => root.join("Cargo.toml").exists() && util::command_exists("cargo").await,
+            Self::Jest => root.join("node_modules/.bin/jest").exists(),
+            Self::Vitest => root.join("node_modules/.bin/vitest").exists(),
+            Self::Phpunit => root.join("vendor/bin/phpunit").exists(),
+        }
+    }
+
+    /// Run the suite and capture its output; file-based reports are written to `report`
+    async fn output(&self, root: &Path, report: &Path) -> Result<Output> {
+        let mut cmd = match self {
+            Self::Cargo => return run_cargo(root).await,
+            Self::Jest => {
+                let mut c = Command::new(root.join("node_modules/.bin/jest"));
+                c.args(["--ci", "--json", "--outputFile"]).arg(report);
+                c
+            }
+            Self::Vitest => {
+                let mut c = Command::new(root.join("node_modules/.bin/vitest"));
+                c.args(["run", "--reporter=junit", "--outputFile"]).arg(report);
+                c
+            }
+            Self::Phpunit => {
+                let mut c = Command::new(root.join("vendor/bin/phpunit"));
+                c.arg("--log-junit").arg(report);
+                c
+            }
+        };
+        cmd.current_dir(root).envs(isolation::env_vars(root));
+        tokio::process::Command::from(cmd)
+            .output()
+            .await
+            .with_context(|| format!("Failed to run {}", self.name()))
+    }
+
+    /// Normalize the runner's results from its stdout or report file
+    pub fn parse(&self, stdout: &str, report: &str) -> Vec<TestCase> {
+        match self {
+            Self::Cargo => parse_libtest_json(stdout).into_iter().chain(parse_libtest_plain(stdout)).collect(),
+            Self::Jest => parse_jest_json(report),
+            Self::Vitest | Self::Phpunit => parse_junit(self.name(), report),
+        }
+    }
+
+    async fn run(self, root: PathBuf) -> Result<SuiteResult> {
+        let report = std::env::temp_dir().join(format!("rcm-test-{}-{}", self.name(), uuid::Uuid::new_v4()));
+        let started = Instant::now();
+        let output = self.output(&root, &report).await?;
+        let report_content = fs::read_to_string(&report).await.unwrap_or_default();
+        let _ = fs::remove_file(&report).await;
+
+        let cases = self.parse(&String::from_utf8_lossy(&output.stdout), &report_content);
+        let error = (!output.status.success() && cases.iter().all(|c| c.status != TestStatus::Failed)).then(|| {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let tail: Vec<&str> = stderr.lines().rev().take(20).collect();
+            tail.into_iter().rev().collect::<Vec<_>>().join("\n")
+        });
+
+        Ok(SuiteResult { suite: self.name().to_string(), cases, time: started.elapsed().as_secs_f64(), error })
+    }
+}
+
+/// Build the cargo tests, then run each test binary with libtest's JSON output
+///
+/// `--format json` is unstable, so RUSTC_BOOTSTRAP is set on the test binaries only;
+/// the build itself never sees nightly features. Doctests are compiled by rustdoc
+/// and run with the plain output instead.
+async fn run_cargo(root: &Path) -> Result<Output> {
+    let build = tokio::process::Command::new("cargo")
+        .args(["test", "--workspace", "--no-run", "--message-format=json-render-diagnostics"])
+        .current_dir(root)
+        .envs(isolation::env_vars(root))
+        .output()
+        .await
+        .context("Failed to build cargo tests")?;
+    if !build.status.success() {
+        return Ok(build);
+    }
+
+    let messages = String::from_utf8_lossy(&build.stdout);
+    let mut combined = Output { status: build.status, stdout: Vec::new(), stderr: Vec::new() };
+    let mut record = |output: Output| {
+        combined.stdout.extend(output.stdout);
+        combined.stderr.extend(output.stderr);
+        if !output.status.success() {
+            combined.status = output.status;
+        }
+    };
+    for (executable, package_dir) in test_executables(&messages) {
+        // cargo runs test binaries from their package directory
+        record(tokio::process::Command::new(&executable)
+            .args(["-Z", "unstable-options", "--format", "json", "--report-time"])
+            .env("RUSTC_BOOTSTRAP", "1")
+            .current_dir(package_dir)
+            .envs(isolation::env_vars(root))
+            .output()
+            .await
+            .with_context(|| format!("Failed to run {}", executable.display()))?);
+    }
+    if has_library(&messages) {
+        record(tokio::process::Command::new("cargo")
+            .args(["test", "--workspace", "--doc", "--no-fail-fast"])
+            .current_dir(root)
+            .envs(isolation::env_vars(root))
+            .output()
+            .await
+            .context("Failed to run cargo doctests")?);
+    }
+    Ok(combined)
+}
+
+/// Test binaries and their package directories from `cargo test --no-run --message-format=json`
+pub fn test_executables(messages: &str) -> Vec<(PathBuf, PathBuf)> {
+    messages
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|v| v["reason"] == "compiler-artifact" && v["profile"]["test"] == true)
+        .filter_map(|v| {
+            let executable = PathBuf::from(v["executable"].as_str()?);
+            let package_dir = Path::new(v["manifest_path"].as_str()?).parent()?.to_path_buf();
+            Some((executable, package_dir))
+        })
+        .collect()
+}
+
+/// Whether the build produced a library, the only target kind with doctests
+fn has_library(messages: &str) -> bool {
+    messages
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|v| v["reason"] == "compiler-artifact")
+        .any(|v| v["target"]["kind"].as_array().into_iter().flatten().any(|k| k == "lib"))
+}
+
+/// libtest `--format json` events, one per line
+pub fn parse_libtest_json(stdout: &str) -> Vec<TestCase> {
+    stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|v| v["type"] == "test")
+        .filter_map(|v| {
+            let status = match v["event"].as_str()? {
+                "ok" => TestStatus::Passed,
+                "failed" | "timeout" => TestStatus::Failed,
+                "ignored" => TestStatus::Skipped,
+                _ => return None,
+            };
+            let full = v["name"].as_str()?;
+            let (classname, name) = full.rsplit_once("::").unwrap_or(("", full));
+            Some(TestCase {
+                suite: "cargo".to_string(),
+                classname: classname.to_string(),
+                name: name.to_string(),
+                status,
+                time: v["exec_time"].as_f64().unwrap_or_default(),
+                failure: (status == TestStatus::Failed)
+                    .then(|| v["stdout"].as_str().unwrap_or("test failed").trim().to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Plain libtest result lines (`test name ... ok`), as printed for doctests
+pub fn parse_libtest_plain(stdout: &str) -> Vec<TestCase> {
+    let line = Regex::new(r"^test (.+) \.\.\. (ok|FAILED|ignored)").unwrap();
+    stdout
+        .lines()
+        .filter_map(|l| line.captures(l))
+        .map(|c| {
+            let status = match &c[2] {
+                "ok" => TestStatus::Passed,
+                "FAILED" => TestStatus::Failed,
+                _ => TestStatus::Skipped,
+            };
+            // Doctests are named `src/lib.rs - path::to::item (line 12)`
+            let (classname, name) = c[1].split_once(" - ").unwrap_or(("", &c[1]));
+            TestCase {
+                suite: "cargo".to_string(),
+                classname: classname.to_string(),
+                name: name.to_string(),
+                status,
+                time: 0.0,
+                failure: (status == TestStatus::Failed).then(|| "doctest failed".to_string()),
+            }
+        })
+        .collect()
+}
+
+/// jest `--json` report
+pub fn parse_jest_json(report: &str) -> Vec<TestCase> {
+    let report: Value = serde_json::from_str(report).unwrap_or_default();
+    report["testResults"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|file| {
+            let classname = file["name"].as_str().unwrap_or_default().to_string();
+            file["assertionResults"].as_array().cloned().unwrap_or_default().into_iter().map(move |t| {
+                let status = match t["status"].as_str() {
+                    Some("passed") => TestStatus::Passed,
+                    Some("failed") => TestStatus::Failed,
+                    _ => TestStatus::Skipped,
+                };
+                let messages: Vec<&str> = t["failureMessages"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+                TestCase {
+                    suite: "jest".to_string(),
+                    classname: classname.clone(),
+                    name: t["fullName"].as_str().or(t["title"].as_str()).unwrap_or_default().to_string(),
+                    status,
+                    time: t["duration"].as_f64().unwrap_or_default() / 1000.0,
+                    failure: (status == TestStatus::Failed).then(|| messages.join("\n")),
+                }
+            })
+        })
+        .collect()
+}
+
+/// JUnit XML written by vitest and phpunit
+pub fn parse_junit(suite: &str, xml: &str) -> Vec<TestCase> {
+    let testcase = Regex::new(r#"(?s)<testcase\b([^>]*?)(?:/>|>(.*?)</testcase>)"#).unwrap();
+    let attr = |attrs: &str, name: &str| {
+        Regex::new(&format!(r#"\b{}="([^"]*)""#, name)).unwrap()
+            .captures(attrs)
+            .map(|c| unescape_xml(&c[1]))
+    };
+    let failure = Regex::new(r#"(?s)<(?:failure|error)\b([^>]*?)(?:/>|>(.*?)</(?:failure|error)>)"#).unwrap();
+
+    testcase
+        .captures_iter(xml)
+        .map(|c| {
+            let attrs = c.get(1).map_or("", |m| m.as_str());
+            let body = c.get(2).map_or("", |m| m.as_str());
+            let failed = failure.captures(body);
+            let status = if failed.is_some() {
+                TestStatus::Failed
+            } else if body.contains("<skipped") {
+                TestStatus::Skipped
+            } else {
+                TestStatus::Passed
+            };
+            TestCase {
+                suite: suite.to_string(),
+                classname: attr(attrs, "classname").or_else(|| attr(attrs, "file")).unwrap_or_default(),
+                name: attr(attrs, "name").unwrap_or_default(),
+                status,
+                time: attr(attrs, "time").and_then(|t| t.parse().ok()).unwrap_or_default(),
+                failure: failed.map(|f| {
+                    let text = f.get(2).map(|m| unescape_xml(m.as_str().trim())).unwrap_or_default();
+                    if text.is_empty() {
+                        attr(f.get(1).map_or("", |m| m.as_str()), "message").unwrap_or_else(|| "test failed".to_string())
+                    } else {
+                        text
+                    }
+                }),
+            }
+        })
+        .collect()
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn unescape_xml(s: &str) -> String {
+    let s = s.strip_prefix("<![CDATA[").and_then(|s| s.strip_suffix("]]>")).unwrap_or(s);
+    s.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+/// Merge suite results into one JUnit document
+pub fn to_junit(results: &[SuiteResult]) -> String {
+    let total = |status| results.iter().map(|r| r.count(status)).sum::<usize>();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"rcm\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+        results.iter().map(|r| r.cases.len()).sum::<usize>(),
+        total(TestStatus::Failed),
+        total(TestStatus::Skipped),
+        results.iter().map(|r| r.time).sum::<f64>(),
+    ));
+    for result in results {
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+            escape_xml(&result.suite), result.cases.len(), result.count(TestStatus::Failed),
+            usize::from(result.error.is_some()), result.count(TestStatus::Skipped), result.time,
+        ));
+        for case in &result.cases {
+            xml.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+                escape_xml(&case.classname), escape_xml(&case.name), case.time,
+            ));
+            match (case.status, &case.failure) {
+                (TestStatus::Failed, failure) => {
+                    let text = failure.as_deref().unwrap_or("test failed");
+                    let message = text.lines().next().unwrap_or_default();
+                    xml.push_str(&format!(
+                        ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                        escape_xml(message), escape_xml(text),
+                    ));
+                }
+                (TestStatus::Skipped, _) => xml.push_str(">\n      <skipped/>\n    </testcase>\n"),
+                (TestStatus::Passed, _) => xml.push_str("/>\n"),
+            }
+        }
+        if let Some(error) = &result.error {
+            xml.push_str(&format!("    <system-err>{}</system-err>\n", escape_xml(error)));
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Run every detected suite in parallel and report the merged results
+pub async fn run(workspace: &Workspace, suites: Option<Vec<String>>, junit: Option<PathBuf>, format: &str) -> Result<()> {
+    let root = workspace.root().to_path_buf();
+    let mut runners = Vec::new();
+    for runner in TestRunner::ALL {
+        if suites.as_ref().is_some_and(|s| !s.iter().any(|n| n == runner.name())) {
+            continue;
+        }
+        if runner.detect(&root).await {
+            runners.push(runner);
+        }
+    }
+    if runners.is_empty() {
+        return Err(anyhow!("No test suites found (cargo, jest, vitest, phpunit)"));
+    }
+
+    if format == "table" {
+        let names: Vec<&str> = runners.iter().map(TestRunner::name).collect();
+        println!("{}", style(format!("🧪 Running {} in parallel...", names.join(", "))).blue());
+    }
+
+    let mut tasks = JoinSet::new();
+    for runner in runners {
+        tasks.spawn(runner.run(root.clone()));
+    }
+    let mut results = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        results.push(result??);
+    }
+    results.sort_by(|a, b| a.suite.cmp(&b.suite));
+
+    let junit = junit.unwrap_or_else(|| root.join(JUNIT_FILE));
+    if let Some(parent) = junit.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    util::write_file(&junit, to_junit(&results)).await?;
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&results)?),
+        "table" => print_summary(&results, &junit),
+        _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+    }
+
+    let failed: Vec<&str> = results.iter().filter(|r| r.failed()).map(|r| r.suite.as_str()).collect();
+    if !failed.is_empty() {
+        return Err(anyhow!("Tests failed in {}", failed.join(", ")));
+    }
+    Ok(())
+}
+
+fn print_summary(results: &[SuiteResult], junit: &Path) {
+    for result in results {
+        for case in result.cases.iter().filter(|c| c.status == TestStatus::Failed) {
+            println!();
+            println!("{} {} › {}", style("✗").red().bold(), style(&case.classname).dim(), style(&case.name).bold());
+            if let Some(failure) = &case.failure {
+                for line in failure.lines().take(30) {
+                    println!("    {}", line);
+                }
+            }
+        }
+        if let Some(error) = &result.error {
+            println!();
+            println!("{} {} failed without reporting tests:", style("✗").red().bold(), style(&result.suite).bold());
+            for line in error.lines() {
+                println!("    {}", line);
+            }
+        }
+    }
+
+    let rows: Vec<SuiteRow> = results
+        .iter()
+        .map(|r| SuiteRow {
+            suite: r.suite.clone(),
+            passed: r.count(TestStatus::Passed),
+            failed: r.count(TestStatus::Failed),
+            skipped: r.count(TestStatus::Skipped),
+            time: format!("{:.1}s", r.time),
+        })
+        .collect();
+    println!();
+    println!("{}", Table::new(rows));
+
+    if results.iter().any(SuiteResult::failed) {
+        println!("{}", style("❌ Some tests failed").red().bold());
+    } else {
+        println!("{}", style("✅ All tests passed").green().bold());
+    }
+    println!("JUnit report: {}", style(junit.display()).cyan());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_libtest_json() {
+        let stdout = r#"{ "type": "suite", "event": "started", "test_count": 2 }
+{ "type": "test", "event": "ok", "name": "util::tests::test_format_bytes", "exec_time": 0.002 }
+{ "type": "test", "event": "failed", "name": "lockfile::tests::test_roundtrip", "stdout": "assertion failed" }"#;
+        let cases = parse_libtest_json(stdout);
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[0].classname, "util::tests");
+        assert_eq!(cases[1].status, TestStatus::Failed);
+        assert_eq!(cases[1].failure.as_deref(), Some("assertion failed"));
+    }
+
+    #[test]
+    fn test_test_executables() {
+        let messages = r#"{"reason":"compiler-artifact","target":{"kind":["lib"]},"profile":{"test":false},"executable":null,"manifest_path":"/ws/core/Cargo.toml"}
+{"reason":"compiler-artifact","target":{"kind":["lib"]},"profile":{"test":true},"executable":"/ws/target/debug/deps/core-1a2b","manifest_path":"/ws/core/Cargo.toml"}
+{"reason":"build-finished","success":true}"#;
+        assert_eq!(
+            test_executables(messages),
+            vec![(PathBuf::from("/ws/target/debug/deps/core-1a2b"), PathBuf::from("/ws/core"))]
+        );
+        assert!(has_library(messages));
+    }
+
+    #[test]
+    fn test_parse_libtest_plain() {
+        let stdout = "running 2 tests\ntest src/lib.rs - parse (line 12) ... ok\ntest src/lib.rs - Config::load (line 40) ... FAILED\n";
+        let cases = parse_libtest_plain(stdout);
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[0].classname, "src/lib.rs");
+        assert_eq!(cases[1].name, "Config::load (line 40)");
+        assert_eq!(cases[1].status, TestStatus::Failed);
+    }
+
+    #[test]
+    fn test_parse_junit_roundtrip() {
+        let xml = r#"<testsuites><testsuite name="Unit">
+<testcase name="it adds" classname="Tests\MathTest" time="0.01"/>
+<testcase name="it divides" classname="Tests\MathTest" time="0.02"><failure message="Division by zero">Division by zero &amp; more</failure></testcase>
+<testcase name="it skips" classname="Tests\MathTest"><skipped/></testcase>
+</testsuite></testsuites>"#;
+        let cases = parse_junit("phpunit", xml);
+        assert_eq!(cases.iter().map(|c| c.status).collect::<Vec<_>>(),
+            vec![TestStatus::Passed, TestStatus::Failed, TestStatus::Skipped]);
+        assert_eq!(cases[1].failure.as_deref(), Some("Division by zero & more"));
+
+        let results = vec![SuiteResult { suite: "phpunit".into(), cases, time: 0.03, error: None }];
+        let merged = parse_junit("phpunit", &to_junit(&results));
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[1].failure.as_deref(), Some("Division by zero & more"));
+    }
+}
@@ -0,0 +1,295 @@
+//! Per-workspace tool versions (`rcm env install|list|shell|direnv`)
+//!
+//! The versions a workspace needs are declared under `[tools]` in `rcm.toml`
+//! (or `"tools"` in `.rcm/workspace.json`):
+//!
+//! ```toml
+//! [tools]
+//! node = "20"
+//! php = "8.3"
+//! rust = "1.79"
+//! ```
+//!
+//! `rcm env install` installs missing versions (rustup; fnm, volta or nvm;
+//! phpenv, brew or apt) and pins them in `.rcm/toolchain.toml`, whose shims in
+//! `.rcm/bin` every spawned process already uses. `rcm env shell` and
+//! `rcm env direnv` put the same shims on PATH in an interactive shell.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+use tabled::{Table, Tabled};
+use crate::engines;
+use crate::members;
+use crate::npm;
+use crate::ppm;
+use crate::remote::shell_quote;
+use crate::tasks::TASKS_FILE;
+use crate::toolchain::{self, ToolchainPins};
+use crate::util;
+use crate::workspace::Workspace;
+
+/// Tools whose versions can be declared
+pub const TOOLS: &[&str] = &["node", "php", "rust"];
+
+/// File written by `rcm env direnv`
+const ENVRC: &str = ".envrc";
+
+/// The `.envrc` line that puts the shims on PATH
+const DIRENV_LINE: &str = "PATH_add .rcm/bin";
+
+#[derive(Debug, Default, Deserialize)]
+struct ToolsSection {
+    #[serde(default)]
+    tools: BTreeMap<String, String>,
+}
+
+fn from_section(section: ToolsSection) -> Result<BTreeMap<String, String>> {
+    for tool in section.tools.keys() {
+        if !TOOLS.contains(&tool.as_str()) {
+            return Err(anyhow!("Unknown tool '{}'; use one of {}", tool, TOOLS.join(", ")));
+        }
+    }
+    Ok(section.tools)
+}
+
+/// Tool versions declared for the workspace at `root`; `rcm.toml` wins over `.rcm/workspace.json`
+pub async fn load(root: &Path) -> Result<BTreeMap<String, String>> {
+    let mut tools = BTreeMap::new();
+    let manifest = root.join(members::MANIFEST);
+    if manifest.exists() {
+        let section: ToolsSection = serde_json::from_str(&tokio::fs::read_to_string(&manifest).await?)
+            .with_context(|| format!("Invalid tools in {}", manifest.display()))?;
+        tools.extend(from_section(section)?);
+    }
+    let toml_path = root.join(TASKS_FILE);
+    if toml_path.exists() {
+        let section: ToolsSection = toml::from_str(&tokio::fs::read_to_string(&toml_path).await?)
+            .with_context(|| format!("Invalid [tools] in {}", toml_path.display()))?;
+        tools.extend(from_section(section)?);
+    }
+    Ok(tools)
+}
+
+/// The pin that stands in for a tool's version
+fn pinned_binary(tool: &str) -> &str {
+    if tool == "rust" { "rustc" } else { tool }
+}
+
+/// Whether a pinned version satisfies a declared one (`20` covers `20.11.1`)
+pub fn satisfies(declared: &str, pinned: &str) -> bool {
+    let declared = declared.trim().trim_start_matches('v');
+    let pinned = pinned.trim().trim_start_matches('v');
+    pinned == declared || pinned.strip_prefix(declared).is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// The pinned version of `tool`, if any
+fn pinned_version(pins: &ToolchainPins, tool: &str) -> Option<String> {
+    pins.get(pinned_binary(tool)).map(|pin| pin.version.clone())
+}
+
+#[derive(Tabled)]
+struct ToolRow {
+    #[tabled(rename = "Tool")]
+    tool: String,
+    #[tabled(rename = "Declared")]
+    declared: String,
+    #[tabled(rename = "Pinned")]
+    pinned: String,
+    #[tabled(rename = "Status")]
+    status: String,
+}
+
+/// Show declared tools and what is pinned for them
+pub async fn list(workspace: &Workspace) -> Result<()> {
+    let root = workspace.root();
+    let tools = load(root).await?;
+    if tools.is_empty() {
+        println!("{}", style("No tool versions declared").yellow());
+        println!("Add them under [tools] in {}, e.g. node = \"20\"", root.join(TASKS_FILE).display());
+        return Ok(());
+    }
+    let pins = ToolchainPins::load(root).await?;
+    let rows: Vec<ToolRow> = tools.iter().map(|(tool, declared)| {
+        let pinned = pinned_version(&pins, tool);
+        let status = match &pinned {
+            Some(version) if satisfies(declared, version) => "✅ pinned",
+            Some(_) => "⚠️  other version",
+            None => "❌ not installed",
+        };
+        ToolRow {
+            tool: tool.clone(),
+            declared: declared.clone(),
+            pinned: pinned.unwrap_or_else(|| "-".to_string()),
+            status: status.to_string(),
+        }
+    }).collect();
+    println!("{}", Table::new(&rows));
+    Ok(())
+}
+
+/// Install Node.js `version` with the first available version manager
+async fn install_node(version: &str) -> Result<()> {
+    let mut cmd = if util::command_exists("fnm").await {
+        let mut c = Command::new("fnm");
+        c.args(["install", version]);
+        c
+    } else if util::command_exists("volta").await {
+        let mut c = Command::new("volta");
+        c.args(["install", &format!("node@{}", version)]);
+        c
+    } else {
+        // nvm is a shell function, so source it first
+        let nvm_dir = engines::nvm_dir()
+            .filter(|dir| dir.join("nvm.sh").exists())
+            .ok_or_else(|| anyhow!("No supported Node.js installer found (fnm, volta or nvm)"))?;
+        let script = format!(". {} && nvm install {}", shell_quote(&nvm_dir.join("nvm.sh").to_string_lossy()), shell_quote(version));
+        let mut c = Command::new("bash");
+        c.args(["-c", &script]);
+        c
+    };
+
+    println!("📦 Installing Node.js {}...", version);
+    util::execute_mutation(&mut cmd).await
+        .with_context(|| format!("Failed to install Node.js {}", version))?;
+    Ok(())
+}
+
+/// Install `tool` at `version` if needed and pin it for the workspace
+async fn install_tool(root: &Path, tool: &str, version: &str) -> Result<()> {
+    match tool {
+        "rust" => engines::switch_rust(root, version).await,
+        "php" => ppm::use_php(root, version, true).await,
+        "node" => {
+            let mut installed = false;
+            for (found, _) in engines::node_installs().await {
+                installed |= npm::satisfies_engine(version, &found)?;
+            }
+            if !installed {
+                install_node(version).await?;
+                if util::is_dry_run() {
+                    return Ok(());
+                }
+            }
+            engines::switch_node(root, version).await
+        }
+        other => Err(anyhow!("Unknown tool '{}'; use one of {}", other, TOOLS.join(", "))),
+    }
+}
+
+/// Install and pin every declared tool that isn't pinned at its version yet
+pub async fn install(workspace: &Workspace, only: &[String], force: bool) -> Result<()> {
+    let root = workspace.root();
+    let tools = load(root).await?;
+    for tool in only {
+        if !tools.contains_key(tool) {
+            return Err(anyhow!("No version declared for '{}' under [tools] in {}", tool, TASKS_FILE));
+        }
+    }
+    let pins = ToolchainPins::load(root).await?;
+    let mut failures = Vec::new();
+    for (tool, version) in tools.iter().filter(|(tool, _)| only.is_empty() || only.contains(tool)) {
+        let current = pinned_version(&pins, tool);
+        if !force && current.as_deref().is_some_and(|pinned| satisfies(version, pinned)) {
+            println!("{} {} {} already pinned", style("✓").green(), tool, version);
+            continue;
+        }
+        println!("{}", style(format!("🔧 {} {}", tool, version)).cyan().bold());
+        if let Err(e) = install_tool(root, tool, version).await {
+            println!("{} {}: {}", style("✗").red(), tool, e);
+            failures.push(tool.clone());
+        }
+    }
+    if !failures.is_empty() {
+        return Err(anyhow!("Failed to install {}", failures.join(", ")));
+    }
+    Ok(())
+}
+
+/// Shell syntax for `rcm env shell`
+fn shell_kind(requested: Option<&str>) -> String {
+    requested.map(str::to_string)
+        .or_else(|| {
+            std::env::var("SHELL").ok()
+                .and_then(|shell| Path::new(&shell).file_name().map(|n| n.to_string_lossy().into_owned()))
+        })
+        .unwrap_or_else(|| if cfg!(windows) { "powershell".to_string() } else { "sh".to_string() })
+}
+
+/// The line that puts `dir` in front of PATH in `shell`
+pub fn export_line(shell: &str, dir: &Path) -> Result<String> {
+    let dir = dir.to_string_lossy();
+    Ok(match shell {
+        "sh" | "bash" | "zsh" | "dash" | "ksh" => format!("export PATH={}:\"$PATH\"", shell_quote(&dir)),
+        "fish" => format!("set -gx PATH {} $PATH", shell_quote(&dir)),
+        "powershell" | "pwsh" => format!("$env:PATH = '{}' + [IO.Path]::PathSeparator + $env:PATH", dir.replace('\'', "''")),
+        other => return Err(anyhow!("Unsupported shell '{}'; use sh, bash, zsh, fish or powershell", other)),
+    })
+}
+
+/// Print the PATH change for `eval "$(rcm env shell)"`
+pub async fn shell(workspace: &Workspace, requested: Option<&str>) -> Result<()> {
+    let dir = toolchain::shim_dir(workspace.root());
+    if !dir.is_dir() {
+        eprintln!("{}", style("No tools pinned yet; run `rcm env install` first").yellow());
+    }
+    let dir = dir.canonicalize().unwrap_or(dir);
+    println!("{}", export_line(&shell_kind(requested), &dir)?);
+    Ok(())
+}
+
+/// Add the shim directory to the workspace's `.envrc`
+pub async fn direnv(workspace: &Workspace) -> Result<()> {
+    let path = workspace.root().join(ENVRC);
+    let current = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    if current.lines().any(|line| line.trim() == DIRENV_LINE) {
+        println!("{} {} already puts the pinned tools on PATH", style("✓").green(), path.display());
+        return Ok(());
+    }
+    let mut content = current;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(DIRENV_LINE);
+    content.push('\n');
+    util::write_file(&path, content).await
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("{}", style(format!("✅ Updated {}", path.display())).green());
+    println!("Run `direnv allow` to load it");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_satisfies() {
+        assert!(satisfies("20", "20.11.1"));
+        assert!(satisfies("1.79", "1.79"));
+        assert!(satisfies("8.3", "v8.3.4"));
+        assert!(!satisfies("20", "200.1.0"));
+        assert!(!satisfies("8.3", "8.2"));
+    }
+
+    #[test]
+    fn test_export_line() {
+        let dir = Path::new("/repo/.rcm/bin");
+        assert_eq!(export_line("zsh", dir).unwrap(), "export PATH=/repo/.rcm/bin:\"$PATH\"");
+        assert_eq!(export_line("fish", dir).unwrap(), "set -gx PATH /repo/.rcm/bin $PATH");
+        assert!(export_line("tcsh", dir).is_err());
+    }
+
+    #[test]
+    fn test_unknown_tool() {
+        let section: ToolsSection = toml::from_str("[tools]\nnode = \"20\"\nruby = \"3.3\"\n").unwrap();
+        assert!(from_section(section).is_err());
+    }
+}
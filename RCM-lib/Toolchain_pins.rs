@@ -0,0 +1,145 @@
+//! Toolchain pins for RCM
+//!
+//! Records per-workspace interpreter versions in `.rcm/toolchain.toml` and exposes
+//! them to spawned processes through shims in `.rcm/bin`
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use crate::util;
+
+/// Location of the pins file relative to the workspace root
+pub const TOOLCHAIN_FILE: &str = ".rcm/toolchain.toml";
+
+/// A pinned tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolPin {
+    /// Requested version (e.g. `8.3`)
+    pub version: String,
+    /// Interpreter the version resolved to
+    pub path: PathBuf,
+}
+
+/// Contents of `.rcm/toolchain.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolchainPins {
+    #[serde(default)]
+    pub tools: BTreeMap<String, ToolPin>,
+}
+
+impl ToolchainPins {
+    pub fn path(workspace_root: &Path) -> PathBuf {
+        workspace_root.join(TOOLCHAIN_FILE)
+    }
+
+    /// Load pins, returning an empty set when the file is missing
+    pub async fn load(workspace_root: &Path) -> Result<Self> {
+        let path = Self::path(workspace_root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path).await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub async fn save(&self, workspace_root: &Path) -> Result<()> {
+        let path = Self::path(workspace_root);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        util::write_file(&path, toml::to_string_pretty(self)?).await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn get(&self, tool: &str) -> Option<&ToolPin> {
+        self.tools.get(tool)
+    }
+
+    /// Pin a tool and (re)write its shim
+    pub async fn pin(&mut self, workspace_root: &Path, tool: &str, pin: ToolPin) -> Result<()> {
+        write_shim(workspace_root, tool, &pin.path).await?;
+        self.tools.insert(tool.to_string(), pin);
+        self.save(workspace_root).await
+    }
+}
+
+/// Directory holding shims for pinned tools
+pub fn shim_dir(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(".rcm").join("bin")
+}
+
+/// `PATH` with the shim directory first, if the workspace has pinned tools
+///
+/// Set this on spawned processes so `php`, `composer` (via its `env php` shebang)
+/// and LET actions pick up the pinned interpreters.
+pub fn shim_path(workspace_root: &Path) -> Option<OsString> {
+    let dir = shim_dir(workspace_root);
+    if !dir.is_dir() {
+        return None;
+    }
+    let current = std::env::var_os("PATH").unwrap_or_default();
+    let paths = std::iter::once(dir).chain(std::env::split_paths(&current));
+    std::env::join_paths(paths).ok()
+}
+
+async fn write_shim(workspace_root: &Path, tool: &str, target: &Path) -> Result<()> {
+    let dir = shim_dir(workspace_root);
+    if util::is_dry_run() {
+        println!("[dry-run] link {} -> {}", dir.join(tool).display(), target.display());
+        return Ok(());
+    }
+    fs::create_dir_all(&dir).await?;
+
+    #[cfg(unix)]
+    {
+        let link = dir.join(tool);
+        if fs::symlink_metadata(&link).await.is_ok() {
+            fs::remove_file(&link).await?;
+        }
+        fs::symlink(target, &link).await
+            .with_context(|| format!("Failed to create shim {}", link.display()))?;
+    }
+
+    #[cfg(windows)]
+    {
+        let shim = dir.join(format!("{}.cmd", tool));
+        fs::write(&shim, format!("@\"{}\" %*\r\n", target.display())).await
+            .with_context(|| format!("Failed to create shim {}", shim.display()))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pins_toml_roundtrip() {
+        let mut pins = ToolchainPins::default();
+        pins.tools.insert("php".to_string(), ToolPin {
+            version: "8.3".to_string(),
+            path: PathBuf::from("/usr/bin/php8.3"),
+        });
+        let text = toml::to_string_pretty(&pins).unwrap();
+        assert!(text.contains("[tools.php]"));
+        let parsed: ToolchainPins = toml::from_str(&text).unwrap();
+        assert_eq!(parsed.get("php"), pins.get("php"));
+    }
+
+    #[test]
+    fn test_shim_path_requires_shim_dir() {
+        let root = std::env::temp_dir().join(format!("rcm-toolchain-test-{}", std::process::id()));
+        assert!(shim_path(&root).is_none());
+
+        std::fs::create_dir_all(shim_dir(&root)).unwrap();
+        let path = shim_path(&root).unwrap();
+        assert_eq!(std::env::split_paths(&path).next(), Some(shim_dir(&root)));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
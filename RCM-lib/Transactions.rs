@@ -0,0 +1,411 @@
+//! Transactions for multi-manager operations
+//!
+//! Snapshots manifests and lockfiles before `rcm add` and `rcm ensure` run the
+//! package managers, and restores every snapshotted file when any of them fails.
+//! Each transaction is recorded in the `transactions` state collection, keyed
+//! by its id; logs written before that, in `.rcm/transactions/log.json`, are
+//! still read.
+
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tabled::{Table, Tabled};
+use tokio::fs;
+use crate::cache;
+use crate::config::Config;
+use crate::lockfile::LOCKFILE_NAME;
+use crate::storage;
+use crate::toolchain::TOOLCHAIN_FILE;
+use crate::util;
+use crate::workspace::Workspace;
+
+/// Directory holding the log and in-flight snapshots, relative to the workspace root
+pub const TRANSACTIONS_DIR: &str = ".rcm/transactions";
+
+/// Number of records kept in the log
+const LOG_LIMIT: usize = 200;
+
+/// State collection holding the log
+const COLLECTION: &str = "transactions";
+
+#[derive(Subcommand)]
+pub enum TransactionCommands {
+    /// Show past add/ensure operations and whether they were rolled back
+    Log {
+        /// Number of entries to show
+        #[arg(long, default_value = "20")]
+        limit: usize,
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    /// Started and never finished (the process was interrupted)
+    Pending,
+    Committed,
+    RolledBack,
+    /// Some files could not be restored; the snapshot is kept
+    RollbackFailed,
+}
+
+impl std::fmt::Display for TransactionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Self::Pending => "pending",
+            Self::Committed => "committed",
+            Self::RolledBack => "rolled back",
+            Self::RollbackFailed => "rollback failed",
+        };
+        f.write_str(label)
+    }
+}
+
+/// A file captured before the operation, relative to the workspace root
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSnapshot {
+    pub path: PathBuf,
+    /// Whether the file existed; files created by the operation are removed on rollback
+    pub existed: bool,
+    /// Whether the snapshot copy is zstd-compressed (`<path>.zst`)
+    #[serde(default)]
+    pub compressed: bool,
+}
+
+impl FileSnapshot {
+    /// Where the copy of this file lives inside a snapshot directory
+    fn stored_at(&self, snapshot_dir: &Path) -> PathBuf {
+        let path = snapshot_dir.join(&self.path);
+        if self.compressed {
+            let mut name = path.into_os_string();
+            name.push(".zst");
+            PathBuf::from(name)
+        } else {
+            path
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionRecord {
+    pub id: String,
+    pub operation: String,
+    pub managers: Vec<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub status: TransactionStatus,
+    pub files: Vec<FileSnapshot>,
+    pub error: Option<String>,
+}
+
+#[derive(Tabled)]
+struct TransactionRow {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Operation")]
+    operation: String,
+    #[tabled(rename = "Managers")]
+    managers: String,
+    #[tabled(rename = "Started")]
+    started_at: String,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "Files")]
+    files: usize,
+}
+
+/// Manifests and lockfiles a manager rewrites
+pub fn manager_files(manager: &str) -> &'static [&'static str] {
+    match manager {
+        "cargo" => &["Cargo.toml", "Cargo.lock"],
+        "npm" => &["package.json", "package-lock.json", "yarn.lock", "pnpm-lock.yaml", "bun.lockb"],
+        "composer" => &["composer.json", "composer.lock"],
+        "go" => &["go.mod", "go.sum"],
+        "bundler" => &["Gemfile", "Gemfile.lock"],
+        "container" => &["docker-compose.yml"],
+        // System packages live outside the workspace and are not rolled back
+        _ => &[],
+    }
+}
+
+/// Files to snapshot: the managers' files plus RCM's own workspace state
+async fn tracked_files(root: &Path, managers: &[String]) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = managers
+        .iter()
+        .flat_map(|m| manager_files(m).iter().map(PathBuf::from))
+        .collect();
+    files.push(PathBuf::from(LOCKFILE_NAME));
+    files.push(PathBuf::from(TOOLCHAIN_FILE));
+
+    // Top-level files in .rcm hold the workspace manifest and config
+    let rcm_dir = root.join(".rcm");
+    if rcm_dir.is_dir() {
+        let mut entries = fs::read_dir(&rcm_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let is_state = matches!(path.extension().and_then(|e| e.to_str()), Some("toml" | "json"));
+            if path.is_file() && is_state {
+                if let Ok(relative) = path.strip_prefix(root) {
+                    files.push(relative.to_path_buf());
+                }
+            }
+        }
+    }
+
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// An in-flight operation that can be committed or rolled back
+pub struct Transaction {
+    root: PathBuf,
+    snapshot_dir: PathBuf,
+    record: TransactionRecord,
+    /// Dry runs change nothing, so nothing is snapshotted or logged
+    persist: bool,
+}
+
+impl Transaction {
+    /// Snapshot the files `managers` may touch and log the operation as pending
+    pub async fn begin(root: &Path, operation: &str, managers: &[String]) -> Result<Self> {
+        let started = chrono::Utc::now();
+        let id = format!("{}-{}", started.format("%Y%m%d%H%M%S"), &uuid::Uuid::new_v4().simple().to_string()[..8]);
+        let snapshot_dir = root.join(TRANSACTIONS_DIR).join(&id);
+        let persist = !util::is_dry_run();
+
+        let mut files = Vec::new();
+        if persist {
+            let cache_config = Config::load(None).await.unwrap_or_default().cache;
+            for relative in tracked_files(root, managers).await? {
+                let source = root.join(&relative);
+                let existed = source.is_file();
+                let file = FileSnapshot { path: relative, existed, compressed: cache_config.compress };
+                if existed {
+                    let target = file.stored_at(&snapshot_dir);
+                    if let Some(parent) = target.parent() {
+                        fs::create_dir_all(parent).await?;
+                    }
+                    let copied = if file.compressed {
+                        cache::compress_file(&source, &target, cache_config.compression_level).map(|_| ())
+                    } else {
+                        fs::copy(&source, &target).await.map(|_| ()).map_err(Into::into)
+                    };
+                    copied.with_context(|| format!("Failed to snapshot {}", file.path.display()))?;
+                }
+                files.push(file);
+            }
+        }
+
+        let transaction = Self {
+            root: root.to_path_buf(),
+            snapshot_dir,
+            record: TransactionRecord {
+                id,
+                operation: operation.to_string(),
+                managers: managers.to_vec(),
+                started_at: started.to_rfc3339(),
+                finished_at: None,
+                status: TransactionStatus::Pending,
+                files,
+                error: None,
+            },
+            persist,
+        };
+        transaction.write_log().await?;
+        Ok(transaction)
+    }
+
+    /// Commit on success, roll back on failure, and pass the result through
+    pub async fn finish<T>(self, result: Result<T>) -> Result<T> {
+        match result {
+            Ok(value) => {
+                self.commit().await?;
+                Ok(value)
+            }
+            Err(e) => {
+                let snapshot_dir = self.snapshot_dir.clone();
+                match self.rollback(&e).await {
+                    Ok(()) => Err(e),
+                    Err(rollback_error) => Err(e.context(format!(
+                        "rollback incomplete ({}); snapshot kept in {}",
+                        rollback_error, snapshot_dir.display()
+                    ))),
+                }
+            }
+        }
+    }
+
+    /// Mark the operation as committed and drop its snapshot
+    pub async fn commit(mut self) -> Result<()> {
+        self.record.status = TransactionStatus::Committed;
+        self.record.finished_at = Some(chrono::Utc::now().to_rfc3339());
+        self.write_log().await?;
+        self.remove_snapshot().await
+    }
+
+    /// Restore every snapshotted file and remove files the operation created
+    pub async fn rollback(mut self, error: &anyhow::Error) -> Result<()> {
+        self.record.error = Some(error.to_string());
+        self.record.finished_at = Some(chrono::Utc::now().to_rfc3339());
+        if !self.persist {
+            return Ok(());
+        }
+
+        println!("{}", style(format!("↩️  Operation failed, restoring {} file(s)...", self.record.files.len())).yellow());
+        let mut failures = Vec::new();
+        for file in &self.record.files {
+            let target = self.root.join(&file.path);
+            let restored = if file.existed && file.compressed {
+                cache::decompress_file(&file.stored_at(&self.snapshot_dir), &target)
+            } else if file.existed {
+                fs::copy(file.stored_at(&self.snapshot_dir), &target).await.map(|_| ()).map_err(Into::into)
+            } else if target.exists() {
+                fs::remove_file(&target).await.map_err(Into::into)
+            } else {
+                Ok(())
+            };
+            if let Err(e) = restored {
+                failures.push(format!("{}: {}", file.path.display(), e));
+            }
+        }
+
+        if failures.is_empty() {
+            self.record.status = TransactionStatus::RolledBack;
+            self.write_log().await?;
+            self.remove_snapshot().await?;
+            println!("{}", style(format!("↩️  Rolled back transaction {}", self.record.id)).yellow().bold());
+            Ok(())
+        } else {
+            self.record.status = TransactionStatus::RollbackFailed;
+            self.write_log().await?;
+            Err(anyhow::anyhow!("could not restore {}", failures.join(", ")))
+        }
+    }
+
+    async fn remove_snapshot(&self) -> Result<()> {
+        if self.snapshot_dir.exists() {
+            fs::remove_dir_all(&self.snapshot_dir).await
+                .with_context(|| format!("Failed to remove {}", self.snapshot_dir.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Insert or update this transaction's record in the log
+    async fn write_log(&self) -> Result<()> {
+        if !self.persist {
+            return Ok(());
+        }
+        let record = self.record.clone();
+        storage::with_store(&self.root, move |store| {
+            store.put_as(COLLECTION, &record.id, &record)?;
+            // Ids start with the start time, so key order is oldest first
+            let ids: Vec<String> = store.list(COLLECTION)?.into_iter().map(|(id, _)| id).collect();
+            for id in ids.iter().take(ids.len().saturating_sub(LOG_LIMIT)) {
+                store.remove(COLLECTION, id)?;
+            }
+            Ok(())
+        })
+        .await
+        .context("Failed to record transaction")
+    }
+}
+
+fn legacy_log_path(root: &Path) -> PathBuf {
+    root.join(TRANSACTIONS_DIR).join("log.json")
+}
+
+/// Transaction records, oldest first
+pub async fn load_log(root: &Path) -> Result<Vec<TransactionRecord>> {
+    let mut records: Vec<TransactionRecord> = storage::with_store(root, |store| store.list_as(COLLECTION))
+        .await?
+        .into_values()
+        .collect();
+    let legacy = legacy_log_path(root);
+    if legacy.exists() {
+        let content = fs::read_to_string(&legacy).await
+            .with_context(|| format!("Failed to read {}", legacy.display()))?;
+        let old: Vec<TransactionRecord> = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", legacy.display()))?;
+        records.extend(old.into_iter().filter(|r| !records.iter().any(|n| n.id == r.id)).collect::<Vec<_>>());
+        records.sort_by(|a, b| a.id.cmp(&b.id));
+    }
+    Ok(records)
+}
+
+/// Handle transaction commands
+pub async fn handle_command(workspace: &Workspace, cmd: TransactionCommands) -> Result<()> {
+    match cmd {
+        TransactionCommands::Log { limit, format } => {
+            let records = load_log(workspace.root()).await?;
+            let recent: Vec<&TransactionRecord> = records.iter().rev().take(limit).collect();
+
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&recent)?),
+                "table" => {
+                    if recent.is_empty() {
+                        println!("{}", style("No transactions recorded yet").dim());
+                        return Ok(());
+                    }
+                    let rows: Vec<TransactionRow> = recent
+                        .iter()
+                        .map(|r| TransactionRow {
+                            id: r.id.clone(),
+                            operation: r.operation.clone(),
+                            managers: r.managers.join(", "),
+                            started_at: r.started_at.clone(),
+                            status: r.status.to_string(),
+                            files: r.files.len(),
+                        })
+                        .collect();
+                    println!("{}", Table::new(rows));
+
+                    for record in recent.iter().filter(|r| r.error.is_some()) {
+                        println!("  {} {}: {}", style("✗").red(), record.id, record.error.as_deref().unwrap_or_default());
+                    }
+                }
+                _ => return Err(anyhow::anyhow!("Unsupported format: {}. Use table or json", format)),
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manager_files() {
+        assert!(manager_files("npm").contains(&"bun.lockb"));
+        assert_eq!(manager_files("composer"), &["composer.json", "composer.lock"]);
+        assert!(manager_files("system").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_step_rolls_back_earlier_steps() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+        let managers = vec!["cargo".to_string(), "npm".to_string()];
+        let transaction = Transaction::begin(root, "ensure", &managers).await.unwrap();
+
+        // cargo succeeds and rewrites its manifest, npm creates a lockfile and then fails
+        std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"changed\"\n").unwrap();
+        std::fs::write(root.join("package-lock.json"), "{}").unwrap();
+        let result: Result<()> = transaction.finish(Err(anyhow::anyhow!("npm install failed"))).await;
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(root.join("Cargo.toml")).unwrap(), "[package]\nname = \"app\"\n");
+        assert!(!root.join("package-lock.json").exists());
+        let log = load_log(root).await.unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].status, TransactionStatus::RolledBack);
+        assert!(!root.join(TRANSACTIONS_DIR).join(&log[0].id).exists());
+    }
+}
@@ -0,0 +1,474 @@
+//! Update command implementation
+//!
+//! `rcm update` runs the workspace update engine. With `--advise` it instead
+//! collects the release notes of every pending upgrade, asks a locally served
+//! GPT model (see `rcm gpt serve`) for a risk assessment, and prints a
+//! suggested upgrade order. Without a running model the order falls back to
+//! semver heuristics.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tabled::{Table, Tabled};
+use crate::cache::CacheStore;
+use crate::commands::letcond::parse_loose_version;
+use crate::commands::outdated::{self, OutdatedDependency};
+use crate::commands::workspace::update_packages;
+use crate::config::Config;
+use crate::model_client;
+use crate::npm::{NpmManager, NpmManagerType};
+use crate::workspace::Workspace;
+
+/// Release notes kept per release and per package, so prompts fit small context windows
+const NOTE_CHARS: usize = 1500;
+const PACKAGE_NOTE_CHARS: usize = 4000;
+
+/// Tokens the model may spend on its answer
+const ADVICE_MAX_TOKENS: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Risk {
+    Low,
+    Medium,
+    High,
+}
+
+impl Risk {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+/// Notes for one release between the current and latest version
+#[derive(Debug, Clone, Serialize)]
+pub struct ReleaseNote {
+    pub version: String,
+    pub body: String,
+}
+
+/// An outdated dependency with the release notes of the versions it would skip
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingUpgrade {
+    pub manager: String,
+    pub name: String,
+    pub current: String,
+    pub latest: String,
+    pub notes: Vec<ReleaseNote>,
+}
+
+/// One step of the suggested order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdvisedUpgrade {
+    pub package: String,
+    pub risk: Risk,
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// The model's (or the heuristic) assessment
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Advice {
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub order: Vec<AdvisedUpgrade>,
+    /// Model that produced the advice, if any
+    #[serde(default, skip_deserializing)]
+    pub model: Option<String>,
+}
+
+#[derive(Tabled)]
+struct AdviceRow {
+    #[tabled(rename = "#")]
+    step: usize,
+    #[tabled(rename = "Package")]
+    package: String,
+    #[tabled(rename = "Upgrade")]
+    upgrade: String,
+    #[tabled(rename = "Risk")]
+    risk: String,
+    #[tabled(rename = "Reason")]
+    reason: String,
+}
+
+/// Owner and repository of a GitHub URL in any of the forms registries use
+pub fn github_repo(url: &str) -> Option<(String, String)> {
+    let rest = url
+        .trim()
+        .trim_start_matches("git+")
+        .split_once("github.com")
+        .map(|(_, rest)| rest)
+        .or_else(|| url.strip_prefix("github:"))?;
+    let mut parts = rest.trim_start_matches([':', '/']).split('/').filter(|p| !p.is_empty());
+    let owner = parts.next()?.to_string();
+    let repo = parts.next()?.trim_end_matches(".git").to_string();
+    Some((owner, repo))
+}
+
+/// Semver-based risk when the model gives none: majors and flagged breaking changes are high
+pub fn heuristic_risk(current: &str, latest: &str, notes: &[ReleaseNote]) -> Risk {
+    let breaking = notes.iter().any(|n| {
+        let body = n.body.to_lowercase();
+        body.contains("breaking") || body.contains("backwards incompatible") || body.contains("removed support")
+    });
+    let (Some(current), Some(latest)) = (parse_loose_version(current), parse_loose_version(latest)) else {
+        return Risk::Medium;
+    };
+    // 0.x minors are breaking by semver convention
+    let major = latest.major > current.major || (current.major == 0 && latest.minor > current.minor);
+    if major || breaking {
+        Risk::High
+    } else if latest.minor > current.minor {
+        Risk::Medium
+    } else {
+        Risk::Low
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.len() <= max {
+        return text.to_string();
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…", &text[..end])
+}
+
+/// Prompt asking for a JSON risk assessment of the pending upgrades
+pub fn build_prompt(upgrades: &[PendingUpgrade]) -> String {
+    let mut prompt = String::from(
+        "You are reviewing dependency upgrades for a software project. For each upgrade below, \
+         read the release notes, judge the risk of breaking the project (low, medium or high), \
+         and suggest an order that applies low-risk upgrades first and groups related packages.\n\
+         Reply with JSON only, in this shape:\n\
+         {\"summary\": \"<2-4 sentences>\", \"order\": [{\"package\": \"<name>\", \"risk\": \"low|medium|high\", \"reason\": \"<one sentence>\"}]}\n\n",
+    );
+    for upgrade in upgrades {
+        prompt.push_str(&format!(
+            "## {} ({}) {} -> {} [semver risk: {}]\n",
+            upgrade.name,
+            upgrade.manager,
+            upgrade.current,
+            upgrade.latest,
+            heuristic_risk(&upgrade.current, &upgrade.latest, &upgrade.notes).label()
+        ));
+        if upgrade.notes.is_empty() {
+            prompt.push_str("No release notes found.\n");
+        }
+        let mut budget = PACKAGE_NOTE_CHARS;
+        for note in &upgrade.notes {
+            if budget == 0 {
+                break;
+            }
+            let body = truncate(note.body.trim(), NOTE_CHARS.min(budget));
+            budget = budget.saturating_sub(body.len());
+            prompt.push_str(&format!("### {}\n{}\n", note.version, body));
+        }
+        prompt.push('\n');
+    }
+    prompt
+}
+
+/// Parse the model's reply, filling in packages it skipped with the heuristic
+pub fn parse_advice(reply: &str, upgrades: &[PendingUpgrade]) -> Option<Advice> {
+    let start = reply.find('{')?;
+    let end = reply.rfind('}')?;
+    let mut advice: Advice = serde_json::from_str(reply.get(start..=end)?).ok()?;
+
+    advice.order.retain(|step| upgrades.iter().any(|u| u.name == step.package));
+    for upgrade in upgrades {
+        if !advice.order.iter().any(|step| step.package == upgrade.name) {
+            advice.order.push(AdvisedUpgrade {
+                package: upgrade.name.clone(),
+                risk: heuristic_risk(&upgrade.current, &upgrade.latest, &upgrade.notes),
+                reason: "Not ranked by the model; semver heuristic".to_string(),
+            });
+        }
+    }
+    Some(advice)
+}
+
+/// Advice from semver alone: lowest risk first
+pub fn heuristic_advice(upgrades: &[PendingUpgrade]) -> Advice {
+    let mut order: Vec<AdvisedUpgrade> = upgrades
+        .iter()
+        .map(|u| {
+            let risk = heuristic_risk(&u.current, &u.latest, &u.notes);
+            let reason = match risk {
+                Risk::High => "Major version or breaking changes in the release notes",
+                Risk::Medium => "Minor version with new features",
+                Risk::Low => "Patch release",
+            };
+            AdvisedUpgrade { package: u.name.clone(), risk, reason: reason.to_string() }
+        })
+        .collect();
+    order.sort_by_key(|step| step.risk);
+    Advice {
+        summary: format!("{} pending upgrades ranked by semver; no model was consulted.", upgrades.len()),
+        order,
+        model: None,
+    }
+}
+
+/// Source repository URL of a dependency
+async fn repository_url(workspace: &Workspace, cache: &CacheStore, dep: &OutdatedDependency) -> Option<String> {
+    match dep.manager.as_str() {
+        "npm" => NpmManager::new(workspace.root(), NpmManagerType::Npm)
+            .info(&dep.name)
+            .await
+            .ok()?
+            .repository,
+        "composer" => {
+            let key = format!("packagist:{}", dep.name);
+            let json: Value = match cache.get_json(&key).await {
+                Some(json) => json,
+                None => {
+                    let url = format!("https://repo.packagist.org/p2/{}.json", dep.name);
+                    let json: Value = reqwest::get(&url).await.ok()?.json().await.ok()?;
+                    if let Err(e) = cache.put_json(&key, &json).await {
+                        tracing::warn!("{}", e);
+                    }
+                    json
+                }
+            };
+            json.pointer(&format!("/packages/{}/0/source/url", dep.name.replace('/', "~1")))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        }
+        _ => None,
+    }
+}
+
+/// GitHub release notes for versions after `current` up to `latest`
+async fn release_notes(client: &reqwest::Client, cache: &CacheStore, repo_url: &str, current: &str, latest: &str) -> Result<Vec<ReleaseNote>> {
+    let Some((owner, repo)) = github_repo(repo_url) else {
+        return Ok(Vec::new());
+    };
+    let (Some(from), Some(to)) = (parse_loose_version(current), parse_loose_version(latest)) else {
+        return Ok(Vec::new());
+    };
+    let key = format!("github-releases:{}/{}", owner, repo);
+    let releases: Vec<Value> = match cache.get_json(&key).await {
+        Some(releases) => releases,
+        None => {
+            let releases = fetch_releases(client, &owner, &repo).await?;
+            if let Err(e) = cache.put_json(&key, &releases).await {
+                tracing::warn!("{}", e);
+            }
+            releases
+        }
+    };
+
+    let mut notes: Vec<(semver::Version, ReleaseNote)> = releases
+        .iter()
+        .filter_map(|release| {
+            let tag = release.get("tag_name").and_then(Value::as_str)?;
+            let version = parse_loose_version(tag)?;
+            (version > from && version <= to).then(|| {
+                let body = release.get("body").and_then(Value::as_str).unwrap_or_default();
+                (version, ReleaseNote { version: tag.to_string(), body: body.to_string() })
+            })
+        })
+        .collect();
+    // Newest first so the budget keeps the most relevant notes
+    notes.sort_by(|a, b| b.0.cmp(&a.0));
+    Ok(notes.into_iter().map(|(_, note)| note).collect())
+}
+
+async fn fetch_releases(client: &reqwest::Client, owner: &str, repo: &str) -> Result<Vec<Value>> {
+    let mut request = client
+        .get(format!("https://api.github.com/repos/{}/{}/releases?per_page=50", owner, repo))
+        .header("User-Agent", "rcm")
+        .header("Accept", "application/vnd.github+json");
+    if let Ok(token) = std::env::var("GITHUB_TOKEN") {
+        request = request.bearer_auth(token);
+    } else if let Some(credential) = crate::auth::credential(&crate::config::Config::load(None).await.unwrap_or_default(), "github").await? {
+        request = credential.apply(request);
+    }
+    let response = request.send().await.context("Failed to fetch release notes")?;
+    if !response.status().is_success() {
+        return Err(anyhow!("GitHub releases for {}/{} returned {}", owner, repo, response.status()));
+    }
+    Ok(response.json().await?)
+}
+
+/// Gather pending upgrades and their release notes
+pub async fn pending_upgrades(workspace: &Workspace, managers: &[String]) -> Result<Vec<PendingUpgrade>> {
+    let client = reqwest::Client::new();
+    // Registry and release metadata is small JSON; the cache keeps repeat runs offline
+    let cache = CacheStore::open(&Config::load(None).await?).await;
+    let mut upgrades = Vec::new();
+    for dep in outdated::collect(workspace, managers).await? {
+        let notes = match repository_url(workspace, &cache, &dep).await {
+            Some(url) => release_notes(&client, &cache, &url, &dep.current, &dep.latest).await.unwrap_or_else(|e| {
+                tracing::warn!("{}", e);
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+        upgrades.push(PendingUpgrade {
+            manager: dep.manager,
+            name: dep.name,
+            current: dep.current,
+            latest: dep.latest,
+            notes,
+        });
+    }
+    Ok(upgrades)
+}
+
+/// Assess pending upgrades with the model, falling back to the heuristic
+pub async fn advise(workspace: &Workspace, managers: &[String], model: Option<&str>, format: &str) -> Result<()> {
+    if format == "table" {
+        println!("{}", style("🔎 Collecting pending upgrades and release notes...").cyan().bold());
+    }
+    let upgrades = pending_upgrades(workspace, managers).await?;
+    if upgrades.is_empty() {
+        if format == "table" {
+            println!("{}", style("✅ All dependencies are up to date").green().bold());
+        }
+        return Ok(());
+    }
+
+    let advice = match model_client::find_model(workspace.root(), model).await? {
+        Some(model) => {
+            if format == "table" {
+                println!("{}", style(format!("🤖 Asking {} for a risk assessment...", model.name)).blue());
+            }
+            let reply = model_client::complete(&model, &build_prompt(&upgrades), ADVICE_MAX_TOKENS).await?;
+            match parse_advice(&reply, &upgrades) {
+                Some(advice) => Advice { model: Some(model.name), ..advice },
+                None => {
+                    tracing::warn!("Model reply was not valid JSON; using the semver heuristic");
+                    heuristic_advice(&upgrades)
+                }
+            }
+        }
+        None => {
+            if format == "table" {
+                println!("{}", style("ℹ️  No running GPT model; ranking by semver only (start one with 'rcm gpt serve')").dim());
+            }
+            heuristic_advice(&upgrades)
+        }
+    };
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "upgrades": upgrades.iter().map(|u| serde_json::json!({
+                "manager": u.manager, "name": u.name, "current": u.current, "latest": u.latest,
+                "release_notes": u.notes.len(),
+            })).collect::<Vec<_>>(),
+            "advice": advice,
+        }))?),
+        "table" => {
+            let rows: Vec<AdviceRow> = advice
+                .order
+                .iter()
+                .enumerate()
+                .filter_map(|(i, step)| {
+                    let upgrade = upgrades.iter().find(|u| u.name == step.package)?;
+                    let risk = match step.risk {
+                        Risk::High => style(step.risk.label()).red().to_string(),
+                        Risk::Medium => style(step.risk.label()).yellow().to_string(),
+                        Risk::Low => style(step.risk.label()).green().to_string(),
+                    };
+                    Some(AdviceRow {
+                        step: i + 1,
+                        package: format!("{} ({})", upgrade.name, upgrade.manager),
+                        upgrade: format!("{} → {}", upgrade.current, upgrade.latest),
+                        risk,
+                        reason: step.reason.clone(),
+                    })
+                })
+                .collect();
+            println!();
+            println!("{}", Table::new(rows));
+            if !advice.summary.is_empty() {
+                println!();
+                println!("{}", style("📝 Summary").bold());
+                println!("{}", advice.summary.trim());
+            }
+            println!();
+            println!("Run {} to apply the upgrades", style("rcm update").cyan());
+        }
+        _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+    }
+    Ok(())
+}
+
+/// Update dependencies, or advise on the pending upgrades with `--advise`
+pub async fn run(
+    workspace: &Workspace,
+    managers: Option<Vec<String>>,
+    advise_only: bool,
+    model: Option<String>,
+    format: &str,
+) -> Result<()> {
+    let managers = managers.unwrap_or_else(|| workspace.enabled_managers());
+    if managers.is_empty() {
+        return Err(anyhow!("No package managers enabled. Run 'rcm init' to configure managers."));
+    }
+    if advise_only {
+        advise(workspace, &managers, model.as_deref(), format).await
+    } else {
+        update_packages(workspace, &managers).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upgrade(name: &str, current: &str, latest: &str, note: &str) -> PendingUpgrade {
+        PendingUpgrade {
+            manager: "npm".to_string(),
+            name: name.to_string(),
+            current: current.to_string(),
+            latest: latest.to_string(),
+            notes: vec![ReleaseNote { version: latest.to_string(), body: note.to_string() }],
+        }
+    }
+
+    #[test]
+    fn test_heuristic_risk() {
+        assert_eq!(heuristic_risk("4.17.20", "4.17.21", &[]), Risk::Low);
+        assert_eq!(heuristic_risk("18.2.0", "18.3.1", &[]), Risk::Medium);
+        assert_eq!(heuristic_risk("18.2.0", "19.0.0", &[]), Risk::High);
+        assert_eq!(heuristic_risk("0.3.1", "0.4.0", &[]), Risk::High);
+        let notes = [ReleaseNote { version: "v2.1.0".into(), body: "BREAKING: drop Node 16".into() }];
+        assert_eq!(heuristic_risk("2.0.0", "2.1.0", &notes), Risk::High);
+
+        assert_eq!(github_repo("git+https://github.com/lodash/lodash.git"), Some(("lodash".into(), "lodash".into())));
+        assert_eq!(github_repo("git@github.com:symfony/http-kernel.git"), Some(("symfony".into(), "http-kernel".into())));
+        assert_eq!(github_repo("https://gitlab.com/a/b"), None);
+    }
+
+    #[test]
+    fn test_parse_advice() {
+        let upgrades = vec![
+            upgrade("react", "18.2.0", "19.0.0", "Removed legacy APIs"),
+            upgrade("lodash", "4.17.20", "4.17.21", "Security fix"),
+        ];
+        let prompt = build_prompt(&upgrades);
+        assert!(prompt.contains("## react (npm) 18.2.0 -> 19.0.0 [semver risk: high]"));
+
+        let reply = r#"Here you go: {"summary": "Upgrade lodash first.", "order": [
+            {"package": "lodash", "risk": "low", "reason": "Patch"},
+            {"package": "left-pad", "risk": "low", "reason": "Not pending"}
+        ]}"#;
+        let advice = parse_advice(reply, &upgrades).unwrap();
+        let order: Vec<(&str, Risk)> = advice.order.iter().map(|s| (s.package.as_str(), s.risk)).collect();
+        assert_eq!(order, vec![("lodash", Risk::Low), ("react", Risk::High)]);
+        assert!(parse_advice("no json here", &upgrades).is_none());
+
+        assert_eq!(heuristic_advice(&upgrades).order[0].package, "lodash");
+    }
+}
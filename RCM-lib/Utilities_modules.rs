@@ -8,9 +8,13 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::fs;
 use tokio::process::Command as AsyncCommand;
 use walkdir::WalkDir;
+use crate::redact::redact;
+use crate::telemetry;
+use tracing::Instrument;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OsInfo {
@@ -29,6 +33,292 @@ pub struct CommandResult {
     pub duration_ms: u64,
 }
 
+/// Set by the global `--dry-run` flag
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable dry-run mode for this process
+pub fn set_dry_run(enabled: bool) {
+    DRY_RUN.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether mutating commands and file writes should only be printed
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// Set by the global `--read-only` flag
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable read-only mode for this process
+pub fn set_read_only(enabled: bool) {
+    READ_ONLY.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether every filesystem and system mutation must be refused
+pub fn is_read_only() -> bool {
+    READ_ONLY.load(Ordering::Relaxed)
+}
+
+/// Fail with a clear message when read-only mode forbids `action`
+pub fn ensure_writable(action: impl std::fmt::Display) -> Result<()> {
+    if is_read_only() {
+        return Err(anyhow!("Refusing to {} in read-only mode", action));
+    }
+    Ok(())
+}
+
+/// A path the current user cannot write to, and how to fix it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PermissionIssue {
+    /// The path closest to the target that exists
+    pub path: PathBuf,
+    pub problem: String,
+    pub fix: String,
+}
+
+/// Why the current user could not create or write `path`, if they couldn't
+pub fn diagnose_write_access(path: &Path) -> Option<PermissionIssue> {
+    let existing = path.ancestors().find(|p| p.exists())?;
+    let metadata = std::fs::metadata(existing).ok()?;
+    if existing != path && !metadata.is_dir() {
+        return Some(PermissionIssue {
+            path: existing.to_path_buf(),
+            problem: format!("{} is a file, so {} cannot be created under it", existing.display(), path.display()),
+            fix: format!("Move {} aside or point RCM at another directory", existing.display()),
+        });
+    }
+    access_issue(existing, &metadata)
+}
+
+#[cfg(unix)]
+fn access_issue(path: &Path, metadata: &std::fs::Metadata) -> Option<PermissionIssue> {
+    use std::os::unix::fs::MetadataExt;
+    
+    let (uid, gids) = current_ids()?;
+    let mode = metadata.mode() & 0o777;
+    if !write_allowed(uid, &gids, metadata.uid(), metadata.gid(), mode) {
+        let problem = format!(
+            "{} is owned by uid {} with mode {:o}; you are uid {}",
+            path.display(), metadata.uid(), mode, uid
+        );
+        let fix = if metadata.uid() == uid {
+            format!("chmod u+w {}", path.display())
+        } else if dirs::home_dir().map_or(false, |home| path.starts_with(home)) {
+            format!("sudo chown -R $(id -u) {}", path.display())
+        } else {
+            "Re-run with sudo, or move RCM's directories under your home (`rcm config set cache.directory ~/.cache/rcm`)".to_string()
+        };
+        return Some(PermissionIssue { path: path.to_path_buf(), problem, fix });
+    }
+    None
+}
+
+#[cfg(not(unix))]
+fn access_issue(path: &Path, metadata: &std::fs::Metadata) -> Option<PermissionIssue> {
+    if !metadata.permissions().readonly() {
+        return None;
+    }
+    Some(PermissionIssue {
+        path: path.to_path_buf(),
+        problem: format!("{} is marked read-only", path.display()),
+        fix: format!("attrib -R {}, or run from an elevated prompt", path.display()),
+    })
+}
+
+/// The effective uid and group ids of this process, as reported by `id`
+#[cfg(unix)]
+fn current_ids() -> Option<(u32, Vec<u32>)> {
+    let id = |flag: &str| -> Option<String> {
+        let output = Command::new("id").arg(flag).output().ok()?;
+        output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    let uid = id("-u")?.parse().ok()?;
+    let gids = id("-G")?.split_whitespace().filter_map(|g| g.parse().ok()).collect();
+    Some((uid, gids))
+}
+
+/// Whether the owner, group or other write bit applies to this user
+fn write_allowed(uid: u32, gids: &[u32], owner: u32, group: u32, mode: u32) -> bool {
+    if uid == 0 {
+        return true;
+    }
+    if uid == owner {
+        return mode & 0o200 != 0;
+    }
+    if gids.contains(&group) {
+        return mode & 0o020 != 0;
+    }
+    mode & 0o002 != 0
+}
+
+/// Turn an I/O error into one that says which path is inaccessible and how to fix it
+pub fn io_error(error: std::io::Error, action: &str, path: &Path) -> anyhow::Error {
+    if error.kind() != std::io::ErrorKind::PermissionDenied {
+        return anyhow::Error::new(error).context(format!("Failed to {} {}", action, path.display()));
+    }
+    match diagnose_write_access(path) {
+        Some(issue) => anyhow!(
+            "Permission denied trying to {} {}: {}\n  Fix: {}",
+            action, path.display(), issue.problem, issue.fix
+        ),
+        None => anyhow!("Permission denied trying to {} {}", action, path.display()),
+    }
+}
+
+/// A suggestion for command output that shows missing privileges
+pub fn privilege_hint(stderr: &str) -> Option<&'static str> {
+    let lower = stderr.to_lowercase();
+    if lower.contains("are you root") || lower.contains("must be run as root") || lower.contains("requires root") {
+        Some("This command needs root; re-run with sudo")
+    } else if lower.contains("permission denied") || lower.contains("eacces") || lower.contains("operation not permitted") {
+        Some("A path or privilege is missing; run `rcm doctor` to see which directories are not writable")
+    } else {
+        None
+    }
+}
+
+/// Render a command line (with working directory) for display
+pub fn describe_command(cmd: &Command) -> String {
+    let mut line = cmd.get_program().to_string_lossy().to_string();
+    for arg in cmd.get_args() {
+        let arg = arg.to_string_lossy();
+        if arg.is_empty() || arg.contains(char::is_whitespace) {
+            line.push_str(&format!(" '{}'", arg));
+        } else {
+            line.push(' ');
+            line.push_str(&arg);
+        }
+    }
+    if let Some(dir) = cmd.get_current_dir() {
+        line.push_str(&format!("  (in {})", dir.display()));
+    }
+    redact(&line)
+}
+
+/// Print a command instead of running it when in dry-run mode
+///
+/// Returns `true` if the caller should skip execution.
+pub fn skip_in_dry_run(cmd: &Command) -> bool {
+    if is_dry_run() {
+        println!("[dry-run] {}", describe_command(cmd));
+        return true;
+    }
+    false
+}
+
+/// Execute a command that changes the system; in dry-run mode only print it
+pub async fn execute_mutation(cmd: &mut Command) -> Result<CommandResult> {
+    ensure_writable(format_args!("run `{}`", describe_command(cmd)))?;
+    if skip_in_dry_run(cmd) {
+        return Ok(CommandResult {
+            success: true,
+            exit_code: 0,
+            stdout: String::new(),
+            stderr: String::new(),
+            duration_ms: 0,
+        });
+    }
+    execute_command(cmd).await
+}
+
+/// Run a command with inherited stdio so its output streams through; honours dry-run and read-only
+pub async fn execute_streaming(cmd: Command) -> Result<()> {
+    let described = describe_command(&cmd);
+    ensure_writable(format_args!("run `{}`", described))?;
+    if skip_in_dry_run(&cmd) {
+        return Ok(());
+    }
+    tracing::debug!("Executing: {}", described);
+    let span = telemetry::command_span(&cmd);
+    let status = AsyncCommand::from(cmd).status().instrument(span.clone()).await
+        .with_context(|| format!("Failed to run {}", described))?;
+    span.record("process.exit_code", status.code().unwrap_or(-1));
+    if !status.success() {
+        let error = anyhow!("`{}` exited with {}", described, status);
+        telemetry::record_error(&span, &error);
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Capture a command's output inside an `exec` span, leaving the exit status to the caller
+///
+/// For call sites with their own failure messages; the span is still marked
+/// failed when the command exits non-zero.
+pub async fn traced_output(cmd: &mut AsyncCommand) -> Result<std::process::Output> {
+    let described = describe_command(cmd.as_std());
+    tracing::debug!("Executing: {}", described);
+    let span = telemetry::command_span(cmd.as_std());
+    let output = cmd.output().instrument(span.clone()).await
+        .with_context(|| format!("Failed to run {}", described))?;
+    span.record("process.exit_code", output.status.code().unwrap_or(-1));
+    if !output.status.success() {
+        telemetry::record_error(&span, &anyhow!("`{}` exited with {}", described, output.status));
+    }
+    Ok(output)
+}
+
+/// Write a file; in dry-run mode print the lines that would change instead
+pub async fn write_file(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let contents = contents.as_ref();
+    ensure_writable(format_args!("write {}", path.display()))?;
+    if !is_dry_run() {
+        return fs::write(path, contents).await.map_err(|e| io_error(e, "write", path));
+    }
+    
+    match fs::read(path).await {
+        Ok(existing) if existing == contents => {}
+        Ok(existing) => {
+            println!("[dry-run] modify {}", path.display());
+            let old = String::from_utf8_lossy(&existing);
+            let new = String::from_utf8_lossy(contents);
+            for line in line_changes(&old, &new) {
+                println!("    {}", redact(&line));
+            }
+        }
+        Err(_) => println!("[dry-run] create {} ({})", path.display(), format_bytes(contents.len() as u64)),
+    }
+    Ok(())
+}
+
+/// Lines removed (`-`) and added (`+`) between two versions of a text file
+pub fn line_changes(old: &str, new: &str) -> Vec<String> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    
+    let mut changes: Vec<String> = old_lines
+        .iter()
+        .filter(|l| !new_lines.contains(l))
+        .map(|l| format!("- {}", l))
+        .collect();
+    changes.extend(new_lines.iter().filter(|l| !old_lines.contains(l)).map(|l| format!("+ {}", l)));
+    changes
+}
+
+/// Whether a process with this PID is running
+pub async fn process_alive(pid: u32) -> bool {
+    let mut cmd = if cfg!(windows) {
+        let mut c = AsyncCommand::new("tasklist");
+        c.args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"]);
+        c
+    } else {
+        let mut c = AsyncCommand::new("kill");
+        c.args(["-0", &pid.to_string()]);
+        c
+    };
+    match cmd.output().await {
+        // `"name","pid",...` per match; anything else (a localized "no tasks" notice) means gone
+        Ok(output) if cfg!(windows) => {
+            let pid_field = format!("\"{}\"", pid);
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .any(|line| line.split(',').nth(1) == Some(pid_field.as_str()))
+        }
+        Ok(output) => output.status.success(),
+        Err(_) => false,
+    }
+}
+
 /// Check if a command exists in PATH
 pub async fn command_exists(command: &str) -> bool {
     Command::new("which")
@@ -51,9 +341,11 @@ pub async fn command_exists(command: &str) -> bool {
 
 /// Execute a command and return result
 pub async fn execute_command(cmd: &mut Command) -> Result<CommandResult> {
+    tracing::debug!("Executing: {}", redact(&format!("{:?}", cmd)));
+    let span = telemetry::command_span(cmd);
     let start = std::time::Instant::now();
     
-    let output = cmd.output()
+    let output = span.in_scope(|| cmd.output())
         .context("Failed to execute command")?;
     
     let duration_ms = start.elapsed().as_millis() as u64;
@@ -63,14 +355,19 @@ pub async fn execute_command(cmd: &mut Command) -> Result<CommandResult> {
     
     let exit_code = output.status.code().unwrap_or(-1);
     let success = output.status.success();
+    span.record("process.exit_code", exit_code);
     
     if !success {
-        return Err(anyhow!(
-            "Command failed with exit code {}\nStdout: {}\nStderr: {}",
+        let hint = privilege_hint(&stderr).map(|h| format!("\nHint: {}", h)).unwrap_or_default();
+        let error = anyhow!(
+            "Command failed with exit code {}\nStdout: {}\nStderr: {}{}",
             exit_code,
-            stdout,
-            stderr
-        ));
+            redact(&stdout),
+            redact(&stderr),
+            hint
+        );
+        telemetry::record_error(&span, &error);
+        return Err(error);
     }
     
     Ok(CommandResult {
@@ -84,9 +381,11 @@ pub async fn execute_command(cmd: &mut Command) -> Result<CommandResult> {
 
 /// Execute a command asynchronously
 pub async fn execute_command_async(cmd: &mut AsyncCommand) -> Result<CommandResult> {
+    tracing::debug!("Executing: {}", redact(&format!("{:?}", cmd)));
+    let span = telemetry::command_span(cmd.as_std());
     let start = std::time::Instant::now();
     
-    let output = cmd.output().await
+    let output = cmd.output().instrument(span.clone()).await
         .context("Failed to execute async command")?;
     
     let duration_ms = start.elapsed().as_millis() as u64;
@@ -96,14 +395,17 @@ pub async fn execute_command_async(cmd: &mut AsyncCommand) -> Result<CommandResu
     
     let exit_code = output.status.code().unwrap_or(-1);
     let success = output.status.success();
+    span.record("process.exit_code", exit_code);
     
     if !success {
-        return Err(anyhow!(
+        let error = anyhow!(
             "Async command failed with exit code {}\nStdout: {}\nStderr: {}",
             exit_code,
-            stdout,
-            stderr
-        ));
+            redact(&stdout),
+            redact(&stderr)
+        );
+        telemetry::record_error(&span, &error);
+        return Err(error);
     }
     
     Ok(CommandResult {
@@ -229,8 +531,9 @@ pub fn validate_package_name(name: &str) -> Result<()> {
         return Err(anyhow!("Package name too long (max 214 characters)"));
     }
     
-    // Check for valid characters (alphanumeric, hyphens, underscores, dots, slashes)
-    let valid_chars_regex = Regex::new(r"^[a-zA-Z0-9._/-]+$")?;
+    // Check for valid characters (alphanumeric, hyphens, underscores, dots, slashes;
+    // a leading '@' for scoped npm packages)
+    let valid_chars_regex = Regex::new(r"^@?[a-zA-Z0-9._/-]+$")?;
     if !valid_chars_regex.is_match(name) {
         return Err(anyhow!("Package name contains invalid characters"));
     }
@@ -341,8 +644,13 @@ pub async fn create_temp_dir(prefix: &str) -> Result<PathBuf> {
 /// Remove directory recursively
 pub async fn remove_dir_all(path: &Path) -> Result<()> {
     if path.exists() {
+        ensure_writable(format_args!("remove {}", path.display()))?;
+    }
+    if path.exists() && is_dry_run() {
+        println!("[dry-run] remove {}", path.display());
+    } else if path.exists() {
         fs::remove_dir_all(path).await
-            .context("Failed to remove directory")?;
+            .map_err(|e| io_error(e, "remove", path))?;
     }
     Ok(())
 }
@@ -375,17 +683,98 @@ pub async fn copy_dir_all(src: &Path, dst: &Path) -> Result<()> {
 }
 
 /// Get file hash (SHA-256)
+///
+/// Streams the file in chunks so multi-gigabyte model files are not loaded into memory.
 pub async fn get_file_hash(path: &Path) -> Result<String> {
-    use sha2::{Sha256, Digest};
-    
-    let content = fs::read(path).await
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || hash_file_streaming(&path, |_| {}))
+        .await
+        .context("File hashing task failed")?
+}
+
+/// Read size for streaming hashes
+const HASH_CHUNK_BYTES: usize = 1 << 20;
+
+/// Blocking streaming SHA-256 of a file; `on_chunk` gets the size of each chunk read
+pub fn hash_file_streaming(path: &Path, mut on_chunk: impl FnMut(usize)) -> Result<String> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)
         .context("Failed to read file for hashing")?;
-    
-    let mut hasher = Sha256::new();
-    hasher.update(&content);
-    let result = hasher.finalize();
-    
-    Ok(format!("{:x}", result))
+    let mut buffer = vec![0u8; HASH_CHUNK_BYTES];
+    let mut hasher = FileHasher::new();
+    loop {
+        let read = file.read(&mut buffer)
+            .context("Failed to read file for hashing")?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        on_chunk(read);
+    }
+    Ok(hasher.finish_hex())
+}
+
+/// SHA-256 backend for file hashes: ARM-lib's hardware path when it wins the
+/// startup benchmark, otherwise the pure-Rust sha2 crate
+enum FileHasher {
+    Portable(sha2::Sha256),
+    #[cfg(feature = "arm")]
+    Accelerated(arm_lib::crypto::Sha256),
+}
+
+impl FileHasher {
+    fn new() -> Self {
+        use sha2::Digest;
+
+        #[cfg(feature = "arm")]
+        if use_accelerated_sha256() {
+            return Self::Accelerated(arm_lib::crypto::Sha256::new());
+        }
+        Self::Portable(sha2::Sha256::new())
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        use sha2::Digest;
+
+        match self {
+            Self::Portable(hasher) => hasher.update(data),
+            #[cfg(feature = "arm")]
+            Self::Accelerated(hasher) => hasher.update(data),
+        }
+    }
+
+    fn finish_hex(self) -> String {
+        use sha2::Digest;
+
+        match self {
+            Self::Portable(hasher) => format!("{:x}", hasher.finalize()),
+            #[cfg(feature = "arm")]
+            Self::Accelerated(hasher) => arm_lib::crypto::to_hex(&hasher.finalize()),
+        }
+    }
+}
+
+/// Benchmark ARM-lib's SHA-256 against sha2 once per process
+#[cfg(feature = "arm")]
+fn use_accelerated_sha256() -> bool {
+    use arm_lib::crypto;
+    use sha2::Digest;
+    static GATE: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+
+    *GATE.get_or_init(|| {
+        if !crypto::Primitive::Sha256.accelerated() {
+            return false;
+        }
+        let sample = crypto::bench_sample();
+        let faster = crypto::faster_than(
+            &sample,
+            |data| { crypto::sha256(data); },
+            |data| { sha2::Sha256::digest(data); },
+        );
+        tracing::debug!(accelerated = faster, "SHA-256 backend selected");
+        faster
+    })
 }
 
 /// Verify file hash
@@ -596,6 +985,36 @@ mod tests {
         assert_eq!(format_bytes(1048576), "1.0 MB");
     }
     
+    #[test]
+    fn test_line_changes() {
+        let changes = line_changes("a = 1\nb = 2\n", "a = 1\nb = 3\nc = 4\n");
+        assert_eq!(changes, vec!["- b = 2", "+ b = 3", "+ c = 4"]);
+    }
+    
+    #[test]
+    fn test_describe_command() {
+        let mut cmd = Command::new("apt-get");
+        cmd.args(["install", "-y", "build essential"]);
+        assert_eq!(describe_command(&cmd), "apt-get install -y 'build essential'");
+    }
+    
+    #[test]
+    fn test_write_allowed() {
+        assert!(write_allowed(0, &[], 1000, 1000, 0o555));
+        assert!(write_allowed(1000, &[], 1000, 1000, 0o755));
+        assert!(!write_allowed(1000, &[], 0, 0, 0o755));
+        assert!(write_allowed(1000, &[50], 0, 50, 0o775));
+        assert!(!write_allowed(1000, &[50], 1000, 50, 0o575));
+        assert!(write_allowed(1000, &[], 0, 0, 0o777));
+    }
+    
+    #[test]
+    fn test_privilege_hint() {
+        assert!(privilege_hint("E: Could not open lock file - open (13: Permission denied)").is_some());
+        assert!(privilege_hint("E: are you root?").unwrap().contains("sudo"));
+        assert!(privilege_hint("error: package not found").is_none());
+    }
+    
     #[tokio::test]
     async fn test_format_duration() {
         assert_eq!(format_duration(500), "500ms");
@@ -0,0 +1,294 @@
+//! Verify command implementation
+//!
+//! Checks `sha256sum`-style checksum files (the `SHA256SUMS` written by
+//! `rcm package`, vendored package lists, model shard manifests) on a pool of
+//! worker threads. Files are hashed in streaming chunks, largest first, and an
+//! idle worker steals queued files from busy ones so one huge shard does not
+//! hold up the rest.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+use tabled::{Table, Tabled};
+use crate::util;
+use crate::workspace::Workspace;
+
+/// A file and the SHA-256 it should have
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChecksumEntry {
+    pub path: PathBuf,
+    pub expected: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerifyStatus {
+    Ok,
+    Mismatch,
+    Missing,
+    Error,
+}
+
+impl std::fmt::Display for VerifyStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Self::Ok => "✅ ok",
+            Self::Mismatch => "❌ mismatch",
+            Self::Missing => "❌ missing",
+            Self::Error => "❌ error",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Outcome for one file
+#[derive(Debug, Clone, Serialize, Tabled)]
+pub struct FileResult {
+    #[tabled(rename = "File", display_with = "display_path")]
+    pub path: PathBuf,
+    #[tabled(rename = "Status")]
+    pub status: VerifyStatus,
+    #[tabled(rename = "Size", display_with = "display_size")]
+    pub bytes: u64,
+    #[tabled(rename = "Detail")]
+    pub detail: String,
+}
+
+fn display_path(path: &Path) -> String {
+    path.display().to_string()
+}
+
+fn display_size(bytes: &u64) -> String {
+    util::format_bytes(*bytes)
+}
+
+/// Per-file results plus aggregate throughput
+#[derive(Debug, Serialize)]
+pub struct VerifyReport {
+    pub files: Vec<FileResult>,
+    pub failures: usize,
+    pub bytes: u64,
+    pub elapsed_secs: f64,
+    pub throughput_mib_s: f64,
+}
+
+/// Parse `<sha256>  <file>` lines; file names are relative to `base`
+pub fn parse_checksums(content: &str, base: &Path) -> Result<Vec<ChecksumEntry>> {
+    let mut entries = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let malformed = || anyhow!("Malformed checksum line {}: {}", number + 1, line);
+        let (hash, name) = line.split_once(char::is_whitespace).ok_or_else(malformed)?;
+        // Binary-mode entries are written as `<hash> *<file>`
+        let name = name.trim_start();
+        let name = name.strip_prefix('*').unwrap_or(name);
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) || name.is_empty() {
+            return Err(malformed());
+        }
+        entries.push(ChecksumEntry { path: base.join(name), expected: hash.to_ascii_lowercase() });
+    }
+    Ok(entries)
+}
+
+/// Verify every entry on `jobs` threads; results keep the input order
+pub fn verify_entries(entries: &[ChecksumEntry], jobs: usize, progress: &ProgressBar) -> VerifyReport {
+    let started = Instant::now();
+    let sizes: Vec<Option<u64>> = entries
+        .iter()
+        .map(|e| std::fs::metadata(&e.path).ok().map(|m| m.len()))
+        .collect();
+
+    // Deal the largest files out first so none of them starts last
+    let mut order: Vec<usize> = (0..entries.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(sizes[i].unwrap_or(0)));
+    let jobs = jobs.clamp(1, entries.len().max(1));
+    let queues: Vec<Mutex<VecDeque<usize>>> = (0..jobs).map(|_| Mutex::new(VecDeque::new())).collect();
+    for (n, index) in order.into_iter().enumerate() {
+        queues[n % jobs].lock().unwrap().push_back(index);
+    }
+
+    let results: Vec<Mutex<Option<FileResult>>> = entries.iter().map(|_| Mutex::new(None)).collect();
+    std::thread::scope(|scope| {
+        for worker in 0..jobs {
+            let (queues, results, sizes) = (&queues, &results, &sizes);
+            scope.spawn(move || {
+                while let Some(index) = next_job(queues, worker) {
+                    let result = verify_one(&entries[index], sizes[index], progress);
+                    *results[index].lock().unwrap() = Some(result);
+                }
+            });
+        }
+    });
+
+    let files: Vec<FileResult> = results
+        .into_iter()
+        .map(|r| r.into_inner().unwrap().expect("every queued entry is verified"))
+        .collect();
+    let failures = files.iter().filter(|f| f.status != VerifyStatus::Ok).count();
+    let bytes = files.iter().filter(|f| f.status != VerifyStatus::Missing).map(|f| f.bytes).sum();
+    let elapsed_secs = started.elapsed().as_secs_f64();
+    let throughput_mib_s = bytes as f64 / (1024.0 * 1024.0) / elapsed_secs.max(1e-9);
+    VerifyReport { files, failures, bytes, elapsed_secs, throughput_mib_s }
+}
+
+/// Take from the worker's own queue, otherwise steal from the back of another's
+fn next_job(queues: &[Mutex<VecDeque<usize>>], worker: usize) -> Option<usize> {
+    if let Some(job) = queues[worker].lock().unwrap().pop_front() {
+        return Some(job);
+    }
+    (1..queues.len()).find_map(|offset| queues[(worker + offset) % queues.len()].lock().unwrap().pop_back())
+}
+
+fn verify_one(entry: &ChecksumEntry, size: Option<u64>, progress: &ProgressBar) -> FileResult {
+    let mut result = FileResult {
+        path: entry.path.clone(),
+        status: VerifyStatus::Ok,
+        bytes: size.unwrap_or(0),
+        detail: String::new(),
+    };
+    if size.is_none() {
+        result.status = VerifyStatus::Missing;
+        result.detail = "File not found".to_string();
+        return result;
+    }
+    match util::hash_file_streaming(&entry.path, |read| progress.inc(read as u64)) {
+        Ok(actual) if actual.eq_ignore_ascii_case(&entry.expected) => {}
+        Ok(actual) => {
+            result.status = VerifyStatus::Mismatch;
+            result.detail = format!("expected {}, got {}", entry.expected, actual);
+        }
+        Err(e) => {
+            result.status = VerifyStatus::Error;
+            result.detail = format!("{:#}", e);
+        }
+    }
+    result
+}
+
+/// `SHA256SUMS` at the root and under each `dist/<profile>/`
+fn default_checksum_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = vec![root.join("SHA256SUMS")];
+    if let Ok(profiles) = std::fs::read_dir(root.join("dist")) {
+        let mut dist: Vec<PathBuf> = profiles.flatten().map(|e| e.path().join("SHA256SUMS")).collect();
+        dist.sort();
+        files.extend(dist);
+    }
+    files.retain(|f| f.is_file());
+    files
+}
+
+/// Verify checksum files and report failures and throughput
+pub async fn run(workspace: &Workspace, checksum_files: Vec<PathBuf>, jobs: Option<usize>, format: &str) -> Result<()> {
+    if format != "table" && format != "json" {
+        return Err(anyhow!("Unsupported format: {}. Use table or json", format));
+    }
+
+    let checksum_files = if checksum_files.is_empty() {
+        default_checksum_files(workspace.root())
+    } else {
+        checksum_files
+    };
+    if checksum_files.is_empty() {
+        return Err(anyhow!("No checksum files given and none found (SHA256SUMS, dist/*/SHA256SUMS)"));
+    }
+
+    let mut entries = Vec::new();
+    for file in &checksum_files {
+        let content = tokio::fs::read_to_string(file)
+            .await
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let base = file.parent().unwrap_or(Path::new("."));
+        entries.extend(parse_checksums(&content, base).with_context(|| format!("Invalid checksum file {}", file.display()))?);
+    }
+    if entries.is_empty() {
+        return Err(anyhow!("Checksum files list no files"));
+    }
+
+    let jobs = jobs.unwrap_or_else(|| std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4));
+    let total: u64 = entries.iter().filter_map(|e| std::fs::metadata(&e.path).ok()).map(|m| m.len()).sum();
+    let pb = if format == "table" {
+        println!("{} Verifying {} file(s) from {} checksum file(s) on {} worker(s)...",
+            style("🔐").bold(), entries.len(), checksum_files.len(), jobs);
+        let pb = ProgressBar::new(total);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec})")
+                .unwrap()
+                .progress_chars("#>-"),
+        );
+        pb
+    } else {
+        ProgressBar::hidden()
+    };
+
+    let worker_pb = pb.clone();
+    let report = tokio::task::spawn_blocking(move || verify_entries(&entries, jobs, &worker_pb))
+        .await
+        .context("Verification workers failed")?;
+    pb.finish_and_clear();
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        let failed: Vec<&FileResult> = report.files.iter().filter(|f| f.status != VerifyStatus::Ok).collect();
+        if !failed.is_empty() {
+            println!("{}", Table::new(failed));
+        }
+        let summary = format!(
+            "{} file(s), {} hashed in {:.1}s ({:.0} MiB/s)",
+            report.files.len(), util::format_bytes(report.bytes), report.elapsed_secs, report.throughput_mib_s
+        );
+        if report.failures == 0 {
+            println!("{} {}", style("✅ All checksums match:").green().bold(), summary);
+        } else {
+            println!("{} {} failed; {}", style("❌").bold(), report.failures, summary);
+        }
+    }
+
+    if report.failures > 0 {
+        return Err(anyhow!("{} file(s) failed verification", report.failures));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELLO: &str = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+
+    #[test]
+    fn test_parse_checksums() {
+        let content = format!("# release\n{}  app.tar.gz\n{} *model-00001.gguf\n", HELLO, HELLO.to_uppercase());
+        let entries = parse_checksums(&content, Path::new("dist/release")).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].path, Path::new("dist/release/app.tar.gz"));
+        assert_eq!(entries[1].path, Path::new("dist/release/model-00001.gguf"));
+        assert_eq!(entries[1].expected, HELLO);
+        assert!(parse_checksums("abc  file\n", Path::new(".")).is_err());
+        assert!(parse_checksums(HELLO, Path::new(".")).is_err());
+    }
+
+    #[test]
+    fn test_verify_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("good"), "hello\n").unwrap();
+        std::fs::write(dir.path().join("bad"), "tampered\n").unwrap();
+        let content = format!("{h}  good\n{h}  bad\n{h}  gone\n", h = HELLO);
+        let entries = parse_checksums(&content, dir.path()).unwrap();
+
+        let report = verify_entries(&entries, 2, &ProgressBar::hidden());
+        let statuses: Vec<VerifyStatus> = report.files.iter().map(|f| f.status).collect();
+        assert_eq!(statuses, vec![VerifyStatus::Ok, VerifyStatus::Mismatch, VerifyStatus::Missing]);
+        assert_eq!(report.failures, 2);
+        assert_eq!(report.bytes, 15);
+    }
+}
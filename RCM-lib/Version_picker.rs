@@ -0,0 +1,385 @@
+//! Conflict-aware version selection for `rcm add`
+//!
+//! Before installing `<package>@latest`, the newest release is checked against
+//! what the workspace already pins: npm peer dependencies and `engines.node`,
+//! Composer `require` entries (including `php`) against `composer.lock` and
+//! the PHP version, and a crate's `rust-version` against the workspace MSRV.
+//! On a conflict the compatible releases are listed and, on a terminal, the
+//! user picks one instead of waiting for the manager's resolver to fail.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use dialoguer::Select;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use std::path::Path;
+use crate::commands::letcond::parse_loose_version;
+use crate::npm::{self, satisfies_engine};
+use crate::util;
+use crate::workspace::Workspace;
+
+/// Compatible releases offered in the picker
+const MAX_CHOICES: usize = 10;
+
+/// A published release and what it requires
+#[derive(Debug, Clone, Default)]
+pub struct Release {
+    pub version: String,
+    /// Package (or `php`, `node`, `rust`) -> constraint
+    pub requires: BTreeMap<String, String>,
+}
+
+/// Versions the workspace already has, keyed like `Release::requires`
+pub type Installed = BTreeMap<String, semver::Version>;
+
+/// Composer's tilde: the last given part may grow, so `~8.3` is `>=8.3, <9.0`
+/// and `~8.3.1` is `>=8.3.1, <8.4.0` (semver's `~8.3` would stop at 8.4)
+fn composer_tilde(version: &str) -> Option<String> {
+    let parts: Vec<u64> = version.split('.').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    let upper = match parts.as_slice() {
+        [major] | [major, _] => format!("{}.0.0", major + 1),
+        [major, minor, ..] => format!("{}.{}.0", major, minor + 1),
+        [] => return None,
+    };
+    Some(format!(">={}, <{}", version, upper))
+}
+
+/// Whether `version` satisfies a Composer constraint; `None` when it can't be parsed
+///
+/// Composer ANDs with spaces or commas and ORs with `|`/`||`.
+pub fn composer_satisfies(constraint: &str, version: &semver::Version) -> Option<bool> {
+    let constraint = constraint.split('@').next().unwrap_or(constraint).trim();
+    if constraint.is_empty() || constraint == "*" {
+        return Some(true);
+    }
+    let mut any = false;
+    for alternative in constraint.split('|').map(str::trim).filter(|a| !a.is_empty()) {
+        let mut comparators: Vec<String> = Vec::new();
+        let mut operator = String::new();
+        for token in alternative.split([' ', ',']).filter(|t| !t.is_empty()) {
+            if token.chars().all(|c| matches!(c, '<' | '>' | '=' | '^' | '~' | '!')) {
+                operator.push_str(token);
+            } else {
+                let token = token.trim_start_matches('v');
+                let comparator = match std::mem::take(&mut operator).as_str() {
+                    "~" => composer_tilde(token)?,
+                    _ if token.starts_with('~') => composer_tilde(&token[1..])?,
+                    op => format!("{}{}", op, token),
+                };
+                comparators.push(comparator);
+            }
+        }
+        let req = semver::VersionReq::parse(&comparators.join(", ")).ok()?;
+        any |= req.matches(version);
+    }
+    Some(any)
+}
+
+/// Why `release` can't be installed next to `installed`; empty when it can
+///
+/// Requirements on things the workspace doesn't have are not conflicts, and
+/// constraints that can't be parsed are left to the manager.
+pub fn conflicts(manager: &str, release: &Release, installed: &Installed) -> Vec<String> {
+    release.requires.iter()
+        .filter_map(|(name, constraint)| {
+            let have = installed.get(name)?;
+            let ok = match manager {
+                "composer" => composer_satisfies(constraint, have),
+                // crates.io `rust_version` is a minimum
+                "cargo" => parse_loose_version(constraint).map(|min| *have >= min),
+                _ => satisfies_engine(constraint, have).ok(),
+            };
+            match ok {
+                Some(false) => Some(format!("needs {} {} (workspace has {})", name, constraint, have)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn is_prerelease(version: &str) -> bool {
+    semver::Version::parse(version.trim_start_matches('v')).map_or(version.contains('-'), |v| !v.pre.is_empty())
+}
+
+/// GET a registry document, with the registry's `rcm auth` credentials if any
+async fn get_json(registry: &str, url: &str, accept: Option<&str>) -> Result<Value> {
+    let mut request = reqwest::Client::new().get(url).header("User-Agent", "rcm");
+    if let Some(accept) = accept {
+        request = request.header("Accept", accept);
+    }
+    let config = crate::config::Config::load(None).await.unwrap_or_default();
+    // The URL may come from a mirror setting, so only send a login issued for its host
+    match crate::auth::credential_for(&config, registry, url).await {
+        Ok(Some(credential)) => request = credential.apply(request),
+        Ok(None) => {}
+        Err(e) => tracing::debug!("Querying {} without credentials: {}", url, e),
+    }
+    let response = request.send().await.with_context(|| format!("Failed to query {}", url))?;
+    if !response.status().is_success() {
+        return Err(anyhow!("{} returned {}", url, response.status()));
+    }
+    response.json().await.with_context(|| format!("Invalid response from {}", url))
+}
+
+fn string_map(value: Option<&Value>) -> BTreeMap<String, String> {
+    value.and_then(Value::as_object).into_iter().flatten()
+        .filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string())))
+        .collect()
+}
+
+/// npm releases, newest first, from the abbreviated packument
+async fn npm_releases(name: &str) -> Result<Vec<Release>> {
+    let url = format!("https://registry.npmjs.org/{}", name.replace('/', "%2F"));
+    let doc = get_json("npmjs", &url, Some("application/vnd.npm.install-v1+json")).await?;
+    let mut releases: Vec<Release> = doc.get("versions").and_then(Value::as_object).into_iter().flatten()
+        .filter(|(_, meta)| meta.get("deprecated").is_none())
+        .map(|(version, meta)| {
+            let mut requires = string_map(meta.get("peerDependencies"));
+            if let Some(node) = meta.pointer("/engines/node").and_then(Value::as_str) {
+                requires.insert("node".to_string(), node.to_string());
+            }
+            Release { version: version.clone(), requires }
+        })
+        .collect();
+    sort_newest_first(&mut releases);
+    Ok(releases)
+}
+
+/// Composer releases, newest first, expanding Packagist's minified metadata
+async fn composer_releases(name: &str) -> Result<Vec<Release>> {
+    let doc = get_json("packagist", &format!("https://repo.packagist.org/p2/{}.json", name), None).await?;
+    let entries = doc.pointer(&format!("/packages/{}", name.replace('~', "~0").replace('/', "~1")))
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("Packagist has no releases for {}", name))?;
+
+    // Each entry only lists keys that changed from the previous one
+    let mut require = BTreeMap::new();
+    let mut releases = Vec::new();
+    for entry in entries {
+        match entry.get("require") {
+            Some(Value::String(unset)) if unset == "__unset" => require.clear(),
+            Some(value) => require = string_map(Some(value)),
+            None => {}
+        }
+        if let Some(version) = entry.get("version").and_then(Value::as_str) {
+            releases.push(Release { version: version.trim_start_matches('v').to_string(), requires: require.clone() });
+        }
+    }
+    sort_newest_first(&mut releases);
+    Ok(releases)
+}
+
+/// crates.io releases, newest first, without yanked ones
+async fn cargo_releases(name: &str) -> Result<Vec<Release>> {
+    let doc = get_json("crates.io", &format!("https://crates.io/api/v1/crates/{}/versions", name), None).await?;
+    let mut releases: Vec<Release> = doc.get("versions").and_then(Value::as_array).into_iter().flatten()
+        .filter(|v| !v.get("yanked").and_then(Value::as_bool).unwrap_or(false))
+        .filter_map(|v| {
+            let version = v.get("num")?.as_str()?.to_string();
+            let mut requires = BTreeMap::new();
+            if let Some(msrv) = v.get("rust_version").and_then(Value::as_str) {
+                requires.insert("rust".to_string(), msrv.to_string());
+            }
+            Some(Release { version, requires })
+        })
+        .collect();
+    sort_newest_first(&mut releases);
+    Ok(releases)
+}
+
+fn sort_newest_first(releases: &mut [Release]) {
+    releases.sort_by(|a, b| {
+        let parse = |v: &str| semver::Version::parse(v.trim_start_matches('v')).ok().or_else(|| parse_loose_version(v));
+        parse(&b.version).cmp(&parse(&a.version))
+    });
+}
+
+async fn read_json(path: &Path) -> Option<Value> {
+    serde_json::from_str(&tokio::fs::read_to_string(path).await.ok()?).ok()
+}
+
+/// Node version plus the installed version of every npm dependency
+async fn npm_installed(root: &Path, names: impl Iterator<Item = &String>) -> Installed {
+    let mut installed = Installed::new();
+    if let Some(node) = npm::node_version(root).await {
+        installed.insert("node".to_string(), node);
+    }
+    for name in names.filter(|n| n.as_str() != "node") {
+        let manifest = root.join("node_modules").join(name).join("package.json");
+        if let Some(version) = read_json(&manifest).await
+            .and_then(|m| m.get("version").and_then(Value::as_str).and_then(|v| semver::Version::parse(v).ok()))
+        {
+            installed.insert(name.clone(), version);
+        }
+    }
+    installed
+}
+
+/// PHP version (`config.platform.php` first) plus every package in composer.lock
+async fn composer_installed(root: &Path) -> Installed {
+    let mut installed = Installed::new();
+    let platform = read_json(&root.join("composer.json")).await
+        .and_then(|c| c.pointer("/config/platform/php").and_then(Value::as_str).map(str::to_string));
+    let php = match platform {
+        Some(php) => parse_loose_version(&php),
+        None => {
+            let mut cmd = std::process::Command::new("php");
+            cmd.args(["-r", "echo PHP_VERSION;"]);
+            util::execute_command(&mut cmd).await.ok().and_then(|r| parse_loose_version(&r.stdout))
+        }
+    };
+    if let Some(php) = php {
+        installed.insert("php".to_string(), php);
+    }
+    if let Some(lock) = read_json(&root.join("composer.lock")).await {
+        for key in ["packages", "packages-dev"] {
+            for package in lock.get(key).and_then(Value::as_array).into_iter().flatten() {
+                let (Some(name), Some(version)) = (
+                    package.get("name").and_then(Value::as_str),
+                    package.get("version").and_then(Value::as_str).and_then(parse_loose_version),
+                ) else {
+                    continue;
+                };
+                installed.insert(name.to_string(), version);
+            }
+        }
+    }
+    installed
+}
+
+/// The workspace MSRV (`rust-version`), else the installed rustc
+async fn cargo_installed(root: &Path) -> Installed {
+    let manifest: Option<toml::Value> = tokio::fs::read_to_string(root.join("Cargo.toml")).await.ok()
+        .and_then(|c| toml::from_str(&c).ok());
+    let msrv = manifest.as_ref().and_then(|m| {
+        m.get("package").and_then(|p| p.get("rust-version")).and_then(|v| v.as_str())
+            .or_else(|| m.get("workspace").and_then(|w| w.get("package")).and_then(|p| p.get("rust-version")).and_then(|v| v.as_str()))
+            .and_then(parse_loose_version)
+    });
+    let rust = match msrv {
+        Some(msrv) => Some(msrv),
+        None => {
+            let mut cmd = std::process::Command::new("rustc");
+            cmd.arg("--version");
+            util::execute_command(&mut cmd).await.ok().and_then(|r| parse_loose_version(&r.stdout))
+        }
+    };
+    rust.map(|v| Installed::from([("rust".to_string(), v)])).unwrap_or_default()
+}
+
+/// Check `name@latest` before installing; returns a replacement version when one was picked
+///
+/// Registry lookups that fail are logged and skipped so `add` still works offline.
+pub async fn pick(workspace: &Workspace, manager: &str, name: &str, requested: &str) -> Result<Option<String>> {
+    if requested != "latest" || !matches!(manager, "npm" | "composer" | "cargo") {
+        return Ok(None);
+    }
+    let root = workspace.root();
+    let releases = match manager {
+        "npm" => npm_releases(name).await,
+        "composer" => composer_releases(name).await,
+        _ => cargo_releases(name).await,
+    };
+    let releases: Vec<Release> = match releases {
+        Ok(releases) => releases.into_iter().filter(|r| !is_prerelease(&r.version)).collect(),
+        Err(e) => {
+            tracing::debug!("Skipping compatibility check for {}: {}", name, e);
+            return Ok(None);
+        }
+    };
+    let Some(latest) = releases.first() else {
+        return Ok(None);
+    };
+
+    let installed = match manager {
+        "npm" => npm_installed(root, latest.requires.keys()).await,
+        "composer" => composer_installed(root).await,
+        _ => cargo_installed(root).await,
+    };
+    let problems = conflicts(manager, latest, &installed);
+    if problems.is_empty() {
+        return Ok(None);
+    }
+
+    println!("{}", style(format!("⚠️  {} {} conflicts with this workspace:", name, latest.version)).yellow().bold());
+    for problem in &problems {
+        println!("   • {}", problem);
+    }
+
+    let compatible: Vec<&Release> = releases.iter()
+        .filter(|r| conflicts(manager, r, &installed).is_empty())
+        .take(MAX_CHOICES)
+        .collect();
+    if compatible.is_empty() {
+        return Err(anyhow!("No release of {} is compatible with this workspace. Use {}@{} to install the latest anyway", name, name, latest.version));
+    }
+
+    if util::is_dry_run() || !std::io::stdin().is_terminal() {
+        let choice = &compatible[0].version;
+        let listed: Vec<&str> = compatible.iter().map(|r| r.version.as_str()).collect();
+        println!("   Compatible: {}", listed.join(", "));
+        println!("{}", style(format!("📌 Using {} {}", name, choice)).blue());
+        return Ok(Some(choice.clone()));
+    }
+
+    let mut items: Vec<String> = compatible.iter().map(|r| r.version.clone()).collect();
+    items.push(format!("{} anyway (let {} resolve it)", latest.version, manager));
+    let selection = Select::new()
+        .with_prompt(format!("Version of {} to add", name))
+        .items(&items)
+        .default(0)
+        .interact()?;
+    if selection == compatible.len() {
+        return Ok(None);
+    }
+    Ok(Some(compatible[selection].version.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(version: &str, requires: &[(&str, &str)]) -> Release {
+        Release {
+            version: version.to_string(),
+            requires: requires.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        }
+    }
+
+    #[test]
+    fn test_npm_peer_conflicts() {
+        let installed = Installed::from([("react".to_string(), semver::Version::new(17, 0, 2))]);
+        let latest = release("5.0.0", &[("react", "^18.0.0"), ("react-dom", "^18.0.0")]);
+        assert_eq!(conflicts("npm", &latest, &installed), vec!["needs react ^18.0.0 (workspace has 17.0.2)"]);
+        assert!(conflicts("npm", &release("4.2.0", &[("react", "^16.8.0 || ^17.0.0")]), &installed).is_empty());
+    }
+
+    #[test]
+    fn test_composer_constraints() {
+        let php = semver::Version::new(8, 1, 27);
+        assert_eq!(composer_satisfies(">=8.2", &php), Some(false));
+        assert_eq!(composer_satisfies("^7.4 || ^8.0", &php), Some(true));
+        assert_eq!(composer_satisfies(">=8.0 <8.4", &php), Some(true));
+        assert_eq!(composer_satisfies("dev-main", &php), None);
+    }
+
+    #[test]
+    fn test_composer_tilde() {
+        let version = |v: &str| semver::Version::parse(v).unwrap();
+        assert_eq!(composer_satisfies("~8.3", &version("8.4.1")), Some(true));
+        assert_eq!(composer_satisfies("~8.3", &version("8.2.0")), Some(false));
+        assert_eq!(composer_satisfies("~8.3", &version("9.0.0")), Some(false));
+        assert_eq!(composer_satisfies("~8.3.1", &version("8.3.9")), Some(true));
+        assert_eq!(composer_satisfies("~8.3.1", &version("8.4.0")), Some(false));
+        assert_eq!(composer_satisfies("~ 8", &version("8.9.0")), Some(true));
+        assert_eq!(composer_satisfies("~7.4 || ~8.3", &version("7.4.33")), Some(true));
+    }
+
+    #[test]
+    fn test_cargo_msrv_conflict() {
+        let installed = Installed::from([("rust".to_string(), semver::Version::new(1, 70, 0))]);
+        assert_eq!(conflicts("cargo", &release("2.0.0", &[("rust", "1.74")]), &installed).len(), 1);
+        assert!(conflicts("cargo", &release("1.9.0", &[("rust", "1.65")]), &installed).is_empty());
+    }
+}
@@ -7,10 +7,23 @@ use console::style;
 use tabled::{Table, Tabled};
 use serde_json;
 use crate::commands::WorkspaceCommands;
+use crate::config::Config;
+use crate::dashboard;
+use crate::metrics::{self, MetricsSample};
 use crate::workspace::Workspace;
-use crate::npm::{NpmManager, NpmManagerType};
+use crate::container::ContainerManager;
+use crate::go::{self, GoManager};
+use crate::isolation;
+use crate::mirrors;
+use crate::logging;
+use crate::members;
+use crate::npm::{dependency_problems, node_engine, node_version, resolve_manager_type, satisfies_engine, NpmManagerType};
+use crate::pip::{self, PythonManager};
 use crate::ppm::ComposerManager;
 use crate::system::SystemManager;
+use crate::telemetry;
+use crate::util;
+use crate::webhooks;
 
 #[derive(Tabled)]
 struct DependencyRow {
@@ -27,16 +40,99 @@ struct DependencyRow {
 }
 
 /// Handle workspace commands
-pub async fn handle_command(workspace: &Workspace, cmd: WorkspaceCommands) -> Result<()> {
+pub async fn handle_command(workspace: &Workspace, config: &Config, cmd: WorkspaceCommands) -> Result<()> {
     match cmd {
-        WorkspaceCommands::List { format } => list_packages(workspace, &format).await,
-        WorkspaceCommands::Sync => sync_packages(workspace).await,
-        WorkspaceCommands::Clean => clean_workspace(workspace).await,
-        WorkspaceCommands::Update => update_packages(workspace).await,
-        WorkspaceCommands::Check => check_workspace(workspace).await,
+        WorkspaceCommands::List { format, recursive: true } => list_members(workspace, config, &format).await,
+        WorkspaceCommands::List { format, .. } => list_packages(workspace, &format).await,
+        WorkspaceCommands::Sync { no_members } => {
+            sync_packages(workspace).await?;
+            then_members(workspace, config, no_members).await
+        }
+        WorkspaceCommands::Clean { no_members } => {
+            clean_workspace(workspace).await?;
+            then_members(workspace, config, no_members).await
+        }
+        WorkspaceCommands::Update { no_members } => {
+            update_packages(workspace, &workspace.enabled_managers()).await?;
+            then_members(workspace, config, no_members).await
+        }
+        WorkspaceCommands::Check => check_workspace(workspace, config).await,
+        WorkspaceCommands::Trends { last, format } => metrics::show_trends(workspace.root(), last, &format).await,
+        WorkspaceCommands::Export { endpoint, output } => {
+            dashboard::export(workspace, &config.dashboard, &config.security.license_policy, endpoint, output.as_deref()).await
+        }
+        WorkspaceCommands::Isolate { off } => isolation::toggle(workspace.root(), off).await,
+        WorkspaceCommands::Du { format } => disk_usage(workspace, &format).await,
     }
 }
 
+#[derive(Tabled, serde::Serialize)]
+struct UsageRow {
+    #[tabled(rename = "Path")]
+    path: String,
+    #[tabled(rename = "What")]
+    what: String,
+    #[tabled(rename = "Size", display_with = "display_size")]
+    bytes: u64,
+}
+
+fn display_size(bytes: &u64) -> String {
+    util::format_bytes(*bytes)
+}
+
+/// Show what takes space in the workspace, including isolated manager homes
+async fn disk_usage(workspace: &Workspace, format: &str) -> Result<()> {
+    let root = workspace.root();
+    let isolated = isolation::IsolationSettings::load(root).enabled;
+
+    let mut entries: Vec<(std::path::PathBuf, String)> = vec![
+        (root.join("target"), "cargo build output".to_string()),
+        (root.join("node_modules"), "node packages".to_string()),
+        (root.join("vendor"), "composer packages".to_string()),
+        (root.join(".rcm").join("cache"), "RCM cache".to_string()),
+        (root.join(".rcm").join("temp"), "temporary files".to_string()),
+        (root.join(logging::LOGS_DIR), "traces".to_string()),
+    ];
+    for (_, what, dir) in isolation::homes(root) {
+        let state = if isolated { "isolated" } else { "isolation off" };
+        entries.push((dir, format!("{} ({})", what, state)));
+    }
+
+    let mut rows = Vec::new();
+    for (path, what) in entries {
+        if !path.exists() {
+            continue;
+        }
+        let bytes = util::calculate_directory_size(&path).await?;
+        let shown = path.strip_prefix(root).unwrap_or(&path).display().to_string();
+        rows.push(UsageRow { path: shown, what, bytes });
+    }
+    rows.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+    let total: u64 = rows.iter().map(|r| r.bytes).sum();
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "isolated": isolated,
+            "total_bytes": total,
+            "entries": rows,
+        }))?),
+        "table" => {
+            println!("{}", style("💾 Workspace disk usage").cyan().bold());
+            if rows.is_empty() {
+                println!("Nothing to report");
+            } else {
+                println!("{}", Table::new(&rows));
+            }
+            println!("Total: {}", style(util::format_bytes(total)).bold());
+            if isolated {
+                println!("🔒 cargo, npm and composer homes are isolated under {}", isolation::HOMES_DIR);
+            }
+        }
+        _ => return Err(anyhow!("Unknown format: {}. Use 'table' or 'json'", format)),
+    }
+    Ok(())
+}
+
 /// List all packages in the workspace
 async fn list_packages(workspace: &Workspace, format: &str) -> Result<()> {
     let dependencies = workspace.list_dependencies();
@@ -100,6 +196,40 @@ async fn list_packages(workspace: &Workspace, format: &str) -> Result<()> {
     Ok(())
 }
 
+/// List the root workspace and each member project
+async fn list_members(workspace: &Workspace, config: &Config, format: &str) -> Result<()> {
+    let root = workspace.root();
+    let member_dirs = members::discover(root)?;
+    if format == "json" {
+        let mut all = serde_json::Map::new();
+        all.insert(".".to_string(), serde_json::to_value(workspace.list_dependencies())?);
+        for dir in &member_dirs {
+            let member = Workspace::new(Some(dir.to_string_lossy().as_ref()), config.clone()).await?;
+            all.insert(members::relative(root, dir), serde_json::to_value(member.list_dependencies())?);
+        }
+        println!("{}", serde_json::to_string_pretty(&all)?);
+        return Ok(());
+    }
+
+    println!("{}", style("📁 .").bold());
+    list_packages(workspace, format).await?;
+    for dir in &member_dirs {
+        let member = Workspace::new(Some(dir.to_string_lossy().as_ref()), config.clone()).await?;
+        println!();
+        println!("{}", style(format!("📁 {}", members::relative(root, dir))).bold());
+        list_packages(&member, format).await?;
+    }
+    Ok(())
+}
+
+/// Repeat the current workspace command in each member project
+async fn then_members(workspace: &Workspace, config: &Config, skip: bool) -> Result<()> {
+    if skip {
+        return Ok(());
+    }
+    members::propagate(workspace.root(), config.core.parallel_jobs).await
+}
+
 /// Synchronize all package managers
 async fn sync_packages(workspace: &Workspace) -> Result<()> {
     println!("{}", style("🔄 Synchronizing all package managers...").cyan().bold());
@@ -114,6 +244,9 @@ async fn sync_packages(workspace: &Workspace) -> Result<()> {
             "cargo" => sync_cargo(workspace).await,
             "npm" => sync_npm(workspace).await,
             "composer" => sync_composer(workspace).await,
+            "python" => sync_python(workspace).await,
+            "go" => sync_go(workspace).await,
+            "container" => ContainerManager::new(workspace.root()).pull_missing().await,
             "system" => sync_system(workspace).await,
             _ => Err(anyhow!("Unknown manager: {}", manager)),
         };
@@ -121,10 +254,12 @@ async fn sync_packages(workspace: &Workspace) -> Result<()> {
         match result {
             Ok(_) => {
                 println!("{}", style(format!("✅ {} synchronized", manager)).green());
+                tracing::debug!(manager = %manager, "synchronized");
                 sync_results.push((manager.clone(), true));
             }
             Err(e) => {
                 println!("{}", style(format!("❌ {} failed: {}", manager, e)).red());
+                tracing::warn!(manager = %manager, error = %e, "sync failed");
                 sync_results.push((manager.clone(), false));
             }
         }
@@ -153,9 +288,12 @@ async fn sync_cargo(workspace: &Workspace) -> Result<()> {
     
     let mut cmd = tokio::process::Command::new("cargo");
     cmd.current_dir(workspace.root());
+    cmd.envs(isolation::env_vars(workspace.root()));
+    cmd.envs(mirrors::env_vars());
     cmd.arg("update");
+    cmd.args(mirrors::cargo_args());
     
-    let output = cmd.output().await?;
+    let output = util::traced_output(&mut cmd).await?;
     if !output.status.success() {
         return Err(anyhow!("Cargo sync failed: {}", String::from_utf8_lossy(&output.stderr)));
     }
@@ -170,12 +308,12 @@ async fn sync_npm(workspace: &Workspace) -> Result<()> {
         return Ok(());
     }
     
-    let npm_manager = NpmManager::new(workspace.root(), NpmManagerType::Npm);
-    let mut cmd = tokio::process::Command::new("npm");
+    let manager_type = resolve_manager_type(workspace.root(), None).await?;
+    let mut cmd = tokio::process::Command::new(manager_type.command());
     cmd.current_dir(workspace.root());
     cmd.arg("install");
     
-    let output = cmd.output().await?;
+    let output = util::traced_output(&mut cmd).await?;
     if !output.status.success() {
         return Err(anyhow!("NPM sync failed: {}", String::from_utf8_lossy(&output.stderr)));
     }
@@ -194,7 +332,7 @@ async fn sync_composer(workspace: &Workspace) -> Result<()> {
     cmd.current_dir(workspace.root());
     cmd.arg("install");
     
-    let output = cmd.output().await?;
+    let output = util::traced_output(&mut cmd).await?;
     if !output.status.success() {
         return Err(anyhow!("Composer sync failed: {}", String::from_utf8_lossy(&output.stderr)));
     }
@@ -202,6 +340,26 @@ async fn sync_composer(workspace: &Workspace) -> Result<()> {
     Ok(())
 }
 
+/// Synchronize Python dependencies
+async fn sync_python(workspace: &Workspace) -> Result<()> {
+    if !pip::has_python_project(workspace.root()) {
+        return Ok(());
+    }
+    
+    let manager_type = pip::resolve_manager_type(workspace.root(), None).await?;
+    PythonManager::new(workspace.root(), manager_type).sync().await
+}
+
+/// Synchronize Go modules
+async fn sync_go(workspace: &Workspace) -> Result<()> {
+    let go_manager = GoManager::new(workspace.root());
+    if !go_manager.has_module() {
+        return Ok(());
+    }
+    
+    go_manager.download().await
+}
+
 /// Synchronize system dependencies
 async fn sync_system(workspace: &Workspace) -> Result<()> {
     // System dependencies are handled individually
@@ -255,6 +413,20 @@ async fn clean_workspace(workspace: &Workspace) -> Result<()> {
                     cleaned_items.push("Composer vendor directory");
                 }
             }
+            "python" => {
+                if let Err(e) = clean_python(workspace).await {
+                    println!("{}", style(format!("⚠️ Failed to clean Python: {}", e)).yellow());
+                } else {
+                    cleaned_items.push("Python virtualenv and caches");
+                }
+            }
+            "go" => {
+                if let Err(e) = GoManager::new(workspace.root()).clean().await {
+                    println!("{}", style(format!("⚠️ Failed to clean Go: {}", e)).yellow());
+                } else {
+                    cleaned_items.push("Go build artifacts");
+                }
+            }
             _ => {}
         }
     }
@@ -294,9 +466,10 @@ async fn clean_cargo(workspace: &Workspace) -> Result<()> {
     
     let mut cmd = tokio::process::Command::new("cargo");
     cmd.current_dir(workspace.root());
+    cmd.envs(isolation::env_vars(workspace.root()));
     cmd.arg("clean");
     
-    let output = cmd.output().await?;
+    let output = util::traced_output(&mut cmd).await?;
     if !output.status.success() {
         return Err(anyhow!("Cargo clean failed: {}", String::from_utf8_lossy(&output.stderr)));
     }
@@ -348,23 +521,55 @@ async fn clean_composer(workspace: &Workspace) -> Result<()> {
     Ok(())
 }
 
-/// Update all packages
-async fn update_packages(workspace: &Workspace) -> Result<()> {
+/// Clean Python artifacts
+async fn clean_python(workspace: &Workspace) -> Result<()> {
+    if !pip::has_python_project(workspace.root()) {
+        return Ok(());
+    }
+    
+    for dir in [".venv", ".pytest_cache", ".mypy_cache", ".ruff_cache"] {
+        let path = workspace.root().join(dir);
+        if path.exists() {
+            tokio::fs::remove_dir_all(&path).await?;
+        }
+    }
+    
+    // Bytecode caches are scattered through the source tree
+    let pycache: Vec<_> = walkdir::WalkDir::new(workspace.root())
+        .into_iter()
+        .filter_entry(|e| !matches!(e.file_name().to_str(), Some("node_modules" | "vendor" | "target" | ".git")))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_dir() && e.file_name() == "__pycache__")
+        .map(|e| e.into_path())
+        .collect();
+    for dir in pycache {
+        tokio::fs::remove_dir_all(&dir).await?;
+    }
+    
+    Ok(())
+}
+
+/// Update the packages of the given managers
+pub async fn update_packages(workspace: &Workspace, managers: &[String]) -> Result<()> {
     println!("{}", style("📈 Updating all packages...").cyan().bold());
     
-    let enabled_managers = workspace.enabled_managers();
     let mut update_results = Vec::new();
+    let changes = webhooks::snapshot(workspace, managers).await;
     
-    for manager in &enabled_managers {
+    for manager in managers {
         println!("{}", style(format!("🔄 Updating {} packages...", manager)).blue());
         
-        let result = match manager.as_str() {
-            "cargo" => update_cargo(workspace).await,
-            "npm" => update_npm(workspace).await,
-            "composer" => update_composer(workspace).await,
-            "system" => update_system(workspace).await,
-            _ => Err(anyhow!("Unknown manager: {}", manager)),
-        };
+        let result = telemetry::traced(telemetry::manager_span("update", manager), async {
+            match manager.as_str() {
+                "cargo" => update_cargo(workspace).await,
+                "npm" => update_npm(workspace).await,
+                "composer" => update_composer(workspace).await,
+                "python" => update_python(workspace).await,
+                "go" => update_go(workspace).await,
+                "system" => update_system(workspace).await,
+                _ => Err(anyhow!("Unknown manager: {}", manager)),
+            }
+        }).await;
         
         match result {
             Ok(_) => {
@@ -378,6 +583,10 @@ async fn update_packages(workspace: &Workspace) -> Result<()> {
         }
     }
     
+    if let Some(changes) = changes {
+        changes.emit_changes(workspace).await;
+    }
+    
     // Print summary
     println!();
     let successful = update_results.iter().filter(|(_, success)| *success).count();
@@ -401,9 +610,12 @@ async fn update_cargo(workspace: &Workspace) -> Result<()> {
     
     let mut cmd = tokio::process::Command::new("cargo");
     cmd.current_dir(workspace.root());
+    cmd.envs(isolation::env_vars(workspace.root()));
+    cmd.envs(mirrors::env_vars());
     cmd.arg("update");
+    cmd.args(mirrors::cargo_args());
     
-    let output = cmd.output().await?;
+    let output = util::traced_output(&mut cmd).await?;
     if !output.status.success() {
         return Err(anyhow!("Cargo update failed: {}", String::from_utf8_lossy(&output.stderr)));
     }
@@ -418,11 +630,12 @@ async fn update_npm(workspace: &Workspace) -> Result<()> {
         return Ok(());
     }
     
-    let mut cmd = tokio::process::Command::new("npm");
+    let manager_type = resolve_manager_type(workspace.root(), None).await?;
+    let mut cmd = tokio::process::Command::new(manager_type.command());
     cmd.current_dir(workspace.root());
-    cmd.arg("update");
+    cmd.arg(if matches!(manager_type, NpmManagerType::Yarn) { "upgrade" } else { "update" });
     
-    let output = cmd.output().await?;
+    let output = util::traced_output(&mut cmd).await?;
     if !output.status.success() {
         return Err(anyhow!("NPM update failed: {}", String::from_utf8_lossy(&output.stderr)));
     }
@@ -441,7 +654,7 @@ async fn update_composer(workspace: &Workspace) -> Result<()> {
     cmd.current_dir(workspace.root());
     cmd.arg("update");
     
-    let output = cmd.output().await?;
+    let output = util::traced_output(&mut cmd).await?;
     if !output.status.success() {
         return Err(anyhow!("Composer update failed: {}", String::from_utf8_lossy(&output.stderr)));
     }
@@ -449,6 +662,26 @@ async fn update_composer(workspace: &Workspace) -> Result<()> {
     Ok(())
 }
 
+/// Update Python packages
+async fn update_python(workspace: &Workspace) -> Result<()> {
+    if !pip::has_python_project(workspace.root()) {
+        return Ok(());
+    }
+    
+    let manager_type = pip::resolve_manager_type(workspace.root(), None).await?;
+    PythonManager::new(workspace.root(), manager_type).update(&[]).await
+}
+
+/// Update Go modules
+async fn update_go(workspace: &Workspace) -> Result<()> {
+    let go_manager = GoManager::new(workspace.root());
+    if !go_manager.has_module() {
+        return Ok(());
+    }
+    
+    go_manager.update().await
+}
+
 /// Update system packages
 async fn update_system(workspace: &Workspace) -> Result<()> {
     let system_manager = SystemManager::new(workspace.root()).await?;
@@ -457,7 +690,7 @@ async fn update_system(workspace: &Workspace) -> Result<()> {
 }
 
 /// Check workspace health
-async fn check_workspace(workspace: &Workspace) -> Result<()> {
+async fn check_workspace(workspace: &Workspace, config: &Config) -> Result<()> {
     println!("{}", style("🏥 Checking workspace health...").cyan().bold());
     
     let summary = workspace.get_summary().await?;
@@ -479,6 +712,27 @@ async fn check_workspace(workspace: &Workspace) -> Result<()> {
         println!("{}", style("❌ Poor health - needs attention").red().bold());
     }
     
+    let sample = MetricsSample {
+        timestamp: chrono::Utc::now(),
+        health_score: summary.health_score,
+        total_dependencies: summary.total_dependencies,
+        dependencies_by_manager: summary.dependencies_by_manager.iter()
+            .map(|(manager, count)| (manager.to_string(), *count))
+            .collect(),
+        disk_usage_mb: summary.disk_usage_mb,
+        vulnerabilities: summary.security_vulnerabilities,
+        outdated: summary.outdated_dependencies.len(),
+    };
+    if let Err(e) = metrics::record(workspace.root(), &sample).await {
+        tracing::warn!("Failed to record workspace metrics: {}", e);
+    }
+    
+    // Validate the node version against package.json engines
+    let engine_error = check_node_engine(workspace).await?;
+    check_npm_tree(workspace).await;
+    check_python_env(workspace).await;
+    let go_error = check_go_module(workspace).await;
+    
     // Show dependencies by manager
     if !summary.dependencies_by_manager.is_empty() {
         println!();
@@ -525,6 +779,143 @@ async fn check_workspace(workspace: &Workspace) -> Result<()> {
     }
     
     println!("  • Run {} to keep packages up to date", style("rcm workspace update").cyan());
+    println!("  • Run {} to see how these metrics changed over time", style("rcm workspace trends").cyan());
     
-    Ok(())
+    if config.dashboard.push_on_check && config.dashboard.endpoint.is_some() {
+        println!();
+        if let Err(e) = dashboard::export(workspace, &config.dashboard, &config.security.license_policy, None, None).await {
+            println!("{} Dashboard export failed: {}", style("⚠️").yellow(), e);
+        }
+    }
+    
+    match engine_error.or(go_error) {
+        Some(message) => Err(anyhow!(message)),
+        None => Ok(()),
+    }
+}
+
+/// Print missing, invalid and extraneous npm packages from the installed tree
+async fn check_npm_tree(workspace: &Workspace) {
+    if !workspace.root().join("package.json").exists() || !util::command_exists("npm").await {
+        return;
+    }
+    
+    println!();
+    println!("{}", style("🌳 npm dependency tree").bold());
+    match dependency_problems(workspace.root()).await {
+        Ok(problems) if problems.is_empty() => {
+            println!("  {} all top-level packages installed and valid", style("✓").green());
+        }
+        Ok(problems) => {
+            for problem in &problems {
+                println!("  {} {}", style("✗").red(), problem);
+            }
+            println!("  Run {} to install missing packages", style("rcm ensure").cyan());
+        }
+        Err(e) => println!("  {} {}", style("⚠").yellow(), e),
+    }
+}
+
+/// Print missing and conflicting packages in the Python environment
+async fn check_python_env(workspace: &Workspace) {
+    if !workspace.enabled_managers().iter().any(|m| m == "python") || !pip::has_python_project(workspace.root()) {
+        return;
+    }
+    
+    println!();
+    println!("{}", style("🐍 Python environment").bold());
+    let manager = match pip::resolve_manager_type(workspace.root(), None).await {
+        Ok(manager_type) => PythonManager::new(workspace.root(), manager_type),
+        Err(e) => {
+            println!("  {} {}", style("⚠").yellow(), e);
+            return;
+        }
+    };
+    match manager.problems().await {
+        Ok(problems) if problems.is_empty() => {
+            println!("  {} all declared packages installed and compatible", style("✓").green());
+        }
+        Ok(problems) => {
+            for problem in &problems {
+                println!("  {} {}", style("✗").red(), problem);
+            }
+            println!("  Run {} to install missing packages", style("rcm workspace sync").cyan());
+        }
+        Err(e) => println!("  {} {}", style("⚠").yellow(), e),
+    }
+}
+
+/// Print the go directive and `go mod verify` results; returns an error message when go is too old
+async fn check_go_module(workspace: &Workspace) -> Option<String> {
+    let go_manager = GoManager::new(workspace.root());
+    if !workspace.enabled_managers().iter().any(|m| m == "go") || !go_manager.has_module() {
+        return None;
+    }
+    
+    println!();
+    println!("{}", style("🐹 Go module").bold());
+    let go_mod = match go_manager.load_go_mod().await {
+        Ok(go_mod) => go_mod,
+        Err(e) => {
+            println!("  {} {}", style("⚠").yellow(), e);
+            return None;
+        }
+    };
+    let Some(installed) = go_manager.version().await else {
+        println!("  {} go not found (go.mod requires {})", style("✗").red(), go_mod.go.as_deref().unwrap_or("any"));
+        return Some("Go is required by go.mod".to_string());
+    };
+    
+    let mut error = None;
+    match &go_mod.go {
+        Some(required) if !go::satisfies_go_directive(required, &installed) => {
+            println!("  {} go {} does not satisfy go {}", style("✗").red(), installed, required);
+            error = Some(format!("go {} does not satisfy go.mod's go {}", installed, required));
+        }
+        Some(required) => println!("  {} go {} satisfies go {}", style("✓").green(), installed, required),
+        None => println!("  {} go {} (no go directive)", style("✓").green(), installed),
+    }
+    
+    match go_manager.verify().await {
+        Ok(problems) if problems.is_empty() => {
+            println!("  {} {} requirements verified against go.sum", style("✓").green(), go_mod.requires.len());
+        }
+        Ok(problems) => {
+            for problem in &problems {
+                println!("  {} {}", style("✗").red(), problem);
+            }
+            println!("  Run {} to re-download modules", style("rcm workspace sync").cyan());
+        }
+        Err(e) => println!("  {} {}", style("⚠").yellow(), e),
+    }
+    error
+}
+
+/// Print the engines check; returns an error message when node does not satisfy it
+async fn check_node_engine(workspace: &Workspace) -> Result<Option<String>> {
+    let Some(range) = node_engine(workspace.root()).await? else {
+        return Ok(None);
+    };
+    
+    println!();
+    println!("{}", style("🔧 Engines").bold());
+    let Some(version) = node_version(workspace.root()).await else {
+        println!("  {} node not found (engines.node requires {})", style("✗").red(), range);
+        return Ok(Some(format!("Node.js is required (engines.node: {})", range)));
+    };
+    
+    match satisfies_engine(&range, &version) {
+        Ok(true) => {
+            println!("  {} node v{} satisfies {}", style("✓").green(), version, range);
+            Ok(None)
+        }
+        Ok(false) => {
+            println!("  {} node v{} does not satisfy {}", style("✗").red(), version, range);
+            Ok(Some(format!("node v{} does not satisfy engines.node {}", version, range)))
+        }
+        Err(e) => {
+            println!("  {} {}", style("⚠").yellow(), e);
+            Ok(None)
+        }
+    }
 }
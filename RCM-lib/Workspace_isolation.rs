@@ -0,0 +1,128 @@
+//! Per-workspace package manager homes
+//!
+//! With isolation on, spawned cargo, npm and composer processes get
+//! `CARGO_HOME`, the npm cache and global prefix, and `COMPOSER_HOME` pointed
+//! at `.rcm/homes`, so experiments in one workspace don't touch the user's
+//! registries, caches or globally installed tools. Toggled per workspace with
+//! `rcm workspace isolate` and recorded in `.rcm/isolation.toml`.
+
+use anyhow::{Context, Result};
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use crate::util;
+
+/// Isolation settings relative to the workspace root
+pub const ISOLATION_FILE: &str = ".rcm/isolation.toml";
+
+/// Root of the workspace-local homes
+pub const HOMES_DIR: &str = ".rcm/homes";
+
+/// Contents of `.rcm/isolation.toml`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IsolationSettings {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+impl IsolationSettings {
+    /// Read the settings; a missing or unreadable file means isolation is off
+    ///
+    /// Synchronous because it runs while building every manager command.
+    pub fn load(workspace_root: &Path) -> Self {
+        std::fs::read_to_string(workspace_root.join(ISOLATION_FILE))
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub async fn save(&self, workspace_root: &Path) -> Result<()> {
+        let path = workspace_root.join(ISOLATION_FILE);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        util::write_file(&path, toml::to_string_pretty(self)?).await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Each isolated home: (manager, description, directory)
+pub fn homes(workspace_root: &Path) -> Vec<(&'static str, &'static str, PathBuf)> {
+    let dir = workspace_root.join(HOMES_DIR);
+    vec![
+        ("cargo", "CARGO_HOME", dir.join("cargo")),
+        ("npm", "npm cache", dir.join("npm").join("cache")),
+        ("npm", "npm prefix", dir.join("npm").join("prefix")),
+        ("composer", "COMPOSER_HOME", dir.join("composer")),
+    ]
+}
+
+/// Environment variables that redirect the managers' homes into the workspace
+pub fn home_vars(workspace_root: &Path) -> Vec<(&'static str, PathBuf)> {
+    let dir = workspace_root.join(HOMES_DIR);
+    vec![
+        ("CARGO_HOME", dir.join("cargo")),
+        ("npm_config_cache", dir.join("npm").join("cache")),
+        ("npm_config_prefix", dir.join("npm").join("prefix")),
+        ("COMPOSER_HOME", dir.join("composer")),
+        ("COMPOSER_CACHE_DIR", dir.join("composer").join("cache")),
+    ]
+}
+
+/// Variables to set on a spawned manager process; empty unless isolation is on
+///
+/// Pass to `Command::envs` alongside the toolchain shim `PATH`.
+pub fn env_vars(workspace_root: &Path) -> Vec<(&'static str, PathBuf)> {
+    if IsolationSettings::load(workspace_root).enabled {
+        home_vars(workspace_root)
+    } else {
+        Vec::new()
+    }
+}
+
+/// `rcm workspace isolate [--off]`
+pub async fn toggle(workspace_root: &Path, off: bool) -> Result<()> {
+    let mut settings = IsolationSettings::load(workspace_root);
+    settings.enabled = !off;
+    settings.save(workspace_root).await?;
+
+    if off {
+        println!("{}", style("🔓 Isolation off: cargo, npm and composer use your user-level homes again").green().bold());
+        if workspace_root.join(HOMES_DIR).exists() {
+            println!("   {} is kept; remove it to reclaim the space", HOMES_DIR);
+        }
+        return Ok(());
+    }
+
+    for (_, _, dir) in homes(workspace_root) {
+        if !util::is_dry_run() {
+            fs::create_dir_all(&dir).await
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+    }
+    println!("{}", style("🔒 Isolation on: manager state now lives in the workspace").green().bold());
+    for (name, path) in home_vars(workspace_root) {
+        println!("   {} = {}", style(name).cyan(), path.display());
+    }
+    println!("   Registries and tools are fetched again on first use; see {}", style("rcm workspace du").cyan());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_vars_follow_settings() {
+        let root = std::env::temp_dir().join(format!("rcm-isolation-test-{}", std::process::id()));
+        assert!(env_vars(&root).is_empty());
+
+        std::fs::create_dir_all(root.join(".rcm")).unwrap();
+        std::fs::write(root.join(ISOLATION_FILE), "enabled = true\n").unwrap();
+        let vars = env_vars(&root);
+        assert!(vars.iter().any(|(name, path)| *name == "CARGO_HOME" && path.starts_with(root.join(HOMES_DIR))));
+        assert!(vars.iter().any(|(name, _)| *name == "COMPOSER_HOME"));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
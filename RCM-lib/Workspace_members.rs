@@ -0,0 +1,347 @@
+//! Multi-project workspaces
+//!
+//! A monorepo root lists the RCM-managed projects it contains under `members`
+//! in `.rcm/workspace.json`:
+//!
+//! ```json
+//! { "members": ["services/api", "web", "packages/*"] }
+//! ```
+//!
+//! `dir/*` takes every direct subdirectory of `dir` that has its own `.rcm`.
+//! Members may list members of their own. `rcm ensure` and `rcm workspace
+//! sync|clean|update` run at the root and then in every member below it, at most
+//! `core.parallel_jobs` at a time; `rcm workspace list --recursive` lists each
+//! member's dependencies.
+
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Instant;
+use tabled::{Table, Tabled};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use crate::util;
+
+pub const MANIFEST: &str = ".rcm/workspace.json";
+
+/// Set on member runs started by a root, which already covers every descendant
+pub const MEMBER_ENV: &str = "RCM_MEMBER_RUN";
+
+/// Output lines shown for each failed member
+const FAILURE_TAIL: usize = 15;
+
+/// The part of `.rcm/workspace.json` this module reads
+#[derive(Debug, Default, Deserialize)]
+struct MembersManifest {
+    #[serde(default)]
+    members: Vec<String>,
+}
+
+/// Member entries declared by the workspace at `root`
+fn declared(root: &Path) -> Result<Vec<String>> {
+    let path = root.join(MANIFEST);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let manifest: MembersManifest = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(manifest.members)
+}
+
+/// A top-level section of `.rcm/workspace.json`, or its default when absent
+pub fn read_section<T: DeserializeOwned + Default>(root: &Path, key: &str) -> Result<T> {
+    let path = root.join(MANIFEST);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(T::default()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let manifest: serde_json::Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    match manifest.get(key) {
+        Some(section) => serde_json::from_value(section.clone())
+            .with_context(|| format!("Invalid {} in {}", key, path.display())),
+        None => Ok(T::default()),
+    }
+}
+
+/// Replace one top-level section of `.rcm/workspace.json`, keeping the rest of the file
+pub async fn write_section<T: Serialize>(root: &Path, key: &str, value: &T) -> Result<()> {
+    let path = root.join(MANIFEST);
+    let mut manifest = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => serde_json::json!({}),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    manifest
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("{} is not a JSON object", path.display()))?
+        .insert(key.to_string(), serde_json::to_value(value)?);
+    if !util::is_dry_run() {
+        tokio::fs::create_dir_all(path.parent().unwrap_or(root)).await?;
+    }
+    util::write_file(&path, format!("{}\n", serde_json::to_string_pretty(&manifest)?)).await
+}
+
+/// `path` with `.` and `..` resolved without touching the filesystem
+fn clean(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Directories one member entry stands for
+fn expand(root: &Path, entry: &str) -> Result<Vec<PathBuf>> {
+    let entry = entry.trim().trim_end_matches('/');
+    if let Some(parent) = entry.strip_suffix("/*").or((entry == "*").then_some("")) {
+        let base = root.join(parent);
+        let listing = std::fs::read_dir(&base)
+            .with_context(|| format!("Member '{}' in {}: cannot read {}", entry, root.join(MANIFEST).display(), base.display()))?;
+        let mut dirs: Vec<PathBuf> = listing
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|path| path.join(".rcm").is_dir())
+            .collect();
+        dirs.sort();
+        return Ok(dirs);
+    }
+    let dir = clean(&root.join(entry));
+    if !dir.is_dir() {
+        return Err(anyhow!("Member '{}' in {} is not a directory", entry, root.join(MANIFEST).display()));
+    }
+    Ok(vec![dir])
+}
+
+/// Every member below `root`, depth first; a directory listed twice is visited once
+pub fn discover(root: &Path) -> Result<Vec<PathBuf>> {
+    let top = root.canonicalize()
+        .with_context(|| format!("Failed to resolve workspace root {}", root.display()))?;
+    let mut seen = HashSet::new();
+    seen.insert(top.clone());
+    let mut found = Vec::new();
+    walk(root, &top, &mut seen, &mut found)?;
+    Ok(found)
+}
+
+fn walk(dir: &Path, top: &Path, seen: &mut HashSet<PathBuf>, found: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in declared(dir)? {
+        for member in expand(dir, &entry)? {
+            let canonical = member.canonicalize()
+                .with_context(|| format!("Failed to resolve member '{}' in {}", entry, dir.join(MANIFEST).display()))?;
+            if !canonical.starts_with(top) {
+                return Err(anyhow!(
+                    "Member '{}' in {} resolves to {}, outside the workspace root {}",
+                    entry, dir.join(MANIFEST).display(), canonical.display(), top.display()
+                ));
+            }
+            if !seen.insert(canonical) {
+                continue;
+            }
+            found.push(member.clone());
+            walk(&member, top, seen, found)?;
+        }
+    }
+    Ok(())
+}
+
+/// `dir` relative to the workspace root, for display
+pub fn relative(root: &Path, dir: &Path) -> String {
+    dir.strip_prefix(root).unwrap_or(dir).to_string_lossy().replace('\\', "/")
+}
+
+/// Whether this process is a member run started by a root
+pub fn inside() -> bool {
+    std::env::var_os(MEMBER_ENV).is_some()
+}
+
+/// The command line to repeat in a member, without `-w/-wPATH/--workspace`
+pub fn forwarded_args(args: &[OsString]) -> Vec<String> {
+    let mut forwarded = Vec::new();
+    let mut skip_value = false;
+    for arg in args {
+        if std::mem::take(&mut skip_value) {
+            continue;
+        }
+        let arg = arg.to_string_lossy();
+        if arg == "-w" || arg == "--workspace" {
+            skip_value = true;
+        } else if !arg.starts_with("--workspace=") && !(arg.starts_with("-w") && !arg.starts_with("--")) {
+            forwarded.push(arg.into_owned());
+        }
+    }
+    forwarded
+}
+
+#[derive(Debug)]
+struct MemberResult {
+    member: String,
+    ok: bool,
+    duration_ms: u64,
+    output: String,
+}
+
+#[derive(Tabled)]
+struct MemberRow {
+    #[tabled(rename = "Member")]
+    member: String,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "Time")]
+    time: String,
+}
+
+async fn run_member(binary: &Path, dir: &Path, member: String, args: &[String]) -> MemberResult {
+    let started = Instant::now();
+    let output = tokio::process::Command::new(binary)
+        .arg("-w")
+        .arg(dir)
+        .args(args)
+        .env(MEMBER_ENV, "1")
+        .stdin(Stdio::null())
+        .output()
+        .await;
+    let (ok, output) = match output {
+        Ok(out) => (
+            out.status.success(),
+            format!("{}{}", String::from_utf8_lossy(&out.stdout), String::from_utf8_lossy(&out.stderr)),
+        ),
+        Err(e) => (false, format!("Failed to start rcm: {}", e)),
+    };
+    MemberResult { member, ok, duration_ms: started.elapsed().as_millis() as u64, output }
+}
+
+/// Repeat the current command in every member of the workspace at `root`
+pub async fn propagate(root: &Path, parallel_jobs: usize) -> Result<()> {
+    if inside() {
+        return Ok(());
+    }
+    let members = discover(root)?;
+    if members.is_empty() {
+        return Ok(());
+    }
+    let binary = std::env::current_exe().context("Cannot locate the rcm binary to run in members")?;
+    let args: Vec<OsString> = std::env::args_os().skip(1).collect();
+    let args = Arc::new(forwarded_args(&args));
+    let jobs = parallel_jobs.max(1);
+
+    println!();
+    println!("{}", style(format!("🗂️  Running in {} member(s), {} at a time", members.len(), jobs)).bold());
+    let limit = Arc::new(Semaphore::new(jobs));
+    let mut tasks = JoinSet::new();
+    for dir in members {
+        let member = relative(root, &dir);
+        let (limit, binary, args) = (limit.clone(), binary.clone(), args.clone());
+        tasks.spawn(async move {
+            let _permit = limit.acquire_owned().await.expect("member semaphore closed");
+            println!("{} {}", style("→").cyan(), member);
+            let result = run_member(&binary, &dir, member, &args).await;
+            if result.ok {
+                println!("{} {} ({})", style("✓").green(), result.member, util::format_duration(result.duration_ms));
+            } else {
+                println!("{} {}", style("✗").red(), result.member);
+            }
+            result
+        });
+    }
+    let mut results = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        results.push(result?);
+    }
+    results.sort_by(|a, b| a.member.cmp(&b.member));
+
+    let rows: Vec<MemberRow> = results.iter().map(|r| MemberRow {
+        member: r.member.clone(),
+        status: if r.ok { "✅ ok".to_string() } else { "❌ failed".to_string() },
+        time: util::format_duration(r.duration_ms),
+    }).collect();
+    println!("{}", Table::new(&rows));
+
+    let failed: Vec<&MemberResult> = results.iter().filter(|r| !r.ok).collect();
+    if failed.is_empty() {
+        println!("{}", style(format!("✅ All {} member(s) succeeded", results.len())).green());
+        return Ok(());
+    }
+    for result in &failed {
+        println!();
+        println!("{}", style(format!("── {} ──", result.member)).red().bold());
+        let lines: Vec<&str> = result.output.lines().collect();
+        for line in &lines[lines.len().saturating_sub(FAILURE_TAIL)..] {
+            println!("  {}", line);
+        }
+    }
+    let names: Vec<&str> = failed.iter().map(|r| r.member.as_str()).collect();
+    Err(anyhow!("{} of {} member(s) failed: {}", failed.len(), results.len(), names.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn project(dir: &Path, members: &[&str]) {
+        std::fs::create_dir_all(dir.join(".rcm")).unwrap();
+        let manifest = serde_json::json!({ "name": "test", "members": members });
+        std::fs::write(dir.join(MANIFEST), manifest.to_string()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sections() {
+        let root = tempdir().unwrap();
+        let root = root.path();
+        project(root, &["web"]);
+        let missing: Vec<String> = read_section(root, "tags").unwrap();
+        assert!(missing.is_empty());
+
+        write_section(root, "tags", &vec!["a", "b"]).await.unwrap();
+        assert_eq!(read_section::<Vec<String>>(root, "tags").unwrap(), vec!["a", "b"]);
+        assert_eq!(declared(root).unwrap(), vec!["web"]);
+    }
+
+    #[test]
+    fn test_discover() {
+        let root = tempdir().unwrap();
+        let root = root.path();
+        project(root, &["services/*", "web", "web"]);
+        project(&root.join("services/api"), &["../../web"]);
+        project(&root.join("services/worker"), &[]);
+        std::fs::create_dir_all(root.join("services/docs")).unwrap();
+        project(&root.join("web"), &[".."]);
+
+        let found: Vec<String> = discover(root).unwrap().iter().map(|d| relative(root, d)).collect();
+        assert_eq!(found, vec!["services/api", "web", "services/worker"]);
+
+        project(root, &["missing"]);
+        assert!(discover(root).is_err());
+
+        let outer = tempdir().unwrap();
+        let nested = outer.path().join("repo");
+        project(&nested, &["../.."]);
+        assert!(discover(&nested).is_err());
+        project(&nested, &["../*"]);
+        project(&outer.path().join("sibling"), &[]);
+        assert!(discover(&nested).is_err());
+    }
+
+    #[test]
+    fn test_forwarded_args() {
+        let args: Vec<OsString> = ["-w", "/repo", "--dry-run", "-w/other", "workspace", "--workspace=x", "sync"]
+            .iter().map(OsString::from).collect();
+        assert_eq!(forwarded_args(&args), vec!["--dry-run", "workspace", "sync"]);
+    }
+}
@@ -0,0 +1,289 @@
+//! Workspace metrics history for RCM
+//!
+//! Every `rcm workspace check` records a sample in the `metrics` state
+//! collection; `rcm workspace trends` renders the samples as sparklines so
+//! regressions in health, dependency count, disk usage or vulnerabilities
+//! stand out. Samples from `.rcm/metrics/history.jsonl`, where they used to
+//! be appended, are still read.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tabled::{Table, Tabled};
+use tokio::fs;
+use crate::storage;
+use crate::util;
+
+/// Pre-state-store history file, relative to the workspace root
+pub const HISTORY_FILE: &str = ".rcm/metrics/history.jsonl";
+
+/// State collection holding the samples
+const COLLECTION: &str = "metrics";
+
+/// One `workspace check` run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSample {
+    pub timestamp: DateTime<Utc>,
+    pub health_score: f64,
+    pub total_dependencies: usize,
+    #[serde(default)]
+    pub dependencies_by_manager: BTreeMap<String, usize>,
+    pub disk_usage_mb: f64,
+    pub vulnerabilities: usize,
+    #[serde(default)]
+    pub outdated: usize,
+}
+
+/// A tracked metric and which direction is an improvement
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    Health,
+    Dependencies,
+    DiskUsage,
+    Vulnerabilities,
+    Outdated,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 5] = [Self::Health, Self::Dependencies, Self::DiskUsage, Self::Vulnerabilities, Self::Outdated];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Health => "Health score",
+            Self::Dependencies => "Dependencies",
+            Self::DiskUsage => "Disk usage (MB)",
+            Self::Vulnerabilities => "Vulnerabilities",
+            Self::Outdated => "Outdated",
+        }
+    }
+
+    pub fn value(&self, sample: &MetricsSample) -> f64 {
+        match self {
+            Self::Health => sample.health_score,
+            Self::Dependencies => sample.total_dependencies as f64,
+            Self::DiskUsage => sample.disk_usage_mb,
+            Self::Vulnerabilities => sample.vulnerabilities as f64,
+            Self::Outdated => sample.outdated as f64,
+        }
+    }
+
+    /// Only health improves as it grows
+    fn higher_is_better(&self) -> bool {
+        matches!(self, Self::Health)
+    }
+
+    /// Whether moving from `from` to `to` is a regression
+    pub fn regressed(&self, from: f64, to: f64) -> bool {
+        // Disk usage jitters between runs; ignore changes under 5%
+        let tolerance = if *self == Self::DiskUsage { from.abs() * 0.05 } else { 0.0 };
+        if self.higher_is_better() {
+            to < from - tolerance
+        } else {
+            to > from + tolerance
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct TrendRow {
+    #[tabled(rename = "Metric")]
+    metric: String,
+    #[tabled(rename = "Trend")]
+    sparkline: String,
+    #[tabled(rename = "First")]
+    first: String,
+    #[tabled(rename = "Latest")]
+    latest: String,
+    #[tabled(rename = "Change")]
+    change: String,
+}
+
+pub fn history_path(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(HISTORY_FILE)
+}
+
+/// Store key of a sample; fixed-width UTC timestamps sort chronologically
+fn sample_key(sample: &MetricsSample) -> String {
+    sample.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true)
+}
+
+/// Add a sample to the history (skipped in dry-run mode)
+pub async fn record(workspace_root: &Path, sample: &MetricsSample) -> Result<()> {
+    if util::is_dry_run() {
+        return Ok(());
+    }
+    let sample = sample.clone();
+    storage::with_store(workspace_root, move |store| store.put_as(COLLECTION, &sample_key(&sample), &sample))
+        .await
+        .context("Failed to record workspace metrics")
+}
+
+/// Parse history lines, skipping any that are truncated or malformed
+pub fn parse_history(content: &str) -> Vec<MetricsSample> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(sample) => Some(sample),
+            Err(e) => {
+                tracing::warn!("Skipping malformed metrics sample: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Load the recorded samples, oldest first
+pub async fn load_history(workspace_root: &Path) -> Result<Vec<MetricsSample>> {
+    let mut samples: Vec<MetricsSample> = storage::with_store(workspace_root, |store| store.list_as(COLLECTION))
+        .await?
+        .into_values()
+        .collect();
+    let path = history_path(workspace_root);
+    if path.exists() {
+        let content = fs::read_to_string(&path).await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        samples.extend(parse_history(&content));
+    }
+    samples.sort_by_key(|s| s.timestamp);
+    Ok(samples)
+}
+
+/// Render values as a unicode sparkline scaled between their min and max
+pub fn sparkline(values: &[f64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+    values
+        .iter()
+        .map(|v| {
+            if range <= f64::EPSILON {
+                BARS[3]
+            } else {
+                BARS[(((v - min) / range) * 7.0).round() as usize]
+            }
+        })
+        .collect()
+}
+
+fn format_value(metric: Metric, value: f64) -> String {
+    match metric {
+        Metric::Health => format!("{:.0}%", value),
+        Metric::DiskUsage => format!("{:.1}", value),
+        _ => format!("{:.0}", value),
+    }
+}
+
+/// Metrics whose latest sample is worse than the one before it
+pub fn regressions(samples: &[MetricsSample]) -> Vec<Metric> {
+    let [.., previous, latest] = samples else {
+        return Vec::new();
+    };
+    Metric::ALL
+        .into_iter()
+        .filter(|m| m.regressed(m.value(previous), m.value(latest)))
+        .collect()
+}
+
+/// Print the last `last` samples as trends
+pub async fn show_trends(workspace_root: &Path, last: usize, format: &str) -> Result<()> {
+    let history = load_history(workspace_root).await?;
+    let samples = &history[history.len().saturating_sub(last)..];
+
+    match format {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(samples)?);
+            return Ok(());
+        }
+        "table" => {}
+        _ => return Err(anyhow!("Unsupported format: {}. Use table or json", format)),
+    }
+
+    let (Some(first), Some(latest)) = (samples.first(), samples.last()) else {
+        println!("No metrics recorded yet. Run {} to record a sample.", style("rcm workspace check").cyan());
+        return Ok(());
+    };
+
+    println!(
+        "{}",
+        style(format!(
+            "📈 Workspace trends ({} runs, {} → {})",
+            samples.len(),
+            first.timestamp.format("%Y-%m-%d"),
+            latest.timestamp.format("%Y-%m-%d")
+        ))
+        .cyan()
+        .bold()
+    );
+
+    let rows: Vec<TrendRow> = Metric::ALL
+        .into_iter()
+        .map(|metric| {
+            let values: Vec<f64> = samples.iter().map(|s| metric.value(s)).collect();
+            let (from, to) = (metric.value(first), metric.value(latest));
+            let delta = to - from;
+            let change = if delta.abs() < 0.05 {
+                style("=".to_string()).dim().to_string()
+            } else if metric.regressed(from, to) {
+                style(format!("{:+.1}", delta)).red().to_string()
+            } else {
+                style(format!("{:+.1}", delta)).green().to_string()
+            };
+            TrendRow {
+                metric: metric.label().to_string(),
+                sparkline: sparkline(&values),
+                first: format_value(metric, from),
+                latest: format_value(metric, to),
+                change,
+            }
+        })
+        .collect();
+    println!("{}", Table::new(rows));
+
+    let regressed = regressions(samples);
+    if !regressed.is_empty() {
+        let names: Vec<&str> = regressed.iter().map(Metric::label).collect();
+        println!("{} Regressed since the previous run: {}", style("⚠️").yellow(), names.join(", "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(health: f64, deps: usize, disk: f64, vulns: usize) -> MetricsSample {
+        MetricsSample {
+            timestamp: Utc::now(),
+            health_score: health,
+            total_dependencies: deps,
+            dependencies_by_manager: BTreeMap::new(),
+            disk_usage_mb: disk,
+            vulnerabilities: vulns,
+            outdated: 0,
+        }
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]), "▁▂▃▄▅▆▇█");
+        assert_eq!(sparkline(&[5.0, 5.0]), "▄▄");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn test_regressions() {
+        let samples = vec![sample(95.0, 40, 500.0, 0), sample(80.0, 40, 510.0, 2)];
+        assert_eq!(regressions(&samples), vec![Metric::Health, Metric::Vulnerabilities]);
+        assert!(regressions(&samples[..1]).is_empty());
+        assert!(Metric::DiskUsage.regressed(500.0, 600.0));
+
+        let line = serde_json::to_string(&samples[0]).unwrap();
+        let parsed = parse_history(&format!("{}\n{{\"truncated\n", line));
+        assert_eq!(parsed, vec![samples[0].clone()]);
+    }
+}